@@ -5,8 +5,9 @@ use std::path::PathBuf;
 use clap::{ArgAction, ColorChoice, Parser, Subcommand, ValueHint};
 
 use crate::commands::{
-    BackendsCommand, ChatCommand, ConfigCommand, DoctorCommand, 
-    InitCommand, ToolsCommand, CompletionsCommand, ManpagesCommand,
+    BackendsCommand, BuildCommand, ChatCommand, ConfigCommand, DevCommand, DoctorCommand,
+    Execute, FmtCommand, InitCommand, LintCommand, PromptsCommand, RunCommand, TestCommand,
+    ToolsCommand, WorkflowsCommand, CompletionsCommand, ManpagesCommand, McpCommand,
     MigrateCommands,
 };
 use crate::error::CliError;
@@ -127,6 +128,9 @@ pub enum Command {
     /// Manage MCP tools
     Tools(ToolsCommand),
 
+    /// Serve Tachikoma's primitives over the Model Context Protocol
+    Mcp(McpCommand),
+
     /// Manage AI backends
     Backends(BackendsCommand),
 
@@ -164,22 +168,6 @@ pub enum Command {
     Chat(ChatCommand),
 }
 
-/// Shell completions generation
-#[derive(Debug, Parser)]
-pub struct CompletionsCommand {
-    /// Shell to generate completions for
-    #[arg(value_enum)]
-    pub shell: clap_complete::Shell,
-}
-
-/// Generate man pages
-#[derive(Debug, Parser)]
-pub struct ManpagesCommand {
-    /// Directory to output man pages
-    #[arg(short, long, default_value = "man")]
-    pub output: PathBuf,
-}
-
 impl Cli {
     /// Load configuration from file or default locations
     pub async fn load_config(&self) -> Result<tachikoma_common_config::TachikomaConfig, CliError> {
@@ -192,12 +180,12 @@ impl Cli {
                     .parent()
                     .unwrap_or_else(|| std::path::Path::new("."));
                 let loader = ConfigLoader::new(project_dir);
-                loader.load().map_err(CliError::Config)
+                loader.load().map_err(CliError::from)
             }
             None => {
                 // Use current directory as project root
                 let loader = ConfigLoader::default();
-                loader.load().map_err(CliError::Config)
+                loader.load().map_err(CliError::from)
             }
         }
     }
@@ -213,9 +201,11 @@ impl Cli {
 
         match self.command {
             Command::Init(cmd) => cmd.execute(&ctx).await,
+            Command::Build(cmd) => cmd.execute(&ctx).await,
             Command::Config(cmd) => cmd.execute(&ctx).await,
             Command::Doctor(cmd) => cmd.execute(&ctx).await,
             Command::Tools(cmd) => cmd.execute(&ctx).await,
+            Command::Mcp(cmd) => cmd.execute(&ctx).await,
             Command::Backends(cmd) => cmd.execute(&ctx).await,
             Command::Migrate(cmd) => cmd.execute(&ctx).await,
             Command::Completions(cmd) => {
@@ -235,20 +225,6 @@ impl Cli {
     }
 }
 
-impl CompletionsCommand {
-    /// Execute the completions command
-    pub fn execute(&self, _ctx: &CommandContext) -> Result<(), CliError> {
-        use clap::CommandFactory;
-        use clap_complete::generate;
-        use std::io;
-
-        let mut cmd = Cli::command();
-        let name = cmd.get_name().to_string();
-        generate(self.shell, &mut cmd, name, &mut io::stdout());
-        Ok(())
-    }
-}
-
 /// Context passed to all commands
 #[derive(Debug)]
 pub struct CommandContext {
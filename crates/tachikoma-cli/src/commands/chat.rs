@@ -89,7 +89,7 @@ impl ChatCommand {
 
     async fn execute_auto_mode(&self, _ctx: &CommandContext) -> Result<(), CliError> {
         let prompt = self.prompt.as_ref().ok_or_else(|| {
-            CliError::InvalidInput("Prompt required for auto mode".to_string())
+            CliError::validation("Prompt required for auto mode")
         })?;
 
         println!("🤖 Auto-generating spec for: {}", prompt);
@@ -199,7 +199,7 @@ Generated from conversational interview on {}.
 
     fn generate_basic_spec(&self, prompt: &str) -> Result<String, CliError> {
         let title = prompt.trim();
-        let acceptance_criteria = vec![
+        let acceptance_criteria = [
             "Core functionality is implemented",
             "Error handling is comprehensive", 
             "Documentation is complete",
@@ -409,12 +409,15 @@ impl Conversation {
     }
 }
 
+// Kept whole for the transcript, though only answers feed the spec
+#[allow(dead_code)]
 #[derive(Debug)]
 struct Exchange {
     question: String,
     answer: String,
 }
 
+#[allow(dead_code)]
 #[derive(Debug)]
 struct Message {
     speaker: String,
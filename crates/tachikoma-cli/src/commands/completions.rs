@@ -1,6 +1,6 @@
 //! Shell completion generation.
 
-use std::io::{self, Write};
+use std::io;
 use std::path::PathBuf;
 
 use clap::{Command, CommandFactory};
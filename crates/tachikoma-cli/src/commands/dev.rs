@@ -0,0 +1,9 @@
+//! Dev command implementation.
+
+use clap::Parser;
+
+/// Start development server
+#[derive(Debug, Parser)]
+pub struct DevCommand {
+    // TODO: Add development server options
+}
@@ -35,16 +35,12 @@ impl DoctorCommand {
 
         let mut issues = Vec::new();
         let mut warnings = Vec::new();
-        let mut all_good = true;
 
         // Check environment tools
         if self.should_check("tools") {
             let (tool_issues, tool_warnings) = self.check_environment().await;
             issues.extend(tool_issues);
             warnings.extend(tool_warnings);
-            if !issues.is_empty() || !warnings.is_empty() {
-                all_good = false;
-            }
             println!();
         }
 
@@ -53,9 +49,6 @@ impl DoctorCommand {
             let (key_issues, key_warnings) = self.check_api_keys();
             issues.extend(key_issues);
             warnings.extend(key_warnings);
-            if !issues.is_empty() || !warnings.is_empty() {
-                all_good = false;
-            }
             println!();
         }
 
@@ -64,9 +57,6 @@ impl DoctorCommand {
             let (config_issues, config_warnings) = self.check_configuration().await;
             issues.extend(config_issues);
             warnings.extend(config_warnings);
-            if !issues.is_empty() || !warnings.is_empty() {
-                all_good = false;
-            }
             println!();
         }
 
@@ -0,0 +1,9 @@
+//! Fmt command implementation.
+
+use clap::Parser;
+
+/// Format code and configurations
+#[derive(Debug, Parser)]
+pub struct FmtCommand {
+    // TODO: Add formatting options
+}
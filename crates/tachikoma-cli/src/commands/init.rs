@@ -3,7 +3,7 @@
 use clap::Parser;
 use std::path::Path;
 use std::fs;
-use dialoguer::{Confirm, Select, Input};
+use dialoguer::{Confirm, Select};
 use console::{style, Emoji};
 
 use crate::cli::CommandContext;
@@ -44,7 +44,7 @@ struct SystemStatus {
 }
 
 impl InitCommand {
-    pub async fn execute(&self, ctx: &CommandContext) -> Result<(), CliError> {
+    pub async fn execute(&self, _ctx: &CommandContext) -> Result<(), CliError> {
         if !self.quick {
             println!("{} {}", SPIDER, style("Tachikoma Setup").bold());
             println!();
@@ -66,15 +66,15 @@ impl InitCommand {
 
         // Ensure .tachikoma directory exists
         fs::create_dir_all(".tachikoma")
-            .map_err(|e| CliError::Io(format!("Failed to create .tachikoma directory: {}", e)))?;
+            .map_err(|e| CliError::io("Failed to create .tachikoma directory", e))?;
 
         // Write configuration
         let config_path = Path::new(".tachikoma/config.yaml");
         let config_yaml = serde_yaml::to_string(&config)
-            .map_err(|e| CliError::Config(format!("Failed to serialize config: {}", e).into()))?;
+            .map_err(|e| CliError::config(format!("Failed to serialize config: {}", e)))?;
         
         fs::write(config_path, config_yaml)
-            .map_err(|e| CliError::Io(format!("Failed to write config file: {}", e)))?;
+            .map_err(|e| CliError::io("Failed to write config file", e))?;
 
         if !self.quick {
             println!("  {} Created .tachikoma/config.yaml", CHECK);
@@ -227,7 +227,7 @@ impl InitCommand {
             .items(&brain_options)
             .default(brain_default)
             .interact()
-            .map_err(|e| CliError::Io(format!("Input error: {}", e)))?;
+            .map_err(|dialoguer::Error::IO(e)| CliError::io("Input error", e))?;
         
         // Oracle selection  
         let oracle_options = vec!["o3", "claude", "gemini"];
@@ -236,14 +236,14 @@ impl InitCommand {
             .items(&oracle_options)
             .default(0)
             .interact()
-            .map_err(|e| CliError::Io(format!("Input error: {}", e)))?;
+            .map_err(|dialoguer::Error::IO(e)| CliError::io("Input error", e))?;
         
         // Attended mode
         let attended = Confirm::new()
             .with_prompt("Enable attended mode by default?")
             .default(true)
             .interact()
-            .map_err(|e| CliError::Io(format!("Input error: {}", e)))?;
+            .map_err(|dialoguer::Error::IO(e)| CliError::io("Input error", e))?;
 
         // Build config
         let mut config = TachikomaConfig::default();
@@ -257,17 +257,17 @@ impl InitCommand {
     fn create_specs_directory(&self) -> Result<(), CliError> {
         // Create specs directory
         fs::create_dir_all("specs")
-            .map_err(|e| CliError::Io(format!("Failed to create specs directory: {}", e)))?;
+            .map_err(|e| CliError::io("Failed to create specs directory", e))?;
 
         // Create README.md
         let readme_content = include_str!("../templates/specs_readme.md");
         fs::write("specs/README.md", readme_content)
-            .map_err(|e| CliError::Io(format!("Failed to create specs/README.md: {}", e)))?;
+            .map_err(|e| CliError::io("Failed to create specs/README.md", e))?;
 
         // Create getting started spec
         let getting_started_content = include_str!("../templates/getting_started_spec.md");
         fs::write("specs/001-getting-started.md", getting_started_content)
-            .map_err(|e| CliError::Io(format!("Failed to create getting started spec: {}", e)))?;
+            .map_err(|e| CliError::io("Failed to create getting started spec", e))?;
 
         Ok(())
     }
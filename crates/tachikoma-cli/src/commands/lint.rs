@@ -0,0 +1,9 @@
+//! Lint command implementation.
+
+use clap::Parser;
+
+/// Lint code and configurations
+#[derive(Debug, Parser)]
+pub struct LintCommand {
+    // TODO: Add lint options
+}
@@ -142,9 +142,9 @@ impl ManpagesCommand {
         writeln!(writer, ".SH NAME")?;
         writeln!(
             writer,
-            "{parent}-{name} \\- {}",
+            "{parent}-{name} \\- {about}",
             name = cmd.get_name(),
-            cmd.get_about().map(|s| s.to_string()).unwrap_or_default()
+            about = cmd.get_about().map(|s| s.to_string()).unwrap_or_default()
         )?;
 
         // SYNOPSIS
@@ -316,11 +316,11 @@ impl ManpagesCommand {
             writeln!(writer, "{}", subcommands.join(",\n"))?;
         }
 
-        writeln!(writer, "")?;
+        writeln!(writer)?;
         writeln!(writer, ".SH BUGS")?;
         writeln!(writer, "Report bugs at: https://github.com/tachikoma-project/tachikoma/issues")?;
 
-        writeln!(writer, "")?;
+        writeln!(writer)?;
         writeln!(writer, ".SH AUTHORS")?;
         writeln!(writer, "Tachikoma Contributors")?;
 
@@ -0,0 +1,187 @@
+//! MCP command implementation.
+
+use std::net::SocketAddr;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use clap::{Args, Parser, Subcommand};
+use tachikoma_primitives::mcp::{generate_token, SHELL_TOOLS};
+use tachikoma_primitives::rate_limit::RateLimitConfig;
+use tachikoma_primitives::{McpServer, PrimitiveConfig, PrimitiveContext, PrimitiveRegistry};
+
+use crate::cli::CommandContext;
+use crate::error::CliError;
+
+/// Model Context Protocol integration
+#[derive(Debug, Parser)]
+pub struct McpCommand {
+    #[command(subcommand)]
+    pub command: McpSubcommand,
+}
+
+/// MCP subcommands
+#[derive(Debug, Subcommand)]
+pub enum McpSubcommand {
+    /// Serve the file, shell and search primitives to MCP clients
+    Serve(ServeArgs),
+}
+
+/// Options for `tachikoma mcp serve`
+#[derive(Debug, Args)]
+pub struct ServeArgs {
+    /// Working directory for the tools; relative paths resolve against it
+    #[arg(long, default_value = ".")]
+    pub root: PathBuf,
+
+    /// Paths the tools may touch (repeatable; default: the root)
+    #[arg(long = "allow", value_name = "PATH")]
+    pub allow: Vec<PathBuf>,
+
+    /// Paths the tools may never touch (repeatable)
+    #[arg(long = "deny", value_name = "PATH")]
+    pub deny: Vec<PathBuf>,
+
+    /// Only serve these tools (repeatable; default: all, except bash and
+    /// process over HTTP)
+    #[arg(long = "tool", value_name = "NAME")]
+    pub tools: Vec<String>,
+
+    /// Serve over HTTP on this address instead of stdio
+    #[arg(long, value_name = "ADDR")]
+    pub http: Option<SocketAddr>,
+
+    /// Let --http listen on an address other machines can reach
+    #[arg(long, requires = "http")]
+    pub allow_remote: bool,
+}
+
+impl McpCommand {
+    pub async fn execute(&self, _ctx: &CommandContext) -> Result<(), CliError> {
+        match &self.command {
+            McpSubcommand::Serve(args) => args.serve().await,
+        }
+    }
+}
+
+impl ServeArgs {
+    async fn serve(&self) -> Result<(), CliError> {
+        let root = self
+            .root
+            .canonicalize()
+            .map_err(|e| CliError::io_with_path("Cannot open MCP root", e, &self.root))?;
+        let absolute = |path: &PathBuf| if path.is_absolute() { path.clone() } else { root.join(path) };
+
+        let defaults = PrimitiveConfig::default();
+        let config = PrimitiveConfig {
+            allowed_paths: if self.allow.is_empty() {
+                vec![root.clone()]
+            } else {
+                self.allow.iter().map(absolute).collect()
+            },
+            denied_paths: defaults.denied_paths.iter().cloned().chain(self.deny.iter().map(absolute)).collect(),
+            ..defaults
+        };
+
+        let registry = self.registry()?;
+        let ctx = PrimitiveContext::with_config(root, config);
+        let server = McpServer::with_registry(registry, ctx, RateLimitConfig::default());
+
+        match self.http {
+            Some(addr) => {
+                self.check_http_addr(addr)?;
+                let listener = tokio::net::TcpListener::bind(addr)
+                    .await
+                    .map_err(|e| CliError::io(format!("Cannot listen on {}", addr), e))?;
+                let token = generate_token();
+                eprintln!("MCP server listening on http://{}", listener.local_addr().unwrap_or(addr));
+                eprintln!("Send every request with: Authorization: Bearer {}", token);
+                Arc::new(server)
+                    .serve_http(listener, token)
+                    .await
+                    .map_err(|e| CliError::io("MCP server failed", e))
+            }
+            // stdout carries the protocol; logs go to stderr
+            None => server
+                .serve_stdio()
+                .await
+                .map_err(|e| CliError::io("MCP server failed", e)),
+        }
+    }
+
+    /// The tools to serve: those named with --tool, or else every primitive,
+    /// less the shell tools when serving over HTTP
+    fn registry(&self) -> Result<PrimitiveRegistry, CliError> {
+        let mut registry = PrimitiveRegistry::with_defaults();
+        if !self.tools.is_empty() {
+            if let Some(unknown) = self.tools.iter().find(|t| registry.get(t).is_none()) {
+                return Err(CliError::not_found("tool", unknown.as_str()));
+            }
+            registry.retain(|name| self.tools.iter().any(|t| t == name));
+        } else if self.http.is_some() {
+            registry.retain(|name| !SHELL_TOOLS.contains(&name));
+        }
+        Ok(registry)
+    }
+
+    /// Refuse to expose the tools beyond this machine unless asked to
+    fn check_http_addr(&self, addr: SocketAddr) -> Result<(), CliError> {
+        if addr.ip().is_loopback() || self.allow_remote {
+            return Ok(());
+        }
+        Err(CliError::user(format!(
+            "Refusing to serve MCP on {}, which other machines can reach; pass --allow-remote to allow it",
+            addr
+        )))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn serve_args(args: &[&str]) -> ServeArgs {
+        let cli = crate::cli::Cli::try_parse_from(["tachikoma", "mcp", "serve"].iter().chain(args)).unwrap();
+        match cli.command {
+            crate::cli::Command::Mcp(McpCommand { command: McpSubcommand::Serve(args) }) => args,
+            other => panic!("parsed as {:?}", other),
+        }
+    }
+
+    fn tool_names(args: &ServeArgs) -> Vec<String> {
+        args.registry().unwrap().mcp_tools().into_iter().map(|t| t.name).collect()
+    }
+
+    #[test]
+    fn test_http_leaves_out_shell_tools() {
+        let stdio = tool_names(&serve_args(&[]));
+        assert!(stdio.iter().any(|t| t == "bash"));
+        assert!(stdio.iter().any(|t| t == "process"));
+
+        let http = tool_names(&serve_args(&["--http", "127.0.0.1:8931"]));
+        assert!(!http.is_empty());
+        assert!(http.iter().all(|t| !SHELL_TOOLS.contains(&t.as_str())));
+        assert_eq!(http.len(), stdio.len() - SHELL_TOOLS.len());
+
+        let named = serve_args(&["--http", "127.0.0.1:8931", "--tool", "bash", "--tool", "read_file"]);
+        assert_eq!(tool_names(&named), ["bash", "read_file"]);
+
+        assert!(serve_args(&["--tool", "rm"]).registry().is_err());
+    }
+
+    #[test]
+    fn test_http_stays_local_unless_allowed() {
+        let args = serve_args(&["--http", "0.0.0.0:8931"]);
+        assert!(matches!(args.check_http_addr(args.http.unwrap()), Err(CliError::User { .. })));
+
+        let args = serve_args(&["--http", "0.0.0.0:8931", "--allow-remote"]);
+        assert!(args.check_http_addr(args.http.unwrap()).is_ok());
+
+        for addr in ["127.0.0.1:8931", "[::1]:8931"] {
+            let args = serve_args(&["--http", addr]);
+            assert!(args.check_http_addr(args.http.unwrap()).is_ok());
+        }
+
+        // --allow-remote means nothing without --http
+        assert!(crate::cli::Cli::try_parse_from(["tachikoma", "mcp", "serve", "--allow-remote"]).is_err());
+    }
+}
@@ -1,6 +1,6 @@
 use std::path::PathBuf;
 
-use clap::{Parser, Subcommand};
+use clap::Parser;
 use tachikoma_database::database::migration::cli::{MigrateCli, MigrateCommand};
 
 use crate::cli::CommandContext;
@@ -30,6 +30,6 @@ impl MigrateCommands {
             migrations_dir: self.migrations_dir.clone(),
         };
 
-        migrate_cli.run().await.map_err(CliError::from)
+        migrate_cli.run().await.map_err(|e| CliError::Other(e.into()))
     }
 }
\ No newline at end of file
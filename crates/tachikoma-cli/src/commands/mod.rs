@@ -1,24 +1,45 @@
 //! Command module organization and shared traits.
 
 mod backends;
+mod build;
 mod chat;
 mod completions;
 mod config;
+mod dev;
 mod doctor;
+mod fmt;
 mod init;
+mod lint;
 mod manpages;
+mod mcp;
 mod migrate;
+mod prompts;
+mod run;
+mod test;
 mod tools;
+mod workflows;
 
 pub use backends::BackendsCommand;
+pub use build::BuildCommand;
 pub use chat::ChatCommand;
-pub use completions::CompletionsCommand;
+pub use completions::{
+    generate_dynamic_bash_completions, generate_dynamic_fish_completions,
+    generate_dynamic_zsh_completions, CompletionsCommand,
+};
 pub use config::ConfigCommand;
+pub use dev::DevCommand;
 pub use doctor::DoctorCommand;
+pub use fmt::FmtCommand;
 pub use init::InitCommand;
+pub use lint::LintCommand;
 pub use manpages::ManpagesCommand;
+pub use mcp::McpCommand;
 pub use migrate::MigrateCommands;
+pub use prompts::PromptsCommand;
+pub use run::RunCommand;
+pub use test::TestCommand;
 pub use tools::ToolsCommand;
+pub use workflows::WorkflowsCommand;
 
 use async_trait::async_trait;
 
@@ -0,0 +1,9 @@
+//! Prompts command implementation.
+
+use clap::Parser;
+
+/// Manage prompts
+#[derive(Debug, Parser)]
+pub struct PromptsCommand {
+    // TODO: Add subcommands for prompt management
+}
@@ -0,0 +1,9 @@
+//! Run command implementation.
+
+use clap::Parser;
+
+/// Run an agent or workflow
+#[derive(Debug, Parser)]
+pub struct RunCommand {
+    // TODO: Add agent and workflow selection
+}
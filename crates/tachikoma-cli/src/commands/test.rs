@@ -0,0 +1,9 @@
+//! Test command implementation.
+
+use clap::Parser;
+
+/// Run tests
+#[derive(Debug, Parser)]
+pub struct TestCommand {
+    // TODO: Add test selection and reporting options
+}
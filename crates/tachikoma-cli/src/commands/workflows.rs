@@ -0,0 +1,9 @@
+//! Workflows command implementation.
+
+use clap::Parser;
+
+/// Manage workflows
+#[derive(Debug, Parser)]
+pub struct WorkflowsCommand {
+    // TODO: Add subcommands for workflow management
+}
@@ -1,6 +1,5 @@
 //! CLI error handling and formatting.
 
-use std::fmt;
 use std::io;
 use std::process::ExitCode;
 
@@ -1,7 +1,7 @@
 //! Error formatting for CLI output.
 
 use std::fmt::Write as FmtWrite;
-use std::io::{self, IsTerminal, Write};
+use std::io::{self, IsTerminal};
 
 use crate::error::CliError;
 use crate::output::color::{ColorMode, Styled, Color};
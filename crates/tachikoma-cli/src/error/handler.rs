@@ -151,7 +151,7 @@ pub fn handle_error_with_context(
 /// Enhance error messages with context
 fn enhance_error_with_context(error: CliError, context: &ErrorContext) -> CliError {
     match &error {
-        CliError::NotFound { resource_type, resource_name, suggestions } => {
+        CliError::NotFound { resource_type, resource_name, suggestions, .. } => {
             // Add command-specific suggestions
             let mut enhanced_suggestions = suggestions.clone();
             
@@ -159,20 +159,20 @@ fn enhance_error_with_context(error: CliError, context: &ErrorContext) -> CliErr
                 match (cmd.as_str(), resource_type.as_str()) {
                     ("tools", "tool") => {
                         enhanced_suggestions.extend([
-                            format!("Use 'tachikoma tools list' to see available tools"),
+                            "Use 'tachikoma tools list' to see available tools".to_string(),
                             format!("Use 'tachikoma tools install {resource_name}' to install a tool"),
                         ]);
                     }
                     ("backends", "backend") => {
                         enhanced_suggestions.extend([
-                            format!("Use 'tachikoma backends list' to see available backends"),
+                            "Use 'tachikoma backends list' to see available backends".to_string(),
                             format!("Use 'tachikoma backends add {resource_name}' to add a backend"),
                         ]);
                     }
                     ("config", "configuration") => {
                         enhanced_suggestions.extend([
-                            format!("Use 'tachikoma config init' to create a configuration"),
-                            format!("Use 'tachikoma config show' to see current configuration"),
+                            "Use 'tachikoma config init' to create a configuration".to_string(),
+                            "Use 'tachikoma config show' to see current configuration".to_string(),
                         ]);
                     }
                     _ => {}
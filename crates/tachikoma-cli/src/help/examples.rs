@@ -101,6 +101,20 @@ impl CommandExamples {
             },
         ]);
 
+        // MCP command examples
+        examples.insert("mcp", vec![
+            Example {
+                description: "Serve the primitives to an editor over stdio",
+                command: "tachikoma mcp serve --root .",
+                output: None,
+            },
+            Example {
+                description: "Serve read-only tools over HTTP (send the printed bearer token with each request)",
+                command: "tachikoma mcp serve --http 127.0.0.1:8931 --tool read_file --tool list_files --tool code_search",
+                output: None,
+            },
+        ]);
+
         // Backends command examples
         examples.insert("backends", vec![
             Example {
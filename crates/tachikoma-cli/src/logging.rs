@@ -0,0 +1,119 @@
+//! CLI logging integration with tracing.
+
+use std::path::PathBuf;
+
+use tracing_subscriber::{fmt, layer::SubscriberExt, util::SubscriberInitExt, EnvFilter};
+
+use crate::output::color::ColorMode;
+
+/// Logging configuration
+#[derive(Debug, Clone)]
+pub struct LogConfig {
+    /// Verbosity level (0-3)
+    pub verbosity: u8,
+    /// Quiet mode
+    pub quiet: bool,
+    /// Color mode
+    pub color: ColorMode,
+    /// Log to file
+    pub log_file: Option<PathBuf>,
+}
+
+impl Default for LogConfig {
+    fn default() -> Self {
+        Self {
+            verbosity: 0,
+            quiet: false,
+            color: ColorMode::Auto,
+            log_file: None,
+        }
+    }
+}
+
+impl LogConfig {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn verbosity(mut self, level: u8) -> Self {
+        self.verbosity = level;
+        self
+    }
+
+    pub fn quiet(mut self, quiet: bool) -> Self {
+        self.quiet = quiet;
+        self
+    }
+
+    pub fn color(mut self, color: ColorMode) -> Self {
+        self.color = color;
+        self
+    }
+
+    pub fn log_file(mut self, path: impl Into<PathBuf>) -> Self {
+        self.log_file = Some(path.into());
+        self
+    }
+
+    /// Get the log level filter string
+    pub fn level_filter(&self) -> &'static str {
+        match self.verbosity {
+            0 if self.quiet => "error",
+            0 => "warn",
+            1 => "info",
+            2 => "debug",
+            _ => "trace",
+        }
+    }
+}
+
+/// Initialize logging with the given configuration
+pub fn init(config: &LogConfig) {
+    let filter = EnvFilter::try_from_default_env()
+        .unwrap_or_else(|_| EnvFilter::new(config.level_filter()));
+
+    let fmt_layer = fmt::layer()
+        .with_ansi(config.color.should_color())
+        .with_target(config.verbosity >= 2)
+        .with_writer(std::io::stderr);
+
+    let subscriber = tracing_subscriber::registry().with(filter).with(fmt_layer);
+
+    if let Some(path) = &config.log_file {
+        let file = std::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .expect("Failed to open log file");
+
+        subscriber
+            .with(fmt::layer().with_writer(file).with_ansi(false))
+            .init();
+    } else {
+        subscriber.init();
+    }
+}
+
+/// Initialize logging from CLI flags
+pub fn init_from_cli(verbosity: u8, quiet: bool, color: clap::ColorChoice) {
+    let config = LogConfig::new()
+        .verbosity(verbosity)
+        .quiet(quiet)
+        .color(color.into());
+
+    init(&config);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_level_filter() {
+        assert_eq!(LogConfig::new().level_filter(), "warn");
+        assert_eq!(LogConfig::new().quiet(true).level_filter(), "error");
+        assert_eq!(LogConfig::new().verbosity(1).level_filter(), "info");
+        assert_eq!(LogConfig::new().verbosity(2).level_filter(), "debug");
+        assert_eq!(LogConfig::new().verbosity(5).level_filter(), "trace");
+    }
+}
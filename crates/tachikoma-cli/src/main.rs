@@ -7,13 +7,9 @@ use std::process::ExitCode;
 use clap::Parser;
 use tracing::error;
 
-mod cli;
-mod commands;
-mod error;
-mod output;
-
-use cli::Cli;
-use error::CliError;
+use tachikoma_cli::cli::Cli;
+use tachikoma_cli::logging;
+use tachikoma_cli::CliError;
 
 /// Application exit codes
 #[repr(u8)]
@@ -38,7 +34,7 @@ fn main() -> ExitCode {
     let cli = Cli::parse();
 
     // Initialize tracing based on verbosity
-    init_tracing(&cli);
+    logging::init_from_cli(cli.verbose, cli.quiet, cli.color);
 
     // Run the async runtime
     let runtime = tokio::runtime::Builder::new_multi_thread()
@@ -50,7 +46,7 @@ fn main() -> ExitCode {
         Ok(()) => Exit::Success.into(),
         Err(e) => {
             error!("{e}");
-            e.exit_code().into()
+            e.exit_code()
         }
     }
 }
@@ -62,21 +58,3 @@ async fn run(cli: Cli) -> Result<(), CliError> {
     // Execute the command
     cli.execute(config).await
 }
-
-fn init_tracing(cli: &Cli) {
-    use tracing_subscriber::{fmt, prelude::*, EnvFilter};
-
-    let filter = match cli.verbose {
-        0 if cli.quiet => EnvFilter::new("error"),
-        0 => EnvFilter::new("warn"),
-        1 => EnvFilter::new("info"),
-        2 => EnvFilter::new("debug"),
-        _ => EnvFilter::new("trace"),
-    };
-
-    let subscriber = tracing_subscriber::registry()
-        .with(filter)
-        .with(fmt::layer().with_target(cli.verbose >= 2));
-
-    subscriber.init();
-}
\ No newline at end of file
@@ -1,12 +1,12 @@
 //! Output formatting utilities for CLI commands.
 
-mod color;
-mod format;
-mod icons;
-mod printer;
-mod progress;
-mod table;
-mod text;
+pub mod color;
+pub mod format;
+pub mod icons;
+pub mod printer;
+pub mod progress;
+pub mod table;
+pub mod text;
 
 pub use color::{
     Color, ColorContext, ColorDepth, ColorMode, Palette, Style, Styled,
@@ -85,7 +85,6 @@ pub fn print_output<T>(ctx: &CommandContext, value: &T) -> Result<(), CliError>
 where
     T: FormattedOutput + Serialize,
 {
-    let output = Output::new(ctx);
     match ctx.format {
         OutputFormat::Text => {
             println!("{}", value.format_text());
@@ -113,7 +112,7 @@ where
         })?,
     };
 
-    writeln!(writer, "{}", output).map_err(CliError::Io)?;
+    writeln!(writer, "{}", output)?;
     Ok(())
 }
 
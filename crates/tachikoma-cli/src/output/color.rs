@@ -52,10 +52,7 @@ pub fn detect_color_support() -> bool {
     }
 
     // Check TERM
-    match env::var("TERM").as_deref() {
-        Ok("dumb") | Ok("") => false,
-        _ => true,
-    }
+    !matches!(env::var("TERM").as_deref(), Ok("dumb") | Ok(""))
 }
 
 /// Color depth support level
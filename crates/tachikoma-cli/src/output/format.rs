@@ -24,7 +24,7 @@ pub trait Displayable: Serialize + std::fmt::Display {
     }
 
     /// Format as table (optional, return None if not supported)
-    fn as_table(&self) -> Option<crate::table::Table> {
+    fn as_table(&self) -> Option<super::table::Table> {
         None
     }
 }
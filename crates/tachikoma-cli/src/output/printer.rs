@@ -5,7 +5,7 @@ use std::io::{self, Write};
 use serde::Serialize;
 
 use crate::cli::{CommandContext, OutputFormat};
-use crate::output::{is_terminal, terminal_width, Table, TextFormatter};
+use crate::output::{is_terminal, terminal_width, Table};
 
 /// Output configuration
 #[derive(Debug, Clone)]
@@ -27,7 +27,7 @@ impl OutputConfig {
                 clap::ColorChoice::Auto => is_tty,
             },
             width: if is_tty { terminal_width() } else { 80 },
-            quiet: ctx.verbose == 0 && false, // TODO: Add quiet flag to CLI
+            quiet: false, // TODO: Add quiet flag to CLI
         }
     }
 }
@@ -206,7 +206,7 @@ impl StreamingOutput {
     /// Flush buffered lines
     pub fn flush(&mut self) -> io::Result<()> {
         for line in self.buffer.drain(..) {
-            println!("{line}");
+            self.output.message(&line);
         }
         io::stdout().flush()
     }
@@ -240,8 +240,6 @@ mod tests {
 
     #[test]
     fn test_streaming_output() {
-        use std::io::Write;
-        
         let config = OutputConfig {
             format: OutputFormat::Text,
             color: false,
@@ -1,6 +1,5 @@
 //! Table formatting for CLI output.
 
-use serde::Serialize;
 use serde_json::Value;
 
 /// Table rendering style
@@ -198,7 +197,7 @@ impl Table {
         horiz(output, "┌", "┬", "┐");
 
         // Header
-        output.push_str("│");
+        output.push('│');
         for (i, col) in self.columns.iter().enumerate() {
             let cell = self.format_cell(&col.header, widths[i], col.alignment);
             if color {
@@ -214,7 +213,7 @@ impl Table {
 
         // Rows
         for row in &self.rows {
-            output.push_str("│");
+            output.push('│');
             for (i, cell) in row.iter().enumerate() {
                 if i >= self.columns.len() {
                     break;
@@ -242,7 +241,7 @@ impl Table {
         // Separator with alignment
         output.push('|');
         for (i, col) in self.columns.iter().enumerate() {
-            let w = widths[i];
+            let w = widths[i].max(3);
             let sep = match col.alignment {
                 Alignment::Left => format!(":{}", "-".repeat(w)),
                 Alignment::Right => format!("{}:", "-".repeat(w)),
@@ -20,10 +20,11 @@ impl TextFormatter {
 
     /// Wrap text to fit terminal width
     pub fn wrap(&self, text: &str) -> String {
+        let indent = " ".repeat(self.indent);
         let options = Options::new(self.width.saturating_sub(self.indent))
             .word_splitter(WordSplitter::NoHyphenation)
-            .initial_indent(&" ".repeat(self.indent))
-            .subsequent_indent(&" ".repeat(self.indent));
+            .initial_indent(&indent)
+            .subsequent_indent(&indent);
 
         wrap(text, options).join("\n")
     }
@@ -270,7 +271,7 @@ mod tests {
         let spinner1 = indicator.spinner(0);
         let spinner2 = indicator.spinner(1);
         assert_ne!(spinner1, spinner2);
-        assert!(spinner1.len() > 0);
+        assert!(!spinner1.is_empty());
     }
 
     #[test]
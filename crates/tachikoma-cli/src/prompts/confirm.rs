@@ -0,0 +1,118 @@
+//! Confirmation prompt (yes/no).
+
+use std::io::{self, BufRead, Write};
+
+use super::{is_interactive, PromptError, PromptResult, Theme};
+use crate::output::color::{ColorMode, Styled};
+
+/// Confirmation prompt builder
+pub struct Confirm {
+    message: String,
+    default: Option<bool>,
+    theme: Theme,
+    color_mode: ColorMode,
+}
+
+impl Confirm {
+    pub fn new(message: impl Into<String>) -> Self {
+        Self {
+            message: message.into(),
+            default: None,
+            theme: Theme::default(),
+            color_mode: ColorMode::Auto,
+        }
+    }
+
+    /// Set default value (shown when user presses Enter)
+    pub fn default(mut self, default: bool) -> Self {
+        self.default = Some(default);
+        self
+    }
+
+    /// Set theme
+    pub fn theme(mut self, theme: Theme) -> Self {
+        self.theme = theme;
+        self
+    }
+
+    /// Set color mode
+    pub fn color_mode(mut self, mode: ColorMode) -> Self {
+        self.color_mode = mode;
+        self
+    }
+
+    /// Run the prompt
+    pub fn prompt(self) -> PromptResult<bool> {
+        if !is_interactive() {
+            return self.default.ok_or(PromptError::NotInteractive);
+        }
+
+        let hint = match self.default {
+            Some(true) => "(Y/n)",
+            Some(false) => "(y/N)",
+            None => "(y/n)",
+        };
+
+        let prefix = Styled::new(&self.theme.prompt_prefix)
+            .with_color_mode(self.color_mode)
+            .fg(self.theme.active_color);
+
+        print!("{prefix} {} {hint} ", self.message);
+        io::stdout().flush()?;
+
+        let stdin = io::stdin();
+        let mut input = String::new();
+        stdin.lock().read_line(&mut input)?;
+
+        let input = input.trim().to_lowercase();
+
+        match input.as_str() {
+            "" => self.default.ok_or(PromptError::ValidationFailed(
+                "Please enter y or n".to_string(),
+            )),
+            "y" | "yes" => Ok(true),
+            "n" | "no" => Ok(false),
+            _ => Err(PromptError::ValidationFailed(
+                "Please enter y or n".to_string(),
+            )),
+        }
+    }
+
+    /// Run prompt with retry on invalid input
+    pub fn prompt_until_valid(self) -> PromptResult<bool> {
+        loop {
+            match self.clone().prompt() {
+                Ok(result) => return Ok(result),
+                Err(PromptError::ValidationFailed(msg)) => {
+                    eprintln!(
+                        "{} {}",
+                        Styled::new("!").fg(self.theme.error_color),
+                        msg
+                    );
+                }
+                Err(e) => return Err(e),
+            }
+        }
+    }
+}
+
+impl Clone for Confirm {
+    fn clone(&self) -> Self {
+        Self {
+            message: self.message.clone(),
+            default: self.default,
+            theme: self.theme.clone(),
+            color_mode: self.color_mode,
+        }
+    }
+}
+
+/// Convenience function for simple confirmation
+pub fn confirm(message: &str) -> PromptResult<bool> {
+    Confirm::new(message).default(false).prompt()
+}
+
+/// Convenience function for confirmation with default true
+pub fn confirm_default_yes(message: &str) -> PromptResult<bool> {
+    Confirm::new(message).default(true).prompt()
+}
@@ -0,0 +1,187 @@
+//! Text input prompt.
+
+use std::io::{self, BufRead, Write};
+
+use super::{is_interactive, PromptError, PromptResult, Theme};
+use crate::output::color::{ColorMode, Styled};
+
+/// Validation function type
+pub type Validator<T> = Box<dyn Fn(&str) -> Result<T, String>>;
+
+/// Text input prompt builder
+pub struct Input<T> {
+    message: String,
+    default: Option<String>,
+    placeholder: Option<String>,
+    validator: Option<Validator<T>>,
+    theme: Theme,
+    color_mode: ColorMode,
+    allow_empty: bool,
+}
+
+impl<T> Input<T> {
+    pub fn new(message: impl Into<String>) -> Self {
+        Self {
+            message: message.into(),
+            default: None,
+            placeholder: None,
+            validator: None,
+            theme: Theme::default(),
+            color_mode: ColorMode::Auto,
+            allow_empty: false,
+        }
+    }
+
+    /// Set default value
+    pub fn default(mut self, default: impl Into<String>) -> Self {
+        self.default = Some(default.into());
+        self
+    }
+
+    /// Set placeholder text
+    pub fn placeholder(mut self, placeholder: impl Into<String>) -> Self {
+        self.placeholder = Some(placeholder.into());
+        self
+    }
+
+    /// Set validator function
+    pub fn validate<F>(mut self, f: F) -> Self
+    where
+        F: Fn(&str) -> Result<T, String> + 'static,
+    {
+        self.validator = Some(Box::new(f));
+        self
+    }
+
+    /// Allow empty input
+    pub fn allow_empty(mut self) -> Self {
+        self.allow_empty = true;
+        self
+    }
+
+    /// Set theme
+    pub fn theme(mut self, theme: Theme) -> Self {
+        self.theme = theme;
+        self
+    }
+
+    /// Set color mode
+    pub fn color_mode(mut self, mode: ColorMode) -> Self {
+        self.color_mode = mode;
+        self
+    }
+}
+
+impl Input<String> {
+    /// Run the prompt (string version)
+    pub fn prompt(self) -> PromptResult<String> {
+        if !is_interactive() {
+            return self.default.ok_or(PromptError::NotInteractive);
+        }
+
+        let prefix = Styled::new(&self.theme.prompt_prefix)
+            .with_color_mode(self.color_mode)
+            .fg(self.theme.active_color);
+
+        let hint = match (&self.default, &self.placeholder) {
+            (Some(d), _) => format!("(default: {d})"),
+            (_, Some(p)) => format!("({p})"),
+            _ => String::new(),
+        };
+
+        let hint_styled = Styled::new(&hint)
+            .with_color_mode(self.color_mode)
+            .fg(self.theme.hint_color);
+
+        print!("{prefix} {} {hint_styled} ", self.message);
+        io::stdout().flush()?;
+
+        let stdin = io::stdin();
+        let mut input = String::new();
+        stdin.lock().read_line(&mut input)?;
+
+        let input = input.trim();
+
+        if input.is_empty() {
+            if let Some(default) = self.default {
+                return Ok(default);
+            }
+            if !self.allow_empty {
+                return Err(PromptError::ValidationFailed(
+                    "Input cannot be empty".to_string(),
+                ));
+            }
+        }
+
+        if let Some(validator) = self.validator {
+            validator(input).map_err(PromptError::ValidationFailed)
+        } else {
+            Ok(input.to_string())
+        }
+    }
+}
+
+impl<T> Input<T>
+where
+    T: std::str::FromStr,
+    <T as std::str::FromStr>::Err: std::fmt::Display,
+{
+    /// Run the prompt with parsing
+    pub fn prompt_parsed(self) -> PromptResult<T> {
+        if !is_interactive() {
+            if let Some(default) = &self.default {
+                return default
+                    .parse()
+                    .map_err(|e: <T as std::str::FromStr>::Err| {
+                        PromptError::ValidationFailed(e.to_string())
+                    });
+            }
+            return Err(PromptError::NotInteractive);
+        }
+
+        let prefix = Styled::new(&self.theme.prompt_prefix)
+            .with_color_mode(self.color_mode)
+            .fg(self.theme.active_color);
+
+        print!("{prefix} {} ", self.message);
+        if let Some(default) = &self.default {
+            let hint = Styled::new(format!("(default: {default})"))
+                .with_color_mode(self.color_mode)
+                .fg(self.theme.hint_color);
+            print!("{hint} ");
+        }
+        io::stdout().flush()?;
+
+        let stdin = io::stdin();
+        let mut input = String::new();
+        stdin.lock().read_line(&mut input)?;
+
+        let input = input.trim();
+
+        if input.is_empty() {
+            if let Some(default) = self.default {
+                return default
+                    .parse()
+                    .map_err(|e: <T as std::str::FromStr>::Err| {
+                        PromptError::ValidationFailed(e.to_string())
+                    });
+            }
+        }
+
+        input
+            .parse()
+            .map_err(|e: <T as std::str::FromStr>::Err| {
+                PromptError::ValidationFailed(e.to_string())
+            })
+    }
+}
+
+/// Convenience function for simple text input
+pub fn input(message: &str) -> PromptResult<String> {
+    Input::new(message).prompt()
+}
+
+/// Convenience function for text input with default
+pub fn input_with_default(message: &str, default: &str) -> PromptResult<String> {
+    Input::new(message).default(default).prompt()
+}
@@ -6,15 +6,15 @@ mod multiselect;
 mod password;
 mod select;
 
-pub use confirm::Confirm;
-pub use input::Input;
-pub use multiselect::MultiSelect;
-pub use password::Password;
-pub use select::{Select, SelectOption};
+pub use confirm::{confirm, confirm_default_yes, Confirm};
+pub use input::{input, input_with_default, Input};
+pub use multiselect::{multiselect, MultiSelect};
+pub use password::{password, password_with_confirmation, Password};
+pub use select::{select, Select, SelectOption};
 
-use std::io::{self, IsTerminal, Write};
+use std::io::{self, IsTerminal};
 
-use crate::output::color::{Color, ColorMode, Styled};
+use crate::output::color::Color;
 
 /// Check if we're in interactive mode
 pub fn is_interactive() -> bool {
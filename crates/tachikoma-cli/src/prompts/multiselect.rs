@@ -0,0 +1,275 @@
+//! Multi-selection prompt.
+
+use std::io;
+
+use crossterm::{
+    cursor,
+    event::{self, Event, KeyCode, KeyEvent},
+    execute,
+    terminal::{self, ClearType},
+};
+
+use super::{is_interactive, PromptError, PromptResult, SelectOption, Theme};
+use crate::output::color::{ColorMode, Styled};
+
+/// Multi-selection prompt
+pub struct MultiSelect<T> {
+    message: String,
+    options: Vec<SelectOption<T>>,
+    defaults: Vec<bool>,
+    min_selections: Option<usize>,
+    max_selections: Option<usize>,
+    theme: Theme,
+    color_mode: ColorMode,
+    page_size: usize,
+}
+
+impl<T> MultiSelect<T> {
+    pub fn new(message: impl Into<String>) -> Self {
+        Self {
+            message: message.into(),
+            options: Vec::new(),
+            defaults: Vec::new(),
+            min_selections: None,
+            max_selections: None,
+            theme: Theme::default(),
+            color_mode: ColorMode::Auto,
+            page_size: 10,
+        }
+    }
+
+    /// Add an option
+    pub fn option(mut self, option: SelectOption<T>, selected: bool) -> Self {
+        self.defaults.push(selected);
+        self.options.push(option);
+        self
+    }
+
+    /// Add options from iterator
+    pub fn options(
+        mut self,
+        options: impl IntoIterator<Item = (SelectOption<T>, bool)>,
+    ) -> Self {
+        for (opt, selected) in options {
+            self.defaults.push(selected);
+            self.options.push(opt);
+        }
+        self
+    }
+
+    /// Add simple string options (all unselected)
+    pub fn items(mut self, items: impl IntoIterator<Item = T>) -> Self
+    where
+        T: ToString + Clone,
+    {
+        for item in items {
+            let label = item.to_string();
+            self.defaults.push(false);
+            self.options.push(SelectOption::new(item, label));
+        }
+        self
+    }
+
+    /// Set minimum required selections
+    pub fn min(mut self, min: usize) -> Self {
+        self.min_selections = Some(min);
+        self
+    }
+
+    /// Set maximum allowed selections
+    pub fn max(mut self, max: usize) -> Self {
+        self.max_selections = Some(max);
+        self
+    }
+
+    /// Set theme
+    pub fn theme(mut self, theme: Theme) -> Self {
+        self.theme = theme;
+        self
+    }
+
+    /// Set color mode
+    pub fn color_mode(mut self, mode: ColorMode) -> Self {
+        self.color_mode = mode;
+        self
+    }
+
+    /// Run the prompt
+    pub fn prompt(self) -> PromptResult<Vec<T>>
+    where
+        T: Clone,
+    {
+        if self.options.is_empty() {
+            return Err(PromptError::ValidationFailed(
+                "No options provided".to_string(),
+            ));
+        }
+
+        if !is_interactive() {
+            // Return default selections
+            let selected: Vec<T> = self
+                .options
+                .iter()
+                .zip(&self.defaults)
+                .filter(|(_, &selected)| selected)
+                .map(|(opt, _)| opt.value.clone())
+                .collect();
+            return Ok(selected);
+        }
+
+        terminal::enable_raw_mode()?;
+        let result = self.run_interactive();
+        terminal::disable_raw_mode()?;
+
+        result
+    }
+
+    fn run_interactive(self) -> PromptResult<Vec<T>>
+    where
+        T: Clone,
+    {
+        let mut stdout = io::stdout();
+        let mut cursor = 0usize;
+        let mut selections = self.defaults.clone();
+        let mut scroll_offset = 0;
+
+        // Print prompt message
+        let prefix = Styled::new(&self.theme.prompt_prefix)
+            .with_color_mode(self.color_mode)
+            .fg(self.theme.active_color);
+
+        let hint = Styled::new("(Space to select, Enter to confirm)")
+            .with_color_mode(self.color_mode)
+            .fg(self.theme.hint_color);
+
+        println!("{prefix} {} {hint}", self.message);
+
+        loop {
+            let visible_end = (scroll_offset + self.page_size).min(self.options.len());
+
+            // Render options
+            for (i, option) in self.options[scroll_offset..visible_end].iter().enumerate() {
+                let idx = scroll_offset + i;
+                let is_cursor = idx == cursor;
+                let is_selected = selections[idx];
+
+                let check = if is_selected {
+                    Styled::new(&self.theme.selected_prefix)
+                        .with_color_mode(self.color_mode)
+                        .fg(self.theme.active_color)
+                } else {
+                    Styled::new(&self.theme.unselected_prefix)
+                        .with_color_mode(self.color_mode)
+                };
+
+                let cursor_indicator = if is_cursor { "❯" } else { " " };
+
+                let label = if is_cursor {
+                    Styled::new(&option.label)
+                        .with_color_mode(self.color_mode)
+                        .fg(self.theme.active_color)
+                } else {
+                    Styled::new(&option.label).with_color_mode(self.color_mode)
+                };
+
+                println!("{cursor_indicator} {check} {label}");
+            }
+
+            // Handle input
+            if let Event::Key(KeyEvent { code, .. }) = event::read()? {
+                match code {
+                    KeyCode::Up | KeyCode::Char('k') if cursor > 0 => {
+                        cursor -= 1;
+                        if cursor < scroll_offset {
+                            scroll_offset = cursor;
+                        }
+                    }
+                    KeyCode::Down | KeyCode::Char('j') if cursor + 1 < self.options.len() => {
+                        cursor += 1;
+                        if cursor >= scroll_offset + self.page_size {
+                            scroll_offset = cursor - self.page_size + 1;
+                        }
+                    }
+                    KeyCode::Char(' ') => {
+                        // Toggle selection
+                        let current = selections[cursor];
+                        let selected_count: usize = selections.iter().filter(|&&s| s).count();
+
+                        // Check max before selecting
+                        if !current {
+                            if let Some(max) = self.max_selections {
+                                if selected_count >= max {
+                                    continue;
+                                }
+                            }
+                        }
+
+                        selections[cursor] = !current;
+                    }
+                    KeyCode::Char('a') => {
+                        // Select/deselect all
+                        let all_selected = selections.iter().all(|&s| s);
+                        selections.fill(!all_selected);
+                    }
+                    KeyCode::Enter => {
+                        let selected_count: usize =
+                            selections.iter().filter(|&&s| s).count();
+
+                        // Validate minimum
+                        if let Some(min) = self.min_selections {
+                            if selected_count < min {
+                                // Show error but don't exit
+                                continue;
+                            }
+                        }
+
+                        // Clear display
+                        execute!(
+                            stdout,
+                            cursor::MoveUp(visible_end as u16 - scroll_offset as u16),
+                            terminal::Clear(ClearType::FromCursorDown)
+                        )?;
+
+                        // Collect selected values
+                        let result: Vec<T> = self
+                            .options
+                            .iter()
+                            .zip(&selections)
+                            .filter(|(_, &selected)| selected)
+                            .map(|(opt, _)| opt.value.clone())
+                            .collect();
+
+                        // Show selected count
+                        let count_styled = Styled::new(format!("{} selected", result.len()))
+                            .with_color_mode(self.color_mode)
+                            .fg(self.theme.active_color);
+                        println!("{count_styled}");
+
+                        return Ok(result);
+                    }
+                    KeyCode::Esc | KeyCode::Char('q') => {
+                        execute!(
+                            stdout,
+                            cursor::MoveUp(visible_end as u16 - scroll_offset as u16),
+                            terminal::Clear(ClearType::FromCursorDown)
+                        )?;
+                        return Err(PromptError::Cancelled);
+                    }
+                    _ => {}
+                }
+            }
+
+            // Move cursor up to redraw
+            execute!(
+                stdout,
+                cursor::MoveUp(visible_end as u16 - scroll_offset as u16),
+                terminal::Clear(ClearType::FromCursorDown)
+            )?;
+        }
+    }
+}
+
+/// Convenience function for multi-selection
+pub fn multiselect<T: Clone + ToString>(message: &str, items: Vec<T>) -> PromptResult<Vec<T>> {
+    MultiSelect::new(message).items(items).prompt()
+}
@@ -0,0 +1,95 @@
+//! Password input prompt (masked).
+
+use std::io::{self, Write};
+
+use super::{is_interactive, PromptError, PromptResult, Theme};
+use crate::output::color::{ColorMode, Styled};
+
+/// Password input prompt
+pub struct Password {
+    message: String,
+    confirm: bool,
+    confirm_message: String,
+    theme: Theme,
+    color_mode: ColorMode,
+}
+
+impl Password {
+    pub fn new(message: impl Into<String>) -> Self {
+        Self {
+            message: message.into(),
+            confirm: false,
+            confirm_message: "Confirm password".to_string(),
+            theme: Theme::default(),
+            color_mode: ColorMode::Auto,
+        }
+    }
+
+    /// Require password confirmation
+    pub fn with_confirmation(mut self) -> Self {
+        self.confirm = true;
+        self
+    }
+
+    /// Set confirmation message
+    pub fn confirm_message(mut self, message: impl Into<String>) -> Self {
+        self.confirm_message = message.into();
+        self
+    }
+
+    /// Set theme
+    pub fn theme(mut self, theme: Theme) -> Self {
+        self.theme = theme;
+        self
+    }
+
+    /// Set color mode
+    pub fn color_mode(mut self, mode: ColorMode) -> Self {
+        self.color_mode = mode;
+        self
+    }
+
+    /// Run the prompt
+    pub fn prompt(self) -> PromptResult<String> {
+        if !is_interactive() {
+            return Err(PromptError::NotInteractive);
+        }
+
+        let password = self.read_password(&self.message)?;
+
+        if self.confirm {
+            let confirmed = self.read_password(&self.confirm_message)?;
+            if password != confirmed {
+                return Err(PromptError::ValidationFailed(
+                    "Passwords do not match".to_string(),
+                ));
+            }
+        }
+
+        Ok(password)
+    }
+
+    fn read_password(&self, message: &str) -> PromptResult<String> {
+        let prefix = Styled::new(&self.theme.prompt_prefix)
+            .with_color_mode(self.color_mode)
+            .fg(self.theme.active_color);
+
+        print!("{prefix} {message}: ");
+        io::stdout().flush()?;
+
+        // Use rpassword for cross-platform password reading
+        let password = rpassword::read_password()?;
+
+        Ok(password)
+    }
+}
+
+/// Convenience function for password input
+pub fn password(message: &str) -> PromptResult<String> {
+    Password::new(message).prompt()
+}
+
+/// Convenience function for password with confirmation
+pub fn password_with_confirmation(message: &str) -> PromptResult<String> {
+    Password::new(message).with_confirmation().prompt()
+}
@@ -0,0 +1,238 @@
+//! Single selection prompt.
+
+use std::io;
+
+use crossterm::{
+    cursor,
+    event::{self, Event, KeyCode, KeyEvent},
+    execute,
+    terminal::{self, ClearType},
+};
+
+use super::{is_interactive, PromptError, PromptResult, Theme};
+use crate::output::color::{ColorMode, Styled};
+
+/// Selection option
+#[derive(Debug, Clone)]
+pub struct SelectOption<T> {
+    pub value: T,
+    pub label: String,
+    pub hint: Option<String>,
+}
+
+impl<T> SelectOption<T> {
+    pub fn new(value: T, label: impl Into<String>) -> Self {
+        Self {
+            value,
+            label: label.into(),
+            hint: None,
+        }
+    }
+
+    pub fn with_hint(mut self, hint: impl Into<String>) -> Self {
+        self.hint = Some(hint.into());
+        self
+    }
+}
+
+/// Single selection prompt
+pub struct Select<T> {
+    message: String,
+    options: Vec<SelectOption<T>>,
+    default: usize,
+    theme: Theme,
+    color_mode: ColorMode,
+    page_size: usize,
+}
+
+impl<T> Select<T> {
+    pub fn new(message: impl Into<String>) -> Self {
+        Self {
+            message: message.into(),
+            options: Vec::new(),
+            default: 0,
+            theme: Theme::default(),
+            color_mode: ColorMode::Auto,
+            page_size: 10,
+        }
+    }
+
+    /// Add an option
+    pub fn option(mut self, option: SelectOption<T>) -> Self {
+        self.options.push(option);
+        self
+    }
+
+    /// Add options from iterator
+    pub fn options(mut self, options: impl IntoIterator<Item = SelectOption<T>>) -> Self {
+        self.options.extend(options);
+        self
+    }
+
+    /// Add simple string options
+    pub fn items(mut self, items: impl IntoIterator<Item = T>) -> Self
+    where
+        T: ToString + Clone,
+    {
+        for item in items {
+            let label = item.to_string();
+            self.options.push(SelectOption::new(item, label));
+        }
+        self
+    }
+
+    /// Set default selection index
+    pub fn default(mut self, index: usize) -> Self {
+        self.default = index;
+        self
+    }
+
+    /// Set page size
+    pub fn page_size(mut self, size: usize) -> Self {
+        self.page_size = size;
+        self
+    }
+
+    /// Set theme
+    pub fn theme(mut self, theme: Theme) -> Self {
+        self.theme = theme;
+        self
+    }
+
+    /// Set color mode
+    pub fn color_mode(mut self, mode: ColorMode) -> Self {
+        self.color_mode = mode;
+        self
+    }
+
+    /// Run the prompt
+    pub fn prompt(self) -> PromptResult<T>
+    where
+        T: Clone,
+    {
+        if self.options.is_empty() {
+            return Err(PromptError::ValidationFailed(
+                "No options provided".to_string(),
+            ));
+        }
+
+        if !is_interactive() {
+            // Return default option
+            return Ok(self.options[self.default.min(self.options.len() - 1)]
+                .value
+                .clone());
+        }
+
+        terminal::enable_raw_mode()?;
+        let result = self.run_interactive();
+        terminal::disable_raw_mode()?;
+
+        result
+    }
+
+    fn run_interactive(self) -> PromptResult<T>
+    where
+        T: Clone,
+    {
+        let mut stdout = io::stdout();
+        let mut selected = self.default.min(self.options.len() - 1);
+        let mut scroll_offset = 0;
+
+        // Print prompt message
+        let prefix = Styled::new(&self.theme.prompt_prefix)
+            .with_color_mode(self.color_mode)
+            .fg(self.theme.active_color);
+        println!("{prefix} {}", self.message);
+
+        loop {
+            // Calculate visible range
+            let visible_end = (scroll_offset + self.page_size).min(self.options.len());
+
+            // Render options
+            for (i, option) in self.options[scroll_offset..visible_end].iter().enumerate() {
+                let idx = scroll_offset + i;
+                let is_selected = idx == selected;
+
+                let prefix = if is_selected {
+                    Styled::new("❯")
+                        .with_color_mode(self.color_mode)
+                        .fg(self.theme.active_color)
+                } else {
+                    Styled::new(" ").with_color_mode(self.color_mode)
+                };
+
+                let label = if is_selected {
+                    Styled::new(&option.label)
+                        .with_color_mode(self.color_mode)
+                        .fg(self.theme.active_color)
+                } else {
+                    Styled::new(&option.label).with_color_mode(self.color_mode)
+                };
+
+                if let Some(hint) = &option.hint {
+                    let hint_styled = Styled::new(hint)
+                        .with_color_mode(self.color_mode)
+                        .fg(self.theme.hint_color);
+                    println!("{prefix} {label} {hint_styled}");
+                } else {
+                    println!("{prefix} {label}");
+                }
+            }
+
+            // Handle input
+            if let Event::Key(KeyEvent { code, .. }) = event::read()? {
+                match code {
+                    KeyCode::Up | KeyCode::Char('k') if selected > 0 => {
+                        selected -= 1;
+                        if selected < scroll_offset {
+                            scroll_offset = selected;
+                        }
+                    }
+                    KeyCode::Down | KeyCode::Char('j') if selected + 1 < self.options.len() => {
+                        selected += 1;
+                        if selected >= scroll_offset + self.page_size {
+                            scroll_offset = selected - self.page_size + 1;
+                        }
+                    }
+                    KeyCode::Enter => {
+                        // Clear selection display
+                        execute!(
+                            stdout,
+                            cursor::MoveUp(visible_end as u16 - scroll_offset as u16),
+                            terminal::Clear(ClearType::FromCursorDown)
+                        )?;
+
+                        // Show selected value
+                        let selected_styled = Styled::new(&self.options[selected].label)
+                            .with_color_mode(self.color_mode)
+                            .fg(self.theme.active_color);
+                        println!("{selected_styled}");
+
+                        return Ok(self.options[selected].value.clone());
+                    }
+                    KeyCode::Esc | KeyCode::Char('q') => {
+                        execute!(
+                            stdout,
+                            cursor::MoveUp(visible_end as u16 - scroll_offset as u16),
+                            terminal::Clear(ClearType::FromCursorDown)
+                        )?;
+                        return Err(PromptError::Cancelled);
+                    }
+                    _ => {}
+                }
+            }
+
+            // Move cursor up to redraw
+            execute!(
+                stdout,
+                cursor::MoveUp(visible_end as u16 - scroll_offset as u16),
+                terminal::Clear(ClearType::FromCursorDown)
+            )?;
+        }
+    }
+}
+
+/// Convenience function for simple selection
+pub fn select<T: Clone + ToString>(message: &str, items: Vec<T>) -> PromptResult<T> {
+    Select::new(message).items(items).prompt()
+}
@@ -163,8 +163,8 @@ impl Detection {
     }
     
     fn looks_like_version(s: &str) -> bool {
-        // Check if string looks like a version number
-        let chars: Vec<char> = s.chars().collect();
+        // Check if string looks like a version number, "v1.2.3" included
+        let chars: Vec<char> = s.strip_prefix('v').unwrap_or(s).chars().collect();
         
         // Must start with a digit
         if chars.is_empty() || !chars[0].is_ascii_digit() {
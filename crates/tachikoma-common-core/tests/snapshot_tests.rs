@@ -1,7 +1,39 @@
 //! Snapshot tests for core types.
 
 use serde::{Deserialize, Serialize};
-use tachikoma_test_harness::{assert_json, assert_yaml, assert_debug, with_redactions};
+
+/// The settings tachikoma-test-harness snapshots with: `tests/snapshots`,
+/// no module prefix, sorted maps
+fn snapshot_settings() -> insta::Settings {
+    let mut settings = insta::Settings::clone_current();
+    settings.set_snapshot_path("snapshots");
+    settings.set_prepend_module_to_snapshot(false);
+    settings.set_sort_maps(true);
+    settings
+}
+
+macro_rules! assert_json {
+    ($value:expr) => {
+        snapshot_settings().bind(|| insta::assert_json_snapshot!($value))
+    };
+    ($name:expr, $value:expr) => {
+        snapshot_settings().bind(|| insta::assert_json_snapshot!($name, $value))
+    };
+}
+
+macro_rules! assert_yaml {
+    ($value:expr) => {
+        snapshot_settings().bind(|| insta::assert_yaml_snapshot!($value))
+    };
+}
+
+fn with_redactions<F: FnOnce()>(redactions: &[(&str, &'static str)], f: F) {
+    let mut settings = snapshot_settings();
+    for (selector, placeholder) in redactions {
+        settings.add_redaction(selector, *placeholder);
+    }
+    settings.bind(f)
+}
 
 #[derive(Debug, Serialize, Deserialize)]
 struct ApiResponse {
@@ -1,6 +1,5 @@
 /// Build-time SQLite configuration options
 /// These are set when compiling SQLite (if using bundled)
-
 pub struct SqliteCompileOptions {
     /// Maximum number of attached databases
     pub max_attached: u32,
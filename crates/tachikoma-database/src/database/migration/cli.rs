@@ -7,7 +7,6 @@ use sqlx::sqlite::SqlitePool;
 use std::path::PathBuf;
 use tokio::fs;
 use chrono::Utc;
-use tracing::info;
 
 #[derive(Parser)]
 #[command(name = "migrate")]
@@ -25,7 +24,7 @@ pub struct MigrateCli {
     pub migrations_dir: PathBuf,
 }
 
-#[derive(Subcommand)]
+#[derive(Debug, Clone, Subcommand)]
 pub enum MigrateCommand {
     /// Create a new migration
     Create {
@@ -158,7 +157,7 @@ impl MigrateCli {
 
         let target = match (to, current) {
             (Some(t), _) => t,
-            (None, Some(cur)) => {
+            (None, Some(_)) => {
                 // Calculate target version based on count
                 let applied = runner.get_applied().await?;
                 if applied.len() < count as usize {
@@ -328,7 +327,7 @@ impl MigrateCli {
         while let Some(entry) = entries.next_entry().await? {
             let path = entry.path();
 
-            if path.extension().map_or(false, |ext| ext == "sql") {
+            if path.extension().is_some_and(|ext| ext == "sql") {
                 if let Some(migration) = parse_migration_file(&path).await? {
                     migrations.push(migration);
                 }
@@ -4,7 +4,7 @@ use super::types::*;
 use chrono::Utc;
 use sqlx::{Row, SqlitePool};
 use std::collections::HashMap;
-use tracing::{debug, info, warn};
+use tracing::{info, warn};
 
 pub struct MigrationRunner {
     pool: SqlitePool,
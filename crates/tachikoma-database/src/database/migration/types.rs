@@ -2,7 +2,6 @@
 
 use chrono::{DateTime, Utc};
 use thiserror::Error;
-use std::path::PathBuf;
 
 #[derive(Debug, Error)]
 pub enum MigrationError {
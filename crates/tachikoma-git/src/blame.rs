@@ -1,6 +1,6 @@
 //! Git blame functionality.
 
-use crate::{GitCommit, GitOid, GitRepository, GitResult, GitSignature};
+use crate::{GitCommit, GitError, GitOid, GitRepository, GitResult, GitSignature};
 use serde::{Deserialize, Serialize};
 use std::path::Path;
 
@@ -159,13 +159,6 @@ impl GitRepository {
         Ok(detailed)
     }
 
-    /// Get a specific commit by OID.
-    pub fn get_commit(&self, oid: &GitOid) -> GitResult<GitCommit> {
-        self.with_repo(|repo| {
-            let commit = repo.find_commit(oid.as_git2())?;
-            Ok(GitCommit::from_git2(&commit))
-        })
-    }
 }
 
 /// Per-line blame information.
@@ -196,7 +189,7 @@ impl GitRepository {
             let head = repo.head()?.peel_to_tree()?;
             let entry = head.get_path(path)?;
             let blob = repo.find_blob(entry.id())?;
-            Ok(String::from_utf8_lossy(blob.content()).to_string())
+            Ok::<_, GitError>(String::from_utf8_lossy(blob.content()).to_string())
         })?;
 
         let lines: Vec<&str> = content.lines().collect();
@@ -65,7 +65,7 @@ impl GitRepository {
                     branch.get().target(),
                     upstream_branch.get().target(),
                 ) {
-                    Ok(repo.graph_ahead_behind(local_oid, upstream_oid)?)
+                    Ok::<_, GitError>(repo.graph_ahead_behind(local_oid, upstream_oid)?)
                 } else {
                     Ok((0, 0))
                 }
@@ -0,0 +1,201 @@
+//! Git cherry-pick and revert operations.
+//!
+//! Both apply a list of commits to HEAD, committing each in turn. On a
+//! conflict they stop with the conflicts in the index and work tree and
+//! return the commits not yet applied: resolve and stage the conflicts,
+//! [`GitRepository::pick_continue`] to commit, then pick the rest. Or
+//! [`GitRepository::pick_abort`].
+
+use crate::merge::collect_conflicts;
+use crate::progress::applying;
+use crate::rebase::ensure_clean;
+use crate::{ConflictFile, GitError, GitOid, GitProgress, GitRepository, GitResult, ProgressCallback};
+use git2::{Repository, RepositoryState};
+use serde::{Deserialize, Serialize};
+use tracing::debug;
+
+/// Cherry-pick and revert options.
+#[derive(Debug, Clone, Default)]
+pub struct PickOptions {
+    /// Parent to diff against when the commit is a merge (1-based).
+    pub mainline: Option<u32>,
+}
+
+impl PickOptions {
+    /// Pick merge commits relative to this parent.
+    pub fn mainline(parent: u32) -> Self {
+        Self { mainline: Some(parent) }
+    }
+}
+
+/// Cherry-pick or revert result.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PickResult {
+    /// Commits created, in order.
+    pub commits: Vec<GitOid>,
+    /// Commits whose changes were already in HEAD, so nothing was committed.
+    pub skipped: Vec<GitOid>,
+    /// Commit that conflicted.
+    pub stopped_at: Option<GitOid>,
+    /// Commits after the conflicted one, not applied.
+    pub remaining: Vec<GitOid>,
+    /// Conflicted files, when stopped.
+    pub conflicts: Vec<ConflictFile>,
+}
+
+#[derive(Clone, Copy)]
+enum Pick {
+    CherryPick,
+    Revert,
+}
+
+impl GitRepository {
+    /// Apply the changes of `commits` to HEAD, one commit each.
+    pub fn cherry_pick(
+        &self,
+        commits: &[GitOid],
+        options: Option<PickOptions>,
+        progress: Option<ProgressCallback<'_>>,
+    ) -> GitResult<PickResult> {
+        self.pick(Pick::CherryPick, commits, options.unwrap_or_default(), progress)
+    }
+
+    /// Undo the changes of `commits`, one revert commit each.
+    pub fn revert(
+        &self,
+        commits: &[GitOid],
+        options: Option<PickOptions>,
+        progress: Option<ProgressCallback<'_>>,
+    ) -> GitResult<PickResult> {
+        self.pick(Pick::Revert, commits, options.unwrap_or_default(), progress)
+    }
+
+    /// Commit a cherry-pick or revert once its conflicts are resolved and staged.
+    pub fn pick_continue(&self) -> GitResult<GitOid> {
+        self.with_repo_mut(|repo| {
+            in_progress(repo)?;
+            // Conflicts may have been staged by another process
+            let mut index = repo.index()?;
+            index.read(false)?;
+            if index.has_conflicts() {
+                let files = collect_conflicts(&index)?
+                    .into_iter()
+                    .map(|c| c.path.display().to_string())
+                    .collect();
+                return Err(GitError::MergeConflict { files });
+            }
+            commit_in_progress(repo).map(GitOid::from_git2)
+        })
+    }
+
+    /// Abort a cherry-pick or revert, resetting to HEAD.
+    pub fn pick_abort(&self) -> GitResult<()> {
+        self.with_repo_mut(|repo| {
+            in_progress(repo)?;
+            let head = repo.head()?.peel_to_commit()?;
+            repo.reset(head.as_object(), git2::ResetType::Hard, None)?;
+            repo.cleanup_state()?;
+            Ok(())
+        })
+    }
+
+    fn pick(
+        &self,
+        kind: Pick,
+        commits: &[GitOid],
+        options: PickOptions,
+        progress: Option<ProgressCallback<'_>>,
+    ) -> GitResult<PickResult> {
+        let mut noop = |_: &GitProgress| {};
+        let progress = progress.unwrap_or(&mut noop);
+
+        self.with_repo_mut(|repo| {
+            if repo.state() != RepositoryState::Clean {
+                return Err(GitError::InvalidOperation {
+                    message: format!("Cannot pick commits: {:?} in progress", repo.state()),
+                });
+            }
+            ensure_clean(repo)?;
+
+            let mut result = PickResult {
+                commits: Vec::new(),
+                skipped: Vec::new(),
+                stopped_at: None,
+                remaining: Vec::new(),
+                conflicts: Vec::new(),
+            };
+            for (i, oid) in commits.iter().enumerate() {
+                let commit = repo.find_commit(oid.as_git2()).map_err(|_| GitError::CommitNotFound {
+                    oid: oid.to_hex(),
+                })?;
+                applying(progress, i + 1, commits.len(), &commit);
+
+                match kind {
+                    Pick::CherryPick => {
+                        let mut opts = git2::CherrypickOptions::new();
+                        if let Some(parent) = options.mainline {
+                            opts.mainline(parent);
+                        }
+                        repo.cherrypick(&commit, Some(&mut opts))?;
+                    }
+                    Pick::Revert => {
+                        let mut opts = git2::RevertOptions::new();
+                        if let Some(parent) = options.mainline {
+                            opts.mainline(parent);
+                        }
+                        repo.revert(&commit, Some(&mut opts))?;
+                    }
+                }
+
+                let index = repo.index()?;
+                if index.has_conflicts() {
+                    result.stopped_at = Some(*oid);
+                    result.remaining = commits[i + 1..].to_vec();
+                    result.conflicts = collect_conflicts(&index)?;
+                    return Ok(result);
+                }
+
+                let head_tree = repo.head()?.peel_to_tree()?.id();
+                let mut index = index;
+                if index.write_tree()? == head_tree {
+                    debug!("Nothing to commit for {}", oid);
+                    repo.cleanup_state()?;
+                    result.skipped.push(*oid);
+                } else {
+                    result.commits.push(GitOid::from_git2(commit_in_progress(repo)?));
+                }
+            }
+            Ok(result)
+        })
+    }
+}
+
+fn in_progress(repo: &Repository) -> GitResult<()> {
+    match repo.state() {
+        RepositoryState::CherryPick | RepositoryState::Revert => Ok(()),
+        _ => Err(GitError::InvalidOperation {
+            message: "No cherry-pick or revert in progress".to_string(),
+        }),
+    }
+}
+
+/// Commit the index with the message git prepared; a cherry-pick keeps its author.
+fn commit_in_progress(repo: &Repository) -> GitResult<git2::Oid> {
+    let committer = repo.signature()?;
+    let author = match repo.state() {
+        RepositoryState::CherryPick => {
+            let picked = std::fs::read_to_string(repo.path().join("CHERRY_PICK_HEAD"))?;
+            repo.find_commit(git2::Oid::from_str(picked.trim())?)?.author().to_owned()
+        }
+        _ => committer.clone(),
+    };
+    // Drop the "# Conflicts:" notes git leaves in the message
+    let message = git2::message_prettify(repo.message()?, Some(b'#'))?;
+
+    let tree = repo.find_tree(repo.index()?.write_tree()?)?;
+    let head = repo.head()?.peel_to_commit()?;
+    let oid = repo.commit(Some("HEAD"), &author, &committer, &message, &tree, &[&head])?;
+
+    repo.cleanup_state()?;
+    Ok(oid)
+}
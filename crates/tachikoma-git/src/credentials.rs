@@ -211,6 +211,20 @@ impl Default for CredentialStore {
     }
 }
 
+impl crate::CredentialProvider for CredentialStore {
+    fn get_credentials(
+        &self,
+        url: &str,
+        username_from_url: Option<&str>,
+        allowed_types: CredentialType,
+    ) -> Result<Cred, git2::Error> {
+        match self.get(url) {
+            Some(cred) if cred.is_compatible_with(allowed_types) => cred.to_git2(),
+            _ => crate::DefaultCredentialProvider.get_credentials(url, username_from_url, allowed_types),
+        }
+    }
+}
+
 fn url_matches_pattern(url: &str, pattern: &str) -> bool {
     // Simple pattern matching: pattern can be a prefix or contain wildcard
     if pattern.contains('*') {
@@ -282,7 +296,7 @@ impl CredentialCallback {
         move |url, username_from_url, allowed_types| {
             // Try store first
             if let Some(ref store) = self.store {
-                if let Ok(store) = store.try_lock() {
+                if let Some(store) = store.try_lock() {
                     if let Some(cred) = store.get(url) {
                         if cred.is_compatible_with(allowed_types) {
                             if let Ok(git_cred) = cred.to_git2() {
@@ -353,6 +367,7 @@ impl Default for CredentialCallback {
 
 /// SSH key utilities.
 pub mod ssh {
+    use super::SshKeyType;
     use std::path::PathBuf;
 
     /// Get the default SSH directory.
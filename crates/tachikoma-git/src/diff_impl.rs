@@ -13,7 +13,7 @@ impl GitRepository {
         self.with_repo(|repo| {
             let mut opts = build_diff_options(&options);
             let diff = repo.diff_index_to_workdir(None, Some(&mut opts))?;
-            parse_diff(&diff, &options)
+            parse_diff(diff, &options)
         })
     }
 
@@ -23,7 +23,7 @@ impl GitRepository {
             let head = repo.head()?.peel_to_tree()?;
             let mut opts = build_diff_options(&options);
             let diff = repo.diff_tree_to_index(Some(&head), None, Some(&mut opts))?;
-            parse_diff(&diff, &options)
+            parse_diff(diff, &options)
         })
     }
 
@@ -42,7 +42,7 @@ impl GitRepository {
 
             let mut opts = build_diff_options(&options);
             let diff = repo.diff_tree_to_tree(Some(&old_tree), Some(&new_tree), Some(&mut opts))?;
-            parse_diff(&diff, &options)
+            parse_diff(diff, &options)
         })
     }
 
@@ -52,7 +52,7 @@ impl GitRepository {
             let head = repo.head()?.peel_to_tree()?;
             let mut opts = build_diff_options(&options);
             let diff = repo.diff_tree_to_workdir_with_index(Some(&head), Some(&mut opts))?;
-            parse_diff(&diff, &options)
+            parse_diff(diff, &options)
         })
     }
 
@@ -81,9 +81,9 @@ impl GitRepository {
 
             let stats = diff.stats()?;
             Ok(DiffStats {
-                files_changed: stats.files_changed(),
-                insertions: stats.insertions(),
-                deletions: stats.deletions(),
+                files_changed: stats.files_changed() as u32,
+                insertions: stats.insertions() as u32,
+                deletions: stats.deletions() as u32,
             })
         })
     }
@@ -108,12 +108,11 @@ fn build_diff_options(options: &DiffOptions) -> Git2DiffOpts {
     opts
 }
 
-fn parse_diff(diff: &Diff, options: &DiffOptions) -> GitResult<GitDiff> {
+fn parse_diff(mut diff: Diff, options: &DiffOptions) -> GitResult<GitDiff> {
     let mut files = Vec::new();
     let mut total_stats = DiffStats::default();
 
     // Find renames if enabled
-    let mut diff = diff.clone();
     if options.detect_renames {
         let mut find_opts = git2::DiffFindOptions::new();
         find_opts.renames(true);
@@ -128,35 +127,27 @@ fn parse_diff(diff: &Diff, options: &DiffOptions) -> GitResult<GitDiff> {
         let delta = diff.get_delta(delta_idx).unwrap();
         let mut file = parse_delta(&delta);
 
-        // Parse hunks for this file
-        diff.foreach(
-            &mut |_, _| true,
-            None,
-            Some(&mut |d, h| {
-                if d.new_file().path() == delta.new_file().path() {
-                    if let Some(hunk) = parse_hunk(h) {
-                        file.hunks.push(hunk);
-                    }
-                }
-                true
-            }),
-            Some(&mut |d, h, l| {
-                if d.new_file().path() == delta.new_file().path() {
-                    if let Some(hunk) = file.hunks.last_mut() {
-                        if let Some(line) = parse_line(l) {
-                            // Update stats
-                            match line.origin {
-                                LineOrigin::Addition => file.stats.insertions += 1,
-                                LineOrigin::Deletion => file.stats.deletions += 1,
-                                _ => {}
-                            }
-                            hunk.lines.push(line);
+        // Parse hunks for this file; binary files have no patch
+        if let Some(patch) = git2::Patch::from_diff(&diff, delta_idx)? {
+            for hunk_idx in 0..patch.num_hunks() {
+                let (h, line_count) = patch.hunk(hunk_idx)?;
+                let Some(mut hunk) = parse_hunk(&h) else {
+                    continue;
+                };
+                for line_idx in 0..line_count {
+                    if let Some(line) = parse_line(&patch.line_in_hunk(hunk_idx, line_idx)?) {
+                        // Update stats
+                        match line.origin {
+                            LineOrigin::Addition => file.stats.insertions += 1,
+                            LineOrigin::Deletion => file.stats.deletions += 1,
+                            _ => {}
                         }
+                        hunk.lines.push(line);
                     }
                 }
-                true
-            }),
-        )?;
+                file.hunks.push(hunk);
+            }
+        }
 
         total_stats.files_changed += 1;
         total_stats.insertions += file.stats.insertions;
@@ -178,7 +169,7 @@ fn parse_delta(delta: &DiffDelta) -> DiffFile {
         git2::Delta::Modified => DiffStatus::Modified,
         git2::Delta::Renamed => DiffStatus::Renamed,
         git2::Delta::Copied => DiffStatus::Copied,
-        git2::Delta::TypeChange => DiffStatus::TypeChange,
+        git2::Delta::Typechange => DiffStatus::TypeChange,
         git2::Delta::Untracked => DiffStatus::Untracked,
         git2::Delta::Ignored => DiffStatus::Ignored,
         git2::Delta::Conflicted => DiffStatus::Conflicted,
@@ -196,8 +187,8 @@ fn parse_delta(delta: &DiffDelta) -> DiffFile {
         new_oid: if new_file.id().is_zero() { None } else { Some(GitOid::from_git2(new_file.id())) },
         is_binary: old_file.is_binary() || new_file.is_binary(),
         mode_changed: old_file.mode() != new_file.mode(),
-        old_mode: Some(old_file.mode()),
-        new_mode: Some(new_file.mode()),
+        old_mode: Some(u32::from(old_file.mode())),
+        new_mode: Some(u32::from(new_file.mode())),
         hunks: Vec::new(),
         stats: DiffStats::default(),
     }
@@ -0,0 +1,316 @@
+//! Git fetch and pull operations.
+
+use crate::{
+    CredentialProvider, DefaultCredentialProvider, GitError, GitOid, GitProgress, GitRepository, GitResult,
+    MergeOptions, MergeResult, ProgressCallback, RebaseResult,
+};
+use git2::{AutotagOption, FetchOptions, FetchPrune, RemoteCallbacks};
+use std::cell::RefCell;
+use tracing::{debug, info};
+
+/// Credential requests answered before a fetch gives up.
+const MAX_CREDENTIAL_ATTEMPTS: usize = 3;
+
+/// Fetch options.
+#[derive(Debug, Clone, Default)]
+pub struct FetchOpts {
+    /// Remote name (default: "origin").
+    pub remote: Option<String>,
+    /// Refspecs to fetch (default: the remote's configured ones).
+    pub refspecs: Vec<String>,
+    /// Remove remote-tracking references that no longer exist on the remote.
+    pub prune: bool,
+    /// Fetch all tags, not just those pointing into fetched history.
+    pub tags: bool,
+}
+
+impl FetchOpts {
+    /// Create default fetch options.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set remote name.
+    pub fn from_remote(mut self, name: impl Into<String>) -> Self {
+        self.remote = Some(name.into());
+        self
+    }
+
+    /// Add a refspec.
+    pub fn refspec(mut self, refspec: impl Into<String>) -> Self {
+        self.refspecs.push(refspec.into());
+        self
+    }
+
+    /// Enable pruning.
+    pub fn prune(mut self) -> Self {
+        self.prune = true;
+        self
+    }
+
+    /// Fetch all tags.
+    pub fn with_tags(mut self) -> Self {
+        self.tags = true;
+        self
+    }
+}
+
+/// A reference changed by a fetch.
+#[derive(Debug, Clone)]
+pub struct UpdatedRef {
+    /// Full reference name (e.g., "refs/remotes/origin/main").
+    pub name: String,
+    /// Previous target (None if the reference is new).
+    pub old: Option<GitOid>,
+    /// New target.
+    pub new: GitOid,
+}
+
+/// Fetch result.
+#[derive(Debug, Clone)]
+pub struct FetchSummary {
+    /// Remote fetched from.
+    pub remote: String,
+    /// References created or moved.
+    pub updated: Vec<UpdatedRef>,
+    /// Objects received.
+    pub received_objects: usize,
+    /// Bytes received.
+    pub received_bytes: usize,
+}
+
+/// How `pull` integrates the fetched branch.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum PullStrategy {
+    /// Merge it into the current branch (fast-forward when possible).
+    #[default]
+    Merge,
+    /// Rebase the current branch onto it.
+    Rebase,
+}
+
+/// Pull options.
+#[derive(Debug, Clone, Default)]
+pub struct PullOpts {
+    /// Remote name (default: the branch's upstream remote, or "origin").
+    pub remote: Option<String>,
+    /// Remote branch to integrate (default: the branch's upstream, or the
+    /// branch of the same name).
+    pub branch: Option<String>,
+    /// Merge or rebase.
+    pub strategy: PullStrategy,
+}
+
+impl PullOpts {
+    /// Create default pull options.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set remote name.
+    pub fn from_remote(mut self, name: impl Into<String>) -> Self {
+        self.remote = Some(name.into());
+        self
+    }
+
+    /// Set the remote branch.
+    pub fn branch(mut self, name: impl Into<String>) -> Self {
+        self.branch = Some(name.into());
+        self
+    }
+
+    /// Rebase instead of merging.
+    pub fn rebase(mut self) -> Self {
+        self.strategy = PullStrategy::Rebase;
+        self
+    }
+}
+
+/// How a pull ended.
+#[derive(Debug, Clone)]
+pub enum PullOutcome {
+    /// The fetched branch was merged.
+    Merged(MergeResult),
+    /// The current branch was rebased.
+    Rebased(RebaseResult),
+}
+
+/// Pull result.
+#[derive(Debug, Clone)]
+pub struct PullResult {
+    /// What the fetch brought in.
+    pub fetch: FetchSummary,
+    /// The merge or rebase that followed.
+    pub outcome: PullOutcome,
+}
+
+impl GitRepository {
+    /// Fetch from a remote.
+    pub fn fetch(&self, options: FetchOpts) -> GitResult<FetchSummary> {
+        self.fetch_with(options, None, None)
+    }
+
+    /// Fetch with credentials and a progress callback.
+    pub fn fetch_with(
+        &self,
+        options: FetchOpts,
+        credentials: Option<Box<dyn CredentialProvider>>,
+        progress: Option<ProgressCallback<'_>>,
+    ) -> GitResult<FetchSummary> {
+        let mut noop = |_: &GitProgress| {};
+        let progress = RefCell::new(progress.unwrap_or(&mut noop));
+        let credentials = credentials.unwrap_or_else(|| Box::new(DefaultCredentialProvider));
+        let remote_name = options.remote.as_deref().unwrap_or("origin");
+
+        self.with_repo(|repo| {
+            let mut remote = repo.find_remote(remote_name).map_err(|_| GitError::RemoteNotFound {
+                name: remote_name.to_string(),
+            })?;
+
+            let updated = RefCell::new(Vec::new());
+            let mut attempts = 0;
+            let mut callbacks = RemoteCallbacks::new();
+            callbacks.credentials(|url, username_from_url, allowed_types| {
+                attempts += 1;
+                if attempts > MAX_CREDENTIAL_ATTEMPTS {
+                    return Err(git2::Error::from_str("authentication failed: credentials rejected"));
+                }
+                credentials.get_credentials(url, username_from_url, allowed_types)
+            });
+            callbacks.transfer_progress(|stats| {
+                (progress.borrow_mut())(&GitProgress::Transfer {
+                    received_objects: stats.received_objects(),
+                    indexed_objects: stats.indexed_objects(),
+                    total_objects: stats.total_objects(),
+                    received_bytes: stats.received_bytes(),
+                });
+                true
+            });
+            callbacks.sideband_progress(|text| {
+                let text = String::from_utf8_lossy(text).trim().to_string();
+                if !text.is_empty() {
+                    (progress.borrow_mut())(&GitProgress::Remote(text));
+                }
+                true
+            });
+            callbacks.update_tips(|name, old, new| {
+                debug!("Fetch updated {}: {} -> {}", name, old, new);
+                updated.borrow_mut().push(UpdatedRef {
+                    name: name.to_string(),
+                    old: (!old.is_zero()).then(|| GitOid::from_git2(old)),
+                    new: GitOid::from_git2(new),
+                });
+                true
+            });
+
+            let mut fetch_opts = FetchOptions::new();
+            fetch_opts.remote_callbacks(callbacks);
+            fetch_opts.prune(if options.prune { FetchPrune::On } else { FetchPrune::Unspecified });
+            if options.tags {
+                fetch_opts.download_tags(AutotagOption::All);
+            }
+
+            let refspecs: Vec<&str> = options.refspecs.iter().map(|s| s.as_str()).collect();
+            remote
+                .fetch(&refspecs, Some(&mut fetch_opts), None)
+                .map_err(remote_error)?;
+            drop(fetch_opts);
+
+            let stats = remote.stats();
+            let summary = FetchSummary {
+                remote: remote_name.to_string(),
+                updated: updated.into_inner(),
+                received_objects: stats.received_objects(),
+                received_bytes: stats.received_bytes(),
+            };
+            info!("Fetched {}: {} refs updated", remote_name, summary.updated.len());
+            Ok(summary)
+        })
+    }
+
+    /// Fetch the current branch's upstream and merge or rebase onto it.
+    pub fn pull(&self, options: PullOpts) -> GitResult<PullResult> {
+        self.pull_with(options, None, None)
+    }
+
+    /// Pull with credentials and a progress callback.
+    pub fn pull_with(
+        &self,
+        options: PullOpts,
+        credentials: Option<Box<dyn CredentialProvider>>,
+        progress: Option<ProgressCallback<'_>>,
+    ) -> GitResult<PullResult> {
+        let mut noop = |_: &GitProgress| {};
+        let progress = progress.unwrap_or(&mut noop);
+        let (remote, upstream) = self.pull_target(&options)?;
+
+        let fetch = self.fetch_with(FetchOpts::new().from_remote(&remote), credentials, Some(&mut *progress))?;
+
+        let target = self.with_repo(|repo| {
+            let reference = repo.find_reference(&upstream).map_err(|_| GitError::RefNotFound {
+                name: upstream.clone(),
+            })?;
+            let commit = reference.peel_to_commit()?;
+            Ok::<_, GitError>(GitOid::from_git2(commit.id()))
+        })?;
+
+        let outcome = match options.strategy {
+            PullStrategy::Merge => {
+                let short = upstream.strip_prefix("refs/remotes/").unwrap_or(&upstream);
+                let merge_options =
+                    MergeOptions::standard().with_message(format!("Merge remote-tracking branch '{}'", short));
+                PullOutcome::Merged(self.merge(&target, Some(merge_options))?)
+            }
+            PullStrategy::Rebase => PullOutcome::Rebased(self.rebase(&upstream, Some(progress))?),
+        };
+
+        Ok(PullResult { fetch, outcome })
+    }
+
+    /// The remote to fetch and the remote-tracking reference to integrate.
+    fn pull_target(&self, options: &PullOpts) -> GitResult<(String, String)> {
+        self.with_repo(|repo| {
+            let head = repo.head()?;
+            let branch_ref = head.name().filter(|_| head.is_branch()).ok_or_else(|| GitError::InvalidOperation {
+                message: "Cannot pull: HEAD is detached".to_string(),
+            })?;
+            let branch = head.shorthand().unwrap_or_default();
+
+            let configured_remote = repo
+                .branch_upstream_remote(branch_ref)
+                .ok()
+                .and_then(|r| r.as_str().map(String::from));
+            let remote = options
+                .remote
+                .clone()
+                .or(configured_remote)
+                .unwrap_or_else(|| "origin".to_string());
+
+            let upstream = match &options.branch {
+                Some(name) => format!("refs/remotes/{}/{}", remote, name),
+                None => repo
+                    .branch_upstream_name(branch_ref)
+                    .ok()
+                    .and_then(|r| r.as_str().map(String::from))
+                    .filter(|name| name.starts_with(&format!("refs/remotes/{}/", remote)))
+                    .unwrap_or_else(|| format!("refs/remotes/{}/{}", remote, branch)),
+            };
+            Ok((remote, upstream))
+        })
+    }
+}
+
+/// Classify an error from talking to a remote.
+fn remote_error(e: git2::Error) -> GitError {
+    if e.code() == git2::ErrorCode::Auth || e.message().contains("authentication") {
+        GitError::AuthFailed {
+            reason: e.message().to_string(),
+        }
+    } else if matches!(e.class(), git2::ErrorClass::Net | git2::ErrorClass::Http | git2::ErrorClass::Ssh) {
+        GitError::Network {
+            message: e.message().to_string(),
+        }
+    } else {
+        GitError::Git2(e)
+    }
+}
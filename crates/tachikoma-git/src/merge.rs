@@ -103,11 +103,11 @@ impl GitRepository {
     /// Merge a branch into HEAD.
     pub fn merge_branch(&self, branch: &str, options: Option<MergeOptions>) -> GitResult<MergeResult> {
         let oid = self.with_repo(|repo| {
-            let branch = repo.find_branch(branch, git2::BranchType::Local)?;
-            let oid = branch.get().target().ok_or_else(|| GitError::BranchNotFound {
+            let found = repo.find_branch(branch, git2::BranchType::Local)?;
+            let oid = found.get().target().ok_or_else(|| GitError::BranchNotFound {
                 name: branch.to_string(),
             })?;
-            Ok(GitOid::from_git2(oid))
+            Ok::<_, GitError>(GitOid::from_git2(oid))
         })?;
 
         self.merge(&oid, options)
@@ -135,17 +135,7 @@ impl GitRepository {
 
             // Can fast-forward?
             if merge_base == head.id() && options.allow_ff {
-                // Fast-forward
-                repo.checkout_tree(their_commit.tree()?.as_object(), None)?;
-                repo.set_head_detached(their_commit.id())?;
-                // Re-attach to branch
-                if let Ok(head_ref) = repo.head() {
-                    if let Some(name) = head_ref.shorthand() {
-                        let branch_ref = format!("refs/heads/{}", name);
-                        repo.reference(&branch_ref, their_commit.id(), true, "fast-forward")?;
-                        repo.set_head(&branch_ref)?;
-                    }
-                }
+                fast_forward(repo, &their_commit)?;
 
                 return Ok(MergeResult {
                     result_type: MergeResultType::FastForward,
@@ -257,9 +247,6 @@ impl GitRepository {
 
             // Get merge heads
             let head = repo.head()?.peel_to_commit()?;
-            let merge_heads: Vec<git2::Oid> = repo.mergehead_foreach(|oid| {
-                true
-            }).map(|_| Vec::new()).unwrap_or_default();
 
             // For simplicity, we'll just get MERGE_HEAD directly
             let merge_head_path = repo.path().join("MERGE_HEAD");
@@ -328,7 +315,23 @@ impl GitRepository {
     }
 }
 
-fn collect_conflicts(index: &git2::Index) -> GitResult<Vec<ConflictFile>> {
+/// Move HEAD (and the branch it's on) to `target` and check it out.
+pub(crate) fn fast_forward(repo: &git2::Repository, target: &git2::Commit) -> GitResult<()> {
+    repo.checkout_tree(target.as_object(), Some(git2::build::CheckoutBuilder::new().safe()))?;
+
+    let head = repo.head()?;
+    match head.name().filter(|_| head.is_branch()) {
+        Some(branch_ref) => {
+            let branch_ref = branch_ref.to_string();
+            repo.reference(&branch_ref, target.id(), true, "fast-forward")?;
+            repo.set_head(&branch_ref)?;
+        }
+        None => repo.set_head_detached(target.id())?,
+    }
+    Ok(())
+}
+
+pub(crate) fn collect_conflicts(index: &git2::Index) -> GitResult<Vec<ConflictFile>> {
     let mut conflicts = Vec::new();
 
     for conflict in index.conflicts()? {
@@ -0,0 +1,45 @@
+//! Progress reporting for long-running operations.
+
+use crate::GitOid;
+
+/// Progress of a fetch, pull, rebase, cherry-pick or revert.
+#[derive(Debug, Clone)]
+pub enum GitProgress {
+    /// Objects received from a remote.
+    Transfer {
+        /// Objects received so far.
+        received_objects: usize,
+        /// Objects indexed so far.
+        indexed_objects: usize,
+        /// Objects to receive.
+        total_objects: usize,
+        /// Bytes received so far.
+        received_bytes: usize,
+    },
+    /// Progress text sent by the remote (e.g. "Counting objects: 100%").
+    Remote(String),
+    /// Applying a commit.
+    Applying {
+        /// Position of the commit, starting at 1.
+        current: usize,
+        /// Commits to apply.
+        total: usize,
+        /// The commit being applied.
+        commit: GitOid,
+        /// Its summary line.
+        summary: String,
+    },
+}
+
+/// Progress callback, called as an operation advances.
+pub type ProgressCallback<'a> = &'a mut dyn FnMut(&GitProgress);
+
+/// Report an applied commit.
+pub(crate) fn applying(progress: &mut dyn FnMut(&GitProgress), current: usize, total: usize, commit: &git2::Commit) {
+    progress(&GitProgress::Applying {
+        current,
+        total,
+        commit: GitOid::from_git2(commit.id()),
+        summary: commit.summary().unwrap_or_default().to_string(),
+    });
+}
@@ -148,7 +148,6 @@ impl GitRepository {
                 p.total = total as u32;
                 p.bytes = bytes as u64;
                 debug!("Push progress: {}/{} objects, {} bytes", current, total, bytes);
-                true // Continue
             });
 
             // Track pushed refs
@@ -0,0 +1,190 @@
+//! Git rebase operations.
+//!
+//! A rebase replays the current branch's commits onto another commit, one at
+//! a time. When one of them conflicts it stops with the conflicts in the
+//! index and work tree; resolve and stage them, then call
+//! [`GitRepository::rebase_continue`], or give up with
+//! [`GitRepository::rebase_abort`].
+
+use crate::merge::{collect_conflicts, fast_forward};
+use crate::progress::applying;
+use crate::{ConflictFile, GitError, GitOid, GitProgress, GitRepository, GitResult, ProgressCallback};
+use git2::{Rebase, Repository, RepositoryState, Signature};
+use serde::{Deserialize, Serialize};
+use tracing::info;
+
+/// Rebase result.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RebaseResult {
+    /// How the rebase ended.
+    pub status: RebaseStatus,
+    /// HEAD after the rebase (or where it stopped).
+    pub head: Option<GitOid>,
+    /// Commits applied so far.
+    pub applied: usize,
+    /// Commit that could not be applied cleanly.
+    pub stopped_at: Option<GitOid>,
+    /// Conflicted files, when stopped.
+    pub conflicts: Vec<ConflictFile>,
+}
+
+/// How a rebase ended.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RebaseStatus {
+    /// The branch already contains the target.
+    UpToDate,
+    /// The branch had no commits of its own and was moved to the target.
+    FastForward,
+    /// Every commit was replayed.
+    Completed,
+    /// Stopped on a conflict that needs resolution.
+    Conflict,
+}
+
+impl GitRepository {
+    /// Rebase the current branch onto `upstream` (a branch, tag or commit).
+    pub fn rebase(&self, upstream: &str, progress: Option<ProgressCallback<'_>>) -> GitResult<RebaseResult> {
+        let mut noop = |_: &GitProgress| {};
+        let progress = progress.unwrap_or(&mut noop);
+
+        self.with_repo_mut(|repo| {
+            if repo.state() != RepositoryState::Clean {
+                return Err(GitError::InvalidOperation {
+                    message: format!("Cannot rebase: {:?} in progress", repo.state()),
+                });
+            }
+            ensure_clean(repo)?;
+
+            let head = repo.head()?;
+            if !head.is_branch() {
+                return Err(GitError::InvalidOperation {
+                    message: "Cannot rebase: HEAD is detached".to_string(),
+                });
+            }
+            let head_commit = head.peel_to_commit()?;
+            let target = repo
+                .revparse_single(upstream)
+                .map_err(|_| GitError::RefNotFound {
+                    name: upstream.to_string(),
+                })?
+                .peel_to_commit()?;
+
+            let base = repo.merge_base(head_commit.id(), target.id())?;
+            if base == target.id() {
+                return Ok(finished(RebaseStatus::UpToDate, head_commit.id(), 0));
+            }
+            if base == head_commit.id() {
+                fast_forward(repo, &target)?;
+                return Ok(finished(RebaseStatus::FastForward, target.id(), 0));
+            }
+
+            let branch = repo.reference_to_annotated_commit(&head)?;
+            let onto = repo.find_annotated_commit(target.id())?;
+            let mut rebase = repo.rebase(Some(&branch), Some(&onto), None, None)?;
+            info!("Rebasing {} commits onto {}", rebase.len(), upstream);
+            replay(repo, &mut rebase, progress)
+        })
+    }
+
+    /// Continue a rebase after resolving and staging its conflicts.
+    pub fn rebase_continue(&self, progress: Option<ProgressCallback<'_>>) -> GitResult<RebaseResult> {
+        let mut noop = |_: &GitProgress| {};
+        let progress = progress.unwrap_or(&mut noop);
+
+        self.with_repo_mut(|repo| {
+            let mut rebase = open_rebase(repo)?;
+            if let Some(stopped) = stopped_at(&mut rebase) {
+                // Conflicts may have been staged by another process
+                let mut index = repo.index()?;
+                index.read(false)?;
+                if index.has_conflicts() {
+                    return conflicted(repo, &index, &mut rebase, stopped);
+                }
+                commit_step(&mut rebase, &repo.signature()?)?;
+            }
+            replay(repo, &mut rebase, progress)
+        })
+    }
+
+    /// Abort a rebase, restoring the branch and work tree to where they were.
+    pub fn rebase_abort(&self) -> GitResult<()> {
+        self.with_repo_mut(|repo| {
+            open_rebase(repo)?.abort()?;
+            Ok(())
+        })
+    }
+}
+
+/// Apply the remaining operations, stopping at the first conflict.
+fn replay(repo: &Repository, rebase: &mut Rebase, progress: &mut dyn FnMut(&GitProgress)) -> GitResult<RebaseResult> {
+    let sig = repo.signature()?;
+    let total = rebase.len();
+
+    while let Some(operation) = rebase.next() {
+        let id = operation?.id();
+        let current = rebase.operation_current().map_or(total, |i| i + 1);
+        applying(progress, current, total, &repo.find_commit(id)?);
+
+        let index = repo.index()?;
+        if index.has_conflicts() {
+            return conflicted(repo, &index, rebase, id);
+        }
+        commit_step(rebase, &sig)?;
+    }
+
+    rebase.finish(Some(&sig))?;
+    let head = repo.head()?.peel_to_commit()?;
+    info!("Rebase complete at {}", head.id());
+    Ok(finished(RebaseStatus::Completed, head.id(), total))
+}
+
+/// Commit the current operation; one whose changes are already upstream is skipped.
+fn commit_step(rebase: &mut Rebase, sig: &Signature) -> GitResult<()> {
+    match rebase.commit(None, sig, None) {
+        Err(e) if e.code() != git2::ErrorCode::Applied => Err(e.into()),
+        _ => Ok(()),
+    }
+}
+
+fn open_rebase(repo: &Repository) -> GitResult<Rebase<'_>> {
+    repo.open_rebase(None).map_err(|_| GitError::InvalidOperation {
+        message: "No rebase in progress".to_string(),
+    })
+}
+
+fn stopped_at(rebase: &mut Rebase) -> Option<git2::Oid> {
+    let current = rebase.operation_current()?;
+    rebase.nth(current).map(|operation| operation.id())
+}
+
+fn conflicted(repo: &Repository, index: &git2::Index, rebase: &mut Rebase, stopped: git2::Oid) -> GitResult<RebaseResult> {
+    Ok(RebaseResult {
+        status: RebaseStatus::Conflict,
+        head: repo.head()?.target().map(GitOid::from_git2),
+        applied: rebase.operation_current().unwrap_or(0),
+        stopped_at: Some(GitOid::from_git2(stopped)),
+        conflicts: collect_conflicts(index)?,
+    })
+}
+
+fn finished(status: RebaseStatus, head: git2::Oid, applied: usize) -> RebaseResult {
+    RebaseResult {
+        status,
+        head: Some(GitOid::from_git2(head)),
+        applied,
+        stopped_at: None,
+        conflicts: Vec::new(),
+    }
+}
+
+/// Fail with [`GitError::DirtyWorkDir`] if tracked files have uncommitted changes.
+pub(crate) fn ensure_clean(repo: &Repository) -> GitResult<()> {
+    let mut options = git2::StatusOptions::new();
+    options.include_untracked(false).include_ignored(false);
+    if repo.statuses(Some(&mut options))?.is_empty() {
+        Ok(())
+    } else {
+        Err(GitError::DirtyWorkDir)
+    }
+}
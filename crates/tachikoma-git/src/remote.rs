@@ -72,7 +72,7 @@ impl GitRepository {
     pub fn add_remote(&self, name: &str, url: &str) -> GitResult<GitRemote> {
         self.with_repo_mut(|repo| {
             repo.remote(name, url)?;
-            Ok(())
+            Ok::<_, GitError>(())
         })?;
 
         self.get_remote(name)
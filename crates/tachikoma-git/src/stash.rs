@@ -0,0 +1,190 @@
+//! Git stash operations.
+//!
+//! Stashing sets local changes aside and resets the work tree to HEAD.
+//! Applying a stash can conflict with what has been committed since; the
+//! conflicts are left in the index and work tree, and `stash_pop` keeps the
+//! stash so nothing is lost.
+
+use crate::merge::collect_conflicts;
+use crate::{ConflictFile, GitError, GitOid, GitRepository, GitResult};
+use git2::{ErrorCode, Repository, StashApplyOptions, StashFlags, StashSaveOptions};
+use serde::{Deserialize, Serialize};
+use tracing::info;
+
+/// Stash options.
+#[derive(Debug, Clone, Default)]
+pub struct StashOptions {
+    /// Stash message. libgit2 cannot combine a message with pathspecs, so
+    /// setting both is an error.
+    pub message: Option<String>,
+    /// Stash untracked files too.
+    pub include_untracked: bool,
+    /// Leave staged changes in place.
+    pub keep_index: bool,
+    /// Only stash changes to these paths.
+    pub pathspecs: Vec<String>,
+}
+
+impl StashOptions {
+    /// Create default stash options.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the stash message.
+    pub fn with_message(mut self, message: impl Into<String>) -> Self {
+        self.message = Some(message.into());
+        self
+    }
+
+    /// Stash untracked files too.
+    pub fn include_untracked(mut self) -> Self {
+        self.include_untracked = true;
+        self
+    }
+
+    /// Leave staged changes in place.
+    pub fn keep_index(mut self) -> Self {
+        self.keep_index = true;
+        self
+    }
+
+    /// Limit the stash to a path.
+    pub fn path(mut self, pathspec: impl Into<String>) -> Self {
+        self.pathspecs.push(pathspec.into());
+        self
+    }
+}
+
+/// A stash entry.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StashEntry {
+    /// Position in the stash list (0 is the most recent).
+    pub index: usize,
+    /// Stash message.
+    pub message: String,
+    /// Stash commit OID.
+    pub oid: GitOid,
+}
+
+/// Result of applying or popping a stash.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StashApplyResult {
+    /// Conflicted files; empty if the stash applied cleanly.
+    pub conflicts: Vec<ConflictFile>,
+    /// Whether the stash was dropped.
+    pub dropped: bool,
+}
+
+impl GitRepository {
+    /// Stash local changes. Returns None if there was nothing to stash.
+    pub fn stash_push(&self, options: StashOptions) -> GitResult<Option<StashEntry>> {
+        if options.message.is_some() && !options.pathspecs.is_empty() {
+            return Err(GitError::InvalidOperation {
+                message: "A stash limited to paths cannot have a message".to_string(),
+            });
+        }
+
+        self.with_repo_mut(|repo| {
+            let stasher = repo.signature()?;
+            let mut flags = StashFlags::DEFAULT;
+            if options.include_untracked {
+                flags |= StashFlags::INCLUDE_UNTRACKED;
+            }
+            if options.keep_index {
+                flags |= StashFlags::KEEP_INDEX;
+            }
+
+            let saved = if options.pathspecs.is_empty() {
+                repo.stash_save2(&stasher, options.message.as_deref(), Some(flags))
+            } else {
+                let mut save_opts = StashSaveOptions::new(stasher);
+                save_opts.flags(Some(flags));
+                for pathspec in &options.pathspecs {
+                    save_opts.pathspec(pathspec.as_str());
+                }
+                repo.stash_save_ext(Some(&mut save_opts))
+            };
+            match saved {
+                Ok(oid) => info!("Stashed changes as {}", oid),
+                Err(e) if e.code() == ErrorCode::NotFound => return Ok(None),
+                Err(e) => return Err(e.into()),
+            }
+
+            Ok(entries(repo)?.into_iter().next())
+        })
+    }
+
+    /// List stash entries, most recent first.
+    pub fn stash_list(&self) -> GitResult<Vec<StashEntry>> {
+        self.with_repo_mut(entries)
+    }
+
+    /// Apply a stash, keeping it. With `restore_index` staged changes are
+    /// restored as staged.
+    pub fn stash_apply(&self, index: usize, restore_index: bool) -> GitResult<StashApplyResult> {
+        self.with_repo_mut(|repo| {
+            Ok(StashApplyResult {
+                conflicts: apply(repo, index, restore_index)?,
+                dropped: false,
+            })
+        })
+    }
+
+    /// Apply a stash and drop it, unless applying it conflicted.
+    pub fn stash_pop(&self, index: usize, restore_index: bool) -> GitResult<StashApplyResult> {
+        self.with_repo_mut(|repo| {
+            let conflicts = apply(repo, index, restore_index)?;
+            let dropped = conflicts.is_empty();
+            if dropped {
+                repo.stash_drop(index)?;
+            }
+            Ok(StashApplyResult { conflicts, dropped })
+        })
+    }
+
+    /// Drop a stash.
+    pub fn stash_drop(&self, index: usize) -> GitResult<()> {
+        self.with_repo_mut(|repo| repo.stash_drop(index).map_err(|e| stash_error(e, index)))
+    }
+}
+
+fn entries(repo: &mut Repository) -> GitResult<Vec<StashEntry>> {
+    let mut entries = Vec::new();
+    repo.stash_foreach(|index, message, oid| {
+        entries.push(StashEntry {
+            index,
+            message: message.to_string(),
+            oid: GitOid::from_git2(*oid),
+        });
+        true
+    })?;
+    Ok(entries)
+}
+
+/// Apply a stash, returning the conflicts it left.
+fn apply(repo: &mut Repository, index: usize, restore_index: bool) -> GitResult<Vec<ConflictFile>> {
+    // The index may have been changed by another process
+    repo.index()?.read(false)?;
+
+    let mut apply_opts = StashApplyOptions::new();
+    if restore_index {
+        apply_opts.reinstantiate_index();
+    }
+    repo.stash_apply(index, Some(&mut apply_opts))
+        .map_err(|e| stash_error(e, index))?;
+    collect_conflicts(&repo.index()?)
+}
+
+fn stash_error(e: git2::Error, index: usize) -> GitError {
+    match e.code() {
+        ErrorCode::NotFound => GitError::RefNotFound {
+            name: format!("stash@{{{}}}", index),
+        },
+        // Local changes in the way, or staged changes that cannot be restored
+        ErrorCode::Conflict | ErrorCode::Uncommitted => GitError::InvalidOperation {
+            message: format!("Cannot apply stash@{{{}}}: {}", index, e.message()),
+        },
+        _ => GitError::Git2(e),
+    }
+}
@@ -81,7 +81,7 @@ fn get_branch_info(
             if let Ok(upstream_branch) = local_branch.upstream() {
                 let upstream_name = upstream_branch.name()?.map(String::from);
 
-                let (ahead, behind) = if let (Some(local_oid), Ok(upstream_ref)) =
+                let (ahead, behind) = if let (Some(local_oid), Some(upstream_ref)) =
                     (head.target(), upstream_branch.into_reference().target())
                 {
                     repo.graph_ahead_behind(local_oid, upstream_ref)
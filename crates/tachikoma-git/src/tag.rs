@@ -0,0 +1,234 @@
+//! Git tag operations.
+//!
+//! Tags are either lightweight (a reference straight to a commit) or
+//! annotated (a tag object with a tagger and message). Release tags are
+//! recognized by their semantic version, with or without a leading "v".
+
+use crate::{GitError, GitOid, GitRepository, GitResult, GitSignature, GitTag};
+use git2::{ErrorCode, Repository};
+use semver::Version;
+use serde::{Deserialize, Serialize};
+use std::process::Command;
+use tracing::info;
+
+/// Lines that start a signature appended to a tag message.
+const SIGNATURE_HEADERS: &[&str] = &[
+    "-----BEGIN PGP SIGNATURE-----",
+    "-----BEGIN SSH SIGNATURE-----",
+    "-----BEGIN SIGNED MESSAGE-----",
+];
+
+/// Tag creation options.
+#[derive(Debug, Clone, Default)]
+pub struct TagOptions {
+    /// Revision to tag (default: HEAD).
+    pub target: Option<String>,
+    /// Annotation message; without one the tag is lightweight.
+    pub message: Option<String>,
+    /// Replace an existing tag of the same name.
+    pub force: bool,
+}
+
+impl TagOptions {
+    /// Create options for a lightweight tag on HEAD.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Create options for an annotated tag on HEAD.
+    pub fn annotated(message: impl Into<String>) -> Self {
+        Self {
+            message: Some(message.into()),
+            ..Self::default()
+        }
+    }
+
+    /// Tag this revision instead of HEAD.
+    pub fn at(mut self, target: impl Into<String>) -> Self {
+        self.target = Some(target.into());
+        self
+    }
+
+    /// Replace an existing tag.
+    pub fn force(mut self) -> Self {
+        self.force = true;
+        self
+    }
+}
+
+/// Result of verifying a tag.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TagVerification {
+    /// The tag.
+    pub tag: GitTag,
+    /// Whether the tag is annotated.
+    pub annotated: bool,
+    /// Whether the tag carries a signature.
+    pub signed: bool,
+    /// Whether the signature checked out.
+    pub verified: bool,
+    /// Output of the signature check, if one ran.
+    pub output: Option<String>,
+}
+
+impl GitRepository {
+    /// Create a tag.
+    pub fn create_tag(&self, name: &str, options: TagOptions) -> GitResult<GitTag> {
+        if !git2::Tag::is_valid_name(name) {
+            return Err(GitError::InvalidOperation {
+                message: format!("Invalid tag name: {}", name),
+            });
+        }
+        let revision = options.target.as_deref().unwrap_or("HEAD");
+
+        self.with_repo(|repo| {
+            let target = repo.revparse_single(revision).map_err(|_| GitError::RefNotFound {
+                name: revision.to_string(),
+            })?;
+
+            let created = match &options.message {
+                Some(message) => {
+                    let message = git2::message_prettify(message, None)?;
+                    repo.tag(name, &target, &repo.signature()?, &message, options.force)
+                }
+                None => repo.tag_lightweight(name, &target, options.force),
+            };
+            created.map_err(|e| match e.code() {
+                ErrorCode::Exists => GitError::InvalidOperation {
+                    message: format!("Tag already exists: {}", name),
+                },
+                _ => GitError::Git2(e),
+            })?;
+
+            info!("Created tag {} at {}", name, target.id());
+            find_tag(repo, name)
+        })
+    }
+
+    /// Get a tag by name.
+    pub fn get_tag(&self, name: &str) -> GitResult<GitTag> {
+        self.with_repo(|repo| find_tag(repo, name))
+    }
+
+    /// List tags, optionally matching a glob pattern (e.g. "v1.*"), sorted by name.
+    pub fn list_tags(&self, pattern: Option<&str>) -> GitResult<Vec<GitTag>> {
+        self.with_repo(|repo| {
+            let mut names: Vec<String> = repo.tag_names(pattern)?.iter().flatten().map(String::from).collect();
+            names.sort();
+            names.iter().map(|name| find_tag(repo, name)).collect()
+        })
+    }
+
+    /// Delete a tag.
+    pub fn delete_tag(&self, name: &str) -> GitResult<()> {
+        self.with_repo(|repo| {
+            repo.tag_delete(name).map_err(|e| match e.code() {
+                ErrorCode::NotFound => GitError::RefNotFound {
+                    name: format!("refs/tags/{}", name),
+                },
+                _ => GitError::Git2(e),
+            })?;
+            info!("Deleted tag {}", name);
+            Ok(())
+        })
+    }
+
+    /// Verify a tag: it must exist and point at a commit, and its signature,
+    /// if it has one, is checked with `git verify-tag`.
+    pub fn verify_tag(&self, name: &str) -> GitResult<TagVerification> {
+        let (tag, annotated, signed) = self.with_repo(|repo| {
+            let tag = find_tag(repo, name)?;
+            repo.find_commit(tag.target.as_git2()).map_err(|_| GitError::CommitNotFound {
+                oid: tag.target.to_hex(),
+            })?;
+
+            let object = repo.find_tag(tag.oid.as_git2()).ok();
+            let annotated = object.is_some();
+            let signed = object
+                .as_ref()
+                .and_then(|t| t.message())
+                .is_some_and(|m| signature_start(m).is_some());
+            Ok::<_, GitError>((tag, annotated, signed))
+        })?;
+
+        if !signed {
+            return Ok(TagVerification {
+                tag,
+                annotated,
+                signed,
+                verified: false,
+                output: None,
+            });
+        }
+
+        let output = Command::new("git")
+            .args(["verify-tag", "--raw", name])
+            .current_dir(self.root_path())
+            .output()?;
+        Ok(TagVerification {
+            tag,
+            annotated,
+            signed,
+            verified: output.status.success(),
+            output: Some(String::from_utf8_lossy(&output.stderr).trim().to_string()),
+        })
+    }
+
+    /// The release tag with the highest semantic version.
+    ///
+    /// With a `prefix` only tags starting with it are considered, and it is
+    /// stripped before parsing ("tachikoma-v1.2.0" with prefix "tachikoma-").
+    /// Pre-release versions are skipped.
+    pub fn latest_release_tag(&self, prefix: Option<&str>) -> GitResult<Option<GitTag>> {
+        self.with_repo(|repo| {
+            let names = repo.tag_names(None)?;
+            let latest = names
+                .iter()
+                .flatten()
+                .filter_map(|name| {
+                    let version = release_version(name.strip_prefix(prefix.unwrap_or(""))?)?;
+                    Some((version, name))
+                })
+                .max_by(|a, b| a.0.cmp(&b.0));
+
+            latest.map(|(_, name)| find_tag(repo, name)).transpose()
+        })
+    }
+}
+
+/// Parse a release version from a tag name such as "v1.2.3" or "1.2.3".
+fn release_version(name: &str) -> Option<Version> {
+    let version = Version::parse(name.strip_prefix('v').unwrap_or(name)).ok()?;
+    version.pre.is_empty().then_some(version)
+}
+
+fn find_tag(repo: &Repository, name: &str) -> GitResult<GitTag> {
+    let refname = format!("refs/tags/{}", name);
+    let reference = repo
+        .find_reference(&refname)
+        .map_err(|_| GitError::RefNotFound { name: refname.clone() })?;
+    let oid = reference.target().ok_or(GitError::RefNotFound { name: refname })?;
+    let object = repo.find_object(oid, None)?;
+    let target = object.peel_to_commit().map(|c| c.id()).unwrap_or(oid);
+
+    let (message, tagger) = match object.as_tag() {
+        Some(tag) => (
+            tag.message().map(|m| m[..signature_start(m).unwrap_or(m.len())].trim_end().to_string()),
+            tag.tagger().map(|s| GitSignature::from_git2(&s)),
+        ),
+        None => (None, None),
+    };
+
+    Ok(GitTag {
+        name: name.to_string(),
+        oid: GitOid::from_git2(oid),
+        target: GitOid::from_git2(target),
+        message,
+        tagger,
+    })
+}
+
+/// Offset of the signature block in a tag message.
+fn signature_start(message: &str) -> Option<usize> {
+    SIGNATURE_HEADERS.iter().filter_map(|header| message.find(header)).min()
+}
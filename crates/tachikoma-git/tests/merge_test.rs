@@ -0,0 +1,53 @@
+//! Tests for merging.
+
+use std::path::Path;
+use tachikoma_git::{GitOid, GitRepository, MergeOptions, MergeResultType};
+use tempfile::TempDir;
+
+fn setup() -> (TempDir, git2::Repository, GitRepository) {
+    let temp_dir = TempDir::new().expect("Failed to create temp dir");
+    let raw = git2::Repository::init(temp_dir.path()).expect("Failed to initialize repository");
+    let mut config = raw.config().unwrap();
+    config.set_str("user.name", "Test User").unwrap();
+    config.set_str("user.email", "test@example.com").unwrap();
+    commit(&raw, "a.txt", "one\n", "Initial commit");
+
+    let repo = GitRepository::open(temp_dir.path()).expect("Failed to open repository");
+    (temp_dir, raw, repo)
+}
+
+fn commit(repo: &git2::Repository, file: &str, content: &str, message: &str) -> GitOid {
+    std::fs::write(repo.workdir().unwrap().join(file), content).unwrap();
+    let mut index = repo.index().unwrap();
+    index.read(true).unwrap();
+    index.add_path(Path::new(file)).unwrap();
+    index.write().unwrap();
+    let tree = repo.find_tree(index.write_tree().unwrap()).unwrap();
+    let sig = repo.signature().unwrap();
+    let parents: Vec<git2::Commit> = repo.head().ok().and_then(|h| h.peel_to_commit().ok()).into_iter().collect();
+    let parents: Vec<&git2::Commit> = parents.iter().collect();
+    let oid = repo.commit(Some("HEAD"), &sig, &sig, message, &tree, &parents).unwrap();
+    GitOid::from_git2(oid)
+}
+
+#[test]
+fn test_fast_forward_moves_current_branch() {
+    let (_temp_dir, raw, repo) = setup();
+    let main = raw.head().unwrap().name().unwrap().to_string();
+    let base = raw.head().unwrap().peel_to_commit().unwrap();
+    raw.branch("feature", &base, false).unwrap();
+    raw.set_head("refs/heads/feature").unwrap();
+    let tip = commit(&raw, "a.txt", "two\n", "Feature commit");
+    raw.set_head(&main).unwrap();
+    raw.checkout_head(Some(git2::build::CheckoutBuilder::new().force())).unwrap();
+
+    let merged = repo.merge_branch("feature", Some(MergeOptions::standard())).unwrap();
+    assert_eq!(merged.result_type, MergeResultType::FastForward);
+
+    // HEAD stays on the branch it was on, and that branch moves
+    let head = raw.head().unwrap();
+    assert_eq!(head.name(), Some(main.as_str()));
+    assert_eq!(head.target(), Some(tip.as_git2()));
+    assert!(raw.find_reference("refs/heads/HEAD").is_err());
+    assert_eq!(std::fs::read_to_string(repo.root_path().join("a.txt")).unwrap(), "two\n");
+}
@@ -1,7 +1,7 @@
 //! Tests for Git remote management functionality.
 
 use std::path::Path;
-use tachikoma_git::{GitRepository, GitRepositoryOptions};
+use tachikoma_git::GitRepository;
 use tempfile::TempDir;
 
 fn setup_test_repo() -> (TempDir, GitRepository) {
@@ -30,10 +30,11 @@ fn setup_test_repo() -> (TempDir, GitRepository) {
         &tree,
         &[],
     ).unwrap();
-    
+
+    drop(tree);
     drop(git_repo);
 
-    let repo = GitRepository::open(&work_dir, GitRepositoryOptions::default())
+    let repo = GitRepository::open(&work_dir)
         .expect("Failed to open repository");
 
     (temp_dir, repo)
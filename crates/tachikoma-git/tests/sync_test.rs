@@ -0,0 +1,187 @@
+//! Tests for fetch, pull, rebase, cherry-pick and revert.
+
+use std::path::Path;
+use tachikoma_git::{
+    FetchOpts, GitOid, GitProgress, GitRepository, MergeResultType, PullOpts, PullOutcome, RebaseStatus,
+};
+use tempfile::TempDir;
+
+/// An upstream repository and a clone of it.
+fn setup() -> (TempDir, git2::Repository, GitRepository) {
+    let temp_dir = TempDir::new().expect("Failed to create temp dir");
+    let upstream_path = temp_dir.path().join("upstream");
+    let upstream = git2::Repository::init(&upstream_path).expect("Failed to initialize upstream");
+    configure(&upstream);
+    commit(&upstream, "a.txt", "one\n", "Initial commit");
+
+    let local_path = temp_dir.path().join("local");
+    let local = git2::Repository::clone(upstream_path.to_str().unwrap(), &local_path).expect("Failed to clone");
+    configure(&local);
+    drop(local);
+
+    let repo = GitRepository::open(&local_path).expect("Failed to open repository");
+    (temp_dir, upstream, repo)
+}
+
+fn configure(repo: &git2::Repository) {
+    let mut config = repo.config().unwrap();
+    config.set_str("user.name", "Test User").unwrap();
+    config.set_str("user.email", "test@example.com").unwrap();
+}
+
+fn commit(repo: &git2::Repository, file: &str, content: &str, message: &str) -> GitOid {
+    let root = repo.workdir().unwrap();
+    std::fs::write(root.join(file), content).unwrap();
+    let mut index = repo.index().unwrap();
+    index.add_path(Path::new(file)).unwrap();
+    index.write().unwrap();
+    let tree = repo.find_tree(index.write_tree().unwrap()).unwrap();
+    let sig = repo.signature().unwrap();
+    let parents: Vec<git2::Commit> = repo.head().ok().and_then(|h| h.peel_to_commit().ok()).into_iter().collect();
+    let parents: Vec<&git2::Commit> = parents.iter().collect();
+    let oid = repo.commit(Some("HEAD"), &sig, &sig, message, &tree, &parents).unwrap();
+    GitOid::from_git2(oid)
+}
+
+/// Stage a file the way `git add` would, from outside the repository handle.
+fn stage(repo: &git2::Repository, file: &str) {
+    let mut index = repo.index().unwrap();
+    index.read(true).unwrap();
+    index.add_path(Path::new(file)).unwrap();
+    index.write().unwrap();
+}
+
+fn read(repo: &GitRepository, file: &str) -> String {
+    std::fs::read_to_string(repo.root_path().join(file)).unwrap()
+}
+
+fn head(repo: &GitRepository) -> git2::Oid {
+    repo.with_repo(|r| r.head().unwrap().target().unwrap())
+}
+
+#[test]
+fn test_fetch_and_pull_merge() {
+    let (_temp_dir, upstream, repo) = setup();
+    let upstream_head = commit(&upstream, "a.txt", "two\n", "Second commit");
+
+    let mut events = Vec::new();
+    let mut progress = |p: &GitProgress| events.push(p.clone());
+    let fetched = repo
+        .fetch_with(FetchOpts::new(), None, Some(&mut progress))
+        .expect("Failed to fetch");
+    let branch = upstream.head().unwrap().shorthand().unwrap().to_string();
+    let tracking = format!("refs/remotes/origin/{}", branch);
+    let update = fetched.updated.iter().find(|u| u.name == tracking).expect("No tracking ref update");
+    assert_eq!(update.new, upstream_head);
+    assert!(events.iter().any(|e| matches!(e, GitProgress::Transfer { .. })));
+
+    // Nothing new on the second fetch
+    assert!(repo.fetch(FetchOpts::new()).unwrap().updated.is_empty());
+
+    let pulled = repo.pull(PullOpts::new()).expect("Failed to pull");
+    match pulled.outcome {
+        PullOutcome::Merged(merge) => assert_eq!(merge.result_type, MergeResultType::FastForward),
+        other => panic!("Unexpected outcome: {:?}", other),
+    }
+    assert_eq!(head(&repo), upstream_head.as_git2());
+    assert_eq!(read(&repo, "a.txt"), "two\n");
+    assert!(repo.with_repo(|r| r.head().unwrap().is_branch()));
+}
+
+#[test]
+fn test_rebase_conflict_continue_and_abort() {
+    let (_temp_dir, upstream, repo) = setup();
+    let local = repo.with_repo(|r| git2::Repository::open(r.workdir().unwrap()).unwrap());
+    commit(&local, "b.txt", "mine\n", "Add b");
+    commit(&local, "a.txt", "local\n", "Change a locally");
+    let upstream_head = commit(&upstream, "a.txt", "upstream\n", "Change a upstream");
+
+    let mut applying = Vec::new();
+    let mut progress = |p: &GitProgress| {
+        if let GitProgress::Applying { current, total, summary, .. } = p {
+            applying.push((*current, *total, summary.clone()));
+        }
+    };
+    let pulled = repo
+        .pull_with(PullOpts::new().rebase(), None, Some(&mut progress))
+        .expect("Failed to pull");
+    let PullOutcome::Rebased(rebased) = pulled.outcome else {
+        panic!("Expected a rebase");
+    };
+    assert_eq!(rebased.status, RebaseStatus::Conflict);
+    assert_eq!(rebased.applied, 1);
+    assert_eq!(rebased.conflicts[0].path, Path::new("a.txt"));
+    assert_eq!(applying, [(1, 2, "Add b".to_string()), (2, 2, "Change a locally".to_string())]);
+
+    // Continuing with the conflict unresolved stops again
+    assert_eq!(repo.rebase_continue(None).unwrap().status, RebaseStatus::Conflict);
+
+    // Resolve, stage and continue
+    std::fs::write(repo.root_path().join("a.txt"), "both\n").unwrap();
+    stage(&local, "a.txt");
+    let done = repo.rebase_continue(None).expect("Failed to continue");
+    assert_eq!(done.status, RebaseStatus::Completed);
+
+    let rebased_head = local.find_commit(head(&repo)).unwrap();
+    assert_eq!(rebased_head.summary(), Some("Change a locally"));
+    assert_eq!(rebased_head.parent(0).unwrap().parent_id(0).unwrap(), upstream_head.as_git2());
+    assert_eq!(read(&repo, "a.txt"), "both\n");
+    assert_eq!(local.state(), git2::RepositoryState::Clean);
+
+    // A conflicting rebase can be abandoned
+    commit(&local, "a.txt", "again\n", "Change a again");
+    let before = head(&repo);
+    commit(&upstream, "a.txt", "upstream again\n", "Change a upstream again");
+    repo.fetch(FetchOpts::new()).unwrap();
+    let branch = upstream.head().unwrap().shorthand().unwrap().to_string();
+    let result = repo.rebase(&format!("origin/{}", branch), None).unwrap();
+    assert_eq!(result.status, RebaseStatus::Conflict);
+    repo.rebase_abort().expect("Failed to abort");
+    assert_eq!(head(&repo), before);
+    assert_eq!(read(&repo, "a.txt"), "again\n");
+    assert_eq!(local.state(), git2::RepositoryState::Clean);
+}
+
+#[test]
+fn test_cherry_pick_and_revert() {
+    let (_temp_dir, _upstream, repo) = setup();
+    let local = repo.with_repo(|r| git2::Repository::open(r.workdir().unwrap()).unwrap());
+    let base = local.head().unwrap().peel_to_commit().unwrap();
+    let main = local.head().unwrap().name().unwrap().to_string();
+
+    // Two commits on a side branch
+    local.branch("side", &base, false).unwrap();
+    local.set_head("refs/heads/side").unwrap();
+    let add_b = commit(&local, "b.txt", "b\n", "Add b");
+    let change_a = commit(&local, "a.txt", "side\n", "Change a on side");
+    local.set_head(&main).unwrap();
+    local.checkout_head(Some(git2::build::CheckoutBuilder::new().force())).unwrap();
+    commit(&local, "a.txt", "main\n", "Change a on main");
+
+    let picked = repo.cherry_pick(&[add_b, change_a], None, None).expect("Failed to cherry-pick");
+    assert_eq!(picked.commits.len(), 1);
+    assert_eq!(picked.stopped_at, Some(change_a));
+    assert!(picked.remaining.is_empty());
+    assert_eq!(read(&repo, "b.txt"), "b\n");
+    let copy = local.find_commit(picked.commits[0].as_git2()).unwrap();
+    assert_eq!(copy.summary(), Some("Add b"));
+
+    repo.pick_abort().expect("Failed to abort");
+    assert_eq!(read(&repo, "a.txt"), "main\n");
+
+    // Pick it again, resolve and commit
+    repo.cherry_pick(&[change_a], None, None).unwrap();
+    assert!(repo.pick_continue().is_err());
+    std::fs::write(repo.root_path().join("a.txt"), "resolved\n").unwrap();
+    stage(&local, "a.txt");
+    let resolved = repo.pick_continue().expect("Failed to continue");
+    let resolved = local.find_commit(resolved.as_git2()).unwrap();
+    assert_eq!(resolved.summary(), Some("Change a on side"));
+    assert_eq!(local.state(), git2::RepositoryState::Clean);
+
+    let reverted = repo.revert(&picked.commits, None, None).expect("Failed to revert");
+    assert_eq!(reverted.commits.len(), 1);
+    assert!(!repo.root_path().join("b.txt").exists());
+    let revert = local.find_commit(reverted.commits[0].as_git2()).unwrap();
+    assert_eq!(revert.summary(), Some("Revert \"Add b\""));
+}
@@ -0,0 +1,145 @@
+//! Tests for tag and stash management.
+
+use std::path::Path;
+use tachikoma_git::{GitError, GitOid, GitRepository, StashOptions, TagOptions};
+use tempfile::TempDir;
+
+fn setup() -> (TempDir, git2::Repository, GitRepository) {
+    let temp_dir = TempDir::new().expect("Failed to create temp dir");
+    let raw = git2::Repository::init(temp_dir.path()).expect("Failed to initialize repository");
+    let mut config = raw.config().unwrap();
+    config.set_str("user.name", "Test User").unwrap();
+    config.set_str("user.email", "test@example.com").unwrap();
+    commit(&raw, "a.txt", "one\n", "Initial commit");
+
+    let repo = GitRepository::open(temp_dir.path()).expect("Failed to open repository");
+    (temp_dir, raw, repo)
+}
+
+fn commit(repo: &git2::Repository, file: &str, content: &str, message: &str) -> GitOid {
+    std::fs::write(repo.workdir().unwrap().join(file), content).unwrap();
+    let mut index = repo.index().unwrap();
+    index.read(true).unwrap();
+    index.add_path(Path::new(file)).unwrap();
+    index.write().unwrap();
+    let tree = repo.find_tree(index.write_tree().unwrap()).unwrap();
+    let sig = repo.signature().unwrap();
+    let parents: Vec<git2::Commit> = repo.head().ok().and_then(|h| h.peel_to_commit().ok()).into_iter().collect();
+    let parents: Vec<&git2::Commit> = parents.iter().collect();
+    let oid = repo.commit(Some("HEAD"), &sig, &sig, message, &tree, &parents).unwrap();
+    GitOid::from_git2(oid)
+}
+
+fn read(repo: &GitRepository, file: &str) -> String {
+    std::fs::read_to_string(repo.root_path().join(file)).unwrap()
+}
+
+fn write(repo: &GitRepository, file: &str, content: &str) {
+    std::fs::write(repo.root_path().join(file), content).unwrap();
+}
+
+#[test]
+fn test_create_list_and_delete_tags() {
+    let (_temp_dir, raw, repo) = setup();
+    let first = GitOid::from_git2(raw.head().unwrap().target().unwrap());
+    let second = commit(&raw, "a.txt", "two\n", "Second commit");
+
+    let light = repo.create_tag("v1.2.0", TagOptions::new().at(first.to_hex())).unwrap();
+    assert_eq!(light.oid, first);
+    assert_eq!(light.target, first);
+    assert!(light.message.is_none() && light.tagger.is_none());
+
+    let annotated = repo.create_tag("v1.10.0", TagOptions::annotated("Release 1.10")).unwrap();
+    assert_ne!(annotated.oid, second);
+    assert_eq!(annotated.target, second);
+    assert_eq!(annotated.message.as_deref(), Some("Release 1.10"));
+    assert_eq!(annotated.tagger.unwrap().name, "Test User");
+
+    repo.create_tag("v2.0.0-rc.1", TagOptions::new()).unwrap();
+    repo.create_tag("nightly", TagOptions::new()).unwrap();
+    repo.create_tag("tool-v3.0.0", TagOptions::new()).unwrap();
+
+    // Existing tags are only replaced when forced
+    assert!(repo.create_tag("v1.2.0", TagOptions::new()).is_err());
+    let moved = repo.create_tag("v1.2.0", TagOptions::new().force()).unwrap();
+    assert_eq!(moved.target, second);
+    assert!(repo.create_tag("bad..name", TagOptions::new()).is_err());
+
+    let names: Vec<String> = repo.list_tags(None).unwrap().into_iter().map(|t| t.name).collect();
+    assert_eq!(names, ["nightly", "tool-v3.0.0", "v1.10.0", "v1.2.0", "v2.0.0-rc.1"]);
+    assert_eq!(repo.list_tags(Some("v1.*")).unwrap().len(), 2);
+
+    // Highest release by version, not by name; pre-releases are skipped
+    let latest = repo.latest_release_tag(None).unwrap().unwrap();
+    assert_eq!(latest.name, "v1.10.0");
+    let tool = repo.latest_release_tag(Some("tool-")).unwrap().unwrap();
+    assert_eq!(tool.name, "tool-v3.0.0");
+    assert!(repo.latest_release_tag(Some("other-")).unwrap().is_none());
+
+    let verified = repo.verify_tag("v1.10.0").unwrap();
+    assert!(verified.annotated);
+    assert!(!verified.signed && !verified.verified);
+
+    repo.delete_tag("v1.10.0").unwrap();
+    assert!(matches!(repo.get_tag("v1.10.0"), Err(GitError::RefNotFound { .. })));
+    assert!(matches!(repo.delete_tag("v1.10.0"), Err(GitError::RefNotFound { .. })));
+    assert_eq!(repo.latest_release_tag(None).unwrap().unwrap().name, "v1.2.0");
+}
+
+#[test]
+fn test_stash_push_list_and_pop() {
+    let (_temp_dir, _raw, repo) = setup();
+    assert!(repo.stash_push(StashOptions::new()).unwrap().is_none());
+
+    write(&repo, "a.txt", "dirty\n");
+    write(&repo, "new.txt", "untracked\n");
+    let entry = repo
+        .stash_push(StashOptions::new().with_message("loop run").include_untracked())
+        .unwrap()
+        .expect("Nothing stashed");
+    assert_eq!(entry.index, 0);
+    assert!(entry.message.contains("loop run"));
+    assert_eq!(read(&repo, "a.txt"), "one\n");
+    assert!(!repo.root_path().join("new.txt").exists());
+
+    // A second stash, limited to one path
+    write(&repo, "a.txt", "two\n");
+    write(&repo, "b.txt", "ignored by the stash\n");
+    let named = StashOptions::new().with_message("partial").path("a.txt");
+    assert!(matches!(repo.stash_push(named), Err(GitError::InvalidOperation { .. })));
+    repo.stash_push(StashOptions::new().path("a.txt")).unwrap().unwrap();
+    assert_eq!(read(&repo, "a.txt"), "one\n");
+    assert!(repo.root_path().join("b.txt").exists());
+    std::fs::remove_file(repo.root_path().join("b.txt")).unwrap();
+
+    let stashes = repo.stash_list().unwrap();
+    assert_eq!(stashes.len(), 2);
+    assert_eq!(stashes[1].oid, entry.oid);
+
+    let applied = repo.stash_apply(0, false).unwrap();
+    assert!(applied.conflicts.is_empty() && !applied.dropped);
+    assert_eq!(read(&repo, "a.txt"), "two\n");
+    repo.with_repo(|r| r.reset(&r.head().unwrap().peel(git2::ObjectType::Commit).unwrap(), git2::ResetType::Hard, None))
+        .unwrap();
+
+    repo.stash_drop(0).unwrap();
+    let popped = repo.stash_pop(0, false).unwrap();
+    assert!(popped.dropped);
+    assert_eq!(read(&repo, "a.txt"), "dirty\n");
+    assert_eq!(read(&repo, "new.txt"), "untracked\n");
+    assert!(repo.stash_list().unwrap().is_empty());
+    assert!(matches!(repo.stash_drop(0), Err(GitError::RefNotFound { .. })));
+}
+
+#[test]
+fn test_stash_pop_conflict_keeps_stash() {
+    let (_temp_dir, raw, repo) = setup();
+    write(&repo, "a.txt", "stashed\n");
+    repo.stash_push(StashOptions::new()).unwrap().unwrap();
+    commit(&raw, "a.txt", "committed\n", "Change a");
+
+    let popped = repo.stash_pop(0, false).unwrap();
+    assert!(!popped.dropped);
+    assert_eq!(popped.conflicts[0].path, Path::new("a.txt"));
+    assert_eq!(repo.stash_list().unwrap().len(), 1);
+}
@@ -2,7 +2,8 @@
 //!
 //! This crate provides a plugin architecture for:
 //! - **Agents**: LLM backends that execute tool calls (Claude, OpenCode, Ollama)
-//! - **Trackers**: Task/spec state managers (Specs, Beads, JSON)
+//! - **Trackers**: Task/spec state managers (Specs, Beads, JSON); built-in
+//!   implementations live in [`trackers`]
 //! - **Templates**: Handlebars-based prompt customization
 //!
 //! ## Plugin Discovery
@@ -23,12 +24,14 @@
 
 pub mod agent;
 pub mod tracker;
+pub mod trackers;
 pub mod template;
 pub mod manifest;
 pub mod loader;
 
 pub use agent::AgentPlugin;
-pub use tracker::TrackerPlugin;
+pub use tracker::{Task, TaskStatus, TrackerPlugin};
+pub use trackers::{BeadsTracker, IssueExportTracker, SpecsTracker};
 pub use template::TemplateEngine;
 pub use manifest::{PluginManifest, PluginType, PluginRequirement};
 pub use loader::PluginLoader;
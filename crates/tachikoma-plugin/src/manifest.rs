@@ -65,7 +65,7 @@ impl PluginManifest {
         
         for req in &self.requires {
             if !req.check() {
-                errors.push(format!("{}: {}", req.requirement_type(), req.name));
+                errors.push(format!("{}: {}", req.requirement_type(), req.name()));
             }
         }
         
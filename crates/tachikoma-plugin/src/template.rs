@@ -2,7 +2,6 @@
 //!
 //! Uses Handlebars templates for system and task prompts.
 
-use std::collections::HashMap;
 use std::path::{Path, PathBuf};
 use handlebars::Handlebars;
 use serde::Serialize;
@@ -184,6 +183,7 @@ pub struct PatternInfo {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use std::collections::HashMap;
     
     #[test]
     fn test_template_engine_basic() {
@@ -5,7 +5,7 @@
 use async_trait::async_trait;
 use serde::{Deserialize, Serialize};
 
-use crate::{PluginManifest, Result};
+use crate::{PluginError, PluginManifest, Result};
 
 /// A task to be executed
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -31,11 +31,45 @@ pub struct Task {
     /// Acceptance criteria (if any)
     pub criteria: Vec<TaskCriterion>,
     
+    /// Where the task stands
+    #[serde(default)]
+    pub status: TaskStatus,
+    
     /// Additional metadata
     #[serde(default)]
     pub metadata: serde_json::Value,
 }
 
+/// Task state, normalized across trackers
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TaskStatus {
+    /// Not started
+    #[default]
+    Open,
+    
+    /// Being worked on
+    InProgress,
+    
+    /// Set aside for a human; not handed out until reopened
+    Blocked,
+    
+    /// Done
+    Closed,
+}
+
+impl TaskStatus {
+    /// `open`, `in_progress`, `blocked` or `closed`
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            TaskStatus::Open => "open",
+            TaskStatus::InProgress => "in_progress",
+            TaskStatus::Blocked => "blocked",
+            TaskStatus::Closed => "closed",
+        }
+    }
+}
+
 /// A single acceptance criterion
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct TaskCriterion {
@@ -101,6 +135,11 @@ pub trait TrackerPlugin: Send + Sync {
     /// Returns None if all tasks are complete or blocked.
     async fn next_task(&self) -> Result<Option<Task>>;
     
+    /// Every task `next_task` could return, in the order it would return them
+    async fn ready_tasks(&self) -> Result<Vec<Task>> {
+        Ok(self.next_task().await?.into_iter().collect())
+    }
+    
     /// Get a specific task by ID
     async fn get_task(&self, id: &str) -> Result<Option<Task>>;
     
@@ -113,6 +152,31 @@ pub trait TrackerPlugin: Send + Sync {
     /// Mark a task as complete
     async fn complete_task(&self, id: &str) -> Result<()>;
     
+    /// Mark a task as complete, keeping `reason` where the tracker has room for it
+    async fn complete_task_with_reason(&self, id: &str, reason: Option<&str>) -> Result<()> {
+        let _ = reason;
+        self.complete_task(id).await
+    }
+    
+    /// Set a task aside for a human
+    async fn block_task(&self, id: &str) -> Result<()> {
+        Err(PluginError::ExecutionFailed(format!(
+            "{} can't block {}",
+            self.manifest().name,
+            id
+        )))
+    }
+    
+    /// Add a line to a task's notes; trackers without notes drop it
+    async fn note_task(&self, _id: &str, _note: &str) -> Result<()> {
+        Ok(())
+    }
+    
+    /// Open tasks that `id` is waiting on, deepest first
+    async fn blocked_by(&self, _id: &str) -> Result<Vec<String>> {
+        Ok(Vec::new())
+    }
+    
     /// Mark a specific criterion as complete
     async fn complete_criterion(&self, task_id: &str, criterion_idx: usize) -> Result<()>;
     
@@ -0,0 +1,880 @@
+//! Beads tracker
+//!
+//! Beads keeps its issues in a SQLite database that is a local cache of the
+//! git-tracked `.beads/issues.jsonl` export; `bd` re-imports the JSONL
+//! whenever it changes. [`BeadsStore`] reads and writes that JSONL file
+//! directly, so the tracker works (and can be tested) without `bd`
+//! installed, and computes the ready set from the dependency graph itself:
+//! an issue is ready while it is open or in progress, nothing it `blocks` on
+//! is still open, and its parent epic isn't blocked.
+//!
+//! Writes take a lock file, re-read the store, apply the change and replace
+//! the file with a rename, so a status transition is all-or-nothing and
+//! concurrent writers can't lose each other's updates. Issues that aren't
+//! touched are written back byte-for-byte.
+//!
+//! [`BeadsTracker`] is the [`TrackerPlugin`] over the store; `sync` runs
+//! `bd sync` when `bd` is installed.
+
+use std::collections::{HashMap, HashSet};
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant, SystemTime};
+
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::tracker::{Progress, Task, TaskStatus, TrackerPlugin};
+use crate::{PluginError, PluginManifest, Result};
+
+use super::{builtin_manifest, check_criterion, not_initialized, parse_criteria, summarize};
+
+const ISSUES_FILE: &str = ".beads/issues.jsonl";
+const LOCK_FILE: &str = ".beads/issues.jsonl.lock";
+
+/// Give up waiting for the lock after this long
+const LOCK_TIMEOUT: Duration = Duration::from_secs(10);
+/// A lock with no PID in it that is older than this was left behind by a
+/// writer that crashed before writing one
+const STALE_LOCK: Duration = Duration::from_secs(60);
+
+/// Statuses that no longer block anything
+const DONE_STATUSES: &[&str] = &["closed", "tombstone"];
+/// Statuses `ready` returns (in_progress so an interrupted task is picked up again)
+const READY_STATUSES: &[&str] = &["open", "in_progress"];
+
+/// One issue as stored in the JSONL export (only the fields the store uses)
+#[derive(Debug, Clone, Deserialize)]
+struct Issue {
+    id: String,
+    title: String,
+    #[serde(default)]
+    description: String,
+    #[serde(default)]
+    notes: String,
+    #[serde(default)]
+    status: String,
+    #[serde(default)]
+    priority: u8,
+    #[serde(default)]
+    issue_type: String,
+    #[serde(default)]
+    assignee: Option<String>,
+    #[serde(default)]
+    labels: Vec<String>,
+    #[serde(default)]
+    dependencies: Vec<Dependency>,
+    #[serde(default)]
+    created_at: String,
+}
+
+/// `issue_id` depends on `depends_on_id`
+#[derive(Debug, Clone, Deserialize)]
+struct Dependency {
+    issue_id: String,
+    depends_on_id: String,
+    #[serde(rename = "type", default)]
+    dep_type: String,
+}
+
+/// A beads issue as the store hands it out, with its `blocks` dependencies
+/// resolved in both directions
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Bead {
+    pub id: String,
+    pub title: String,
+    #[serde(default)]
+    pub description: String,
+    #[serde(default)]
+    pub notes: String,
+    pub status: String,
+    #[serde(default)]
+    pub priority: u8,
+    #[serde(default)]
+    pub issue_type: String,
+    #[serde(default)]
+    pub owner: Option<String>,
+    #[serde(default)]
+    pub labels: Vec<String>,
+    #[serde(default)]
+    pub depends_on: Vec<String>,
+    #[serde(default)]
+    pub blocks: Vec<String>,
+}
+
+/// An issue to add with `BeadsStore::create`
+#[derive(Debug, Clone, Default)]
+pub struct NewIssue {
+    pub title: String,
+    pub description: String,
+    pub priority: u8,
+    pub issue_type: String,
+    pub labels: Vec<String>,
+}
+
+/// A line of the store: the issue plus its original text
+#[derive(Debug, Clone)]
+struct Entry {
+    line: String,
+    issue: Issue,
+}
+
+/// The native beads store for a project
+#[derive(Debug, Clone)]
+pub struct BeadsStore {
+    path: PathBuf,
+    lock_path: PathBuf,
+}
+
+impl BeadsStore {
+    pub fn new(project_root: &Path) -> Self {
+        Self {
+            path: project_root.join(ISSUES_FILE),
+            lock_path: project_root.join(LOCK_FILE),
+        }
+    }
+
+    /// Whether the project has a JSONL export to work from
+    pub fn exists(&self) -> bool {
+        self.path.exists()
+    }
+
+    /// Every issue that hasn't been deleted, closed ones included
+    pub fn all(&self) -> Result<Vec<Bead>> {
+        let entries = self.load()?;
+        Ok(entries
+            .iter()
+            .filter(|e| e.issue.status != "tombstone")
+            .map(|e| to_bead(&e.issue, &entries))
+            .collect())
+    }
+
+    /// Issues that aren't closed
+    pub fn open(&self) -> Result<Vec<Bead>> {
+        let entries = self.load()?;
+        Ok(entries
+            .iter()
+            .filter(|e| !DONE_STATUSES.contains(&e.issue.status.as_str()))
+            .map(|e| to_bead(&e.issue, &entries))
+            .collect())
+    }
+
+    pub fn get(&self, task_id: &str) -> Result<Bead> {
+        let entries = self.load()?;
+        entries
+            .iter()
+            .find(|e| e.issue.id == task_id)
+            .map(|e| to_bead(&e.issue, &entries))
+            .ok_or_else(|| self.missing(task_id))
+    }
+
+    /// Unblocked open/in-progress issues, highest priority (then oldest) first
+    ///
+    /// An issue is blocked while anything it depends on (`blocks`) is still
+    /// open, or while its parent epic is blocked.
+    pub fn ready(&self) -> Result<Vec<Bead>> {
+        let entries = self.load()?;
+        let graph = Graph::new(&entries);
+
+        let mut ready: Vec<&Issue> = entries
+            .iter()
+            .map(|e| &e.issue)
+            .filter(|i| READY_STATUSES.contains(&i.status.as_str()))
+            .filter(|i| !graph.is_blocked(&i.id, &mut HashSet::new()))
+            .collect();
+        ready.sort_by(|a, b| a.priority.cmp(&b.priority).then_with(|| a.created_at.cmp(&b.created_at)));
+
+        Ok(ready.into_iter().map(|i| to_bead(i, &entries)).collect())
+    }
+
+    /// Open issues that must be closed before `task_id` is ready, in the
+    /// order they can be worked on (deepest dependency first)
+    pub fn blocking_chain(&self, task_id: &str) -> Result<Vec<String>> {
+        let entries = self.load()?;
+        let graph = Graph::new(&entries);
+        if !graph.issues.contains_key(task_id) {
+            return Err(self.missing(task_id));
+        }
+
+        let mut chain = Vec::new();
+        graph.collect_blockers(task_id, &mut HashSet::new(), &mut chain);
+        chain.retain(|id| id != task_id);
+        Ok(chain)
+    }
+
+    /// Set an issue's status unconditionally
+    pub fn set_status(&self, task_id: &str, status: &str) -> Result<()> {
+        self.transition(task_id, &[], status, None)?;
+        Ok(())
+    }
+
+    /// Close an issue
+    pub fn close(&self, task_id: &str, reason: Option<&str>) -> Result<()> {
+        self.transition(task_id, &[], "closed", reason)?;
+        Ok(())
+    }
+
+    /// Atomically move an issue to `to` if its status is one of `from`
+    ///
+    /// An empty `from` accepts any current status. Returns false (and
+    /// changes nothing) if the issue was in some other state, e.g. another
+    /// runner already claimed it.
+    pub fn transition(&self, task_id: &str, from: &[&str], to: &str, reason: Option<&str>) -> Result<bool> {
+        let _lock = self.lock()?;
+        let mut entries = self.load()?;
+
+        let missing = self.missing(task_id);
+        let entry = entries.iter_mut().find(|e| e.issue.id == task_id).ok_or(missing)?;
+        if !from.is_empty() && !from.contains(&entry.issue.status.as_str()) {
+            return Ok(false);
+        }
+
+        let mut value: Value = serde_json::from_str(&entry.line)?;
+        let now = chrono::Local::now().to_rfc3339();
+        value["status"] = to.into();
+        value["updated_at"] = now.clone().into();
+        if to == "closed" {
+            value["closed_at"] = now.into();
+            if let Some(reason) = reason {
+                value["close_reason"] = reason.into();
+            }
+        } else if let Some(map) = value.as_object_mut() {
+            map.remove("closed_at");
+            map.remove("close_reason");
+        }
+
+        entry.line = serde_json::to_string(&value)?;
+        entry.issue.status = to.to_string();
+        self.save(&entries)?;
+        Ok(true)
+    }
+
+    /// Add an open issue and return its id (`<prefix>-<hash>`, the prefix
+    /// taken from the existing issues)
+    pub fn create(&self, new: &NewIssue) -> Result<String> {
+        let _lock = self.lock()?;
+        let mut entries = self.load()?;
+
+        let prefix = entries
+            .first()
+            .and_then(|e| e.issue.id.rsplit_once('-'))
+            .map_or("bd", |(prefix, _)| prefix)
+            .to_string();
+        let now = chrono::Local::now();
+        let mut seed = now.timestamp_nanos_opt().unwrap_or_default() as u64;
+        let id = loop {
+            let mut hasher = std::collections::hash_map::DefaultHasher::new();
+            std::hash::Hash::hash(&(&new.title, seed), &mut hasher);
+            let id = format!("{}-{:04x}", prefix, std::hash::Hasher::finish(&hasher) & 0xffff);
+            if !entries.iter().any(|e| e.issue.id == id) {
+                break id;
+            }
+            seed += 1;
+        };
+
+        let issue_type = if new.issue_type.is_empty() { "task" } else { new.issue_type.as_str() };
+        let value = serde_json::json!({
+            "id": id,
+            "title": new.title,
+            "description": new.description,
+            "status": "open",
+            "priority": new.priority,
+            "issue_type": issue_type,
+            "labels": new.labels,
+            "created_at": now.to_rfc3339(),
+            "updated_at": now.to_rfc3339(),
+        });
+        let line = serde_json::to_string(&value)?;
+        entries.push(Entry {
+            issue: serde_json::from_str(&line)?,
+            line,
+        });
+        self.save(&entries)?;
+        Ok(id)
+    }
+
+    /// Make `task_id` wait for `depends_on` (a `blocks` dependency)
+    pub fn add_dependency(&self, task_id: &str, depends_on: &str) -> Result<()> {
+        let _lock = self.lock()?;
+        let mut entries = self.load()?;
+        if !entries.iter().any(|e| e.issue.id == depends_on) {
+            return Err(self.missing(depends_on));
+        }
+        let missing = self.missing(task_id);
+        let entry = entries.iter_mut().find(|e| e.issue.id == task_id).ok_or(missing)?;
+
+        let mut value: Value = serde_json::from_str(&entry.line)?;
+        let dependency = serde_json::json!({
+            "issue_id": task_id,
+            "depends_on_id": depends_on,
+            "type": "blocks",
+            "created_at": chrono::Local::now().to_rfc3339(),
+        });
+        match value["dependencies"].as_array_mut() {
+            Some(dependencies) => dependencies.push(dependency),
+            None => value["dependencies"] = serde_json::json!([dependency]),
+        }
+
+        entry.line = serde_json::to_string(&value)?;
+        entry.issue = serde_json::from_str(&entry.line)?;
+        self.save(&entries)?;
+        Ok(())
+    }
+
+    /// Add a line to an issue's notes
+    pub fn append_note(&self, task_id: &str, note: &str) -> Result<()> {
+        self.update(task_id, |value| {
+            value["notes"] = append_line(value["notes"].as_str().unwrap_or_default(), note).into();
+            Ok(())
+        })
+    }
+
+    /// Apply `change` to one issue's JSON and write the store back
+    fn update(&self, task_id: &str, change: impl FnOnce(&mut Value) -> Result<()>) -> Result<()> {
+        let _lock = self.lock()?;
+        let mut entries = self.load()?;
+        let missing = self.missing(task_id);
+        let entry = entries.iter_mut().find(|e| e.issue.id == task_id).ok_or(missing)?;
+
+        let mut value: Value = serde_json::from_str(&entry.line)?;
+        change(&mut value)?;
+        value["updated_at"] = chrono::Local::now().to_rfc3339().into();
+
+        entry.line = serde_json::to_string(&value)?;
+        entry.issue = serde_json::from_str(&entry.line)?;
+        self.save(&entries)
+    }
+
+    fn missing(&self, task_id: &str) -> PluginError {
+        PluginError::NotFound(format!("task {} in {}", task_id, self.path.display()))
+    }
+
+    fn load(&self) -> Result<Vec<Entry>> {
+        let content = std::fs::read_to_string(&self.path)?;
+
+        let mut entries = Vec::new();
+        for (line_no, line) in content.lines().enumerate() {
+            if line.trim().is_empty() {
+                continue;
+            }
+            let issue: Issue = serde_json::from_str(line).map_err(|e| {
+                PluginError::ExecutionFailed(format!("Invalid issue at {}:{}: {}", self.path.display(), line_no + 1, e))
+            })?;
+            entries.push(Entry {
+                line: line.to_string(),
+                issue,
+            });
+        }
+
+        Ok(entries)
+    }
+
+    /// Write through a temp file and rename, so readers never see a partial store
+    fn save(&self, entries: &[Entry]) -> Result<()> {
+        let tmp = self.path.with_extension(format!("jsonl.tmp-{}", std::process::id()));
+        {
+            let mut file = std::fs::File::create(&tmp)?;
+            for entry in entries {
+                writeln!(file, "{}", entry.line)?;
+            }
+            file.sync_all()?;
+        }
+        std::fs::rename(&tmp, &self.path)?;
+        Ok(())
+    }
+
+    fn lock(&self) -> Result<StoreLock> {
+        let started = Instant::now();
+        loop {
+            match OpenOptions::new().write(true).create_new(true).open(&self.lock_path) {
+                Ok(mut file) => {
+                    let _ = write!(file, "{}", std::process::id());
+                    return Ok(StoreLock(self.lock_path.clone()));
+                }
+                Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => {
+                    // Only break the lock once its writer is gone, however old it is
+                    let holder = lock_holder(&self.lock_path);
+                    let stale = match holder {
+                        Some(pid) => !process_alive(pid),
+                        None => std::fs::metadata(&self.lock_path)
+                            .and_then(|m| m.modified())
+                            .ok()
+                            .and_then(|t| SystemTime::now().duration_since(t).ok())
+                            .is_some_and(|age| age > STALE_LOCK),
+                    };
+                    // Re-check the holder so a lock just taken by someone else survives
+                    if stale && lock_holder(&self.lock_path) == holder {
+                        tracing::warn!("Removing stale beads lock {}", self.lock_path.display());
+                        let _ = std::fs::remove_file(&self.lock_path);
+                        continue;
+                    }
+                    if started.elapsed() > LOCK_TIMEOUT {
+                        return Err(PluginError::ExecutionFailed(format!(
+                            "Timed out waiting for {}",
+                            self.lock_path.display()
+                        )));
+                    }
+                    std::thread::sleep(Duration::from_millis(50));
+                }
+                Err(e) => return Err(e.into()),
+            }
+        }
+    }
+}
+
+/// PID written into a lock file by the writer holding it
+fn lock_holder(lock_path: &Path) -> Option<u32> {
+    std::fs::read_to_string(lock_path).ok()?.trim().parse().ok()
+}
+
+/// Whether a process with this PID still exists
+fn process_alive(pid: u32) -> bool {
+    let proc = Path::new("/proc");
+    if proc.join("self").exists() {
+        return proc.join(pid.to_string()).exists();
+    }
+    // No procfs (macOS): `kill -0` fails for a missing process
+    std::process::Command::new("kill")
+        .args(["-0", &pid.to_string()])
+        .stderr(std::process::Stdio::null())
+        .status()
+        .map_or(true, |s| s.success())
+}
+
+/// Held while writing; removes the lock file on drop
+struct StoreLock(PathBuf);
+
+impl Drop for StoreLock {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.0);
+    }
+}
+
+/// Dependency edges between issues
+struct Graph<'a> {
+    issues: HashMap<&'a str, &'a Issue>,
+}
+
+impl<'a> Graph<'a> {
+    fn new(entries: &'a [Entry]) -> Self {
+        Self {
+            issues: entries.iter().map(|e| (e.issue.id.as_str(), &e.issue)).collect(),
+        }
+    }
+
+    fn is_open(&self, id: &str) -> bool {
+        // A dependency on an issue we don't have can't be satisfied
+        self.issues
+            .get(id)
+            .map_or(true, |i| !DONE_STATUSES.contains(&i.status.as_str()))
+    }
+
+    /// Blocked by an open `blocks` dependency, or by a blocked parent
+    fn is_blocked(&self, id: &str, seen: &mut HashSet<String>) -> bool {
+        if !seen.insert(id.to_string()) {
+            return false;
+        }
+        let Some(issue) = self.issues.get(id) else {
+            return false;
+        };
+
+        issue.dependencies.iter().filter(|d| d.issue_id == id).any(|d| match d.dep_type.as_str() {
+            "blocks" | "" => self.is_open(&d.depends_on_id),
+            "parent-child" => self.is_blocked(&d.depends_on_id, seen),
+            _ => false,
+        })
+    }
+
+    /// Open blockers of `id` in post-order, so each comes after its own blockers
+    fn collect_blockers(&self, id: &str, seen: &mut HashSet<String>, chain: &mut Vec<String>) {
+        if !seen.insert(id.to_string()) {
+            return;
+        }
+        let Some(issue) = self.issues.get(id) else {
+            return;
+        };
+
+        for dep in issue.dependencies.iter().filter(|d| d.issue_id == id) {
+            match dep.dep_type.as_str() {
+                "blocks" | "" if self.is_open(&dep.depends_on_id) => {
+                    self.collect_blockers(&dep.depends_on_id, seen, chain);
+                    if !chain.contains(&dep.depends_on_id) {
+                        chain.push(dep.depends_on_id.clone());
+                    }
+                }
+                // A parent's blockers hold up its children too
+                "parent-child" => {
+                    let mut parent_chain = Vec::new();
+                    self.collect_blockers(&dep.depends_on_id, seen, &mut parent_chain);
+                    chain.extend(parent_chain.into_iter().filter(|p| p != &dep.depends_on_id));
+                }
+                _ => {}
+            }
+        }
+    }
+}
+
+fn to_bead(issue: &Issue, entries: &[Entry]) -> Bead {
+    let depends_on = issue
+        .dependencies
+        .iter()
+        .filter(|d| d.issue_id == issue.id && matches!(d.dep_type.as_str(), "blocks" | ""))
+        .map(|d| d.depends_on_id.clone())
+        .collect();
+    let blocks = entries
+        .iter()
+        .flat_map(|e| &e.issue.dependencies)
+        .filter(|d| d.depends_on_id == issue.id && matches!(d.dep_type.as_str(), "blocks" | ""))
+        .map(|d| d.issue_id.clone())
+        .collect();
+
+    Bead {
+        id: issue.id.clone(),
+        title: issue.title.clone(),
+        description: issue.description.clone(),
+        notes: issue.notes.clone(),
+        status: issue.status.clone(),
+        priority: issue.priority,
+        issue_type: issue.issue_type.clone(),
+        owner: issue.assignee.clone(),
+        labels: issue.labels.clone(),
+        depends_on,
+        blocks,
+    }
+}
+
+/// `text` with `line` added on a line of its own
+pub fn append_line(text: &str, line: &str) -> String {
+    let text = text.trim_end();
+    if text.is_empty() {
+        line.to_string()
+    } else {
+        format!("{}\n{}", text, line)
+    }
+}
+
+/// Tracker backed by a beads issue store
+pub struct BeadsTracker {
+    manifest: PluginManifest,
+    root: Option<PathBuf>,
+    store: Option<BeadsStore>,
+}
+
+impl BeadsTracker {
+    pub fn new() -> Self {
+        Self {
+            manifest: builtin_manifest("beads", "Beads issue tracker (.beads/issues.jsonl)"),
+            root: None,
+            store: None,
+        }
+    }
+
+    fn store(&self) -> Result<&BeadsStore> {
+        self.store.as_ref().ok_or_else(not_initialized)
+    }
+}
+
+impl Default for BeadsTracker {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[async_trait]
+impl TrackerPlugin for BeadsTracker {
+    fn manifest(&self) -> &PluginManifest {
+        &self.manifest
+    }
+
+    async fn init(&mut self, root: &Path) -> Result<()> {
+        let store = BeadsStore::new(root);
+        if !store.exists() {
+            return Err(PluginError::InitializationFailed(format!(
+                "{} not found (run `bd init`, or `bd export` to create it)",
+                root.join(ISSUES_FILE).display()
+            )));
+        }
+        self.root = Some(root.to_path_buf());
+        self.store = Some(store);
+        Ok(())
+    }
+
+    async fn next_task(&self) -> Result<Option<Task>> {
+        Ok(self.ready_tasks().await?.into_iter().next())
+    }
+
+    async fn ready_tasks(&self) -> Result<Vec<Task>> {
+        Ok(self.store()?.ready()?.iter().map(to_task).collect())
+    }
+
+    async fn get_task(&self, id: &str) -> Result<Option<Task>> {
+        match self.store()?.get(id) {
+            Ok(bead) => Ok(Some(to_task(&bead))),
+            Err(PluginError::NotFound(_)) => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+
+    async fn list_tasks(&self) -> Result<Vec<Task>> {
+        Ok(self.store()?.all()?.iter().map(to_task).collect())
+    }
+
+    async fn start_task(&self, id: &str) -> Result<()> {
+        self.store()?.set_status(id, "in_progress")
+    }
+
+    async fn complete_task(&self, id: &str) -> Result<()> {
+        self.store()?.close(id, None)
+    }
+
+    async fn complete_task_with_reason(&self, id: &str, reason: Option<&str>) -> Result<()> {
+        self.store()?.close(id, reason)
+    }
+
+    async fn block_task(&self, id: &str) -> Result<()> {
+        self.store()?.set_status(id, "blocked")
+    }
+
+    async fn note_task(&self, id: &str, note: &str) -> Result<()> {
+        self.store()?.append_note(id, note)
+    }
+
+    async fn blocked_by(&self, id: &str) -> Result<Vec<String>> {
+        self.store()?.blocking_chain(id)
+    }
+
+    async fn complete_criterion(&self, task_id: &str, criterion_idx: usize) -> Result<()> {
+        self.store()?.update(task_id, |issue| {
+            // Criteria can live in either field; indices run across them in order
+            let mut offset = 0;
+            for field in ["description", "notes"] {
+                let text = issue[field].as_str().unwrap_or_default().to_string();
+                let count = parse_criteria(&text).len();
+                if criterion_idx < offset + count {
+                    let updated = check_criterion(&text, criterion_idx - offset).unwrap_or(text);
+                    issue[field] = updated.into();
+                    return Ok(());
+                }
+                offset += count;
+            }
+            Err(PluginError::NotFound(format!("criterion {} of {}", criterion_idx, task_id)))
+        })
+    }
+
+    async fn progress(&self) -> Result<Progress> {
+        Ok(summarize(&self.list_tasks().await?))
+    }
+
+    async fn sync(&self) -> Result<()> {
+        let root = self.root.as_ref().ok_or_else(not_initialized)?;
+        if which::which("bd").is_err() {
+            // Nothing to do: the JSONL is the store, and it is committed with the code
+            return Ok(());
+        }
+
+        let output = std::process::Command::new("bd").arg("sync").current_dir(root).output()?;
+        if !output.status.success() {
+            return Err(PluginError::ExecutionFailed(format!(
+                "bd sync failed: {}",
+                String::from_utf8_lossy(&output.stderr).trim()
+            )));
+        }
+        Ok(())
+    }
+
+    async fn reload(&mut self) -> Result<()> {
+        self.store()?.load().map(|_| ())
+    }
+}
+
+fn to_task(bead: &Bead) -> Task {
+    let status = match bead.status.as_str() {
+        "closed" | "tombstone" => TaskStatus::Closed,
+        "in_progress" => TaskStatus::InProgress,
+        "blocked" => TaskStatus::Blocked,
+        _ => TaskStatus::Open,
+    };
+
+    Task {
+        id: bead.id.clone(),
+        name: bead.title.clone(),
+        description: bead.description.clone(),
+        path: None,
+        priority: bead.priority,
+        dependencies: bead.depends_on.clone(),
+        criteria: parse_criteria(&format!("{}\n{}", bead.description, bead.notes)),
+        status,
+        metadata: serde_json::json!({
+            "issue_type": bead.issue_type,
+            "owner": bead.owner,
+            "labels": bead.labels,
+            "notes": bead.notes,
+            "blocks": bead.blocks,
+        }),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn fixture() -> TempDir {
+        let temp = TempDir::new().unwrap();
+        std::fs::create_dir_all(temp.path().join(".beads")).unwrap();
+        let lines = [
+            r#"{"id":"p-1","title":"Schema","status":"closed","priority":1,"created_at":"2025-01-01T00:00:00Z"}"#,
+            r#"{"id":"p-2","title":"API","status":"open","priority":2,"created_at":"2025-01-02T00:00:00Z","dependencies":[{"issue_id":"p-2","depends_on_id":"p-1","type":"blocks"}],"design":"keep me"}"#,
+            r#"{"id":"p-3","title":"UI","status":"open","priority":1,"created_at":"2025-01-03T00:00:00Z","dependencies":[{"issue_id":"p-3","depends_on_id":"p-2","type":"blocks"}]}"#,
+            r#"{"id":"p-4","title":"Epic","status":"open","priority":0,"created_at":"2025-01-04T00:00:00Z","dependencies":[{"issue_id":"p-4","depends_on_id":"p-3","type":"blocks"}]}"#,
+            r#"{"id":"p-4.1","title":"Epic child","status":"open","priority":0,"created_at":"2025-01-05T00:00:00Z","dependencies":[{"issue_id":"p-4.1","depends_on_id":"p-4","type":"parent-child"}]}"#,
+            r#"{"id":"p-5","title":"Docs","status":"in_progress","priority":3,"created_at":"2025-01-06T00:00:00Z","dependencies":[{"issue_id":"p-5","depends_on_id":"p-1","type":"related"}]}"#,
+        ];
+        std::fs::write(temp.path().join(ISSUES_FILE), lines.join("\n") + "\n").unwrap();
+        temp
+    }
+
+    fn ids(tasks: &[Bead]) -> Vec<&str> {
+        tasks.iter().map(|t| t.id.as_str()).collect()
+    }
+
+    #[test]
+    fn test_ready_set_and_blocking_chain() {
+        let temp = fixture();
+        let store = BeadsStore::new(temp.path());
+
+        // p-3 waits on p-2, p-4 on p-3, and the child inherits its epic's blockers
+        assert_eq!(ids(&store.ready().unwrap()), ["p-2", "p-5"]);
+        assert_eq!(store.blocking_chain("p-4.1").unwrap(), ["p-2", "p-3"]);
+        assert!(store.blocking_chain("p-2").unwrap().is_empty());
+
+        let api = store.get("p-2").unwrap();
+        assert_eq!(api.depends_on, ["p-1"]);
+        assert_eq!(api.blocks, ["p-3"]);
+        assert_eq!(ids(&store.open().unwrap()).len(), 5);
+        assert_eq!(ids(&store.all().unwrap()).len(), 6);
+    }
+
+    #[test]
+    fn test_transitions_are_conditional_and_preserve_other_fields() {
+        let temp = fixture();
+        let store = BeadsStore::new(temp.path());
+        let before = std::fs::read_to_string(temp.path().join(ISSUES_FILE)).unwrap();
+
+        assert!(store.transition("p-2", &["open"], "in_progress", None).unwrap());
+        // Already claimed
+        assert!(!store.transition("p-2", &["open"], "in_progress", None).unwrap());
+
+        store.close("p-2", Some("done")).unwrap();
+        assert_eq!(ids(&store.ready().unwrap()), ["p-3", "p-5"]);
+
+        let after = std::fs::read_to_string(temp.path().join(ISSUES_FILE)).unwrap();
+        let changed: Value = serde_json::from_str(after.lines().nth(1).unwrap()).unwrap();
+        assert_eq!(changed["status"], "closed");
+        assert_eq!(changed["close_reason"], "done");
+        assert_eq!(changed["design"], "keep me");
+        // Untouched issues are written back verbatim
+        assert_eq!(after.lines().nth(2), before.lines().nth(2));
+        assert!(!temp.path().join(LOCK_FILE).exists());
+        assert!(matches!(store.set_status("missing", "open"), Err(PluginError::NotFound(_))));
+    }
+
+    #[test]
+    fn test_create_issues_with_dependencies() {
+        let temp = fixture();
+        let store = BeadsStore::new(temp.path());
+        let new = |title: &str| NewIssue {
+            title: title.to_string(),
+            priority: 0,
+            ..NewIssue::default()
+        };
+
+        let model = store.create(&new("User model")).unwrap();
+        let login = store.create(&new("Login endpoint")).unwrap();
+        assert!(model.starts_with("p-") && model != login);
+        store.add_dependency(&login, &model).unwrap();
+        // The parent waits for its subtasks
+        store.add_dependency("p-2", &login).unwrap();
+
+        assert_eq!(ids(&store.ready().unwrap()), [model.as_str(), "p-5"]);
+        assert_eq!(store.get(&login).unwrap().depends_on, [model.as_str()]);
+        assert_eq!(store.get("p-2").unwrap().depends_on, ["p-1".to_string(), login.clone()]);
+        assert_eq!(store.get(&model).unwrap().issue_type, "task");
+        assert!(store.add_dependency(&login, "missing").is_err());
+    }
+
+    #[test]
+    fn test_lock_is_only_broken_when_its_writer_is_gone() {
+        let temp = fixture();
+        let store = BeadsStore::new(temp.path());
+        let lock = temp.path().join(LOCK_FILE);
+
+        let mut child = std::process::Command::new("true").spawn().unwrap();
+        let dead = child.id();
+        child.wait().unwrap();
+        assert!(!process_alive(dead));
+        assert!(process_alive(std::process::id()));
+
+        // A dead writer's lock goes straight away, whatever its age
+        std::fs::write(&lock, dead.to_string()).unwrap();
+        store.set_status("p-2", "in_progress").unwrap();
+        assert!(!lock.exists());
+
+        // An old lock whose writer is still running is waited on
+        let file = std::fs::File::create(&lock).unwrap();
+        write!(&file, "{}", std::process::id()).unwrap();
+        file.set_modified(SystemTime::now() - 2 * STALE_LOCK).unwrap();
+        assert_eq!(lock_holder(&lock), Some(std::process::id()));
+        let started = Instant::now();
+        assert!(store.set_status("p-2", "open").is_err());
+        assert!(started.elapsed() >= LOCK_TIMEOUT);
+        assert!(lock.exists());
+    }
+
+    #[tokio::test]
+    async fn test_ready_order_and_transitions() {
+        let temp = TempDir::new().unwrap();
+        std::fs::create_dir_all(temp.path().join(".beads")).unwrap();
+        std::fs::write(
+            temp.path().join(ISSUES_FILE),
+            concat!(
+                r#"{"id":"b-1","title":"Base","status":"open","priority":2,"description":"- [ ] a\n- [ ] b"}"#,
+                "\n",
+                r#"{"id":"b-2","title":"Urgent but blocked","status":"open","priority":0,"dependencies":[{"issue_id":"b-2","depends_on_id":"b-1","type":"blocks"}]}"#,
+                "\n",
+                r#"{"id":"b-3","title":"Later","status":"open","priority":3}"#,
+                "\n",
+            ),
+        )
+        .unwrap();
+
+        let mut tracker = BeadsTracker::new();
+        tracker.init(temp.path()).await.unwrap();
+
+        assert_eq!(tracker.next_task().await.unwrap().unwrap().id, "b-1");
+        assert_eq!(tracker.blocked_by("b-2").await.unwrap(), ["b-1"]);
+
+        tracker.complete_criterion("b-1", 1).await.unwrap();
+        tracker.note_task("b-1", "attempt 1: timed out").await.unwrap();
+        let task = tracker.get_task("b-1").await.unwrap().unwrap();
+        assert!(!task.criteria[0].completed);
+        assert!(task.criteria[1].completed);
+        assert_eq!(task.metadata["notes"], "attempt 1: timed out");
+        assert!(tracker.get_task("b-9").await.unwrap().is_none());
+
+        tracker.complete_task_with_reason("b-1", Some("done")).await.unwrap();
+        let next = tracker.next_task().await.unwrap().unwrap();
+        assert_eq!(next.id, "b-2");
+        assert_eq!(next.dependencies, ["b-1"]);
+
+        tracker.block_task("b-2").await.unwrap();
+        let ready: Vec<String> = tracker.ready_tasks().await.unwrap().into_iter().map(|t| t.id).collect();
+        assert_eq!(ready, ["b-3"]);
+        assert_eq!(tracker.get_task("b-2").await.unwrap().unwrap().status, TaskStatus::Blocked);
+
+        let progress = tracker.progress().await.unwrap();
+        assert_eq!((progress.completed_tasks, progress.total_tasks), (1, 3));
+    }
+}
@@ -0,0 +1,474 @@
+//! Issue export tracker
+//!
+//! Drives the loop from an offline JSON export of a hosted tracker, so a
+//! project whose backlog lives in GitHub or Jira can be worked without
+//! network access. Two shapes are recognized:
+//!
+//! - GitHub: the array printed by `gh issue list --json number,title,body,state,labels`
+//!   (or the REST API's issue list). Dependencies come from "Depends on #12" /
+//!   "Blocked by #12" lines in the body; priority from `P0`-`P4` labels.
+//! - Jira: a search export, `{"issues": [{"key", "fields": {...}}]}`.
+//!   Dependencies come from "is blocked by" issue links; priority from
+//!   `fields.priority.name`.
+//!
+//! Status changes are written back into the export in the source's own
+//! vocabulary (GitHub `state` plus `in-progress`/`blocked` labels, Jira
+//! `fields.status.name`), so the file can be diffed or re-imported.
+
+use std::path::{Path, PathBuf};
+
+use async_trait::async_trait;
+use regex::Regex;
+use serde_json::Value;
+
+use crate::tracker::{Progress, Task, TaskStatus, TrackerPlugin};
+use crate::{PluginError, PluginManifest, Result};
+
+use super::{builtin_manifest, check_criterion, not_initialized, parse_criteria, summarize, write_atomic};
+
+const IN_PROGRESS_LABEL: &str = "in-progress";
+const BLOCKED_LABEL: &str = "blocked";
+
+/// Which tracker produced the export
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Format {
+    GitHub,
+    Jira,
+}
+
+/// Tracker backed by a GitHub or Jira issue export
+pub struct IssueExportTracker {
+    manifest: PluginManifest,
+    file: PathBuf,
+    path: Option<PathBuf>,
+}
+
+impl IssueExportTracker {
+    /// `file` is resolved against the project root in `init`
+    pub fn new(file: impl Into<PathBuf>) -> Self {
+        Self {
+            manifest: builtin_manifest("issues", "Offline GitHub/Jira issue export (JSON)"),
+            file: file.into(),
+            path: None,
+        }
+    }
+
+    fn load(&self) -> Result<(Format, Value)> {
+        let path = self.path.as_ref().ok_or_else(not_initialized)?;
+        let export: Value = serde_json::from_str(&std::fs::read_to_string(path)?)?;
+        let format = match &export {
+            Value::Array(_) => Format::GitHub,
+            Value::Object(map) if map.get("issues").is_some_and(Value::is_array) => Format::Jira,
+            _ => {
+                return Err(PluginError::ExecutionFailed(format!(
+                    "{} is neither a GitHub issue list nor a Jira issue export",
+                    path.display()
+                )))
+            }
+        };
+        Ok((format, export))
+    }
+
+    fn issues(export: &Value) -> &[Value] {
+        match export {
+            Value::Array(issues) => issues,
+            _ => export["issues"].as_array().map(Vec::as_slice).unwrap_or_default(),
+        }
+    }
+
+    fn tasks(&self) -> Result<Vec<Task>> {
+        let (format, export) = self.load()?;
+        Ok(Self::issues(&export).iter().map(|issue| to_task(format, issue)).collect())
+    }
+
+    /// Apply `change` to one issue and write the export back
+    fn modify(&self, id: &str, change: impl FnOnce(Format, &mut Value) -> Result<()>) -> Result<()> {
+        let (format, mut export) = self.load()?;
+        let issues = match &mut export {
+            Value::Array(issues) => issues,
+            other => other["issues"].as_array_mut().ok_or_else(not_initialized)?,
+        };
+        let issue = issues
+            .iter_mut()
+            .find(|i| issue_id(format, i) == id)
+            .ok_or_else(|| PluginError::NotFound(format!("issue {}", id)))?;
+        change(format, issue)?;
+
+        let path = self.path.as_ref().ok_or_else(not_initialized)?;
+        write_atomic(path, &(serde_json::to_string_pretty(&export)? + "\n"))
+    }
+}
+
+#[async_trait]
+impl TrackerPlugin for IssueExportTracker {
+    fn manifest(&self) -> &PluginManifest {
+        &self.manifest
+    }
+
+    async fn init(&mut self, root: &Path) -> Result<()> {
+        let path = root.join(&self.file);
+        if !path.exists() {
+            return Err(PluginError::InitializationFailed(format!(
+                "{} not found",
+                path.display()
+            )));
+        }
+        self.path = Some(path);
+        self.load().map(|_| ())
+    }
+
+    async fn next_task(&self) -> Result<Option<Task>> {
+        Ok(self.ready_tasks().await?.into_iter().next())
+    }
+
+    async fn ready_tasks(&self) -> Result<Vec<Task>> {
+        let tasks = self.tasks()?;
+        // Issues missing from the export count as done
+        let done = |id: &str| tasks.iter().find(|t| t.id == id).map_or(true, |t| t.status == TaskStatus::Closed);
+
+        let mut ready: Vec<Task> = tasks
+            .iter()
+            .filter(|t| matches!(t.status, TaskStatus::Open | TaskStatus::InProgress))
+            .filter(|t| t.dependencies.iter().all(|d| done(d)))
+            .cloned()
+            .collect();
+        // Stable: equal priorities keep export order
+        ready.sort_by_key(|t| t.priority);
+
+        Ok(ready)
+    }
+
+    async fn get_task(&self, id: &str) -> Result<Option<Task>> {
+        Ok(self.tasks()?.into_iter().find(|t| t.id == id))
+    }
+
+    async fn list_tasks(&self) -> Result<Vec<Task>> {
+        self.tasks()
+    }
+
+    async fn start_task(&self, id: &str) -> Result<()> {
+        self.modify(id, |format, issue| {
+            match format {
+                Format::GitHub => {
+                    if let Some(labels) = issue["labels"].as_array_mut() {
+                        if !labels.iter().any(|l| label_name(l) == IN_PROGRESS_LABEL) {
+                            labels.push(serde_json::json!({ "name": IN_PROGRESS_LABEL }));
+                        }
+                    } else {
+                        issue["labels"] = serde_json::json!([{ "name": IN_PROGRESS_LABEL }]);
+                    }
+                }
+                Format::Jira => issue["fields"]["status"]["name"] = "In Progress".into(),
+            }
+            Ok(())
+        })
+    }
+
+    async fn complete_task(&self, id: &str) -> Result<()> {
+        self.complete_task_with_reason(id, None).await
+    }
+
+    async fn complete_task_with_reason(&self, id: &str, reason: Option<&str>) -> Result<()> {
+        self.modify(id, |format, issue| {
+            match format {
+                Format::GitHub => {
+                    // Match the export's casing: gh prints OPEN/CLOSED, the REST API open/closed
+                    let upper = issue["state"].as_str().is_some_and(|s| s.chars().all(|c| c.is_uppercase()));
+                    issue["state"] = if upper { "CLOSED" } else { "closed" }.into();
+                    if let Some(labels) = issue["labels"].as_array_mut() {
+                        labels.retain(|l| !matches!(label_name(l), IN_PROGRESS_LABEL | BLOCKED_LABEL));
+                    }
+                    if let Some(reason) = reason {
+                        issue["stateReason"] = reason.into();
+                    }
+                }
+                Format::Jira => {
+                    issue["fields"]["status"]["name"] = "Done".into();
+                    if let Some(reason) = reason {
+                        issue["fields"]["resolution"]["description"] = reason.into();
+                    }
+                }
+            }
+            Ok(())
+        })
+    }
+
+    async fn block_task(&self, id: &str) -> Result<()> {
+        self.modify(id, |format, issue| {
+            match format {
+                Format::GitHub => {
+                    let labels = issue["labels"].as_array().cloned().unwrap_or_default();
+                    let mut labels: Vec<Value> =
+                        labels.into_iter().filter(|l| label_name(l) != IN_PROGRESS_LABEL).collect();
+                    if !labels.iter().any(|l| label_name(l) == BLOCKED_LABEL) {
+                        labels.push(serde_json::json!({ "name": BLOCKED_LABEL }));
+                    }
+                    issue["labels"] = labels.into();
+                }
+                Format::Jira => issue["fields"]["status"]["name"] = "Blocked".into(),
+            }
+            Ok(())
+        })
+    }
+
+    async fn complete_criterion(&self, task_id: &str, criterion_idx: usize) -> Result<()> {
+        self.modify(task_id, |format, issue| {
+            let field = match format {
+                Format::GitHub => &mut issue["body"],
+                Format::Jira => &mut issue["fields"]["description"],
+            };
+            let text = field.as_str().ok_or_else(|| {
+                PluginError::ExecutionFailed(format!("{} has no plain-text description to edit", task_id))
+            })?;
+            let updated = check_criterion(text, criterion_idx)
+                .ok_or_else(|| PluginError::NotFound(format!("criterion {} of {}", criterion_idx, task_id)))?;
+            *field = updated.into();
+            Ok(())
+        })
+    }
+
+    async fn progress(&self) -> Result<Progress> {
+        Ok(summarize(&self.tasks()?))
+    }
+
+    async fn sync(&self) -> Result<()> {
+        // An offline export has nowhere to push to
+        Ok(())
+    }
+
+    async fn reload(&mut self) -> Result<()> {
+        self.load().map(|_| ())
+    }
+}
+
+fn issue_id(format: Format, issue: &Value) -> String {
+    match format {
+        Format::GitHub => issue["number"]
+            .as_u64()
+            .map(|n| n.to_string())
+            .unwrap_or_else(|| issue["id"].to_string()),
+        Format::Jira => issue["key"].as_str().unwrap_or_default().to_string(),
+    }
+}
+
+fn label_name(label: &Value) -> &str {
+    label["name"].as_str().or_else(|| label.as_str()).unwrap_or_default()
+}
+
+fn status(format: Format, issue: &Value) -> TaskStatus {
+    match format {
+        Format::GitHub => {
+            let labels = issue["labels"].as_array().map(Vec::as_slice).unwrap_or_default();
+            let labelled = |name: &str| labels.iter().any(|l| label_name(l) == name);
+            if issue["state"].as_str().is_some_and(|s| s.eq_ignore_ascii_case("closed")) {
+                TaskStatus::Closed
+            } else if labelled(BLOCKED_LABEL) {
+                TaskStatus::Blocked
+            } else if labelled(IN_PROGRESS_LABEL) {
+                TaskStatus::InProgress
+            } else {
+                TaskStatus::Open
+            }
+        }
+        Format::Jira => {
+            let status = &issue["fields"]["status"];
+            let name = status["name"].as_str().unwrap_or_default().to_lowercase();
+            if status["statusCategory"]["key"] == "done" || matches!(name.as_str(), "done" | "closed" | "resolved") {
+                TaskStatus::Closed
+            } else if name == "blocked" {
+                TaskStatus::Blocked
+            } else if name == "in progress" {
+                TaskStatus::InProgress
+            } else {
+                TaskStatus::Open
+            }
+        }
+    }
+}
+
+fn to_task(format: Format, issue: &Value) -> Task {
+    match format {
+        Format::GitHub => {
+            let body = issue["body"].as_str().unwrap_or_default();
+            let labels: Vec<&str> = issue["labels"]
+                .as_array()
+                .map(|l| l.iter().map(label_name).collect())
+                .unwrap_or_default();
+            let priority = labels
+                .iter()
+                .find_map(|l| {
+                    l.strip_prefix('P')
+                        .or_else(|| l.strip_prefix("priority:"))
+                        .and_then(|p| p.trim().parse().ok())
+                })
+                .unwrap_or(2);
+
+            // "Depends on #3", "Blocked by #4, #5"
+            let dep_re = Regex::new(r"(?im)(?:depends on|blocked by):?(.*)$").unwrap();
+            let num_re = Regex::new(r"#(\d+)").unwrap();
+            let dependencies = dep_re
+                .captures_iter(body)
+                .flat_map(|c| {
+                    num_re
+                        .captures_iter(c.get(1).map_or("", |m| m.as_str()))
+                        .map(|n| n[1].to_string())
+                        .collect::<Vec<_>>()
+                })
+                .collect();
+
+            Task {
+                id: issue_id(format, issue),
+                name: issue["title"].as_str().unwrap_or_default().to_string(),
+                description: body.to_string(),
+                path: issue["url"].as_str().or(issue["html_url"].as_str()).map(str::to_string),
+                priority,
+                dependencies,
+                criteria: parse_criteria(body),
+                status: status(format, issue),
+                metadata: serde_json::json!({
+                    "issue_type": "issue",
+                    "labels": labels,
+                    "owner": issue["assignees"][0]["login"],
+                }),
+            }
+        }
+        Format::Jira => {
+            let fields = &issue["fields"];
+            let description = description_text(&fields["description"]);
+            let priority = match fields["priority"]["name"].as_str().map(str::to_lowercase).as_deref() {
+                Some("highest") | Some("blocker") => 0,
+                Some("high") | Some("critical") => 1,
+                Some("low") | Some("minor") => 3,
+                Some("lowest") | Some("trivial") => 4,
+                _ => 2,
+            };
+            let dependencies = fields["issuelinks"]
+                .as_array()
+                .map(|links| {
+                    links
+                        .iter()
+                        .filter(|l| l["type"]["inward"].as_str().is_some_and(|s| s.contains("blocked by")))
+                        .filter_map(|l| l["inwardIssue"]["key"].as_str().map(str::to_string))
+                        .collect()
+                })
+                .unwrap_or_default();
+
+            Task {
+                id: issue_id(format, issue),
+                name: fields["summary"].as_str().unwrap_or_default().to_string(),
+                criteria: parse_criteria(&description),
+                description,
+                path: issue["self"].as_str().map(str::to_string),
+                priority,
+                dependencies,
+                status: status(format, issue),
+                metadata: serde_json::json!({
+                    "issue_type": fields["issuetype"]["name"].as_str().unwrap_or("task").to_lowercase(),
+                    "labels": fields["labels"],
+                    "owner": fields["assignee"]["displayName"],
+                }),
+            }
+        }
+    }
+}
+
+/// Plain text of a Jira description: a string, or an Atlassian Document Format tree
+fn description_text(value: &Value) -> String {
+    fn walk(node: &Value, out: &mut String) {
+        if let Some(text) = node["text"].as_str() {
+            out.push_str(text);
+        }
+        if let Some(children) = node["content"].as_array() {
+            for child in children {
+                walk(child, out);
+            }
+        }
+        if matches!(node["type"].as_str(), Some("paragraph") | Some("heading") | Some("listItem")) {
+            out.push('\n');
+        }
+    }
+
+    match value {
+        Value::String(s) => s.clone(),
+        Value::Object(_) => {
+            let mut out = String::new();
+            walk(value, &mut out);
+            out
+        }
+        _ => String::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[tokio::test]
+    async fn test_github_export() {
+        let temp = TempDir::new().unwrap();
+        std::fs::write(
+            temp.path().join("issues.json"),
+            r##"[
+                {"number": 7, "title": "Add login", "state": "OPEN", "labels": [{"name": "P1"}],
+                 "body": "Depends on #3\n\n- [ ] form\n- [ ] session"},
+                {"number": 3, "title": "Add users table", "state": "OPEN", "labels": [], "body": ""},
+                {"number": 1, "title": "Old", "state": "CLOSED", "labels": [], "body": ""}
+            ]"##,
+        )
+        .unwrap();
+
+        let mut tracker = IssueExportTracker::new("issues.json");
+        tracker.init(temp.path()).await.unwrap();
+
+        // #7 has the higher priority but waits on #3
+        assert_eq!(tracker.next_task().await.unwrap().unwrap().id, "3");
+        tracker.start_task("3").await.unwrap();
+        assert_eq!(tracker.progress().await.unwrap().in_progress_tasks, 1);
+
+        tracker.complete_task_with_reason("3", Some("completed")).await.unwrap();
+        let next = tracker.next_task().await.unwrap().unwrap();
+        assert_eq!((next.id.as_str(), next.priority), ("7", 1));
+
+        tracker.complete_criterion("7", 0).await.unwrap();
+        let task = tracker.get_task("7").await.unwrap().unwrap();
+        assert!(task.criteria[0].completed);
+
+        tracker.block_task("7").await.unwrap();
+        assert_eq!(tracker.get_task("7").await.unwrap().unwrap().status, TaskStatus::Blocked);
+        assert!(tracker.next_task().await.unwrap().is_none());
+
+        let saved: Value = serde_json::from_str(&std::fs::read_to_string(temp.path().join("issues.json")).unwrap()).unwrap();
+        assert_eq!(saved[1]["state"], "CLOSED");
+        assert_eq!(saved[1]["stateReason"], "completed");
+        assert_eq!(saved[0]["labels"], serde_json::json!([{ "name": "P1" }, { "name": "blocked" }]));
+    }
+
+    #[tokio::test]
+    async fn test_jira_export() {
+        let temp = TempDir::new().unwrap();
+        std::fs::write(
+            temp.path().join("jira.json"),
+            r#"{"issues": [
+                {"key": "APP-2", "fields": {"summary": "Checkout", "status": {"name": "To Do"},
+                 "priority": {"name": "Highest"},
+                 "issuelinks": [{"type": {"name": "Blocks", "inward": "is blocked by"}, "inwardIssue": {"key": "APP-1"}}],
+                 "description": {"type": "doc", "content": [{"type": "paragraph", "content": [{"type": "text", "text": "Pay with card"}]}]}}},
+                {"key": "APP-1", "fields": {"summary": "Cart", "status": {"name": "Done"}, "priority": {"name": "Low"}}}
+            ]}"#,
+        )
+        .unwrap();
+
+        let mut tracker = IssueExportTracker::new("jira.json");
+        tracker.init(temp.path()).await.unwrap();
+
+        let next = tracker.next_task().await.unwrap().unwrap();
+        assert_eq!(next.id, "APP-2");
+        assert_eq!(next.priority, 0);
+        assert_eq!(next.dependencies, ["APP-1"]);
+        assert_eq!(next.description, "Pay with card\n");
+
+        tracker.complete_task("APP-2").await.unwrap();
+        assert!(tracker.next_task().await.unwrap().is_none());
+    }
+}
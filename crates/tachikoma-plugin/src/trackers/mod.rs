@@ -0,0 +1,133 @@
+//! Built-in tracker plugins
+//!
+//! - [`BeadsTracker`]: the beads issue store (`.beads/issues.jsonl`, read
+//!   and written through [`beads::BeadsStore`])
+//! - [`SpecsTracker`]: `specs/README.md` lookup table and spec checkboxes
+//! - [`IssueExportTracker`]: an offline GitHub or Jira issue export (JSON)
+//!
+//! Trackers read their backing files on every call, so edits made by the
+//! agent (ticking a checkbox, closing an issue) are picked up without an
+//! explicit reload.
+
+pub mod beads;
+pub mod issues;
+pub mod specs;
+
+pub use beads::BeadsTracker;
+pub use issues::IssueExportTracker;
+pub use specs::SpecsTracker;
+
+use std::path::Path;
+
+use regex::Regex;
+
+use crate::tracker::{Progress, Task, TaskCriterion, TaskStatus};
+use crate::{PluginError, PluginManifest, PluginType, Result};
+
+/// Manifest for a built-in tracker
+fn builtin_manifest(name: &str, description: &str) -> PluginManifest {
+    PluginManifest {
+        name: name.to_string(),
+        version: env!("CARGO_PKG_VERSION").to_string(),
+        plugin_type: PluginType::Tracker,
+        description: description.to_string(),
+        author: None,
+        homepage: None,
+        requires: Vec::new(),
+        config: Default::default(),
+        adapter: None,
+        builtin: true,
+    }
+}
+
+fn checkbox_regex() -> Regex {
+    Regex::new(r"^(\s*[-*]\s*\[)([ xX])(\]\s*)(.+)$").unwrap()
+}
+
+/// Markdown checkboxes in `content`
+fn parse_criteria(content: &str) -> Vec<TaskCriterion> {
+    let re = checkbox_regex();
+    content
+        .lines()
+        .filter_map(|line| re.captures(line))
+        .map(|caps| TaskCriterion {
+            text: caps[4].trim().to_string(),
+            completed: &caps[2] != " ",
+        })
+        .collect()
+}
+
+/// Tick the `index`-th checkbox in `content`; `None` if there is no such checkbox
+fn check_criterion(content: &str, index: usize) -> Option<String> {
+    let re = checkbox_regex();
+    let mut seen = 0;
+    let mut found = false;
+
+    let lines: Vec<String> = content
+        .lines()
+        .map(|line| match re.captures(line) {
+            Some(caps) => {
+                let current = seen;
+                seen += 1;
+                if current == index {
+                    found = true;
+                    format!("{}x{}{}", &caps[1], &caps[3], &caps[4])
+                } else {
+                    line.to_string()
+                }
+            }
+            None => line.to_string(),
+        })
+        .collect();
+
+    found.then(|| {
+        let mut updated = lines.join("\n");
+        if content.ends_with('\n') {
+            updated.push('\n');
+        }
+        updated
+    })
+}
+
+/// Replace `path` through a temp file so readers never see a partial write
+fn write_atomic(path: &Path, content: &str) -> Result<()> {
+    let file_name = path
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let tmp = path.with_file_name(format!(".{}.tmp-{}", file_name, std::process::id()));
+    std::fs::write(&tmp, content)?;
+    std::fs::rename(&tmp, path)?;
+    Ok(())
+}
+
+fn not_initialized() -> PluginError {
+    PluginError::ExecutionFailed("tracker used before init()".to_string())
+}
+
+/// Progress over every task, closed ones included
+fn summarize(tasks: &[Task]) -> Progress {
+    let criteria = tasks.iter().flat_map(|t| &t.criteria);
+    Progress {
+        total_tasks: tasks.len(),
+        completed_tasks: tasks.iter().filter(|t| t.status == TaskStatus::Closed).count(),
+        in_progress_tasks: tasks.iter().filter(|t| t.status == TaskStatus::InProgress).count(),
+        total_criteria: criteria.clone().count(),
+        completed_criteria: criteria.filter(|c| c.completed).count(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_check_criterion() {
+        let content = "Intro\n- [ ] one\n- [x] two\n  * [ ] three\n";
+        assert_eq!(parse_criteria(content).len(), 3);
+
+        let updated = check_criterion(content, 2).unwrap();
+        assert_eq!(updated, "Intro\n- [ ] one\n- [x] two\n  * [x] three\n");
+        assert!(check_criterion(content, 3).is_none());
+    }
+}
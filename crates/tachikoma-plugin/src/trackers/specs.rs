@@ -0,0 +1,384 @@
+//! Specs tracker
+//!
+//! Works from the `specs/README.md` lookup table ("the pin"): every table
+//! row links to a spec file, and a spec is done once all checkboxes in its
+//! `## Acceptance Criteria` section are ticked (or its `**Status:**` header
+//! says Complete). Specs are handed out in table order, skipping any whose
+//! `**Dependencies:**` aren't done yet and any whose status is Blocked.
+//! Spec files are parsed with `tachikoma-spec`.
+
+use std::path::{Path, PathBuf};
+
+use async_trait::async_trait;
+use regex::Regex;
+use tachikoma_spec::SpecParser;
+
+use crate::tracker::{Progress, Task, TaskCriterion, TaskStatus, TrackerPlugin};
+use crate::{PluginError, PluginManifest, Result};
+
+use super::{builtin_manifest, not_initialized, summarize, write_atomic};
+
+const DEFAULT_SPECS_DIR: &str = "specs";
+const CRITERIA_SECTION: &str = "Acceptance Criteria";
+
+/// One row of the README lookup table
+#[derive(Debug, Clone)]
+struct SpecEntry {
+    id: String,
+    name: String,
+    path: PathBuf,
+    phase: u32,
+}
+
+/// A spec file as the tracker sees it
+#[derive(Debug, Clone)]
+struct SpecState {
+    task: Task,
+    /// 0-based line of each acceptance criterion, parallel to `task.criteria`
+    criteria_lines: Vec<usize>,
+}
+
+/// Tracker backed by a `specs/` directory
+pub struct SpecsTracker {
+    manifest: PluginManifest,
+    specs_dir: PathBuf,
+    root: Option<PathBuf>,
+}
+
+impl SpecsTracker {
+    pub fn new() -> Self {
+        Self::with_dir(DEFAULT_SPECS_DIR)
+    }
+
+    /// Use a specs directory other than `specs/` (relative to the project root)
+    pub fn with_dir(specs_dir: impl Into<PathBuf>) -> Self {
+        Self {
+            manifest: builtin_manifest("specs", "Markdown specs indexed by specs/README.md"),
+            specs_dir: specs_dir.into(),
+            root: None,
+        }
+    }
+
+    fn dir(&self) -> Result<PathBuf> {
+        self.root.as_ref().map(|r| r.join(&self.specs_dir)).ok_or_else(not_initialized)
+    }
+
+    /// Rows of the README table, in order
+    fn entries(&self) -> Result<Vec<SpecEntry>> {
+        let dir = self.dir()?;
+        let readme = std::fs::read_to_string(dir.join("README.md"))?;
+
+        // | 001 | [Project Structure](phase-00-setup/001-project-structure.md) | init, scaffold |
+        let row_re = Regex::new(r"^\|\s*(\d{3}[a-z]?)\s*\|\s*\[([^\]]+)\]\(([^)]+)\)").unwrap();
+        let phase_re = Regex::new(r"^##\s*Phase\s*(\d+)").unwrap();
+
+        let mut phase = 0;
+        let mut entries = Vec::new();
+        for line in readme.lines() {
+            if let Some(caps) = phase_re.captures(line) {
+                phase = caps[1].parse().unwrap_or(0);
+            } else if let Some(caps) = row_re.captures(line) {
+                entries.push(SpecEntry {
+                    id: caps[1].to_string(),
+                    name: caps[2].trim().to_string(),
+                    path: dir.join(&caps[3]),
+                    phase,
+                });
+            }
+        }
+
+        Ok(entries)
+    }
+
+    /// Every spec whose file exists, in table order
+    fn load(&self) -> Result<Vec<SpecState>> {
+        let parser = SpecParser::new();
+        let mut specs = Vec::new();
+        for entry in self.entries()? {
+            match std::fs::read_to_string(&entry.path) {
+                Ok(content) => specs.push(spec_state(&parser, &entry, &content)),
+                // Index rows for specs that haven't been written yet
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => continue,
+                Err(e) => return Err(e.into()),
+            }
+        }
+        Ok(specs)
+    }
+
+    fn find(&self, id: &str) -> Result<SpecState> {
+        self.load()?
+            .into_iter()
+            .find(|s| s.task.id == id)
+            .ok_or_else(|| PluginError::NotFound(format!("spec {}", id)))
+    }
+
+    /// Rewrite one spec file line by line
+    fn edit(&self, spec: &SpecState, change: impl Fn(usize, &str) -> Option<String>) -> Result<()> {
+        let path = spec.task.path.as_ref().ok_or_else(|| PluginError::NotFound(spec.task.id.clone()))?;
+        let content = std::fs::read_to_string(path)?;
+        let mut updated: Vec<String> = content
+            .lines()
+            .enumerate()
+            .map(|(n, line)| change(n, line).unwrap_or_else(|| line.to_string()))
+            .collect();
+        if content.ends_with('\n') {
+            updated.push(String::new());
+        }
+        write_atomic(Path::new(path), &updated.join("\n"))
+    }
+
+    fn set_status(&self, spec: &SpecState, status: &str) -> Result<()> {
+        let status_re = status_regex();
+        self.edit(spec, |_, line| {
+            status_re
+                .captures(line)
+                .map(|caps| format!("{}{}{}", &caps[1], status, &caps[3]))
+        })
+    }
+}
+
+impl Default for SpecsTracker {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[async_trait]
+impl TrackerPlugin for SpecsTracker {
+    fn manifest(&self) -> &PluginManifest {
+        &self.manifest
+    }
+
+    async fn init(&mut self, root: &Path) -> Result<()> {
+        let readme = root.join(&self.specs_dir).join("README.md");
+        if !readme.exists() {
+            return Err(PluginError::InitializationFailed(format!(
+                "{} not found",
+                readme.display()
+            )));
+        }
+        self.root = Some(root.to_path_buf());
+        Ok(())
+    }
+
+    async fn next_task(&self) -> Result<Option<Task>> {
+        Ok(self.ready_tasks().await?.into_iter().next())
+    }
+
+    async fn ready_tasks(&self) -> Result<Vec<Task>> {
+        let tasks = self.list_tasks().await?;
+        // Dependencies outside the table count as done
+        let done = |id: &str| tasks.iter().find(|t| t.id == id).map_or(true, |t| t.status == TaskStatus::Closed);
+
+        Ok(tasks
+            .iter()
+            .filter(|t| matches!(t.status, TaskStatus::Open | TaskStatus::InProgress))
+            .filter(|t| t.dependencies.iter().all(|d| done(d)))
+            .cloned()
+            .collect())
+    }
+
+    async fn get_task(&self, id: &str) -> Result<Option<Task>> {
+        Ok(self.load()?.into_iter().find(|s| s.task.id == id).map(|s| s.task))
+    }
+
+    async fn list_tasks(&self) -> Result<Vec<Task>> {
+        Ok(self.load()?.into_iter().map(|s| s.task).collect())
+    }
+
+    async fn start_task(&self, id: &str) -> Result<()> {
+        let spec = self.find(id)?;
+        self.set_status(&spec, "In Progress")
+    }
+
+    async fn complete_task(&self, id: &str) -> Result<()> {
+        let spec = self.find(id)?;
+        let checkbox_re = Regex::new(r"^(\s*[-*]\s*\[) (\].*)$").unwrap();
+        let status_re = status_regex();
+
+        self.edit(&spec, |n, line| {
+            if spec.criteria_lines.contains(&n) {
+                checkbox_re.captures(line).map(|caps| format!("{}x{}", &caps[1], &caps[2]))
+            } else {
+                status_re
+                    .captures(line)
+                    .map(|caps| format!("{}Complete{}", &caps[1], &caps[3]))
+            }
+        })
+    }
+
+    /// Sets the spec's `**Status:**` header to Blocked
+    async fn block_task(&self, id: &str) -> Result<()> {
+        let spec = self.find(id)?;
+        self.set_status(&spec, "Blocked")
+    }
+
+    async fn complete_criterion(&self, task_id: &str, criterion_idx: usize) -> Result<()> {
+        let spec = self.find(task_id)?;
+        let line_no = *spec
+            .criteria_lines
+            .get(criterion_idx)
+            .ok_or_else(|| PluginError::NotFound(format!("criterion {} of spec {}", criterion_idx, task_id)))?;
+        let checkbox_re = Regex::new(r"^(\s*[-*]\s*\[) (\].*)$").unwrap();
+
+        self.edit(&spec, |n, line| {
+            (n == line_no)
+                .then(|| checkbox_re.captures(line))
+                .flatten()
+                .map(|caps| format!("{}x{}", &caps[1], &caps[2]))
+        })
+    }
+
+    async fn progress(&self) -> Result<Progress> {
+        Ok(summarize(&self.list_tasks().await?))
+    }
+
+    async fn sync(&self) -> Result<()> {
+        // Spec files are plain files in the repository; committing them is the caller's job
+        Ok(())
+    }
+
+    async fn reload(&mut self) -> Result<()> {
+        self.entries().map(|_| ())
+    }
+}
+
+/// `**Status:** Planned` (with the value in group 2)
+fn status_regex() -> Regex {
+    Regex::new(r"^(\*\*Status:\*\*\s*)([^\s].*?)(\s*)$").unwrap()
+}
+
+fn header_field<'a>(content: &'a str, field: &str) -> Option<&'a str> {
+    let prefix = format!("**{}:**", field);
+    content
+        .lines()
+        .take_while(|l| !l.starts_with("## "))
+        .find_map(|l| l.trim().strip_prefix(prefix.as_str()))
+        .map(str::trim)
+}
+
+fn spec_state(parser: &SpecParser, entry: &SpecEntry, content: &str) -> SpecState {
+    let parsed = parser.parse_safe(content);
+
+    let mut checkboxes: Vec<_> = parsed
+        .acceptance_criteria
+        .iter()
+        .filter(|c| c.section == CRITERIA_SECTION)
+        .collect();
+    if checkboxes.is_empty() {
+        checkboxes = parsed.acceptance_criteria.iter().collect();
+    }
+
+    let status = header_field(content, "Status")
+        .map(str::to_string)
+        .unwrap_or_else(|| parsed.metadata.status.clone());
+    let header = status.to_lowercase();
+    let all_checked = !checkboxes.is_empty() && checkboxes.iter().all(|c| c.checked);
+    let task_status = if all_checked || header.starts_with("complete") || header == "done" {
+        TaskStatus::Closed
+    } else if header.starts_with("blocked") {
+        TaskStatus::Blocked
+    } else if header.starts_with("in progress") {
+        TaskStatus::InProgress
+    } else {
+        TaskStatus::Open
+    };
+
+    // "**Dependencies:** 551 (Application Shell), 560" -> ["551", "560"]
+    let dep_re = Regex::new(r"\b(\d{3}[a-z]?)\b").unwrap();
+    let dependencies = header_field(content, "Dependencies")
+        .map(|deps| {
+            dep_re
+                .captures_iter(deps)
+                .map(|c| c[1].to_string())
+                .filter(|d| *d != entry.id)
+                .collect()
+        })
+        .unwrap_or_default();
+
+    let priority = header_field(content, "Priority")
+        .and_then(|p| p.trim_start_matches('P').chars().next())
+        .and_then(|c| c.to_digit(10))
+        .unwrap_or(2) as u8;
+
+    SpecState {
+        task: Task {
+            id: entry.id.clone(),
+            name: entry.name.clone(),
+            description: content.to_string(),
+            path: Some(entry.path.display().to_string()),
+            priority,
+            dependencies,
+            criteria: checkboxes
+                .iter()
+                .map(|c| TaskCriterion {
+                    text: c.text.trim().to_string(),
+                    completed: c.checked,
+                })
+                .collect(),
+            status: task_status,
+            metadata: serde_json::json!({
+                "issue_type": format!("spec (phase {})", entry.phase),
+                "phase": entry.phase,
+                "status": status,
+            }),
+        },
+        criteria_lines: checkboxes.iter().map(|c| c.line).collect(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn write(dir: &Path, name: &str, content: &str) {
+        let path = dir.join(name);
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(path, content).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_specs_in_order_with_dependencies() {
+        let temp = TempDir::new().unwrap();
+        let specs = temp.path().join("specs");
+        write(
+            &specs,
+            "README.md",
+            "## Phase 0: Setup (001-002)\n| Spec | File | Keywords |\n|---|---|---|\n\
+             | 001 | [Scaffold](phase-00/001-scaffold.md) | init |\n\
+             | 002 | [Workspace](phase-00/002-workspace.md) | cargo |\n",
+        );
+        write(
+            &specs,
+            "phase-00/001-scaffold.md",
+            "# Spec 001: Scaffold\n\n**Status:** Planned\n\n## Acceptance Criteria\n\n- [x] Dirs\n- [ ] README\n\n## Notes\n\n- [ ] not a criterion\n",
+        );
+        write(
+            &specs,
+            "phase-00/002-workspace.md",
+            "# Spec 002: Workspace\n\n**Status:** Planned\n**Dependencies:** 001 (Scaffold)\n\n## Acceptance Criteria\n\n- [ ] Cargo.toml\n",
+        );
+
+        let mut tracker = SpecsTracker::new();
+        tracker.init(temp.path()).await.unwrap();
+
+        let next = tracker.next_task().await.unwrap().unwrap();
+        assert_eq!(next.id, "001");
+        assert_eq!(next.criteria.len(), 2);
+
+        tracker.complete_criterion("001", 1).await.unwrap();
+        let next = tracker.next_task().await.unwrap().unwrap();
+        assert_eq!(next.id, "002");
+        assert_eq!(next.dependencies, ["001"]);
+
+        tracker.complete_task("002").await.unwrap();
+        assert!(tracker.next_task().await.unwrap().is_none());
+        let content = std::fs::read_to_string(specs.join("phase-00/002-workspace.md")).unwrap();
+        assert!(content.contains("**Status:** Complete\n"));
+        assert!(content.contains("- [x] Cargo.toml"));
+
+        let progress = tracker.progress().await.unwrap();
+        assert_eq!((progress.completed_tasks, progress.total_tasks), (2, 2));
+    }
+}
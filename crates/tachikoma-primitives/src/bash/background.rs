@@ -0,0 +1,596 @@
+//! Background processes: start a command now, come back to it later.
+//!
+//! `bash` waits for its command to finish, which a dev server or a file
+//! watcher never does. [`bash_start`] spawns the command and returns a handle
+//! instead. Its output (stdout and stderr interleaved, as they arrive) piles
+//! up in a buffer that [`bash_read`] reads by byte offset, [`bash_wait`] blocks
+//! until a pattern such as `listening on` shows up or the process exits,
+//! [`bash_write`] feeds its stdin and [`bash_kill`] stops it along with
+//! everything it started.
+//!
+//! Handles live in the context's [`ProcessTable`], which its clones share.
+//! When the last clone goes away at the end of a session, every process
+//! still running is killed.
+
+use regex::Regex;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::process::Stdio;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWriteExt};
+use tokio::process::{Child, ChildStdin, Command};
+use tokio::sync::{mpsc, watch, Mutex as AsyncMutex};
+use tracing::{debug, instrument};
+
+use super::{BashOptions, CommandValidator};
+use crate::{
+    context::PrimitiveContext,
+    error::{PrimitiveError, PrimitiveResult},
+    result::{ExecutionMetadata, ProcessResult},
+};
+
+/// Output kept per process; the oldest is dropped first.
+pub const MAX_BUFFERED_BYTES: usize = 1024 * 1024;
+
+/// Most output returned by one read.
+const DEFAULT_READ_BYTES: usize = 64 * 1024;
+
+/// Processes one session may run at once.
+const MAX_PROCESSES: usize = 16;
+
+/// Time between SIGTERM and SIGKILL when killing a process, and the longest
+/// wait for it to go after SIGKILL.
+const KILL_GRACE_PERIOD: Duration = Duration::from_secs(2);
+
+/// How long output is still collected after a process exits. Children it
+/// left running can hold its pipes open indefinitely.
+const OUTPUT_DRAIN_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// The background processes of a session.
+#[derive(Clone, Default)]
+pub struct ProcessTable {
+    inner: Arc<Table>,
+}
+
+#[derive(Default)]
+struct Table {
+    processes: Mutex<BTreeMap<String, Arc<Process>>>,
+    next_id: AtomicU64,
+}
+
+impl fmt::Debug for ProcessTable {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ProcessTable").field("ids", &self.ids()).finish()
+    }
+}
+
+impl ProcessTable {
+    /// Handles of the processes started and not yet killed.
+    pub fn ids(&self) -> Vec<String> {
+        self.inner.processes.lock().unwrap().keys().cloned().collect()
+    }
+
+    /// Kill every process and forget them.
+    pub fn kill_all(&self) {
+        // Dropping a handle kills its process
+        std::mem::take(&mut *self.inner.processes.lock().unwrap());
+    }
+
+    fn insert(&self, process: Process) -> PrimitiveResult<String> {
+        let mut processes = self.inner.processes.lock().unwrap();
+        if processes.values().filter(|p| p.shared.exit_code().is_none()).count() >= MAX_PROCESSES {
+            return Err(PrimitiveError::Validation {
+                message: format!("Already running {} background processes; kill one first", MAX_PROCESSES),
+            });
+        }
+        let id = format!("p{}", self.inner.next_id.fetch_add(1, Ordering::Relaxed) + 1);
+        processes.insert(id.clone(), Arc::new(process));
+        Ok(id)
+    }
+
+    fn get(&self, id: &str) -> PrimitiveResult<Arc<Process>> {
+        self.inner.processes.lock().unwrap().get(id).cloned().ok_or_else(|| PrimitiveError::Validation {
+            message: format!("No background process {}", id),
+        })
+    }
+
+    fn remove(&self, id: &str) -> Option<Arc<Process>> {
+        self.inner.processes.lock().unwrap().remove(id)
+    }
+}
+
+/// A started process.
+struct Process {
+    command: String,
+    pid: Option<u32>,
+    shared: Arc<Shared>,
+    stdin: AsyncMutex<Option<ChildStdin>>,
+    /// Where the next read without an offset starts.
+    cursor: Mutex<usize>,
+}
+
+impl Drop for Process {
+    fn drop(&mut self) {
+        if self.shared.exit_code().is_none() {
+            debug!("Killing background process: {}", self.command);
+            self.shared.stop(Stop::Kill);
+        }
+    }
+}
+
+/// A request to the task that owns the child to stop it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Stop {
+    Term,
+    Kill,
+}
+
+/// What the process's tasks and its handle both see.
+struct Shared {
+    output: Mutex<Output>,
+    /// Set as soon as the process has been reaped.
+    exit_code: Mutex<Option<i32>>,
+    /// Set once the output written before the exit has been collected.
+    drained: AtomicBool,
+    /// Bumped on new output, on exit and once drained.
+    changed: watch::Sender<u64>,
+    stops: mpsc::UnboundedSender<Stop>,
+}
+
+impl Shared {
+    fn exit_code(&self) -> Option<i32> {
+        *self.exit_code.lock().unwrap()
+    }
+
+    fn drained(&self) -> bool {
+        self.drained.load(Ordering::Acquire)
+    }
+
+    fn bump(&self) {
+        self.changed.send_modify(|version| *version += 1);
+    }
+
+    /// Signals go through the task that owns the child, which only sends them
+    /// before reaping it, so they can't hit a reused pid.
+    fn stop(&self, stop: Stop) {
+        let _ = self.stops.send(stop);
+    }
+
+    /// Wait until `done` holds or `deadline` passes; returns whether it holds.
+    async fn wait_until(
+        &self,
+        changed: &mut watch::Receiver<u64>,
+        deadline: tokio::time::Instant,
+        done: impl Fn(&Shared) -> bool,
+    ) -> bool {
+        while !done(self) {
+            if !matches!(tokio::time::timeout_at(deadline, changed.changed()).await, Ok(Ok(()))) {
+                return done(self);
+            }
+        }
+        true
+    }
+}
+
+/// The retained tail of a process's output.
+#[derive(Default)]
+struct Output {
+    bytes: Vec<u8>,
+    /// Offset of `bytes[0]` in everything written.
+    start: usize,
+}
+
+impl Output {
+    fn end(&self) -> usize {
+        self.start + self.bytes.len()
+    }
+
+    fn push(&mut self, chunk: &[u8]) {
+        self.bytes.extend_from_slice(chunk);
+        if self.bytes.len() > MAX_BUFFERED_BYTES {
+            let excess = self.bytes.len() - MAX_BUFFERED_BYTES;
+            self.bytes.drain(..excess);
+            self.start += excess;
+        }
+    }
+
+    /// Up to `max` bytes from `offset`, without splitting a character.
+    ///
+    /// Returns the text, where it really starts and where it ends.
+    fn read(&self, offset: usize, max: usize) -> (String, usize, usize) {
+        let from = offset.clamp(self.start, self.end());
+        let mut to = from.saturating_add(max).min(self.end());
+        while to > from && to < self.end() && (self.bytes[to - self.start] & 0xC0) == 0x80 {
+            to -= 1;
+        }
+        let text = String::from_utf8_lossy(&self.bytes[from - self.start..to - self.start]).into_owned();
+        (text, from, to)
+    }
+}
+
+/// Start a command in the background.
+///
+/// The command runs with `bash -c` in its own process group; `options`
+/// supplies the working directory, environment and blocked commands (its
+/// timeout doesn't apply).
+///
+/// # Example
+///
+/// ```no_run
+/// use tachikoma_primitives::{PrimitiveContext, bash_start, bash_wait, bash_kill};
+/// use std::path::PathBuf;
+/// use std::time::Duration;
+///
+/// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+/// let ctx = PrimitiveContext::new(PathBuf::from("."));
+/// let server = bash_start(&ctx, "npm run dev", None).await?;
+/// let ready = bash_wait(&ctx, &server.id, Some("listening on"), Duration::from_secs(60)).await?;
+/// assert_eq!(ready.matched, Some(true));
+/// // ... run tests against it ...
+/// bash_kill(&ctx, &server.id).await?;
+/// # Ok(())
+/// # }
+/// ```
+#[instrument(skip(ctx, options), fields(command = %command, op_id = %ctx.operation_id))]
+pub async fn bash_start(
+    ctx: &PrimitiveContext,
+    command: &str,
+    options: Option<BashOptions>,
+) -> PrimitiveResult<ProcessResult> {
+    let start = Instant::now();
+    let options = options.unwrap_or_default();
+    CommandValidator::new(&options.blocked_commands).validate(command)?;
+
+    let working_dir = options
+        .working_dir
+        .as_ref()
+        .map(|p| ctx.resolve_path(p))
+        .unwrap_or_else(|| ctx.working_dir.clone());
+    if !ctx.is_path_allowed(&working_dir) {
+        return Err(PrimitiveError::PathNotAllowed { path: working_dir });
+    }
+
+    let mut cmd = Command::new("bash");
+    cmd.arg("-c").arg(command).current_dir(&working_dir);
+    if options.clear_env {
+        cmd.env_clear();
+    }
+    cmd.envs(&options.env_vars);
+    spawn(ctx, command, cmd, start)
+}
+
+/// Start a prebuilt command in the background.
+///
+/// For callers that wrap the command themselves, such as in a sandbox;
+/// `command` is what the process is listed as. The caller sets the working
+/// directory and environment and does any validation. Stdio, the process
+/// group and kill-on-drop are set here, as for [`bash_start`].
+#[instrument(skip(ctx, cmd), fields(command = %command, op_id = %ctx.operation_id))]
+pub async fn bash_start_command(ctx: &PrimitiveContext, command: &str, cmd: Command) -> PrimitiveResult<ProcessResult> {
+    spawn(ctx, command, cmd, Instant::now())
+}
+
+fn spawn(ctx: &PrimitiveContext, command: &str, mut cmd: Command, start: Instant) -> PrimitiveResult<ProcessResult> {
+    cmd.stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true);
+    // Its own group, so killing it takes its children too
+    #[cfg(unix)]
+    cmd.process_group(0);
+
+    let mut child = cmd.spawn().map_err(PrimitiveError::Io)?;
+    let pid = child.id();
+    let (stops, mut stop_requests) = mpsc::unbounded_channel();
+    let shared = Arc::new(Shared {
+        output: Mutex::new(Output::default()),
+        exit_code: Mutex::new(None),
+        drained: AtomicBool::new(false),
+        changed: watch::channel(0).0,
+        stops,
+    });
+
+    let pumps = [
+        child.stdout.take().map(|out| tokio::spawn(pump(out, shared.clone()))),
+        child.stderr.take().map(|err| tokio::spawn(pump(err, shared.clone()))),
+    ];
+    let stdin = child.stdin.take();
+    let watcher = shared.clone();
+    tokio::spawn(async move {
+        let status = loop {
+            tokio::select! {
+                // A child that has exited is reaped before any signal is sent
+                biased;
+                status = child.wait() => break status,
+                Some(stop) = stop_requests.recv() => stop_child(&mut child, stop),
+            }
+        };
+        let code = status.ok().and_then(|s| s.code()).unwrap_or(-1);
+        *watcher.exit_code.lock().unwrap() = Some(code);
+        watcher.bump();
+
+        // Collect what it wrote before exiting, but not from children that
+        // outlive it and keep the pipes open
+        let deadline = tokio::time::Instant::now() + OUTPUT_DRAIN_TIMEOUT;
+        for mut pump in pumps.into_iter().flatten() {
+            if tokio::time::timeout_at(deadline, &mut pump).await.is_err() {
+                pump.abort();
+            }
+        }
+        watcher.drained.store(true, Ordering::Release);
+        watcher.bump();
+    });
+
+    let process = Process {
+        command: command.to_string(),
+        pid,
+        shared,
+        stdin: AsyncMutex::new(stdin),
+        cursor: Mutex::new(0),
+    };
+    let id = ctx.processes.insert(process)?;
+    debug!("Started background process {} (pid {:?})", id, pid);
+
+    let process = ctx.processes.get(&id)?;
+    Ok(status(ctx, &id, &process, String::new(), 0, 0, 0, None, start))
+}
+
+async fn pump(mut reader: impl AsyncRead + Unpin, shared: Arc<Shared>) {
+    let mut buf = [0u8; 8192];
+    while let Ok(n) = reader.read(&mut buf).await {
+        if n == 0 {
+            break;
+        }
+        shared.output.lock().unwrap().push(&buf[..n]);
+        shared.bump();
+    }
+}
+
+/// Read a background process's output.
+///
+/// Reads from `offset`, or from where the last read or wait left off, up to
+/// `max_bytes` (64 KiB by default). Doesn't wait for output.
+pub async fn bash_read(
+    ctx: &PrimitiveContext,
+    id: &str,
+    offset: Option<usize>,
+    max_bytes: Option<usize>,
+) -> PrimitiveResult<ProcessResult> {
+    let start = Instant::now();
+    let process = ctx.processes.get(id)?;
+    Ok(read(ctx, id, &process, offset, max_bytes.unwrap_or(DEFAULT_READ_BYTES), None, start))
+}
+
+/// Write to a background process's stdin, closing it after if `close` is set.
+pub async fn bash_write(ctx: &PrimitiveContext, id: &str, input: &str, close: bool) -> PrimitiveResult<ProcessResult> {
+    let start = Instant::now();
+    let process = ctx.processes.get(id)?;
+    let mut stdin = process.stdin.lock().await;
+    let Some(pipe) = stdin.as_mut() else {
+        return Err(PrimitiveError::Validation {
+            message: format!("The stdin of {} is closed", id),
+        });
+    };
+    pipe.write_all(input.as_bytes()).await?;
+    pipe.flush().await?;
+    if close {
+        stdin.take();
+    }
+    drop(stdin);
+
+    let end = process.shared.output.lock().unwrap().end();
+    Ok(status(ctx, id, &process, String::new(), end, end, 0, None, start))
+}
+
+/// Wait until `pattern` (a regex) appears in the unread output, or, without
+/// a pattern, until the process exits.
+///
+/// Gives up after `timeout` without failing: the result says whether the
+/// pattern matched and carries the output read while waiting.
+pub async fn bash_wait(
+    ctx: &PrimitiveContext,
+    id: &str,
+    pattern: Option<&str>,
+    timeout: Duration,
+) -> PrimitiveResult<ProcessResult> {
+    let start = Instant::now();
+    let process = ctx.processes.get(id)?;
+    let pattern = pattern
+        .map(|p| Regex::new(p).map_err(|_| PrimitiveError::InvalidPattern { pattern: p.to_string() }))
+        .transpose()?;
+    let from = *process.cursor.lock().unwrap();
+
+    let mut changed = process.shared.changed.subscribe();
+    let deadline = tokio::time::Instant::now() + timeout;
+    let matched = loop {
+        let exited = process.shared.drained();
+        if let Some(pattern) = &pattern {
+            let output = process.shared.output.lock().unwrap();
+            let (text, _, _) = output.read(from, usize::MAX);
+            if pattern.is_match(&text) {
+                break Some(true);
+            }
+        }
+        if exited {
+            break pattern.as_ref().map(|_| false);
+        }
+        match tokio::time::timeout_at(deadline, changed.changed()).await {
+            Ok(Ok(())) => continue,
+            _ => break pattern.as_ref().map(|_| false),
+        }
+    };
+
+    Ok(read(ctx, id, &process, None, DEFAULT_READ_BYTES, matched, start))
+}
+
+/// Kill a background process and its children, and forget its handle.
+///
+/// Sends SIGTERM, then SIGKILL if it's still running after a grace period,
+/// and gives up waiting for it after another. The result carries its exit
+/// code and any output not read yet.
+pub async fn bash_kill(ctx: &PrimitiveContext, id: &str) -> PrimitiveResult<ProcessResult> {
+    let start = Instant::now();
+    let process = ctx.processes.get(id)?;
+
+    let shared = &process.shared;
+    let mut changed = shared.changed.subscribe();
+    let exited = |shared: &Shared| shared.exit_code().is_some();
+    if !exited(shared) {
+        shared.stop(Stop::Term);
+        let deadline = tokio::time::Instant::now() + KILL_GRACE_PERIOD;
+        if !shared.wait_until(&mut changed, deadline, exited).await {
+            shared.stop(Stop::Kill);
+            let deadline = tokio::time::Instant::now() + KILL_GRACE_PERIOD;
+            if !shared.wait_until(&mut changed, deadline, exited).await {
+                debug!("Process {} is still running after SIGKILL", id);
+            }
+        }
+    }
+    if exited(shared) {
+        let deadline = tokio::time::Instant::now() + OUTPUT_DRAIN_TIMEOUT;
+        shared.wait_until(&mut changed, deadline, Shared::drained).await;
+    }
+
+    let result = read(ctx, id, &process, None, DEFAULT_READ_BYTES, None, start);
+    ctx.processes.remove(id);
+    Ok(result)
+}
+
+/// Read from `offset` (or the cursor) and move the cursor past what was read.
+fn read(
+    ctx: &PrimitiveContext,
+    id: &str,
+    process: &Process,
+    offset: Option<usize>,
+    max_bytes: usize,
+    matched: Option<bool>,
+    start: Instant,
+) -> ProcessResult {
+    let mut cursor = process.cursor.lock().unwrap();
+    let requested = offset.unwrap_or(*cursor);
+    let (text, from, to) = process.shared.output.lock().unwrap().read(requested, max_bytes);
+    *cursor = to;
+    drop(cursor);
+    status(ctx, id, process, text, from, to, from.saturating_sub(requested), matched, start)
+}
+
+#[allow(clippy::too_many_arguments)]
+fn status(
+    ctx: &PrimitiveContext,
+    id: &str,
+    process: &Process,
+    output: String,
+    offset: usize,
+    next_offset: usize,
+    dropped_bytes: usize,
+    matched: Option<bool>,
+    start: Instant,
+) -> ProcessResult {
+    let exit_code = process.shared.exit_code();
+    ProcessResult {
+        id: id.to_string(),
+        command: process.command.clone(),
+        pid: process.pid,
+        running: exit_code.is_none(),
+        exit_code,
+        output,
+        offset,
+        next_offset,
+        dropped_bytes,
+        matched,
+        metadata: ExecutionMetadata {
+            duration: start.elapsed(),
+            operation_id: ctx.operation_id.clone(),
+            primitive: "process".to_string(),
+        },
+    }
+}
+
+/// Stop a child that hasn't been reaped yet, along with its group.
+fn stop_child(child: &mut Child, stop: Stop) {
+    #[cfg(unix)]
+    if let Some(pid) = child.id() {
+        let signal = match stop {
+            Stop::Term => nix::sys::signal::Signal::SIGTERM,
+            Stop::Kill => nix::sys::signal::Signal::SIGKILL,
+        };
+        let _ = nix::sys::signal::killpg(nix::unistd::Pid::from_raw(pid as i32), signal);
+    }
+    #[cfg(not(unix))]
+    {
+        let _ = stop;
+        let _ = child.start_kill();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    #[tokio::test]
+    async fn test_interactive_process() {
+        let ctx = PrimitiveContext::new(PathBuf::from("/tmp"));
+        let started = bash_start(&ctx, "echo ready; read name; echo \"hello $name\"; exit 3", None)
+            .await
+            .unwrap();
+        assert!(started.running);
+
+        let ready = bash_wait(&ctx, &started.id, Some("^ready"), Duration::from_secs(10)).await.unwrap();
+        assert_eq!(ready.matched, Some(true));
+        assert_eq!((ready.output.as_str(), ready.next_offset), ("ready\n", 6));
+
+        bash_write(&ctx, &started.id, "tachikoma\n", true).await.unwrap();
+        let done = bash_wait(&ctx, &started.id, None, Duration::from_secs(10)).await.unwrap();
+        assert_eq!((done.output.as_str(), done.offset), ("hello tachikoma\n", 6));
+        assert_eq!((done.running, done.exit_code), (false, Some(3)));
+
+        // Reads by offset go back over what was already seen
+        let again = bash_read(&ctx, &started.id, Some(0), None).await.unwrap();
+        assert_eq!(again.output, "ready\nhello tachikoma\n");
+    }
+
+    #[tokio::test]
+    async fn test_exit_with_children_holding_the_pipes() {
+        let ctx = PrimitiveContext::new(PathBuf::from("/tmp"));
+        let started = bash_start(&ctx, "sleep 5 & echo bye; exit 4", None).await.unwrap();
+
+        // The orphaned sleep keeps stdout open; the exit is reported anyway
+        let done = bash_wait(&ctx, &started.id, None, Duration::from_secs(4)).await.unwrap();
+        assert_eq!((done.running, done.exit_code), (false, Some(4)));
+        assert_eq!(done.output, "bye\n");
+        assert!(done.metadata.duration < Duration::from_secs(3));
+
+        let killed = bash_kill(&ctx, &started.id).await.unwrap();
+        assert_eq!(killed.exit_code, Some(4));
+    }
+
+    #[tokio::test]
+    async fn test_kill_and_session_cleanup() {
+        let ctx = PrimitiveContext::new(PathBuf::from("/tmp"));
+        let server = bash_start(&ctx, "sleep 60 & wait", None).await.unwrap();
+        let quiet = bash_wait(&ctx, &server.id, Some("never"), Duration::from_millis(100)).await.unwrap();
+        assert_eq!((quiet.matched, quiet.running), (Some(false), true));
+
+        let killed = bash_kill(&ctx, &server.id).await.unwrap();
+        assert!(!killed.running);
+        assert!(bash_read(&ctx, &server.id, None, None).await.is_err());
+
+        // Dropping the last context kills what's left, children included
+        let orphan = bash_start(&ctx, "sleep 60", None).await.unwrap();
+        let pid = nix::unistd::Pid::from_raw(orphan.pid.unwrap() as i32);
+        drop(ctx);
+        let mut alive = true;
+        for _ in 0..50 {
+            tokio::time::sleep(Duration::from_millis(20)).await;
+            // Reaped by the runtime once killed
+            if nix::sys::signal::kill(pid, None).is_err() {
+                alive = false;
+                break;
+            }
+        }
+        assert!(!alive);
+    }
+}
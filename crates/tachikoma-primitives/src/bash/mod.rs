@@ -1,16 +1,20 @@
 //! Bash command execution primitive.
 
+mod background;
 mod cancel;
 mod error;
 mod options;
 mod output;
+mod primitive;
 mod sanitize;
 mod timeout;
 
+pub use background::{bash_kill, bash_read, bash_start, bash_start_command, bash_wait, bash_write, ProcessTable, MAX_BUFFERED_BYTES};
 pub use cancel::*;
 pub use error::*;
 pub use options::*;
 pub use output::*;
+pub use primitive::{BashInput, BashPrimitive, ProcessInput, ProcessPrimitive};
 pub use sanitize::*;
 pub use timeout::*;
 
@@ -66,23 +70,10 @@ pub async fn bash(
     let start = Instant::now();
     let options = options.unwrap_or_default();
 
-    // Use timeout if specified
-    if let Some(timeout_duration) = options.timeout {
-        return bash_with_timeout_and_config(
-            ctx, 
-            command, 
-            timeout_duration, 
-            options.working_dir.as_deref(),
-            &options.output_config
-        ).await;
-    }
-
     // Validate command
     let validator = CommandValidator::new(&options.blocked_commands);
     validator.validate(command)?;
 
-    debug!("Executing command: {}", command);
-
     // Determine working directory
     let working_dir = options
         .working_dir
@@ -95,6 +86,13 @@ pub async fn bash(
         return Err(PrimitiveError::PathNotAllowed { path: working_dir });
     }
 
+    // Use timeout if specified
+    if let Some(timeout_duration) = options.timeout {
+        return bash_with_timeout_and_options(ctx, command, timeout_duration, &options).await;
+    }
+
+    debug!("Executing command: {}", command);
+
     // Build command
     let mut cmd = Command::new("bash");
     cmd.arg("-c")
@@ -225,7 +225,9 @@ pub enum OutputLine {
 
 /// Stream output line by line.
 pub struct OutputStreamer {
-    tx: mpsc::Sender<OutputLine>,
+    /// Dropped on the first `recv`, so the stream ends with the last sender handed out
+    tx: Option<mpsc::Sender<OutputLine>>,
+    weak: mpsc::WeakSender<OutputLine>,
     rx: mpsc::Receiver<OutputLine>,
 }
 
@@ -233,16 +235,26 @@ impl OutputStreamer {
     /// Create a new output streamer.
     pub fn new(buffer_size: usize) -> Self {
         let (tx, rx) = mpsc::channel(buffer_size);
-        Self { tx, rx }
+        Self {
+            weak: tx.downgrade(),
+            tx: Some(tx),
+            rx,
+        }
     }
 
     /// Get a sender for streaming.
+    ///
+    /// Once streaming has finished the sender is closed and sends fail.
     pub fn sender(&self) -> mpsc::Sender<OutputLine> {
-        self.tx.clone()
+        self.tx
+            .clone()
+            .or_else(|| self.weak.upgrade())
+            .unwrap_or_else(|| mpsc::channel(1).0)
     }
 
-    /// Receive the next line.
+    /// Receive the next line, or `None` once every sender is dropped.
     pub async fn recv(&mut self) -> Option<OutputLine> {
+        self.tx.take();
         self.rx.recv().await
     }
 
@@ -0,0 +1,178 @@
+//! `bash` and `process` as registry primitives.
+
+use async_trait::async_trait;
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::time::Duration;
+
+use super::{bash, bash_kill, bash_read, bash_start, bash_wait, bash_write, BashOptions};
+use crate::{
+    context::PrimitiveContext,
+    error::PrimitiveResult,
+    result::{BashResult, ProcessResult},
+    traits::Primitive,
+};
+
+/// How long `process` waits when no timeout is given.
+const DEFAULT_WAIT_SECS: u64 = 30;
+
+/// JSON input for `bash`.
+#[derive(Debug, Clone, Deserialize)]
+pub struct BashInput {
+    /// Command line, run with `bash -c`.
+    pub command: String,
+    /// Directory to run in, relative to the working directory.
+    #[serde(default)]
+    pub working_dir: Option<String>,
+    /// Kill the command after this many seconds.
+    #[serde(default)]
+    pub timeout_secs: Option<u64>,
+}
+
+/// The `bash` primitive.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct BashPrimitive;
+
+#[async_trait]
+impl Primitive for BashPrimitive {
+    type Input = BashInput;
+    type Output = BashResult;
+
+    fn name(&self) -> &'static str {
+        "bash"
+    }
+
+    fn description(&self) -> &'static str {
+        "Run a shell command and return its exit code, stdout and stderr."
+    }
+
+    async fn execute(&self, ctx: &PrimitiveContext, input: BashInput) -> PrimitiveResult<BashResult> {
+        let mut options = BashOptions::new();
+        if let Some(dir) = &input.working_dir {
+            options = options.working_dir(dir);
+        }
+        if let Some(secs) = input.timeout_secs {
+            options = options.timeout(Duration::from_secs(secs));
+        }
+        bash(ctx, &input.command, Some(options)).await
+    }
+
+    fn input_schema(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "command": {"type": "string", "description": "Command line, run with bash -c"},
+                "working_dir": {"type": "string", "description": "Directory to run in (default: the working directory)"},
+                "timeout_secs": {"type": "integer", "minimum": 1, "description": "Kill the command after this many seconds (default: 120)"}
+            },
+            "required": ["command"]
+        })
+    }
+}
+
+/// JSON input for `process`, one variant per action.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(tag = "action", rename_all = "snake_case")]
+pub enum ProcessInput {
+    /// Start a command in the background.
+    Start {
+        /// Command line, run with `bash -c`.
+        command: String,
+        /// Directory to run in, relative to the working directory.
+        #[serde(default)]
+        working_dir: Option<String>,
+    },
+    /// Read output without waiting.
+    Read {
+        /// Process id returned by `start`.
+        id: String,
+        /// Byte offset to read from (default: where the last read ended).
+        #[serde(default)]
+        offset: Option<usize>,
+        /// Most bytes to return.
+        #[serde(default)]
+        max_bytes: Option<usize>,
+    },
+    /// Write to stdin.
+    Write {
+        /// Process id returned by `start`.
+        id: String,
+        /// Text to write, newlines included.
+        input: String,
+        /// Close stdin afterwards.
+        #[serde(default)]
+        close: bool,
+    },
+    /// Wait for a pattern in the output, or for the process to exit.
+    Wait {
+        /// Process id returned by `start`.
+        id: String,
+        /// Regex to wait for (default: wait for exit).
+        #[serde(default)]
+        pattern: Option<String>,
+        /// Give up after this many seconds.
+        #[serde(default)]
+        timeout_secs: Option<u64>,
+    },
+    /// Kill the process and its children.
+    Kill {
+        /// Process id returned by `start`.
+        id: String,
+    },
+}
+
+/// The `process` primitive.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ProcessPrimitive;
+
+#[async_trait]
+impl Primitive for ProcessPrimitive {
+    type Input = ProcessInput;
+    type Output = ProcessResult;
+
+    fn name(&self) -> &'static str {
+        "process"
+    }
+
+    fn description(&self) -> &'static str {
+        "Run a long-lived command such as a dev server in the background: start it, read its output, write to its stdin, wait for a line like 'listening on', and kill it."
+    }
+
+    async fn execute(&self, ctx: &PrimitiveContext, input: ProcessInput) -> PrimitiveResult<ProcessResult> {
+        match input {
+            ProcessInput::Start { command, working_dir } => {
+                let mut options = BashOptions::new();
+                if let Some(dir) = &working_dir {
+                    options = options.working_dir(dir);
+                }
+                bash_start(ctx, &command, Some(options)).await
+            }
+            ProcessInput::Read { id, offset, max_bytes } => bash_read(ctx, &id, offset, max_bytes).await,
+            ProcessInput::Write { id, input, close } => bash_write(ctx, &id, &input, close).await,
+            ProcessInput::Wait { id, pattern, timeout_secs } => {
+                let timeout = Duration::from_secs(timeout_secs.unwrap_or(DEFAULT_WAIT_SECS));
+                bash_wait(ctx, &id, pattern.as_deref(), timeout).await
+            }
+            ProcessInput::Kill { id } => bash_kill(ctx, &id).await,
+        }
+    }
+
+    fn input_schema(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "action": {"type": "string", "enum": ["start", "read", "write", "wait", "kill"]},
+                "command": {"type": "string", "description": "start: command line, run with bash -c"},
+                "working_dir": {"type": "string", "description": "start: directory to run in (default: the working directory)"},
+                "id": {"type": "string", "description": "Process id returned by start"},
+                "offset": {"type": "integer", "minimum": 0, "description": "read: byte offset to read from (default: where the last read ended)"},
+                "max_bytes": {"type": "integer", "minimum": 1, "description": "read: most bytes to return (default: 65536)"},
+                "input": {"type": "string", "description": "write: text for stdin, newlines included"},
+                "close": {"type": "boolean", "description": "write: close stdin afterwards"},
+                "pattern": {"type": "string", "description": "wait: regex to wait for in new output (default: wait for exit)"},
+                "timeout_secs": {"type": "integer", "minimum": 1, "description": "wait: give up after this many seconds (default: 30)"}
+            },
+            "required": ["action"]
+        })
+    }
+}
@@ -5,6 +5,7 @@ use crate::{
     error::{PrimitiveError, PrimitiveResult},
     result::{BashResult, ExecutionMetadata},
 };
+use super::options::BashOptions;
 use super::output::{OutputConfig, capture_output};
 use std::process::Stdio;
 use std::time::{Duration, Instant};
@@ -40,10 +41,28 @@ pub async fn bash_with_timeout_and_config(
     timeout_duration: Duration,
     working_dir: Option<&str>,
     output_config: &OutputConfig,
+) -> PrimitiveResult<BashResult> {
+    let options = BashOptions {
+        working_dir: working_dir.map(str::to_string),
+        output_config: output_config.clone(),
+        ..BashOptions::default()
+    };
+    bash_with_timeout_and_options(ctx, command, timeout_duration, &options).await
+}
+
+/// Execute a bash command with timeout, honouring the working directory,
+/// environment and output settings of `options`.
+pub(crate) async fn bash_with_timeout_and_options(
+    ctx: &PrimitiveContext,
+    command: &str,
+    timeout_duration: Duration,
+    options: &BashOptions,
 ) -> PrimitiveResult<BashResult> {
     let start = Instant::now();
 
-    let working_dir = working_dir
+    let working_dir = options
+        .working_dir
+        .as_deref()
         .map(|p| ctx.resolve_path(p))
         .unwrap_or_else(|| ctx.working_dir.clone());
 
@@ -62,6 +81,13 @@ pub async fn bash_with_timeout_and_config(
         .stderr(Stdio::piped())
         .kill_on_drop(true);
 
+    if options.clear_env {
+        cmd.env_clear();
+    }
+    for (key, value) in &options.env_vars {
+        cmd.env(key, value);
+    }
+
     // On Unix, create new process group for proper cleanup
     #[cfg(unix)]
     {
@@ -78,7 +104,7 @@ pub async fn bash_with_timeout_and_config(
     let pid = child.id();
 
     // Execute with timeout
-    let result = timeout(timeout_duration, execute_and_capture_with_config(&mut child, output_config)).await;
+    let result = timeout(timeout_duration, execute_and_capture_with_config(&mut child, &options.output_config)).await;
 
     match result {
         Ok(Ok((exit_code, captured))) => {
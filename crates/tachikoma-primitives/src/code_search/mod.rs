@@ -3,10 +3,12 @@
 mod format;
 mod options;
 mod parser;
+mod primitive;
 
 pub use format::{FormatConfig, OutputFormat, format_results, format_summary};
 pub use options::CodeSearchOptions;
 pub use parser::{RipgrepMatch, RipgrepOutput};
+pub use primitive::{CodeSearchInput, CodeSearchPrimitive};
 
 use crate::{
     context::PrimitiveContext,
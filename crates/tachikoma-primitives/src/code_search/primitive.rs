@@ -0,0 +1,88 @@
+//! `code_search` as a registry primitive.
+
+use async_trait::async_trait;
+use serde::Deserialize;
+use serde_json::{json, Value};
+
+use super::{code_search, CodeSearchOptions};
+use crate::{context::PrimitiveContext, error::PrimitiveResult, result::CodeSearchResult, traits::Primitive};
+
+/// JSON input for `code_search`.
+#[derive(Debug, Clone, Deserialize)]
+pub struct CodeSearchInput {
+    /// Regex to search for.
+    pub pattern: String,
+    /// Directory or file to search, relative to the working directory.
+    #[serde(default = "current_dir")]
+    pub path: String,
+    /// Ripgrep file type, e.g. `rust`.
+    #[serde(default)]
+    pub file_type: Option<String>,
+    /// Only files matching this glob.
+    #[serde(default)]
+    pub glob: Option<String>,
+    /// Lines of context around each match.
+    #[serde(default)]
+    pub context: usize,
+    /// Ignore case.
+    #[serde(default)]
+    pub case_insensitive: bool,
+    /// Maximum number of matches.
+    #[serde(default)]
+    pub max_matches: Option<usize>,
+}
+
+fn current_dir() -> String {
+    ".".to_string()
+}
+
+/// The `code_search` primitive.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct CodeSearchPrimitive;
+
+#[async_trait]
+impl Primitive for CodeSearchPrimitive {
+    type Input = CodeSearchInput;
+    type Output = CodeSearchResult;
+
+    fn name(&self) -> &'static str {
+        "code_search"
+    }
+
+    fn description(&self) -> &'static str {
+        "Search file contents for a regex with ripgrep."
+    }
+
+    async fn execute(&self, ctx: &PrimitiveContext, input: CodeSearchInput) -> PrimitiveResult<CodeSearchResult> {
+        let mut options = CodeSearchOptions::new().context(input.context);
+        if let Some(file_type) = &input.file_type {
+            options = options.file_type(file_type);
+        }
+        if let Some(glob) = &input.glob {
+            options = options.glob(glob);
+        }
+        if input.case_insensitive {
+            options = options.case_insensitive();
+        }
+        if let Some(max) = input.max_matches {
+            options = options.max_matches(max);
+        }
+        code_search(ctx, &input.pattern, &input.path, Some(options)).await
+    }
+
+    fn input_schema(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "pattern": {"type": "string", "description": "Regex to search for"},
+                "path": {"type": "string", "description": "Directory or file to search (default: the working directory)"},
+                "file_type": {"type": "string", "description": "Ripgrep file type, e.g. \"rust\""},
+                "glob": {"type": "string", "description": "Only files matching this glob"},
+                "context": {"type": "integer", "minimum": 0, "description": "Lines of context around each match"},
+                "case_insensitive": {"type": "boolean", "description": "Ignore case"},
+                "max_matches": {"type": "integer", "minimum": 1, "description": "Maximum number of matches"}
+            },
+            "required": ["pattern"]
+        })
+    }
+}
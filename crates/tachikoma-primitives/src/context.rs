@@ -1,6 +1,6 @@
 //! Execution context for primitives.
 
-use std::path::PathBuf;
+use std::path::{Component, PathBuf};
 use std::time::Duration;
 
 /// Configuration for primitive execution.
@@ -45,6 +45,9 @@ pub struct PrimitiveContext {
     pub config: PrimitiveConfig,
     /// Unique operation ID for logging.
     pub operation_id: String,
+    /// Background processes, shared by clones and killed with the last one.
+    #[cfg(feature = "bash")]
+    pub processes: crate::bash::ProcessTable,
 }
 
 impl PrimitiveContext {
@@ -54,6 +57,8 @@ impl PrimitiveContext {
             working_dir,
             config: PrimitiveConfig::default(),
             operation_id: uuid::Uuid::new_v4().to_string(),
+            #[cfg(feature = "bash")]
+            processes: Default::default(),
         }
     }
 
@@ -63,17 +68,34 @@ impl PrimitiveContext {
             working_dir,
             config,
             operation_id: uuid::Uuid::new_v4().to_string(),
+            #[cfg(feature = "bash")]
+            processes: Default::default(),
         }
     }
 
     /// Resolve a path relative to working directory.
+    ///
+    /// `.` and `..` components are folded away, so a path like
+    /// `src/../../etc` can't slip past the allowed and denied path checks.
     pub fn resolve_path(&self, path: &str) -> PathBuf {
         let path = PathBuf::from(path);
-        if path.is_absolute() {
+        let joined = if path.is_absolute() {
             path
         } else {
             self.working_dir.join(path)
+        };
+
+        let mut resolved = PathBuf::new();
+        for component in joined.components() {
+            match component {
+                Component::CurDir => {}
+                Component::ParentDir => {
+                    resolved.pop();
+                }
+                other => resolved.push(other),
+            }
         }
+        resolved
     }
 
     /// Check if a path is allowed.
@@ -3,9 +3,13 @@
 mod options;
 mod diff;
 mod unique;
+mod multi;
+mod primitive;
 pub mod atomic;
 
 pub use options::EditFileOptions;
+pub use multi::{apply_patch, multi_edit, FileEdit};
+pub use primitive::{EditFileInput, EditFilePrimitive, MultiEditInput, MultiEditPrimitive};
 pub use diff::Diff;
 pub use unique::{
     UniquenessResult, MatchLocation, MatchSelection, EditValidationError,
@@ -186,6 +190,7 @@ pub async fn edit_file(
     };
 
     let replacements = if options.replace_all { count } else { 1 };
+    let diff = diff::create_diff(&content, &new_content).to_string();
 
     // Dry run - don't write
     if options.dry_run {
@@ -194,6 +199,7 @@ pub async fn edit_file(
             success: true,
             replacements,
             path: resolved_path,
+            diff,
             metadata: ExecutionMetadata {
                 duration,
                 operation_id: ctx.operation_id.clone(),
@@ -248,6 +254,7 @@ pub async fn edit_file(
         success: true,
         replacements,
         path: resolved_path,
+        diff,
         metadata: ExecutionMetadata {
             duration,
             operation_id: ctx.operation_id.clone(),
@@ -489,6 +496,7 @@ mod tests {
 
         assert!(result.success);
         assert_eq!(result.replacements, 1);
+        assert_eq!(result.diff, "@@ -1,1 +1,1 @@\n-Hello, World!\n+Hello, Rust!\n");
 
         // File should be unchanged
         let content = fs::read_to_string(&file_path).unwrap();
@@ -0,0 +1,506 @@
+//! Edits across several files, applied all or nothing.
+//!
+//! Every edit (or every hunk of a unified diff) is checked against the
+//! files in memory before anything is written. Only then are the files
+//! written, each atomically; if a write fails, the files already written
+//! are put back.
+
+use serde::Deserialize;
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+use std::time::Instant;
+use tracing::{debug, instrument, warn};
+
+use super::{atomic::write_atomic, diff::create_diff, unique};
+use crate::{
+    context::PrimitiveContext,
+    error::{PrimitiveError, PrimitiveResult},
+    result::{ChangeKind, ExecutionMetadata, FileChange, MultiEditResult},
+};
+
+/// One search-and-replace edit.
+#[derive(Debug, Clone, Deserialize)]
+pub struct FileEdit {
+    /// File to edit, relative to the working directory.
+    pub path: String,
+    /// Text to replace; empty to create the file.
+    pub old_string: String,
+    /// Replacement text.
+    pub new_string: String,
+    /// Replace every occurrence instead of requiring a unique match.
+    #[serde(default)]
+    pub replace_all: bool,
+}
+
+/// A file as the edits leave it.
+struct Planned {
+    path: PathBuf,
+    /// Contents on disk, `None` if the file doesn't exist yet.
+    original: Option<Vec<u8>>,
+    /// Contents to write, `None` to delete the file.
+    content: Option<String>,
+    replacements: usize,
+}
+
+/// Apply search-and-replace edits to one or more files.
+///
+/// Edits to the same file apply in order, each to the result of the one
+/// before. An edit with an empty `old_string` creates its file.
+#[instrument(skip(ctx, edits), fields(edits = edits.len(), op_id = %ctx.operation_id))]
+pub async fn multi_edit(ctx: &PrimitiveContext, edits: &[FileEdit], dry_run: bool) -> PrimitiveResult<MultiEditResult> {
+    let start = Instant::now();
+    if edits.is_empty() {
+        return Err(PrimitiveError::Validation {
+            message: "no edits given".to_string(),
+        });
+    }
+
+    let mut plan: Vec<Planned> = Vec::new();
+    for (index, edit) in edits.iter().enumerate() {
+        let fail = |source| PrimitiveError::EditFailed {
+            edit: index + 1,
+            path: ctx.resolve_path(&edit.path),
+            source: Box::new(source),
+        };
+        let file = planned(ctx, &mut plan, &edit.path, edit.old_string.is_empty()).map_err(fail)?;
+        apply_edit(file, edit).map_err(fail)?;
+    }
+
+    finish(ctx, plan, dry_run, start)
+}
+
+/// Apply a unified diff (`git diff` or `diff -u` output).
+///
+/// Hunks are located by their context, preferring the line numbers in the
+/// hunk header when the same lines occur more than once. `/dev/null` as the
+/// old or new file creates or deletes a file.
+#[instrument(skip(ctx, patch), fields(op_id = %ctx.operation_id))]
+pub async fn apply_patch(ctx: &PrimitiveContext, patch: &str, dry_run: bool) -> PrimitiveResult<MultiEditResult> {
+    let start = Instant::now();
+    let files = parse_patch(patch)?;
+
+    let mut plan: Vec<Planned> = Vec::new();
+    let mut number = 0;
+    for file in &files {
+        let path = match (&file.old_path, &file.new_path) {
+            (Some(old), Some(new)) if old != new => {
+                return Err(PrimitiveError::Validation {
+                    message: format!("renaming {} to {} is not supported", old, new),
+                });
+            }
+            (_, Some(path)) | (Some(path), None) => path,
+            (None, None) => {
+                return Err(PrimitiveError::Validation {
+                    message: "a file patch names neither an old nor a new file".to_string(),
+                });
+            }
+        };
+
+        // Hunk line numbers refer to the original file
+        let mut shift: isize = 0;
+        for hunk in &file.hunks {
+            number += 1;
+            let fail = |source| PrimitiveError::EditFailed {
+                edit: number,
+                path: ctx.resolve_path(path),
+                source: Box::new(source),
+            };
+            let planned = planned(ctx, &mut plan, path, file.old_path.is_none()).map_err(fail)?;
+            shift += apply_hunk(planned, hunk, shift).map_err(fail)?;
+        }
+
+        let planned = planned(ctx, &mut plan, path, file.old_path.is_none())?;
+        if file.new_path.is_none() {
+            planned.content = None;
+        } else if let (Some(content), Some(newline)) = (&mut planned.content, file.eof_newline) {
+            let has = content.ends_with('\n');
+            if has && !newline {
+                content.pop();
+            } else if !has && newline && !content.is_empty() {
+                content.push('\n');
+            }
+        }
+    }
+
+    finish(ctx, plan, dry_run, start)
+}
+
+/// The planned state of `path`, read from disk the first time it's touched.
+fn planned<'a>(
+    ctx: &PrimitiveContext,
+    plan: &'a mut Vec<Planned>,
+    path: &str,
+    may_create: bool,
+) -> PrimitiveResult<&'a mut Planned> {
+    let resolved = ctx.resolve_path(path);
+    if !ctx.is_path_allowed(&resolved) {
+        return Err(PrimitiveError::PathNotAllowed { path: resolved });
+    }
+
+    if let Some(index) = plan.iter().position(|p| p.path == resolved) {
+        return Ok(&mut plan[index]);
+    }
+
+    let original = match fs::read(&resolved) {
+        Ok(bytes) => Some(bytes),
+        Err(e) if e.kind() == io::ErrorKind::NotFound && may_create => None,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => {
+            return Err(PrimitiveError::FileNotFound { path: resolved });
+        }
+        Err(e) if e.kind() == io::ErrorKind::PermissionDenied => {
+            return Err(PrimitiveError::PermissionDenied { path: resolved });
+        }
+        Err(e) => return Err(PrimitiveError::Io(e)),
+    };
+    let content = Some(original.as_deref().map(String::from_utf8_lossy).unwrap_or_default().into_owned());
+
+    plan.push(Planned {
+        path: resolved,
+        original,
+        content,
+        replacements: 0,
+    });
+    Ok(plan.last_mut().expect("just pushed"))
+}
+
+fn apply_edit(file: &mut Planned, edit: &FileEdit) -> PrimitiveResult<()> {
+    let Some(content) = &mut file.content else {
+        return Err(PrimitiveError::FileNotFound { path: file.path.clone() });
+    };
+
+    if edit.old_string.is_empty() {
+        if file.original.is_some() || !content.is_empty() {
+            return Err(PrimitiveError::Validation {
+                message: "old_string cannot be empty for an existing file".to_string(),
+            });
+        }
+        *content = edit.new_string.clone();
+        file.replacements += 1;
+        return Ok(());
+    }
+    if edit.old_string == edit.new_string {
+        return Err(PrimitiveError::Validation {
+            message: "old_string and new_string are identical".to_string(),
+        });
+    }
+
+    let count = content.matches(edit.old_string.as_str()).count();
+    if count == 0 {
+        return Err(PrimitiveError::TargetNotFound);
+    }
+    if count > 1 && !edit.replace_all {
+        let uniqueness = unique::check_uniqueness(content, &edit.old_string, 3);
+        return Err(PrimitiveError::NotUnique {
+            count,
+            details: unique::format_matches(&uniqueness),
+        });
+    }
+
+    *content = if edit.replace_all {
+        content.replace(edit.old_string.as_str(), &edit.new_string)
+    } else {
+        content.replacen(edit.old_string.as_str(), &edit.new_string, 1)
+    };
+    file.replacements += if edit.replace_all { count } else { 1 };
+    Ok(())
+}
+
+/// Apply one hunk, returning how many lines it added (or removed, if negative).
+fn apply_hunk(file: &mut Planned, hunk: &Hunk, shift: isize) -> PrimitiveResult<isize> {
+    let Some(content) = &mut file.content else {
+        return Err(PrimitiveError::FileNotFound { path: file.path.clone() });
+    };
+
+    let trailing_newline = content.is_empty() || content.ends_with('\n');
+    let mut lines: Vec<&str> = content.lines().collect();
+    let expected = (hunk.old_start.saturating_sub(1) as isize + shift).max(0) as usize;
+
+    let at = if hunk.old_lines.is_empty() {
+        // Pure insertion after line `old_start`
+        (hunk.old_start as isize + shift).clamp(0, lines.len() as isize) as usize
+    } else {
+        let candidates: Vec<usize> = (0..=lines.len().saturating_sub(hunk.old_lines.len()))
+            .filter(|&i| {
+                lines.len() >= hunk.old_lines.len()
+                    && lines[i..i + hunk.old_lines.len()].iter().zip(&hunk.old_lines).all(|(a, b)| a == b)
+            })
+            .collect();
+        match candidates.as_slice() {
+            [] => return Err(PrimitiveError::TargetNotFound),
+            [only] => *only,
+            many if many.contains(&expected) => expected,
+            many => {
+                let uniqueness = unique::check_uniqueness(content, &hunk.old_lines.join("\n"), 3);
+                return Err(PrimitiveError::NotUnique {
+                    count: many.len(),
+                    details: unique::format_matches(&uniqueness),
+                });
+            }
+        }
+    };
+
+    lines.splice(at..at + hunk.old_lines.len(), hunk.new_lines.iter().map(String::as_str));
+    let mut updated = lines.join("\n");
+    if trailing_newline && !updated.is_empty() {
+        updated.push('\n');
+    }
+    *content = updated;
+    file.replacements += 1;
+
+    Ok(hunk.new_lines.len() as isize - hunk.old_lines.len() as isize)
+}
+
+/// Write the plan (unless it's a dry run) and describe it.
+fn finish(ctx: &PrimitiveContext, plan: Vec<Planned>, dry_run: bool, start: Instant) -> PrimitiveResult<MultiEditResult> {
+    let mut diff = String::new();
+    let mut files = Vec::new();
+    for file in &plan {
+        let before = file.original.as_deref().map(String::from_utf8_lossy).unwrap_or_default();
+        let after = file.content.as_deref().unwrap_or_default();
+        let name = file.path.strip_prefix(&ctx.working_dir).unwrap_or(&file.path).display().to_string();
+        let kind = match (&file.original, &file.content) {
+            (None, _) => ChangeKind::Created,
+            (Some(_), None) => ChangeKind::Deleted,
+            (Some(_), Some(_)) => ChangeKind::Modified,
+        };
+
+        let old_name = if kind == ChangeKind::Created { "/dev/null".to_string() } else { format!("a/{}", name) };
+        let new_name = if kind == ChangeKind::Deleted { "/dev/null".to_string() } else { format!("b/{}", name) };
+        diff.push_str(&format!("--- {}\n+++ {}\n{}", old_name, new_name, create_diff(&before, after)));
+
+        files.push(FileChange {
+            path: file.path.clone(),
+            kind,
+            replacements: file.replacements,
+        });
+    }
+
+    if !dry_run {
+        commit(&plan)?;
+    }
+
+    let duration = start.elapsed();
+    debug!("Applied edits to {} file(s) in {:?}", files.len(), duration);
+    Ok(MultiEditResult {
+        files,
+        diff,
+        dry_run,
+        metadata: ExecutionMetadata {
+            duration,
+            operation_id: ctx.operation_id.clone(),
+            primitive: "multi_edit".to_string(),
+        },
+    })
+}
+
+/// Write every file, restoring the ones already written if one fails.
+fn commit(plan: &[Planned]) -> PrimitiveResult<()> {
+    for (written, file) in plan.iter().enumerate() {
+        let result = match &file.content {
+            Some(content) => file
+                .path
+                .parent()
+                .map_or(Ok(()), fs::create_dir_all)
+                .and_then(|_| write_atomic(&file.path, content.as_bytes())),
+            None => fs::remove_file(&file.path),
+        };
+
+        if let Err(e) = result {
+            for file in plan[..written].iter().rev() {
+                let restored = match &file.original {
+                    Some(original) => write_atomic(&file.path, original),
+                    None => fs::remove_file(&file.path),
+                };
+                if let Err(restore_error) = restored {
+                    warn!("Could not restore {:?}: {}", file.path, restore_error);
+                }
+            }
+            return Err(PrimitiveError::EditFailed {
+                edit: written + 1,
+                path: file.path.clone(),
+                source: Box::new(PrimitiveError::Io(e)),
+            });
+        }
+    }
+    Ok(())
+}
+
+/// The changes to one file in a unified diff.
+#[derive(Debug, Default)]
+struct FilePatch {
+    /// `None` for `/dev/null`.
+    old_path: Option<String>,
+    new_path: Option<String>,
+    hunks: Vec<Hunk>,
+    /// Whether the new file ends with a newline, if the patch says.
+    eof_newline: Option<bool>,
+}
+
+#[derive(Debug)]
+struct Hunk {
+    old_start: usize,
+    old_lines: Vec<String>,
+    new_lines: Vec<String>,
+}
+
+fn parse_patch(patch: &str) -> PrimitiveResult<Vec<FilePatch>> {
+    let invalid = |message: String| PrimitiveError::Validation { message };
+    let mut files: Vec<FilePatch> = Vec::new();
+    let mut lines = patch.lines().peekable();
+    let mut last_side = ' ';
+
+    while let Some(line) = lines.next() {
+        if let Some(old) = line.strip_prefix("--- ") {
+            let Some(new) = lines.next().and_then(|l| l.strip_prefix("+++ ")) else {
+                return Err(invalid(format!("expected a +++ line after `{}`", line)));
+            };
+            files.push(FilePatch {
+                old_path: patch_path(old),
+                new_path: patch_path(new),
+                ..FilePatch::default()
+            });
+        } else if let Some(header) = line.strip_prefix("@@ ") {
+            let file = files
+                .last_mut()
+                .ok_or_else(|| invalid("hunk before any --- / +++ file header".to_string()))?;
+            let (old_start, old_count, new_count) =
+                parse_hunk_header(header).ok_or_else(|| invalid(format!("malformed hunk header `{}`", line)))?;
+
+            let mut hunk = Hunk {
+                old_start,
+                old_lines: Vec::new(),
+                new_lines: Vec::new(),
+            };
+            while hunk.old_lines.len() < old_count || hunk.new_lines.len() < new_count {
+                let Some(body) = lines.next() else {
+                    return Err(invalid(format!("hunk `{}` ends early", line)));
+                };
+                // Some editors strip the space from empty context lines
+                let (side, text) = match body.chars().next() {
+                    Some(side @ (' ' | '-' | '+')) => (side, &body[1..]),
+                    None => (' ', ""),
+                    Some('\\') => {
+                        file.eof_newline = Some(last_side == '-');
+                        continue;
+                    }
+                    Some(_) => return Err(invalid(format!("unexpected line in hunk `{}`: `{}`", line, body))),
+                };
+                if side != '+' {
+                    hunk.old_lines.push(text.to_string());
+                }
+                if side != '-' {
+                    hunk.new_lines.push(text.to_string());
+                }
+                last_side = side;
+            }
+            file.hunks.push(hunk);
+        } else if line.starts_with("\\ No newline") {
+            if let Some(file) = files.last_mut() {
+                file.eof_newline = Some(last_side == '-');
+            }
+        }
+    }
+
+    if files.is_empty() {
+        return Err(invalid("no file changes in patch".to_string()));
+    }
+    Ok(files)
+}
+
+/// `a/src/lib.rs\t2024-01-01 ...` -> `src/lib.rs`; `/dev/null` -> `None`.
+fn patch_path(header: &str) -> Option<String> {
+    let path = header.split('\t').next().unwrap_or(header).trim();
+    if path == "/dev/null" {
+        return None;
+    }
+    let path = path.strip_prefix("a/").or_else(|| path.strip_prefix("b/")).unwrap_or(path);
+    Some(path.to_string())
+}
+
+/// `-12,3 +12,4 @@ fn main()` -> (12, 3, 4)
+fn parse_hunk_header(header: &str) -> Option<(usize, usize, usize)> {
+    let mut parts = header.split_whitespace();
+    let range = |part: Option<&str>, sign: char| -> Option<(usize, usize)> {
+        let part = part?.strip_prefix(sign)?;
+        match part.split_once(',') {
+            Some((start, count)) => Some((start.parse().ok()?, count.parse().ok()?)),
+            None => Some((part.parse().ok()?, 1)),
+        }
+    };
+    let (old_start, old_count) = range(parts.next(), '-')?;
+    let (_, new_count) = range(parts.next(), '+')?;
+    Some((old_start, old_count, new_count))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    fn edit(path: &str, old: &str, new: &str) -> FileEdit {
+        FileEdit {
+            path: path.to_string(),
+            old_string: old.to_string(),
+            new_string: new.to_string(),
+            replace_all: false,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_multi_edit_is_all_or_nothing() {
+        let dir = tempdir().unwrap();
+        fs::write(dir.path().join("a.rs"), "fn a() {}\nfn b() {}\n").unwrap();
+        fs::write(dir.path().join("b.rs"), "use a;\nuse a;\n").unwrap();
+        let ctx = PrimitiveContext::new(dir.path().to_path_buf());
+
+        // The second file's edit is ambiguous, so neither file changes
+        let edits = [edit("a.rs", "fn a", "fn alpha"), edit("b.rs", "use a;", "use alpha;")];
+        let err = multi_edit(&ctx, &edits, false).await.unwrap_err();
+        assert!(matches!(&err, PrimitiveError::EditFailed { edit: 2, source, .. } if matches!(**source, PrimitiveError::NotUnique { count: 2, .. })));
+        assert_eq!(fs::read_to_string(dir.path().join("a.rs")).unwrap(), "fn a() {}\nfn b() {}\n");
+
+        let edits = [
+            edit("a.rs", "fn a", "fn alpha"),
+            edit("a.rs", "fn alpha() {}", "fn alpha() { b() }"),
+            edit("new/c.rs", "", "mod a;\n"),
+        ];
+        let result = multi_edit(&ctx, &edits, false).await.unwrap();
+        assert_eq!(result.files.len(), 2);
+        assert_eq!(result.files[0].replacements, 2);
+        assert_eq!(result.files[1].kind, ChangeKind::Created);
+        assert!(result.diff.starts_with("--- a/a.rs\n+++ b/a.rs\n"));
+        assert!(result.diff.contains("--- /dev/null\n+++ b/new/c.rs\n"));
+        assert_eq!(fs::read_to_string(dir.path().join("a.rs")).unwrap(), "fn alpha() { b() }\nfn b() {}\n");
+        assert_eq!(fs::read_to_string(dir.path().join("new/c.rs")).unwrap(), "mod a;\n");
+    }
+
+    #[tokio::test]
+    async fn test_apply_patch() {
+        let dir = tempdir().unwrap();
+        fs::write(dir.path().join("lib.rs"), "one\ntwo\nthree\nfour\nfive\n").unwrap();
+        fs::write(dir.path().join("old.rs"), "gone\n").unwrap();
+        let ctx = PrimitiveContext::new(dir.path().to_path_buf());
+
+        let patch = "diff --git a/lib.rs b/lib.rs\n--- a/lib.rs\n+++ b/lib.rs\n@@ -1,2 +1,3 @@\n one\n+one and a half\n two\n@@ -4,2 +5,2 @@\n four\n-five\n+FIVE\n--- a/old.rs\n+++ /dev/null\n@@ -1 +0,0 @@\n-gone\n--- /dev/null\n+++ b/new.rs\n@@ -0,0 +1 @@\n+fresh\n\\ No newline at end of file\n";
+
+        let preview = apply_patch(&ctx, patch, true).await.unwrap();
+        assert!(preview.dry_run);
+        assert!(dir.path().join("old.rs").exists());
+
+        let result = apply_patch(&ctx, patch, false).await.unwrap();
+        let kinds: Vec<ChangeKind> = result.files.iter().map(|f| f.kind).collect();
+        assert_eq!(kinds, [ChangeKind::Modified, ChangeKind::Deleted, ChangeKind::Created]);
+        assert_eq!(
+            fs::read_to_string(dir.path().join("lib.rs")).unwrap(),
+            "one\none and a half\ntwo\nthree\nfour\nFIVE\n"
+        );
+        assert!(!dir.path().join("old.rs").exists());
+        assert_eq!(fs::read_to_string(dir.path().join("new.rs")).unwrap(), "fresh");
+
+        // Context that's no longer there fails the whole patch
+        let stale = "--- a/lib.rs\n+++ b/lib.rs\n@@ -1,1 +1,1 @@\n-zero\n+ZERO\n";
+        let err = apply_patch(&ctx, stale, false).await.unwrap_err();
+        assert!(matches!(&err, PrimitiveError::EditFailed { edit: 1, source, .. } if matches!(**source, PrimitiveError::TargetNotFound)));
+    }
+}
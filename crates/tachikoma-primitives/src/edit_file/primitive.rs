@@ -0,0 +1,137 @@
+//! `edit_file` as a registry primitive.
+
+use async_trait::async_trait;
+use serde::Deserialize;
+use serde_json::{json, Value};
+
+use super::{apply_patch, edit_file, multi_edit, EditFileOptions, FileEdit};
+use crate::{
+    context::PrimitiveContext,
+    error::{PrimitiveError, PrimitiveResult},
+    result::{EditFileResult, MultiEditResult},
+    traits::Primitive,
+};
+
+/// JSON input for `edit_file`.
+#[derive(Debug, Clone, Deserialize)]
+pub struct EditFileInput {
+    /// File to edit, relative to the working directory.
+    pub path: String,
+    /// Text to replace; must match exactly once unless `replace_all` is set.
+    pub old_string: String,
+    /// Replacement text.
+    pub new_string: String,
+    /// Replace every occurrence.
+    #[serde(default)]
+    pub replace_all: bool,
+    /// Report the change without writing it.
+    #[serde(default)]
+    pub dry_run: bool,
+}
+
+/// The `edit_file` primitive.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct EditFilePrimitive;
+
+#[async_trait]
+impl Primitive for EditFilePrimitive {
+    type Input = EditFileInput;
+    type Output = EditFileResult;
+
+    fn name(&self) -> &'static str {
+        "edit_file"
+    }
+
+    fn description(&self) -> &'static str {
+        "Replace an exact, unique string in a file with new text."
+    }
+
+    async fn execute(&self, ctx: &PrimitiveContext, input: EditFileInput) -> PrimitiveResult<EditFileResult> {
+        let options = EditFileOptions {
+            replace_all: input.replace_all,
+            dry_run: input.dry_run,
+            ..EditFileOptions::default()
+        };
+        edit_file(ctx, &input.path, &input.old_string, &input.new_string, Some(options)).await
+    }
+
+    fn input_schema(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "path": {"type": "string", "description": "File to edit, relative to the working directory"},
+                "old_string": {"type": "string", "description": "Exact text to replace; must occur once unless replace_all is set"},
+                "new_string": {"type": "string", "description": "Replacement text"},
+                "replace_all": {"type": "boolean", "description": "Replace every occurrence"},
+                "dry_run": {"type": "boolean", "description": "Report the change without writing it"}
+            },
+            "required": ["path", "old_string", "new_string"]
+        })
+    }
+}
+
+/// JSON input for `multi_edit`: either `edits` or `patch`.
+#[derive(Debug, Clone, Deserialize)]
+pub struct MultiEditInput {
+    /// Search-and-replace edits, applied in order.
+    #[serde(default)]
+    pub edits: Vec<FileEdit>,
+    /// A unified diff.
+    #[serde(default)]
+    pub patch: Option<String>,
+    /// Report the changes without writing them.
+    #[serde(default)]
+    pub dry_run: bool,
+}
+
+/// The `multi_edit` primitive.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct MultiEditPrimitive;
+
+#[async_trait]
+impl Primitive for MultiEditPrimitive {
+    type Input = MultiEditInput;
+    type Output = MultiEditResult;
+
+    fn name(&self) -> &'static str {
+        "multi_edit"
+    }
+
+    fn description(&self) -> &'static str {
+        "Apply several edits across files, or a unified diff, all or nothing."
+    }
+
+    async fn execute(&self, ctx: &PrimitiveContext, input: MultiEditInput) -> PrimitiveResult<MultiEditResult> {
+        match (input.patch, input.edits.is_empty()) {
+            (Some(patch), true) => apply_patch(ctx, &patch, input.dry_run).await,
+            (None, false) => multi_edit(ctx, &input.edits, input.dry_run).await,
+            _ => Err(PrimitiveError::Validation {
+                message: "give either edits or patch".to_string(),
+            }),
+        }
+    }
+
+    fn input_schema(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "edits": {
+                    "type": "array",
+                    "description": "Edits applied in order; later edits to a file see the earlier ones",
+                    "items": {
+                        "type": "object",
+                        "properties": {
+                            "path": {"type": "string", "description": "File to edit, relative to the working directory"},
+                            "old_string": {"type": "string", "description": "Exact text to replace; must occur once unless replace_all is set. Empty to create the file"},
+                            "new_string": {"type": "string", "description": "Replacement text"},
+                            "replace_all": {"type": "boolean", "description": "Replace every occurrence"}
+                        },
+                        "required": ["path", "old_string", "new_string"]
+                    }
+                },
+                "patch": {"type": "string", "description": "A unified diff, as from git diff; instead of edits"},
+                "dry_run": {"type": "boolean", "description": "Report the changes without writing them"}
+            }
+        })
+    }
+}
@@ -72,6 +72,17 @@ pub enum PrimitiveError {
     #[error("edit target not found in file")]
     TargetNotFound,
 
+    /// One edit of a multi-file edit or patch failed; nothing was changed.
+    #[error("edit {edit} ({path}) failed: {source}")]
+    EditFailed {
+        /// Number of the failing edit or hunk, from 1.
+        edit: usize,
+        /// File the edit applies to.
+        path: PathBuf,
+        /// Why it failed.
+        source: Box<PrimitiveError>,
+    },
+
     /// Read file specific error.
     #[error("read file error: {0}")]
     ReadFile(#[from] crate::read_file::ReadFileError),
@@ -8,6 +8,15 @@
 //! - `bash` - Execute shell commands
 //! - `edit_file` - Search and replace in files
 //! - `code_search` - Search code with ripgrep
+//!
+//! Alongside `bash`, `process` runs commands such as dev servers in the
+//! background, handing back an id to read output from, wait on and kill.
+//!
+//! The `symbols` feature adds `find_symbol` and `find_references`, backed
+//! by an incremental on-disk index of Rust, TypeScript and Python sources.
+//!
+//! With the `mcp` feature, [`McpServer`] serves them to other agents and
+//! editors over the Model Context Protocol.
 
 #![warn(missing_docs)]
 
@@ -30,12 +39,20 @@ pub mod edit_file;
 #[cfg(feature = "code-search")]
 pub mod code_search;
 
+#[cfg(feature = "symbols")]
+pub mod symbols;
+
 pub mod rate_limit;
+pub mod traits;
+
+#[cfg(feature = "mcp")]
+pub mod mcp;
 
 // Re-exports
 pub use context::{PrimitiveConfig, PrimitiveContext};
 pub use error::{PrimitiveError, PrimitiveResult};
-pub use result::{ExecutionMetadata, ReadFileResult, ListFilesResult, FileEntry, BashResult, EditFileResult, CodeSearchResult, SearchMatch};
+pub use traits::{DynPrimitive, McpToolDefinition, Primitive, PrimitiveRegistry};
+pub use result::{ExecutionMetadata, ReadFileResult, ListFilesResult, FileEntry, BashResult, ProcessResult, EditFileResult, MultiEditResult, FileChange, ChangeKind, CodeSearchResult, SearchMatch, SymbolSearchResult, SymbolMatch, SymbolKind};
 
 #[cfg(feature = "read-file")]
 pub use read_file::{read_file, ReadFileOptions};
@@ -44,11 +61,12 @@ pub use read_file::{read_file, ReadFileOptions};
 pub use list_files::{list_files, ListFilesOptions, SortBy, list_files_recursive, list_files_recursive_with_callback, RecursiveOptions, RecursiveIterator};
 
 #[cfg(feature = "bash")]
-pub use bash::{bash, bash_success, bash_sequence, bash_with_timeout, BashOptions, TimeoutCommand, CancellationToken, CancellationWatcher};
+pub use bash::{bash, bash_success, bash_sequence, bash_with_timeout, BashOptions, bash_start, bash_start_command, bash_read, bash_write, bash_wait, bash_kill, ProcessTable, TimeoutCommand, CancellationToken, CancellationWatcher};
 
 #[cfg(feature = "edit-file")]
 pub use edit_file::{
     edit_file, edit_file_preview, EditFileOptions, Diff, EditPreview,
+    multi_edit, apply_patch, FileEdit,
     UniquenessResult, MatchLocation, MatchSelection, EditValidationError,
     check_uniqueness, format_matches, select_match, validate_edit_target
 };
@@ -56,6 +74,12 @@ pub use edit_file::{
 #[cfg(feature = "code-search")]
 pub use code_search::{code_search, search_literal, find_files, CodeSearchOptions};
 
+#[cfg(feature = "symbols")]
+pub use symbols::{find_symbol, find_references, SymbolIndex, SymbolOptions};
+
+#[cfg(feature = "mcp")]
+pub use mcp::McpServer;
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -2,9 +2,11 @@
 
 mod options;
 mod recursive;
+mod primitive;
 
 pub use options::{ListFilesOptions, SortBy};
 pub use recursive::{list_files_recursive, list_files_recursive_with_callback, RecursiveIterator, RecursiveOptions};
+pub use primitive::{ListFilesInput, ListFilesPrimitive};
 
 use crate::{
     context::PrimitiveContext,
@@ -0,0 +1,80 @@
+//! `list_files` as a registry primitive.
+
+use async_trait::async_trait;
+use serde::Deserialize;
+use serde_json::{json, Value};
+
+use super::{list_files, ListFilesOptions};
+use crate::{context::PrimitiveContext, error::PrimitiveResult, result::ListFilesResult, traits::Primitive};
+
+/// JSON input for `list_files`.
+#[derive(Debug, Clone, Deserialize)]
+pub struct ListFilesInput {
+    /// Directory to list, relative to the working directory.
+    #[serde(default = "current_dir")]
+    pub path: String,
+    /// Descend into subdirectories.
+    #[serde(default)]
+    pub recursive: bool,
+    /// Only files with this extension.
+    #[serde(default)]
+    pub extension: Option<String>,
+    /// Only names matching this glob pattern.
+    #[serde(default)]
+    pub pattern: Option<String>,
+    /// Include dotfiles.
+    #[serde(default)]
+    pub include_hidden: bool,
+    /// Maximum number of entries.
+    #[serde(default)]
+    pub limit: Option<usize>,
+}
+
+fn current_dir() -> String {
+    ".".to_string()
+}
+
+/// The `list_files` primitive.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ListFilesPrimitive;
+
+#[async_trait]
+impl Primitive for ListFilesPrimitive {
+    type Input = ListFilesInput;
+    type Output = ListFilesResult;
+
+    fn name(&self) -> &'static str {
+        "list_files"
+    }
+
+    fn description(&self) -> &'static str {
+        "List the entries of a directory, optionally recursively and filtered by extension or pattern."
+    }
+
+    async fn execute(&self, ctx: &PrimitiveContext, input: ListFilesInput) -> PrimitiveResult<ListFilesResult> {
+        let options = ListFilesOptions {
+            recursive: input.recursive,
+            extension: input.extension,
+            pattern: input.pattern,
+            include_hidden: input.include_hidden,
+            limit: input.limit,
+            include_dirs: true,
+            ..ListFilesOptions::default()
+        };
+        list_files(ctx, &input.path, Some(options)).await
+    }
+
+    fn input_schema(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "path": {"type": "string", "description": "Directory to list (default: the working directory)"},
+                "recursive": {"type": "boolean", "description": "Descend into subdirectories"},
+                "extension": {"type": "string", "description": "Only files with this extension, e.g. \"rs\""},
+                "pattern": {"type": "string", "description": "Only names matching this glob pattern"},
+                "include_hidden": {"type": "boolean", "description": "Include dotfiles"},
+                "limit": {"type": "integer", "minimum": 1, "description": "Maximum number of entries"}
+            }
+        })
+    }
+}
@@ -0,0 +1,552 @@
+//! Model Context Protocol server for the primitives.
+//!
+//! [`McpServer`] answers MCP JSON-RPC requests (`initialize`, `ping`,
+//! `tools/list`, `tools/call`) with the tools of a [`PrimitiveRegistry`].
+//! Every call runs in the server's [`PrimitiveContext`], so its allowed and
+//! denied paths apply, and must get a permit from the rate limiter first.
+//!
+//! Two transports:
+//!
+//! - stdio: one JSON-RPC message per line ([`McpServer::serve_stdio`])
+//! - HTTP: `POST` a message, get the response back as JSON, or as a single
+//!   SSE event when the client only accepts `text/event-stream`
+//!   ([`McpServer::serve_http`]); every request must carry the server's
+//!   bearer token, see [`generate_token`]
+//!
+//! Failed tool calls are tool results with `isError` set, so the calling
+//! model sees what went wrong; protocol mistakes are JSON-RPC errors.
+
+use serde_json::{json, Value};
+use std::io;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::io::{AsyncBufRead, AsyncBufReadExt, AsyncReadExt, AsyncWrite, AsyncWriteExt, BufReader};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::time::timeout;
+use tracing::{debug, info};
+
+use crate::context::PrimitiveContext;
+use crate::rate_limit::{RateLimitConfig, SharedRateLimiter};
+use crate::traits::PrimitiveRegistry;
+
+/// Protocol revisions this server speaks, newest first.
+pub const PROTOCOL_VERSIONS: &[&str] = &["2025-06-18", "2025-03-26", "2024-11-05"];
+
+/// Tools that run arbitrary commands, and so are only served over HTTP when
+/// asked for by name.
+pub const SHELL_TOOLS: &[&str] = &["bash", "process"];
+
+/// Largest HTTP request body accepted.
+const MAX_BODY_BYTES: usize = 10 * 1024 * 1024;
+
+/// Longest HTTP request or header line accepted.
+const MAX_LINE_BYTES: usize = 8 * 1024;
+
+/// Most HTTP headers accepted in one request.
+const MAX_HEADERS: usize = 100;
+
+/// How long a client gets to send its request head, and then its body.
+const READ_TIMEOUT: Duration = Duration::from_secs(10);
+
+// JSON-RPC error codes
+const PARSE_ERROR: i64 = -32700;
+const INVALID_REQUEST: i64 = -32600;
+const METHOD_NOT_FOUND: i64 = -32601;
+const INVALID_PARAMS: i64 = -32602;
+
+/// Serves a primitive registry over MCP.
+pub struct McpServer {
+    registry: PrimitiveRegistry,
+    ctx: PrimitiveContext,
+    limiter: SharedRateLimiter,
+}
+
+impl McpServer {
+    /// Serve the default primitives in `ctx` with the default rate limits.
+    pub fn new(ctx: PrimitiveContext) -> Self {
+        Self::with_registry(PrimitiveRegistry::with_defaults(), ctx, RateLimitConfig::default())
+    }
+
+    /// Serve the primitives of `registry`.
+    pub fn with_registry(registry: PrimitiveRegistry, ctx: PrimitiveContext, rate_limits: RateLimitConfig) -> Self {
+        Self {
+            registry,
+            ctx,
+            limiter: SharedRateLimiter::new(rate_limits),
+        }
+    }
+
+    /// Answer one JSON-RPC message; notifications and responses get no answer.
+    pub async fn handle(&self, message: Value) -> Option<Value> {
+        let id = message.get("id").cloned();
+        let Some(method) = message.get("method").and_then(Value::as_str) else {
+            if message.get("result").is_some() || message.get("error").is_some() {
+                return None;
+            }
+            return Some(error_response(id.unwrap_or(Value::Null), INVALID_REQUEST, "Expected a JSON-RPC request"));
+        };
+        let Some(id) = id else {
+            debug!("MCP notification: {}", method);
+            return None;
+        };
+
+        let params = message.get("params").cloned().unwrap_or(Value::Null);
+        let result = match method {
+            "initialize" => Ok(self.initialize(&params)),
+            "ping" => Ok(json!({})),
+            "tools/list" => Ok(json!({ "tools": self.tools() })),
+            "tools/call" => self.call_tool(&params).await,
+            _ => Err((METHOD_NOT_FOUND, format!("Method not found: {}", method))),
+        };
+
+        Some(match result {
+            Ok(result) => json!({ "jsonrpc": "2.0", "id": id, "result": result }),
+            Err((code, message)) => error_response(id, code, &message),
+        })
+    }
+
+    /// Answer one serialized message.
+    pub async fn handle_line(&self, line: &str) -> Option<String> {
+        let response = match serde_json::from_str::<Value>(line) {
+            Ok(Value::Array(_)) => Some(error_response(Value::Null, INVALID_REQUEST, "Batches are not supported")),
+            Ok(message) => self.handle(message).await,
+            Err(e) => Some(error_response(Value::Null, PARSE_ERROR, &format!("Parse error: {}", e))),
+        };
+        response.map(|r| r.to_string())
+    }
+
+    /// Serve on stdin/stdout until stdin closes.
+    pub async fn serve_stdio(&self) -> io::Result<()> {
+        self.serve(BufReader::new(tokio::io::stdin()), tokio::io::stdout()).await
+    }
+
+    /// Serve newline-delimited messages from `reader`, answering on `writer`.
+    pub async fn serve<R, W>(&self, reader: R, mut writer: W) -> io::Result<()>
+    where
+        R: AsyncBufRead + Unpin,
+        W: AsyncWrite + Unpin,
+    {
+        let mut lines = reader.lines();
+        while let Some(line) = lines.next_line().await? {
+            if line.trim().is_empty() {
+                continue;
+            }
+            if let Some(response) = self.handle_line(&line).await {
+                writer.write_all(response.as_bytes()).await?;
+                writer.write_all(b"\n").await?;
+                writer.flush().await?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Serve HTTP requests on `listener`, one request per connection.
+    ///
+    /// Requests without `Authorization: Bearer <token>` are refused, as are
+    /// heads with an overlong line or too many headers (431) and clients
+    /// that take more than ten seconds to send the head or the body (408).
+    pub async fn serve_http(self: Arc<Self>, listener: TcpListener, token: String) -> io::Result<()> {
+        info!("MCP server listening on http://{}", listener.local_addr()?);
+        let token: Arc<str> = token.into();
+        loop {
+            let (stream, peer) = listener.accept().await?;
+            let server = self.clone();
+            let token = token.clone();
+            tokio::spawn(async move {
+                if let Err(e) = server.serve_connection(stream, &token).await {
+                    debug!("MCP request from {} failed: {}", peer, e);
+                }
+            });
+        }
+    }
+
+    async fn serve_connection(&self, stream: TcpStream, token: &str) -> io::Result<()> {
+        let mut reader = BufReader::new(stream);
+
+        // The head is read before the token is checked, so bound it in time
+        // and size
+        let (status, content_type, body) = match timeout(READ_TIMEOUT, read_head(&mut reader)).await {
+            Err(_) => ("408 Request Timeout", None, String::new()),
+            Ok(Ok(None)) => ("431 Request Header Fields Too Large", None, String::new()),
+            Ok(Ok(Some(head))) => self.respond(&mut reader, head, token).await?,
+            Ok(Err(e)) => return Err(e),
+        };
+
+        let mut head = format!("HTTP/1.1 {}\r\nContent-Length: {}\r\nConnection: close\r\n", status, body.len());
+        if let Some(content_type) = content_type {
+            head.push_str(&format!("Content-Type: {}\r\n", content_type));
+        }
+        if status.starts_with("401") {
+            head.push_str("WWW-Authenticate: Bearer\r\n");
+        }
+        if status.starts_with("405") {
+            head.push_str("Allow: POST\r\n");
+        }
+        head.push_str("\r\n");
+
+        let mut stream = reader.into_inner();
+        stream.write_all(head.as_bytes()).await?;
+        stream.write_all(body.as_bytes()).await?;
+        stream.shutdown().await
+    }
+
+    async fn respond(
+        &self,
+        reader: &mut BufReader<TcpStream>,
+        head: RequestHead,
+        token: &str,
+    ) -> io::Result<(&'static str, Option<&'static str>, String)> {
+        // Browsers send an Origin; only local pages may drive the tools
+        Ok(if head.origin.as_deref().is_some_and(|o| !is_local_origin(o)) {
+            ("403 Forbidden", None, String::new())
+        } else if !is_authorized(head.authorization.as_deref(), token) {
+            ("401 Unauthorized", None, String::new())
+        } else if head.method != "POST" {
+            ("405 Method Not Allowed", None, String::new())
+        } else if head.content_length > MAX_BODY_BYTES {
+            ("413 Payload Too Large", None, String::new())
+        } else {
+            let mut body = vec![0; head.content_length];
+            if timeout(READ_TIMEOUT, reader.read_exact(&mut body)).await.is_err() {
+                return Ok(("408 Request Timeout", None, String::new()));
+            }
+            let accept = head.accept;
+            match self.handle_line(&String::from_utf8_lossy(&body)).await {
+                None => ("202 Accepted", None, String::new()),
+                Some(response) if accept.contains("text/event-stream") && !accept.contains("application/json") => {
+                    ("200 OK", Some("text/event-stream"), format!("event: message\ndata: {}\n\n", response))
+                }
+                Some(response) => ("200 OK", Some("application/json"), response),
+            }
+        })
+    }
+
+    fn initialize(&self, params: &Value) -> Value {
+        let version = params
+            .get("protocolVersion")
+            .and_then(Value::as_str)
+            .filter(|v| PROTOCOL_VERSIONS.contains(v))
+            .unwrap_or(PROTOCOL_VERSIONS[0]);
+        json!({
+            "protocolVersion": version,
+            "capabilities": { "tools": { "listChanged": false } },
+            "serverInfo": { "name": "tachikoma", "version": env!("CARGO_PKG_VERSION") },
+        })
+    }
+
+    fn tools(&self) -> Vec<Value> {
+        self.registry
+            .mcp_tools()
+            .into_iter()
+            .map(|tool| {
+                json!({
+                    "name": tool.name,
+                    "description": tool.description,
+                    "inputSchema": tool.input_schema,
+                })
+            })
+            .collect()
+    }
+
+    async fn call_tool(&self, params: &Value) -> Result<Value, (i64, String)> {
+        let name = params
+            .get("name")
+            .and_then(Value::as_str)
+            .ok_or((INVALID_PARAMS, "Missing tool name".to_string()))?;
+        if self.registry.get(name).is_none() {
+            return Err((INVALID_PARAMS, format!("Unknown tool: {}", name)));
+        }
+        let arguments = params.get("arguments").cloned().unwrap_or_else(|| json!({}));
+
+        if let Err(e) = self.limiter.try_acquire(name).await {
+            return Ok(tool_result(&e.to_string(), true));
+        }
+
+        let ctx = PrimitiveContext {
+            operation_id: uuid::Uuid::new_v4().to_string(),
+            ..self.ctx.clone()
+        };
+        Ok(match self.registry.execute(name, &ctx, arguments).await {
+            Ok(output) => tool_result(&serde_json::to_string_pretty(&output).unwrap_or_default(), false),
+            Err(e) => tool_result(&e.to_string(), true),
+        })
+    }
+}
+
+/// The parts of an HTTP request head the server looks at.
+#[derive(Default)]
+struct RequestHead {
+    method: String,
+    content_length: usize,
+    accept: String,
+    origin: Option<String>,
+    authorization: Option<String>,
+}
+
+/// Read a request line and headers, or `None` when a line is longer than
+/// [`MAX_LINE_BYTES`] or there are more than [`MAX_HEADERS`] headers.
+async fn read_head<R: AsyncBufRead + Unpin>(reader: &mut R) -> io::Result<Option<RequestHead>> {
+    let mut line = String::new();
+    if !read_line_capped(reader, &mut line).await? {
+        return Ok(None);
+    }
+    let mut head = RequestHead {
+        method: line.split_whitespace().next().unwrap_or_default().to_string(),
+        ..RequestHead::default()
+    };
+
+    for _ in 0..=MAX_HEADERS {
+        if !read_line_capped(reader, &mut line).await? {
+            return Ok(None);
+        }
+        if line.trim().is_empty() {
+            return Ok(Some(head));
+        }
+        if let Some((name, value)) = line.split_once(':') {
+            let value = value.trim();
+            match name.trim().to_ascii_lowercase().as_str() {
+                "content-length" => head.content_length = value.parse().unwrap_or(0),
+                "accept" => head.accept = value.to_ascii_lowercase(),
+                "origin" => head.origin = Some(value.to_string()),
+                "authorization" => head.authorization = Some(value.to_string()),
+                _ => {}
+            }
+        }
+    }
+    Ok(None)
+}
+
+/// Read one line into `line`; false when it runs past [`MAX_LINE_BYTES`].
+/// End of input reads as an empty line.
+async fn read_line_capped<R: AsyncBufRead + Unpin>(reader: &mut R, line: &mut String) -> io::Result<bool> {
+    line.clear();
+    let read = (&mut *reader).take(MAX_LINE_BYTES as u64).read_line(line).await?;
+    Ok(read < MAX_LINE_BYTES || line.ends_with('\n'))
+}
+
+fn tool_result(text: &str, is_error: bool) -> Value {
+    json!({
+        "content": [{ "type": "text", "text": text }],
+        "isError": is_error,
+    })
+}
+
+fn error_response(id: Value, code: i64, message: &str) -> Value {
+    json!({
+        "jsonrpc": "2.0",
+        "id": id,
+        "error": { "code": code, "message": message },
+    })
+}
+
+/// A fresh bearer token for [`McpServer::serve_http`].
+pub fn generate_token() -> String {
+    format!("{}{}", uuid::Uuid::new_v4().simple(), uuid::Uuid::new_v4().simple())
+}
+
+/// Whether an `Authorization` header carries `token`, compared in constant
+/// time so the token can't be guessed a byte at a time.
+fn is_authorized(authorization: Option<&str>, token: &str) -> bool {
+    let Some((scheme, credentials)) = authorization.and_then(|a| a.split_once(' ')) else {
+        return false;
+    };
+    let credentials = credentials.trim().as_bytes();
+    scheme.eq_ignore_ascii_case("bearer")
+        && credentials.len() == token.len()
+        && credentials.iter().zip(token.as_bytes()).fold(0, |diff, (a, b)| diff | (a ^ b)) == 0
+}
+
+/// Whether an `Origin` header names this machine.
+fn is_local_origin(origin: &str) -> bool {
+    let host = origin.split_once("://").map_or(origin, |(_, rest)| rest);
+    let host = if host.starts_with('[') {
+        host.split(']').next().map(|h| &h[1..]).unwrap_or_default()
+    } else {
+        host.split([':', '/']).next().unwrap_or_default()
+    };
+    matches!(host, "localhost" | "127.0.0.1" | "::1")
+}
+
+#[cfg(all(test, feature = "all"))]
+mod tests {
+    use super::*;
+    use crate::context::PrimitiveConfig;
+
+    fn server(dir: &std::path::Path, rate_limits: RateLimitConfig) -> McpServer {
+        let config = PrimitiveConfig {
+            allowed_paths: vec![dir.to_path_buf()],
+            ..PrimitiveConfig::default()
+        };
+        let ctx = PrimitiveContext::with_config(dir.to_path_buf(), config);
+        McpServer::with_registry(PrimitiveRegistry::with_defaults(), ctx, rate_limits)
+    }
+
+    #[tokio::test]
+    async fn test_stdio_session() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path().join("project");
+        std::fs::create_dir(&root).unwrap();
+        std::fs::write(root.join("a.txt"), "hello\n").unwrap();
+        std::fs::write(dir.path().join("secret.txt"), "nope\n").unwrap();
+
+        let rate_limits = RateLimitConfig {
+            primitive_burst: [("list_files".to_string(), 1)].into(),
+            ..RateLimitConfig::default()
+        };
+        let server = server(&root, rate_limits);
+
+        let input = [
+            r#"{"jsonrpc":"2.0","id":1,"method":"initialize","params":{"protocolVersion":"2025-03-26","capabilities":{},"clientInfo":{"name":"test","version":"0"}}}"#,
+            r#"{"jsonrpc":"2.0","method":"notifications/initialized"}"#,
+            r#"{"jsonrpc":"2.0","id":2,"method":"tools/list"}"#,
+            r#"{"jsonrpc":"2.0","id":3,"method":"tools/call","params":{"name":"read_file","arguments":{"path":"a.txt"}}}"#,
+            r#"{"jsonrpc":"2.0","id":4,"method":"tools/call","params":{"name":"read_file","arguments":{"path":"../secret.txt"}}}"#,
+            r#"{"jsonrpc":"2.0","id":5,"method":"tools/call","params":{"name":"list_files","arguments":{}}}"#,
+            r#"{"jsonrpc":"2.0","id":6,"method":"tools/call","params":{"name":"list_files","arguments":{}}}"#,
+            r#"{"jsonrpc":"2.0","id":7,"method":"tools/call","params":{"name":"rm","arguments":{}}}"#,
+            "not json",
+        ]
+        .join("\n");
+        let mut output = Vec::new();
+        server.serve(input.as_bytes(), &mut output).await.unwrap();
+
+        let responses: Vec<Value> = String::from_utf8(output)
+            .unwrap()
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        // No answer to the notification
+        assert_eq!(responses.len(), 8);
+
+        assert_eq!(responses[0]["result"]["protocolVersion"], "2025-03-26");
+        let tools: Vec<&str> = responses[1]["result"]["tools"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|t| t["name"].as_str().unwrap())
+            .collect();
+        assert_eq!(
+            tools,
+            ["bash", "code_search", "edit_file", "find_references", "find_symbol", "list_files", "multi_edit", "process", "read_file"]
+        );
+        assert_eq!(responses[1]["result"]["tools"][8]["inputSchema"]["required"][0], "path");
+
+        let read = &responses[2]["result"];
+        assert_eq!(read["isError"], false);
+        assert!(read["content"][0]["text"].as_str().unwrap().contains("hello"));
+
+        // Outside the allowed paths, even through `..`
+        assert_eq!(responses[3]["result"]["isError"], true);
+
+        // Burst of one
+        assert_eq!(responses[4]["result"]["isError"], false);
+        assert_eq!(responses[5]["result"]["isError"], true);
+        assert!(responses[5]["result"]["content"][0]["text"].as_str().unwrap().contains("Rate limit"));
+
+        assert_eq!(responses[6]["error"]["code"], INVALID_PARAMS);
+        assert_eq!(responses[7]["error"]["code"], PARSE_ERROR);
+    }
+
+    #[tokio::test]
+    async fn test_http_transport() {
+        let dir = tempfile::tempdir().unwrap();
+        let server = Arc::new(server(dir.path(), RateLimitConfig::default()));
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let token = generate_token();
+        tokio::spawn(server.serve_http(listener, token.clone()));
+        let auth = format!("Authorization: Bearer {}\r\n", token);
+
+        async fn post(addr: std::net::SocketAddr, headers: &str, body: &str) -> String {
+            let mut stream = TcpStream::connect(addr).await.unwrap();
+            let request = format!(
+                "POST /mcp HTTP/1.1\r\nHost: {}\r\nContent-Type: application/json\r\n{}Content-Length: {}\r\n\r\n{}",
+                addr,
+                headers,
+                body.len(),
+                body
+            );
+            stream.write_all(request.as_bytes()).await.unwrap();
+            let mut response = String::new();
+            stream.read_to_string(&mut response).await.unwrap();
+            response
+        }
+
+        let ping = r#"{"jsonrpc":"2.0","id":1,"method":"ping"}"#;
+        let response = post(addr, &format!("{}Accept: application/json, text/event-stream\r\n", auth), ping).await;
+        assert!(response.starts_with("HTTP/1.1 200 OK"));
+        assert!(response.contains("Content-Type: application/json"));
+        assert!(response.contains(r#""result":{}"#));
+
+        let response = post(addr, &format!("{}Accept: text/event-stream\r\n", auth), ping).await;
+        assert!(response.contains("Content-Type: text/event-stream"));
+        assert!(response.contains("data: {\"id\":1"));
+
+        let notification = r#"{"jsonrpc":"2.0","method":"notifications/initialized"}"#;
+        assert!(post(addr, &auth, notification).await.starts_with("HTTP/1.1 202"));
+        let evil = format!("{}Origin: https://evil.example\r\n", auth);
+        assert!(post(addr, &evil, ping).await.starts_with("HTTP/1.1 403"));
+        let local = format!("{}Origin: http://localhost:3000\r\n", auth);
+        assert!(post(addr, &local, ping).await.starts_with("HTTP/1.1 200"));
+
+        // No token, someone else's token, or the token under another scheme
+        let response = post(addr, "", ping).await;
+        assert!(response.starts_with("HTTP/1.1 401"));
+        assert!(response.contains("WWW-Authenticate: Bearer"));
+        let wrong = format!("Authorization: Bearer {}\r\n", generate_token());
+        assert!(post(addr, &wrong, ping).await.starts_with("HTTP/1.1 401"));
+        let basic = format!("Authorization: Basic {}\r\n", token);
+        assert!(post(addr, &basic, ping).await.starts_with("HTTP/1.1 401"));
+        let lowercase = format!("Authorization: bearer {}\r\n", token);
+        assert!(post(addr, &lowercase, ping).await.starts_with("HTTP/1.1 200"));
+    }
+
+    /// Send `request` as is and read the whole response.
+    async fn send_raw(addr: std::net::SocketAddr, request: &[u8]) -> String {
+        let mut stream = TcpStream::connect(addr).await.unwrap();
+        // The server may answer and close before it has read everything
+        let _ = stream.write_all(request).await;
+        let mut response = Vec::new();
+        let _ = stream.read_to_end(&mut response).await;
+        String::from_utf8_lossy(&response).into_owned()
+    }
+
+    async fn http_server() -> std::net::SocketAddr {
+        let dir = tempfile::tempdir().unwrap();
+        let server = Arc::new(server(dir.path(), RateLimitConfig::default()));
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(server.serve_http(listener, generate_token()));
+        addr
+    }
+
+    #[tokio::test]
+    async fn test_http_head_limits() {
+        let addr = http_server().await;
+
+        let long_line = format!("POST /mcp HTTP/1.1\r\nX-Filler: {}\r\n\r\n", "a".repeat(MAX_LINE_BYTES));
+        assert!(send_raw(addr, long_line.as_bytes()).await.starts_with("HTTP/1.1 431"));
+
+        let long_request_line = format!("POST /{} HTTP/1.1\r\n\r\n", "a".repeat(MAX_LINE_BYTES));
+        assert!(send_raw(addr, long_request_line.as_bytes()).await.starts_with("HTTP/1.1 431"));
+
+        let many_headers = format!("POST /mcp HTTP/1.1\r\n{}\r\n", "X-Filler: a\r\n".repeat(MAX_HEADERS + 1));
+        assert!(send_raw(addr, many_headers.as_bytes()).await.starts_with("HTTP/1.1 431"));
+
+        // Right at the limits is fine, and falls through to the token check
+        let at_limit = format!("POST /mcp HTTP/1.1\r\n{}\r\n", "X-Filler: a\r\n".repeat(MAX_HEADERS));
+        assert!(send_raw(addr, at_limit.as_bytes()).await.starts_with("HTTP/1.1 401"));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_http_head_timeout() {
+        let addr = http_server().await;
+
+        // Half a head, then nothing: the paused clock runs on to the timeout
+        let mut stream = TcpStream::connect(addr).await.unwrap();
+        stream.write_all(b"POST /mcp HTTP/1.1\r\nAccept: appl").await.unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).await.unwrap();
+        assert!(response.starts_with("HTTP/1.1 408"));
+    }
+}
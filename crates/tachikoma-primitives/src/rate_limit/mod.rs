@@ -134,22 +134,25 @@ impl RateLimiter {
         let global_available = global.available_tokens();
         drop(global);
 
+        // A bucket holds at most its burst size, so that is the limit reported
+        let primitive_burst = self.config.primitive_burst
+            .get(primitive)
+            .copied()
+            .unwrap_or(self.config.default_burst_size);
+
         let primitive_available = if let Some(limiter) = self.primitive_limiters.get(primitive) {
             let bucket = limiter.lock().await;
             bucket.available_tokens()
         } else {
-            self.config.default_burst_size
+            primitive_burst
         };
 
         RateLimitStatus {
             primitive: primitive.to_string(),
             primitive_tokens_available: primitive_available,
             global_tokens_available: global_available,
-            primitive_limit: self.config.primitive_limits
-                .get(primitive)
-                .copied()
-                .unwrap_or(self.config.default_tokens_per_second),
-            global_limit: self.config.global_tokens_per_second,
+            primitive_limit: primitive_burst,
+            global_limit: self.config.global_burst_size,
         }
     }
 }
@@ -4,11 +4,13 @@ mod error;
 mod logging;
 mod options;
 mod suggest;
+mod primitive;
 
 pub use error::{ReadFileError, ReadFileErrorResponse};
 pub use logging::{log_read_error, log_read_success};
 pub use options::ReadFileOptions;
 pub use suggest::find_similar_files;
+pub use primitive::{ReadFileInput, ReadFilePrimitive};
 
 use crate::{
     context::PrimitiveContext,
@@ -0,0 +1,58 @@
+//! `read_file` as a registry primitive.
+
+use async_trait::async_trait;
+use serde::Deserialize;
+use serde_json::{json, Value};
+
+use super::{read_file, ReadFileOptions};
+use crate::{context::PrimitiveContext, error::PrimitiveResult, result::ReadFileResult, traits::Primitive};
+
+/// JSON input for `read_file`.
+#[derive(Debug, Clone, Deserialize)]
+pub struct ReadFileInput {
+    /// File to read, relative to the working directory.
+    pub path: String,
+    /// First line to read (1-based).
+    #[serde(default)]
+    pub start_line: Option<usize>,
+    /// Last line to read (inclusive).
+    #[serde(default)]
+    pub end_line: Option<usize>,
+}
+
+/// The `read_file` primitive.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ReadFilePrimitive;
+
+#[async_trait]
+impl Primitive for ReadFilePrimitive {
+    type Input = ReadFileInput;
+    type Output = ReadFileResult;
+
+    fn name(&self) -> &'static str {
+        "read_file"
+    }
+
+    fn description(&self) -> &'static str {
+        "Read a file's contents, optionally limited to a range of lines."
+    }
+
+    async fn execute(&self, ctx: &PrimitiveContext, input: ReadFileInput) -> PrimitiveResult<ReadFileResult> {
+        let mut options = ReadFileOptions::new();
+        options.start_line = input.start_line;
+        options.end_line = input.end_line;
+        read_file(ctx, &input.path, Some(options)).await
+    }
+
+    fn input_schema(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "path": {"type": "string", "description": "File to read, relative to the working directory"},
+                "start_line": {"type": "integer", "minimum": 1, "description": "First line to read (1-based)"},
+                "end_line": {"type": "integer", "minimum": 1, "description": "Last line to read (inclusive)"}
+            },
+            "required": ["path"]
+        })
+    }
+}
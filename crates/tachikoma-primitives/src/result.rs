@@ -101,6 +101,33 @@ impl BashResult {
     }
 }
 
+/// State of a background process, with the output a call read from it.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProcessResult {
+    /// Handle within the session, e.g. `p1`.
+    pub id: String,
+    /// Command line.
+    pub command: String,
+    /// Operating system process ID.
+    pub pid: Option<u32>,
+    /// Whether the process is still running.
+    pub running: bool,
+    /// Exit code once it has exited (-1 when killed by a signal).
+    pub exit_code: Option<i32>,
+    /// Output read by this call, stdout and stderr interleaved.
+    pub output: String,
+    /// Byte offset of `output` in everything the process has written.
+    pub offset: usize,
+    /// Offset to continue reading from.
+    pub next_offset: usize,
+    /// Bytes before `offset` that were dropped from the buffer unread.
+    pub dropped_bytes: usize,
+    /// For a wait on a pattern, whether the pattern appeared.
+    pub matched: Option<bool>,
+    /// Execution metadata.
+    pub metadata: ExecutionMetadata,
+}
+
 /// Result of an edit_file operation.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct EditFileResult {
@@ -110,10 +137,49 @@ pub struct EditFileResult {
     pub replacements: usize,
     /// File path.
     pub path: PathBuf,
+    /// Unified diff of the change.
+    #[serde(default)]
+    pub diff: String,
+    /// Execution metadata.
+    pub metadata: ExecutionMetadata,
+}
+
+/// Result of a multi_edit operation.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MultiEditResult {
+    /// Files changed, in the order first edited.
+    pub files: Vec<FileChange>,
+    /// Unified diff of every change.
+    pub diff: String,
+    /// Whether the changes were only previewed.
+    pub dry_run: bool,
     /// Execution metadata.
     pub metadata: ExecutionMetadata,
 }
 
+/// One file changed by a multi_edit.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FileChange {
+    /// File path.
+    pub path: PathBuf,
+    /// What happened to the file.
+    pub kind: ChangeKind,
+    /// Edits or hunks applied to it.
+    pub replacements: usize,
+}
+
+/// How a multi_edit changed a file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ChangeKind {
+    /// The file was created.
+    Created,
+    /// The file was edited.
+    Modified,
+    /// The file was deleted.
+    Deleted,
+}
+
 /// Result of a code_search operation.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct CodeSearchResult {
@@ -144,4 +210,73 @@ pub struct SearchMatch {
     pub context_before: Vec<String>,
     /// Context lines after.
     pub context_after: Vec<String>,
-}
\ No newline at end of file
+}
+/// Result of a find_symbol or find_references operation.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SymbolSearchResult {
+    /// Definitions or references found.
+    pub matches: Vec<SymbolMatch>,
+    /// Symbol name searched for.
+    pub query: String,
+    /// Total matches found.
+    pub total_count: usize,
+    /// Whether results were truncated.
+    pub truncated: bool,
+    /// Execution metadata.
+    pub metadata: ExecutionMetadata,
+}
+
+/// A definition of, or reference to, a symbol.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SymbolMatch {
+    /// File path.
+    pub path: PathBuf,
+    /// Line number (1-indexed).
+    pub line_number: usize,
+    /// Column number (1-indexed).
+    pub column: usize,
+    /// Line content.
+    pub line_content: String,
+    /// Symbol name.
+    pub name: String,
+    /// What the symbol is, or `Reference` for a use of it.
+    pub kind: SymbolKind,
+    /// Enclosing definition, e.g. `Parser::parse` or `tests`.
+    pub scope: Option<String>,
+}
+
+/// What kind of item a symbol is.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SymbolKind {
+    /// A free function.
+    Function,
+    /// A function inside an impl, trait or class.
+    Method,
+    /// A struct.
+    Struct,
+    /// An enum.
+    Enum,
+    /// A union.
+    Union,
+    /// A trait.
+    Trait,
+    /// An impl block, named after its self type.
+    Impl,
+    /// A class.
+    Class,
+    /// A TypeScript interface.
+    Interface,
+    /// A type alias.
+    Type,
+    /// A module or namespace.
+    Module,
+    /// A constant.
+    Const,
+    /// A static.
+    Static,
+    /// A macro.
+    Macro,
+    /// A use of a symbol rather than its definition.
+    Reference,
+}
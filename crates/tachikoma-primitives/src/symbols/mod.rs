@@ -0,0 +1,476 @@
+//! Symbol index: where things are defined and used.
+//!
+//! `find_symbol` answers "where is `Parser` defined?" and `find_references`
+//! answers "who calls `parse`?" for Rust, TypeScript/JavaScript and Python,
+//! without a language server. The index lives in `.tachikoma/index/` under
+//! the working directory, ignored by git, and is refreshed on every query: files whose
+//! size and modification time are unchanged keep their entries, changed and
+//! new files are re-parsed, and deleted files are dropped.
+
+mod parse;
+mod primitive;
+
+pub use parse::{parse, Definition, Language, ParsedFile};
+pub use primitive::{FindReferencesInput, FindReferencesPrimitive, FindSymbolInput, FindSymbolPrimitive};
+
+use crate::{
+    context::PrimitiveContext,
+    error::{PrimitiveError, PrimitiveResult},
+    result::{ExecutionMetadata, SymbolKind, SymbolMatch, SymbolSearchResult},
+};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{Instant, UNIX_EPOCH};
+use tracing::{debug, instrument, warn};
+use walkdir::WalkDir;
+
+/// Where the index is kept, relative to the working directory.
+pub const INDEX_PATH: &str = ".tachikoma/index/symbols.json";
+
+/// Bumped when the stored format or the parser changes.
+const INDEX_VERSION: u32 = 2;
+
+/// Maximum matches to return by default.
+const DEFAULT_MAX_RESULTS: usize = 100;
+
+/// Directories never worth indexing.
+const SKIP_DIRS: &[&str] = &["target", "node_modules", "dist", "build", "__pycache__", "venv"];
+
+/// Options for symbol queries.
+#[derive(Debug, Clone, Default)]
+pub struct SymbolOptions {
+    /// Only definitions of this kind (find_symbol only).
+    pub kind: Option<SymbolKind>,
+    /// Only files under this path, relative to the working directory.
+    pub path: Option<String>,
+    /// Maximum number of matches.
+    pub max_results: Option<usize>,
+}
+
+impl SymbolOptions {
+    /// Create new default options.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Only definitions of this kind.
+    pub fn kind(mut self, kind: SymbolKind) -> Self {
+        self.kind = Some(kind);
+        self
+    }
+
+    /// Only files under this path.
+    pub fn path(mut self, path: &str) -> Self {
+        self.path = Some(path.to_string());
+        self
+    }
+
+    /// Set maximum matches.
+    pub fn max_results(mut self, max: usize) -> Self {
+        self.max_results = Some(max);
+        self
+    }
+}
+
+/// An indexed file.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct IndexedFile {
+    language: Language,
+    size: u64,
+    /// Modification time in nanoseconds since the epoch.
+    modified: u128,
+    definitions: Vec<Definition>,
+    /// Identifiers used in the file, to narrow reference searches.
+    identifiers: Vec<String>,
+}
+
+/// The on-disk symbol index of a directory tree.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct SymbolIndex {
+    version: u32,
+    /// Keyed by path relative to the root.
+    files: BTreeMap<PathBuf, IndexedFile>,
+    #[serde(skip)]
+    root: PathBuf,
+    #[serde(skip)]
+    dirty: bool,
+}
+
+impl SymbolIndex {
+    /// Load the index stored under `root`, or start an empty one.
+    pub fn open(root: &Path) -> Self {
+        let stored = fs::read_to_string(root.join(INDEX_PATH))
+            .ok()
+            .and_then(|json| serde_json::from_str::<SymbolIndex>(&json).ok())
+            .filter(|index| index.version == INDEX_VERSION);
+        let mut index = stored.unwrap_or_default();
+        index.version = INDEX_VERSION;
+        index.root = root.to_path_buf();
+        index
+    }
+
+    /// Bring the index up to date with the files on disk.
+    ///
+    /// Returns how many files were parsed or dropped.
+    pub fn refresh(&mut self, ctx: &PrimitiveContext) -> usize {
+        let mut seen = Vec::new();
+        let mut changed = 0;
+
+        let walker = WalkDir::new(&self.root)
+            .follow_links(ctx.config.follow_symlinks)
+            .max_depth(ctx.config.max_depth)
+            .into_iter()
+            .filter_entry(|entry| entry.depth() == 0 || !is_skipped(entry));
+        for entry in walker.filter_map(Result::ok) {
+            let Some(language) = Language::from_path(entry.path()) else {
+                continue;
+            };
+            let Ok(metadata) = entry.metadata() else {
+                continue;
+            };
+            if !metadata.is_file()
+                || metadata.len() > ctx.config.max_file_size as u64
+                || !ctx.is_path_allowed(&entry.path().to_path_buf())
+            {
+                continue;
+            }
+            let Ok(relative) = entry.path().strip_prefix(&self.root) else {
+                continue;
+            };
+            let relative = relative.to_path_buf();
+            let modified = metadata
+                .modified()
+                .ok()
+                .and_then(|time| time.duration_since(UNIX_EPOCH).ok())
+                .map_or(0, |since| since.as_nanos());
+            seen.push(relative.clone());
+
+            let current = self.files.get(&relative);
+            if current.is_some_and(|file| file.size == metadata.len() && file.modified == modified) {
+                continue;
+            }
+            let Ok(source) = fs::read_to_string(entry.path()) else {
+                continue;
+            };
+            let parsed = parse::parse(language, &source);
+            self.files.insert(
+                relative,
+                IndexedFile {
+                    language,
+                    size: metadata.len(),
+                    modified,
+                    definitions: parsed.definitions,
+                    identifiers: parsed.identifiers.into_iter().collect(),
+                },
+            );
+            changed += 1;
+        }
+
+        seen.sort();
+        let before = self.files.len();
+        self.files.retain(|path, _| seen.binary_search(path).is_ok());
+        changed += before - self.files.len();
+
+        if changed > 0 {
+            self.dirty = true;
+        }
+        debug!("Symbol index: {} files, {} changed", self.files.len(), changed);
+        changed
+    }
+
+    /// Write the index back if it changed.
+    pub fn save(&mut self) -> std::io::Result<()> {
+        if !self.dirty {
+            return Ok(());
+        }
+        let path = self.root.join(INDEX_PATH);
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+            // A cache, not something to commit
+            let gitignore = parent.join(".gitignore");
+            if !gitignore.exists() {
+                fs::write(gitignore, "*\n")?;
+            }
+        }
+        let json = serde_json::to_string(self).map_err(std::io::Error::other)?;
+        // Write then rename, so a concurrent query never reads half an index
+        let tmp = path.with_extension(format!("json.{}", std::process::id()));
+        fs::write(&tmp, json)?;
+        fs::rename(&tmp, &path)?;
+        self.dirty = false;
+        Ok(())
+    }
+
+    /// Every indexed definition, with the file it's in.
+    pub fn definitions(&self) -> impl Iterator<Item = (&Path, Language, &Definition)> {
+        self.files
+            .iter()
+            .flat_map(|(path, file)| file.definitions.iter().map(move |d| (path.as_path(), file.language, d)))
+    }
+
+    /// Files that use an identifier, with their language.
+    fn files_using<'a>(&'a self, name: &'a str) -> impl Iterator<Item = (&'a Path, &'a IndexedFile)> {
+        self.files
+            .iter()
+            .filter(move |(_, file)| file.identifiers.binary_search_by(|i| i.as_str().cmp(name)).is_ok())
+            .map(|(path, file)| (path.as_path(), file))
+    }
+}
+
+fn is_skipped(entry: &walkdir::DirEntry) -> bool {
+    let name = entry.file_name().to_string_lossy();
+    entry.file_type().is_dir() && (name.starts_with('.') || SKIP_DIRS.contains(&name.as_ref()))
+}
+
+/// Find where a symbol is defined.
+///
+/// `name` may be qualified with its enclosing scope, as in `Parser::parse`
+/// or `Store.load`; the qualifier must match the end of the definition's scope.
+///
+/// # Example
+///
+/// ```no_run
+/// use tachikoma_primitives::{PrimitiveContext, find_symbol, SymbolOptions, SymbolKind};
+/// use std::path::PathBuf;
+///
+/// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+/// let ctx = PrimitiveContext::new(PathBuf::from("."));
+/// let result = find_symbol(&ctx, "Config", Some(SymbolOptions::new().kind(SymbolKind::Struct))).await?;
+/// for m in result.matches {
+///     println!("{}:{} {:?}", m.path.display(), m.line_number, m.kind);
+/// }
+/// # Ok(())
+/// # }
+/// ```
+#[instrument(skip(ctx, options), fields(name = %name, op_id = %ctx.operation_id))]
+pub async fn find_symbol(
+    ctx: &PrimitiveContext,
+    name: &str,
+    options: Option<SymbolOptions>,
+) -> PrimitiveResult<SymbolSearchResult> {
+    let start = Instant::now();
+    let options = options.unwrap_or_default();
+    let (qualifier, name) = split_qualified(name)?;
+    let index = open_index(ctx, &options)?;
+    let under = options.path.as_deref().map(|path| ctx.resolve_path(path));
+
+    let mut matches = Vec::new();
+    let mut lines = FileLines::new(ctx);
+    for (path, _, definition) in index.definitions() {
+        if definition.name != name
+            || options.kind.is_some_and(|kind| kind != definition.kind)
+            || !is_under(ctx, path, under.as_deref())
+        {
+            continue;
+        }
+        if let Some(qualifier) = &qualifier {
+            let scope = definition.scope.as_deref().unwrap_or_default().replace('.', "::");
+            if scope != *qualifier && !scope.ends_with(&format!("::{}", qualifier)) {
+                continue;
+            }
+        }
+        matches.push(SymbolMatch {
+            path: path.to_path_buf(),
+            line_number: definition.line,
+            column: definition.column,
+            line_content: lines.line(path, definition.line),
+            name: definition.name.clone(),
+            kind: definition.kind,
+            scope: definition.scope.clone(),
+        });
+    }
+
+    Ok(finish(ctx, "find_symbol", name, matches, &options, start))
+}
+
+/// Find where a symbol is used.
+///
+/// Matches are whole-identifier occurrences outside comments and strings,
+/// excluding the definitions themselves. Each carries the qualified name of
+/// the definition it appears in as its scope.
+#[instrument(skip(ctx, options), fields(name = %name, op_id = %ctx.operation_id))]
+pub async fn find_references(
+    ctx: &PrimitiveContext,
+    name: &str,
+    options: Option<SymbolOptions>,
+) -> PrimitiveResult<SymbolSearchResult> {
+    let start = Instant::now();
+    let options = options.unwrap_or_default();
+    // References are found by name; a qualifier can't be checked at the use site
+    let (_, name) = split_qualified(name)?;
+    let index = open_index(ctx, &options)?;
+    let under = options.path.as_deref().map(|path| ctx.resolve_path(path));
+
+    let mut matches = Vec::new();
+    for (path, file) in index.files_using(name) {
+        if !is_under(ctx, path, under.as_deref()) {
+            continue;
+        }
+        let Ok(source) = fs::read_to_string(ctx.working_dir.join(path)) else {
+            continue;
+        };
+        let code = parse::code_lines(file.language, &source);
+        for ((index, line), original) in code.iter().enumerate().zip(source.lines()) {
+            let number = index + 1;
+            for (offset, ident) in parse::identifiers_in(line) {
+                let column = offset + 1;
+                if ident != name
+                    || file.definitions.iter().any(|d| d.line == number && d.column == column && d.name == name)
+                {
+                    continue;
+                }
+                // The innermost definition around the use
+                let scope = file
+                    .definitions
+                    .iter()
+                    .filter(|d| d.line <= number && number <= d.end_line)
+                    .min_by_key(|d| d.end_line - d.line)
+                    .map(|d| d.qualified_name(file.language));
+                matches.push(SymbolMatch {
+                    path: path.to_path_buf(),
+                    line_number: number,
+                    column,
+                    line_content: original.to_string(),
+                    name: name.to_string(),
+                    kind: SymbolKind::Reference,
+                    scope,
+                });
+            }
+        }
+    }
+
+    Ok(finish(ctx, "find_references", name, matches, &options, start))
+}
+
+/// Split `a::b::name` or `a.b.name` into the qualifier (as `a::b`) and name.
+fn split_qualified(query: &str) -> PrimitiveResult<(Option<String>, &str)> {
+    let query = query.trim();
+    let (qualifier, name) = match query.rfind(['.', ':']) {
+        Some(at) => {
+            let qualifier = query[..at].trim_end_matches(':').replace('.', "::");
+            (Some(qualifier).filter(|q| !q.is_empty()), &query[at + 1..])
+        }
+        None => (None, query),
+    };
+    if name.is_empty() || !name.chars().all(|c| c.is_alphanumeric() || c == '_' || c == '$') {
+        return Err(PrimitiveError::Validation {
+            message: format!("Not a symbol name: {:?}", query),
+        });
+    }
+    Ok((qualifier, name))
+}
+
+/// Load and refresh the index, saving it when it changed.
+fn open_index(ctx: &PrimitiveContext, options: &SymbolOptions) -> PrimitiveResult<SymbolIndex> {
+    if let Some(path) = &options.path {
+        let resolved = ctx.resolve_path(path);
+        if !ctx.is_path_allowed(&resolved) {
+            return Err(PrimitiveError::PathNotAllowed { path: resolved });
+        }
+    }
+
+    let mut index = SymbolIndex::open(&ctx.working_dir);
+    index.refresh(ctx);
+    // A read-only tree still gets answers, just without the cache
+    if let Err(e) = index.save() {
+        warn!("Could not save symbol index: {}", e);
+    }
+    Ok(index)
+}
+
+fn is_under(ctx: &PrimitiveContext, path: &Path, under: Option<&Path>) -> bool {
+    under.is_none_or(|under| ctx.working_dir.join(path).starts_with(under))
+}
+
+fn finish(
+    ctx: &PrimitiveContext,
+    primitive: &str,
+    name: &str,
+    mut matches: Vec<SymbolMatch>,
+    options: &SymbolOptions,
+    start: Instant,
+) -> SymbolSearchResult {
+    let total_count = matches.len();
+    let max_results = options.max_results.unwrap_or(DEFAULT_MAX_RESULTS);
+    matches.truncate(max_results);
+
+    SymbolSearchResult {
+        truncated: total_count > matches.len(),
+        matches,
+        query: name.to_string(),
+        total_count,
+        metadata: ExecutionMetadata {
+            duration: start.elapsed(),
+            operation_id: ctx.operation_id.clone(),
+            primitive: primitive.to_string(),
+        },
+    }
+}
+
+/// Source lines for match context, reading each file at most once.
+struct FileLines<'a> {
+    ctx: &'a PrimitiveContext,
+    cache: BTreeMap<PathBuf, Vec<String>>,
+}
+
+impl<'a> FileLines<'a> {
+    fn new(ctx: &'a PrimitiveContext) -> Self {
+        Self { ctx, cache: BTreeMap::new() }
+    }
+
+    fn line(&mut self, path: &Path, number: usize) -> String {
+        let lines = self.cache.entry(path.to_path_buf()).or_insert_with(|| {
+            fs::read_to_string(self.ctx.working_dir.join(path))
+                .map(|source| source.lines().map(str::to_string).collect())
+                .unwrap_or_default()
+        });
+        lines.get(number.wrapping_sub(1)).cloned().unwrap_or_default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_find_symbol_and_references() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::create_dir(dir.path().join("src")).unwrap();
+        fs::write(
+            dir.path().join("src/lib.rs"),
+            "pub struct Parser;\n\nimpl Parser {\n    pub fn parse(&self) {}\n}\n\nfn run() {\n    Parser.parse(); // parse\n}\n",
+        )
+        .unwrap();
+        fs::write(dir.path().join("app.py"), "def parse(text):\n    return text\n\nparse('x')\n").unwrap();
+        let ctx = PrimitiveContext::new(dir.path().to_path_buf());
+
+        let found = find_symbol(&ctx, "Parser::parse", None).await.unwrap();
+        assert_eq!(found.matches.len(), 1);
+        let method = &found.matches[0];
+        assert_eq!((method.path.as_path(), method.line_number, method.kind), (Path::new("src/lib.rs"), 4, SymbolKind::Method));
+        assert_eq!(method.scope.as_deref(), Some("Parser"));
+        assert_eq!(method.line_content, "    pub fn parse(&self) {}");
+
+        let all = find_symbol(&ctx, "parse", None).await.unwrap();
+        assert_eq!(all.total_count, 2);
+        let structs = find_symbol(&ctx, "Parser", Some(SymbolOptions::new().kind(SymbolKind::Struct))).await.unwrap();
+        assert_eq!(structs.matches.len(), 1);
+
+        let refs = find_references(&ctx, "parse", None).await.unwrap();
+        let sites: Vec<_> = refs
+            .matches
+            .iter()
+            .map(|m| (m.path.to_string_lossy().into_owned(), m.line_number, m.scope.clone()))
+            .collect();
+        assert_eq!(sites, [("app.py".to_string(), 4, None), ("src/lib.rs".to_string(), 8, Some("run".to_string()))]);
+        assert!(dir.path().join(INDEX_PATH).exists());
+
+        // Edits are picked up on the next query
+        fs::write(dir.path().join("app.py"), "def parse_all(text):\n    return text\n").unwrap();
+        let refs = find_references(&ctx, "parse", None).await.unwrap();
+        assert_eq!(refs.total_count, 1);
+        assert!(find_symbol(&ctx, "parse_all", None).await.unwrap().matches[0].path.ends_with("app.py"));
+    }
+}
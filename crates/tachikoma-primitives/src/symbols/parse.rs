@@ -0,0 +1,683 @@
+//! Definitions and identifiers in Rust, TypeScript/JavaScript and Python.
+//!
+//! A line-oriented parser, not a compiler front end: comments and string
+//! contents are blanked out, definitions are recognized by their leading
+//! keywords, and scopes are tracked by braces (Rust, TypeScript) or by
+//! indentation (Python). That is enough to say where a name is defined,
+//! what it is and what it sits inside of.
+
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
+use std::path::Path;
+use std::sync::OnceLock;
+
+use crate::result::SymbolKind;
+
+/// A language the index understands.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Language {
+    /// Rust.
+    Rust,
+    /// TypeScript and JavaScript.
+    TypeScript,
+    /// Python.
+    Python,
+}
+
+impl Language {
+    /// The language of a file, from its extension.
+    pub fn from_path(path: &Path) -> Option<Self> {
+        match path.extension()?.to_str()? {
+            "rs" => Some(Self::Rust),
+            "ts" | "tsx" | "mts" | "cts" | "js" | "jsx" | "mjs" | "cjs" => Some(Self::TypeScript),
+            "py" | "pyi" => Some(Self::Python),
+            _ => None,
+        }
+    }
+
+    /// Separator between scope names, as the language writes paths.
+    pub fn separator(self) -> &'static str {
+        match self {
+            Self::Rust => "::",
+            Self::TypeScript | Self::Python => ".",
+        }
+    }
+}
+
+/// A definition found in a file.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Definition {
+    /// Name of the item.
+    pub name: String,
+    /// What the item is.
+    pub kind: SymbolKind,
+    /// Line of the name (1-indexed).
+    pub line: usize,
+    /// Column of the name (1-indexed).
+    pub column: usize,
+    /// Last line of the item's body.
+    pub end_line: usize,
+    /// Enclosing definitions, joined with the language's separator.
+    pub scope: Option<String>,
+    /// Visible outside its module: `pub` in Rust, `export`ed in TypeScript,
+    /// not `_`-prefixed in Python.
+    pub public: bool,
+}
+
+impl Definition {
+    /// The name qualified by its scope, e.g. `Parser::parse`.
+    pub fn qualified_name(&self, language: Language) -> String {
+        match &self.scope {
+            Some(scope) => format!("{}{}{}", scope, language.separator(), self.name),
+            None => self.name.clone(),
+        }
+    }
+}
+
+/// What a file defines and which identifiers it uses.
+#[derive(Debug, Clone, Default)]
+pub struct ParsedFile {
+    /// Definitions in source order.
+    pub definitions: Vec<Definition>,
+    /// Every identifier appearing in code.
+    pub identifiers: BTreeSet<String>,
+}
+
+/// Parse a file's definitions and identifiers.
+pub fn parse(language: Language, source: &str) -> ParsedFile {
+    let lines = code_lines(language, source);
+    let definitions = match language {
+        Language::Python => python_definitions(&lines),
+        Language::Rust | Language::TypeScript => braced_definitions(language, &lines),
+    };
+
+    let mut identifiers = BTreeSet::new();
+    for line in &lines {
+        for (_, ident) in identifiers_in(line) {
+            identifiers.insert(ident.to_string());
+        }
+    }
+
+    ParsedFile { definitions, identifiers }
+}
+
+/// Identifiers in a line of code with their byte offsets.
+pub fn identifiers_in(line: &str) -> impl Iterator<Item = (usize, &str)> {
+    regex(&IDENT, r"[A-Za-z_$][A-Za-z0-9_$]*")
+        .find_iter(line)
+        .map(|m| (m.start(), m.as_str()))
+}
+
+/// The source's lines with comments and string contents replaced by spaces.
+///
+/// Columns are preserved, so offsets in the result are offsets in the source.
+pub fn code_lines(language: Language, source: &str) -> Vec<String> {
+    let mut state = Carry::Code;
+    source.lines().map(|line| strip_line(language, line, &mut state)).collect()
+}
+
+/// What an unfinished line leaves open for the next one.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Carry {
+    Code,
+    BlockComment,
+    /// A string that may span lines: Python triple quotes, JS template literals
+    /// and Rust's ordinary strings.
+    String { quote: char, triple: bool },
+}
+
+fn strip_line(language: Language, line: &str, state: &mut Carry) -> String {
+    let chars: Vec<char> = line.chars().collect();
+    let mut out = String::with_capacity(line.len());
+    let mut i = 0;
+
+    // Pushes `c` as spaces of the same byte width, keeping byte offsets stable
+    fn blank(out: &mut String, c: char) {
+        out.extend(std::iter::repeat_n(' ', c.len_utf8()));
+    }
+
+    while i < chars.len() {
+        let c = chars[i];
+        let next = chars.get(i + 1).copied();
+        match *state {
+            Carry::BlockComment => {
+                if c == '*' && next == Some('/') {
+                    out.push_str("  ");
+                    i += 2;
+                    *state = Carry::Code;
+                    continue;
+                }
+                blank(&mut out, c);
+            }
+            Carry::String { quote, triple } => {
+                if c == '\\' {
+                    blank(&mut out, c);
+                    if let Some(escaped) = next {
+                        blank(&mut out, escaped);
+                    }
+                    i += 2;
+                    continue;
+                }
+                let closes = if triple {
+                    c == quote && next == Some(quote) && chars.get(i + 2) == Some(&quote)
+                } else {
+                    c == quote
+                };
+                if closes {
+                    let width = if triple { 3 } else { 1 };
+                    out.extend(std::iter::repeat_n(quote, width));
+                    i += width;
+                    *state = Carry::Code;
+                    continue;
+                }
+                blank(&mut out, c);
+            }
+            Carry::Code => {
+                let line_comment = match language {
+                    Language::Python => c == '#',
+                    Language::Rust | Language::TypeScript => c == '/' && next == Some('/'),
+                };
+                if line_comment {
+                    chars[i..].iter().for_each(|&c| blank(&mut out, c));
+                    break;
+                }
+                if language != Language::Python && c == '/' && next == Some('*') {
+                    out.push_str("  ");
+                    i += 2;
+                    *state = Carry::BlockComment;
+                    continue;
+                }
+                match (language, c) {
+                    (Language::Rust, '\'') => {
+                        // A char literal ('a', '\n', '{'); anything else is a lifetime
+                        let close = if next == Some('\\') {
+                            chars[(i + 3).min(chars.len())..].iter().take(8).position(|&c| c == '\'').map(|p| i + 3 + p)
+                        } else if chars.get(i + 2) == Some(&'\'') {
+                            Some(i + 2)
+                        } else {
+                            None
+                        };
+                        if let Some(close) = close {
+                            out.push('\'');
+                            chars[i + 1..close].iter().for_each(|&c| blank(&mut out, c));
+                            out.push('\'');
+                            i = close + 1;
+                            continue;
+                        }
+                        out.push(c);
+                    }
+                    (Language::Rust, '"') | (Language::TypeScript, '"' | '\'' | '`') | (Language::Python, '"' | '\'') => {
+                        let triple = language == Language::Python
+                            && next == Some(c)
+                            && chars.get(i + 2) == Some(&c);
+                        let width = if triple { 3 } else { 1 };
+                        out.extend(std::iter::repeat_n(c, width));
+                        i += width;
+                        *state = Carry::String { quote: c, triple };
+                        continue;
+                    }
+                    _ => out.push(c),
+                }
+            }
+        }
+        i += 1;
+    }
+
+    // Only some strings may run on to the next line
+    if let Carry::String { quote, triple } = *state {
+        let continues = match language {
+            Language::Rust => true,
+            Language::TypeScript => quote == '`' || line.ends_with('\\'),
+            Language::Python => triple || line.ends_with('\\'),
+        };
+        if !continues {
+            *state = Carry::Code;
+        }
+    }
+    out
+}
+
+static IDENT: OnceLock<Regex> = OnceLock::new();
+static RUST_FN: OnceLock<Regex> = OnceLock::new();
+static RUST_ITEM: OnceLock<Regex> = OnceLock::new();
+static RUST_MACRO: OnceLock<Regex> = OnceLock::new();
+static RUST_IMPL: OnceLock<Regex> = OnceLock::new();
+static TS_FUNCTION: OnceLock<Regex> = OnceLock::new();
+static TS_ITEM: OnceLock<Regex> = OnceLock::new();
+static TS_TYPE: OnceLock<Regex> = OnceLock::new();
+static TS_ARROW: OnceLock<Regex> = OnceLock::new();
+static TS_METHOD: OnceLock<Regex> = OnceLock::new();
+static PY_DEF: OnceLock<Regex> = OnceLock::new();
+static PY_CONST: OnceLock<Regex> = OnceLock::new();
+
+fn regex(cell: &'static OnceLock<Regex>, pattern: &str) -> &'static Regex {
+    cell.get_or_init(|| Regex::new(pattern).expect("valid symbol pattern"))
+}
+
+/// Words that look like a method name at the start of a statement.
+const TS_STATEMENTS: &[&str] = &[
+    "if", "for", "while", "switch", "catch", "return", "function", "with", "do", "else", "new", "typeof", "await",
+    "super", "this", "throw", "yield",
+];
+
+/// A definition recognized at the start of a line.
+struct Found {
+    name: String,
+    kind: SymbolKind,
+    column: usize,
+    /// The definition can own a scope: an item whose body nests others.
+    opens_scope: bool,
+}
+
+fn rust_definition(line: &str, in_container: bool) -> Option<Found> {
+    let found = |m: regex::Match<'_>, kind| Found {
+        name: m.as_str().to_string(),
+        kind,
+        column: m.start() + 1,
+        opens_scope: true,
+    };
+
+    let fn_pattern = concat!(
+        r"^\s*(?:pub(?:\s*\([^)]*\))?\s+)?(?:default\s+)?(?:const\s+)?(?:async\s+)?(?:unsafe\s+)?",
+        r#"(?:extern\s+(?:"[^"]*"\s*)?)?fn\s+([A-Za-z_]\w*)"#
+    );
+    if let Some(c) = regex(&RUST_FN, fn_pattern).captures(line) {
+        let kind = if in_container { SymbolKind::Method } else { SymbolKind::Function };
+        return Some(found(c.get(1)?, kind));
+    }
+    let item_pattern = concat!(
+        r"^\s*(?:pub(?:\s*\([^)]*\))?\s+)?(?:unsafe\s+|auto\s+)*",
+        r"(struct|enum|union|trait|type|mod|const|static)\s+(?:mut\s+)?([A-Za-z_]\w*)"
+    );
+    if let Some(c) = regex(&RUST_ITEM, item_pattern).captures(line) {
+        let kind = match &c[1] {
+            "struct" => SymbolKind::Struct,
+            "enum" => SymbolKind::Enum,
+            "union" => SymbolKind::Union,
+            "trait" => SymbolKind::Trait,
+            "type" => SymbolKind::Type,
+            "mod" => SymbolKind::Module,
+            "const" => SymbolKind::Const,
+            _ => SymbolKind::Static,
+        };
+        return Some(found(c.get(2)?, kind));
+    }
+    if let Some(c) = regex(&RUST_MACRO, r"^\s*macro_rules!\s*([A-Za-z_]\w*)").captures(line) {
+        return Some(found(c.get(1)?, SymbolKind::Macro));
+    }
+    if let Some(c) = regex(&RUST_IMPL, r"^\s*(?:unsafe\s+)?impl\b(.*)").captures(line) {
+        let rest = c.get(1)?;
+        let (offset, name) = impl_self_type(rest.as_str())?;
+        return Some(Found {
+            name: name.to_string(),
+            kind: SymbolKind::Impl,
+            column: rest.start() + offset + 1,
+            opens_scope: true,
+        });
+    }
+    None
+}
+
+/// The self type of an impl header (the text after `impl`) and its offset.
+///
+/// `impl<T> Display for Wrapper<T> {` gives `Wrapper`.
+fn impl_self_type(header: &str) -> Option<(usize, &str)> {
+    // Skip the impl's own generic parameters
+    let mut start = 0;
+    if header.trim_start().starts_with('<') {
+        let mut depth = 0;
+        for (i, c) in header.char_indices() {
+            match c {
+                '<' => depth += 1,
+                '>' => {
+                    depth -= 1;
+                    if depth == 0 {
+                        start = i + 1;
+                        break;
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+
+    let mut end = header[start..].find(['{', ';']).map_or(header.len(), |at| start + at);
+    if let Some(at) = header[start..end].find(" where") {
+        end = start + at;
+    }
+    let mut path_start = header[start..end].find(" for ").map_or(start, |at| start + at + 5);
+    loop {
+        let rest = &header[path_start..end];
+        let trimmed = rest.trim_start_matches([' ', '\t', '&']);
+        let trimmed = trimmed.strip_prefix("dyn ").unwrap_or(trimmed);
+        if trimmed.len() == rest.len() {
+            break;
+        }
+        path_start += rest.len() - trimmed.len();
+    }
+
+    // The last path segment before any generic arguments
+    let path_len = header[path_start..end]
+        .find(|c: char| !(c.is_alphanumeric() || c == '_' || c == ':'))
+        .unwrap_or(end - path_start);
+    let path = &header[path_start..path_start + path_len];
+    let segment_start = path.rfind("::").map_or(0, |at| at + 2);
+    let segment = &path[segment_start..];
+    (!segment.is_empty()).then_some((path_start + segment_start, segment))
+}
+
+fn ts_definition(line: &str, in_class: bool) -> Option<Found> {
+    let found = |m: regex::Match<'_>, kind| Found {
+        name: m.as_str().to_string(),
+        kind,
+        column: m.start() + 1,
+        opens_scope: true,
+    };
+
+    macro_rules! exported {
+        ($($pattern:literal),+) => {
+            concat!(r"^\s*(?:export\s+)?(?:default\s+)?(?:declare\s+)?", $($pattern),+)
+        };
+    }
+    if let Some(c) = regex(&TS_FUNCTION, exported!(r"(?:async\s+)?function\s*\*?\s*([A-Za-z_$][\w$]*)")).captures(line) {
+        return Some(found(c.get(1)?, SymbolKind::Function));
+    }
+    let item_pattern = exported!(r"(?:abstract\s+)?(class|interface|(?:const\s+)?enum|namespace|module)\s+([A-Za-z_$][\w$]*)");
+    if let Some(c) = regex(&TS_ITEM, item_pattern).captures(line) {
+        let kind = match &c[1] {
+            "class" => SymbolKind::Class,
+            "interface" => SymbolKind::Interface,
+            "namespace" | "module" => SymbolKind::Module,
+            _ => SymbolKind::Enum,
+        };
+        return Some(found(c.get(2)?, kind));
+    }
+    if let Some(c) = regex(&TS_TYPE, exported!(r"type\s+([A-Za-z_$][\w$]*)\s*(?:<[^=]*>)?\s*=")).captures(line) {
+        return Some(found(c.get(1)?, SymbolKind::Type));
+    }
+    let arrow_pattern = exported!(
+        r"(?:const|let|var)\s+([A-Za-z_$][\w$]*)\s*(?::[^=]+)?=\s*(?:async\s+)?",
+        r"(?:function\b|\([^)]*\)\s*(?::[^=]+)?=>|[A-Za-z_$][\w$]*\s*=>)"
+    );
+    if let Some(c) = regex(&TS_ARROW, arrow_pattern).captures(line) {
+        return Some(found(c.get(1)?, SymbolKind::Function));
+    }
+    if in_class {
+        let c = regex(&TS_METHOD, concat!(
+            r"^\s*(?:(?:public|private|protected|static|readonly|abstract|override|async|get|set)\s+)*",
+            r"\*?\s*(#?[A-Za-z_$][\w$]*)\s*(?:<[^>]*>)?\s*\("
+        ))
+        .captures(line)?;
+        let name = c.get(1)?;
+        if TS_STATEMENTS.contains(&name.as_str()) {
+            return None;
+        }
+        return Some(found(name, SymbolKind::Method));
+    }
+    None
+}
+
+/// An open definition whose body hasn't closed yet.
+struct OpenScope {
+    /// Index into the definitions.
+    definition: usize,
+    /// Brace depth inside the body.
+    depth: usize,
+}
+
+fn braced_definitions(language: Language, lines: &[String]) -> Vec<Definition> {
+    let mut definitions: Vec<Definition> = Vec::new();
+    let mut open: Vec<OpenScope> = Vec::new();
+    // A definition waiting for the `{` of its body, and the depth it was found at
+    let mut pending: Option<(usize, usize)> = None;
+    let mut depth = 0usize;
+
+    for (index, line) in lines.iter().enumerate() {
+        let number = index + 1;
+        let innermost = open.last().map(|o| &definitions[o.definition]);
+        // Methods only sit directly in the body of their container
+        let in_container = innermost.is_some_and(|d| {
+            matches!(d.kind, SymbolKind::Impl | SymbolKind::Trait | SymbolKind::Class)
+                && open.last().is_some_and(|o| o.depth == depth)
+        });
+        let found = match language {
+            Language::Rust => rust_definition(line, in_container),
+            _ => ts_definition(line, in_container),
+        };
+
+        let mut name_end = 0;
+        if let Some(found) = found {
+            if let Some((unfinished, _)) = pending.take() {
+                definitions[unfinished].end_line = definitions[unfinished].line;
+            }
+            let scope = scope_name(language, &definitions, &open);
+            name_end = found.column - 1 + found.name.len();
+            let public = match language {
+                Language::Rust => {
+                    let line = line.trim_start();
+                    found.kind != SymbolKind::Impl && (line.starts_with("pub ") || line.starts_with("pub("))
+                }
+                _ => line.trim_start().starts_with("export "),
+            };
+            definitions.push(Definition {
+                name: found.name,
+                kind: found.kind,
+                line: number,
+                column: found.column,
+                end_line: number,
+                scope,
+                public,
+            });
+            if found.opens_scope {
+                pending = Some((definitions.len() - 1, depth));
+            }
+        }
+
+        for (offset, c) in line.char_indices() {
+            match c {
+                '{' => {
+                    depth += 1;
+                    if let Some((definition, _)) = pending.filter(|_| offset >= name_end) {
+                        open.push(OpenScope { definition, depth });
+                        pending = None;
+                    }
+                }
+                '}' => {
+                    if open.last().is_some_and(|o| o.depth == depth) {
+                        let closed = open.pop().expect("checked above");
+                        definitions[closed.definition].end_line = number;
+                    }
+                    depth = depth.saturating_sub(1);
+                }
+                // `struct Unit;`, a trait method without a body, `type A = B;`
+                ';' if offset >= name_end => {
+                    if let Some((definition, at)) = pending {
+                        if at == depth {
+                            definitions[definition].end_line = number;
+                            pending = None;
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+
+    let last = lines.len().max(1);
+    for scope in open {
+        definitions[scope.definition].end_line = last;
+    }
+    definitions
+}
+
+fn python_definitions(lines: &[String]) -> Vec<Definition> {
+    let mut definitions: Vec<Definition> = Vec::new();
+    // (indentation, definition index) of open defs and classes
+    let mut open: Vec<(usize, usize)> = Vec::new();
+    let mut last_code_line = 0;
+    let mut brackets = 0i32;
+
+    for (index, line) in lines.iter().enumerate() {
+        let number = index + 1;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let continuation = brackets > 0;
+        for c in line.chars() {
+            match c {
+                '(' | '[' | '{' => brackets += 1,
+                ')' | ']' | '}' => brackets -= 1,
+                _ => {}
+            }
+        }
+        if continuation {
+            last_code_line = number;
+            continue;
+        }
+
+        let indent = line.len() - line.trim_start().len();
+        while open.last().is_some_and(|&(open_indent, _)| open_indent >= indent) {
+            let (_, definition) = open.pop().expect("checked above");
+            definitions[definition].end_line = last_code_line;
+        }
+        last_code_line = number;
+
+        let in_class = open.last().is_some_and(|&(_, d)| definitions[d].kind == SymbolKind::Class);
+        let def = regex(&PY_DEF, r"^\s*(?:async\s+def|def|(class))\s+([A-Za-z_]\w*)").captures(line);
+        if let Some(c) = def {
+            let name = c.get(2).expect("group always matches");
+            let kind = if c.get(1).is_some() {
+                SymbolKind::Class
+            } else if in_class {
+                SymbolKind::Method
+            } else {
+                SymbolKind::Function
+            };
+            let scope = (!open.is_empty()).then(|| {
+                open.iter().map(|&(_, d)| definitions[d].name.as_str()).collect::<Vec<_>>().join(".")
+            });
+            definitions.push(Definition {
+                name: name.as_str().to_string(),
+                kind,
+                line: number,
+                column: name.start() + 1,
+                end_line: number,
+                scope,
+                public: !name.as_str().starts_with('_'),
+            });
+            open.push((indent, definitions.len() - 1));
+        } else if indent == 0 {
+            if let Some(c) = regex(&PY_CONST, r"^([A-Z][A-Z0-9_]*)\s*(?::[^=]+)?=[^=]").captures(line) {
+                let name = c.get(1).expect("group always matches");
+                definitions.push(Definition {
+                    name: name.as_str().to_string(),
+                    kind: SymbolKind::Const,
+                    line: number,
+                    column: 1,
+                    end_line: number,
+                    scope: None,
+                    public: true,
+                });
+            }
+        }
+    }
+
+    for (_, definition) in open {
+        definitions[definition].end_line = last_code_line;
+    }
+    definitions
+}
+
+fn scope_name(language: Language, definitions: &[Definition], open: &[OpenScope]) -> Option<String> {
+    if open.is_empty() {
+        return None;
+    }
+    let names: Vec<&str> = open.iter().map(|o| definitions[o.definition].name.as_str()).collect();
+    Some(names.join(language.separator()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn summary(language: Language, source: &str) -> Vec<(String, SymbolKind, usize, usize, Option<String>)> {
+        parse(language, source)
+            .definitions
+            .into_iter()
+            .map(|d| (d.name, d.kind, d.line, d.end_line, d.scope))
+            .collect()
+    }
+
+    #[test]
+    fn test_rust_definitions_and_scopes() {
+        let source = r#"/// A parser.
+pub struct Parser {
+    input: String, // fn not_a_fn() {
+}
+
+impl<'a> fmt::Display for Parser {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{{ fn fake() }}")
+    }
+}
+
+pub trait Parse {
+    fn parse(
+        &self,
+    ) -> Result<(), ()>;
+}
+
+mod tests {
+    const BRACE: char = '{';
+    #[test]
+    fn test_parse() {}
+}
+"#;
+        let s = |v: &str| Some(v.to_string());
+        assert_eq!(
+            summary(Language::Rust, source),
+            vec![
+                ("Parser".to_string(), SymbolKind::Struct, 2, 4, None),
+                ("Parser".to_string(), SymbolKind::Impl, 6, 10, None),
+                ("fmt".to_string(), SymbolKind::Method, 7, 9, s("Parser")),
+                ("Parse".to_string(), SymbolKind::Trait, 12, 16, None),
+                ("parse".to_string(), SymbolKind::Method, 13, 15, s("Parse")),
+                ("tests".to_string(), SymbolKind::Module, 18, 22, None),
+                ("BRACE".to_string(), SymbolKind::Const, 19, 19, s("tests")),
+                ("test_parse".to_string(), SymbolKind::Function, 21, 21, s("tests")),
+            ]
+        );
+        let parsed = parse(Language::Rust, source);
+        assert!(!parsed.identifiers.contains("not_a_fn"));
+        let public: Vec<&str> = parsed.definitions.iter().filter(|d| d.public).map(|d| d.name.as_str()).collect();
+        assert_eq!(public, ["Parser", "Parse"]);
+    }
+
+    #[test]
+    fn test_typescript_and_python_definitions() {
+        let ts = "export class Store {\n  private items = [];\n  async load(id: string): Promise<void> {\n    if (id) {}\n  }\n}\nexport const render = (x: number) => {\n  return `${x}`;\n};\ninterface Props {}\n";
+        let s = |v: &str| Some(v.to_string());
+        assert_eq!(
+            summary(Language::TypeScript, ts),
+            vec![
+                ("Store".to_string(), SymbolKind::Class, 1, 6, None),
+                ("load".to_string(), SymbolKind::Method, 3, 5, s("Store")),
+                ("render".to_string(), SymbolKind::Function, 7, 9, None),
+                ("Props".to_string(), SymbolKind::Interface, 10, 10, None),
+            ]
+        );
+
+        let py = "MAX_ITEMS = 10\n\nclass Store:\n    \"\"\"Holds items.\n\n    def not_a_method(self):\n    \"\"\"\n    def load(self,\n  id):\n        return id\n\n\ndef main():\n    pass\n";
+        assert_eq!(
+            summary(Language::Python, py),
+            vec![
+                ("MAX_ITEMS".to_string(), SymbolKind::Const, 1, 1, None),
+                ("Store".to_string(), SymbolKind::Class, 3, 10, None),
+                ("load".to_string(), SymbolKind::Method, 8, 10, s("Store")),
+                ("main".to_string(), SymbolKind::Function, 13, 14, None),
+            ]
+        );
+    }
+}
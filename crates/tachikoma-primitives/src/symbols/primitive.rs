@@ -0,0 +1,125 @@
+//! `find_symbol` and `find_references` as registry primitives.
+
+use async_trait::async_trait;
+use serde::Deserialize;
+use serde_json::{json, Value};
+
+use super::{find_references, find_symbol, SymbolOptions};
+use crate::{
+    context::PrimitiveContext,
+    error::PrimitiveResult,
+    result::{SymbolKind, SymbolSearchResult},
+    traits::Primitive,
+};
+
+/// JSON input for `find_symbol`.
+#[derive(Debug, Clone, Deserialize)]
+pub struct FindSymbolInput {
+    /// Symbol name, optionally qualified as in `Parser::parse`.
+    pub name: String,
+    /// Only definitions of this kind.
+    #[serde(default)]
+    pub kind: Option<SymbolKind>,
+    /// Only files under this path, relative to the working directory.
+    #[serde(default)]
+    pub path: Option<String>,
+    /// Maximum number of matches.
+    #[serde(default)]
+    pub max_results: Option<usize>,
+}
+
+/// JSON input for `find_references`.
+#[derive(Debug, Clone, Deserialize)]
+pub struct FindReferencesInput {
+    /// Symbol name.
+    pub name: String,
+    /// Only files under this path, relative to the working directory.
+    #[serde(default)]
+    pub path: Option<String>,
+    /// Maximum number of matches.
+    #[serde(default)]
+    pub max_results: Option<usize>,
+}
+
+fn options(kind: Option<SymbolKind>, path: Option<&str>, max_results: Option<usize>) -> SymbolOptions {
+    SymbolOptions {
+        kind,
+        path: path.map(str::to_string),
+        max_results,
+    }
+}
+
+/// The `find_symbol` primitive.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct FindSymbolPrimitive;
+
+#[async_trait]
+impl Primitive for FindSymbolPrimitive {
+    type Input = FindSymbolInput;
+    type Output = SymbolSearchResult;
+
+    fn name(&self) -> &'static str {
+        "find_symbol"
+    }
+
+    fn description(&self) -> &'static str {
+        "Find where a function, type, trait, class or other symbol is defined (Rust, TypeScript, Python)."
+    }
+
+    async fn execute(&self, ctx: &PrimitiveContext, input: FindSymbolInput) -> PrimitiveResult<SymbolSearchResult> {
+        let options = options(input.kind, input.path.as_deref(), input.max_results);
+        find_symbol(ctx, &input.name, Some(options)).await
+    }
+
+    fn input_schema(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "name": {"type": "string", "description": "Symbol name, optionally qualified (e.g. \"Parser::parse\" or \"Store.load\")"},
+                "kind": {
+                    "type": "string",
+                    "enum": ["function", "method", "struct", "enum", "union", "trait", "impl", "class", "interface", "type", "module", "const", "static", "macro"],
+                    "description": "Only definitions of this kind"
+                },
+                "path": {"type": "string", "description": "Only files under this path (default: the working directory)"},
+                "max_results": {"type": "integer", "minimum": 1, "description": "Maximum number of matches"}
+            },
+            "required": ["name"]
+        })
+    }
+}
+
+/// The `find_references` primitive.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct FindReferencesPrimitive;
+
+#[async_trait]
+impl Primitive for FindReferencesPrimitive {
+    type Input = FindReferencesInput;
+    type Output = SymbolSearchResult;
+
+    fn name(&self) -> &'static str {
+        "find_references"
+    }
+
+    fn description(&self) -> &'static str {
+        "Find where a symbol is used, with the function or type each use is in (Rust, TypeScript, Python)."
+    }
+
+    async fn execute(&self, ctx: &PrimitiveContext, input: FindReferencesInput) -> PrimitiveResult<SymbolSearchResult> {
+        let options = options(None, input.path.as_deref(), input.max_results);
+        find_references(ctx, &input.name, Some(options)).await
+    }
+
+    fn input_schema(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "name": {"type": "string", "description": "Symbol name"},
+                "path": {"type": "string", "description": "Only files under this path (default: the working directory)"},
+                "max_results": {"type": "integer", "minimum": 1, "description": "Maximum number of matches"}
+            },
+            "required": ["name"]
+        })
+    }
+}
@@ -10,6 +10,19 @@ use crate::{
     error::PrimitiveResult,
 };
 
+#[cfg(feature = "read-file")]
+use crate::read_file::ReadFilePrimitive;
+#[cfg(feature = "list-files")]
+use crate::list_files::ListFilesPrimitive;
+#[cfg(feature = "bash")]
+use crate::bash::{BashPrimitive, ProcessPrimitive};
+#[cfg(feature = "edit-file")]
+use crate::edit_file::{EditFilePrimitive, MultiEditPrimitive};
+#[cfg(feature = "code-search")]
+use crate::code_search::CodeSearchPrimitive;
+#[cfg(feature = "symbols")]
+use crate::symbols::{FindReferencesPrimitive, FindSymbolPrimitive};
+
 /// Common trait for all primitives.
 #[async_trait]
 pub trait Primitive: Send + Sync {
@@ -85,6 +98,7 @@ pub struct PrimitiveWrapper<P> {
 }
 
 impl<P> PrimitiveWrapper<P> {
+    /// Wrap a primitive.
     pub fn new(primitive: P) -> Self {
         Self { inner: primitive }
     }
@@ -182,12 +196,23 @@ impl PrimitiveRegistry {
         
         #[cfg(feature = "bash")]
         self.register(BashPrimitive);
+        #[cfg(feature = "bash")]
+        self.register(ProcessPrimitive);
         
         #[cfg(feature = "edit-file")]
         self.register(EditFilePrimitive);
+
+        #[cfg(feature = "edit-file")]
+        self.register(MultiEditPrimitive);
         
         #[cfg(feature = "code-search")]
         self.register(CodeSearchPrimitive);
+
+        #[cfg(feature = "symbols")]
+        self.register(FindSymbolPrimitive);
+
+        #[cfg(feature = "symbols")]
+        self.register(FindReferencesPrimitive);
     }
 
     /// Get a primitive by name.
@@ -211,12 +236,20 @@ impl PrimitiveRegistry {
         primitive.execute_json(ctx, input).await
     }
 
-    /// Get all MCP tool definitions.
+    /// Get all MCP tool definitions, sorted by name.
     pub fn mcp_tools(&self) -> Vec<McpToolDefinition> {
-        self.primitives
+        let mut tools: Vec<McpToolDefinition> = self
+            .primitives
             .values()
             .map(|p| p.mcp_tool_definition())
-            .collect()
+            .collect();
+        tools.sort_by(|a, b| a.name.cmp(&b.name));
+        tools
+    }
+
+    /// Keep only the primitives whose names pass `keep`.
+    pub fn retain(&mut self, mut keep: impl FnMut(&str) -> bool) {
+        self.primitives.retain(|name, _| keep(name));
     }
 
     /// List all primitive names.
@@ -229,4 +262,33 @@ impl Default for PrimitiveRegistry {
     fn default() -> Self {
         Self::with_defaults()
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(feature = "all")]
+    #[tokio::test]
+    async fn test_default_registry_executes_json() {
+        let mut registry = PrimitiveRegistry::with_defaults();
+        let names: Vec<String> = registry.mcp_tools().into_iter().map(|t| t.name).collect();
+        assert_eq!(names, ["bash", "code_search", "edit_file", "find_references", "find_symbol", "list_files", "multi_edit", "process", "read_file"]);
+
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("notes.txt"), "one\ntwo\nthree\n").unwrap();
+        let ctx = PrimitiveContext::new(dir.path().to_path_buf());
+
+        let output = registry
+            .execute("read_file", &ctx, serde_json::json!({"path": "notes.txt", "start_line": 2, "end_line": 2}))
+            .await
+            .unwrap();
+        assert_eq!(output["content"].as_str().unwrap().trim(), "2\ttwo");
+
+        let err = registry.execute("read_file", &ctx, serde_json::json!({"file": "notes.txt"})).await;
+        assert!(err.unwrap_err().to_string().contains("missing field `path`"));
+
+        registry.retain(|name| name != "bash");
+        assert!(registry.get("bash").is_none());
+    }
+}
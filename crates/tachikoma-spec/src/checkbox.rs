@@ -247,6 +247,14 @@ impl CheckboxTracker {
     ) -> Result<(), CheckboxError> {
         let mut affected_specs = std::collections::HashSet::new();
 
+        // Check every spec can be written before changing anything
+        for (id, checked) in &updates {
+            let changes = self.checkboxes.get(id).is_some_and(|cb| cb.checked != *checked);
+            if changes && !self.spec_paths.contains_key(&id.spec_id) {
+                return Err(CheckboxError::SpecNotLoaded(id.spec_id));
+            }
+        }
+
         for (id, checked) in updates {
             if let Some(checkbox) = self.checkboxes.get_mut(&id) {
                 if checkbox.checked != checked {
@@ -80,8 +80,8 @@ impl CitationParser {
             CitationFormat::ImplementsTag,
             CitationFormat::PerTag,
             CitationFormat::AtSpec,
-            CitationFormat::SpecFilename,
             CitationFormat::MarkdownLink,
+            CitationFormat::SpecFilename,
             CitationFormat::SpecId, // Most general last
         ];
 
@@ -110,9 +110,9 @@ impl CitationParser {
 
         for (format, regex) in &self.patterns {
             for mat in regex.find_iter(line) {
-                // Skip if this position was already matched by a more specific pattern
+                // Skip if this text overlaps a match of a more specific pattern
                 let pos = (mat.start(), mat.end());
-                if matched_positions.iter().any(|&(s, e)| pos.0 >= s && pos.1 <= e) {
+                if matched_positions.iter().any(|&(s, e)| pos.0 < e && s < pos.1) {
                     continue;
                 }
 
@@ -377,7 +377,11 @@ impl PlanParser {
             }
 
             // Parse task items (numbered or bulleted lists)
-            if (line.starts_with("1.") || line.starts_with("- ") || line.starts_with("* "))
+            let numbered = line
+                .trim_start_matches(|c: char| c.is_ascii_digit())
+                .starts_with(". ")
+                && line.starts_with(|c: char| c.is_ascii_digit());
+            if (numbered || line.starts_with("- ") || line.starts_with("* "))
                 && current_phase.is_some()
             {
                 let task_text = line
@@ -162,7 +162,7 @@ impl SpecParser {
                     r"spec:(\d{3})"
                 ).unwrap(),
                 spec_ref_filename: Regex::new(
-                    r"(\d{3})-[\w-]+\.md"
+                    r"\b(\d{3})-[a-z][\w-]*(?:\.md)?"
                 ).unwrap(),
                 spec_ref_link: Regex::new(
                     r"\[.*?[Ss]pec\s*(\d+).*?\]\([^)]+\)"
@@ -698,7 +698,9 @@ References:
 
     #[test]
     fn test_unclosed_code_block() {
-        let content = r#"# Test Spec
+        let content = r#"# Spec 1: Test Spec
+
+- **Status**: Planned
 
 ## Code
 
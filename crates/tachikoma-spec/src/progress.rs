@@ -746,7 +746,7 @@ mod tests {
         let bar = calc.render_progress_bar(50);
         assert!(bar.contains("██████████"));
         assert!(bar.contains("░░░░░░░░░░"));
-        assert_eq!(bar.len(), 22); // [20 chars]
+        assert_eq!(bar.chars().count(), 22); // [20 chars]
 
         let bar_full = calc.render_progress_bar(100);
         assert_eq!(bar_full, "[████████████████████]");
@@ -350,19 +350,20 @@ impl ReadmeLookup {
     /// Parse list entry format
     fn parse_list_entry(&self, line: &str) -> Option<ReadmeSpecEntry> {
         let re = regex::Regex::new(
-            r"^\s*[-*]\s*\[([^\]]+)\.md\]\(([^)]+)\)\s*[-:]*\s*(\w+)?"
+            r"^\s*[-*]\s*\[([^\]]+)\.md\]\(([^)]+)\)\s*[-:]*\s*([\w -]+)?"
         ).ok()?;
 
         if let Some(caps) = re.captures(line) {
             let filename = caps.get(1)?.as_str();
             let link = caps.get(2)?.as_str().to_string();
             let status = caps.get(3)
-                .map(|m| SpecStatus::from_str(m.as_str()))
+                .map(|m| SpecStatus::from_str(m.as_str().trim()))
                 .unwrap_or(SpecStatus::Planned);
 
             // Extract ID from filename (e.g., "116-spec-directory")
-            let id: u32 = filename.split('-').next()?.parse().ok()?;
-            let title = filename[3..].replace('-', " "); // Skip "116-" prefix
+            let (id, title) = filename.split_once('-')?;
+            let id: u32 = id.parse().ok()?;
+            let title = title.to_string();
 
             Some(ReadmeSpecEntry {
                 id,
@@ -240,6 +240,10 @@ impl TemplateEngine {
 
         for cap in re.captures_iter(content) {
             let name = cap[1].to_string();
+            // `this` is the current item inside an `{{#each}}` block
+            if name == "this" {
+                continue;
+            }
             if !variables.iter().any(|v: &TemplateVariable| v.name == name) {
                 variables.push(TemplateVariable {
                     name: name.clone(),
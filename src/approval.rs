@@ -0,0 +1,534 @@
+//! Approval - Attended mode for destructive tool calls
+//!
+//! With `--attended` (or `policies.attended_by_default`), every `bash` and
+//! `edit_file` call is shown to a human before it runs: the command, or the
+//! edit as a diff. The reply is one of
+//!
+//! - approve: run the call as proposed
+//! - deny (with a reason): the call is skipped and the reason goes back to
+//!   the model as the tool error
+//! - edit: run a corrected call instead (a different command, or the edit
+//!   after a pass through `$EDITOR`)
+//!
+//! Tools from MCP servers ask too, since they can do anything, unless their
+//! server is configured with `auto_approve: true`.
+//!
+//! Reads, searches and beads calls never ask. Neither do bash commands that
+//! match an auto-approve rule: a built-in list of build/test/inspection
+//! commands plus `policies.auto_approve`. The command is split into words
+//! the way the shell would, and a rule matches when its words start the
+//! command. Anything with shell syntax that could chain, background,
+//! redirect or expand (`;`, `&`, `|`, `>`, `$`, backticks) always asks, as
+//! do the forms of a safe command that write (`git diff --output`), change
+//! state (`git branch -D`) or run code the command names (`cargo --config`,
+//! `go test -exec`, `pytest -p`).
+
+use async_trait::async_trait;
+use serde_json::Value;
+use std::io::{BufRead, Write};
+use std::sync::Arc;
+use tokio::sync::{mpsc, oneshot};
+
+use crate::config::{McpConfig, PolicyConfig};
+use crate::mcp;
+
+/// Commands that never need approval
+const SAFE_COMMANDS: &[&str] = &[
+    "cargo check",
+    "cargo build",
+    "cargo test",
+    "cargo clippy",
+    "cargo fmt --check",
+    "cargo tree",
+    "git status",
+    "git diff",
+    "git log",
+    "git show",
+    "git branch",
+    "npm test",
+    "npm run build",
+    "npm run lint",
+    "dotnet build",
+    "dotnet test",
+    "go build",
+    "go test",
+    "go vet",
+    "pytest",
+    "ls",
+    "pwd",
+];
+
+/// Shell syntax that could smuggle a second command past a rule, or expand
+/// into one
+const SHELL_SYNTAX: &[char] = &[';', '&', '|', '>', '<', '`', '$', '(', ')', '\n', '\r'];
+
+/// Arguments that make a safe command write files or run code of the
+/// caller's choosing, keyed by the words a rule starts with
+const UNSAFE_ARGS: &[(&str, &[&str])] = &[
+    ("git diff", &["--output"]),
+    ("git log", &["--output"]),
+    ("git show", &["--output"]),
+    // Config overrides and other manifests can set a runner or rustc
+    // wrapper, and `+toolchain` picks another compiler
+    ("cargo", &["--config", "-Z", "--manifest-path", "+"]),
+    // Go takes its flags with one dash or two
+    ("go", &["-exec", "--exec", "-toolexec", "--toolexec"]),
+    // Plugins and config files are Python that runs at startup
+    ("pytest", &["-p", "-c", "--config-file", "--rootdir"]),
+];
+
+/// Safe commands whose only safe arguments are these (the listing forms)
+const LISTING_ONLY: &[(&str, &[&str])] = &[(
+    "git branch",
+    &["--list", "-a", "--all", "-r", "--remotes", "-v", "-vv", "--verbose"],
+)];
+
+/// Tools that change files or run commands
+const GATED_TOOLS: &[&str] = &["bash", "process", "edit_file", "multi_edit"];
+
+/// A tool call waiting for a human
+#[derive(Debug, Clone)]
+pub struct ApprovalRequest {
+    pub tool: String,
+    pub input: Value,
+    /// The command, or the edit as a diff
+    pub preview: String,
+}
+
+impl ApprovalRequest {
+    pub fn new(tool: &str, input: &Value) -> Self {
+        Self {
+            tool: tool.to_string(),
+            input: input.clone(),
+            preview: preview(tool, input),
+        }
+    }
+
+    /// The bash command being proposed
+    pub fn command(&self) -> Option<&str> {
+        self.input.get("command").and_then(|v| v.as_str())
+    }
+}
+
+/// A human's reply
+#[derive(Debug, Clone, PartialEq)]
+pub enum Decision {
+    Approve,
+    Deny(String),
+    /// Run this input instead
+    Edit(Value),
+}
+
+/// Asks a human about a tool call
+#[async_trait]
+pub trait Approver: Send + Sync {
+    async fn review(&self, request: ApprovalRequest) -> Decision;
+}
+
+/// Which calls need a human
+#[derive(Debug, Clone)]
+pub struct ApprovalPolicy {
+    auto_approve: Vec<String>,
+    /// MCP servers whose tools run without asking
+    trusted_servers: Vec<String>,
+}
+
+impl ApprovalPolicy {
+    pub fn from_config(policies: &PolicyConfig, mcp: &McpConfig) -> Self {
+        let mut auto_approve: Vec<String> = SAFE_COMMANDS.iter().map(|s| s.to_string()).collect();
+        auto_approve.extend(policies.auto_approve.iter().map(|s| s.trim().to_string()));
+        let trusted_servers = mcp
+            .servers
+            .iter()
+            .filter(|(_, server)| server.auto_approve)
+            .map(|(name, _)| name.clone())
+            .collect();
+        Self {
+            auto_approve,
+            trusted_servers,
+        }
+    }
+
+    pub fn needs_approval(&self, tool: &str, input: &Value) -> bool {
+        if let Some((server, _)) = tool.split_once(mcp::SEPARATOR) {
+            return !self.trusted_servers.iter().any(|trusted| trusted == server);
+        }
+        if !GATED_TOOLS.contains(&tool) {
+            return false;
+        }
+        let command = input.get("command").and_then(|v| v.as_str());
+        match tool {
+            "bash" => command.is_none_or(|command| !self.is_auto_approved(command)),
+            // Reading, waiting and killing only touch what was approved at start
+            "process" => match input.get("action").and_then(|v| v.as_str()) {
+                Some("start") => command.is_none_or(|command| !self.is_auto_approved(command)),
+                Some("read" | "wait" | "kill") => false,
+                _ => true,
+            },
+            _ => true,
+        }
+    }
+
+    fn is_auto_approved(&self, command: &str) -> bool {
+        if command.contains(SHELL_SYNTAX) {
+            return false;
+        }
+        // Unbalanced quotes: the shell would read more than we can see
+        let Some(words) = shlex::split(command) else {
+            return false;
+        };
+        self.auto_approve.iter().any(|rule| match shlex::split(rule) {
+            Some(rule_words) if !rule_words.is_empty() && words.starts_with(&rule_words) => {
+                safe_args(rule, &words[rule_words.len()..])
+            }
+            _ => false,
+        })
+    }
+}
+
+/// Whether the arguments after a matched rule keep the command read-only
+fn safe_args(rule: &str, args: &[String]) -> bool {
+    if let Some((_, allowed)) = LISTING_ONLY.iter().find(|(r, _)| *r == rule) {
+        return args.iter().all(|arg| allowed.contains(&arg.as_str()));
+    }
+    let rule_words = rule.split_whitespace().collect::<Vec<_>>();
+    !UNSAFE_ARGS
+        .iter()
+        .filter(|(prefix, _)| rule_words.starts_with(&prefix.split_whitespace().collect::<Vec<_>>()))
+        .any(|(_, flags)| args.iter().any(|arg| flags.iter().any(|flag| is_flag(arg, flag))))
+}
+
+/// Whether `arg` passes `flag`, with its value attached or not, or as part
+/// of a cluster of one-letter flags (`-vp plugin`)
+fn is_flag(arg: &str, flag: &str) -> bool {
+    if arg.starts_with(flag) {
+        return true;
+    }
+    match (flag.strip_prefix('-'), arg.strip_prefix('-')) {
+        (Some(letter), Some(cluster)) if letter.len() == 1 && !cluster.starts_with('-') => {
+            cluster.contains(letter)
+        }
+        _ => false,
+    }
+}
+
+/// The approval gate handed to the agentic loop
+#[derive(Clone)]
+pub struct Approval {
+    policy: ApprovalPolicy,
+    approver: Arc<dyn Approver>,
+}
+
+impl Approval {
+    pub fn new(policy: ApprovalPolicy, approver: Arc<dyn Approver>) -> Self {
+        Self { policy, approver }
+    }
+
+    /// Decide what to run for a tool call
+    ///
+    /// Returns the input to execute (the original, or the human's edit), or
+    /// the message to send back to the model when the call is denied.
+    pub async fn check(&self, tool: &str, input: &Value) -> Result<Value, String> {
+        if !self.policy.needs_approval(tool, input) {
+            return Ok(input.clone());
+        }
+
+        match self.approver.review(ApprovalRequest::new(tool, input)).await {
+            Decision::Approve => Ok(input.clone()),
+            Decision::Edit(edited) => Ok(edited),
+            Decision::Deny(reason) if reason.trim().is_empty() => {
+                Err("Denied by the user. Try a different approach.".to_string())
+            }
+            Decision::Deny(reason) => Err(format!("Denied by the user: {}", reason.trim())),
+        }
+    }
+}
+
+/// Note prepended to a tool result when the human changed the call
+pub fn edited_note(tool: &str, original: &Value, edited: &Value) -> Option<String> {
+    if original == edited {
+        return None;
+    }
+    Some(match (tool, edited.get("command").and_then(|v| v.as_str())) {
+        ("bash" | "process", Some(command)) => format!("[The user edited this command before running it: {}]\n", command),
+        _ => "[The user edited this call before running it]\n".to_string(),
+    })
+}
+
+/// The command, or a diff of the edit
+fn preview(tool: &str, input: &Value) -> String {
+    let field = |name: &str| input.get(name).and_then(|v| v.as_str()).unwrap_or_default();
+
+    match tool {
+        "bash" => {
+            let mut preview = format!("$ {}", field("command"));
+            if !field("working_dir").is_empty() {
+                preview.push_str(&format!("\n  (in {})", field("working_dir")));
+            }
+            preview
+        }
+        "process" => match field("action") {
+            "start" => {
+                let mut preview = format!("$ {} &", field("command"));
+                if !field("working_dir").is_empty() {
+                    preview.push_str(&format!("\n  (in {})", field("working_dir")));
+                }
+                preview
+            }
+            "write" => format!("{} < {:?}", field("id"), field("input")),
+            action => format!("{} {}", action, field("id")),
+        },
+        "edit_file" => edit_preview(input),
+        "multi_edit" => match input.get("edits").and_then(|v| v.as_array()) {
+            Some(edits) if !edits.is_empty() => edits.iter().map(edit_preview).collect(),
+            _ => field("patch").to_string(),
+        },
+        _ => serde_json::to_string_pretty(input).unwrap_or_default(),
+    }
+}
+
+/// One search-and-replace edit as a diff
+fn edit_preview(edit: &Value) -> String {
+    let field = |name: &str| edit.get(name).and_then(|v| v.as_str()).unwrap_or_default();
+
+    let old = field("old_string");
+    let mut preview = if old.is_empty() {
+        format!("+++ {} (new file)\n", field("path"))
+    } else {
+        format!("--- {}\n+++ {}\n", field("path"), field("path"))
+    };
+    for line in old.lines() {
+        preview.push_str(&format!("-{}\n", line));
+    }
+    for line in field("new_string").lines() {
+        preview.push_str(&format!("+{}\n", line));
+    }
+    preview
+}
+
+/// Prompts on the terminal (`ralph run --attended`)
+pub struct ConsoleApprover;
+
+#[async_trait]
+impl Approver for ConsoleApprover {
+    async fn review(&self, request: ApprovalRequest) -> Decision {
+        tokio::task::spawn_blocking(move || prompt(&request))
+            .await
+            .unwrap_or_else(|e| Decision::Deny(format!("approval prompt failed: {}", e)))
+    }
+}
+
+fn prompt(request: &ApprovalRequest) -> Decision {
+    let stdin = std::io::stdin();
+    let mut lines = stdin.lock().lines();
+    let mut ask = |question: &str| -> Option<String> {
+        print!("{}", question);
+        let _ = std::io::stdout().flush();
+        lines.next().and_then(|l| l.ok())
+    };
+
+    println!("\n──── Approval needed: {} ────", request.tool);
+    println!("{}", request.preview.trim_end());
+
+    loop {
+        let Some(answer) = ask("[a]pprove / [d]eny / [e]dit > ") else {
+            return Decision::Deny("no one answered the approval prompt (stdin closed)".to_string());
+        };
+        match answer.trim() {
+            "a" | "approve" | "y" | "yes" => return Decision::Approve,
+            "d" | "deny" | "n" | "no" => {
+                let reason = ask("Reason (sent to the model) > ").unwrap_or_default();
+                return Decision::Deny(reason);
+            }
+            "e" | "edit" => match request.tool.as_str() {
+                "bash" | "process" if request.command().is_some() => {
+                    let command = ask("Command > ").unwrap_or_default();
+                    if command.trim().is_empty() {
+                        continue;
+                    }
+                    let mut input = request.input.clone();
+                    input["command"] = command.trim().into();
+                    return Decision::Edit(input);
+                }
+                _ => match edit_in_editor(&request.input) {
+                    Ok(input) => return Decision::Edit(input),
+                    Err(e) => println!("Edit failed: {}", e),
+                },
+            },
+            _ => {}
+        }
+    }
+}
+
+/// Open `new_string` in `$EDITOR` and return the input with the result
+///
+/// Calls without a `new_string` (MCP tools) are edited as JSON.
+fn edit_in_editor(input: &Value) -> anyhow::Result<Value> {
+    let editor = std::env::var("VISUAL")
+        .or_else(|_| std::env::var("EDITOR"))
+        .unwrap_or_else(|_| "vi".to_string());
+    let path = std::env::temp_dir().join(format!("ralph-edit-{}.txt", uuid::Uuid::new_v4()));
+    let original = match input.get("new_string").and_then(|v| v.as_str()) {
+        Some(new_string) => new_string.to_string(),
+        None => serde_json::to_string_pretty(input)?,
+    };
+    std::fs::write(&path, original)?;
+
+    let status = std::process::Command::new("sh")
+        .arg("-c")
+        .arg(format!("{} \"$1\"", editor))
+        .arg("sh")
+        .arg(&path)
+        .status();
+    let edited = std::fs::read_to_string(&path);
+    let _ = std::fs::remove_file(&path);
+
+    if !status?.success() {
+        anyhow::bail!("{} exited with an error", editor);
+    }
+    if input.get("new_string").is_none() {
+        return Ok(serde_json::from_str(&edited?)?);
+    }
+    let mut input = input.clone();
+    input["new_string"] = edited?.into();
+    Ok(input)
+}
+
+/// A request and where to send the answer
+pub type PendingApproval = (ApprovalRequest, oneshot::Sender<Decision>);
+
+/// Hands requests to another task, e.g. the TUI (`ralph tui --attended`)
+pub struct ChannelApprover {
+    tx: mpsc::Sender<PendingApproval>,
+}
+
+impl ChannelApprover {
+    pub fn new(tx: mpsc::Sender<PendingApproval>) -> Self {
+        Self { tx }
+    }
+}
+
+#[async_trait]
+impl Approver for ChannelApprover {
+    async fn review(&self, request: ApprovalRequest) -> Decision {
+        let (reply_tx, reply_rx) = oneshot::channel();
+        if self.tx.send((request, reply_tx)).await.is_err() {
+            return Decision::Deny("the approval UI has closed".to_string());
+        }
+        reply_rx
+            .await
+            .unwrap_or_else(|_| Decision::Deny("the approval UI has closed".to_string()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_policy() {
+        let policy = ApprovalPolicy::from_config(
+            &PolicyConfig {
+                auto_approve: vec!["make lint".to_string()],
+                ..Default::default()
+            },
+            &McpConfig::default(),
+        );
+
+        assert!(!policy.needs_approval("read_file", &json!({"path": "src/main.rs"})));
+        assert!(!policy.needs_approval("bash", &json!({"command": "cargo check"})));
+        assert!(!policy.needs_approval("bash", &json!({"command": "cargo test -p core"})));
+        assert!(!policy.needs_approval("bash", &json!({"command": "make lint"})));
+
+        assert!(policy.needs_approval("bash", &json!({"command": "cargo install foo"})));
+        assert!(policy.needs_approval("bash", &json!({"command": "cargo checkout"})));
+        assert!(policy.needs_approval("bash", &json!({"command": "cargo check && rm -rf /"})));
+        assert!(policy.needs_approval("bash", &json!({"command": "git status > /etc/passwd"})));
+        assert!(policy.needs_approval("edit_file", &json!({"path": "a", "old_string": "", "new_string": "x"})));
+
+        // Backgrounding, expansion and quoting cannot hide a second command
+        assert!(policy.needs_approval("bash", &json!({"command": "cargo test & rm -rf ~"})));
+        assert!(policy.needs_approval("bash", &json!({"command": "cargo test&rm -rf ~"})));
+        assert!(policy.needs_approval("bash", &json!({"command": "cargo test$IFS-p$IFS'x'"})));
+        assert!(policy.needs_approval("bash", &json!({"command": "cargo test \"unterminated"})));
+        assert!(!policy.needs_approval("bash", &json!({"command": "cargo test 'my test'"})));
+
+        // Only the listing forms of git branch, and no --output files
+        assert!(!policy.needs_approval("bash", &json!({"command": "git branch"})));
+        assert!(!policy.needs_approval("bash", &json!({"command": "git branch -a -v"})));
+        assert!(policy.needs_approval("bash", &json!({"command": "git branch -D main"})));
+        assert!(policy.needs_approval("bash", &json!({"command": "git branch -f main HEAD~3"})));
+        assert!(!policy.needs_approval("bash", &json!({"command": "git diff --stat"})));
+        assert!(policy.needs_approval("bash", &json!({"command": "git diff --output=src/main.rs"})));
+        assert!(policy.needs_approval("bash", &json!({"command": "git log \"--output\" notes.txt"})));
+
+        // Nothing that swaps in a runner, wrapper, compiler or plugin
+        assert!(policy.needs_approval(
+            "bash",
+            &json!({"command": "cargo test --config \"target.x86_64-unknown-linux-gnu.runner=['rm','-rf','~']\""})
+        ));
+        assert!(policy.needs_approval("bash", &json!({"command": "cargo build --config build.rustc-wrapper=/tmp/x"})));
+        assert!(policy.needs_approval("bash", &json!({"command": "cargo build -Zunstable-options"})));
+        assert!(policy.needs_approval("bash", &json!({"command": "cargo test --manifest-path /tmp/x/Cargo.toml"})));
+        assert!(policy.needs_approval("bash", &json!({"command": "cargo test +nightly"})));
+        assert!(policy.needs_approval("bash", &json!({"command": "go test -exec /tmp/x"})));
+        assert!(policy.needs_approval("bash", &json!({"command": "go test -toolexec /tmp/x"})));
+        assert!(policy.needs_approval("bash", &json!({"command": "go test --toolexec=/tmp/x ./..."})));
+        assert!(policy.needs_approval("bash", &json!({"command": "pytest -p evil_plugin"})));
+        assert!(policy.needs_approval("bash", &json!({"command": "pytest -vp evil_plugin"})));
+        assert!(policy.needs_approval("bash", &json!({"command": "pytest -c /tmp/pytest.ini"})));
+        assert!(policy.needs_approval("bash", &json!({"command": "pytest --rootdir=/tmp/x"})));
+        assert!(!policy.needs_approval("bash", &json!({"command": "cargo test --release -- --nocapture"})));
+        assert!(!policy.needs_approval("bash", &json!({"command": "go test -v ./..."})));
+        assert!(!policy.needs_approval("bash", &json!({"command": "pytest -x -v tests/"})));
+
+        assert!(!policy.needs_approval("process", &json!({"action": "start", "command": "make lint"})));
+        assert!(policy.needs_approval("process", &json!({"action": "start", "command": "npm run dev"})));
+        assert!(policy.needs_approval("process", &json!({"action": "write", "id": "p1", "input": "y\n"})));
+        assert!(!policy.needs_approval("process", &json!({"action": "kill", "id": "p1"})));
+    }
+
+    #[test]
+    fn test_mcp_tools_ask_unless_server_is_trusted() {
+        let mcp: McpConfig = serde_yaml::from_str(
+            "servers:\n  schema:\n    command: ./schema-mcp\n  docs:\n    command: docs-mcp\n    auto_approve: true\n",
+        )
+        .unwrap();
+        let policy = ApprovalPolicy::from_config(&PolicyConfig::default(), &mcp);
+
+        assert!(policy.needs_approval("schema__drop_table", &json!({"table": "users"})));
+        assert!(!policy.needs_approval("docs__search", &json!({"query": "retry"})));
+        // Servers that aren't configured at all still ask
+        assert!(policy.needs_approval("other__tool", &json!({})));
+        assert!(!policy.needs_approval("code_search", &json!({"pattern": "fn main"})));
+    }
+
+    struct Scripted(Decision);
+
+    #[async_trait]
+    impl Approver for Scripted {
+        async fn review(&self, _request: ApprovalRequest) -> Decision {
+            self.0.clone()
+        }
+    }
+
+    #[tokio::test]
+    async fn test_check_decisions() {
+        let policy = ApprovalPolicy::from_config(&PolicyConfig::default(), &McpConfig::default());
+        let input = json!({"command": "rm -rf target"});
+
+        let deny = Approval::new(policy.clone(), Arc::new(Scripted(Decision::Deny("keep the cache".into()))));
+        assert_eq!(
+            deny.check("bash", &input).await.unwrap_err(),
+            "Denied by the user: keep the cache"
+        );
+        // Safe calls never reach the approver
+        assert!(deny.check("bash", &json!({"command": "cargo check"})).await.is_ok());
+
+        let edited = json!({"command": "rm -rf target/debug"});
+        let edit = Approval::new(policy, Arc::new(Scripted(Decision::Edit(edited.clone()))));
+        assert_eq!(edit.check("bash", &input).await.unwrap(), edited);
+        assert!(edited_note("bash", &input, &edited).unwrap().contains("rm -rf target/debug"));
+    }
+}
@@ -0,0 +1,300 @@
+//! Anthropic Messages API backend
+//!
+//! Streams responses over SSE and reconstructs the content blocks.
+
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use futures::StreamExt;
+use reqwest::Client;
+use serde::{Deserialize, Serialize};
+use tokio::sync::mpsc;
+
+use super::retry::ApiError as ProviderError;
+use super::{api_key, endpoint, ModelBackend, ModelRequest};
+use crate::claude_client::{ApiResponse, ContentBlock, Message, Usage};
+use crate::config::BackendConfig;
+
+const CLAUDE_API_URL: &str = "https://api.anthropic.com/v1/messages";
+
+/// Request body for Claude API
+#[derive(Debug, Serialize)]
+struct ApiRequest {
+    model: String,
+    max_tokens: u32,
+    system: String,
+    messages: Vec<Message>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    tools: Vec<ApiTool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    stream: Option<bool>,
+}
+
+#[derive(Debug, Serialize)]
+struct ApiTool {
+    name: String,
+    description: String,
+    input_schema: serde_json::Value,
+}
+
+/// Stream event types
+#[allow(dead_code)]
+#[derive(Debug, Deserialize)]
+#[serde(tag = "type")]
+enum StreamEvent {
+    #[serde(rename = "message_start")]
+    MessageStart { message: ApiResponse },
+    #[serde(rename = "content_block_start")]
+    ContentBlockStart {
+        index: usize,
+        content_block: ContentBlock,
+    },
+    #[serde(rename = "content_block_delta")]
+    ContentBlockDelta {
+        index: usize,
+        delta: ContentDelta,
+    },
+    #[serde(rename = "content_block_stop")]
+    ContentBlockStop { index: usize },
+    #[serde(rename = "message_delta")]
+    MessageDelta {
+        delta: MessageDeltaContent,
+        usage: Option<Usage>,
+    },
+    #[serde(rename = "message_stop")]
+    MessageStop,
+    #[serde(rename = "ping")]
+    Ping,
+    #[serde(rename = "error")]
+    Error { error: ApiError },
+}
+
+#[derive(Debug, Deserialize)]
+struct ContentDelta {
+    #[serde(rename = "type")]
+    delta_type: String,
+    #[serde(default)]
+    text: String,
+    #[serde(default)]
+    partial_json: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct MessageDeltaContent {
+    stop_reason: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ApiError {
+    #[serde(rename = "type")]
+    error_type: String,
+    message: String,
+}
+
+/// Claude via the Anthropic Messages API
+pub struct AnthropicBackend {
+    client: Client,
+    api_key: String,
+    api_url: String,
+    model: String,
+}
+
+impl AnthropicBackend {
+    pub fn new(api_key: String, model: &str) -> Self {
+        Self {
+            client: Client::new(),
+            api_key,
+            api_url: CLAUDE_API_URL.to_string(),
+            model: model.to_string(),
+        }
+    }
+
+    /// Build from project config, reading `ANTHROPIC_API_KEY` if no key is configured
+    pub fn from_config(model: &str, config: &BackendConfig) -> Result<Self> {
+        let key = api_key(config, &["anthropic", "claude"], "ANTHROPIC_API_KEY")
+            .context("ANTHROPIC_API_KEY environment variable not set")?;
+
+        let mut backend = Self::new(key, model);
+        if let Some(url) = endpoint(config, "anthropic", "ANTHROPIC_API_URL") {
+            backend.api_url = url;
+        }
+        Ok(backend)
+    }
+
+    /// Process SSE stream and reconstruct response
+    async fn process_stream(
+        &self,
+        response: reqwest::Response,
+        output_tx: Option<mpsc::Sender<String>>,
+    ) -> Result<ApiResponse> {
+        let mut content_blocks: Vec<ContentBlock> = Vec::new();
+        let mut current_text = String::new();
+        let mut current_tool_input = String::new();
+        let mut stop_reason: Option<String> = None;
+        let mut usage: Option<Usage> = None;
+        let mut message_id = String::new();
+
+        let stream = response.bytes_stream();
+        let mut buffer = String::new();
+
+        tokio::pin!(stream);
+
+        while let Some(chunk) = stream.next().await {
+            let chunk = chunk.context("Failed to read stream chunk")?;
+            buffer.push_str(&String::from_utf8_lossy(&chunk));
+
+            // Process complete lines
+            while let Some(newline_pos) = buffer.find('\n') {
+                let line = buffer[..newline_pos].trim().to_string();
+                buffer = buffer[newline_pos + 1..].to_string();
+
+                if line.is_empty() || line.starts_with("event:") {
+                    continue;
+                }
+
+                if let Some(data) = line.strip_prefix("data: ") {
+                    if data == "[DONE]" {
+                        continue;
+                    }
+
+                    match serde_json::from_str::<StreamEvent>(data) {
+                        Ok(event) => match event {
+                            StreamEvent::MessageStart { message } => {
+                                message_id = message.id;
+                                // Input tokens are only reported at message start
+                                usage = message.usage;
+                            }
+                            StreamEvent::ContentBlockStart { content_block, .. } => {
+                                match &content_block {
+                                    ContentBlock::Text { .. } => {
+                                        current_text.clear();
+                                    }
+                                    ContentBlock::ToolUse { .. } => {
+                                        current_tool_input.clear();
+                                    }
+                                    _ => {}
+                                }
+                                content_blocks.push(content_block);
+                            }
+                            StreamEvent::ContentBlockDelta { delta, .. } => {
+                                if delta.delta_type == "text_delta" {
+                                    current_text.push_str(&delta.text);
+                                    // Stream text to output
+                                    if let Some(tx) = &output_tx {
+                                        let _ = tx.send(delta.text.clone()).await;
+                                    }
+                                } else if delta.delta_type == "input_json_delta" {
+                                    current_tool_input.push_str(&delta.partial_json);
+                                }
+                            }
+                            StreamEvent::ContentBlockStop { index } => {
+                                if let Some(block) = content_blocks.get_mut(index) {
+                                    match block {
+                                        ContentBlock::Text { text } => {
+                                            *text = current_text.clone();
+                                        }
+                                        ContentBlock::ToolUse { input, .. } => {
+                                            if let Ok(parsed) =
+                                                serde_json::from_str(&current_tool_input)
+                                            {
+                                                *input = parsed;
+                                            }
+                                        }
+                                        _ => {}
+                                    }
+                                }
+                            }
+                            StreamEvent::MessageDelta {
+                                delta,
+                                usage: delta_usage,
+                            } => {
+                                stop_reason = delta.stop_reason;
+                                if let Some(delta_usage) = delta_usage {
+                                    // message_delta carries the final output count
+                                    let input_tokens = usage
+                                        .as_ref()
+                                        .map(|u| u.input_tokens)
+                                        .unwrap_or(delta_usage.input_tokens);
+                                    usage = Some(Usage {
+                                        input_tokens: input_tokens.max(delta_usage.input_tokens),
+                                        output_tokens: delta_usage.output_tokens,
+                                    });
+                                }
+                            }
+                            StreamEvent::MessageStop => {
+                                // Stream complete
+                            }
+                            StreamEvent::Error { error } => {
+                                return Err(ProviderError::from_stream("Claude", &error.error_type, &error.message).into());
+                            }
+                            StreamEvent::Ping => {}
+                        },
+                        Err(e) => {
+                            tracing::debug!("Failed to parse stream event: {} - {}", e, data);
+                        }
+                    }
+                }
+            }
+        }
+
+        Ok(ApiResponse {
+            id: message_id,
+            content: content_blocks,
+            stop_reason,
+            usage,
+        })
+    }
+}
+
+#[async_trait]
+impl ModelBackend for AnthropicBackend {
+    fn name(&self) -> &str {
+        "anthropic"
+    }
+
+    fn model(&self) -> &str {
+        &self.model
+    }
+
+    async fn complete(
+        &self,
+        request: ModelRequest<'_>,
+        output_tx: Option<mpsc::Sender<String>>,
+    ) -> Result<ApiResponse> {
+        let tools: Vec<ApiTool> = request
+            .tools
+            .iter()
+            .map(|t| ApiTool {
+                name: t.name.clone(),
+                description: t.description.clone(),
+                input_schema: t.input_schema.clone(),
+            })
+            .collect();
+
+        let api_request = ApiRequest {
+            model: self.model.clone(),
+            max_tokens: request.max_tokens,
+            system: request.system.to_string(),
+            messages: request.messages.to_vec(),
+            tools,
+            stream: Some(true),
+        };
+
+        let response = self
+            .client
+            .post(&self.api_url)
+            .header("x-api-key", &self.api_key)
+            .header("anthropic-version", "2023-06-01")
+            .header("content-type", "application/json")
+            .json(&api_request)
+            .send()
+            .await
+            .context("Failed to send request to Claude API")?;
+
+        if !response.status().is_success() {
+            return Err(ProviderError::from_response("Claude", response).await.into());
+        }
+
+        // Process SSE stream
+        self.process_stream(response, output_tx).await
+    }
+}
@@ -0,0 +1,118 @@
+//! Mock backend - scripted responses for offline testing
+//!
+//! Responses are served in order; once the script runs out the backend
+//! ends the turn so the loop always terminates. A script can be loaded
+//! from a JSON array of responses (`endpoints.mock` or `RALPH_MOCK_SCRIPT`).
+
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use std::collections::VecDeque;
+use std::sync::Mutex;
+use tokio::sync::mpsc;
+
+use super::{endpoint, ModelBackend, ModelRequest, STOP_END_TURN, STOP_TOOL_USE};
+use crate::claude_client::{ApiResponse, ContentBlock, Message, Usage};
+use crate::config::BackendConfig;
+
+/// Backend that replays a fixed list of responses
+pub struct MockBackend {
+    responses: Mutex<VecDeque<ApiResponse>>,
+    requests: Mutex<Vec<Vec<Message>>>,
+}
+
+impl MockBackend {
+    pub fn new(responses: Vec<ApiResponse>) -> Self {
+        Self {
+            responses: Mutex::new(responses.into()),
+            requests: Mutex::new(Vec::new()),
+        }
+    }
+
+    /// Load a script from `endpoints.mock` / `RALPH_MOCK_SCRIPT`, or start empty
+    pub fn from_config(config: &BackendConfig) -> Result<Self> {
+        let Some(path) = endpoint(config, "mock", "RALPH_MOCK_SCRIPT") else {
+            return Ok(Self::new(Vec::new()));
+        };
+
+        let content = std::fs::read_to_string(&path)
+            .with_context(|| format!("Failed to read mock script {}", path))?;
+        let responses: Vec<ApiResponse> = serde_json::from_str(&content)
+            .with_context(|| format!("Failed to parse mock script {}", path))?;
+        Ok(Self::new(responses))
+    }
+
+    /// A response that ends the turn with text
+    pub fn text(text: &str) -> ApiResponse {
+        ApiResponse {
+            id: String::new(),
+            content: vec![ContentBlock::Text {
+                text: text.to_string(),
+            }],
+            stop_reason: Some(STOP_END_TURN.to_string()),
+            usage: Some(Usage {
+                input_tokens: 100,
+                output_tokens: 10,
+            }),
+        }
+    }
+
+    /// A response that calls a single tool
+    #[allow(dead_code)] // Used by tests
+    pub fn tool_use(name: &str, input: serde_json::Value) -> ApiResponse {
+        ApiResponse {
+            id: String::new(),
+            content: vec![ContentBlock::ToolUse {
+                id: format!("toolu_{}", uuid::Uuid::new_v4().simple()),
+                name: name.to_string(),
+                input,
+            }],
+            stop_reason: Some(STOP_TOOL_USE.to_string()),
+            usage: Some(Usage {
+                input_tokens: 100,
+                output_tokens: 10,
+            }),
+        }
+    }
+
+    /// Conversations sent to the backend, one entry per call
+    #[allow(dead_code)] // Used by tests
+    pub fn requests(&self) -> Vec<Vec<Message>> {
+        self.requests.lock().unwrap().clone()
+    }
+}
+
+#[async_trait]
+impl ModelBackend for MockBackend {
+    fn name(&self) -> &str {
+        "mock"
+    }
+
+    fn model(&self) -> &str {
+        "mock"
+    }
+
+    async fn complete(
+        &self,
+        request: ModelRequest<'_>,
+        output_tx: Option<mpsc::Sender<String>>,
+    ) -> Result<ApiResponse> {
+        self.requests.lock().unwrap().push(request.messages.to_vec());
+
+        let response = self
+            .responses
+            .lock()
+            .unwrap()
+            .pop_front()
+            .unwrap_or_else(|| Self::text("Mock script exhausted."));
+
+        if let Some(tx) = &output_tx {
+            for block in &response.content {
+                if let ContentBlock::Text { text } = block {
+                    let _ = tx.send(text.clone()).await;
+                }
+            }
+        }
+
+        Ok(response)
+    }
+}
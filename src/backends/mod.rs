@@ -0,0 +1,284 @@
+//! Model Backends - Pluggable LLM providers for the agentic loop
+//!
+//! Every backend speaks the same tool-use conversation format (the
+//! `Message`/`ContentBlock` types from `claude_client`) and translates it to
+//! its own wire protocol. Stop reasons are normalized to the Anthropic
+//! vocabulary ("end_turn", "tool_use", "max_tokens") so the loop doesn't
+//! need to know which backend it is talking to.
+//!
+//! The backend is chosen by `--backend`/`--model` or by `backend.brain` in
+//! `.tachikoma/config.yaml`. Calls that hit a rate limit or an overloaded
+//! provider are retried with backoff (`triage.api_retry`).
+
+mod anthropic;
+mod mock;
+mod ollama;
+mod openai;
+pub mod replay;
+pub mod retry;
+
+pub use anthropic::AnthropicBackend;
+pub use mock::MockBackend;
+pub use ollama::OllamaBackend;
+pub use openai::OpenAiBackend;
+pub use replay::{RecordingBackend, ReplayBackend};
+pub use retry::{RetryPolicy, RetryingBackend};
+
+use anyhow::Result;
+use async_trait::async_trait;
+use clap::ValueEnum;
+use std::fmt;
+use std::sync::Arc;
+use tokio::sync::mpsc;
+
+use crate::claude_client::{ApiResponse, Message};
+use crate::config::BackendConfig;
+use crate::primitives::ToolDefinition;
+
+/// Normalized stop reason: the model finished its turn
+pub const STOP_END_TURN: &str = "end_turn";
+/// Normalized stop reason: the model wants tool results
+pub const STOP_TOOL_USE: &str = "tool_use";
+/// Normalized stop reason: the response hit the output token limit
+pub const STOP_MAX_TOKENS: &str = "max_tokens";
+
+/// Output token limit per model call
+pub const DEFAULT_MAX_TOKENS: u32 = 8192;
+
+/// A single model call: the full conversation so far plus the tool schema
+#[derive(Debug, Clone, Copy)]
+pub struct ModelRequest<'a> {
+    pub system: &'a str,
+    pub messages: &'a [Message],
+    pub tools: &'a [ToolDefinition],
+    pub max_tokens: u32,
+}
+
+/// A tool-calling model provider
+#[async_trait]
+pub trait ModelBackend: Send + Sync {
+    /// Backend identifier ("anthropic", "openai", "ollama", "mock", "replay")
+    fn name(&self) -> &str;
+
+    /// Model that requests are sent to
+    fn model(&self) -> &str;
+
+    /// Send one turn of the conversation and return the assistant reply
+    ///
+    /// Streaming backends forward text deltas to `output_tx` as they arrive;
+    /// non-streaming backends send the complete text once the reply is in.
+    async fn complete(
+        &self,
+        request: ModelRequest<'_>,
+        output_tx: Option<mpsc::Sender<String>>,
+    ) -> Result<ApiResponse>;
+}
+
+/// Which provider family to talk to
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum BackendKind {
+    /// Anthropic Messages API
+    #[value(alias = "claude")]
+    Anthropic,
+    /// OpenAI chat completions (or any compatible server)
+    Openai,
+    /// Local Ollama server
+    Ollama,
+    /// Scripted responses for offline testing
+    Mock,
+    /// Responses from a recorded fixture (`--record`)
+    Replay,
+}
+
+impl BackendKind {
+    /// Model used when none is configured
+    pub fn default_model(self) -> &'static str {
+        match self {
+            BackendKind::Anthropic => "claude-sonnet-4-20250514",
+            BackendKind::Openai => "gpt-4o",
+            BackendKind::Ollama => "qwen2.5-coder",
+            BackendKind::Mock => "mock",
+            BackendKind::Replay => "replay",
+        }
+    }
+
+    fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "anthropic" | "claude" => Some(BackendKind::Anthropic),
+            "openai" => Some(BackendKind::Openai),
+            "ollama" => Some(BackendKind::Ollama),
+            "mock" => Some(BackendKind::Mock),
+            "replay" => Some(BackendKind::Replay),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for BackendKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            BackendKind::Anthropic => "anthropic",
+            BackendKind::Openai => "openai",
+            BackendKind::Ollama => "ollama",
+            BackendKind::Mock => "mock",
+            BackendKind::Replay => "replay",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// A resolved backend choice
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BackendSpec {
+    pub kind: BackendKind,
+    pub model: String,
+}
+
+impl fmt::Display for BackendSpec {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} ({})", self.kind, self.model)
+    }
+}
+
+/// Parse a `backend.brain` value into a backend and optional model
+///
+/// Accepts a bare backend name ("claude", "openai", "ollama", "mock",
+/// "replay"), an explicit "backend:model" pair, or a well-known model name
+/// ("claude-*", "gpt-*", "o1"/"o3"/"o4-*").
+pub fn parse_brain(brain: &str) -> Result<(BackendKind, Option<String>)> {
+    let brain = brain.trim();
+
+    if let Some(kind) = BackendKind::from_name(brain) {
+        return Ok((kind, None));
+    }
+
+    if let Some((prefix, model)) = brain.split_once(':') {
+        if let Some(kind) = BackendKind::from_name(prefix) {
+            if model.is_empty() {
+                return Ok((kind, None));
+            }
+            return Ok((kind, Some(model.to_string())));
+        }
+    }
+
+    if brain.starts_with("claude-") {
+        return Ok((BackendKind::Anthropic, Some(brain.to_string())));
+    }
+
+    if brain.starts_with("gpt-") || openai::is_reasoning_model(brain) {
+        return Ok((BackendKind::Openai, Some(brain.to_string())));
+    }
+
+    anyhow::bail!(
+        "Unsupported backend '{}'. Use anthropic, openai, ollama, mock or replay \
+         (e.g. 'ollama:qwen2.5-coder' for a local model).",
+        brain
+    )
+}
+
+/// Resolve the backend from CLI flags, falling back to the project config
+///
+/// `--backend` wins over the config; `--model` on its own keeps the
+/// configured backend unless the model name identifies a different one.
+pub fn resolve(
+    cli_backend: Option<BackendKind>,
+    cli_model: Option<&str>,
+    config: &BackendConfig,
+) -> Result<BackendSpec> {
+    let (config_kind, config_model) = parse_brain(&config.brain)?;
+
+    let kind = cli_backend
+        .or_else(|| cli_model.and_then(|m| parse_brain(m).ok()).map(|(k, _)| k))
+        .unwrap_or(config_kind);
+
+    let model = match cli_model {
+        Some(m) => match parse_brain(m) {
+            Ok((_, Some(parsed))) => parsed,
+            _ => m.to_string(),
+        },
+        None if kind == config_kind => {
+            config_model.unwrap_or_else(|| kind.default_model().to_string())
+        }
+        None => kind.default_model().to_string(),
+    };
+
+    Ok(BackendSpec { kind, model })
+}
+
+/// Construct the backend for a resolved spec
+pub fn create(spec: &BackendSpec, config: &BackendConfig) -> Result<Arc<dyn ModelBackend>> {
+    let backend: Arc<dyn ModelBackend> = match spec.kind {
+        BackendKind::Anthropic => Arc::new(AnthropicBackend::from_config(&spec.model, config)?),
+        BackendKind::Openai => Arc::new(OpenAiBackend::from_config(&spec.model, config)?),
+        BackendKind::Ollama => Arc::new(OllamaBackend::from_config(&spec.model, config)),
+        BackendKind::Mock => Arc::new(MockBackend::from_config(config)?),
+        BackendKind::Replay => Arc::new(ReplayBackend::from_config(config)?),
+    };
+    Ok(backend)
+}
+
+/// Look up an API key from the config (by any of `names`) or the environment
+fn api_key(config: &BackendConfig, names: &[&str], env_var: &str) -> Option<String> {
+    names
+        .iter()
+        .find_map(|n| config.api_keys.get(*n).cloned())
+        .or_else(|| std::env::var(env_var).ok())
+        .filter(|k| !k.is_empty())
+}
+
+/// Look up a custom endpoint from the config or the environment
+fn endpoint(config: &BackendConfig, name: &str, env_var: &str) -> Option<String> {
+    config
+        .endpoints
+        .get(name)
+        .cloned()
+        .or_else(|| std::env::var(env_var).ok())
+        .map(|url| url.trim_end_matches('/').to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_brain() {
+        assert_eq!(parse_brain("claude").unwrap(), (BackendKind::Anthropic, None));
+        assert_eq!(
+            parse_brain("ollama:llama3:8b").unwrap(),
+            (BackendKind::Ollama, Some("llama3:8b".to_string()))
+        );
+        assert_eq!(
+            parse_brain("gpt-4o").unwrap(),
+            (BackendKind::Openai, Some("gpt-4o".to_string()))
+        );
+        assert_eq!(parse_brain("o3").unwrap().0, BackendKind::Openai);
+        assert!(parse_brain("gemini").is_err());
+    }
+
+    #[test]
+    fn test_resolve_precedence() {
+        let config = BackendConfig {
+            brain: "ollama:qwen2.5-coder".to_string(),
+            ..Default::default()
+        };
+
+        // Config only
+        let spec = resolve(None, None, &config).unwrap();
+        assert_eq!(spec.kind, BackendKind::Ollama);
+        assert_eq!(spec.model, "qwen2.5-coder");
+
+        // --model keeps the configured backend
+        let spec = resolve(None, Some("llama3.1"), &config).unwrap();
+        assert_eq!(spec.kind, BackendKind::Ollama);
+        assert_eq!(spec.model, "llama3.1");
+
+        // --backend without --model uses that backend's default
+        let spec = resolve(Some(BackendKind::Anthropic), None, &config).unwrap();
+        assert_eq!(spec.kind, BackendKind::Anthropic);
+        assert_eq!(spec.model, BackendKind::Anthropic.default_model());
+
+        // A well-known model name implies its backend
+        let spec = resolve(None, Some("claude-opus-4-20250514"), &config).unwrap();
+        assert_eq!(spec.kind, BackendKind::Anthropic);
+    }
+}
@@ -0,0 +1,275 @@
+//! Ollama backend - local models through `/api/chat` with tool calling
+//!
+//! Ollama doesn't assign ids to tool calls, so ids are generated here and
+//! tool results are matched back to their call by tool name.
+
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use reqwest::Client;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use tokio::sync::mpsc;
+
+use super::openai::to_function_tools;
+use super::retry::ApiError;
+use super::{endpoint, ModelBackend, ModelRequest, STOP_END_TURN, STOP_MAX_TOKENS, STOP_TOOL_USE};
+use crate::claude_client::{ApiResponse, ContentBlock, Message, Role, Usage};
+use crate::config::BackendConfig;
+
+const OLLAMA_BASE_URL: &str = "http://localhost:11434";
+
+#[derive(Debug, Serialize)]
+struct OllamaChatRequest {
+    model: String,
+    messages: Vec<OllamaMessage>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    tools: Vec<serde_json::Value>,
+    stream: bool,
+    options: OllamaOptions,
+}
+
+#[derive(Debug, Serialize)]
+struct OllamaOptions {
+    num_predict: u32,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct OllamaMessage {
+    role: String,
+    #[serde(default)]
+    content: String,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    tool_calls: Vec<OllamaToolCall>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    tool_name: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct OllamaToolCall {
+    function: OllamaFunctionCall,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct OllamaFunctionCall {
+    name: String,
+    #[serde(default)]
+    arguments: serde_json::Value,
+}
+
+#[derive(Debug, Deserialize)]
+struct OllamaChatResponse {
+    message: OllamaMessage,
+    #[serde(default)]
+    done_reason: Option<String>,
+    #[serde(default)]
+    prompt_eval_count: Option<u32>,
+    #[serde(default)]
+    eval_count: Option<u32>,
+}
+
+/// A model served by a local (or remote) Ollama instance
+pub struct OllamaBackend {
+    client: Client,
+    base_url: String,
+    model: String,
+}
+
+impl OllamaBackend {
+    pub fn new(model: &str, base_url: &str) -> Self {
+        Self {
+            client: Client::new(),
+            base_url: base_url.trim_end_matches('/').to_string(),
+            model: model.to_string(),
+        }
+    }
+
+    /// Build from project config, honoring `OLLAMA_BASE_URL`
+    pub fn from_config(model: &str, config: &BackendConfig) -> Self {
+        let base_url = endpoint(config, "ollama", "OLLAMA_BASE_URL")
+            .unwrap_or_else(|| OLLAMA_BASE_URL.to_string());
+        Self::new(model, &base_url)
+    }
+}
+
+/// Convert the conversation to Ollama chat messages
+fn to_ollama_messages(system: &str, messages: &[Message]) -> Vec<OllamaMessage> {
+    let mut out = vec![OllamaMessage {
+        role: "system".to_string(),
+        content: system.to_string(),
+        tool_calls: Vec::new(),
+        tool_name: None,
+    }];
+
+    // Tool results only carry the call id; Ollama wants the tool name
+    let mut names_by_id: HashMap<&str, &str> = HashMap::new();
+
+    for message in messages {
+        let mut text = Vec::new();
+        let mut tool_calls = Vec::new();
+        let mut tool_results = Vec::new();
+
+        for block in &message.content {
+            match block {
+                ContentBlock::Text { text: t } => text.push(t.clone()),
+                ContentBlock::ToolUse { id, name, input } => {
+                    names_by_id.insert(id, name);
+                    tool_calls.push(OllamaToolCall {
+                        function: OllamaFunctionCall {
+                            name: name.clone(),
+                            arguments: input.clone(),
+                        },
+                    });
+                }
+                ContentBlock::ToolResult {
+                    tool_use_id,
+                    content,
+                    is_error,
+                } => {
+                    let content = if is_error == &Some(true) {
+                        format!("ERROR: {}", content)
+                    } else {
+                        content.clone()
+                    };
+                    tool_results.push(OllamaMessage {
+                        role: "tool".to_string(),
+                        content,
+                        tool_calls: Vec::new(),
+                        tool_name: names_by_id.get(tool_use_id.as_str()).map(|n| n.to_string()),
+                    });
+                }
+            }
+        }
+
+        match message.role {
+            Role::Assistant => out.push(OllamaMessage {
+                role: "assistant".to_string(),
+                content: text.join("\n"),
+                tool_calls,
+                tool_name: None,
+            }),
+            Role::User => {
+                out.extend(tool_results);
+                if !text.is_empty() {
+                    out.push(OllamaMessage {
+                        role: "user".to_string(),
+                        content: text.join("\n"),
+                        tool_calls: Vec::new(),
+                        tool_name: None,
+                    });
+                }
+            }
+        }
+    }
+
+    out
+}
+
+#[async_trait]
+impl ModelBackend for OllamaBackend {
+    fn name(&self) -> &str {
+        "ollama"
+    }
+
+    fn model(&self) -> &str {
+        &self.model
+    }
+
+    async fn complete(
+        &self,
+        request: ModelRequest<'_>,
+        output_tx: Option<mpsc::Sender<String>>,
+    ) -> Result<ApiResponse> {
+        let chat_request = OllamaChatRequest {
+            model: self.model.clone(),
+            messages: to_ollama_messages(request.system, request.messages),
+            tools: to_function_tools(request.tools),
+            stream: false,
+            options: OllamaOptions {
+                num_predict: request.max_tokens,
+            },
+        };
+
+        let response = self
+            .client
+            .post(format!("{}/api/chat", self.base_url))
+            .json(&chat_request)
+            .send()
+            .await
+            .with_context(|| format!("Failed to reach Ollama at {}", self.base_url))?;
+
+        if !response.status().is_success() {
+            return Err(ApiError::from_response("Ollama", response).await.into());
+        }
+
+        let chat_response: OllamaChatResponse = response
+            .json()
+            .await
+            .context("Failed to parse Ollama chat response")?;
+
+        let mut content = Vec::new();
+        let text = chat_response.message.content;
+        if !text.is_empty() {
+            if let Some(tx) = &output_tx {
+                let _ = tx.send(text.clone()).await;
+            }
+            content.push(ContentBlock::Text { text });
+        }
+        for call in chat_response.message.tool_calls {
+            content.push(ContentBlock::ToolUse {
+                id: format!("call_{}", uuid::Uuid::new_v4().simple()),
+                name: call.function.name,
+                input: call.function.arguments,
+            });
+        }
+
+        let has_tool_calls = content.iter().any(|b| matches!(b, ContentBlock::ToolUse { .. }));
+        let stop_reason = match chat_response.done_reason.as_deref() {
+            _ if has_tool_calls => STOP_TOOL_USE,
+            Some("length") => STOP_MAX_TOKENS,
+            _ => STOP_END_TURN,
+        };
+
+        Ok(ApiResponse {
+            id: String::new(),
+            content,
+            stop_reason: Some(stop_reason.to_string()),
+            usage: Some(Usage {
+                input_tokens: chat_response.prompt_eval_count.unwrap_or(0),
+                output_tokens: chat_response.eval_count.unwrap_or(0),
+            }),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tool_results_carry_tool_name() {
+        let messages = vec![
+            Message {
+                role: Role::Assistant,
+                content: vec![ContentBlock::ToolUse {
+                    id: "call_abc".to_string(),
+                    name: "bash".to_string(),
+                    input: serde_json::json!({"command": "cargo check"}),
+                }],
+            },
+            Message {
+                role: Role::User,
+                content: vec![ContentBlock::ToolResult {
+                    tool_use_id: "call_abc".to_string(),
+                    content: "Exit code: 0".to_string(),
+                    is_error: None,
+                }],
+            },
+        ];
+
+        let ollama = to_ollama_messages("system", &messages);
+        assert_eq!(ollama.len(), 3);
+        assert_eq!(ollama[1].tool_calls[0].function.arguments["command"], "cargo check");
+        assert_eq!(ollama[2].role, "tool");
+        assert_eq!(ollama[2].tool_name.as_deref(), Some("bash"));
+    }
+}
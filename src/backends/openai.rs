@@ -0,0 +1,394 @@
+//! OpenAI-compatible chat completions backend
+//!
+//! Works against api.openai.com or any server exposing the same
+//! `/chat/completions` tool-calling API (vLLM, LM Studio, llama.cpp, ...).
+
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use reqwest::Client;
+use serde::{Deserialize, Serialize};
+use tokio::sync::mpsc;
+
+use super::retry::ApiError;
+use super::{
+    api_key, endpoint, ModelBackend, ModelRequest, STOP_END_TURN, STOP_MAX_TOKENS, STOP_TOOL_USE,
+};
+use crate::claude_client::{ApiResponse, ContentBlock, Message, Role, Usage};
+use crate::config::BackendConfig;
+use crate::primitives::ToolDefinition;
+
+const OPENAI_BASE_URL: &str = "https://api.openai.com/v1";
+
+#[derive(Debug, Serialize)]
+struct ChatRequest {
+    model: String,
+    messages: Vec<ChatMessage>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    tools: Vec<serde_json::Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    max_tokens: Option<u32>,
+    /// Reasoning models reject `max_tokens` and take this instead
+    #[serde(skip_serializing_if = "Option::is_none")]
+    max_completion_tokens: Option<u32>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct ChatMessage {
+    role: String,
+    #[serde(default)]
+    content: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    tool_calls: Vec<ChatToolCall>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    tool_call_id: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct ChatToolCall {
+    id: String,
+    #[serde(rename = "type", default = "function_type")]
+    call_type: String,
+    function: ChatFunctionCall,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct ChatFunctionCall {
+    name: String,
+    /// JSON-encoded arguments
+    arguments: String,
+}
+
+#[derive(Debug, Serialize)]
+struct ChatTool {
+    #[serde(rename = "type")]
+    tool_type: &'static str,
+    function: ChatFunction,
+}
+
+#[derive(Debug, Serialize)]
+struct ChatFunction {
+    name: String,
+    description: String,
+    parameters: serde_json::Value,
+}
+
+#[derive(Debug, Deserialize)]
+struct ChatResponse {
+    #[serde(default)]
+    id: String,
+    choices: Vec<ChatChoice>,
+    usage: Option<ChatUsage>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ChatChoice {
+    message: ChatMessage,
+    finish_reason: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ChatUsage {
+    prompt_tokens: u32,
+    completion_tokens: u32,
+}
+
+fn function_type() -> String {
+    "function".to_string()
+}
+
+/// Any OpenAI-compatible chat completions endpoint
+pub struct OpenAiBackend {
+    client: Client,
+    api_key: Option<String>,
+    base_url: String,
+    model: String,
+}
+
+impl OpenAiBackend {
+    pub fn new(model: &str, base_url: &str, api_key: Option<String>) -> Self {
+        Self {
+            client: Client::new(),
+            api_key,
+            base_url: base_url.trim_end_matches('/').to_string(),
+            model: model.to_string(),
+        }
+    }
+
+    /// Build from project config
+    ///
+    /// An API key is only required for the official endpoint; local
+    /// OpenAI-compatible servers usually run without one.
+    pub fn from_config(model: &str, config: &BackendConfig) -> Result<Self> {
+        let base_url = endpoint(config, "openai", "OPENAI_BASE_URL")
+            .unwrap_or_else(|| OPENAI_BASE_URL.to_string());
+        let key = api_key(config, &["openai"], "OPENAI_API_KEY");
+
+        if key.is_none() && base_url == OPENAI_BASE_URL {
+            anyhow::bail!("OPENAI_API_KEY environment variable not set");
+        }
+
+        Ok(Self::new(model, &base_url, key))
+    }
+}
+
+/// Convert the conversation to chat-completions messages
+fn to_chat_messages(system: &str, messages: &[Message]) -> Vec<ChatMessage> {
+    let mut out = vec![ChatMessage {
+        role: "system".to_string(),
+        content: Some(system.to_string()),
+        tool_calls: Vec::new(),
+        tool_call_id: None,
+    }];
+
+    for message in messages {
+        let mut text = Vec::new();
+        let mut tool_calls = Vec::new();
+        let mut tool_results = Vec::new();
+
+        for block in &message.content {
+            match block {
+                ContentBlock::Text { text: t } => text.push(t.clone()),
+                ContentBlock::ToolUse { id, name, input } => tool_calls.push(ChatToolCall {
+                    id: id.clone(),
+                    call_type: function_type(),
+                    function: ChatFunctionCall {
+                        name: name.clone(),
+                        // Arguments that weren't valid JSON go back as sent
+                        arguments: match input {
+                            serde_json::Value::String(raw) => raw.clone(),
+                            input => input.to_string(),
+                        },
+                    },
+                }),
+                ContentBlock::ToolResult {
+                    tool_use_id,
+                    content,
+                    is_error,
+                } => {
+                    let content = if is_error == &Some(true) {
+                        format!("ERROR: {}", content)
+                    } else {
+                        content.clone()
+                    };
+                    tool_results.push(ChatMessage {
+                        role: "tool".to_string(),
+                        content: Some(content),
+                        tool_calls: Vec::new(),
+                        tool_call_id: Some(tool_use_id.clone()),
+                    });
+                }
+            }
+        }
+
+        match message.role {
+            Role::Assistant => out.push(ChatMessage {
+                role: "assistant".to_string(),
+                content: if text.is_empty() { None } else { Some(text.join("\n")) },
+                tool_calls,
+                tool_call_id: None,
+            }),
+            Role::User => {
+                // Tool messages must directly follow the assistant's tool calls
+                out.extend(tool_results);
+                if !text.is_empty() {
+                    out.push(ChatMessage {
+                        role: "user".to_string(),
+                        content: Some(text.join("\n")),
+                        tool_calls: Vec::new(),
+                        tool_call_id: None,
+                    });
+                }
+            }
+        }
+    }
+
+    out
+}
+
+/// Whether `model` is an o-series reasoning model, which takes
+/// `max_completion_tokens` instead of `max_tokens`
+pub(super) fn is_reasoning_model(model: &str) -> bool {
+    ["o1", "o3", "o4"]
+        .iter()
+        .any(|p| model == *p || model.starts_with(&format!("{}-", p)))
+}
+
+/// Convert tool definitions to chat-completions function tools (also used by Ollama)
+pub(super) fn to_function_tools(tools: &[ToolDefinition]) -> Vec<serde_json::Value> {
+    tools
+        .iter()
+        .map(|t| {
+            serde_json::to_value(ChatTool {
+                tool_type: "function",
+                function: ChatFunction {
+                    name: t.name.clone(),
+                    description: t.description.clone(),
+                    parameters: t.input_schema.clone(),
+                },
+            })
+            .unwrap_or_default()
+        })
+        .collect()
+}
+
+#[async_trait]
+impl ModelBackend for OpenAiBackend {
+    fn name(&self) -> &str {
+        "openai"
+    }
+
+    fn model(&self) -> &str {
+        &self.model
+    }
+
+    async fn complete(
+        &self,
+        request: ModelRequest<'_>,
+        output_tx: Option<mpsc::Sender<String>>,
+    ) -> Result<ApiResponse> {
+        let reasoning = is_reasoning_model(&self.model);
+        let chat_request = ChatRequest {
+            model: self.model.clone(),
+            messages: to_chat_messages(request.system, request.messages),
+            tools: to_function_tools(request.tools),
+            max_tokens: (!reasoning).then_some(request.max_tokens),
+            max_completion_tokens: reasoning.then_some(request.max_tokens),
+        };
+
+        let mut http = self
+            .client
+            .post(format!("{}/chat/completions", self.base_url))
+            .json(&chat_request);
+        if let Some(key) = &self.api_key {
+            http = http.bearer_auth(key);
+        }
+
+        let response = http
+            .send()
+            .await
+            .context("Failed to send request to OpenAI-compatible API")?;
+
+        if !response.status().is_success() {
+            return Err(ApiError::from_response("OpenAI", response).await.into());
+        }
+
+        let chat_response: ChatResponse = response
+            .json()
+            .await
+            .context("Failed to parse chat completions response")?;
+
+        let choice = chat_response
+            .choices
+            .into_iter()
+            .next()
+            .context("Chat completions response had no choices")?;
+
+        let mut content = Vec::new();
+        if let Some(text) = choice.message.content.filter(|t| !t.is_empty()) {
+            if let Some(tx) = &output_tx {
+                let _ = tx.send(text.clone()).await;
+            }
+            content.push(ContentBlock::Text { text });
+        }
+        for call in choice.message.tool_calls {
+            // Unparseable arguments stay a raw string; the loop answers the
+            // call with the parse error instead of running the tool
+            let input = serde_json::from_str(&call.function.arguments)
+                .unwrap_or(serde_json::Value::String(call.function.arguments));
+            content.push(ContentBlock::ToolUse {
+                id: call.id,
+                name: call.function.name,
+                input,
+            });
+        }
+
+        let has_tool_calls = content.iter().any(|b| matches!(b, ContentBlock::ToolUse { .. }));
+        let stop_reason = match choice.finish_reason.as_deref() {
+            _ if has_tool_calls => STOP_TOOL_USE,
+            Some("length") => STOP_MAX_TOKENS,
+            _ => STOP_END_TURN,
+        };
+
+        Ok(ApiResponse {
+            id: chat_response.id,
+            content,
+            stop_reason: Some(stop_reason.to_string()),
+            usage: chat_response.usage.map(|u| Usage {
+                input_tokens: u.prompt_tokens,
+                output_tokens: u.completion_tokens,
+            }),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tool_results_follow_assistant_calls() {
+        let messages = vec![
+            Message {
+                role: Role::User,
+                content: vec![ContentBlock::Text {
+                    text: "Fix the bug".to_string(),
+                }],
+            },
+            Message {
+                role: Role::Assistant,
+                content: vec![ContentBlock::ToolUse {
+                    id: "call_1".to_string(),
+                    name: "read_file".to_string(),
+                    input: serde_json::json!({"path": "src/lib.rs"}),
+                }],
+            },
+            Message {
+                role: Role::User,
+                content: vec![ContentBlock::ToolResult {
+                    tool_use_id: "call_1".to_string(),
+                    content: "fn main() {}".to_string(),
+                    is_error: None,
+                }],
+            },
+        ];
+
+        let chat = to_chat_messages("system", &messages);
+        let roles: Vec<_> = chat.iter().map(|m| m.role.as_str()).collect();
+        assert_eq!(roles, ["system", "user", "assistant", "tool"]);
+        assert_eq!(chat[2].tool_calls[0].function.arguments, r#"{"path":"src/lib.rs"}"#);
+        assert_eq!(chat[3].tool_call_id.as_deref(), Some("call_1"));
+    }
+
+    #[test]
+    fn test_reasoning_models_send_max_completion_tokens() {
+        assert!(is_reasoning_model("o3-mini"));
+        assert!(is_reasoning_model("o1"));
+        assert!(!is_reasoning_model("gpt-4o"));
+
+        let request = ChatRequest {
+            model: "o4-mini".to_string(),
+            messages: Vec::new(),
+            tools: Vec::new(),
+            max_tokens: None,
+            max_completion_tokens: Some(4096),
+        };
+        let body = serde_json::to_value(&request).unwrap();
+        assert!(body.get("max_tokens").is_none());
+        assert_eq!(body["max_completion_tokens"], 4096);
+    }
+
+    #[test]
+    fn test_unparseable_arguments_round_trip() {
+        let messages = vec![Message {
+            role: Role::Assistant,
+            content: vec![ContentBlock::ToolUse {
+                id: "call_1".to_string(),
+                name: "read_file".to_string(),
+                input: serde_json::Value::String(r#"{"path": "src/lib.rs""#.to_string()),
+            }],
+        }];
+
+        let chat = to_chat_messages("system", &messages);
+        assert_eq!(chat[1].tool_calls[0].function.arguments, r#"{"path": "src/lib.rs""#);
+    }
+}
@@ -0,0 +1,378 @@
+//! Record and replay - deterministic agentic-loop runs without a live model
+//!
+//! `ralph run --record fixture.json` wraps the configured backend and saves
+//! every model call to a fixture:
+//!
+//! - the tool results sent with the request (the previous turn's calls)
+//! - the text streamed while the reply came in
+//! - the reply itself
+//! - after the run, a snapshot of the project's files
+//!
+//! `ralph run --replay fixture.json` (or `--backend replay` with
+//! `endpoints.replay` / `RALPH_REPLAY`) serves the replies back in order.
+//! The tools run for real, so the run can be checked end to end: a tool
+//! result that differs from the recording is reported as a divergence, and
+//! the final file tree is compared with the snapshot. Tool results are
+//! stored with the project root masked as `$PROJECT`, so a fixture replays
+//! in any checkout.
+
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, VecDeque};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use tokio::sync::mpsc;
+
+use super::{endpoint, ModelBackend, ModelRequest};
+use crate::claude_client::{ApiResponse, ContentBlock, Role};
+use crate::config::BackendConfig;
+
+/// Directories left out of file tree snapshots
+const SNAPSHOT_SKIP: &[&str] = &[".git", ".ralph", ".beads", "target", "node_modules"];
+
+/// Stands in for the project root in recorded tool results
+const PROJECT_MARKER: &str = "$PROJECT";
+
+/// One recorded model call
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Exchange {
+    /// Tool results sent with the request
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tool_results: Vec<ContentBlock>,
+    /// Text streamed while the reply came in
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub stream: Vec<String>,
+    pub response: ApiResponse,
+}
+
+/// A recorded run
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Fixture {
+    /// Backend and model the run was recorded against
+    pub backend: String,
+    pub model: String,
+    pub exchanges: Vec<Exchange>,
+    /// Project files after the run: path -> content
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub final_tree: BTreeMap<String, String>,
+}
+
+impl Fixture {
+    pub fn load(path: &Path) -> Result<Self> {
+        let content = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read fixture {}", path.display()))?;
+        serde_json::from_str(&content).with_context(|| format!("Failed to parse fixture {}", path.display()))
+    }
+
+    pub fn save(&self, path: &Path) -> Result<()> {
+        if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(path, serde_json::to_string_pretty(self)? + "\n")
+            .with_context(|| format!("Failed to write fixture {}", path.display()))
+    }
+
+    /// How the project's files differ from the recorded snapshot
+    pub fn diff_tree(&self, project_root: &Path) -> Result<Vec<String>> {
+        Ok(diff_trees(&self.final_tree, &snapshot(project_root)?))
+    }
+}
+
+/// The project's files, minus VCS, build and harness state
+pub fn snapshot(project_root: &Path) -> Result<BTreeMap<String, String>> {
+    let mut tree = BTreeMap::new();
+    let mut pending = vec![project_root.to_path_buf()];
+    while let Some(dir) = pending.pop() {
+        for entry in std::fs::read_dir(&dir)? {
+            let entry = entry?;
+            let path = entry.path();
+            let name = entry.file_name().to_string_lossy().to_string();
+            if entry.file_type()?.is_dir() {
+                if !SNAPSHOT_SKIP.contains(&name.as_str()) {
+                    pending.push(path);
+                }
+                continue;
+            }
+
+            let bytes = std::fs::read(&path)?;
+            let content = String::from_utf8(bytes)
+                .unwrap_or_else(|e| format!("<binary: {} bytes>", e.as_bytes().len()));
+            let relative = path.strip_prefix(project_root).unwrap_or(&path);
+            tree.insert(relative.to_string_lossy().replace('\\', "/"), content);
+        }
+    }
+    Ok(tree)
+}
+
+/// One line per file that was added, removed or changed
+pub fn diff_trees(expected: &BTreeMap<String, String>, actual: &BTreeMap<String, String>) -> Vec<String> {
+    let mut diffs = Vec::new();
+    for (path, want) in expected {
+        match actual.get(path) {
+            None => diffs.push(format!("- {} (missing)", path)),
+            Some(got) if got != want => {
+                let line = want
+                    .lines()
+                    .zip(got.lines())
+                    .position(|(a, b)| a != b)
+                    .unwrap_or_else(|| want.lines().count().min(got.lines().count()));
+                diffs.push(format!("~ {} (differs from line {})", path, line + 1));
+            }
+            Some(_) => {}
+        }
+    }
+    for path in actual.keys().filter(|p| !expected.contains_key(*p)) {
+        diffs.push(format!("+ {} (not in the snapshot)", path));
+    }
+    diffs
+}
+
+/// Tool results at the end of a request (the reply to the previous turn),
+/// with the project root masked
+fn trailing_tool_results(request: &ModelRequest<'_>, project_root: Option<&Path>) -> Vec<ContentBlock> {
+    let root = project_root.map(|r| r.display().to_string());
+    let Some(message) = request.messages.last().filter(|m| matches!(m.role, Role::User)) else {
+        return Vec::new();
+    };
+    message
+        .content
+        .iter()
+        .filter_map(|block| match block {
+            ContentBlock::ToolResult {
+                tool_use_id,
+                content,
+                is_error,
+            } => Some(ContentBlock::ToolResult {
+                tool_use_id: tool_use_id.clone(),
+                content: match &root {
+                    Some(root) => content.replace(root.as_str(), PROJECT_MARKER),
+                    None => content.clone(),
+                },
+                is_error: *is_error,
+            }),
+            _ => None,
+        })
+        .collect()
+}
+
+/// Wraps a backend and records every call (`ralph run --record`)
+pub struct RecordingBackend {
+    inner: Arc<dyn ModelBackend>,
+    project_root: PathBuf,
+    exchanges: Mutex<Vec<Exchange>>,
+}
+
+impl RecordingBackend {
+    pub fn new(inner: Arc<dyn ModelBackend>, project_root: &Path) -> Self {
+        Self {
+            inner,
+            project_root: project_root.to_path_buf(),
+            exchanges: Mutex::new(Vec::new()),
+        }
+    }
+
+    /// Everything recorded so far; the caller adds the final tree
+    pub fn fixture(&self) -> Fixture {
+        Fixture {
+            backend: self.inner.name().to_string(),
+            model: self.inner.model().to_string(),
+            exchanges: self.exchanges.lock().unwrap().clone(),
+            final_tree: BTreeMap::new(),
+        }
+    }
+}
+
+#[async_trait]
+impl ModelBackend for RecordingBackend {
+    fn name(&self) -> &str {
+        self.inner.name()
+    }
+
+    fn model(&self) -> &str {
+        self.inner.model()
+    }
+
+    async fn complete(
+        &self,
+        request: ModelRequest<'_>,
+        output_tx: Option<mpsc::Sender<String>>,
+    ) -> Result<ApiResponse> {
+        // Tee the stream: forward each chunk and keep a copy
+        let (tee_tx, mut tee_rx) = mpsc::channel::<String>(100);
+        let tee = tokio::spawn(async move {
+            let mut stream = Vec::new();
+            while let Some(chunk) = tee_rx.recv().await {
+                if let Some(tx) = &output_tx {
+                    let _ = tx.send(chunk.clone()).await;
+                }
+                stream.push(chunk);
+            }
+            stream
+        });
+
+        let response = self.inner.complete(request, Some(tee_tx)).await;
+        let stream = tee.await.unwrap_or_default();
+        let response = response?;
+
+        self.exchanges.lock().unwrap().push(Exchange {
+            tool_results: trailing_tool_results(&request, Some(&self.project_root)),
+            stream,
+            response: response.clone(),
+        });
+        Ok(response)
+    }
+}
+
+/// Serves a fixture's replies in order (`ralph run --replay`)
+pub struct ReplayBackend {
+    model: String,
+    project_root: Option<PathBuf>,
+    exchanges: Mutex<VecDeque<Exchange>>,
+    calls: Mutex<usize>,
+    divergences: Mutex<Vec<String>>,
+}
+
+impl ReplayBackend {
+    pub fn new(fixture: Fixture) -> Self {
+        Self {
+            model: fixture.model,
+            project_root: None,
+            exchanges: Mutex::new(fixture.exchanges.into()),
+            calls: Mutex::new(0),
+            divergences: Mutex::new(Vec::new()),
+        }
+    }
+
+    /// Load the fixture named by `endpoints.replay` / `RALPH_REPLAY`
+    pub fn from_config(config: &BackendConfig) -> Result<Self> {
+        let path = endpoint(config, "replay", "RALPH_REPLAY")
+            .context("The replay backend needs a fixture: pass --replay or set RALPH_REPLAY")?;
+        Ok(Self::new(Fixture::load(Path::new(&path))?))
+    }
+
+    /// Mask this root in tool results before comparing them with the recording
+    pub fn in_project(mut self, project_root: &Path) -> Self {
+        self.project_root = Some(project_root.to_path_buf());
+        self
+    }
+
+    /// Tool results that came out differently from the recording
+    #[allow(dead_code)] // Used by tests
+    pub fn divergences(&self) -> Vec<String> {
+        self.divergences.lock().unwrap().clone()
+    }
+}
+
+#[async_trait]
+impl ModelBackend for ReplayBackend {
+    fn name(&self) -> &str {
+        "replay"
+    }
+
+    fn model(&self) -> &str {
+        &self.model
+    }
+
+    async fn complete(
+        &self,
+        request: ModelRequest<'_>,
+        output_tx: Option<mpsc::Sender<String>>,
+    ) -> Result<ApiResponse> {
+        let call = {
+            let mut calls = self.calls.lock().unwrap();
+            *calls += 1;
+            *calls
+        };
+        let exchange = self
+            .exchanges
+            .lock()
+            .unwrap()
+            .pop_front()
+            .with_context(|| format!("Replay fixture exhausted at call {}", call))?;
+
+        let got = trailing_tool_results(&request, self.project_root.as_deref());
+        for (i, (want, got)) in exchange.tool_results.iter().zip(&got).enumerate() {
+            if let (ContentBlock::ToolResult { content: want, .. }, ContentBlock::ToolResult { content: got, .. }) =
+                (want, got)
+            {
+                if want != got {
+                    let divergence = format!("call {}: tool result {} differs from the recording", call, i + 1);
+                    tracing::warn!("Replay divergence, {}", divergence);
+                    self.divergences.lock().unwrap().push(divergence);
+                }
+            }
+        }
+        if exchange.tool_results.len() != got.len() {
+            self.divergences.lock().unwrap().push(format!(
+                "call {}: {} tool results, recorded {}",
+                call,
+                got.len(),
+                exchange.tool_results.len()
+            ));
+        }
+
+        if let Some(tx) = &output_tx {
+            for chunk in &exchange.stream {
+                let _ = tx.send(chunk.clone()).await;
+            }
+        }
+        Ok(exchange.response)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backends::MockBackend;
+    use crate::claude_client::ClaudeClient;
+    use crate::transcript::ResumePoint;
+    use serde_json::json;
+    use tempfile::TempDir;
+
+    #[tokio::test]
+    async fn test_record_then_replay() {
+        let temp = TempDir::new().unwrap();
+        let mock = Arc::new(MockBackend::new(vec![
+            MockBackend::tool_use(
+                "edit_file",
+                json!({"path": "notes.txt", "old_string": "", "new_string": "first\n"}),
+            ),
+            MockBackend::text("Done."),
+        ]));
+
+        let recorder = Arc::new(RecordingBackend::new(mock, temp.path()));
+        ClaudeClient::new(recorder.clone(), temp.path())
+            .resume_agentic_loop("system", ResumePoint::fresh("Write notes"), 10, 150_000, None)
+            .await
+            .unwrap();
+        let mut fixture = recorder.fixture();
+        fixture.final_tree = snapshot(temp.path()).unwrap();
+        assert_eq!(fixture.exchanges.len(), 2);
+        assert_eq!(fixture.exchanges[0].stream, Vec::<String>::new());
+        assert_eq!(fixture.exchanges[1].stream, ["Done."]);
+        assert_eq!(fixture.exchanges[1].tool_results.len(), 1);
+        assert_eq!(fixture.final_tree["notes.txt"], "first\n");
+
+        // Replaying into a fresh directory reproduces the run
+        let replay_root = TempDir::new().unwrap();
+        let replay = Arc::new(ReplayBackend::new(fixture.clone()).in_project(replay_root.path()));
+        ClaudeClient::new(replay.clone(), replay_root.path())
+            .resume_agentic_loop("system", ResumePoint::fresh("Write notes"), 10, 150_000, None)
+            .await
+            .unwrap();
+        assert_eq!(replay.divergences(), Vec::<String>::new());
+        assert!(fixture.diff_tree(replay_root.path()).unwrap().is_empty());
+
+        // A directory in the way makes the edit fail, and the run diverges
+        let other = TempDir::new().unwrap();
+        std::fs::create_dir(other.path().join("notes.txt")).unwrap();
+        let replay = Arc::new(ReplayBackend::new(fixture.clone()).in_project(other.path()));
+        ClaudeClient::new(replay.clone(), other.path())
+            .resume_agentic_loop("system", ResumePoint::fresh("Write notes"), 10, 150_000, None)
+            .await
+            .unwrap();
+        assert_eq!(replay.divergences(), ["call 2: tool result 1 differs from the recording"]);
+        assert_eq!(fixture.diff_tree(other.path()).unwrap(), ["- notes.txt (missing)"]);
+    }
+}
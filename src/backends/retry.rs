@@ -0,0 +1,311 @@
+//! Model-call retries - Backoff on rate limits, overload and flaky networks
+//!
+//! Backends report HTTP failures as an [`ApiError`]. [`RetryingBackend`]
+//! wraps any backend and repeats a failed call while the error is worth
+//! retrying (429, 529 and other 5xx, timeouts, dropped connections), waiting
+//! as [`RetryPolicy`] says or as long as the provider's `Retry-After` asks.
+//!
+//! `RetryPolicy` has the fields and backoff of `RetryPolicy` in
+//! tachikoma-common-http; Ralph builds on its own, so it keeps a copy.
+//! Errors that survive the retries reach the loop, where `triage` decides
+//! what happens to the task.
+
+use anyhow::Result;
+use async_trait::async_trait;
+use reqwest::StatusCode;
+use std::hash::{BuildHasher, Hasher};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::mpsc;
+
+use super::{ModelBackend, ModelRequest};
+use crate::claude_client::ApiResponse;
+use crate::config::ApiRetryConfig;
+
+/// An error response from a model provider
+#[derive(Debug, thiserror::Error)]
+#[error("{provider} API error {status}: {body}")]
+pub struct ApiError {
+    pub provider: String,
+    pub status: StatusCode,
+    pub body: String,
+    /// Wait the provider asked for (`Retry-After`)
+    pub retry_after: Option<Duration>,
+}
+
+/// How a model call failed, for retries and triage
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ErrorClass {
+    /// 429
+    RateLimited,
+    /// 529, 503
+    Overloaded,
+    /// Other 5xx, timeouts and connection failures
+    Transient,
+    /// Anything a retry won't fix
+    Fatal,
+}
+
+impl ApiError {
+    /// Read a failed response
+    pub async fn from_response(provider: &str, response: reqwest::Response) -> Self {
+        let status = response.status();
+        let retry_after = response
+            .headers()
+            .get(reqwest::header::RETRY_AFTER)
+            .and_then(|v| v.to_str().ok())
+            .and_then(|v| v.trim().parse::<u64>().ok())
+            .map(Duration::from_secs);
+        let body = response.text().await.unwrap_or_default();
+        Self {
+            provider: provider.to_string(),
+            status,
+            body,
+            retry_after,
+        }
+    }
+
+    /// An error event in the middle of a stream (e.g. `overloaded_error`)
+    pub fn from_stream(provider: &str, error_type: &str, message: &str) -> Self {
+        let status = match error_type {
+            "rate_limit_error" => StatusCode::TOO_MANY_REQUESTS,
+            "overloaded_error" => StatusCode::from_u16(529).unwrap_or(StatusCode::SERVICE_UNAVAILABLE),
+            "api_error" => StatusCode::INTERNAL_SERVER_ERROR,
+            _ => StatusCode::BAD_REQUEST,
+        };
+        Self {
+            provider: provider.to_string(),
+            status,
+            body: format!("{} - {}", error_type, message),
+            retry_after: None,
+        }
+    }
+
+    pub fn class(&self) -> ErrorClass {
+        match self.status.as_u16() {
+            429 => ErrorClass::RateLimited,
+            503 | 529 => ErrorClass::Overloaded,
+            500..=599 => ErrorClass::Transient,
+            _ => ErrorClass::Fatal,
+        }
+    }
+}
+
+impl ErrorClass {
+    /// Classify any error from a model call
+    pub fn of(error: &anyhow::Error) -> Self {
+        for cause in error.chain() {
+            if let Some(api) = cause.downcast_ref::<ApiError>() {
+                return api.class();
+            }
+            if let Some(http) = cause.downcast_ref::<reqwest::Error>() {
+                if http.is_timeout() || http.is_connect() || http.is_request() {
+                    return ErrorClass::Transient;
+                }
+            }
+        }
+        ErrorClass::Fatal
+    }
+
+    pub fn is_retryable(self) -> bool {
+        self != ErrorClass::Fatal
+    }
+}
+
+/// Backoff between attempts at a model call
+#[derive(Debug, Clone)]
+pub struct RetryPolicy {
+    /// Maximum number of attempts, the first included
+    pub max_attempts: u32,
+    /// Delay before the first retry
+    pub initial_delay: Duration,
+    /// Longest delay between retries
+    pub max_delay: Duration,
+    /// Growth of the delay per retry
+    pub multiplier: f64,
+    /// Add up to 25% random jitter to each delay
+    pub jitter: bool,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 3,
+            initial_delay: Duration::from_millis(500),
+            max_delay: Duration::from_secs(30),
+            multiplier: 2.0,
+            jitter: true,
+        }
+    }
+}
+
+impl RetryPolicy {
+    pub fn from_config(config: &ApiRetryConfig) -> Self {
+        Self {
+            max_attempts: config.max_attempts.max(1),
+            initial_delay: Duration::from_millis(config.initial_delay_ms),
+            max_delay: Duration::from_millis(config.max_delay_ms),
+            multiplier: config.multiplier,
+            jitter: true,
+        }
+    }
+
+    /// Delay before attempt `attempt` (0-indexed; the first attempt doesn't wait)
+    pub fn delay_for_attempt(&self, attempt: u32) -> Duration {
+        if attempt == 0 {
+            return Duration::ZERO;
+        }
+
+        let base_delay = self.initial_delay.as_millis() as f64 * self.multiplier.powi((attempt - 1) as i32);
+        let delay_ms = base_delay.min(self.max_delay.as_millis() as f64);
+
+        let delay_ms = if self.jitter {
+            // 0-25%, from the randomly keyed std hasher
+            let random = std::collections::hash_map::RandomState::new().build_hasher().finish();
+            delay_ms * (1.0 + (random % 1000) as f64 / 4000.0)
+        } else {
+            delay_ms
+        };
+
+        Duration::from_millis(delay_ms as u64)
+    }
+}
+
+/// Retries a backend's failed calls with backoff
+pub struct RetryingBackend {
+    inner: Arc<dyn ModelBackend>,
+    policy: RetryPolicy,
+}
+
+impl RetryingBackend {
+    pub fn new(inner: Arc<dyn ModelBackend>, policy: RetryPolicy) -> Self {
+        Self { inner, policy }
+    }
+}
+
+#[async_trait]
+impl ModelBackend for RetryingBackend {
+    fn name(&self) -> &str {
+        self.inner.name()
+    }
+
+    fn model(&self) -> &str {
+        self.inner.model()
+    }
+
+    async fn complete(
+        &self,
+        request: ModelRequest<'_>,
+        output_tx: Option<mpsc::Sender<String>>,
+    ) -> Result<ApiResponse> {
+        let mut attempt = 0;
+        loop {
+            let error = match self.inner.complete(request, output_tx.clone()).await {
+                Ok(response) => return Ok(response),
+                Err(e) => e,
+            };
+
+            attempt += 1;
+            let class = ErrorClass::of(&error);
+            if !class.is_retryable() || attempt >= self.policy.max_attempts {
+                return Err(error);
+            }
+
+            let asked = error
+                .chain()
+                .find_map(|cause| cause.downcast_ref::<ApiError>())
+                .and_then(|api| api.retry_after);
+            let delay = asked
+                .unwrap_or_default()
+                .max(self.policy.delay_for_attempt(attempt));
+            tracing::warn!(
+                "{} call failed ({:?}), retrying in {:.1}s (attempt {}/{}): {}",
+                self.inner.name(),
+                class,
+                delay.as_secs_f64(),
+                attempt + 1,
+                self.policy.max_attempts,
+                error
+            );
+            tokio::time::sleep(delay).await;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::claude_client::ContentBlock;
+    use std::sync::Mutex;
+
+    /// Fails with the given statuses, then answers
+    struct Flaky {
+        failures: Mutex<Vec<u16>>,
+    }
+
+    #[async_trait]
+    impl ModelBackend for Flaky {
+        fn name(&self) -> &str {
+            "flaky"
+        }
+
+        fn model(&self) -> &str {
+            "flaky-1"
+        }
+
+        async fn complete(&self, _request: ModelRequest<'_>, _output_tx: Option<mpsc::Sender<String>>) -> Result<ApiResponse> {
+            let next = self.failures.lock().unwrap().pop();
+            if let Some(status) = next {
+                return Err(ApiError {
+                    provider: "Flaky".to_string(),
+                    status: StatusCode::from_u16(status).unwrap(),
+                    body: "try later".to_string(),
+                    retry_after: None,
+                }
+                .into());
+            }
+            Ok(ApiResponse {
+                id: "msg_1".to_string(),
+                content: vec![ContentBlock::Text { text: "done".to_string() }],
+                stop_reason: Some("end_turn".to_string()),
+                usage: None,
+            })
+        }
+    }
+
+    #[tokio::test]
+    async fn test_retries_rate_limits_but_not_bad_requests() {
+        let policy = RetryPolicy {
+            max_attempts: 3,
+            initial_delay: Duration::from_millis(1),
+            jitter: false,
+            ..Default::default()
+        };
+        let request = ModelRequest {
+            system: "",
+            messages: &[],
+            tools: &[],
+            max_tokens: 16,
+        };
+
+        let flaky = Arc::new(Flaky {
+            failures: Mutex::new(vec![529, 429]),
+        });
+        let backend = RetryingBackend::new(flaky.clone(), policy.clone());
+        assert!(backend.complete(request, None).await.is_ok());
+
+        // A third 429 in a row is handed back, classified for triage
+        *flaky.failures.lock().unwrap() = vec![429, 429, 429];
+        let error = backend.complete(request, None).await.unwrap_err();
+        assert_eq!(ErrorClass::of(&error), ErrorClass::RateLimited);
+        assert!(error.to_string().starts_with("Flaky API error 429 Too Many Requests"));
+
+        *flaky.failures.lock().unwrap() = vec![200, 400];
+        let error = backend.complete(request, None).await.unwrap_err();
+        assert_eq!(ErrorClass::of(&error), ErrorClass::Fatal);
+        assert_eq!(flaky.failures.lock().unwrap().len(), 1);
+
+        assert_eq!(policy.delay_for_attempt(0), Duration::ZERO);
+        assert_eq!(policy.delay_for_attempt(3), Duration::from_millis(4));
+    }
+}
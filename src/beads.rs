@@ -0,0 +1,23 @@
+//! Beads Store - Read and write `.beads/issues.jsonl` without the `bd` CLI
+//!
+//! The store is tachikoma-plugin's `BeadsStore`, the same one the beads
+//! tracker plugin works through: it reads and writes the JSONL export
+//! directly, under a lock, and computes the ready set from the dependency
+//! graph itself, so the loop runs (and can be tested) without `bd`
+//! installed.
+//!
+//! `task_parser` uses this store whenever `.beads/issues.jsonl` exists and
+//! falls back to `bd` otherwise, or when `RALPH_BEADS=bd` is set.
+
+use std::path::Path;
+
+pub use tachikoma_plugin::trackers::beads::{append_line, BeadsStore, NewIssue};
+
+/// The store to use for a project, or `None` to fall back to `bd`
+pub fn for_project(project_root: &Path) -> Option<BeadsStore> {
+    if std::env::var("RALPH_BEADS").is_ok_and(|v| v == "bd") {
+        return None;
+    }
+    let store = BeadsStore::new(project_root);
+    store.exists().then_some(store)
+}
@@ -1,21 +1,36 @@
-//! Claude API Client - Handles communication with Claude Sonnet
+//! Agent Client - Runs the agentic loop against a model backend
 //!
-//! Uses the Messages API with streaming and tool use support.
+//! The conversation types mirror the Anthropic Messages API with tool use;
+//! other backends translate to and from them (see `backends`).
 //! Includes exploration detection to prevent context burn.
 
-use anyhow::{Context, Result};
-use futures::StreamExt;
-use reqwest::Client;
+use anyhow::Result;
 use serde::{Deserialize, Serialize};
 use std::path::Path;
+use std::sync::Arc;
+use std::time::Instant;
+use tachikoma_primitives::ProcessTable;
 use tokio::sync::mpsc;
 
-use crate::primitives::{execute_tool, get_tool_definitions, ToolDefinition};
+use crate::approval::{self, Approval};
+use crate::backends::{ModelBackend, ModelRequest, DEFAULT_MAX_TOKENS, STOP_END_TURN};
+use crate::compaction::Compactor;
+use crate::control::{Checkpoint, Interrupt, LoopControl};
+use crate::costs::{CostMeter, Pricing, Spent};
+use crate::events::{emit, EventSender, LoopEvent};
+use crate::mcp::McpTools;
+use crate::primitives::{execute_tool, get_tool_definitions, ToolResult};
+use crate::sandbox::Sandbox;
+use crate::transcript::{ResumePoint, Transcript};
 
 // ============================================================================
 // Exploration Detection
 // ============================================================================
 
+/// Tools that change files: they count as edits and their outputs feed
+/// `progress::extract_modified_files`
+pub(crate) const EDIT_TOOLS: &[&str] = &["edit_file", "multi_edit"];
+
 /// Metrics for detecting exploration spirals
 /// 
 /// Tracks tool usage across iterations to identify when the agent
@@ -36,8 +51,9 @@ impl IterationMetrics {
         match name {
             "read_file" => self.read_file_count += 1,
             "list_files" => self.list_files_count += 1,
-            "code_search" => self.code_search_count += 1,
-            "edit_file" => self.edit_file_count += 1,
+            // Symbol lookups are searches too
+            "code_search" | "find_symbol" | "find_references" => self.code_search_count += 1,
+            "edit_file" | "multi_edit" => self.edit_file_count += 1,
             "bash" => self.bash_count += 1,
             "beads" => self.beads_count += 1,
             _ => {}
@@ -86,9 +102,6 @@ impl IterationMetrics {
     }
 }
 
-const CLAUDE_API_URL: &str = "https://api.anthropic.com/v1/messages";
-const CLAUDE_MODEL: &str = "claude-sonnet-4-20250514"; // Sonnet for agentic work
-
 /// Message role
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "lowercase")]
@@ -125,110 +138,151 @@ pub struct Message {
     pub content: Vec<ContentBlock>,
 }
 
-/// Request body for Claude API
-#[derive(Debug, Serialize)]
-struct ApiRequest {
-    model: String,
-    max_tokens: u32,
-    system: String,
-    messages: Vec<Message>,
-    tools: Vec<ApiTool>,
-    #[serde(skip_serializing_if = "Option::is_none")]
-    stream: Option<bool>,
-}
-
-#[derive(Debug, Serialize)]
-struct ApiTool {
-    name: String,
-    description: String,
-    input_schema: serde_json::Value,
-}
-
-/// Response from Claude API
-#[derive(Debug, Deserialize)]
+/// A single assistant reply, normalized across backends
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ApiResponse {
+    #[serde(default)]
     pub id: String,
     pub content: Vec<ContentBlock>,
     pub stop_reason: Option<String>,
     pub usage: Option<Usage>,
 }
 
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Usage {
-    pub input_tokens: u32,
-    pub output_tokens: u32,
-}
-
-/// Stream event types
-#[derive(Debug, Deserialize)]
-#[serde(tag = "type")]
-pub enum StreamEvent {
-    #[serde(rename = "message_start")]
-    MessageStart { message: ApiResponse },
-    #[serde(rename = "content_block_start")]
-    ContentBlockStart {
-        index: usize,
-        content_block: ContentBlock,
-    },
-    #[serde(rename = "content_block_delta")]
-    ContentBlockDelta {
-        index: usize,
-        delta: ContentDelta,
-    },
-    #[serde(rename = "content_block_stop")]
-    ContentBlockStop { index: usize },
-    #[serde(rename = "message_delta")]
-    MessageDelta {
-        delta: MessageDeltaContent,
-        usage: Option<Usage>,
-    },
-    #[serde(rename = "message_stop")]
-    MessageStop,
-    #[serde(rename = "ping")]
-    Ping,
-    #[serde(rename = "error")]
-    Error { error: ApiError },
-}
-
-#[derive(Debug, Deserialize)]
-pub struct ContentDelta {
-    #[serde(rename = "type")]
-    pub delta_type: String,
     #[serde(default)]
-    pub text: String,
+    pub input_tokens: u32,
     #[serde(default)]
-    pub partial_json: String,
-}
-
-#[derive(Debug, Deserialize)]
-pub struct MessageDeltaContent {
-    pub stop_reason: Option<String>,
-}
-
-#[derive(Debug, Deserialize)]
-pub struct ApiError {
-    #[serde(rename = "type")]
-    pub error_type: String,
-    pub message: String,
+    pub output_tokens: u32,
 }
 
-/// Claude client for making API calls
+/// Drives the agentic loop against a model backend
 pub struct ClaudeClient {
-    client: Client,
-    api_key: String,
+    backend: Arc<dyn ModelBackend>,
     project_root: std::path::PathBuf,
+    transcript: Option<Transcript>,
+    compactor: Option<Compactor>,
+    defer_close: bool,
+    approval: Option<Approval>,
+    sandbox: Option<Sandbox>,
+    costs: Option<CostMeter>,
+    pricing: Pricing,
+    control: Option<LoopControl>,
+    mcp: Option<Arc<McpTools>>,
+    /// Background processes started with `process`, killed with the client
+    processes: ProcessTable,
 }
 
 impl ClaudeClient {
-    pub fn new(api_key: String, project_root: impl AsRef<Path>) -> Self {
+    pub fn new(backend: Arc<dyn ModelBackend>, project_root: impl AsRef<Path>) -> Self {
         Self {
-            client: Client::new(),
-            api_key,
+            pricing: Pricing::for_model(backend.name(), backend.model()),
+            backend,
             project_root: project_root.as_ref().to_path_buf(),
+            transcript: None,
+            compactor: None,
+            defer_close: false,
+            approval: None,
+            sandbox: None,
+            costs: None,
+            control: None,
+            mcp: None,
+            processes: ProcessTable::default(),
+        }
+    }
+
+    /// Record every iteration to a task transcript
+    pub fn with_transcript(mut self, transcript: Transcript) -> Self {
+        self.transcript = Some(transcript);
+        self
+    }
+
+    /// Compact the conversation near the redline instead of stopping
+    ///
+    /// `None` keeps the hard reset behaviour.
+    pub fn with_compaction(mut self, compactor: Option<Compactor>) -> Self {
+        self.compactor = compactor;
+        self
+    }
+
+    /// Hold back the model's `beads close` calls
+    ///
+    /// Used when a verification gate decides whether the task is done: the
+    /// close is acknowledged but not executed, and its reason is returned in
+    /// `LoopResult::close_reason` for the harness to use.
+    pub fn defer_task_close(mut self, defer: bool) -> Self {
+        self.defer_close = defer;
+        self
+    }
+
+    /// Ask a human before running `bash` and `edit_file` calls (attended mode)
+    pub fn with_approval(mut self, approval: Option<Approval>) -> Self {
+        self.approval = approval;
+        self
+    }
+
+    /// Run `bash` calls in a sandbox (`sandbox` in the project config)
+    pub fn with_sandbox(mut self, sandbox: Option<Sandbox>) -> Self {
+        self.sandbox = sandbox;
+        self
+    }
+
+    /// Record spend to the ledger and stop when a budget is spent
+    pub fn with_costs(mut self, meter: CostMeter) -> Self {
+        self.costs = Some(meter);
+        self
+    }
+
+    /// Let a front end pause, steer, skip or abort the loop between iterations
+    pub fn with_control(mut self, control: Option<LoopControl>) -> Self {
+        self.control = control;
+        self
+    }
+
+    /// Offer the tools of the project's MCP servers alongside the primitives
+    pub fn with_mcp(mut self, mcp: Option<Arc<McpTools>>) -> Self {
+        self.mcp = mcp;
+        self
+    }
+
+    /// Execute a tool call, through the approval gate if there is one
+    async fn execute(&self, name: &str, input: &serde_json::Value) -> ToolResult {
+        if let Some(error) = invalid_arguments(input) {
+            return ToolResult::error(error);
+        }
+        let Some(gate) = &self.approval else {
+            return self.dispatch(name, input).await;
+        };
+
+        let approved = match gate.check(name, input).await {
+            Ok(approved) => approved,
+            Err(denied) => return ToolResult::error(denied),
+        };
+        let mut result = self.dispatch(name, &approved).await;
+        if let Some(note) = approval::edited_note(name, input, &approved) {
+            match &mut result.error {
+                Some(error) => error.insert_str(0, &note),
+                None => result.output.insert_str(0, &note),
+            }
+        }
+        result
+    }
+
+    /// Route a call to the MCP server that owns the tool, or to the primitives
+    async fn dispatch(&self, name: &str, input: &serde_json::Value) -> ToolResult {
+        if let Some(mcp) = &self.mcp {
+            if let Some(result) = mcp.call(name, input).await {
+                return result;
+            }
         }
+        execute_tool(name, input, &self.project_root, self.sandbox.as_ref(), &self.processes).await
     }
 
-    /// Run a complete agentic loop for a task
+    /// Run the agentic loop from a resume point
+    ///
+    /// `ResumePoint::fresh` starts a new task; a point rebuilt from a
+    /// transcript carries on its iteration numbering with a fresh budget of
+    /// `max_iterations`.
     ///
     /// Returns when:
     /// - The model completes without tool calls (end_turn)
@@ -236,71 +290,239 @@ impl ClaudeClient {
     /// - Token redline exceeded (needs fresh context)
     /// - An error occurs
     ///
+    /// With compaction enabled the redline is measured against the live
+    /// context (the last request plus its reply). Nearing it compacts the
+    /// conversation; the loop only stops with `Redline` if the context is
+    /// still over the threshold right after a compaction.
+    ///
     /// Includes exploration detection: if the agent makes 5+ exploration calls
     /// (read_file, list_files, code_search) without any edits, an intervention
     /// message is injected to nudge it toward action.
-    pub async fn run_agentic_loop(
+    pub async fn resume_agentic_loop(
         &self,
         system_prompt: &str,
-        initial_message: &str,
+        resume: ResumePoint,
         max_iterations: usize,
         redline_threshold: u32,
-        output_tx: Option<mpsc::Sender<String>>,
+        events: Option<EventSender>,
     ) -> Result<LoopResult> {
-        let mut messages = vec![Message {
-            role: Role::User,
-            content: vec![ContentBlock::Text {
-                text: initial_message.to_string(),
-            }],
-        }];
-
-        let mut total_input_tokens = 0u32;
-        let mut total_output_tokens = 0u32;
-        let mut iterations = 0;
+        self.run_session(system_prompt, resume, max_iterations, redline_threshold, events)
+            .await
+    }
+
+    /// Run one transcript session: start record, the loop, end record
+    async fn run_session(
+        &self,
+        system_prompt: &str,
+        start: ResumePoint,
+        max_iterations: usize,
+        redline_threshold: u32,
+        events: Option<EventSender>,
+    ) -> Result<LoopResult> {
+        self.record(|t| {
+            t.start(
+                self.backend.name(),
+                self.backend.model(),
+                start.iteration,
+                self.approval.is_some(),
+                &start.messages,
+            )
+        });
+
+        let mut spent = Spent {
+            iterations: start.iteration,
+            ..Default::default()
+        };
+        let result = match self
+            .drive_loop(system_prompt, start, max_iterations, redline_threshold, events.as_ref(), &mut spent)
+            .await
+        {
+            Ok(result) => result,
+            Err(e) => {
+                // The calls made before the error were still billed
+                if let Some(meter) = &self.costs {
+                    if let Err(e) = meter.record_failed(self.backend.name(), self.backend.model(), &spent, &e) {
+                        tracing::warn!("Failed to record spend: {}", e);
+                    }
+                }
+                return Err(e);
+            }
+        };
+
+        self.record(|t| t.finish(&result));
+        if let Some(meter) = &self.costs {
+            if let Err(e) = meter.record(self.backend.name(), self.backend.model(), &result) {
+                tracing::warn!("Failed to record spend: {}", e);
+            }
+        }
+        Ok(result)
+    }
+
+    /// Write to the transcript, if any; a failed write never stops the loop
+    fn record(&self, write: impl FnOnce(&Transcript) -> Result<()>) {
+        if let Some(transcript) = &self.transcript {
+            if let Err(e) = write(transcript) {
+                tracing::warn!("Failed to write transcript {}: {}", transcript.path().display(), e);
+            }
+        }
+    }
+
+    async fn drive_loop(
+        &self,
+        system_prompt: &str,
+        start: ResumePoint,
+        max_iterations: usize,
+        redline_threshold: u32,
+        events: Option<&EventSender>,
+        spent: &mut Spent,
+    ) -> Result<LoopResult> {
+        let mut messages = start.messages;
+
+        let mut iterations = start.iteration;
+        let last_iteration = start.iteration + max_iterations;
         
         // Track tool usage to detect exploration spirals
         let mut session_metrics = IterationMetrics::default();
         let mut intervention_sent = false;
         
         // Track tool outputs for progress recording
-        let mut tool_outputs: Vec<String> = Vec::new();
+        let mut tool_outputs: Vec<String> = start.tool_outputs;
+
+        // Size of the last request + reply, and compaction bookkeeping
+        let mut context_tokens = 0u32;
+        let mut compactions = 0usize;
+        let mut compaction_attempted = false;
+        let mut close_reason: Option<String> = None;
 
         loop {
             iterations += 1;
+            spent.iterations = iterations;
+            // Messages added this iteration, for the transcript
+            let mut injected = Vec::new();
+            // Steering and interventions for the next call
+            let mut notes = Vec::new();
 
-            if iterations > max_iterations {
+            if iterations > last_iteration {
                 tracing::warn!("Max iterations ({}) reached", max_iterations);
+                emit(events, stopped(StopReason::MaxIterations, iterations, None)).await;
                 return Ok(LoopResult {
                     iterations,
-                    total_input_tokens,
-                    total_output_tokens,
+                    total_input_tokens: spent.input_tokens,
+                    total_output_tokens: spent.output_tokens,
+                    cost_usd: spent.cost_usd,
                     final_text: String::new(),
                     messages,
                     stop_reason: StopReason::MaxIterations,
                     tool_outputs,
+                    compactions,
+                    close_reason,
                 });
             }
 
-            // Log iteration with metrics
-            if let Some(tx) = &output_tx {
-                let _ = tx
-                    .send(format!(
-                        "\n--- Iteration {} [explore:{}/action:{}] ---\n",
-                        iterations,
-                        session_metrics.total_exploration(),
-                        session_metrics.total_action()
-                    ))
-                    .await;
+            // Stop before the next call once a budget is spent
+            if let Some(exceeded) = self.costs.as_ref().and_then(|m| m.exceeded()) {
+                tracing::warn!("Budget exceeded: {}", exceeded);
+                emit(events, stopped(StopReason::BudgetExceeded, iterations, Some(exceeded.to_string()))).await;
+                return Ok(LoopResult {
+                    iterations,
+                    total_input_tokens: spent.input_tokens,
+                    total_output_tokens: spent.output_tokens,
+                    cost_usd: spent.cost_usd,
+                    final_text: String::new(),
+                    messages,
+                    stop_reason: StopReason::BudgetExceeded,
+                    tool_outputs,
+                    compactions,
+                    close_reason,
+                });
+            }
+
+            // Wait here while paused; pick up steering, skip or abort
+            if let Some(control) = &self.control {
+                match control.checkpoint().await {
+                    Checkpoint::Stop(interrupt) => {
+                        let reason = match interrupt {
+                            Interrupt::Skip => StopReason::Skipped,
+                            Interrupt::Abort => StopReason::Aborted,
+                        };
+                        tracing::info!("Loop stopped by the user: {:?}", reason);
+                        emit(events, stopped(reason.clone(), iterations, None)).await;
+                        return Ok(LoopResult {
+                            iterations,
+                            total_input_tokens: spent.input_tokens,
+                            total_output_tokens: spent.output_tokens,
+                            cost_usd: spent.cost_usd,
+                            final_text: String::new(),
+                            messages,
+                            stop_reason: reason,
+                            tool_outputs,
+                            compactions,
+                            close_reason,
+                        });
+                    }
+                    Checkpoint::Continue { steering } => {
+                        for text in steering {
+                            emit(events, LoopEvent::Steering { message: text.clone() }).await;
+                            notes.push(ContentBlock::Text {
+                                text: format!("[STEERING FROM THE USER] {}", text),
+                            });
+                        }
+                    }
+                }
             }
+
+            // Log iteration with metrics
+            emit(
+                events,
+                LoopEvent::IterationStart {
+                    iteration: iterations,
+                    exploration: session_metrics.total_exploration(),
+                    action: session_metrics.total_action(),
+                },
+            )
+            .await;
             
+            // Compact before the context reaches the redline
+            if let Some(compactor) = &self.compactor {
+                if !compaction_attempted && compactor.should_compact(context_tokens, redline_threshold) {
+                    // With nothing old enough to compact, only stop once actually over
+                    compaction_attempted = context_tokens > redline_threshold;
+                    if let Some(compacted) = compactor.compact(&messages, iterations - 1, self.costs.as_ref()).await {
+                        compaction_attempted = true;
+                        tracing::info!(
+                            "Compacted context at {} tokens: {} -> {} messages",
+                            context_tokens,
+                            messages.len(),
+                            compacted.len()
+                        );
+                        emit(
+                            events,
+                            LoopEvent::Compacted {
+                                context_tokens,
+                                messages_before: messages.len(),
+                                messages_after: compacted.len(),
+                            },
+                        )
+                        .await;
+                        messages = compacted;
+                        compactions += 1;
+                        self.record(|t| t.record_compaction(iterations - 1, &messages));
+                    }
+                }
+            }
+
             // Check for exploration spiral every 3 iterations
             if iterations % 3 == 0 && session_metrics.is_exploration_heavy() && !intervention_sent {
                 intervention_sent = true;
                 let intervention = session_metrics.intervention_message();
                 
-                if let Some(tx) = &output_tx {
-                    let _ = tx.send(format!("\n{}\n", intervention)).await;
-                }
+                emit(
+                    events,
+                    LoopEvent::Intervention {
+                        message: intervention.clone(),
+                    },
+                )
+                .await;
                 
                 tracing::warn!(
                     "Exploration spiral detected: {} exploration calls, {} edits",
@@ -308,47 +530,91 @@ impl ClaudeClient {
                     session_metrics.edit_file_count
                 );
                 
-                // Inject intervention as a user message
-                messages.push(Message {
+                notes.push(ContentBlock::Text { text: intervention });
+            }
+
+            // Notes join the tool results (or the prompt) in one user message
+            if !notes.is_empty() {
+                injected.push(Message {
                     role: Role::User,
-                    content: vec![ContentBlock::Text { text: intervention }],
+                    content: notes.clone(),
                 });
+                append_user(&mut messages, notes);
             }
 
             // Make API call
             let response = self
-                .call_api(system_prompt, &messages, output_tx.clone())
+                .call_api(system_prompt, &messages, events)
                 .await?;
 
             // Track token usage
             if let Some(usage) = &response.usage {
-                total_input_tokens += usage.input_tokens;
-                total_output_tokens += usage.output_tokens;
+                spent.input_tokens += usage.input_tokens;
+                spent.output_tokens += usage.output_tokens;
+                context_tokens = usage.input_tokens + usage.output_tokens;
+
+                let cost = self.pricing.cost(usage.input_tokens, usage.output_tokens);
+                spent.cost_usd += cost;
+                if let Some(meter) = &self.costs {
+                    meter.add(cost);
+                }
+                emit(
+                    events,
+                    LoopEvent::TokenUsage {
+                        input_tokens: usage.input_tokens,
+                        output_tokens: usage.output_tokens,
+                        total_input_tokens: spent.input_tokens,
+                        total_output_tokens: spent.output_tokens,
+                        cost_usd: cost,
+                    },
+                )
+                .await;
             }
 
             // Check for context redline - STOP if exceeded
-            let total_tokens = total_input_tokens + total_output_tokens;
-            if total_tokens > redline_threshold {
+            let redline_tokens = match &self.compactor {
+                Some(compactor) => {
+                    if !compactor.should_compact(context_tokens, redline_threshold) {
+                        compaction_attempted = false;
+                    }
+                    // Over the line but not yet compacted: compact next iteration
+                    if compaction_attempted { context_tokens } else { 0 }
+                }
+                None => spent.input_tokens + spent.output_tokens,
+            };
+            if redline_tokens > redline_threshold {
                 tracing::warn!(
                     "Context redline exceeded: {} tokens (threshold: {}). Stopping for fresh context.",
-                    total_tokens, redline_threshold
+                    redline_tokens, redline_threshold
                 );
-                if let Some(tx) = &output_tx {
-                    let _ = tx
-                        .send(format!(
-                            "\n[REDLINE EXCEEDED: {} tokens > {} threshold - stopping for fresh context]\n",
-                            total_tokens, redline_threshold
-                        ))
-                        .await;
+                let detail = format!("{} tokens > {} threshold", redline_tokens, redline_threshold);
+
+                // The reply is paid for, so keep it; its tool calls never ran
+                let not_run = not_run(&response.content);
+                self.record(|t| t.record_iteration(iterations, &injected, &response, &not_run));
+                messages.push(Message {
+                    role: Role::Assistant,
+                    content: response.content,
+                });
+                if !not_run.is_empty() {
+                    messages.push(Message {
+                        role: Role::User,
+                        content: not_run,
+                    });
                 }
+
+                emit(events, stopped(StopReason::Redline, iterations, Some(detail))).await;
                 return Ok(LoopResult {
                     iterations,
-                    total_input_tokens,
-                    total_output_tokens,
+                    total_input_tokens: spent.input_tokens,
+                    total_output_tokens: spent.output_tokens,
+                    cost_usd: spent.cost_usd,
                     final_text: String::new(),
                     messages,
                     stop_reason: StopReason::Redline,
                     tool_outputs,
+                    compactions,
+                    close_reason,
                 });
             }
 
@@ -371,9 +637,12 @@ impl ClaudeClient {
                 .collect();
 
             if tool_calls.is_empty() {
+                self.record(|t| t.record_iteration(iterations, &injected, &response, &[]));
+
                 // No tool calls - check stop reason
-                if response.stop_reason.as_deref() == Some("end_turn") {
+                if response.stop_reason.as_deref() == Some(STOP_END_TURN) {
                     tracing::info!("Loop completed: end_turn");
+                    emit(events, stopped(StopReason::Completed, iterations, None)).await;
                     // Extract final text before returning
                     let final_text = messages
                         .iter()
@@ -392,12 +661,15 @@ impl ClaudeClient {
 
                     return Ok(LoopResult {
                         iterations,
-                        total_input_tokens,
-                        total_output_tokens,
+                        total_input_tokens: spent.input_tokens,
+                        total_output_tokens: spent.output_tokens,
+                        cost_usd: spent.cost_usd,
                         final_text,
                         messages,
                         stop_reason: StopReason::Completed,
                         tool_outputs,
+                        compactions,
+                        close_reason,
                     });
                 }
             } else {
@@ -409,49 +681,71 @@ impl ClaudeClient {
                     session_metrics.record_tool(&name);
                     
                     // Reset intervention flag if we finally make an edit
-                    if name == "edit_file" {
+                    if EDIT_TOOLS.contains(&name.as_str()) {
                         intervention_sent = false; // Allow future interventions if we spiral again
                     }
                     
-                    if let Some(tx) = &output_tx {
-                        let _ = tx
-                            .send(format!("\n[Executing tool: {}]\n", name))
-                            .await;
-                    }
-
-                    let result = execute_tool(&name, &input, &self.project_root).await;
+                    emit(
+                        events,
+                        LoopEvent::ToolCall {
+                            id: id.clone(),
+                            name: name.clone(),
+                            input: input.clone(),
+                        },
+                    )
+                    .await;
 
-                    // Log result preview
-                    if let Some(tx) = &output_tx {
-                        let preview = if result.output.len() > 500 {
-                            // Safe truncation respecting UTF-8 character boundaries
-                            let mut end = 500;
-                            while !result.output.is_char_boundary(end) && end > 0 {
-                                end -= 1;
-                            }
-                            format!("{}...[truncated]", &result.output[..end])
-                        } else {
-                            result.output.clone()
-                        };
-                        let _ = tx.send(format!("{}\n", preview)).await;
-                    }
+                    let started = Instant::now();
+                    let is_close = name == "beads"
+                        && input.get("action").and_then(|v| v.as_str()) == Some("close");
+                    let result = if self.defer_close && is_close {
+                        close_reason = Some(
+                            input
+                                .get("reason")
+                                .and_then(|v| v.as_str())
+                                .unwrap_or_default()
+                                .to_string(),
+                        );
+                        ToolResult::success(
+                            "Close deferred: verification runs after you end your turn, \
+                             and the task is closed if it passes. Finish up and end your turn.",
+                        )
+                    } else {
+                        self.execute(&name, &input).await
+                    };
 
-                    // Track edit_file outputs for progress recording
-                    if name == "edit_file" && result.success {
+                    // Track edit outputs for progress recording
+                    if EDIT_TOOLS.contains(&name.as_str()) && result.success {
                         tool_outputs.push(result.output.clone());
                     }
 
+                    let content = if result.success {
+                        result.output
+                    } else {
+                        result.error.unwrap_or_else(|| "Unknown error".to_string())
+                    };
+                    emit(
+                        events,
+                        LoopEvent::ToolResult {
+                            id: id.clone(),
+                            name,
+                            success: result.success,
+                            output: content.clone(),
+                            duration_ms: started.elapsed().as_millis() as u64,
+                            violation: result.violation,
+                        },
+                    )
+                    .await;
+
                     tool_results.push(ContentBlock::ToolResult {
                         tool_use_id: id,
-                        content: if result.success {
-                            result.output
-                        } else {
-                            result.error.unwrap_or_else(|| "Unknown error".to_string())
-                        },
+                        content,
                         is_error: if result.success { None } else { Some(true) },
                     });
                 }
 
+                self.record(|t| t.record_iteration(iterations, &injected, &response, &tool_results));
+
                 // Add tool results as user message
                 messages.push(Message {
                     role: Role::User,
@@ -461,174 +755,96 @@ impl ClaudeClient {
         }
     }
 
-    /// Make a single API call with streaming
+    /// Make a single model call with the current conversation
+    ///
+    /// Backends stream plain text; it is forwarded as `TextDelta` events.
     async fn call_api(
         &self,
         system_prompt: &str,
         messages: &[Message],
-        output_tx: Option<mpsc::Sender<String>>,
+        events: Option<&EventSender>,
     ) -> Result<ApiResponse> {
-        let tools: Vec<ApiTool> = get_tool_definitions()
-            .into_iter()
-            .map(|t| ApiTool {
-                name: t.name,
-                description: t.description,
-                input_schema: t.input_schema,
-            })
-            .collect();
+        let mut tools = get_tool_definitions();
+        if let Some(mcp) = &self.mcp {
+            tools.extend(mcp.definitions().await);
+        }
 
-        let request = ApiRequest {
-            model: CLAUDE_MODEL.to_string(),
-            max_tokens: 8192,
-            system: system_prompt.to_string(),
-            messages: messages.to_vec(),
-            tools,
-            stream: Some(true),
+        let request = ModelRequest {
+            system: system_prompt,
+            messages,
+            tools: &tools,
+            max_tokens: DEFAULT_MAX_TOKENS,
         };
 
-        let response = self
-            .client
-            .post(CLAUDE_API_URL)
-            .header("x-api-key", &self.api_key)
-            .header("anthropic-version", "2023-06-01")
-            .header("content-type", "application/json")
-            .json(&request)
-            .send()
-            .await
-            .context("Failed to send request to Claude API")?;
-
-        if !response.status().is_success() {
-            let status = response.status();
-            let body = response.text().await.unwrap_or_default();
-            anyhow::bail!("Claude API error {}: {}", status, body);
-        }
-
-        // Process SSE stream
-        self.process_stream(response, output_tx).await
+        let Some(events) = events.cloned() else {
+            return self.backend.complete(request, None).await;
+        };
+        let (text_tx, mut text_rx) = mpsc::channel::<String>(100);
+        let forward = tokio::spawn(async move {
+            while let Some(text) = text_rx.recv().await {
+                let _ = events.send(LoopEvent::TextDelta { text }).await;
+            }
+        });
+        let response = self.backend.complete(request, Some(text_tx)).await;
+        let _ = forward.await;
+        response
     }
+}
 
-    /// Process SSE stream and reconstruct response
-    async fn process_stream(
-        &self,
-        response: reqwest::Response,
-        output_tx: Option<mpsc::Sender<String>>,
-    ) -> Result<ApiResponse> {
-        let mut content_blocks: Vec<ContentBlock> = Vec::new();
-        let mut current_text = String::new();
-        let mut current_tool_input = String::new();
-        let mut current_block_index: Option<usize> = None;
-        let mut stop_reason: Option<String> = None;
-        let mut usage: Option<Usage> = None;
-        let mut message_id = String::new();
-
-        let stream = response.bytes_stream();
-        let mut buffer = String::new();
-
-        tokio::pin!(stream);
-
-        while let Some(chunk) = stream.next().await {
-            let chunk = chunk.context("Failed to read stream chunk")?;
-            buffer.push_str(&String::from_utf8_lossy(&chunk));
-
-            // Process complete lines
-            while let Some(newline_pos) = buffer.find('\n') {
-                let line = buffer[..newline_pos].trim().to_string();
-                buffer = buffer[newline_pos + 1..].to_string();
-
-                if line.is_empty() || line == "event: message_start" || line.starts_with("event:") {
-                    continue;
-                }
+/// A backend keeps tool arguments it couldn't parse as the raw string;
+/// the model gets the parse error back instead of a run with no arguments
+fn invalid_arguments(input: &serde_json::Value) -> Option<String> {
+    let serde_json::Value::String(raw) = input else {
+        return None;
+    };
+    Some(match serde_json::from_str::<serde_json::Value>(raw) {
+        Err(e) => format!("Tool arguments are not valid JSON ({}): {}", e, raw),
+        Ok(_) => format!("Tool arguments must be a JSON object, got: {}", raw),
+    })
+}
 
-                if let Some(data) = line.strip_prefix("data: ") {
-                    if data == "[DONE]" {
-                        continue;
-                    }
+/// Error results for tool calls skipped at the redline, so a resumed
+/// conversation answers every call
+fn not_run(content: &[ContentBlock]) -> Vec<ContentBlock> {
+    content
+        .iter()
+        .filter_map(|block| match block {
+            ContentBlock::ToolUse { id, .. } => Some(ContentBlock::ToolResult {
+                tool_use_id: id.clone(),
+                content: "Not run: the context redline was reached first. Call it again if you still need it."
+                    .to_string(),
+                is_error: Some(true),
+            }),
+            _ => None,
+        })
+        .collect()
+}
 
-                    match serde_json::from_str::<StreamEvent>(data) {
-                        Ok(event) => match event {
-                            StreamEvent::MessageStart { message } => {
-                                message_id = message.id;
-                            }
-                            StreamEvent::ContentBlockStart {
-                                index,
-                                content_block,
-                            } => {
-                                current_block_index = Some(index);
-                                match &content_block {
-                                    ContentBlock::Text { .. } => {
-                                        current_text.clear();
-                                    }
-                                    ContentBlock::ToolUse { .. } => {
-                                        current_tool_input.clear();
-                                    }
-                                    _ => {}
-                                }
-                                content_blocks.push(content_block);
-                            }
-                            StreamEvent::ContentBlockDelta { index, delta } => {
-                                if delta.delta_type == "text_delta" {
-                                    current_text.push_str(&delta.text);
-                                    // Stream text to output
-                                    if let Some(tx) = &output_tx {
-                                        let _ = tx.send(delta.text.clone()).await;
-                                    }
-                                } else if delta.delta_type == "input_json_delta" {
-                                    current_tool_input.push_str(&delta.partial_json);
-                                }
-                            }
-                            StreamEvent::ContentBlockStop { index } => {
-                                if let Some(block) = content_blocks.get_mut(index) {
-                                    match block {
-                                        ContentBlock::Text { text } => {
-                                            *text = current_text.clone();
-                                        }
-                                        ContentBlock::ToolUse { input, .. } => {
-                                            if let Ok(parsed) =
-                                                serde_json::from_str(&current_tool_input)
-                                            {
-                                                *input = parsed;
-                                            }
-                                        }
-                                        _ => {}
-                                    }
-                                }
-                            }
-                            StreamEvent::MessageDelta {
-                                delta,
-                                usage: delta_usage,
-                            } => {
-                                stop_reason = delta.stop_reason;
-                                if delta_usage.is_some() {
-                                    usage = delta_usage;
-                                }
-                            }
-                            StreamEvent::MessageStop => {
-                                // Stream complete
-                            }
-                            StreamEvent::Error { error } => {
-                                anyhow::bail!("Stream error: {} - {}", error.error_type, error.message);
-                            }
-                            StreamEvent::Ping => {}
-                        },
-                        Err(e) => {
-                            tracing::debug!("Failed to parse stream event: {} - {}", e, data);
-                        }
-                    }
-                }
-            }
-        }
+/// Add `content` to the trailing user message, or start one after a reply
+pub(crate) fn append_user(messages: &mut Vec<Message>, content: Vec<ContentBlock>) {
+    match messages.last_mut() {
+        Some(Message {
+            role: Role::User,
+            content: last,
+        }) => last.extend(content),
+        _ => messages.push(Message {
+            role: Role::User,
+            content,
+        }),
+    }
+}
 
-        Ok(ApiResponse {
-            id: message_id,
-            content: content_blocks,
-            stop_reason,
-            usage,
-        })
+fn stopped(reason: StopReason, iterations: usize, detail: Option<String>) -> LoopEvent {
+    LoopEvent::Stopped {
+        reason,
+        iterations,
+        detail,
     }
 }
 
 /// Why the loop stopped
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
 pub enum StopReason {
     /// Completed successfully (end_turn)
     Completed,
@@ -636,6 +852,12 @@ pub enum StopReason {
     MaxIterations,
     /// Hit token redline - needs fresh context
     Redline,
+    /// A task, run or daily budget is spent
+    BudgetExceeded,
+    /// A human skipped the task (TUI)
+    Skipped,
+    /// A human stopped the loop (TUI)
+    Aborted,
 }
 
 /// Result from running the agentic loop
@@ -644,48 +866,381 @@ pub struct LoopResult {
     pub iterations: usize,
     pub total_input_tokens: u32,
     pub total_output_tokens: u32,
+    /// Estimated spend for this session, at the backend's list prices
+    pub cost_usd: f64,
     pub final_text: String,
     pub messages: Vec<Message>,
     pub stop_reason: StopReason,
-    /// Tool outputs from edit_file calls (for progress tracking)
+    /// Tool outputs from edit_file and multi_edit calls (for progress tracking)
     pub tool_outputs: Vec<String>,
+    /// Times the context was compacted instead of stopping at the redline
+    pub compactions: usize,
+    /// Reason given by the model for a deferred `beads close`
+    pub close_reason: Option<String>,
 }
 
 impl LoopResult {
     pub fn total_tokens(&self) -> u32 {
         self.total_input_tokens + self.total_output_tokens
     }
-
-    pub fn estimated_cost(&self) -> f64 {
-        // Sonnet pricing (as of Jan 2025)
-        // Input: $3 / 1M tokens
-        // Output: $15 / 1M tokens
-        let input_cost = (self.total_input_tokens as f64 / 1_000_000.0) * 3.0;
-        let output_cost = (self.total_output_tokens as f64 / 1_000_000.0) * 15.0;
-        input_cost + output_cost
-    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::backends::MockBackend;
+    use crate::config::BudgetConfig;
+    use crate::costs::{Costs, Ledger};
+    use tempfile::TempDir;
+
+    #[tokio::test]
+    async fn test_loop_with_mock_backend() {
+        let temp = TempDir::new().unwrap();
+        let backend = Arc::new(MockBackend::new(vec![
+            MockBackend::tool_use(
+                "edit_file",
+                serde_json::json!({"path": "hello.txt", "old_string": "", "new_string": "hi"}),
+            ),
+            MockBackend::text("Done."),
+        ]));
+
+        let client = ClaudeClient::new(backend.clone(), temp.path());
+        let (tx, mut rx) = mpsc::channel(100);
+        let result = client
+            .resume_agentic_loop("system", ResumePoint::fresh("Create hello.txt"), 10, 150_000, Some(tx))
+            .await
+            .unwrap();
+
+        assert_eq!(result.stop_reason, StopReason::Completed);
+        assert_eq!(result.iterations, 2);
+
+        let mut events = Vec::new();
+        while let Ok(event) = rx.try_recv() {
+            events.push(event);
+        }
+        let kinds: Vec<String> = events
+            .iter()
+            .map(|e| serde_json::to_value(e).unwrap()["event"].as_str().unwrap().to_string())
+            .collect();
+        assert_eq!(
+            kinds,
+            [
+                "iteration_start",
+                "token_usage",
+                "tool_call",
+                "tool_result",
+                "iteration_start",
+                "text_delta",
+                "token_usage",
+                "stopped"
+            ]
+        );
+        assert!(matches!(&events[3], LoopEvent::ToolResult { name, success: true, .. } if name == "edit_file"));
+        assert_eq!(result.final_text, "Done.");
+        assert_eq!(result.tool_outputs.len(), 1);
+        assert_eq!(std::fs::read_to_string(temp.path().join("hello.txt")).unwrap(), "hi");
+
+        // Second call carries the tool result back to the model
+        let requests = backend.requests();
+        assert_eq!(requests.len(), 2);
+        assert!(matches!(
+            requests[1].last().unwrap().content[0],
+            ContentBlock::ToolResult { .. }
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_multi_edit_counts_as_an_edit() {
+        let mut metrics = IterationMetrics::default();
+        for _ in 0..5 {
+            metrics.record_tool("read_file");
+        }
+        metrics.record_tool("multi_edit");
+        assert!(!metrics.is_exploration_heavy());
+
+        let temp = TempDir::new().unwrap();
+        let backend = Arc::new(MockBackend::new(vec![
+            MockBackend::tool_use(
+                "multi_edit",
+                serde_json::json!({"edits": [
+                    {"path": "a.txt", "old_string": "", "new_string": "a"},
+                    {"path": "b.txt", "old_string": "", "new_string": "b"}
+                ]}),
+            ),
+            MockBackend::text("Done."),
+        ]));
+
+        let client = ClaudeClient::new(backend, temp.path());
+        let result = client
+            .resume_agentic_loop("system", ResumePoint::fresh("Create both"), 10, 150_000, None)
+            .await
+            .unwrap();
 
-    #[test]
-    fn test_loop_result_cost() {
-        let result = LoopResult {
-            iterations: 5,
-            total_input_tokens: 100_000,
-            total_output_tokens: 10_000,
-            final_text: String::new(),
-            messages: vec![],
-            stop_reason: StopReason::Completed,
-            tool_outputs: vec![],
+        let expected: Vec<String> = ["a.txt", "b.txt"]
+            .iter()
+            .map(|f| temp.path().join(f).display().to_string())
+            .collect();
+        assert_eq!(crate::progress::extract_modified_files(&result.tool_outputs), expected);
+    }
+
+    #[tokio::test]
+    async fn test_invalid_tool_arguments_return_parse_error() {
+        let temp = TempDir::new().unwrap();
+        let backend = Arc::new(MockBackend::new(vec![
+            MockBackend::tool_use("read_file", serde_json::Value::String("{\"path\": ".to_string())),
+            MockBackend::text("Done."),
+        ]));
+
+        let client = ClaudeClient::new(backend.clone(), temp.path());
+        client
+            .resume_agentic_loop("system", ResumePoint::fresh("Read it"), 10, 150_000, None)
+            .await
+            .unwrap();
+
+        let requests = backend.requests();
+        match &requests[1].last().unwrap().content[0] {
+            ContentBlock::ToolResult { content, is_error, .. } => {
+                assert_eq!(*is_error, Some(true));
+                assert!(content.contains("not valid JSON"), "{}", content);
+                assert!(content.contains("{\"path\": "), "{}", content);
+            }
+            other => panic!("expected a tool result, got {:?}", other),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_resume_from_transcript() {
+        let temp = TempDir::new().unwrap();
+        let backend = Arc::new(MockBackend::new(vec![
+            MockBackend::tool_use(
+                "edit_file",
+                serde_json::json!({"path": "hello.txt", "old_string": "", "new_string": "hi"}),
+            ),
+            MockBackend::text("Done."),
+        ]));
+
+        // First session runs out of iterations right after the edit
+        let client = ClaudeClient::new(backend.clone(), temp.path())
+            .with_transcript(Transcript::new(temp.path(), "task-1"));
+        let result = client
+            .resume_agentic_loop("system", ResumePoint::fresh("Create hello.txt"), 1, 150_000, None)
+            .await
+            .unwrap();
+        assert_eq!(result.stop_reason, StopReason::MaxIterations);
+
+        let point = crate::transcript::load(temp.path(), "task-1").unwrap();
+        assert_eq!(point.iteration, 1);
+        assert_eq!(point.last_stop, Some(StopReason::MaxIterations));
+
+        let result = client
+            .resume_agentic_loop("system", point, 5, 150_000, None)
+            .await
+            .unwrap();
+        assert_eq!(result.stop_reason, StopReason::Completed);
+        assert_eq!(result.iterations, 2);
+        assert_eq!(result.tool_outputs.len(), 1);
+
+        // The resumed call saw the whole earlier conversation
+        assert_eq!(backend.requests()[1].len(), 3);
+    }
+
+    #[tokio::test]
+    async fn test_notes_join_the_user_message() {
+        // Five list_files calls over two turns trip the intervention on the third
+        let explore = |calls: usize| {
+            let mut response = MockBackend::tool_use("list_files", serde_json::json!({"path": "."}));
+            for _ in 1..calls {
+                response.content.extend(MockBackend::tool_use("list_files", serde_json::json!({"path": "."})).content);
+            }
+            response
+        };
+        let temp = TempDir::new().unwrap();
+        let backend = Arc::new(MockBackend::new(vec![explore(3), explore(2), MockBackend::text("Done.")]));
+
+        let control = LoopControl::new();
+        control.steer("Use the existing parser");
+        let client = ClaudeClient::new(backend.clone(), temp.path())
+            .with_transcript(Transcript::new(temp.path(), "task-1"))
+            .with_control(Some(control));
+        client
+            .resume_agentic_loop("system", ResumePoint::fresh("Add a parser"), 10, 150_000, None)
+            .await
+            .unwrap();
+
+        let requests = backend.requests();
+        let kinds = |message: &Message| -> Vec<&'static str> {
+            message
+                .content
+                .iter()
+                .map(|block| match block {
+                    ContentBlock::Text { .. } => "text",
+                    ContentBlock::ToolUse { .. } => "tool_use",
+                    ContentBlock::ToolResult { .. } => "tool_result",
+                })
+                .collect()
         };
+        // Steering rides with the prompt, the intervention with the tool results
+        assert_eq!(requests[0].len(), 1);
+        assert_eq!(kinds(&requests[0][0]), ["text", "text"]);
+        assert_eq!(requests[2].len(), 5);
+        assert_eq!(kinds(&requests[2][4]), ["tool_result", "tool_result", "text"]);
+
+        // A resume rebuilds the same conversation
+        let point = crate::transcript::load(temp.path(), "task-1").unwrap();
+        assert_eq!(point.messages.len(), 6);
+        assert_eq!(kinds(&point.messages[0]), ["text", "text"]);
+        assert_eq!(kinds(&point.messages[4]), ["tool_result", "tool_result", "text"]);
+    }
+
+    #[tokio::test]
+    async fn test_redline_reply_is_recorded() {
+        let temp = TempDir::new().unwrap();
+        let backend = Arc::new(MockBackend::new(vec![
+            MockBackend::tool_use(
+                "edit_file",
+                serde_json::json!({"path": "hello.txt", "old_string": "", "new_string": "hi"}),
+            ),
+            MockBackend::text("Done."),
+        ]));
+
+        // The first reply alone passes a 50-token redline
+        let client = ClaudeClient::new(backend.clone(), temp.path())
+            .with_transcript(Transcript::new(temp.path(), "task-1"));
+        let result = client
+            .resume_agentic_loop("system", ResumePoint::fresh("Create hello.txt"), 10, 50, None)
+            .await
+            .unwrap();
+        assert_eq!(result.stop_reason, StopReason::Redline);
+        assert!(!temp.path().join("hello.txt").exists());
+
+        let point = crate::transcript::load(temp.path(), "task-1").unwrap();
+        assert_eq!(point.iteration, 1);
+        assert_eq!(point.last_stop, Some(StopReason::Redline));
+        assert_eq!(point.messages.len(), 3);
+        assert!(matches!(
+            &point.messages[2].content[0],
+            ContentBlock::ToolResult { is_error: Some(true), content, .. } if content.starts_with("Not run")
+        ));
+
+        // The resumed call answers the skipped tool call
+        let result = client
+            .resume_agentic_loop("system", point, 5, 150_000, None)
+            .await
+            .unwrap();
+        assert_eq!(result.stop_reason, StopReason::Completed);
+        assert_eq!(backend.requests()[1].len(), 3);
+    }
+
+    #[tokio::test]
+    async fn test_compaction_instead_of_redline() {
+        use crate::compaction::{CompactionMethod, Compactor};
+
+        let with_context = |mut response: ApiResponse, tokens: u32| {
+            response.usage = Some(Usage {
+                input_tokens: tokens,
+                output_tokens: 0,
+            });
+            response
+        };
+        let list = || MockBackend::tool_use("list_files", serde_json::json!({"path": "."}));
+
+        let temp = TempDir::new().unwrap();
+        let backend = Arc::new(MockBackend::new(vec![
+            with_context(list(), 100),
+            with_context(list(), 600),
+            with_context(list(), 200),
+            with_context(MockBackend::text("Done."), 300),
+        ]));
+
+        // Cumulative tokens pass the 1000 redline, but the live context never does
+        let client = ClaudeClient::new(backend.clone(), temp.path())
+            .with_compaction(Some(Compactor::new(CompactionMethod::Truncate, 0.5, 1)));
+        let result = client
+            .resume_agentic_loop("system", ResumePoint::fresh("List the files"), 10, 1000, None)
+            .await
+            .unwrap();
+
+        assert_eq!(result.stop_reason, StopReason::Completed);
+        assert_eq!(result.compactions, 1);
+
+        // The call after the 600-token reply carried the state block
+        let requests = backend.requests();
+        assert_eq!(requests[1][0].content.len(), 1);
+        assert_eq!(requests[2][0].content.len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_budget_stops_loop() {
+        let temp = TempDir::new().unwrap();
+        let backend = Arc::new(MockBackend::new(vec![MockBackend::text("Done.")]));
+        let budget = BudgetConfig {
+            per_run_usd: Some(0.0),
+            ..Default::default()
+        };
+        let costs = Costs::new(temp.path(), &budget).unwrap();
+
+        let client = ClaudeClient::new(backend.clone(), temp.path()).with_costs(costs.meter("bd-1"));
+        let result = client
+            .resume_agentic_loop("system", ResumePoint::fresh("Do it"), 10, 150_000, None)
+            .await
+            .unwrap();
+        assert_eq!(result.stop_reason, StopReason::BudgetExceeded);
+        assert!(backend.requests().is_empty());
+
+        // Stopped sessions are still recorded
+        let entries = Ledger::new(temp.path()).entries().unwrap();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].stop_reason, Some(StopReason::BudgetExceeded));
+    }
+
+    /// Serves the first calls from a script, then fails every call
+    struct FailingBackend(MockBackend, usize);
+
+    #[async_trait::async_trait]
+    impl ModelBackend for FailingBackend {
+        fn name(&self) -> &str {
+            "anthropic"
+        }
+
+        fn model(&self) -> &str {
+            "claude-sonnet-4"
+        }
+
+        async fn complete(
+            &self,
+            request: ModelRequest<'_>,
+            output_tx: Option<mpsc::Sender<String>>,
+        ) -> Result<ApiResponse> {
+            if self.0.requests().len() >= self.1 {
+                anyhow::bail!("connection reset");
+            }
+            self.0.complete(request, output_tx).await
+        }
+    }
+
+    #[tokio::test]
+    async fn test_failed_session_records_spend() {
+        let temp = TempDir::new().unwrap();
+        let script = MockBackend::new(vec![MockBackend::tool_use(
+            "edit_file",
+            serde_json::json!({"path": "hello.txt", "old_string": "", "new_string": "hi"}),
+        )]);
+        let backend = Arc::new(FailingBackend(script, 1));
+        let costs = Costs::new(temp.path(), &BudgetConfig::default()).unwrap();
+
+        let client = ClaudeClient::new(backend, temp.path()).with_costs(costs.meter("bd-1"));
+        let error = client
+            .resume_agentic_loop("system", ResumePoint::fresh("Create hello.txt"), 10, 150_000, None)
+            .await
+            .unwrap_err();
+        assert!(error.to_string().contains("connection reset"));
 
-        // Input: 100k * $3/1M = $0.30
-        // Output: 10k * $15/1M = $0.15
-        // Total: $0.45
-        let cost = result.estimated_cost();
-        assert!((cost - 0.45).abs() < 0.01);
+        // The call before the error is on the ledger
+        let entries = Ledger::new(temp.path()).entries().unwrap();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].stop_reason, None);
+        assert_eq!((entries[0].input_tokens, entries[0].iterations), (100, 2));
+        assert!(entries[0].cost_usd > 0.0);
+        assert!(entries[0].error.as_deref().unwrap().contains("connection reset"));
     }
 }
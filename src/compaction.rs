@@ -0,0 +1,574 @@
+//! Context Compaction - Shrink the conversation instead of rebooting at the redline
+//!
+//! With `loop_config.on_redline: compact`, the loop compacts the conversation
+//! once the live context nears the redline and keeps going in the same
+//! session. Older tool results are either truncated or summarized by a
+//! (cheap) model call, and a structured "state so far" block - files edited,
+//! recent commands, acceptance criteria - is attached to the task prompt so
+//! the model doesn't redo finished work.
+//!
+//! The first message (the task prompt) and the last few assistant/tool
+//! exchanges are always kept verbatim.
+
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use crate::backends::{ModelBackend, ModelRequest};
+use crate::claude_client::{ContentBlock, Message, Role};
+use crate::config::LoopConfig;
+use crate::costs::{CostMeter, Purpose};
+use crate::task_parser::parse_acceptance_criteria;
+
+/// Recent assistant/tool exchanges kept verbatim when compacting
+pub const DEFAULT_KEEP_RECENT: usize = 4;
+
+/// Longest snippet of text or tool input quoted in an outline
+const SNIPPET_CHARS: usize = 120;
+
+/// Older tool results are cut to this many characters by `truncate`
+const TRUNCATED_RESULT_CHARS: usize = 500;
+
+/// Per-result limit when rendering history for the summarizer
+const SUMMARIZER_RESULT_CHARS: usize = 1500;
+
+/// Output token limit for the summary call
+const SUMMARY_MAX_TOKENS: u32 = 1024;
+
+/// Prefix of the state block attached to the task prompt
+const STATE_MARKER: &str = "[STATE SO FAR]";
+
+const SUMMARIZER_PROMPT: &str = "You compress the history of a coding agent's session. \
+Summarize what the agent has done and learned: files created or changed and why, \
+commands run and their outcome, errors hit and how they were resolved, and which \
+acceptance criteria are met or still open. Be concise and factual. Use a short bullet list.";
+
+/// What the loop does when the context reaches the redline
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RedlineStrategy {
+    /// Stop with `StopReason::Redline` and restart the task with a fresh context
+    #[default]
+    Reset,
+    /// Compact the conversation and continue in the same session
+    Compact,
+}
+
+/// How older history is shrunk
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CompactionMethod {
+    /// Cut older tool results and large tool inputs down by policy
+    #[default]
+    Truncate,
+    /// Replace older history with a model-written summary
+    Summarize,
+}
+
+/// Compacts a conversation when the context nears the redline
+#[derive(Clone)]
+pub struct Compactor {
+    method: CompactionMethod,
+    compact_at: f32,
+    keep_recent: usize,
+    summarizer: Option<Arc<dyn ModelBackend>>,
+}
+
+impl Compactor {
+    pub fn new(method: CompactionMethod, compact_at: f32, keep_recent: usize) -> Self {
+        Self {
+            method,
+            compact_at: compact_at.clamp(0.1, 1.0),
+            keep_recent: keep_recent.max(1),
+            summarizer: None,
+        }
+    }
+
+    /// Build from `loop_config`; `None` when the strategy is a hard reset
+    pub fn from_config(config: &LoopConfig) -> Option<Self> {
+        match config.on_redline {
+            RedlineStrategy::Reset => None,
+            RedlineStrategy::Compact => Some(Self::new(
+                config.compaction,
+                config.compact_at,
+                config.keep_recent,
+            )),
+        }
+    }
+
+    pub fn method(&self) -> CompactionMethod {
+        self.method
+    }
+
+    /// Backend used for `CompactionMethod::Summarize`
+    pub fn with_summarizer(mut self, backend: Arc<dyn ModelBackend>) -> Self {
+        self.summarizer = Some(backend);
+        self
+    }
+
+    pub fn summarizer(&self) -> Option<&Arc<dyn ModelBackend>> {
+        self.summarizer.as_ref()
+    }
+
+    /// Whether a context of `context_tokens` is close enough to the redline
+    pub fn should_compact(&self, context_tokens: u32, redline_threshold: u32) -> bool {
+        context_tokens as f32 >= redline_threshold as f32 * self.compact_at
+    }
+
+    /// Compact `messages` after `iteration` iterations
+    ///
+    /// Returns `None` when there is nothing old enough to compact. A summary
+    /// call is charged to the task through `meter`.
+    pub async fn compact(
+        &self,
+        messages: &[Message],
+        iteration: usize,
+        meter: Option<&CostMeter>,
+    ) -> Option<Vec<Message>> {
+        let tail = tail_start(messages, self.keep_recent)?;
+        let state = state_block(messages, iteration);
+
+        if self.method == CompactionMethod::Summarize {
+            if let Some(summarizer) = &self.summarizer {
+                match summarize(summarizer.as_ref(), &messages[1..tail], meter).await {
+                    Ok(summary) => {
+                        let note = format!("{}\n\nSummary of earlier iterations:\n{}", state, summary);
+                        return Some(replace_history(messages, tail, note));
+                    }
+                    Err(e) => tracing::warn!("Summarizing for compaction failed, truncating instead: {}", e),
+                }
+            }
+        }
+
+        Some(truncate(messages, tail, state))
+    }
+}
+
+/// Index where the verbatim tail begins
+///
+/// The tail always starts at an assistant message so every tool result stays
+/// paired with the call that produced it. `None` if the conversation holds no
+/// more than `keep_recent` exchanges.
+pub fn tail_start(messages: &[Message], keep_recent: usize) -> Option<usize> {
+    let assistant_indices: Vec<usize> = messages
+        .iter()
+        .enumerate()
+        .filter(|(_, m)| matches!(m.role, Role::Assistant))
+        .map(|(i, _)| i)
+        .collect();
+
+    let keep = keep_recent.max(1);
+    if assistant_indices.len() <= keep {
+        return None;
+    }
+    Some(assistant_indices[assistant_indices.len() - keep])
+}
+
+/// Replace older history with a rule-based outline (no model call)
+///
+/// Used when resuming with `--compact`.
+pub fn compact(messages: &[Message], keep_recent: usize) -> Vec<Message> {
+    match tail_start(messages, keep_recent) {
+        Some(tail) => {
+            let note = format!(
+                "[COMPACTED] Earlier iterations were compacted. Work done so far:\n{}",
+                outline(&messages[1..tail])
+            );
+            replace_history(messages, tail, note)
+        }
+        None => messages.to_vec(),
+    }
+}
+
+/// Keep the first message (plus `note`) and everything from `tail` on
+fn replace_history(messages: &[Message], tail: usize, note: String) -> Vec<Message> {
+    let mut compacted = vec![with_note(&messages[0], note)];
+    compacted.extend_from_slice(&messages[tail..]);
+    compacted
+}
+
+/// Shrink tool results and large tool inputs before `tail`, keeping structure
+fn truncate(messages: &[Message], tail: usize, state: String) -> Vec<Message> {
+    let mut compacted = messages.to_vec();
+    compacted[0] = with_note(&messages[0], state);
+
+    for message in &mut compacted[1..tail] {
+        for block in &mut message.content {
+            match block {
+                ContentBlock::ToolResult { content, .. } => {
+                    if content.chars().count() > TRUNCATED_RESULT_CHARS {
+                        *content = clip(content, TRUNCATED_RESULT_CHARS);
+                    }
+                }
+                ContentBlock::ToolUse { input, .. } => {
+                    if let Some(fields) = input.as_object_mut() {
+                        for value in fields.values_mut() {
+                            if let Some(s) = value.as_str().filter(|s| s.chars().count() > TRUNCATED_RESULT_CHARS) {
+                                *value = serde_json::Value::String(clip(s, TRUNCATED_RESULT_CHARS));
+                            }
+                        }
+                    }
+                }
+                ContentBlock::Text { .. } => {}
+            }
+        }
+    }
+
+    compacted
+}
+
+/// The task prompt with any previous compaction note replaced by `note`
+fn with_note(first: &Message, note: String) -> Message {
+    let mut content: Vec<ContentBlock> = first
+        .content
+        .iter()
+        .filter(|b| {
+            !matches!(b, ContentBlock::Text { text }
+                if text.starts_with(STATE_MARKER) || text.starts_with("[COMPACTED]"))
+        })
+        .cloned()
+        .collect();
+    content.push(ContentBlock::Text { text: note });
+
+    Message {
+        role: first.role.clone(),
+        content,
+    }
+}
+
+/// Structured summary of the session that survives compaction
+fn state_block(messages: &[Message], iteration: usize) -> String {
+    let mut tool_calls: HashMap<&str, (&str, &serde_json::Value)> = HashMap::new();
+    let mut files = Vec::new();
+    let mut commands = Vec::new();
+
+    for block in messages.iter().flat_map(|m| &m.content) {
+        match block {
+            ContentBlock::ToolUse { id, name, input } => {
+                tool_calls.insert(id, (name, input));
+            }
+            ContentBlock::ToolResult {
+                tool_use_id,
+                content,
+                is_error,
+            } => {
+                let Some((name, input)) = tool_calls.get(tool_use_id.as_str()) else {
+                    continue;
+                };
+                let ok = is_error.is_none();
+                match *name {
+                    "edit_file" if ok => {
+                        if let Some(path) = input.get("path").and_then(|v| v.as_str()) {
+                            files.push(path.to_string());
+                        }
+                    }
+                    "bash" => {
+                        if let Some(command) = input.get("command").and_then(|v| v.as_str()) {
+                            let passed = ok && content.starts_with("Exit code: 0");
+                            commands.push(format!(
+                                "- `{}` ({})",
+                                snippet(command),
+                                if passed { "ok" } else { "failed" }
+                            ));
+                        }
+                    }
+                    _ => {}
+                }
+            }
+            ContentBlock::Text { .. } => {}
+        }
+    }
+
+    files.sort();
+    files.dedup();
+
+    let mut block = format!(
+        "{} The conversation was compacted after iteration {}. Do not redo finished work.\n",
+        STATE_MARKER, iteration
+    );
+
+    block.push_str("\nFiles edited: ");
+    if files.is_empty() {
+        block.push_str("none yet");
+    } else {
+        block.push_str(&files.join(", "));
+    }
+    block.push('\n');
+
+    if !commands.is_empty() {
+        let recent = &commands[commands.len().saturating_sub(5)..];
+        block.push_str(&format!("\nRecent commands:\n{}\n", recent.join("\n")));
+    }
+
+    let prompt = messages
+        .first()
+        .map(|m| {
+            m.content
+                .iter()
+                .filter_map(|b| match b {
+                    ContentBlock::Text { text } => Some(text.as_str()),
+                    _ => None,
+                })
+                .collect::<Vec<_>>()
+                .join("\n")
+        })
+        .unwrap_or_default();
+    let criteria = parse_acceptance_criteria(&prompt);
+    if !criteria.is_empty() {
+        let lines: Vec<String> = criteria
+            .iter()
+            .map(|ac| format!("- [{}] {}", if ac.completed { "x" } else { " " }, ac.text))
+            .collect();
+        block.push_str(&format!("\nAcceptance criteria:\n{}\n", lines.join("\n")));
+    }
+
+    block
+}
+
+/// Ask the summarizer backend to condense older history
+async fn summarize(
+    backend: &dyn ModelBackend,
+    history: &[Message],
+    meter: Option<&CostMeter>,
+) -> anyhow::Result<String> {
+    let request_messages = vec![Message {
+        role: Role::User,
+        content: vec![ContentBlock::Text {
+            text: format!("Session history to summarize:\n\n{}", render(history)),
+        }],
+    }];
+
+    let response = backend
+        .complete(
+            ModelRequest {
+                system: SUMMARIZER_PROMPT,
+                messages: &request_messages,
+                tools: &[],
+                max_tokens: SUMMARY_MAX_TOKENS,
+            },
+            None,
+        )
+        .await?;
+    if let (Some(meter), Some(usage)) = (meter, &response.usage) {
+        if let Err(e) = meter.record_call(backend.name(), backend.model(), usage, Purpose::Compaction) {
+            tracing::warn!("Failed to record spend: {}", e);
+        }
+    }
+
+    let summary: Vec<&str> = response
+        .content
+        .iter()
+        .filter_map(|b| match b {
+            ContentBlock::Text { text } => Some(text.as_str()),
+            _ => None,
+        })
+        .collect();
+
+    if summary.is_empty() {
+        anyhow::bail!("summarizer returned no text");
+    }
+    Ok(summary.join("\n"))
+}
+
+/// Plain-text rendering of history for the summarizer
+fn render(history: &[Message]) -> String {
+    let mut out = String::new();
+    for message in history {
+        for block in &message.content {
+            match block {
+                ContentBlock::Text { text } => {
+                    let who = match message.role {
+                        Role::User => "user",
+                        Role::Assistant => "assistant",
+                    };
+                    out.push_str(&format!("[{}] {}\n", who, clip(text, SUMMARIZER_RESULT_CHARS)));
+                }
+                ContentBlock::ToolUse { name, input, .. } => {
+                    out.push_str(&format!("[tool {}] {}\n", name, clip(&input.to_string(), 500)));
+                }
+                ContentBlock::ToolResult { content, is_error, .. } => {
+                    let tag = if is_error == &Some(true) { "error" } else { "result" };
+                    out.push_str(&format!("[{}] {}\n", tag, clip(content, SUMMARIZER_RESULT_CHARS)));
+                }
+            }
+        }
+    }
+    out
+}
+
+/// One line per tool call (and per assistant remark) in `messages`
+fn outline(messages: &[Message]) -> String {
+    let mut failed: HashMap<&str, bool> = HashMap::new();
+    for block in messages.iter().flat_map(|m| &m.content) {
+        if let ContentBlock::ToolResult { tool_use_id, is_error, .. } = block {
+            failed.insert(tool_use_id, is_error == &Some(true));
+        }
+    }
+
+    let mut lines = Vec::new();
+    for message in messages.iter().filter(|m| matches!(m.role, Role::Assistant)) {
+        for block in &message.content {
+            match block {
+                ContentBlock::Text { text } => {
+                    if let Some(first_line) = text.lines().find(|l| !l.trim().is_empty()) {
+                        lines.push(format!("- said: {}", snippet(first_line)));
+                    }
+                }
+                ContentBlock::ToolUse { id, name, input } => {
+                    let target = ["path", "command", "pattern", "action"]
+                        .iter()
+                        .find_map(|k| input.get(*k).and_then(|v| v.as_str()))
+                        .map(str::to_string)
+                        .unwrap_or_else(|| input.to_string());
+                    let status = match failed.get(id.as_str()) {
+                        Some(true) => " (failed)",
+                        Some(false) => "",
+                        None => " (no result)",
+                    };
+                    lines.push(format!("- {} {}{}", name, snippet(&target), status));
+                }
+                ContentBlock::ToolResult { .. } => {}
+            }
+        }
+    }
+
+    lines.join("\n")
+}
+
+fn snippet(text: &str) -> String {
+    let text = text.trim();
+    if text.chars().count() > SNIPPET_CHARS {
+        let cut: String = text.chars().take(SNIPPET_CHARS).collect();
+        format!("{}...", cut)
+    } else {
+        text.to_string()
+    }
+}
+
+/// Keep the first `max_chars` characters and note how much was dropped
+fn clip(text: &str, max_chars: usize) -> String {
+    let total = text.chars().count();
+    if total <= max_chars {
+        return text.to_string();
+    }
+    let head: String = text.chars().take(max_chars).collect();
+    format!("{}\n...[compacted: {} more chars]", head, total - max_chars)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backends::MockBackend;
+    use crate::config::BudgetConfig;
+    use crate::costs::{Costs, Ledger};
+    use crate::transcript::ResumePoint;
+    use tempfile::TempDir;
+
+    fn conversation(exchanges: usize) -> Vec<Message> {
+        let mut messages =
+            ResumePoint::fresh("## TASK\n\n- [x] Parser exists\n- [ ] Parser handles errors").messages;
+        for i in 0..exchanges {
+            let id = format!("t{}", i);
+            let (name, input, result) = if i % 2 == 0 {
+                (
+                    "edit_file",
+                    serde_json::json!({"path": format!("src/file{}.rs", i), "old_string": "", "new_string": "x".repeat(2000)}),
+                    format!("Created new file: src/file{}.rs", i),
+                )
+            } else {
+                (
+                    "bash",
+                    serde_json::json!({"command": "cargo test"}),
+                    format!("Exit code: 0\n\nSTDOUT:\n{}", "y".repeat(2000)),
+                )
+            };
+            messages.push(Message {
+                role: Role::Assistant,
+                content: vec![ContentBlock::ToolUse { id: id.clone(), name: name.to_string(), input }],
+            });
+            messages.push(Message {
+                role: Role::User,
+                content: vec![ContentBlock::ToolResult { tool_use_id: id, content: result, is_error: None }],
+            });
+        }
+        messages
+    }
+
+    fn first_text(messages: &[Message]) -> String {
+        match messages[0].content.last().unwrap() {
+            ContentBlock::Text { text } => text.clone(),
+            _ => panic!("expected text"),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_truncate_keeps_structure_and_state() {
+        let messages = conversation(6);
+        let compactor = Compactor::new(CompactionMethod::Truncate, 0.8, 2);
+
+        let compacted = compactor.compact(&messages, 6, None).await.unwrap();
+        assert_eq!(compacted.len(), messages.len());
+
+        // Older tool results are clipped, the tail is untouched
+        let ContentBlock::ToolResult { content, .. } = &compacted[4].content[0] else {
+            panic!("expected tool result");
+        };
+        assert!(content.contains("[compacted:"));
+        let ContentBlock::ToolUse { input, .. } = &compacted[9].content[0] else {
+            panic!("expected tool use");
+        };
+        assert_eq!(input["new_string"].as_str().unwrap().len(), 2000);
+
+        let state = first_text(&compacted);
+        assert!(state.starts_with(STATE_MARKER));
+        assert!(state.contains("src/file0.rs, src/file2.rs, src/file4.rs"));
+        assert!(state.contains("`cargo test` (ok)"));
+        assert!(state.contains("- [ ] Parser handles errors"));
+
+        // Compacting again replaces the state block instead of stacking another
+        let again = compactor.compact(&compacted, 7, None).await.unwrap();
+        assert_eq!(again[0].content.len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_summarize_replaces_history() {
+        let messages = conversation(6);
+        let summarizer = Arc::new(MockBackend::new(vec![MockBackend::text("- Created the parser files")]));
+        let compactor =
+            Compactor::new(CompactionMethod::Summarize, 0.8, 2).with_summarizer(summarizer.clone());
+
+        let temp = TempDir::new().unwrap();
+        let costs = Costs::new(temp.path(), &BudgetConfig::default()).unwrap();
+
+        let compacted = compactor.compact(&messages, 6, Some(&costs.meter("bd-1"))).await.unwrap();
+        assert_eq!(compacted.len(), 5);
+        assert!(matches!(compacted[1].role, Role::Assistant));
+        assert!(first_text(&compacted).contains("- Created the parser files"));
+
+        // The summary call is charged to the task
+        let entries = Ledger::new(temp.path()).entries().unwrap();
+        assert_eq!(entries.len(), 1);
+        assert_eq!((entries[0].task_id.as_str(), entries[0].purpose), ("bd-1", Purpose::Compaction));
+        assert_eq!(entries[0].input_tokens, 100);
+
+        // Nothing to compact in a short conversation
+        assert!(compactor.compact(&messages[..5], 2, None).await.is_none());
+        assert!(compactor.should_compact(120_000, 150_000));
+        assert!(!compactor.should_compact(100_000, 150_000));
+    }
+
+    #[test]
+    fn test_outline_compaction() {
+        let messages = conversation(6);
+        let compacted = compact(&messages, 2);
+        assert_eq!(compacted.len(), 5);
+
+        let note = first_text(&compacted);
+        assert!(note.contains("edit_file src/file0.rs"));
+        assert!(note.contains("bash cargo test"));
+        assert!(!note.contains("src/file4.rs"));
+
+        // Short conversations are left alone
+        assert_eq!(compact(&messages[..3], 2).len(), 3);
+    }
+}
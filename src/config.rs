@@ -0,0 +1,391 @@
+//! Project Configuration - Reads `.tachikoma/config.yaml`
+//!
+//! Only the sections Ralph understands are parsed; everything else in the
+//! file is ignored so the same config can be shared with the rest of Tachikoma.
+
+use anyhow::{Context, Result};
+use serde::Deserialize;
+use std::collections::{BTreeMap, HashMap};
+use std::path::Path;
+
+use crate::compaction::{CompactionMethod, RedlineStrategy, DEFAULT_KEEP_RECENT};
+use crate::sandbox::SandboxBackend;
+use crate::triage::Action;
+
+const CONFIG_FILE: &str = ".tachikoma/config.yaml";
+
+/// The subset of the Tachikoma project config used by Ralph
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct ProjectConfig {
+    /// Backend model configuration
+    pub backend: BackendConfig,
+    /// Loop behaviour
+    pub loop_config: LoopConfig,
+    /// Guardrail policies
+    pub policies: PolicyConfig,
+    /// Verification run before a task may be closed
+    pub verify: VerifyConfig,
+    /// Isolation for the `bash` primitive
+    pub sandbox: SandboxConfig,
+    /// Spend limits
+    pub budget: BudgetConfig,
+    /// Retries and what to do with a task that keeps failing
+    pub triage: TriageConfig,
+    /// External tool servers
+    pub mcp: McpConfig,
+}
+
+/// Backend model configuration (mirrors `tachikoma-common-config::BackendConfig`)
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct BackendConfig {
+    /// Fast agentic model used for the loop, e.g. "claude", "gpt-4o", "ollama:qwen2.5-coder"
+    pub brain: String,
+    /// API keys by backend name (falls back to environment variables)
+    pub api_keys: HashMap<String, String>,
+    /// Custom endpoints by backend name (e.g. an OpenAI-compatible local server)
+    pub endpoints: HashMap<String, String>,
+}
+
+impl Default for BackendConfig {
+    fn default() -> Self {
+        Self {
+            brain: "claude".to_string(),
+            api_keys: HashMap::new(),
+            endpoints: HashMap::new(),
+        }
+    }
+}
+
+/// Loop settings Ralph reads from `loop_config`
+///
+/// These sit alongside the Tachikoma loop runner's own `loop_config` fields.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct LoopConfig {
+    /// What to do when the context reaches the redline: "reset" or "compact"
+    pub on_redline: RedlineStrategy,
+    /// How to compact: "truncate" older tool results or "summarize" them with a model call
+    pub compaction: CompactionMethod,
+    /// Model used to summarize (e.g. "claude-3-5-haiku-latest"); defaults to the loop's model
+    pub compaction_model: Option<String>,
+    /// Fraction of the redline at which compaction kicks in
+    pub compact_at: f32,
+    /// Recent assistant/tool exchanges kept verbatim
+    pub keep_recent: usize,
+    /// Stop conditions (Ralph enforces `test_fail_streak`), written as
+    /// `- redline` / `- test_fail_streak: 3`
+    #[serde(with = "serde_yaml::with::singleton_map_recursive")]
+    pub stop_on: Vec<StopCondition>,
+}
+
+impl LoopConfig {
+    /// Consecutive verification failures that stop the loop, if configured
+    pub fn test_fail_streak(&self) -> Option<u32> {
+        self.stop_on.iter().find_map(|c| match c {
+            StopCondition::TestFailStreak(n) => Some(*n),
+            _ => None,
+        })
+    }
+}
+
+/// Stop conditions for the loop (mirrors `tachikoma-common-config::StopCondition`)
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum StopCondition {
+    /// Context window redlined.
+    Redline,
+    /// Consecutive test failures.
+    TestFailStreak(u32),
+    /// No progress on checkboxes.
+    NoProgress(u32),
+    /// Error rate exceeded.
+    ErrorRate(u32),
+    /// Manual stop requested.
+    ManualStop,
+    /// All tasks complete.
+    AllComplete,
+}
+
+/// Guardrail policies (the parts of `tachikoma-common-config::PolicyConfig` Ralph enforces)
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct PolicyConfig {
+    /// Tasks are only closed once verification passes; a test command is
+    /// detected if `verify.commands` is empty
+    pub deploy_requires_tests: bool,
+    /// Ask before running `bash` and `edit_file` calls, as if `--attended` was passed
+    pub attended_by_default: bool,
+    /// Extra bash commands that run without asking in attended mode, e.g. "make lint"
+    pub auto_approve: Vec<String>,
+}
+
+/// Verification gate settings
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct VerifyConfig {
+    /// Commands that must all succeed, e.g. "cargo test", "cargo clippy -- -D warnings"
+    pub commands: Vec<String>,
+    /// Times failing output is fed back to the model before giving up
+    pub repair_rounds: usize,
+    /// Timeout per command
+    pub timeout_secs: u64,
+    /// Also run `Verify: <command>` lines from task descriptions. Off by
+    /// default: task text can come from anyone who can file an issue.
+    pub task_commands: bool,
+}
+
+impl Default for VerifyConfig {
+    fn default() -> Self {
+        Self {
+            commands: Vec::new(),
+            repair_rounds: 2,
+            timeout_secs: 600,
+            task_commands: false,
+        }
+    }
+}
+
+/// Spend limits in US dollars (see `costs`); unset means unlimited
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct BudgetConfig {
+    /// Total spend on one task, across runs
+    pub per_task_usd: Option<f64>,
+    /// Spend in one `ralph run` / `loop` / `tui` invocation
+    pub per_run_usd: Option<f64>,
+    /// Spend per calendar day, across runs
+    pub per_day_usd: Option<f64>,
+}
+
+/// Retry policies (see `triage`)
+///
+/// Each list is what to do the first, second, ... time a task stops that
+/// way; its last action repeats. Whatever the lists say, a task is blocked
+/// with a triage note after `max_attempts`.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct TriageConfig {
+    /// Attempts at a task before it is blocked
+    pub max_attempts: usize,
+    /// Backoff for a single model call that is rate limited or overloaded
+    pub api_retry: ApiRetryConfig,
+    #[serde(with = "serde_yaml::with::singleton_map_recursive")]
+    pub max_iterations: Vec<Action>,
+    #[serde(with = "serde_yaml::with::singleton_map_recursive")]
+    pub redline: Vec<Action>,
+    #[serde(with = "serde_yaml::with::singleton_map_recursive")]
+    pub verification_failed: Vec<Action>,
+    /// API errors that outlasted `api_retry`: 429, 529/503 and other failures
+    #[serde(with = "serde_yaml::with::singleton_map_recursive")]
+    pub rate_limited: Vec<Action>,
+    #[serde(with = "serde_yaml::with::singleton_map_recursive")]
+    pub overloaded: Vec<Action>,
+    #[serde(with = "serde_yaml::with::singleton_map_recursive")]
+    pub api_error: Vec<Action>,
+    /// Any other error from a run
+    #[serde(with = "serde_yaml::with::singleton_map_recursive")]
+    pub error: Vec<Action>,
+}
+
+impl Default for TriageConfig {
+    fn default() -> Self {
+        Self {
+            max_attempts: 3,
+            api_retry: ApiRetryConfig::default(),
+            max_iterations: vec![Action::Retry],
+            redline: vec![Action::Retry],
+            verification_failed: vec![Action::Retry],
+            rate_limited: vec![Action::Retry],
+            overloaded: vec![Action::Retry],
+            api_error: vec![Action::Retry],
+            error: vec![Action::Retry],
+        }
+    }
+}
+
+/// Model-call backoff (mirrors `tachikoma-common-http::retry::RetryPolicy`)
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct ApiRetryConfig {
+    /// Attempts per call, the first included
+    pub max_attempts: u32,
+    pub initial_delay_ms: u64,
+    pub max_delay_ms: u64,
+    pub multiplier: f64,
+}
+
+impl Default for ApiRetryConfig {
+    fn default() -> Self {
+        Self {
+            max_attempts: 5,
+            initial_delay_ms: 2_000,
+            max_delay_ms: 60_000,
+            multiplier: 2.0,
+        }
+    }
+}
+
+/// Sandbox settings for the `bash` primitive
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct SandboxConfig {
+    /// "none", "auto", "bwrap" or "unshare"
+    pub backend: SandboxBackend,
+    /// Allow network access from commands
+    pub network: bool,
+    /// Extra writable paths besides the project and /tmp (relative to the project, or `~/...`)
+    pub writable: Vec<String>,
+    /// Environment variables passed through besides PATH, HOME, the locale
+    /// and the like; the rest of Ralph's environment, API keys included,
+    /// is dropped
+    pub env: Vec<String>,
+    /// CPU seconds per process
+    pub cpu_secs: u64,
+    /// Resident memory for the whole command, enforced by a cgroup (needs
+    /// `systemd-run --user`); unlimited when unset
+    pub memory_mb: Option<u64>,
+    /// Processes and threads for the whole command, enforced by the same
+    /// cgroup; unlimited when unset
+    pub max_procs: Option<u64>,
+}
+
+impl Default for SandboxConfig {
+    fn default() -> Self {
+        Self {
+            backend: SandboxBackend::None,
+            network: false,
+            writable: Vec::new(),
+            env: Vec::new(),
+            cpu_secs: 600,
+            memory_mb: None,
+            max_procs: None,
+        }
+    }
+}
+
+/// MCP servers whose tools the loop may call (see `mcp`)
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct McpConfig {
+    /// Servers by name; the name prefixes their tools, e.g. `schema__list_tables`
+    pub servers: BTreeMap<String, McpServerConfig>,
+}
+
+/// A stdio MCP server
+#[derive(Debug, Clone, Deserialize)]
+pub struct McpServerConfig {
+    /// Program to run (relative to the project if it contains a `/`, else from `PATH`)
+    pub command: String,
+    #[serde(default)]
+    pub args: Vec<String>,
+    #[serde(default)]
+    pub env: HashMap<String, String>,
+    /// Only expose these of the server's tools (default: all)
+    #[serde(default)]
+    pub allow: Vec<String>,
+    /// Never expose these
+    #[serde(default)]
+    pub deny: Vec<String>,
+    /// Seconds to wait for startup or for one call
+    #[serde(default = "default_mcp_timeout")]
+    pub timeout_secs: u64,
+    /// Run this server's tools without asking in attended mode
+    #[serde(default)]
+    pub auto_approve: bool,
+}
+
+fn default_mcp_timeout() -> u64 {
+    60
+}
+
+impl Default for LoopConfig {
+    fn default() -> Self {
+        Self {
+            on_redline: RedlineStrategy::Reset,
+            compaction: CompactionMethod::Truncate,
+            compaction_model: None,
+            compact_at: 0.8,
+            keep_recent: DEFAULT_KEEP_RECENT,
+            stop_on: Vec::new(),
+        }
+    }
+}
+
+/// Load the project config, returning defaults if no config file exists
+pub fn load(project_root: &Path) -> Result<ProjectConfig> {
+    let path = project_root.join(CONFIG_FILE);
+
+    if !path.exists() {
+        return Ok(ProjectConfig::default());
+    }
+
+    let content = std::fs::read_to_string(&path)
+        .with_context(|| format!("Failed to read {}", path.display()))?;
+
+    // An empty file is valid YAML but deserializes to null
+    if content.trim().is_empty() {
+        return Ok(ProjectConfig::default());
+    }
+
+    serde_yaml::from_str(&content).with_context(|| format!("Failed to parse {}", path.display()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_load_missing_config() {
+        let temp = TempDir::new().unwrap();
+        let config = load(temp.path()).unwrap();
+        assert_eq!(config.backend.brain, "claude");
+        assert_eq!(config.loop_config.on_redline, RedlineStrategy::Reset);
+    }
+
+    #[test]
+    fn test_load_partial_config() {
+        let temp = TempDir::new().unwrap();
+        std::fs::create_dir_all(temp.path().join(".tachikoma")).unwrap();
+        std::fs::write(
+            temp.path().join(CONFIG_FILE),
+            "backend:\n  brain: ollama:qwen2.5-coder\n  endpoints:\n    ollama: http://gpu-box:11434\nloop_config:\n  max_iterations: 100\n  on_redline: compact\n  compaction: summarize\n  stop_on:\n    - redline\n    - test_fail_streak: 3\npolicies:\n  deploy_requires_tests: true\n  auto_push: false\n  auto_approve: [make lint]\nverify:\n  commands: [cargo test]\nsandbox:\n  backend: unshare\n  writable: [~/.cargo]\nbudget:\n  per_day_usd: 25\ntriage:\n  max_attempts: 4\n  max_iterations:\n    - retry\n    - escalate: claude-opus-4-1\n  redline: [retry, retry, decompose]\n  api_retry:\n    max_attempts: 8\nmcp:\n  servers:\n    schema:\n      command: ./tools/schema-mcp\n      deny: [drop_table]\nforge:\n  max_rounds: 3\n",
+        )
+        .unwrap();
+
+        let config = load(temp.path()).unwrap();
+        assert_eq!(config.backend.brain, "ollama:qwen2.5-coder");
+        assert_eq!(
+            config.backend.endpoints.get("ollama").map(String::as_str),
+            Some("http://gpu-box:11434")
+        );
+        assert_eq!(config.loop_config.on_redline, RedlineStrategy::Compact);
+        assert_eq!(config.loop_config.compaction, CompactionMethod::Summarize);
+        assert_eq!(config.loop_config.keep_recent, DEFAULT_KEEP_RECENT);
+        assert_eq!(config.loop_config.test_fail_streak(), Some(3));
+        assert!(config.policies.deploy_requires_tests);
+        assert!(!config.policies.attended_by_default);
+        assert_eq!(config.policies.auto_approve, ["make lint"]);
+        assert_eq!(config.verify.commands, ["cargo test"]);
+        assert_eq!(config.verify.repair_rounds, 2);
+        assert_eq!(config.sandbox.backend, SandboxBackend::Unshare);
+        assert!(!config.sandbox.network);
+        assert_eq!(config.sandbox.writable, ["~/.cargo"]);
+        assert_eq!(config.budget.per_day_usd, Some(25.0));
+        assert_eq!(config.budget.per_task_usd, None);
+        assert_eq!(config.triage.max_attempts, 4);
+        assert_eq!(
+            config.triage.max_iterations,
+            [Action::Retry, Action::Escalate("claude-opus-4-1".to_string())]
+        );
+        assert_eq!(config.triage.redline, [Action::Retry, Action::Retry, Action::Decompose]);
+        assert_eq!(config.triage.error, [Action::Retry]);
+        assert_eq!((config.triage.api_retry.max_attempts, config.triage.api_retry.max_delay_ms), (8, 60_000));
+        let schema = &config.mcp.servers["schema"];
+        assert_eq!(schema.command, "./tools/schema-mcp");
+        assert_eq!(schema.deny, ["drop_table"]);
+        assert_eq!(schema.timeout_secs, 60);
+    }
+}
@@ -0,0 +1,130 @@
+//! Loop control - Pause, steer, skip or abort a running loop
+//!
+//! The TUI holds one handle and `ClaudeClient` a clone. Before each model
+//! call the loop stops at a checkpoint:
+//!
+//! - while paused it waits there, between iterations
+//! - messages typed by a human (steering) join the next user turn, next to
+//!   the tool results, when the loop carries on
+//! - a skip or abort ends the session (`StopReason::Skipped` / `Aborted`)
+//!   and the harness decides what happens to the task
+
+use serde::{Deserialize, Serialize};
+use std::sync::{Arc, Mutex};
+use tokio::sync::Notify;
+
+/// Stop the current task early
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Interrupt {
+    /// Park the task and move on to the next one
+    Skip,
+    /// Stop the loop altogether
+    Abort,
+}
+
+/// What the loop should do at a checkpoint
+#[derive(Debug, PartialEq)]
+pub enum Checkpoint {
+    /// Carry on, adding these messages first
+    Continue { steering: Vec<String> },
+    Stop(Interrupt),
+}
+
+#[derive(Default)]
+struct State {
+    paused: bool,
+    steering: Vec<String>,
+    interrupt: Option<Interrupt>,
+}
+
+/// Shared handle between a front end and the loop
+#[derive(Clone, Default)]
+pub struct LoopControl {
+    state: Arc<Mutex<State>>,
+    changed: Arc<Notify>,
+}
+
+impl LoopControl {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn set_paused(&self, paused: bool) {
+        self.update(|state| state.paused = paused);
+    }
+
+    /// Queue a message for the model's next turn
+    pub fn steer(&self, message: impl Into<String>) {
+        let message = message.into();
+        self.update(|state| state.steering.push(message));
+    }
+
+    /// Skip or abort the current task; a paused loop resumes to act on it
+    pub fn interrupt(&self, interrupt: Interrupt) {
+        self.update(|state| {
+            state.interrupt = Some(interrupt);
+            state.paused = false;
+        });
+    }
+
+    /// Wait while paused, then hand over whatever the human asked for
+    pub async fn checkpoint(&self) -> Checkpoint {
+        loop {
+            // Registered before the check, so a change in between still wakes us
+            let changed = self.changed.notified();
+            {
+                let mut state = self.state.lock().unwrap();
+                if let Some(interrupt) = state.interrupt.take() {
+                    return Checkpoint::Stop(interrupt);
+                }
+                if !state.paused {
+                    return Checkpoint::Continue {
+                        steering: std::mem::take(&mut state.steering),
+                    };
+                }
+            }
+            changed.await;
+        }
+    }
+
+    fn update(&self, change: impl FnOnce(&mut State)) {
+        change(&mut self.state.lock().unwrap());
+        self.changed.notify_waiters();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[tokio::test]
+    async fn test_pause_steer_and_skip() {
+        let control = LoopControl::new();
+        assert_eq!(control.checkpoint().await, Checkpoint::Continue { steering: vec![] });
+
+        // A paused loop waits at the checkpoint until resumed
+        control.set_paused(true);
+        let waiting = tokio::spawn({
+            let control = control.clone();
+            async move { control.checkpoint().await }
+        });
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        assert!(!waiting.is_finished());
+
+        control.steer("Use the existing parser");
+        control.set_paused(false);
+        assert_eq!(
+            waiting.await.unwrap(),
+            Checkpoint::Continue {
+                steering: vec!["Use the existing parser".to_string()]
+            }
+        );
+
+        control.set_paused(true);
+        control.interrupt(Interrupt::Skip);
+        assert_eq!(control.checkpoint().await, Checkpoint::Stop(Interrupt::Skip));
+        assert_eq!(control.checkpoint().await, Checkpoint::Continue { steering: vec![] });
+    }
+}
@@ -0,0 +1,515 @@
+//! Costs - Spend ledger and budgets
+//!
+//! Every loop session appends a line to `.ralph/costs.jsonl` with its
+//! tokens and estimated dollars, tagged with the task, the run (one `ralph`
+//! invocation) and the model. A session that fails with an error is recorded
+//! with what it spent before the error, and the calls made outside the loop
+//! (compaction summaries, task decomposition) get lines of their own.
+//! `ralph costs` reports from it.
+//!
+//! Budgets come from the `budget:` config section:
+//!
+//! - `per_task_usd`: everything ever spent on one task
+//! - `per_run_usd`: this invocation of `ralph run` / `loop` / `tui`
+//! - `per_day_usd`: everything spent today (local time), across runs
+//!
+//! Spend is counted live after each model call, so parallel workers share
+//! the run and day totals. Once a budget is spent the loop stops with
+//! `StopReason::BudgetExceeded` before its next call.
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, Local, NaiveDate, Utc};
+use clap::ValueEnum;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fmt;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+use crate::claude_client::{LoopResult, StopReason, Usage};
+use crate::config::BudgetConfig;
+
+const LEDGER_FILE: &str = ".ralph/costs.jsonl";
+
+/// Dollars per million tokens
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Pricing {
+    pub input: f64,
+    pub output: f64,
+}
+
+impl Pricing {
+    /// List prices for a backend's model
+    ///
+    /// Local backends are free. Unknown hosted models are charged at Sonnet
+    /// rates so a budget errs on the side of stopping early.
+    pub fn for_model(backend: &str, model: &str) -> Self {
+        let model = model.to_lowercase();
+        let (input, output) = match backend {
+            "ollama" | "mock" | "replay" => (0.0, 0.0),
+            _ if model.contains("opus") => (15.0, 75.0),
+            _ if model.contains("haiku") => (0.8, 4.0),
+            _ if model.contains("sonnet") => (3.0, 15.0),
+            _ if model.starts_with("gpt-4o-mini") => (0.15, 0.6),
+            _ if model.starts_with("gpt-4o") => (2.5, 10.0),
+            _ if model.starts_with("gpt-4.1-mini") => (0.4, 1.6),
+            _ if model.starts_with("gpt-4.1") => (2.0, 8.0),
+            _ if model.starts_with("o3-mini") || model.starts_with("o4-mini") => (1.1, 4.4),
+            _ => (3.0, 15.0),
+        };
+        Self { input, output }
+    }
+
+    pub fn cost(&self, input_tokens: u32, output_tokens: u32) -> f64 {
+        (input_tokens as f64 * self.input + output_tokens as f64 * self.output) / 1_000_000.0
+    }
+}
+
+/// What a ledger entry paid for
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Purpose {
+    /// A loop session
+    #[default]
+    Session,
+    /// Summarizing history to compact a session's context
+    Compaction,
+    /// Splitting a task into subtasks
+    Decompose,
+}
+
+/// One loop session, or one call made for a task outside the loop
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LedgerEntry {
+    pub timestamp: DateTime<Utc>,
+    pub run_id: String,
+    pub task_id: String,
+    pub backend: String,
+    pub model: String,
+    pub input_tokens: u64,
+    pub output_tokens: u64,
+    pub cost_usd: f64,
+    /// How the session ended; `None` if it failed with an error or isn't a session
+    #[serde(default)]
+    pub stop_reason: Option<StopReason>,
+    /// Model calls in the session (0 in ledgers written before it was recorded)
+    #[serde(default)]
+    pub iterations: usize,
+    #[serde(default)]
+    pub purpose: Purpose,
+    /// The error a failed session stopped with
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+impl LedgerEntry {
+    /// The local date the session ended
+    pub fn day(&self) -> NaiveDate {
+        self.timestamp.with_timezone(&Local).date_naive()
+    }
+}
+
+/// The project's append-only spend ledger
+#[derive(Debug, Clone)]
+pub struct Ledger {
+    path: PathBuf,
+}
+
+impl Ledger {
+    pub fn new(project_root: &Path) -> Self {
+        Self {
+            path: project_root.join(LEDGER_FILE),
+        }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn append(&self, entry: &LedgerEntry) -> Result<()> {
+        if let Some(parent) = self.path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let mut file = std::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)
+            .with_context(|| format!("Failed to open {}", self.path.display()))?;
+        writeln!(file, "{}", serde_json::to_string(entry)?)?;
+        Ok(())
+    }
+
+    /// Every entry, skipping lines that don't parse; empty if there is no ledger yet
+    pub fn entries(&self) -> Result<Vec<LedgerEntry>> {
+        if !self.path.exists() {
+            return Ok(Vec::new());
+        }
+        let content = std::fs::read_to_string(&self.path)
+            .with_context(|| format!("Failed to read {}", self.path.display()))?;
+        Ok(content
+            .lines()
+            .filter_map(|line| serde_json::from_str(line).ok())
+            .collect())
+    }
+}
+
+/// A budget that has been spent
+#[derive(Debug, Clone, PartialEq)]
+pub enum Exceeded {
+    Task { spent: f64, limit: f64 },
+    Run { spent: f64, limit: f64 },
+    Day { spent: f64, limit: f64 },
+}
+
+impl Exceeded {
+    /// Run and day budgets stop the whole loop; a task budget only its task
+    pub fn stops_loop(&self) -> bool {
+        !matches!(self, Exceeded::Task { .. })
+    }
+}
+
+impl fmt::Display for Exceeded {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let (name, spent, limit) = match self {
+            Exceeded::Task { spent, limit } => ("task", spent, limit),
+            Exceeded::Run { spent, limit } => ("run", spent, limit),
+            Exceeded::Day { spent, limit } => ("daily", spent, limit),
+        };
+        write!(f, "{} budget spent (${:.2} of ${:.2})", name, spent, limit)
+    }
+}
+
+/// Spend so far, shared by every task in a run
+#[derive(Debug, Default)]
+struct Spend {
+    run: f64,
+    day: f64,
+    date: Option<NaiveDate>,
+    tasks: HashMap<String, f64>,
+}
+
+/// The ledger and budgets for one run
+#[derive(Debug, Clone)]
+pub struct Costs {
+    ledger: Ledger,
+    budget: BudgetConfig,
+    run_id: String,
+    spend: Arc<Mutex<Spend>>,
+}
+
+impl Costs {
+    /// Start a run, loading today's and each task's spend from the ledger
+    pub fn new(project_root: &Path, budget: &BudgetConfig) -> Result<Self> {
+        let ledger = Ledger::new(project_root);
+        let today = Local::now().date_naive();
+        let mut spend = Spend {
+            date: Some(today),
+            ..Default::default()
+        };
+        for entry in ledger.entries()? {
+            if entry.day() == today {
+                spend.day += entry.cost_usd;
+            }
+            *spend.tasks.entry(entry.task_id).or_default() += entry.cost_usd;
+        }
+
+        Ok(Self {
+            ledger,
+            budget: budget.clone(),
+            run_id: uuid::Uuid::new_v4().to_string(),
+            spend: Arc::new(Mutex::new(spend)),
+        })
+    }
+
+    /// The meter handed to a task's agentic loop
+    pub fn meter(&self, task_id: &str) -> CostMeter {
+        CostMeter {
+            costs: self.clone(),
+            task_id: task_id.to_string(),
+        }
+    }
+
+    /// Spent this run
+    pub fn run_total(&self) -> f64 {
+        self.spend.lock().unwrap().run
+    }
+
+    /// The first budget already spent: the run's, today's, then the task's
+    pub fn exceeded(&self, task_id: Option<&str>) -> Option<Exceeded> {
+        let mut spend = self.spend.lock().unwrap();
+        roll_over(&mut spend);
+
+        let over = |spent: f64, limit: Option<f64>| limit.filter(|limit| spent >= *limit);
+        if let Some(limit) = over(spend.run, self.budget.per_run_usd) {
+            return Some(Exceeded::Run { spent: spend.run, limit });
+        }
+        if let Some(limit) = over(spend.day, self.budget.per_day_usd) {
+            return Some(Exceeded::Day { spent: spend.day, limit });
+        }
+        let task = task_id.map(|id| spend.tasks.get(id).copied().unwrap_or_default())?;
+        over(task, self.budget.per_task_usd).map(|limit| Exceeded::Task { spent: task, limit })
+    }
+
+    fn add(&self, task_id: &str, cost: f64) {
+        let mut spend = self.spend.lock().unwrap();
+        roll_over(&mut spend);
+        spend.run += cost;
+        spend.day += cost;
+        *spend.tasks.entry(task_id.to_string()).or_default() += cost;
+    }
+}
+
+/// Start counting a new day at midnight
+fn roll_over(spend: &mut Spend) {
+    let today = Local::now().date_naive();
+    if spend.date != Some(today) {
+        spend.date = Some(today);
+        spend.day = 0.0;
+    }
+}
+
+/// Counts one task's spend against the budgets
+#[derive(Debug, Clone)]
+pub struct CostMeter {
+    costs: Costs,
+    task_id: String,
+}
+
+impl CostMeter {
+    /// Count a model call
+    pub fn add(&self, cost: f64) {
+        self.costs.add(&self.task_id, cost);
+    }
+
+    pub fn exceeded(&self) -> Option<Exceeded> {
+        self.costs.exceeded(Some(&self.task_id))
+    }
+
+    /// Append a finished session to the ledger
+    pub fn record(&self, backend: &str, model: &str, result: &LoopResult) -> Result<()> {
+        self.costs.ledger.append(&LedgerEntry {
+            stop_reason: Some(result.stop_reason.clone()),
+            ..self.entry(backend, model, &Spent::of(result), Purpose::Session)
+        })
+    }
+
+    /// Append a session that failed with an error, with what it spent until then
+    pub fn record_failed(&self, backend: &str, model: &str, spent: &Spent, error: &anyhow::Error) -> Result<()> {
+        self.costs.ledger.append(&LedgerEntry {
+            error: Some(format!("{:#}", error)),
+            ..self.entry(backend, model, spent, Purpose::Session)
+        })
+    }
+
+    /// Count and record a single call made for the task outside the loop
+    pub fn record_call(&self, backend: &str, model: &str, usage: &Usage, purpose: Purpose) -> Result<()> {
+        let spent = Spent {
+            iterations: 1,
+            input_tokens: usage.input_tokens,
+            output_tokens: usage.output_tokens,
+            cost_usd: Pricing::for_model(backend, model).cost(usage.input_tokens, usage.output_tokens),
+        };
+        self.add(spent.cost_usd);
+        self.costs.ledger.append(&self.entry(backend, model, &spent, purpose))
+    }
+
+    fn entry(&self, backend: &str, model: &str, spent: &Spent, purpose: Purpose) -> LedgerEntry {
+        LedgerEntry {
+            timestamp: Utc::now(),
+            run_id: self.costs.run_id.clone(),
+            task_id: self.task_id.clone(),
+            backend: backend.to_string(),
+            model: model.to_string(),
+            input_tokens: spent.input_tokens as u64,
+            output_tokens: spent.output_tokens as u64,
+            cost_usd: spent.cost_usd,
+            stop_reason: None,
+            iterations: spent.iterations,
+            purpose,
+            error: None,
+        }
+    }
+}
+
+/// What a session has used so far
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Spent {
+    pub iterations: usize,
+    pub input_tokens: u32,
+    pub output_tokens: u32,
+    pub cost_usd: f64,
+}
+
+impl Spent {
+    pub fn of(result: &LoopResult) -> Self {
+        Self {
+            iterations: result.iterations,
+            input_tokens: result.total_input_tokens,
+            output_tokens: result.total_output_tokens,
+            cost_usd: result.cost_usd,
+        }
+    }
+}
+
+/// How `ralph costs` groups the ledger
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum GroupBy {
+    Task,
+    Run,
+    Model,
+    Day,
+}
+
+/// One line of a cost report
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Row {
+    pub key: String,
+    pub sessions: usize,
+    pub input_tokens: u64,
+    pub output_tokens: u64,
+    pub cost_usd: f64,
+}
+
+/// Totals per group: days newest first, everything else most expensive first
+pub fn breakdown(entries: &[LedgerEntry], by: GroupBy) -> Vec<Row> {
+    let mut rows: HashMap<String, Row> = HashMap::new();
+    for entry in entries {
+        let key = match by {
+            GroupBy::Task => entry.task_id.clone(),
+            GroupBy::Run => entry.run_id.clone(),
+            GroupBy::Model => format!("{}:{}", entry.backend, entry.model),
+            GroupBy::Day => entry.day().to_string(),
+        };
+        let row = rows.entry(key.clone()).or_insert_with(|| Row {
+            key,
+            ..Default::default()
+        });
+        if entry.purpose == Purpose::Session {
+            row.sessions += 1;
+        }
+        row.input_tokens += entry.input_tokens;
+        row.output_tokens += entry.output_tokens;
+        row.cost_usd += entry.cost_usd;
+    }
+
+    let mut rows: Vec<Row> = rows.into_values().collect();
+    match by {
+        GroupBy::Day => rows.sort_by(|a, b| b.key.cmp(&a.key)),
+        _ => rows.sort_by(|a, b| b.cost_usd.total_cmp(&a.cost_usd).then_with(|| a.key.cmp(&b.key))),
+    }
+    rows
+}
+
+/// Entries from `since` (local date) onwards
+pub fn since(entries: Vec<LedgerEntry>, since: Option<NaiveDate>) -> Vec<LedgerEntry> {
+    match since {
+        Some(date) => entries.into_iter().filter(|e| e.day() >= date).collect(),
+        None => entries,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn entry(task_id: &str, model: &str, cost_usd: f64) -> LedgerEntry {
+        LedgerEntry {
+            timestamp: Utc::now(),
+            run_id: "earlier".to_string(),
+            task_id: task_id.to_string(),
+            backend: "anthropic".to_string(),
+            model: model.to_string(),
+            input_tokens: 1000,
+            output_tokens: 100,
+            cost_usd,
+            stop_reason: Some(StopReason::Completed),
+            iterations: 5,
+            purpose: Purpose::Session,
+            error: None,
+        }
+    }
+
+    #[test]
+    fn test_budgets_count_ledger_and_live_spend() {
+        let temp = TempDir::new().unwrap();
+        let ledger = Ledger::new(temp.path());
+        ledger.append(&entry("bd-1", "claude-sonnet-4", 4.0)).unwrap();
+
+        let budget = BudgetConfig {
+            per_task_usd: Some(5.0),
+            per_run_usd: Some(3.0),
+            per_day_usd: Some(10.0),
+        };
+        let costs = Costs::new(temp.path(), &budget).unwrap();
+        let meter = costs.meter("bd-1");
+        assert_eq!(meter.exceeded(), None);
+
+        // The ledger's $4 plus $1.50 now goes over the task budget, not the run's
+        meter.add(1.5);
+        assert_eq!(meter.exceeded(), Some(Exceeded::Task { spent: 5.5, limit: 5.0 }));
+        assert!(!meter.exceeded().unwrap().stops_loop());
+        assert_eq!(costs.exceeded(Some("bd-2")), None);
+
+        // Other tasks in the run share the run budget
+        costs.meter("bd-2").add(2.0);
+        let exceeded = costs.exceeded(Some("bd-2")).unwrap();
+        assert_eq!(exceeded, Exceeded::Run { spent: 3.5, limit: 3.0 });
+        assert!(exceeded.stops_loop());
+        assert_eq!(exceeded.to_string(), "run budget spent ($3.50 of $3.00)");
+    }
+
+    #[test]
+    fn test_failed_sessions_and_single_calls_are_recorded() {
+        let temp = TempDir::new().unwrap();
+        let costs = Costs::new(temp.path(), &BudgetConfig::default()).unwrap();
+        let meter = costs.meter("bd-1");
+
+        let spent = Spent {
+            iterations: 3,
+            input_tokens: 2000,
+            output_tokens: 200,
+            cost_usd: 0.25,
+        };
+        meter
+            .record_failed("anthropic", "claude-sonnet-4", &spent, &anyhow::anyhow!("connection reset"))
+            .unwrap();
+        let usage = Usage {
+            input_tokens: 1_000_000,
+            output_tokens: 0,
+        };
+        meter.record_call("anthropic", "claude-haiku-3-5", &usage, Purpose::Compaction).unwrap();
+        assert_eq!(costs.run_total(), 0.8);
+
+        let entries = Ledger::new(temp.path()).entries().unwrap();
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].stop_reason, None);
+        assert_eq!(entries[0].error.as_deref(), Some("connection reset"));
+        assert_eq!((entries[0].input_tokens, entries[0].cost_usd), (2000, 0.25));
+        assert_eq!((entries[1].purpose, entries[1].cost_usd), (Purpose::Compaction, 0.8));
+
+        // Only the session counts as one in reports
+        let rows = breakdown(&entries, GroupBy::Task);
+        assert_eq!((rows[0].sessions, rows[0].cost_usd), (1, 1.05));
+    }
+
+    #[test]
+    fn test_pricing_and_breakdown() {
+        assert_eq!(Pricing::for_model("anthropic", "claude-sonnet-4-20250514").cost(1_000_000, 100_000), 4.5);
+        assert_eq!(Pricing::for_model("ollama", "qwen2.5-coder").cost(1_000_000, 1_000_000), 0.0);
+        assert_eq!(Pricing::for_model("openai", "gpt-4o-mini").input, 0.15);
+
+        let entries = vec![
+            entry("bd-1", "claude-sonnet-4", 1.0),
+            entry("bd-2", "claude-opus-4", 3.0),
+            entry("bd-1", "claude-sonnet-4", 0.5),
+        ];
+        let by_task = breakdown(&entries, GroupBy::Task);
+        assert_eq!(by_task.len(), 2);
+        assert_eq!((by_task[0].key.as_str(), by_task[0].cost_usd), ("bd-2", 3.0));
+        assert_eq!((by_task[1].key.as_str(), by_task[1].sessions), ("bd-1", 2));
+        assert_eq!(breakdown(&entries, GroupBy::Model)[1].key, "anthropic:claude-sonnet-4");
+        assert_eq!(breakdown(&entries, GroupBy::Day)[0].sessions, 3);
+    }
+}
@@ -1,24 +1,48 @@
 //! Task Decomposition - Pre-analyzes large tasks and breaks them into subtasks
 //!
-//! Uses Claude to analyze epic/large tasks and decompose them into smaller,
-//! more focused subtasks that fit within context limits.
+//! Sizing is LLM-free: besides the task text, it estimates which files the
+//! task touches (paths it names, plus files that mention the identifiers it
+//! names) and compares that with what past tasks cost according to the spend
+//! ledger and the knowledge store.
+//!
+//! Tasks that come out too large are handed to the configured model backend,
+//! which proposes subtasks with acceptance criteria and dependencies between
+//! them. The plan is checked for cycles and can be reviewed with
+//! `--plan-only` before the subtasks are created.
 
 use anyhow::{Context, Result};
-use reqwest::Client;
 use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
 use std::path::Path;
 use std::process::Command;
 
+use crate::backends::{ModelBackend, ModelRequest};
+use crate::beads::{self, BeadsStore, NewIssue};
+use crate::costs::{CostMeter, Costs, Ledger, LedgerEntry, Purpose};
+use crate::claude_client::{ContentBlock, Message, Role, StopReason};
+use crate::knowledge::{self, KnowledgeStore};
 use crate::task_parser::{BeadTask, ParsedTask, parse_task, get_ready_tasks};
 
-const CLAUDE_API_URL: &str = "https://api.anthropic.com/v1/messages";
-const CLAUDE_MODEL: &str = "claude-sonnet-4-20250514";
+/// Output token limit for the decomposition call
+const DECOMPOSE_MAX_TOKENS: u32 = 4096;
 
 /// Threshold for considering a task "too large" (in characters)
 const LARGE_TASK_DESCRIPTION_THRESHOLD: usize = 2000;
 
-/// Maximum acceptable number of items in a single task
-const MAX_SCOPE_ITEMS: usize = 5;
+/// A task likely to touch more files than this gets split
+const MAX_ESTIMATED_FILES: usize = 6;
+
+/// Identifiers searched for per task
+const MAX_IDENTIFIERS: usize = 12;
+
+/// An identifier found in more files than this says little about the task
+const MAX_FILES_PER_IDENTIFIER: usize = 8;
+
+/// Past tasks needed before history is trusted
+const MIN_HISTORY_TASKS: usize = 3;
+
+/// Predicted cost, as a multiple of the median past task, that is too large
+const OVERSIZE_FACTOR: f64 = 2.0;
 
 /// Analysis result for a task
 #[derive(Debug, Clone)]
@@ -29,39 +53,240 @@ pub struct TaskAnalysis {
     pub description_chars: usize,
     pub criteria_count: usize,
     pub has_subtasks: bool,
-    pub suggested_subtasks: Vec<SubtaskSuggestion>,
+    /// Files the task is likely to touch
+    pub estimated_files: Vec<String>,
+    /// Tokens and iterations a run would take, going by history
+    pub predicted_tokens: Option<u64>,
+    pub predicted_iterations: Option<usize>,
+}
+
+/// What past tasks cost, from the spend ledger and the knowledge store
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct History {
+    /// Completed tasks with a cost on record
+    pub tasks: usize,
+    pub median_tokens: u64,
+    pub median_iterations: usize,
+    /// Median cost of each file a completed task touched
+    pub tokens_per_file: Option<u64>,
+    pub iterations_per_file: Option<f64>,
+}
+
+impl History {
+    pub fn load(project_root: &Path) -> Self {
+        let ledger = Ledger::new(project_root).entries().unwrap_or_default();
+        let outcomes = KnowledgeStore::new(project_root).entries().unwrap_or_default();
+        Self::from_records(&ledger, &outcomes)
+    }
+
+    /// Sum each completed task's sessions, then take medians across tasks
+    pub fn from_records(ledger: &[LedgerEntry], knowledge: &[knowledge::Entry]) -> Self {
+        let mut per_task: HashMap<&str, (u64, usize, bool)> = HashMap::new();
+        // Splitting a task is not part of doing it
+        for entry in ledger.iter().filter(|e| e.purpose != Purpose::Decompose) {
+            let task = per_task.entry(entry.task_id.as_str()).or_default();
+            task.0 += entry.input_tokens + entry.output_tokens;
+            task.1 += entry.iterations;
+            task.2 |= entry.stop_reason == Some(StopReason::Completed);
+        }
+        let mut files: HashMap<&str, HashSet<&str>> = HashMap::new();
+        for entry in knowledge.iter().filter(|e| e.kind == knowledge::Kind::Outcome) {
+            if let Some(task_id) = &entry.task_id {
+                files.entry(task_id).or_default().extend(entry.files.iter().map(String::as_str));
+            }
+        }
+
+        let completed: Vec<(&str, u64, usize)> = per_task
+            .into_iter()
+            .filter(|(_, (_, _, done))| *done)
+            .map(|(id, (tokens, iterations, _))| (id, tokens, iterations))
+            .collect();
+        let per_file: Vec<(u64, f64)> = completed
+            .iter()
+            .filter_map(|(id, tokens, iterations)| {
+                let count = files.get(id).map_or(0, HashSet::len);
+                (count > 0).then(|| (tokens / count as u64, *iterations as f64 / count as f64))
+            })
+            .collect();
+
+        Self {
+            tasks: completed.len(),
+            median_tokens: median(completed.iter().map(|t| t.1).collect()).unwrap_or_default(),
+            median_iterations: median(completed.iter().map(|t| t.2).collect()).unwrap_or_default(),
+            tokens_per_file: median(per_file.iter().map(|p| p.0).collect()),
+            iterations_per_file: median(per_file.iter().map(|p| (p.1 * 100.0) as u64).collect())
+                .map(|hundredths| hundredths as f64 / 100.0),
+        }
+    }
 }
 
 /// A suggested subtask from decomposition
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SubtaskSuggestion {
+    /// Short name other subtasks in the plan use to depend on this one
+    #[serde(default)]
+    pub key: String,
     pub title: String,
     pub description: String,
+    #[serde(default = "default_priority")]
     pub priority: u8,
+    #[serde(default = "default_issue_type")]
     pub issue_type: String,
+    #[serde(default)]
     pub labels: Vec<String>,
+    #[serde(default)]
+    pub acceptance_criteria: Vec<String>,
+    /// Keys of subtasks that must be done first
+    #[serde(default)]
+    pub depends_on: Vec<String>,
+    #[serde(default)]
+    pub files: Vec<String>,
+}
+
+fn default_priority() -> u8 {
+    2
+}
+
+fn default_issue_type() -> String {
+    "task".to_string()
+}
+
+impl SubtaskSuggestion {
+    /// Description for the issue: criteria as checkboxes, files as a hint
+    pub fn issue_description(&self) -> String {
+        let mut description = self.description.trim().to_string();
+        if !self.acceptance_criteria.is_empty() && !description.contains("- [ ]") {
+            description.push_str("\n\n## Acceptance Criteria\n");
+            for criterion in &self.acceptance_criteria {
+                description.push_str(&format!("- [ ] {}\n", criterion));
+            }
+        }
+        if !self.files.is_empty() {
+            let files: Vec<String> = self.files.iter().map(|f| format!("`{}`", f)).collect();
+            description.push_str(&format!("\n\nFiles: {}", files.join(", ")));
+        }
+        description.trim().to_string()
+    }
 }
 
-/// Response from Claude for decomposition
+/// The model's decomposition reply
 #[derive(Debug, Deserialize)]
 struct DecomposeResponse {
     subtasks: Vec<SubtaskSuggestion>,
     reasoning: String,
 }
 
+/// Subtasks for a parent, and the dependencies between them
+#[derive(Debug, Clone)]
+pub struct Plan {
+    pub parent_id: String,
+    pub subtasks: Vec<SubtaskSuggestion>,
+    pub reasoning: String,
+}
+
+impl Plan {
+    /// Check the subtask graph: unique keys, known dependencies, no cycles
+    ///
+    /// Subtasks without a key get `s1`, `s2`, ... by position.
+    pub fn new(parent_id: &str, mut subtasks: Vec<SubtaskSuggestion>, reasoning: String) -> Result<Self> {
+        for (i, subtask) in subtasks.iter_mut().enumerate() {
+            if subtask.key.trim().is_empty() {
+                subtask.key = format!("s{}", i + 1);
+            }
+        }
+        let mut keys = HashSet::new();
+        for subtask in &subtasks {
+            if !keys.insert(subtask.key.as_str()) {
+                anyhow::bail!("Two subtasks share the key '{}'", subtask.key);
+            }
+        }
+        for subtask in &subtasks {
+            if let Some(unknown) = subtask.depends_on.iter().find(|d| !keys.contains(d.as_str())) {
+                anyhow::bail!("Subtask '{}' depends on unknown subtask '{}'", subtask.key, unknown);
+            }
+        }
+
+        let plan = Self {
+            parent_id: parent_id.to_string(),
+            subtasks,
+            reasoning,
+        };
+        let ordered: usize = plan.waves().iter().map(Vec::len).sum();
+        if ordered < plan.subtasks.len() {
+            anyhow::bail!("Subtask dependencies form a cycle");
+        }
+        Ok(plan)
+    }
+
+    /// Subtasks in waves: each depends only on subtasks in earlier waves
+    pub fn waves(&self) -> Vec<Vec<&SubtaskSuggestion>> {
+        let mut done: HashSet<&str> = HashSet::new();
+        let mut waves = Vec::new();
+        loop {
+            let wave: Vec<&SubtaskSuggestion> = self
+                .subtasks
+                .iter()
+                .filter(|s| !done.contains(s.key.as_str()))
+                .filter(|s| s.depends_on.iter().all(|d| done.contains(d.as_str())))
+                .collect();
+            // Empty once everything is placed, or if the rest is a cycle
+            if wave.is_empty() {
+                return waves;
+            }
+            done.extend(wave.iter().map(|s| s.key.as_str()));
+            waves.push(wave);
+        }
+    }
+
+    /// The plan for review (`--plan-only`)
+    pub fn render(&self) -> String {
+        let waves = self.waves();
+        let mut out = format!(
+            "Plan for {}: {} subtasks in {} wave(s)\n",
+            self.parent_id,
+            self.subtasks.len(),
+            waves.len()
+        );
+        for (i, wave) in waves.iter().enumerate() {
+            out.push_str(&format!("\nWave {}\n", i + 1));
+            for subtask in wave {
+                let after = if subtask.depends_on.is_empty() {
+                    String::new()
+                } else {
+                    format!("  ← {}", subtask.depends_on.join(", "))
+                };
+                out.push_str(&format!(
+                    "  [{}] {} (P{} {}){}\n",
+                    subtask.key, subtask.title, subtask.priority, subtask.issue_type, after
+                ));
+                if !subtask.files.is_empty() {
+                    out.push_str(&format!("       files: {}\n", subtask.files.join(", ")));
+                }
+                for criterion in &subtask.acceptance_criteria {
+                    out.push_str(&format!("       - [ ] {}\n", criterion));
+                }
+            }
+        }
+        if !self.reasoning.is_empty() {
+            out.push_str(&format!("\nReasoning: {}\n", self.reasoning));
+        }
+        out
+    }
+}
+
 /// Analyze a task to determine if it needs decomposition
-pub fn analyze_task(parsed: &ParsedTask, project_root: &Path) -> TaskAnalysis {
+pub fn analyze_task(parsed: &ParsedTask, project_root: &Path, history: &History) -> TaskAnalysis {
     let task = &parsed.task;
     let description_chars = task.description.len() + task.notes.len();
     let criteria_count = parsed.acceptance_criteria.len();
-    
+
     // Check if this task already has subtasks (blocks other tasks)
     let has_subtasks = !task.blocks.is_empty();
-    
+
     // Determine if task is too large
     let mut is_too_large = false;
     let mut reasons = Vec::new();
-    
+
     // Check 1: Description is very long
     if description_chars > LARGE_TASK_DESCRIPTION_THRESHOLD {
         is_too_large = true;
@@ -70,13 +295,13 @@ pub fn analyze_task(parsed: &ParsedTask, project_root: &Path) -> TaskAnalysis {
             description_chars, LARGE_TASK_DESCRIPTION_THRESHOLD
         ));
     }
-    
+
     // Check 2: No acceptance criteria (vague task)
     if criteria_count == 0 && description_chars > 500 {
         is_too_large = true;
         reasons.push("No acceptance criteria defined for complex task".to_string());
     }
-    
+
     // Check 3: Task mentions multiple files/components to create
     let multi_file_indicators = [
         "## Files to Create",
@@ -85,7 +310,7 @@ pub fn analyze_task(parsed: &ParsedTask, project_root: &Path) -> TaskAnalysis {
         "multiple files",
         "several components",
     ];
-    
+
     for indicator in &multi_file_indicators {
         if task.description.contains(indicator) {
             is_too_large = true;
@@ -93,7 +318,7 @@ pub fn analyze_task(parsed: &ParsedTask, project_root: &Path) -> TaskAnalysis {
             break;
         }
     }
-    
+
     // Check 4: Task type is "epic" or "feature" with no subtasks
     if (task.issue_type == "epic" || task.issue_type == "feature") && !has_subtasks {
         if description_chars > 800 {
@@ -104,14 +329,39 @@ pub fn analyze_task(parsed: &ParsedTask, project_root: &Path) -> TaskAnalysis {
             ));
         }
     }
-    
+
+    // Check 5: The repo says it touches many files
+    let estimated_files = estimate_files(parsed, project_root);
+    if estimated_files.len() > MAX_ESTIMATED_FILES {
+        is_too_large = true;
+        reasons.push(format!(
+            "Likely touches {} files (threshold: {})",
+            estimated_files.len(), MAX_ESTIMATED_FILES
+        ));
+    }
+
+    // Check 6: Past tasks of that size cost far more than usual
+    let touched = estimated_files.len().max(1);
+    let predicted_tokens = history.tokens_per_file.map(|per_file| per_file * touched as u64);
+    let predicted_iterations = history.iterations_per_file.map(|per_file| (per_file * touched as f64).ceil() as usize);
+    if let Some(tokens) = predicted_tokens {
+        let limit = history.median_tokens as f64 * OVERSIZE_FACTOR;
+        if history.tasks >= MIN_HISTORY_TASKS && tokens as f64 > limit {
+            is_too_large = true;
+            reasons.push(format!(
+                "Predicted ~{}k tokens; past tasks took a median of {}k",
+                tokens / 1000, history.median_tokens / 1000
+            ));
+        }
+    }
+
     // Don't flag if already has subtasks
     if has_subtasks {
         is_too_large = false;
         reasons.clear();
         reasons.push("Task already has subtasks defined".to_string());
     }
-    
+
     TaskAnalysis {
         task_id: task.id.clone(),
         is_too_large,
@@ -119,31 +369,116 @@ pub fn analyze_task(parsed: &ParsedTask, project_root: &Path) -> TaskAnalysis {
         description_chars,
         criteria_count,
         has_subtasks,
-        suggested_subtasks: Vec::new(),
+        estimated_files,
+        predicted_tokens,
+        predicted_iterations,
+    }
+}
+
+/// Files a task is likely to touch: paths it names, plus files that mention
+/// the code identifiers it names
+pub fn estimate_files(parsed: &ParsedTask, project_root: &Path) -> Vec<String> {
+    let text = format!("{}\n{}\n{}", parsed.task.title, parsed.task.description, parsed.task.notes);
+    let mut files: HashSet<String> = knowledge::mentioned_files(&text).into_iter().collect();
+    for identifier in identifiers(&text) {
+        let found = files_mentioning(project_root, &identifier);
+        if found.len() <= MAX_FILES_PER_IDENTIFIER {
+            files.extend(found);
+        }
     }
+    let mut files: Vec<String> = files.into_iter().collect();
+    files.sort();
+    files
 }
 
-/// Use Claude to decompose a large task into subtasks
+/// Code identifiers in task text: `backticked` names, CamelCase, camelCase
+/// and snake_case words
+fn identifiers(text: &str) -> Vec<String> {
+    let is_identifier = |word: &str| {
+        word.len() >= 4
+            && word.chars().all(|c| c.is_alphanumeric() || c == '_')
+            && word.starts_with(|c: char| c.is_alphabetic() || c == '_')
+    };
+    let looks_like_code = |word: &str| {
+        let inner_upper = word.chars().skip(1).any(|c| c.is_uppercase());
+        let has_lower = word.chars().any(|c| c.is_lowercase());
+        has_lower && (word.contains('_') || inner_upper)
+    };
+
+    let backticked = text
+        .split('`')
+        .skip(1)
+        .step_by(2)
+        .map(|span| span.trim().trim_end_matches("()"))
+        .filter(|span| is_identifier(span));
+    let words = text
+        .split(|c: char| !c.is_alphanumeric() && c != '_')
+        .filter(|word| is_identifier(word) && looks_like_code(word));
+
+    let mut seen = HashSet::new();
+    backticked
+        .chain(words)
+        .filter(|word| seen.insert(word.to_string()))
+        .take(MAX_IDENTIFIERS)
+        .map(str::to_string)
+        .collect()
+}
+
+/// Files containing `identifier` as a whole word, through ripgrep like
+/// `code_search`, or grep where ripgrep isn't installed
+fn files_mentioning(project_root: &Path, identifier: &str) -> Vec<String> {
+    let output = Command::new("rg")
+        .args(["--files-with-matches", "--fixed-strings", "--word-regexp", "--", identifier])
+        .current_dir(project_root)
+        .output()
+        .or_else(|_| {
+            Command::new("grep")
+                .args(["-rlwF", "--exclude-dir=.git", "--exclude-dir=target", "--exclude-dir=node_modules"])
+                .args(["--exclude-dir=.ralph", "--exclude-dir=.beads", "--", identifier, "."])
+                .current_dir(project_root)
+                .output()
+        });
+    match output {
+        Ok(out) => String::from_utf8_lossy(&out.stdout)
+            .lines()
+            .map(|line| line.trim_start_matches("./").to_string())
+            .collect(),
+        Err(e) => {
+            tracing::warn!("Could not search for {}: {}", identifier, e);
+            Vec::new()
+        }
+    }
+}
+
+/// Ask the model to decompose a large task into dependent subtasks
+///
+/// The call is charged to the task through `meter`.
 pub async fn decompose_task(
     parsed: &ParsedTask,
-    api_key: &str,
-) -> Result<Vec<SubtaskSuggestion>> {
+    analysis: &TaskAnalysis,
+    backend: &dyn ModelBackend,
+    meter: &CostMeter,
+) -> Result<Plan> {
     let task = &parsed.task;
-    
+
     let system_prompt = r#"You are a software project manager breaking down large tasks into smaller, focused subtasks.
 
 Your goal is to analyze a large epic/feature task and decompose it into 3-7 smaller subtasks that:
 1. Can each be completed in a single coding session
 2. Have clear, focused scope (1-2 files max)
-3. Have logical dependencies (if any)
+3. State which other subtasks must be finished first
 4. Together fully implement the original task
 
 Output JSON only with this structure:
 {
   "subtasks": [
     {
+      "key": "s1",
       "title": "Short descriptive title",
-      "description": "Clear description with acceptance criteria as checkboxes:\n- [ ] Criterion 1\n- [ ] Criterion 2",
+      "description": "What to build and how it fits the parent task",
+      "acceptance_criteria": ["Criterion 1", "Criterion 2"],
+      "depends_on": [],
+      "files": ["path/to/file.rs"],
       "priority": 2,
       "issue_type": "task",
       "labels": ["relevant", "labels"]
@@ -154,12 +489,21 @@ Output JSON only with this structure:
 
 Rules:
 - Each subtask should be completable in 30-50 Claude API iterations
+- "depends_on" lists the keys of subtasks this one needs; subtasks that don't
+  need each other have no dependency, so they can be worked on in any order
+- Dependencies must not form a cycle
 - Include specific file paths when known
-- First subtask should be foundational (types, interfaces, etc.)
-- Last subtask should be integration/testing
+- Foundational subtasks (types, interfaces, etc.) come first
+- Integration/testing subtasks depend on what they integrate
 - Priority should match parent (default 2)
 - Labels should inherit from parent plus be specific"#;
 
+    let files_section = if analysis.estimated_files.is_empty() {
+        "(none found)".to_string()
+    } else {
+        analysis.estimated_files.join("\n")
+    };
+
     let user_prompt = format!(
         r#"Decompose this large task into smaller subtasks:
 
@@ -175,6 +519,12 @@ Rules:
 ## Notes:
 {}
 
+## Files it likely touches (from searching the repo):
+{}
+
+## Why it needs splitting:
+{}
+
 Output ONLY valid JSON with the subtasks array."#,
         task.id,
         task.title,
@@ -182,43 +532,47 @@ Output ONLY valid JSON with the subtasks array."#,
         task.priority,
         task.labels.join(", "),
         task.description,
-        task.notes
+        task.notes,
+        files_section,
+        analysis.reason
     );
 
-    let client = Client::new();
-    
-    let request_body = serde_json::json!({
-        "model": CLAUDE_MODEL,
-        "max_tokens": 4096,
-        "messages": [
-            {"role": "user", "content": user_prompt}
-        ],
-        "system": system_prompt
-    });
-
-    let response = client
-        .post(CLAUDE_API_URL)
-        .header("x-api-key", api_key)
-        .header("anthropic-version", "2023-06-01")
-        .header("content-type", "application/json")
-        .json(&request_body)
-        .send()
+    let messages = vec![Message {
+        role: Role::User,
+        content: vec![ContentBlock::Text { text: user_prompt }],
+    }];
+    let response = backend
+        .complete(
+            ModelRequest {
+                system: system_prompt,
+                messages: &messages,
+                tools: &[],
+                max_tokens: DECOMPOSE_MAX_TOKENS,
+            },
+            None,
+        )
         .await
-        .context("Failed to call Claude API for decomposition")?;
+        .with_context(|| format!("Decomposition call to {} ({}) failed", backend.name(), backend.model()))?;
+    if let Some(usage) = &response.usage {
+        if let Err(e) = meter.record_call(backend.name(), backend.model(), usage, Purpose::Decompose) {
+            tracing::warn!("Failed to record spend: {}", e);
+        }
+    }
 
-    if !response.status().is_success() {
-        let status = response.status();
-        let body = response.text().await.unwrap_or_default();
-        anyhow::bail!("Claude API error {}: {}", status, body);
+    let text: Vec<&str> = response
+        .content
+        .iter()
+        .filter_map(|b| match b {
+            ContentBlock::Text { text } => Some(text.as_str()),
+            _ => None,
+        })
+        .collect();
+    if text.is_empty() {
+        anyhow::bail!("No text in the decomposition reply");
     }
+    let content = text.join("\n");
+    let content = content.as_str();
 
-    let api_response: serde_json::Value = response.json().await?;
-    
-    // Extract text content from response
-    let content = api_response["content"][0]["text"]
-        .as_str()
-        .context("No text in Claude response")?;
-    
     // Parse JSON from response (handle markdown code blocks)
     let json_str = if content.contains("```json") {
         content
@@ -235,144 +589,192 @@ Output ONLY valid JSON with the subtasks array."#,
     } else {
         content
     };
-    
+
     let decompose_response: DecomposeResponse = serde_json::from_str(json_str.trim())
         .context("Failed to parse decomposition response as JSON")?;
-    
-    println!("Decomposition reasoning: {}", decompose_response.reasoning);
-    
-    Ok(decompose_response.subtasks)
+
+    Plan::new(&task.id, decompose_response.subtasks, decompose_response.reasoning)
 }
 
-/// Create subtasks in beads for a parent task
-pub fn create_subtasks(
-    project_root: &Path,
-    parent_id: &str,
-    parent_labels: &[String],
-    subtasks: &[SubtaskSuggestion],
-) -> Result<Vec<String>> {
-    let mut created_ids = Vec::new();
-    
-    for subtask in subtasks {
+/// Create a plan's subtasks, wired to each other and to the parent
+///
+/// Subtasks are created wave by wave, so each one's dependencies already
+/// exist. The parent depends on every subtask, which keeps it out of the
+/// ready set until they are all closed.
+pub fn create_subtasks(project_root: &Path, parent: &BeadTask, plan: &Plan) -> Result<Vec<String>> {
+    let store = beads::for_project(project_root);
+    let mut ids: HashMap<&str, String> = HashMap::new();
+
+    for subtask in plan.waves().into_iter().flatten() {
         // Merge parent labels with subtask labels
-        let mut all_labels: Vec<String> = parent_labels.to_vec();
+        let mut labels: Vec<String> = parent.labels.clone();
         for label in &subtask.labels {
-            if !all_labels.contains(label) {
-                all_labels.push(label.clone());
+            if !labels.contains(label) {
+                labels.push(label.clone());
             }
         }
-        
-        // Build bd create command
-        let mut args = vec![
-            "create".to_string(),
-            format!("--title={}", subtask.title),
-            format!("--type={}", subtask.issue_type),
-            format!("--priority={}", subtask.priority),
-        ];
-        
-        if !all_labels.is_empty() {
-            args.push(format!("--labels={}", all_labels.join(",")));
-        }
-        
-        if !subtask.description.is_empty() {
-            args.push(format!("--description={}", subtask.description));
-        }
-        
-        let args_refs: Vec<&str> = args.iter().map(|s| s.as_str()).collect();
-        
-        let output = Command::new("bd")
-            .args(&args_refs)
-            .current_dir(project_root)
-            .output()
-            .context("Failed to run 'bd create'")?;
-        
-        if !output.status.success() {
-            let stderr = String::from_utf8_lossy(&output.stderr);
-            tracing::warn!("Failed to create subtask '{}': {}", subtask.title, stderr);
-            continue;
-        }
-        
-        // Parse created task ID from output
-        let stdout = String::from_utf8_lossy(&output.stdout);
-        // Output format: "✓ Created issue: <id>"
-        if let Some(id) = stdout.split("issue:").nth(1) {
-            let id = id.trim().split_whitespace().next().unwrap_or("").to_string();
-            if !id.is_empty() {
-                // Add dependency: parent epic depends on subtask (subtask blocks parent)
-                // This makes subtasks immediately ready to work on
-                let dep_output = Command::new("bd")
-                    .args(["dep", "add", parent_id, &id])
-                    .current_dir(project_root)
-                    .output();
-                
-                if let Ok(dep_out) = dep_output {
-                    if !dep_out.status.success() {
-                        tracing::warn!("Failed to add dependency for {}", id);
-                    }
-                }
-                
-                created_ids.push(id);
-                println!("  Created subtask: {}", subtask.title);
-            }
+        let issue = NewIssue {
+            title: subtask.title.clone(),
+            description: subtask.issue_description(),
+            priority: subtask.priority,
+            issue_type: subtask.issue_type.clone(),
+            labels,
+        };
+
+        let id = match &store {
+            Some(store) => store.create(&issue)?,
+            None => bd_create(project_root, &issue)?,
+        };
+        let mut depends_on: Vec<&str> = subtask.depends_on.iter().filter_map(|key| ids.get(key.as_str())).map(String::as_str).collect();
+        depends_on.sort();
+        for dependency in depends_on {
+            add_dependency(store.as_ref(), project_root, &id, dependency)?;
         }
+        add_dependency(store.as_ref(), project_root, &parent.id, &id)?;
+
+        println!("  Created subtask {} [{}]: {}", id, subtask.key, subtask.title);
+        ids.insert(subtask.key.as_str(), id);
+    }
+
+    Ok(plan.subtasks.iter().filter_map(|s| ids.get(s.key.as_str()).cloned()).collect())
+}
+
+/// Create an issue with `bd` and return its id
+fn bd_create(project_root: &Path, issue: &NewIssue) -> Result<String> {
+    let mut args = vec![
+        "create".to_string(),
+        format!("--title={}", issue.title),
+        format!("--type={}", issue.issue_type),
+        format!("--priority={}", issue.priority),
+    ];
+    if !issue.labels.is_empty() {
+        args.push(format!("--labels={}", issue.labels.join(",")));
+    }
+    if !issue.description.is_empty() {
+        args.push(format!("--description={}", issue.description));
+    }
+
+    let output = Command::new("bd")
+        .args(&args)
+        .current_dir(project_root)
+        .output()
+        .context("Failed to run 'bd create'")?;
+    if !output.status.success() {
+        anyhow::bail!(
+            "bd create failed for '{}': {}",
+            issue.title,
+            String::from_utf8_lossy(&output.stderr)
+        );
+    }
+
+    // Output format: "✓ Created issue: <id>"
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    stdout
+        .split("issue:")
+        .nth(1)
+        .and_then(|rest| rest.split_whitespace().next())
+        .map(str::to_string)
+        .with_context(|| format!("No issue id in bd output: {}", stdout.trim()))
+}
+
+/// Make `task_id` wait for `depends_on`
+fn add_dependency(store: Option<&BeadsStore>, project_root: &Path, task_id: &str, depends_on: &str) -> Result<()> {
+    if let Some(store) = store {
+        return Ok(store.add_dependency(task_id, depends_on)?);
     }
-    
-    Ok(created_ids)
+    let output = Command::new("bd")
+        .args(["dep", "add", task_id, depends_on])
+        .current_dir(project_root)
+        .output()
+        .context("Failed to run 'bd dep add'")?;
+    if !output.status.success() {
+        anyhow::bail!(
+            "bd dep add {} {} failed: {}",
+            task_id,
+            depends_on,
+            String::from_utf8_lossy(&output.stderr)
+        );
+    }
+    Ok(())
+}
+
+/// Split one task that keeps failing (triage `decompose`); returns the new subtask ids
+pub async fn decompose_stuck(
+    project_root: &Path,
+    parsed: &ParsedTask,
+    backend: &dyn ModelBackend,
+    meter: &CostMeter,
+    reason: &str,
+) -> Result<Vec<String>> {
+    let mut analysis = analyze_task(parsed, project_root, &History::load(project_root));
+    analysis.reason = reason.to_string();
+    let plan = decompose_task(parsed, &analysis, backend, meter).await?;
+    if plan.subtasks.is_empty() {
+        anyhow::bail!("No subtasks suggested");
+    }
+    create_subtasks(project_root, &parsed.task, &plan)
 }
 
 /// Analyze all ready tasks and return those needing decomposition
 pub fn find_tasks_needing_decomposition(project_root: &Path) -> Result<Vec<(ParsedTask, TaskAnalysis)>> {
     let ready_tasks = get_ready_tasks(project_root)?;
+    let history = History::load(project_root);
     let mut needs_decomposition = Vec::new();
-    
+
     for task in ready_tasks {
         let parsed = parse_task(&task);
-        let analysis = analyze_task(&parsed, project_root);
-        
+        let analysis = analyze_task(&parsed, project_root, &history);
+
         if analysis.is_too_large {
             needs_decomposition.push((parsed, analysis));
         }
     }
-    
+
     Ok(needs_decomposition)
 }
 
 /// Pre-process: analyze and decompose large tasks before running the loop
-pub async fn preprocess_tasks(project_root: &Path, api_key: &str) -> Result<usize> {
+///
+/// With `plan_only`, the proposed plans are printed and nothing is created.
+pub async fn preprocess_tasks(
+    project_root: &Path,
+    backend: &dyn ModelBackend,
+    costs: &Costs,
+    plan_only: bool,
+) -> Result<usize> {
     println!("\n🔍 Analyzing tasks for decomposition...\n");
-    
+
     let needs_decomposition = find_tasks_needing_decomposition(project_root)?;
-    
+
     if needs_decomposition.is_empty() {
         println!("✓ All tasks are appropriately sized.\n");
         return Ok(0);
     }
-    
+
     println!("Found {} task(s) that may need decomposition:\n", needs_decomposition.len());
-    
+
     let mut total_created = 0;
-    
+
     for (parsed, analysis) in &needs_decomposition {
         println!("─ {} ({})", parsed.task.id, parsed.task.title);
         println!("  Reason: {}", analysis.reason);
         println!("  Decomposing...");
-        
-        match decompose_task(parsed, api_key).await {
-            Ok(subtasks) => {
-                if subtasks.is_empty() {
+
+        match decompose_task(parsed, analysis, backend, &costs.meter(&parsed.task.id)).await {
+            Ok(plan) => {
+                if plan.subtasks.is_empty() {
                     println!("  ⚠ No subtasks suggested");
                     continue;
                 }
-                
-                println!("  Creating {} subtasks...", subtasks.len());
-                
-                let created = create_subtasks(
-                    project_root,
-                    &parsed.task.id,
-                    &parsed.task.labels,
-                    &subtasks,
-                )?;
-                
+                if plan_only {
+                    println!("\n{}", plan.render());
+                    continue;
+                }
+
+                println!("  Creating {} subtasks...", plan.subtasks.len());
+
+                let created = create_subtasks(project_root, &parsed.task, &plan)?;
+
                 total_created += created.len();
                 println!("  ✓ Created {} subtasks\n", created.len());
             }
@@ -381,22 +783,33 @@ pub async fn preprocess_tasks(project_root: &Path, api_key: &str) -> Result<usiz
             }
         }
     }
-    
+
     // Sync beads after creating subtasks
     if total_created > 0 {
         println!("Syncing beads...");
         crate::task_parser::sync_beads(project_root)?;
     }
-    
-    println!("\n✓ Pre-processing complete. Created {} subtasks.\n", total_created);
-    
+
+    if plan_only {
+        println!("\nPLAN ONLY: nothing was created. Run without --plan-only to create these subtasks.\n");
+    } else {
+        println!("\n✓ Pre-processing complete. Created {} subtasks.\n", total_created);
+    }
+
     Ok(total_created)
 }
 
+/// The middle value (upper middle for an even count)
+fn median<T: Ord + Copy>(mut values: Vec<T>) -> Option<T> {
+    values.sort();
+    values.get(values.len() / 2).copied()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
-    
+    use tempfile::TempDir;
+
     #[test]
     fn test_analyze_task_large_description() {
         let task = BeadTask {
@@ -412,14 +825,14 @@ mod tests {
             depends_on: vec![],
             blocks: vec![],
         };
-        
+
         let parsed = parse_task(&task);
-        let analysis = analyze_task(&parsed, Path::new("."));
-        
+        let analysis = analyze_task(&parsed, Path::new("."), &History::default());
+
         assert!(analysis.is_too_large);
         assert!(analysis.reason.contains("chars"));
     }
-    
+
     #[test]
     fn test_analyze_task_with_subtasks() {
         let task = BeadTask {
@@ -435,12 +848,121 @@ mod tests {
             depends_on: vec![],
             blocks: vec!["test-3".to_string()],
         };
-        
+
         let parsed = parse_task(&task);
-        let analysis = analyze_task(&parsed, Path::new("."));
-        
+        let analysis = analyze_task(&parsed, Path::new("."), &History::default());
+
         // Should NOT be marked as too large since it has subtasks
         assert!(!analysis.is_too_large);
         assert!(analysis.has_subtasks);
     }
+
+    #[test]
+    fn test_sizing_uses_repo_and_history() {
+        let temp = TempDir::new().unwrap();
+        std::fs::create_dir_all(temp.path().join("src")).unwrap();
+        for name in ["a", "b", "c"] {
+            std::fs::write(temp.path().join(format!("src/{}.rs", name)), "let s = SessionStore::new();\n").unwrap();
+        }
+        std::fs::write(temp.path().join("src/other.rs"), "fn unrelated() {}\n").unwrap();
+
+        let parsed = parse_task(&BeadTask {
+            id: "t-9".to_string(),
+            title: "Expire sessions".to_string(),
+            description: "Give `SessionStore` a TTL and document it in README.md".to_string(),
+            notes: String::new(),
+            status: "open".to_string(),
+            priority: 2,
+            issue_type: "task".to_string(),
+            owner: None,
+            labels: vec![],
+            depends_on: vec![],
+            blocks: vec![],
+        });
+        assert_eq!(estimate_files(&parsed, temp.path()), ["README.md", "src/a.rs", "src/b.rs", "src/c.rs"]);
+
+        // Three past tasks of one file each, ~10k tokens a file
+        let session = |task_id: &str, tokens: u64| LedgerEntry {
+            timestamp: chrono::Utc::now(),
+            run_id: "r".to_string(),
+            task_id: task_id.to_string(),
+            backend: "anthropic".to_string(),
+            model: "sonnet".to_string(),
+            input_tokens: tokens,
+            output_tokens: 0,
+            cost_usd: 0.0,
+            stop_reason: Some(StopReason::Completed),
+            iterations: 6,
+            purpose: Purpose::Session,
+            error: None,
+        };
+        let ledger = [session("t-1", 9_000), session("t-2", 10_000), session("t-3", 12_000)];
+        let outcomes: Vec<knowledge::Entry> = ["t-1", "t-2", "t-3"]
+            .iter()
+            .map(|id| knowledge::Entry::new(knowledge::Kind::Outcome, "done").with_task(id).with_files(&["src/x.rs".to_string()]))
+            .collect();
+        let history = History::from_records(&ledger, &outcomes);
+        assert_eq!((history.tasks, history.median_tokens, history.tokens_per_file), (3, 10_000, Some(10_000)));
+
+        let analysis = analyze_task(&parsed, temp.path(), &history);
+        assert_eq!(analysis.predicted_tokens, Some(40_000));
+        assert_eq!(analysis.predicted_iterations, Some(24));
+        assert!(analysis.is_too_large);
+        assert!(analysis.reason.contains("Predicted ~40k tokens; past tasks took a median of 10k"));
+    }
+
+    #[test]
+    fn test_plan_waves_and_subtask_creation() {
+        let subtask = |key: &str, depends_on: &[&str]| SubtaskSuggestion {
+            key: key.to_string(),
+            title: format!("Step {}", key),
+            description: "Do it".to_string(),
+            priority: 1,
+            issue_type: "task".to_string(),
+            labels: vec!["auth".to_string()],
+            acceptance_criteria: vec![format!("{} works", key)],
+            depends_on: depends_on.iter().map(|d| d.to_string()).collect(),
+            files: vec![],
+        };
+        let plan = Plan::new(
+            "p-2",
+            vec![subtask("model", &[]), subtask("api", &["model"]), subtask("ui", &[]), subtask("e2e", &["api", "ui"])],
+            "Bottom up".to_string(),
+        )
+        .unwrap();
+        let keys: Vec<Vec<&str>> = plan.waves().iter().map(|w| w.iter().map(|s| s.key.as_str()).collect()).collect();
+        assert_eq!(keys, [vec!["model", "ui"], vec!["api"], vec!["e2e"]]);
+        assert!(plan.render().contains("Wave 3\n  [e2e] Step e2e (P1 task)  ← api, ui\n       - [ ] e2e works"));
+
+        assert!(Plan::new("p", vec![subtask("a", &["b"]), subtask("b", &["a"])], String::new()).is_err());
+        assert!(Plan::new("p", vec![subtask("a", &["missing"])], String::new()).is_err());
+
+        let temp = TempDir::new().unwrap();
+        std::fs::create_dir_all(temp.path().join(".beads")).unwrap();
+        std::fs::write(
+            temp.path().join(".beads/issues.jsonl"),
+            r#"{"id":"p-2","title":"Auth","status":"open","priority":1,"labels":["web"],"created_at":"2025-01-01T00:00:00Z"}"#,
+        )
+        .unwrap();
+        let store = BeadsStore::new(temp.path());
+        let parent = store.get("p-2").unwrap();
+
+        let ids = create_subtasks(temp.path(), &parent, &plan).unwrap();
+        assert_eq!(ids.len(), 4);
+        let e2e = store.get(&ids[3]).unwrap();
+        let mut waits_for = e2e.depends_on.clone();
+        waits_for.sort();
+        let mut expected = vec![ids[1].clone(), ids[2].clone()];
+        expected.sort();
+        assert_eq!(waits_for, expected);
+        assert_eq!(e2e.labels, ["web", "auth"]);
+        assert!(e2e.description.contains("- [ ] e2e works"));
+        // Only the foundations are ready; the parent waits for everything
+        let mut ready: Vec<String> = store.ready().unwrap().into_iter().map(|t| t.id).collect();
+        ready.sort();
+        let mut foundations = vec![ids[0].clone(), ids[2].clone()];
+        foundations.sort();
+        assert_eq!(ready, foundations);
+        assert_eq!(store.get("p-2").unwrap().depends_on.len(), 4);
+    }
 }
@@ -0,0 +1,275 @@
+//! Loop events - Typed progress reports from the agentic loop
+//!
+//! `ClaudeClient` sends a `LoopEvent` for each step of a run: iteration
+//! start, streamed text, tool calls and their results, token usage,
+//! interventions, compactions and the stop reason. Front ends consume the
+//! same stream:
+//!
+//! - the console prints each event's `Display` text
+//! - the TUI updates its panes and counters from the fields
+//! - `--events-json` writes one JSON object per event to stdout (NDJSON),
+//!   with the harness's own messages moved to stderr
+
+use serde::Serialize;
+use serde_json::Value;
+use std::fmt;
+use std::sync::atomic::{AtomicBool, Ordering};
+use tokio::sync::mpsc;
+
+use crate::claude_client::StopReason;
+use crate::sandbox::Violation;
+
+/// Tool output shown on the console, in bytes
+const PREVIEW_BYTES: usize = 500;
+
+/// Set by `--events-json`: stdout carries only NDJSON events
+static JSON_STDOUT: AtomicBool = AtomicBool::new(false);
+
+pub type EventSender = mpsc::Sender<LoopEvent>;
+
+/// Something that happened in the loop or the harness around it
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum LoopEvent {
+    /// A model call is about to be made
+    IterationStart {
+        iteration: usize,
+        /// Exploration and action tool calls so far this session
+        exploration: u32,
+        action: u32,
+    },
+    /// Text streamed from the model
+    TextDelta { text: String },
+    /// The model asked for a tool
+    ToolCall { id: String, name: String, input: Value },
+    /// A tool finished; `output` is what the model sees
+    ToolResult {
+        id: String,
+        name: String,
+        success: bool,
+        output: String,
+        duration_ms: u64,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        violation: Option<Violation>,
+    },
+    /// Usage for one model call, and the session totals
+    TokenUsage {
+        input_tokens: u32,
+        output_tokens: u32,
+        total_input_tokens: u32,
+        total_output_tokens: u32,
+        /// Estimated cost of this call
+        cost_usd: f64,
+    },
+    /// An exploration-spiral nudge was added to the conversation
+    Intervention { message: String },
+    /// A human's message was added to the conversation
+    Steering { message: String },
+    /// The conversation was compacted near the redline
+    Compacted {
+        context_tokens: u32,
+        messages_before: usize,
+        messages_after: usize,
+    },
+    /// The verification gate is running
+    Verifying { commands: Vec<String> },
+    Verified { passed: bool, summary: String },
+    /// The loop stopped
+    Stopped {
+        reason: StopReason,
+        iterations: usize,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        detail: Option<String>,
+    },
+    /// The loop picked a task; `base` is the commit it started from
+    TaskStarted {
+        task_id: String,
+        title: String,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        base: Option<String>,
+    },
+    /// The loop moved on from a task; `head` is the commit it left behind
+    TaskFinished {
+        task_id: String,
+        completed: bool,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        head: Option<String>,
+    },
+    /// A message from the harness (committed, rebooting, ...)
+    Status { level: Level, text: String },
+}
+
+/// How a harness message should be shown
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Level {
+    Info,
+    Success,
+    Warning,
+    Error,
+}
+
+impl LoopEvent {
+    /// The event as one NDJSON line, with the task and a timestamp
+    pub fn to_json_line(&self, task_id: &str) -> String {
+        let mut value = serde_json::to_value(self).unwrap_or_default();
+        if let Value::Object(fields) = &mut value {
+            fields.entry("task_id").or_insert_with(|| task_id.into());
+            fields.insert("timestamp".to_string(), chrono::Utc::now().to_rfc3339().into());
+        }
+        value.to_string()
+    }
+}
+
+/// The console rendering; events the console doesn't show are empty
+impl fmt::Display for LoopEvent {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LoopEvent::IterationStart {
+                iteration,
+                exploration,
+                action,
+            } => write!(
+                f,
+                "\n--- Iteration {} [explore:{}/action:{}] ---\n",
+                iteration, exploration, action
+            ),
+            LoopEvent::TextDelta { text } => write!(f, "{}", text),
+            LoopEvent::ToolCall { name, .. } => write!(f, "\n[Executing tool: {}]\n", name),
+            LoopEvent::ToolResult { output, .. } if output.len() > PREVIEW_BYTES => {
+                // Safe truncation respecting UTF-8 character boundaries
+                let mut end = PREVIEW_BYTES;
+                while !output.is_char_boundary(end) {
+                    end -= 1;
+                }
+                writeln!(f, "{}...[truncated]", &output[..end])
+            }
+            LoopEvent::ToolResult { output, .. } => writeln!(f, "{}", output),
+            LoopEvent::TokenUsage { .. } => Ok(()),
+            LoopEvent::Intervention { message } => write!(f, "\n{}\n", message),
+            LoopEvent::Steering { message } => write!(f, "\n[STEERING] {}\n", message),
+            LoopEvent::Compacted {
+                context_tokens,
+                messages_before,
+                messages_after,
+            } => write!(
+                f,
+                "\n[COMPACTING CONTEXT: {} tokens, {} -> {} messages]\n",
+                context_tokens, messages_before, messages_after
+            ),
+            LoopEvent::Verifying { commands } => write!(f, "\n[VERIFYING: {}]\n", commands.join(" && ")),
+            LoopEvent::Verified { passed, summary } => {
+                let status = if *passed { "PASSED" } else { "FAILED" };
+                writeln!(f, "[VERIFICATION {}: {}]", status, summary)
+            }
+            LoopEvent::Stopped {
+                reason: StopReason::Redline,
+                detail: Some(detail),
+                ..
+            } => write!(f, "\n[REDLINE EXCEEDED: {} - stopping for fresh context]\n", detail),
+            LoopEvent::Stopped {
+                reason: StopReason::BudgetExceeded,
+                detail: Some(detail),
+                ..
+            } => write!(f, "\n[BUDGET EXCEEDED: {}]\n", detail),
+            LoopEvent::Stopped { .. } => Ok(()),
+            LoopEvent::TaskStarted { task_id, title, .. } => {
+                writeln!(f, "→ Starting Task: {}\n  {}", task_id, title)
+            }
+            LoopEvent::TaskFinished { .. } => Ok(()),
+            LoopEvent::Status { text, .. } => writeln!(f, "{}", text),
+        }
+    }
+}
+
+/// Send an event, if anyone is listening; a closed receiver never stops the loop
+pub async fn emit(events: Option<&EventSender>, event: LoopEvent) {
+    if let Some(tx) = events {
+        let _ = tx.send(event).await;
+    }
+}
+
+/// Send a harness message
+pub async fn status(events: &EventSender, level: Level, text: impl Into<String>) {
+    let _ = events
+        .send(LoopEvent::Status {
+            level,
+            text: text.into(),
+        })
+        .await;
+}
+
+/// Switch stdout to NDJSON events (`--events-json`)
+pub fn use_json_stdout() {
+    JSON_STDOUT.store(true, Ordering::Relaxed);
+}
+
+pub fn json_stdout() -> bool {
+    JSON_STDOUT.load(Ordering::Relaxed)
+}
+
+/// Print a run's events to stdout as they arrive: as text, or as NDJSON
+pub async fn print(task_id: String, mut rx: mpsc::Receiver<LoopEvent>) {
+    use std::io::Write;
+
+    while let Some(event) = rx.recv().await {
+        if json_stdout() {
+            println!("{}", event.to_json_line(&task_id));
+        } else {
+            print!("{}", event);
+            let _ = std::io::stdout().flush();
+        }
+    }
+}
+
+/// `println!` for harness messages: moves to stderr when stdout carries events
+macro_rules! say {
+    ($($arg:tt)*) => {
+        if $crate::events::json_stdout() {
+            eprintln!($($arg)*);
+        } else {
+            println!($($arg)*);
+        }
+    };
+}
+pub(crate) use say;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_console_and_json_forms() {
+        let call = LoopEvent::ToolCall {
+            id: "t1".to_string(),
+            name: "bash".to_string(),
+            input: serde_json::json!({"command": "cargo test"}),
+        };
+        assert_eq!(call.to_string(), "\n[Executing tool: bash]\n");
+
+        let line: Value = serde_json::from_str(&call.to_json_line("bd-1")).unwrap();
+        assert_eq!(line["event"], "tool_call");
+        assert_eq!(line["task_id"], "bd-1");
+        assert_eq!(line["input"]["command"], "cargo test");
+        assert!(line["timestamp"].is_string());
+
+        let stopped = LoopEvent::Stopped {
+            reason: StopReason::Redline,
+            iterations: 4,
+            detail: Some("151000 tokens > 150000 threshold".to_string()),
+        };
+        assert!(stopped.to_string().contains("[REDLINE EXCEEDED: 151000 tokens"));
+        let line: Value = serde_json::from_str(&stopped.to_json_line("bd-1")).unwrap();
+        assert_eq!((line["event"].as_str(), line["reason"].as_str()), (Some("stopped"), Some("redline")));
+
+        // Usage only goes to the TUI and the JSON stream
+        let usage = LoopEvent::TokenUsage {
+            input_tokens: 10,
+            output_tokens: 5,
+            total_input_tokens: 10,
+            total_output_tokens: 5,
+            cost_usd: 0.0,
+        };
+        assert_eq!(usage.to_string(), "");
+    }
+}
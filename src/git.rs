@@ -103,6 +103,59 @@ pub fn current_commit_short(path: &Path) -> Result<String> {
     Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
 }
 
+/// Hash of HEAD's tree; it changes exactly when the committed content does
+pub fn head_tree(path: &Path) -> Result<String> {
+    let output = git(path, &["rev-parse", "HEAD^{tree}"])?;
+    if !output.status.success() {
+        anyhow::bail!("No commits yet");
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+/// Files that differ from HEAD in the working tree, untracked ones included
+pub fn changed_files(path: &Path) -> Result<Vec<String>> {
+    let output = git(path, &["status", "--porcelain", "-z", "--untracked-files=all"])?;
+    if !output.status.success() {
+        anyhow::bail!("git status failed: {}", String::from_utf8_lossy(&output.stderr));
+    }
+    // "XY path", with a rename's original path as a separate entry after it
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let mut files = Vec::new();
+    let mut entries = stdout.split('\0').filter(|e| !e.is_empty());
+    while let Some(entry) = entries.next() {
+        if entry.starts_with('R') || entry.starts_with('C') {
+            entries.next();
+        }
+        files.extend(entry.get(3..).map(String::from));
+    }
+    Ok(files)
+}
+
+/// Changes since `base`, as a unified diff
+///
+/// With `end`, the diff runs up to that commit. Without it, the diff is
+/// against the working tree and includes new files git doesn't track yet.
+pub fn diff_since(path: &Path, base: &str, end: Option<&str>) -> Result<String> {
+    let mut args = vec!["diff", base];
+    args.extend(end);
+    let output = git(path, &args)?;
+    if !output.status.success() {
+        anyhow::bail!("git diff failed: {}", String::from_utf8_lossy(&output.stderr));
+    }
+    let mut diff = String::from_utf8_lossy(&output.stdout).to_string();
+    if end.is_some() {
+        return Ok(diff);
+    }
+
+    let untracked = git(path, &["ls-files", "--others", "--exclude-standard"])?;
+    for file in String::from_utf8_lossy(&untracked.stdout).lines() {
+        // Exits 1 because the files differ
+        let output = git(path, &["diff", "--no-index", "--", "/dev/null", file])?;
+        diff.push_str(&String::from_utf8_lossy(&output.stdout));
+    }
+    Ok(diff)
+}
+
 /// Get the current branch name
 pub fn current_branch(path: &Path) -> Result<String> {
     let output = Command::new("git")
@@ -271,6 +324,140 @@ pub fn create_spec_branch(path: &Path, spec_id: u32, spec_name: &str) -> Result<
     Ok(branch_name)
 }
 
+/// Run a git command in `path`, returning its output
+fn git(path: &Path, args: &[&str]) -> Result<std::process::Output> {
+    Command::new("git")
+        .args(args)
+        .current_dir(path)
+        .output()
+        .with_context(|| format!("Failed to run git {}", args.first().unwrap_or(&"")))
+}
+
+/// Check if a local branch exists
+pub fn branch_exists(path: &Path, branch: &str) -> Result<bool> {
+    let output = git(
+        path,
+        &["show-ref", "--verify", "--quiet", &format!("refs/heads/{}", branch)],
+    )?;
+    Ok(output.status.success())
+}
+
+/// Add a worktree at `worktree` checked out on `branch`
+///
+/// The branch is created from HEAD if it doesn't exist yet; an existing
+/// branch (e.g. partial work from an earlier attempt) is checked out as is.
+pub fn add_worktree(repo: &Path, worktree: &Path, branch: &str) -> Result<()> {
+    let worktree_str = worktree.to_string_lossy();
+    let output = if branch_exists(repo, branch)? {
+        git(repo, &["worktree", "add", &worktree_str, branch])?
+    } else {
+        git(repo, &["worktree", "add", "-b", branch, &worktree_str])?
+    };
+
+    if !output.status.success() {
+        anyhow::bail!(
+            "git worktree add failed: {}",
+            String::from_utf8_lossy(&output.stderr)
+        );
+    }
+
+    Ok(())
+}
+
+/// Remove a worktree, discarding anything left uncommitted in it
+pub fn remove_worktree(repo: &Path, worktree: &Path) -> Result<()> {
+    let output = git(
+        repo,
+        &["worktree", "remove", "--force", &worktree.to_string_lossy()],
+    )?;
+
+    if !output.status.success() {
+        anyhow::bail!(
+            "git worktree remove failed: {}",
+            String::from_utf8_lossy(&output.stderr)
+        );
+    }
+
+    // Forget worktrees whose directories are already gone
+    let _ = git(repo, &["worktree", "prune"]);
+    Ok(())
+}
+
+/// Throw away uncommitted changes under `pathspec` (tracked and untracked)
+pub fn discard_changes(path: &Path, pathspec: &str) -> Result<()> {
+    // Fails harmlessly if nothing under pathspec is tracked
+    let _ = git(path, &["checkout", "HEAD", "--", pathspec])?;
+    let _ = git(path, &["clean", "-fdq", "--", pathspec])?;
+    Ok(())
+}
+
+/// Outcome of merging a branch into the current one
+#[derive(Debug, Clone, PartialEq)]
+pub enum MergeOutcome {
+    /// Merged (or fast-forwarded)
+    Merged,
+    /// The branch had nothing new
+    UpToDate,
+    /// The merge conflicted and was aborted; lists the conflicting files
+    Conflict(Vec<String>),
+}
+
+/// Merge `branch` into the current branch with a merge commit
+///
+/// A conflicting merge is aborted so the working tree is left as it was.
+pub fn merge_branch(path: &Path, branch: &str, message: &str) -> Result<MergeOutcome> {
+    let output = git(path, &["merge", "--no-ff", "-m", message, branch])?;
+    let stdout = String::from_utf8_lossy(&output.stdout);
+
+    if output.status.success() {
+        if stdout.contains("Already up to date") {
+            return Ok(MergeOutcome::UpToDate);
+        }
+        return Ok(MergeOutcome::Merged);
+    }
+
+    let conflicts = git(path, &["diff", "--name-only", "--diff-filter=U"])?;
+    let files: Vec<String> = String::from_utf8_lossy(&conflicts.stdout)
+        .lines()
+        .map(|l| l.trim().to_string())
+        .filter(|l| !l.is_empty())
+        .collect();
+
+    if files.is_empty() {
+        anyhow::bail!(
+            "git merge {} failed: {}",
+            branch,
+            String::from_utf8_lossy(&output.stderr)
+        );
+    }
+
+    let abort = git(path, &["merge", "--abort"])?;
+    if !abort.status.success() {
+        anyhow::bail!(
+            "git merge --abort failed after conflicts in {}: {}",
+            files.join(", "),
+            String::from_utf8_lossy(&abort.stderr)
+        );
+    }
+
+    Ok(MergeOutcome::Conflict(files))
+}
+
+/// Delete a local branch that has been merged
+pub fn delete_branch(path: &Path, branch: &str) -> Result<()> {
+    let output = git(path, &["branch", "-d", branch])?;
+
+    if !output.status.success() {
+        anyhow::bail!(
+            "git branch -d {} failed: {}",
+            branch,
+            String::from_utf8_lossy(&output.stderr)
+        );
+    }
+
+    Ok(())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -285,4 +472,69 @@ mod tests {
         fs::create_dir(temp.path().join(".git")).unwrap();
         assert!(is_git_repo(temp.path()));
     }
+
+    #[test]
+    fn test_worktree_merge_and_conflict() {
+        let temp = TempDir::new().unwrap();
+        let repo = temp.path().join("repo");
+        fs::create_dir(&repo).unwrap();
+        init_repo(&repo).unwrap();
+        git(&repo, &["config", "user.name", "Ralph"]).unwrap();
+        git(&repo, &["config", "user.email", "ralph@example.com"]).unwrap();
+        fs::write(repo.join("shared.txt"), "base\n").unwrap();
+        auto_commit_task(&repo, "t-0", "base").unwrap();
+
+        // Clean merge from a worktree branch
+        let wt = temp.path().join("wt-1");
+        add_worktree(&repo, &wt, "ralph/t-1").unwrap();
+        fs::write(wt.join("one.txt"), "one\n").unwrap();
+        auto_commit_task(&wt, "t-1", "one").unwrap();
+        remove_worktree(&repo, &wt).unwrap();
+        assert!(!wt.exists());
+
+        assert_eq!(merge_branch(&repo, "ralph/t-1", "merge t-1").unwrap(), MergeOutcome::Merged);
+        assert!(repo.join("one.txt").exists());
+        delete_branch(&repo, "ralph/t-1").unwrap();
+        assert!(!branch_exists(&repo, "ralph/t-1").unwrap());
+
+        // Conflicting edits to the same line
+        let wt = temp.path().join("wt-2");
+        add_worktree(&repo, &wt, "ralph/t-2").unwrap();
+        fs::write(wt.join("shared.txt"), "from branch\n").unwrap();
+        auto_commit_task(&wt, "t-2", "two").unwrap();
+        remove_worktree(&repo, &wt).unwrap();
+        fs::write(repo.join("shared.txt"), "from main\n").unwrap();
+        auto_commit_task(&repo, "t-3", "three").unwrap();
+
+        let outcome = merge_branch(&repo, "ralph/t-2", "merge t-2").unwrap();
+        assert_eq!(outcome, MergeOutcome::Conflict(vec!["shared.txt".to_string()]));
+        assert!(!has_changes(&repo).unwrap());
+        assert_eq!(fs::read_to_string(repo.join("shared.txt")).unwrap(), "from main\n");
+    }
+    #[test]
+    fn test_diff_since() {
+        let temp = TempDir::new().unwrap();
+        let repo = temp.path();
+        init_repo(repo).unwrap();
+        git(repo, &["config", "user.name", "Ralph"]).unwrap();
+        git(repo, &["config", "user.email", "ralph@example.com"]).unwrap();
+        fs::write(repo.join("a.txt"), "one\n").unwrap();
+        auto_commit_task(repo, "t-0", "base").unwrap();
+        let base = current_commit_short(repo).unwrap();
+
+        // A committed change, an uncommitted one and a new file
+        fs::write(repo.join("a.txt"), "two\n").unwrap();
+        auto_commit_task(repo, "t-1", "partial").unwrap();
+        let partial = current_commit_short(repo).unwrap();
+        fs::write(repo.join("a.txt"), "three\n").unwrap();
+        fs::write(repo.join("new.txt"), "fresh\n").unwrap();
+
+        let committed = diff_since(repo, &base, Some(&partial)).unwrap();
+        assert!(committed.contains("+two"));
+        assert!(!committed.contains("new.txt"));
+
+        let so_far = diff_since(repo, &base, None).unwrap();
+        assert!(so_far.contains("-one") && so_far.contains("+three"));
+        assert!(so_far.contains("+++ b/new.txt") && so_far.contains("+fresh"));
+    }
 }
@@ -0,0 +1,491 @@
+//! Knowledge - What the loop has learned about the project
+//!
+//! Replaces the flat `.ralph/progress.md`. Entries are appended to
+//! `.ralph/knowledge.jsonl`, one JSON object per line:
+//!
+//! - file notes: what a file does, or how to change it safely
+//! - commands: build, test and lint commands that work here
+//! - pitfalls: mistakes to avoid, optionally tied to files
+//! - outcomes: how each task session ended and which files it touched
+//!
+//! The harness records outcomes and passing verification commands; the model
+//! adds notes, commands and pitfalls with the `remember` tool. Nothing is
+//! dropped as the store grows: when the system prompt is built, entries are
+//! ranked by overlap with the task's files and keywords and only the best
+//! fits within the prompt budget are shown, so an early lesson about a file
+//! still surfaces when a later task touches it.
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, Local, NaiveDateTime, TimeZone, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use crate::task_parser::ParsedTask;
+use crate::verify::VerifiedRun;
+
+pub const KNOWLEDGE_FILE: &str = ".ralph/knowledge.jsonl";
+/// Imported once, when there is no knowledge store yet
+const LEGACY_PROGRESS_FILE: &str = ".ralph/progress.md";
+/// ~2000 tokens, the size progress.md used to be cut at
+const MAX_KNOWLEDGE_CHARS: usize = 8000;
+/// Commands are shown for every task, newest first
+const MAX_COMMANDS: usize = 10;
+/// Outcomes shown when no earlier task overlaps this one
+const RECENT_OUTCOMES: usize = 3;
+
+/// What an entry records
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Kind {
+    FileNote,
+    Command,
+    Pitfall,
+    Outcome,
+}
+
+impl Kind {
+    pub fn parse(name: &str) -> Option<Self> {
+        serde_json::from_value(name.into()).ok()
+    }
+}
+
+/// One thing learned
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Entry {
+    pub timestamp: DateTime<Utc>,
+    pub kind: Kind,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub task_id: Option<String>,
+    /// Project-relative paths the entry is about
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub files: Vec<String>,
+    pub text: String,
+}
+
+impl Entry {
+    pub fn new(kind: Kind, text: impl Into<String>) -> Self {
+        Self {
+            timestamp: Utc::now(),
+            kind,
+            task_id: None,
+            files: Vec::new(),
+            text: text.into(),
+        }
+    }
+
+    pub fn with_task(mut self, task_id: &str) -> Self {
+        self.task_id = Some(task_id.to_string());
+        self
+    }
+
+    pub fn with_files(mut self, files: &[String]) -> Self {
+        self.files = files.to_vec();
+        self
+    }
+
+    /// Same fact, whenever it was learned
+    fn same_as(&self, other: &Entry) -> bool {
+        self.kind == other.kind && self.text == other.text && self.files == other.files && self.task_id == other.task_id
+    }
+
+    /// Words and file stems, for matching against a task
+    fn keywords(&self) -> HashSet<String> {
+        let mut words = keywords(&self.text);
+        words.extend(self.files.iter().filter_map(|file| {
+            Path::new(file).file_stem().map(|stem| stem.to_string_lossy().to_lowercase())
+        }));
+        words
+    }
+
+    fn render(&self) -> String {
+        let files = self.files.iter().map(|f| format!("`{}`", f)).collect::<Vec<_>>().join(", ");
+        let first_line = self.text.lines().find(|l| !l.trim().is_empty()).unwrap_or_default().trim();
+        match self.kind {
+            Kind::Command => format!("- `{}`", self.text.trim()),
+            Kind::FileNote | Kind::Pitfall if files.is_empty() => format!("- {}", self.text.trim()),
+            Kind::FileNote => format!("- {}: {}", files, self.text.trim()),
+            Kind::Pitfall => format!("- {} ({})", self.text.trim(), files),
+            Kind::Outcome => {
+                let when = self.timestamp.with_timezone(&Local).format("%Y-%m-%d");
+                let task = self.task_id.as_deref().unwrap_or("?");
+                if files.is_empty() {
+                    format!("- {} {}: {}", when, task, first_line)
+                } else {
+                    format!("- {} {}: {} (files: {})", when, task, first_line, files)
+                }
+            }
+        }
+    }
+}
+
+/// What the current task is about
+#[derive(Debug, Clone, Default)]
+pub struct Query {
+    pub task_id: String,
+    pub files: Vec<String>,
+    pub keywords: HashSet<String>,
+}
+
+impl Query {
+    /// Files and keywords named in the task's title, description and criteria
+    pub fn for_task(parsed: &ParsedTask) -> Self {
+        let mut text = format!("{}\n{}", parsed.task.title, parsed.task.description);
+        for criterion in &parsed.acceptance_criteria {
+            text.push('\n');
+            text.push_str(&criterion.text);
+        }
+        Self {
+            task_id: parsed.task.id.clone(),
+            files: mentioned_files(&text),
+            keywords: keywords(&text),
+        }
+    }
+
+    /// How relevant an entry is; 0 means unrelated
+    fn score(&self, entry: &Entry) -> usize {
+        let mut score = 0;
+        if entry.task_id.as_deref() == Some(self.task_id.as_str()) {
+            score += 10;
+        }
+        let shared_files = entry
+            .files
+            .iter()
+            .filter(|file| self.files.iter().any(|wanted| same_file(file, wanted)))
+            .count();
+        score += 5 * shared_files;
+        let shared_words = entry.keywords().intersection(&self.keywords).count();
+        // A single shared word is noise
+        if shared_words >= 2 {
+            score += shared_words.min(5);
+        }
+        if score > 0 && entry.kind == Kind::Pitfall {
+            score += 1;
+        }
+        score
+    }
+}
+
+/// The project's append-only knowledge store
+#[derive(Debug, Clone)]
+pub struct KnowledgeStore {
+    root: PathBuf,
+    path: PathBuf,
+}
+
+impl KnowledgeStore {
+    pub fn new(project_root: &Path) -> Self {
+        Self {
+            root: project_root.to_path_buf(),
+            path: project_root.join(KNOWLEDGE_FILE),
+        }
+    }
+
+    /// Append an entry, unless the same fact is already known
+    pub fn record(&self, mut entry: Entry) -> Result<()> {
+        entry.files = entry.files.iter().map(|file| self.relative(file)).collect();
+        if self.entries()?.iter().any(|known| known.same_as(&entry)) {
+            return Ok(());
+        }
+        self.append(&[entry])
+    }
+
+    /// Every entry, oldest first, skipping lines that don't parse
+    ///
+    /// The first read of a project that only has a `progress.md` imports its
+    /// entries as outcomes.
+    pub fn entries(&self) -> Result<Vec<Entry>> {
+        if !self.path.exists() {
+            self.import_progress()?;
+        }
+        if !self.path.exists() {
+            return Ok(Vec::new());
+        }
+        let content = std::fs::read_to_string(&self.path)
+            .with_context(|| format!("Failed to read {}", self.path.display()))?;
+        Ok(content
+            .lines()
+            .filter_map(|line| serde_json::from_str(line).ok())
+            .collect())
+    }
+
+    /// The entries worth showing for a task, as prompt markdown; empty if none
+    pub fn relevant(&self, query: &Query) -> Result<String> {
+        let entries = self.entries()?;
+
+        let mut commands: Vec<&Entry> = Vec::new();
+        for entry in entries.iter().rev().filter(|e| e.kind == Kind::Command) {
+            if commands.len() < MAX_COMMANDS && !commands.iter().any(|c| c.text == entry.text) {
+                commands.push(entry);
+            }
+        }
+
+        // Best match first; newer wins a tie
+        let mut ranked: Vec<(usize, usize, &Entry)> = entries
+            .iter()
+            .enumerate()
+            .filter(|(_, e)| e.kind != Kind::Command)
+            .map(|(i, e)| (query.score(e), i, e))
+            .filter(|(score, _, _)| *score > 0)
+            .collect();
+        ranked.sort_by(|a, b| b.0.cmp(&a.0).then(b.1.cmp(&a.1)));
+        if !ranked.iter().any(|(_, _, e)| e.kind == Kind::Outcome) {
+            let recent = entries.iter().enumerate().rev().filter(|(_, e)| e.kind == Kind::Outcome);
+            ranked.extend(recent.take(RECENT_OUTCOMES).map(|(i, e)| (0, i, e)));
+        }
+
+        let mut budget = MAX_KNOWLEDGE_CHARS;
+        let mut take = |entry: &Entry| {
+            let line = entry.render();
+            if line.len() > budget {
+                return None;
+            }
+            budget -= line.len() + 1;
+            Some(line)
+        };
+        let commands: Vec<String> = commands.into_iter().filter_map(&mut take).collect();
+        let mut chosen: Vec<(Kind, String)> = Vec::new();
+        for (_, _, entry) in &ranked {
+            if let Some(line) = take(entry) {
+                chosen.push((entry.kind, line));
+            }
+        }
+        let omitted = ranked.len() - chosen.len();
+
+        let mut sections = Vec::new();
+        if !commands.is_empty() {
+            sections.push(format!("### Commands that work\n{}", commands.join("\n")));
+        }
+        for (kind, title) in [
+            (Kind::Pitfall, "### Pitfalls"),
+            (Kind::FileNote, "### File notes"),
+            (Kind::Outcome, "### Earlier tasks"),
+        ] {
+            let lines: Vec<&str> = chosen.iter().filter(|(k, _)| *k == kind).map(|(_, l)| l.as_str()).collect();
+            if !lines.is_empty() {
+                sections.push(format!("{}\n{}", title, lines.join("\n")));
+            }
+        }
+        if omitted > 0 {
+            sections.push(format!("[{} more related entries omitted]", omitted));
+        }
+        Ok(sections.join("\n\n"))
+    }
+
+    /// Take in what another store learned, e.g. a parallel worker's worktree
+    pub fn absorb(&self, other: &KnowledgeStore) -> Result<()> {
+        if !other.path.exists() {
+            return Ok(());
+        }
+        for entry in other.entries()? {
+            self.record(entry)?;
+        }
+        Ok(())
+    }
+
+    fn append(&self, entries: &[Entry]) -> Result<()> {
+        if let Some(parent) = self.path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let mut file = std::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)
+            .with_context(|| format!("Failed to open {}", self.path.display()))?;
+        for entry in entries {
+            writeln!(file, "{}", serde_json::to_string(entry)?)?;
+        }
+        Ok(())
+    }
+
+    /// Turn `## <time> - <task>` sections of progress.md into outcomes
+    fn import_progress(&self) -> Result<()> {
+        let legacy = self.root.join(LEGACY_PROGRESS_FILE);
+        let Ok(content) = std::fs::read_to_string(&legacy) else {
+            return Ok(());
+        };
+        let entries: Vec<Entry> = content.split("\n## ").filter_map(parse_progress_entry).collect();
+        if !entries.is_empty() {
+            tracing::info!("Imported {} entries from {}", entries.len(), legacy.display());
+            self.append(&entries)?;
+        }
+        Ok(())
+    }
+
+    fn relative(&self, file: &str) -> String {
+        let file = Path::new(file).strip_prefix(&self.root).map(Path::to_path_buf).unwrap_or_else(|_| file.into());
+        file.to_string_lossy().trim_start_matches("./").to_string()
+    }
+}
+
+/// Record how a task session ended
+pub fn record_outcome(project_root: &Path, task_id: &str, summary: &str, files_changed: &[String]) -> Result<()> {
+    KnowledgeStore::new(project_root).record(
+        Entry::new(Kind::Outcome, summary)
+            .with_task(task_id)
+            .with_files(files_changed),
+    )
+}
+
+/// Record the verification commands of a run that passed them
+pub fn record_verified(project_root: &Path, run: &VerifiedRun) -> Result<()> {
+    match &run.report {
+        Some(report) if report.passed() => {
+            let commands: Vec<String> = report.outcomes.iter().map(|o| o.command.clone()).collect();
+            record_commands(project_root, &commands)
+        }
+        _ => Ok(()),
+    }
+}
+
+/// Record commands that passed the verification gate
+pub fn record_commands(project_root: &Path, commands: &[String]) -> Result<()> {
+    let store = KnowledgeStore::new(project_root);
+    for command in commands {
+        store.record(Entry::new(Kind::Command, command.trim()))?;
+    }
+    Ok(())
+}
+
+fn parse_progress_entry(section: &str) -> Option<Entry> {
+    let section = section.trim_start_matches("## ").trim();
+    let (header, body) = section.split_once('\n')?;
+    let (when, task_id) = header.split_once(" - ")?;
+    let timestamp = NaiveDateTime::parse_from_str(when.trim(), "%Y-%m-%d %H:%M")
+        .ok()
+        .and_then(|t| Local.from_local_datetime(&t).single())
+        .map(|t| t.with_timezone(&Utc))?;
+
+    let mut files = Vec::new();
+    let mut summary = Vec::new();
+    for line in body.lines() {
+        match line.strip_prefix("**Files:**") {
+            Some(list) => files.extend(list.split(',').map(|f| f.trim().to_string()).filter(|f| !f.is_empty())),
+            None if line.trim() == "---" => {}
+            None => summary.push(line),
+        }
+    }
+    Some(Entry {
+        timestamp,
+        kind: Kind::Outcome,
+        task_id: Some(task_id.trim().to_string()),
+        files,
+        text: summary.join("\n").trim().to_string(),
+    })
+}
+
+/// Lowercase words of four letters or more, minus filler
+fn keywords(text: &str) -> HashSet<String> {
+    const FILLER: &[&str] = &[
+        "also", "each", "from", "have", "into", "just", "like", "make", "must", "need", "only", "should", "task",
+        "than", "that", "them", "then", "there", "these", "this", "when", "will", "with", "without",
+    ];
+    text.split(|c: char| !c.is_alphanumeric() && c != '_')
+        .filter(|word| word.len() >= 4)
+        .map(str::to_lowercase)
+        .filter(|word| !FILLER.contains(&word.as_str()))
+        .collect()
+}
+
+/// Tokens that look like paths: `src/main.rs`, `Cargo.toml`
+pub(crate) fn mentioned_files(text: &str) -> Vec<String> {
+    let mut files: Vec<String> = text
+        .split(|c: char| c.is_whitespace() || matches!(c, '`' | '"' | '\'' | '(' | ')' | ',' | ';' | '[' | ']'))
+        .map(|token| token.trim_end_matches([':', '.']).trim_start_matches("./"))
+        .filter(|token| {
+            let path = Path::new(token);
+            let stem_ok = path.file_stem().is_some_and(|s| s.len() >= 2);
+            let ext_ok = path
+                .extension()
+                .is_some_and(|e| (1..=5).contains(&e.len()) && e.to_string_lossy().chars().all(|c| c.is_ascii_alphabetic()));
+            stem_ok && ext_ok && !token.contains("://")
+        })
+        .map(str::to_string)
+        .collect();
+    files.sort();
+    files.dedup();
+    files
+}
+
+/// Equal, or one is a suffix path of the other (`main.rs`, `src/main.rs`)
+fn same_file(a: &str, b: &str) -> bool {
+    a == b || a.ends_with(&format!("/{}", b)) || b.ends_with(&format!("/{}", a))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::task_parser::{parse_task, BeadTask};
+    use tempfile::TempDir;
+
+    fn task(id: &str, title: &str, description: &str) -> ParsedTask {
+        parse_task(&BeadTask {
+            id: id.to_string(),
+            title: title.to_string(),
+            description: description.to_string(),
+            notes: String::new(),
+            status: "open".to_string(),
+            priority: 2,
+            issue_type: "task".to_string(),
+            owner: None,
+            labels: vec![],
+            depends_on: vec![],
+            blocks: vec![],
+        })
+    }
+
+    #[test]
+    fn test_relevant_entries_survive_growth() {
+        let temp = TempDir::new().unwrap();
+        let store = KnowledgeStore::new(temp.path());
+        let parser = temp.path().join("src/parser.rs").to_string_lossy().to_string();
+
+        store
+            .record(Entry::new(Kind::Pitfall, "Tokens are byte offsets, not char offsets").with_files(&[parser]))
+            .unwrap();
+        record_commands(temp.path(), &["cargo test --lib".to_string()]).unwrap();
+        // Lots of unrelated history after the early lesson
+        for i in 0..200 {
+            record_outcome(temp.path(), &format!("t-{}", i), "Styled the settings page", &[format!("web/page{}.css", i)])
+                .unwrap();
+        }
+        // Already known: not stored twice
+        record_commands(temp.path(), &["cargo test --lib".to_string()]).unwrap();
+        assert_eq!(store.entries().unwrap().len(), 202);
+
+        let query = Query::for_task(&task("t-300", "Fix unicode in the parser", "Offsets break in `src/parser.rs`."));
+        let prompt = store.relevant(&query).unwrap();
+        assert!(prompt.contains("### Commands that work\n- `cargo test --lib`"));
+        assert!(prompt.contains("- Tokens are byte offsets, not char offsets (`src/parser.rs`)"));
+        // Nothing earlier touched the parser, so the latest tasks stand in
+        assert!(prompt.contains("t-199: Styled the settings page"));
+        assert!(!prompt.contains("t-150:"));
+        assert!(prompt.len() <= MAX_KNOWLEDGE_CHARS + 200);
+    }
+
+    #[test]
+    fn test_imports_progress_md() {
+        let temp = TempDir::new().unwrap();
+        std::fs::create_dir_all(temp.path().join(".ralph")).unwrap();
+        std::fs::write(
+            temp.path().join(LEGACY_PROGRESS_FILE),
+            "\n## 2025-01-10 09:30 - task-001\n\nCompleted task: Add login\n**Files:** src/auth.rs, src/main.rs\n\n---\n\n\
+             \n## 2025-01-11 10:00 - task-002\n\nFixed bug Y\n\n---\n",
+        )
+        .unwrap();
+
+        let entries = KnowledgeStore::new(temp.path()).entries().unwrap();
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].task_id.as_deref(), Some("task-001"));
+        assert_eq!(entries[0].files, vec!["src/auth.rs", "src/main.rs"]);
+        assert_eq!(entries[0].text, "Completed task: Add login");
+        assert_eq!(entries[1].text, "Fixed bug Y");
+
+        // Imported once; the store is the source of truth from then on
+        let query = Query::for_task(&task("task-003", "Login rate limits", "Touches src/auth.rs"));
+        let prompt = KnowledgeStore::new(temp.path()).relevant(&query).unwrap();
+        assert!(prompt.contains("task-001: Completed task: Add login (files: `src/auth.rs`, `src/main.rs`)"));
+        assert!(!prompt.contains("task-002"));
+    }
+}
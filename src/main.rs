@@ -5,25 +5,44 @@
 //! - Geoffrey Huntley
 //!
 //! This harness:
-//! 1. Uses beads issue tracker to find the next task
+//! 1. Uses a tracker (beads by default, or specs / an issue export) to find the next task
 //! 2. Starts a fresh context for each task implementation
 //! 3. Uses the six primitives (read_file, list_files, bash, edit_file, code_search, beads)
 //! 4. Updates issue status when tasks complete
 //! 5. Auto-syncs beads after each successful implementation
 
+mod approval;
+mod backends;
+mod beads;
 mod claude_client;
+mod compaction;
+mod config;
+mod control;
+mod costs;
 mod decompose;
+mod events;
 mod git;
+mod knowledge;
+mod mcp;
+mod parallel;
 mod primitives;
 mod progress;
+mod repomap;
+mod sandbox;
 mod task_parser;
+mod tracker;
+mod transcript;
+mod triage;
 mod tui;
+mod verify;
 
-use anyhow::{Context, Result};
+use anyhow::Result;
 use clap::{Parser, Subcommand};
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::io::stdout;
+use std::sync::Arc;
 use tokio::sync::mpsc;
+use tracing_subscriber::fmt::writer::BoxMakeWriter;
 use tracing_subscriber::EnvFilter;
 use crossterm::{
     terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
@@ -31,10 +50,24 @@ use crossterm::{
 };
 use ratatui::prelude::*;
 
-use claude_client::{ClaudeClient, StopReason};
-use task_parser::{find_next_task, get_progress_summary, get_ready_tasks, get_task, parse_task, ParsedTask};
+use approval::{Approval, ApprovalPolicy, ChannelApprover, ConsoleApprover};
+use backends::replay::{self, Fixture};
+use backends::{BackendKind, BackendSpec, ModelBackend, RecordingBackend, ReplayBackend, RetryPolicy, RetryingBackend};
+use claude_client::{ClaudeClient, LoopResult, StopReason};
+use compaction::{CompactionMethod, Compactor};
+use config::{BackendConfig, ProjectConfig, VerifyConfig};
+use control::LoopControl;
+use costs::{Costs, GroupBy, Ledger};
+use events::{say, EventSender, Level, LoopEvent};
+use knowledge::{KnowledgeStore, Query};
+use mcp::McpTools;
+use sandbox::Sandbox;
+use task_parser::{parse_task, ParsedTask};
+use tracker::{Tracker, TrackerKind};
+use transcript::{ResumePoint, Transcript};
+use triage::{Action, Attempt, Failure, Triage};
 use tui::{App, EventHandler};
-use tui::app::{Task, TaskStatus, OutputLevel};
+use tui::app::{Task, TaskStatus};
 
 /// Result of running a single task
 #[derive(Debug, Clone, PartialEq)]
@@ -45,12 +78,155 @@ enum TaskResult {
     NeedsReboot { had_changes: bool },
     /// Hit max iterations without completing
     MaxIterations,
+    /// Model finished but verification still failed after the repair rounds
+    VerificationFailed,
+    /// Stopped because a budget is spent
+    BudgetExceeded,
+    /// Skipped from the TUI; the task is parked
+    Skipped,
+    /// Stopped from the TUI; the loop ends
+    Aborted,
+}
+
+/// Settings shared by every task run in a session
+#[derive(Clone)]
+struct RunSettings {
+    max_iterations: usize,
+    redline_threshold: u32,
+    auto_sync: bool,
+    backend: Arc<dyn ModelBackend>,
+    /// Where tasks come from (`--tracker`)
+    tracker: Arc<dyn Tracker>,
+    /// Compact instead of stopping at the redline (`loop_config.on_redline: compact`)
+    compactor: Option<Compactor>,
+    /// Verification gate (`verify` and `policies.deploy_requires_tests`)
+    verify: VerifyConfig,
+    require_tests: bool,
+    /// Consecutive verification failures that stop the loop (`stop_on: test_fail_streak`)
+    test_fail_streak: Option<u32>,
+    /// Human approval for `bash`/`edit_file` calls (`--attended`)
+    approval: Option<Approval>,
+    policy: ApprovalPolicy,
+    attended_by_default: bool,
+    /// Isolation for `bash` calls (`sandbox.backend`)
+    sandbox: Option<Sandbox>,
+    /// Tools from the project's MCP servers (`mcp.servers`); shared so each server starts once
+    mcp: Option<Arc<McpTools>>,
+    /// Spend ledger and budgets (`budget`)
+    costs: Costs,
+    /// Pause, steer, skip and abort from the TUI
+    control: Option<LoopControl>,
+    /// What happens to tasks that don't finish (`triage`)
+    triage: Arc<Triage>,
+    /// For creating a bigger model's backend when triage escalates
+    backend_config: BackendConfig,
+    api_retry: RetryPolicy,
+}
+
+/// Create the configured model backend, retrying failed calls
+fn model_backend(project_root: &Path, config: &ProjectConfig, spec: &BackendSpec) -> Result<Arc<dyn ModelBackend>> {
+    Ok(match spec.kind {
+        BackendKind::Replay => Arc::new(ReplayBackend::from_config(&config.backend)?.in_project(project_root)),
+        _ => Arc::new(RetryingBackend::new(
+            backends::create(spec, &config.backend)?,
+            RetryPolicy::from_config(&config.triage.api_retry),
+        )),
+    })
+}
+
+impl RunSettings {
+    /// Create the backend (and summarizer, if compacting) for a run
+    fn new(
+        project_root: &Path,
+        config: &ProjectConfig,
+        spec: &BackendSpec,
+        tracker: Arc<dyn Tracker>,
+        max_iterations: usize,
+        redline_threshold: u32,
+        no_sync: bool,
+    ) -> Result<Self> {
+        let api_retry = RetryPolicy::from_config(&config.triage.api_retry);
+        let backend = model_backend(project_root, config, spec)?;
+
+        let compactor = match Compactor::from_config(&config.loop_config) {
+            Some(compactor) if compactor.method() == CompactionMethod::Summarize => {
+                let summarizer = match &config.loop_config.compaction_model {
+                    Some(model) => {
+                        let spec = backends::resolve(None, Some(model), &config.backend)?;
+                        Arc::new(RetryingBackend::new(backends::create(&spec, &config.backend)?, api_retry.clone()))
+                    }
+                    None => backend.clone(),
+                };
+                Some(compactor.with_summarizer(summarizer))
+            }
+            other => other,
+        };
+
+        Ok(Self {
+            max_iterations,
+            redline_threshold,
+            auto_sync: !no_sync,
+            backend,
+            tracker,
+            compactor,
+            verify: config.verify.clone(),
+            require_tests: config.policies.deploy_requires_tests,
+            test_fail_streak: config.loop_config.test_fail_streak(),
+            approval: None,
+            policy: ApprovalPolicy::from_config(&config.policies, &config.mcp),
+            attended_by_default: config.policies.attended_by_default,
+            sandbox: Sandbox::from_config(&config.sandbox)?,
+            mcp: (!config.mcp.servers.is_empty()).then(|| Arc::new(McpTools::new(project_root, &config.mcp))),
+            costs: Costs::new(project_root, &config.budget)?,
+            control: None,
+            triage: Arc::new(Triage::new(project_root, &config.triage)),
+            backend_config: config.backend.clone(),
+            api_retry,
+        })
+    }
+
+    /// The same settings on another model (triage `escalate`)
+    fn escalated(&self, model: &str) -> Result<Self> {
+        let spec = backends::resolve(None, Some(model), &self.backend_config)?;
+        let backend = backends::create(&spec, &self.backend_config)?;
+        let mut settings = self.clone();
+        settings.backend = Arc::new(RetryingBackend::new(backend, self.api_retry.clone()));
+        Ok(settings)
+    }
+
+    /// Ask on the terminal before destructive tool calls, if attended
+    fn attended(self, attended: bool) -> Self {
+        let approver = Arc::new(ConsoleApprover);
+        self.with_approver(attended, approver)
+    }
+
+    fn with_approver(mut self, attended: bool, approver: Arc<dyn approval::Approver>) -> Self {
+        if attended || self.attended_by_default {
+            self.approval = Some(Approval::new(self.policy.clone(), approver));
+        }
+        self
+    }
+
+    /// Record every model call, the summarizer's included (`run --record`)
+    fn record(&mut self, project_root: &Path) -> Arc<RecordingBackend> {
+        let recorder = Arc::new(RecordingBackend::new(self.backend.clone(), project_root));
+        if let Some(compactor) = self.compactor.take() {
+            let shared = compactor.summarizer().is_some_and(|s| Arc::ptr_eq(s, &self.backend));
+            self.compactor = Some(if shared {
+                compactor.with_summarizer(recorder.clone())
+            } else {
+                compactor
+            });
+        }
+        self.backend = recorder.clone();
+        recorder
+    }
 }
 
 /// Ralph Wiggum Loop - Agentic coding harness
 #[derive(Parser)]
 #[command(name = "ralph")]
-#[command(about = "Agentic coding harness that implements tasks from beads issue tracker")]
+#[command(about = "Agentic coding harness that implements tasks from beads, specs or an issue export")]
 #[command(version)]
 struct Cli {
     #[command(subcommand)]
@@ -63,6 +239,26 @@ struct Cli {
     /// Enable verbose logging
     #[arg(short, long, global = true)]
     verbose: bool,
+
+    /// Model backend (defaults to backend.brain in .tachikoma/config.yaml, then anthropic)
+    #[arg(long, global = true, value_enum)]
+    backend: Option<BackendKind>,
+
+    /// Model name for the selected backend (e.g. claude-sonnet-4-20250514, gpt-4o, qwen2.5-coder)
+    #[arg(long, global = true)]
+    model: Option<String>,
+
+    /// Where tasks come from
+    #[arg(long, global = true, value_enum, default_value = "beads")]
+    tracker: TrackerKind,
+
+    /// Specs directory for `--tracker specs` (default: specs), export file for `--tracker issues`
+    #[arg(long, global = true)]
+    tracker_path: Option<PathBuf>,
+
+    /// Write loop events to stdout as NDJSON (run, loop, resume); everything else goes to stderr
+    #[arg(long, global = true)]
+    events_json: bool,
 }
 
 #[derive(Subcommand)]
@@ -88,6 +284,18 @@ enum Commands {
         /// Auto-decompose large tasks before running
         #[arg(long)]
         auto_decompose: bool,
+
+        /// Ask before each bash command or file edit (safe commands are auto-approved)
+        #[arg(long)]
+        attended: bool,
+
+        /// Save every model call and the final file tree to a fixture
+        #[arg(long, value_name = "FIXTURE", conflicts_with = "replay")]
+        record: Option<PathBuf>,
+
+        /// Serve model replies from a recorded fixture and compare the final file tree
+        #[arg(long, value_name = "FIXTURE")]
+        replay: Option<PathBuf>,
     },
 
     /// Run the Ralph loop continuously until all tasks complete
@@ -115,6 +323,40 @@ enum Commands {
         /// Auto-decompose large tasks before running
         #[arg(long)]
         auto_decompose: bool,
+
+        /// Run up to N independent tasks at once, each in its own git worktree
+        #[arg(long, default_value = "1")]
+        parallel: usize,
+
+        /// Ask before each bash command or file edit (safe commands are auto-approved)
+        #[arg(long)]
+        attended: bool,
+    },
+
+    /// Resume a task from its transcript (.ralph/transcripts/<task-id>.jsonl)
+    Resume {
+        /// Task ID to resume
+        issue: String,
+
+        /// Compact earlier iterations into a summary (recommended after a redline stop)
+        #[arg(long)]
+        compact: bool,
+
+        /// Maximum additional iterations (default: 50)
+        #[arg(short, long, default_value = "50")]
+        max_iterations: usize,
+
+        /// Token limit before forcing fresh context (default: 150000)
+        #[arg(long, default_value = "150000")]
+        redline: u32,
+
+        /// Skip auto-sync after completion
+        #[arg(long)]
+        no_sync: bool,
+
+        /// Ask before each bash command or file edit (implied if the task's last session was attended)
+        #[arg(long)]
+        attended: bool,
     },
 
     /// Show current progress
@@ -136,12 +378,27 @@ enum Commands {
         issue: String,
     },
 
+    /// Report spend from the cost ledger (.ralph/costs.jsonl)
+    Costs {
+        /// Break spend down by task, run, model or day
+        #[arg(long, value_enum, default_value = "day")]
+        by: GroupBy,
+
+        /// Only count sessions from this date on (YYYY-MM-DD)
+        #[arg(long)]
+        since: Option<chrono::NaiveDate>,
+    },
+
     /// Analyze and decompose large tasks into smaller subtasks
     Decompose {
         /// Only analyze without creating subtasks (dry run)
         #[arg(long)]
         dry_run: bool,
 
+        /// Ask for subtasks and print the plan, without creating anything
+        #[arg(long)]
+        plan_only: bool,
+
         /// Decompose a specific task by ID
         #[arg(short, long)]
         issue: Option<String>,
@@ -168,6 +425,10 @@ enum Commands {
         /// Skip auto-sync
         #[arg(long)]
         no_sync: bool,
+
+        /// Ask before each bash command or file edit (safe commands are auto-approved)
+        #[arg(long)]
+        attended: bool,
     },
 }
 
@@ -182,9 +443,17 @@ async fn main() -> Result<()> {
         EnvFilter::new("ralph=info")
     };
 
+    // With --events-json, stdout is only events
+    let log_writer = if cli.events_json {
+        events::use_json_stdout();
+        BoxMakeWriter::new(std::io::stderr)
+    } else {
+        BoxMakeWriter::new(std::io::stdout)
+    };
     tracing_subscriber::fmt()
         .with_env_filter(filter)
         .with_target(false)
+        .with_writer(log_writer)
         .init();
 
     // Determine project root
@@ -192,14 +461,34 @@ async fn main() -> Result<()> {
         .project
         .unwrap_or_else(|| std::env::current_dir().expect("Failed to get current directory"));
 
-    let beads_dir = project_root.join(".beads");
+    let tracker = tracker::create(cli.tracker, &project_root, cli.tracker_path.as_deref())?;
 
-    if !beads_dir.exists() {
-        anyhow::bail!(
-            ".beads/ directory not found at {}. Is this a beads-tracked project?\nRun 'bd init' to initialize beads.",
-            project_root.display()
-        );
+    // Decomposition creates beads subtasks
+    let decomposes = matches!(
+        cli.command,
+        Commands::Decompose { .. }
+            | Commands::Run { auto_decompose: true, .. }
+            | Commands::Loop { auto_decompose: true, .. }
+    );
+    if cli.events_json && matches!(cli.command, Commands::Tui { .. }) {
+        anyhow::bail!("--events-json is for run, loop and resume; the TUI already shows the events");
     }
+    if decomposes && cli.tracker != TrackerKind::Beads {
+        anyhow::bail!("Task decomposition needs the beads tracker (got --tracker {})", tracker.name());
+    }
+
+    let mut project_config = config::load(&project_root)?;
+
+    // `run --replay` serves a fixture instead of calling a model
+    let cli_backend = match &cli.command {
+        Commands::Run { replay: Some(fixture), .. } => {
+            let fixture = fixture.display().to_string();
+            project_config.backend.endpoints.insert("replay".to_string(), fixture);
+            Some(BackendKind::Replay)
+        }
+        _ => cli.backend,
+    };
+    let backend_spec = backends::resolve(cli_backend, cli.model.as_deref(), &project_config.backend)?;
 
     match cli.command {
         Commands::Run {
@@ -208,14 +497,29 @@ async fn main() -> Result<()> {
             redline,
             no_sync,
             auto_decompose,
+            attended,
+            record,
+            replay,
         } => {
+            let mut settings =
+                RunSettings::new(&project_root, &project_config, &backend_spec, tracker, max_iterations, redline, no_sync)?
+                    .attended(attended);
             // Auto-decompose if requested
             if auto_decompose {
-                let api_key = std::env::var("ANTHROPIC_API_KEY")
-                    .context("ANTHROPIC_API_KEY environment variable not set")?;
-                decompose::preprocess_tasks(&project_root, &api_key).await?;
+                decompose::preprocess_tasks(&project_root, settings.backend.as_ref(), &settings.costs, false).await?;
+            }
+            let recorder = record.as_ref().map(|_| settings.record(&project_root));
+            run_single(&project_root, issue.as_deref(), &settings, None).await?;
+
+            if let (Some(path), Some(recorder)) = (&record, recorder) {
+                let mut fixture = recorder.fixture();
+                fixture.final_tree = replay::snapshot(&project_root)?;
+                fixture.save(path)?;
+                say!("Recorded {} model call(s) to {}", fixture.exchanges.len(), path.display());
+            }
+            if let Some(path) = &replay {
+                check_replay(&project_root, path)?;
             }
-            run_single(&project_root, issue.as_deref(), max_iterations, redline, !no_sync).await?;
         }
         Commands::Loop {
             max_iterations,
@@ -224,29 +528,58 @@ async fn main() -> Result<()> {
             fail_streak,
             no_sync,
             auto_decompose,
+            parallel,
+            attended,
         } => {
+            let settings =
+                RunSettings::new(&project_root, &project_config, &backend_spec, tracker, max_iterations, redline, no_sync)?
+                    .attended(attended);
             // Auto-decompose if requested
             if auto_decompose {
-                let api_key = std::env::var("ANTHROPIC_API_KEY")
-                    .context("ANTHROPIC_API_KEY environment variable not set")?;
-                decompose::preprocess_tasks(&project_root, &api_key).await?;
+                decompose::preprocess_tasks(&project_root, settings.backend.as_ref(), &settings.costs, false).await?;
+            }
+            if parallel > 1 && settings.approval.is_some() {
+                anyhow::bail!("Attended mode runs one task at a time; drop --parallel or --attended");
+            }
+            if parallel > 1 {
+                parallel::run_parallel(&project_root, &settings, parallel, max_tasks, fail_streak)
+                    .await?;
+            } else {
+                run_loop(&project_root, &settings, max_tasks, fail_streak).await?;
             }
-            run_loop(&project_root, max_iterations, redline, max_tasks, fail_streak, !no_sync).await?;
+        }
+        Commands::Resume {
+            issue,
+            compact,
+            max_iterations,
+            redline,
+            no_sync,
+            attended,
+        } => {
+            let point = transcript::load(&project_root, &issue)?;
+            let settings =
+                RunSettings::new(&project_root, &project_config, &backend_spec, tracker, max_iterations, redline, no_sync)?
+                    .attended(attended || point.attended);
+            resume_task(&project_root, &issue, point, compact, &settings).await?;
         }
         Commands::Status => {
-            show_status(&project_root)?;
+            show_status(tracker.as_ref())?;
         }
         Commands::List { all } => {
-            list_tasks(&project_root, all)?;
+            list_tasks(tracker.as_ref(), all)?;
         }
         Commands::Next => {
-            show_next(&project_root)?;
+            show_next(tracker.as_ref())?;
         }
         Commands::Show { issue } => {
-            show_task(&project_root, &issue)?;
+            let triage = Triage::new(&project_root, &project_config.triage);
+            show_task(tracker.as_ref(), &triage, &issue)?;
         }
-        Commands::Decompose { dry_run, issue } => {
-            decompose_command(&project_root, dry_run, issue.as_deref()).await?;
+        Commands::Costs { by, since } => {
+            show_costs(&project_root, &project_config, by, since)?;
+        }
+        Commands::Decompose { dry_run, plan_only, issue } => {
+            decompose_command(&project_root, &project_config, &backend_spec, dry_run, plan_only, issue.as_deref()).await?;
         }
         Commands::Tui {
             max_iterations,
@@ -254,8 +587,11 @@ async fn main() -> Result<()> {
             max_tasks,
             fail_streak,
             no_sync,
+            attended,
         } => {
-            run_tui(&project_root, max_iterations, redline, max_tasks, fail_streak, !no_sync).await?;
+            let settings =
+                RunSettings::new(&project_root, &project_config, &backend_spec, tracker, max_iterations, redline, no_sync)?;
+            run_tui(&project_root, settings, max_tasks, fail_streak, attended).await?;
         }
     }
 
@@ -263,90 +599,169 @@ async fn main() -> Result<()> {
 }
 
 /// Run the Ralph loop once for a single task
+///
+/// With `resume`, the loop continues from a transcript instead of starting
+/// from the task prompt.
 async fn run_single(
     project_root: &PathBuf,
     task_id: Option<&str>,
-    max_iterations: usize,
-    redline_threshold: u32,
-    auto_sync: bool,
+    settings: &RunSettings,
+    resume: Option<ResumePoint>,
 ) -> Result<TaskResult> {
+    let tracker = &settings.tracker;
+
     // Find the task to implement
     let parsed = if let Some(id) = task_id {
         // Find specific task
-        let task = get_task(project_root, id)?;
+        let task = tracker.get_task(id)?;
         parse_task(&task)
     } else {
         // Find next ready task
-        find_next_task(project_root)?.ok_or_else(|| anyhow::anyhow!("No ready tasks found! Run 'bd ready' to check."))?
+        tracker
+            .next_task()?
+            .ok_or_else(|| anyhow::anyhow!("No ready tasks found! Run 'ralph list' to check."))?
     };
 
     // Mark task as in_progress
-    task_parser::update_task_status(project_root, &parsed.task.id, "in_progress")?;
+    tracker.start_task(&parsed.task.id)?;
 
-    println!("\n========================================");
-    println!("  RALPH LOOP - Task: {}", parsed.task.id);
-    println!("  {}", parsed.task.title);
-    println!("  Priority: P{} | Type: {}", parsed.task.priority, parsed.task.issue_type);
-    println!("========================================\n");
+    say!("\n========================================");
+    say!("  RALPH LOOP - Task: {}", parsed.task.id);
+    say!("  {}", parsed.task.title);
+    say!("  Priority: P{} | Type: {}", parsed.task.priority, parsed.task.issue_type);
+    say!("========================================\n");
 
     // Show acceptance criteria if any
     if !parsed.acceptance_criteria.is_empty() {
-        println!("Acceptance Criteria:");
+        say!("Acceptance Criteria:");
         for ac in &parsed.acceptance_criteria {
             let mark = if ac.completed { "x" } else { " " };
-            println!("  [{}] {}", mark, ac.text);
+            say!("  [{}] {}", mark, ac.text);
         }
-        println!();
+        say!();
     }
 
-    // Get API key
-    let api_key = std::env::var("ANTHROPIC_API_KEY")
-        .context("ANTHROPIC_API_KEY environment variable not set")?;
-
     // Build the system prompt
-    let system_prompt = build_system_prompt(project_root);
+    let system_prompt = build_system_prompt(project_root, &KnowledgeStore::new(project_root), &parsed).await;
 
     // Build the task prompt
     let task_prompt = build_task_prompt(&parsed);
 
-    // Create output channel for streaming
-    let (tx, mut rx) = mpsc::channel::<String>(100);
+    // Create event channel for streaming
+    let (tx, rx) = mpsc::channel::<LoopEvent>(100);
 
-    // Spawn a task to print output
-    let output_handle = tokio::spawn(async move {
-        while let Some(text) = rx.recv().await {
-            print!("{}", text);
-            use std::io::Write;
-            let _ = std::io::stdout().flush();
-        }
-    });
+    // Spawn a task to print events
+    let output_handle = tokio::spawn(events::print(parsed.task.id.clone(), rx));
 
-    // Run the agentic loop
-    let client = ClaudeClient::new(api_key, project_root);
+    // Verification gate, if configured
+    let verifier = verify::Verifier::for_task(&settings.verify, settings.require_tests, project_root, &parsed, settings.sandbox.as_ref())?;
+    if let Some(verifier) = &verifier {
+        say!("Verification: {}", verifier.commands().join(" && "));
+    }
+    if let Some(sandbox) = &settings.sandbox {
+        say!("Sandbox: {}", sandbox.describe());
+    }
 
-    println!("Starting agentic loop (max {} iterations)...\n", max_iterations);
+    // Run the agentic loop
+    let transcript = Transcript::new(project_root, &parsed.task.id);
+    say!("Transcript: {}", transcript.path().display());
+    let client = ClaudeClient::new(settings.backend.clone(), project_root)
+        .with_transcript(transcript)
+        .with_compaction(settings.compactor.clone())
+        .with_approval(settings.approval.clone())
+        .with_sandbox(settings.sandbox.clone())
+        .with_mcp(settings.mcp.clone())
+        .with_costs(settings.costs.meter(&parsed.task.id))
+        .with_control(settings.control.clone())
+        .defer_task_close(harness_closes(settings, verifier.is_some()));
+
+    say!(
+        "Starting agentic loop on {} ({}) (max {} iterations)...\n",
+        settings.backend.name(),
+        settings.backend.model(),
+        settings.max_iterations
+    );
 
-    let result = client
-        .run_agentic_loop(&system_prompt, &task_prompt, max_iterations, redline_threshold, Some(tx))
-        .await?;
+    let start = resume.unwrap_or_else(|| ResumePoint::fresh(&task_prompt));
+    let run = verify::run_verified(
+        &client,
+        verifier.as_ref(),
+        &system_prompt,
+        start,
+        settings.max_iterations,
+        settings.redline_threshold,
+        Some(tx),
+    )
+    .await?;
+    let result = &run.result;
+    if let Err(e) = knowledge::record_verified(project_root, &run) {
+        tracing::warn!("Failed to record verification commands: {}", e);
+    }
 
     // Wait for output to finish
     output_handle.await?;
 
-    println!("\n\n========================================");
-    println!("  LOOP COMPLETE");
-    println!("========================================");
-    println!("  Iterations: {}", result.iterations);
-    println!("  Input tokens: {}", result.total_input_tokens);
-    println!("  Output tokens: {}", result.total_output_tokens);
-    println!("  Total tokens: {}", result.total_tokens());
-    println!("  Estimated cost: ${:.4}", result.estimated_cost());
-    println!("  Stop reason: {:?}", result.stop_reason);
-    println!("========================================\n");
+    say!("\n\n========================================");
+    say!("  LOOP COMPLETE");
+    say!("========================================");
+    say!("  Iterations: {}", result.iterations);
+    say!("  Input tokens: {}", result.total_input_tokens);
+    say!("  Output tokens: {}", result.total_output_tokens);
+    say!("  Total tokens: {}", result.total_tokens());
+    say!("  Estimated cost: ${:.4}", result.cost_usd);
+    if result.compactions > 0 {
+        say!("  Compactions: {}", result.compactions);
+    }
+    if let Some(report) = &run.report {
+        say!("  Verification: {}", report.summary());
+        say!("  Repair rounds: {}", run.repair_rounds);
+    }
+    say!("  Stop reason: {:?}", result.stop_reason);
+    say!("========================================\n");
+
+    finish_task(project_root, settings, &parsed, &run, None).await
+}
+
+/// Act on how a run stopped: close, commit, block or leave the task
+///
+/// Progress goes to `events` when the TUI is watching, otherwise to the
+/// terminal.
+async fn finish_task(
+    project_root: &Path,
+    settings: &RunSettings,
+    parsed: &ParsedTask,
+    run: &verify::VerifiedRun,
+    events: Option<&EventSender>,
+) -> Result<TaskResult> {
+    let report = |level: Level, text: String| async move {
+        match events {
+            Some(events) => events::status(events, level, text).await,
+            None => say!("{}", text),
+        }
+    };
+    let result = &run.result;
+    let committed = |hash: String| report(Level::Info, format!("Committed partial progress as {}", hash));
 
-    // Determine task result based on stop reason
     let task_result = match result.stop_reason {
+        StopReason::Completed if run.verification_failed() => {
+            report(
+                Level::Error,
+                format!("❌ VERIFICATION FAILED after {} repair round(s). Task left open.", run.repair_rounds),
+            )
+            .await;
+
+            if let Some(hash) = finish_incomplete(project_root, settings, parsed, result, "verification failed, needs review")? {
+                committed(hash).await;
+            }
+            TaskResult::VerificationFailed
+        }
         StopReason::Completed => {
+            // Verified (or the model can't reach the tracker): the harness closes the task
+            if should_close(settings, run) {
+                settings.tracker.complete_task(&parsed.task.id, Some(&run.close_reason()))?;
+            }
+            clear_attempts(settings, &parsed.task.id);
+
             // Record progress for future iterations
             let modified_files = progress::extract_modified_files(&result.tool_outputs);
             if !modified_files.is_empty() {
@@ -359,7 +774,7 @@ async fn run_single(
                         .collect::<Vec<_>>()
                         .join("\n")
                 );
-                if let Err(e) = progress::append_progress(
+                if let Err(e) = knowledge::record_outcome(
                     project_root,
                     &parsed.task.id,
                     &summary,
@@ -370,186 +785,363 @@ async fn run_single(
             }
             
             // Auto-sync if enabled
-            if auto_sync {
-                task_parser::sync_beads(project_root)?;
+            if settings.auto_sync {
+                settings.tracker.sync()?;
                 if let Some(hash) = git::auto_commit_task(project_root, &parsed.task.id, &parsed.task.title)? {
-                    println!("Committed changes as {}", hash);
+                    report(Level::Info, format!("Committed changes as {}", hash)).await;
                 }
             }
             TaskResult::Completed
         }
         StopReason::Redline => {
-            println!("⚠️  REDLINE: Token limit exceeded. Will retry with fresh context.");
-            
-            // Record partial progress for next iteration
-            let modified_files = progress::extract_modified_files(&result.tool_outputs);
-            if !modified_files.is_empty() {
-                let summary = format!(
-                    "Partial progress on: {} (hit redline, will continue)\n\nModified {} file(s)",
-                    parsed.task.title,
-                    modified_files.len()
-                );
-                let _ = progress::append_progress(
-                    project_root,
-                    &parsed.task.id,
-                    &summary,
-                    &modified_files,
-                );
-            }
+            report(Level::Warning, "⚠️  REDLINE: Token limit exceeded. Will retry with fresh context.".to_string()).await;
             
-            // Still sync any progress made
-            let had_changes = if auto_sync {
-                task_parser::sync_beads(project_root)?;
-                if let Some(hash) = git::auto_commit_task(project_root, &parsed.task.id, &parsed.task.title)? {
-                    println!("Committed partial progress as {}", hash);
+            let had_changes = match finish_incomplete(project_root, settings, parsed, result, "hit redline, will continue")? {
+                Some(hash) => {
+                    committed(hash).await;
                     true
-                } else {
-                    println!("No changes to commit.");
+                }
+                None if settings.auto_sync => {
+                    report(Level::Info, "No changes to commit.".to_string()).await;
                     false
                 }
-            } else {
-                !modified_files.is_empty()
+                None => !progress::extract_modified_files(&result.tool_outputs).is_empty(),
             };
             TaskResult::NeedsReboot { had_changes }
         }
         StopReason::MaxIterations => {
-            println!("⚠️  Max iterations reached without completing.");
+            report(Level::Warning, "⚠️  Max iterations reached without completing.".to_string()).await;
             
-            // Record partial progress
-            let modified_files = progress::extract_modified_files(&result.tool_outputs);
-            if !modified_files.is_empty() {
-                let summary = format!(
-                    "Partial progress on: {} (max iterations, needs review)\n\nModified {} file(s)",
-                    parsed.task.title,
-                    modified_files.len()
-                );
-                let _ = progress::append_progress(
-                    project_root,
-                    &parsed.task.id,
-                    &summary,
-                    &modified_files,
-                );
-            }
-            
-            if auto_sync {
-                task_parser::sync_beads(project_root)?;
-                if let Some(hash) = git::auto_commit_task(project_root, &parsed.task.id, &parsed.task.title)? {
-                    println!("Committed partial progress as {}", hash);
-                }
+            if let Some(hash) = finish_incomplete(project_root, settings, parsed, result, "max iterations, needs review")? {
+                committed(hash).await;
             }
             TaskResult::MaxIterations
         }
+        StopReason::BudgetExceeded => {
+            report(
+                Level::Error,
+                format!("💸 BUDGET EXCEEDED: {}. Task left open.", budget_stopped(settings, &parsed.task.id)),
+            )
+            .await;
+
+            if let Some(hash) = finish_incomplete(project_root, settings, parsed, result, "budget spent, needs review")? {
+                committed(hash).await;
+            }
+            TaskResult::BudgetExceeded
+        }
+        StopReason::Skipped | StopReason::Aborted => {
+            report(Level::Warning, format!("⏹  Stopped by the user ({:?}).", result.stop_reason)).await;
+
+            if let Some(hash) = finish_incomplete(project_root, settings, parsed, result, "stopped by the user")? {
+                committed(hash).await;
+            }
+            user_stopped(settings, &parsed.task.id, &result.stop_reason)
+        }
     };
 
     Ok(task_result)
 }
 
+/// Record what an unfinished run changed and, with auto-sync, commit it
+///
+/// `reason` says why the run stopped ("hit redline, will continue") and
+/// goes into the knowledge store summary. Returns the commit, if one was
+/// made.
+fn finish_incomplete(
+    project_root: &Path,
+    settings: &RunSettings,
+    parsed: &ParsedTask,
+    result: &LoopResult,
+    reason: &str,
+) -> Result<Option<String>> {
+    let modified_files = progress::extract_modified_files(&result.tool_outputs);
+    if !modified_files.is_empty() {
+        let summary = format!(
+            "Partial progress on: {} ({})\n\nModified {} file(s)",
+            parsed.task.title,
+            reason,
+            modified_files.len()
+        );
+        if let Err(e) = knowledge::record_outcome(project_root, &parsed.task.id, &summary, &modified_files) {
+            tracing::warn!("Failed to record progress: {}", e);
+        }
+    }
+
+    if !settings.auto_sync {
+        return Ok(None);
+    }
+    settings.tracker.sync()?;
+    git::auto_commit_task(project_root, &parsed.task.id, &parsed.task.title)
+}
+
+/// Describe the budget that stopped a task
+///
+/// A spent task budget also blocks the task, so the loop doesn't pick it
+/// straight back up; raising `budget.per_task_usd` and reopening it resumes
+/// work. Run and daily budgets stop the loop instead.
+fn budget_stopped(settings: &RunSettings, task_id: &str) -> String {
+    match settings.costs.exceeded(Some(task_id)) {
+        Some(exceeded) if !exceeded.stops_loop() => {
+            if let Err(e) = settings.tracker.block_task(task_id) {
+                tracing::warn!("Failed to mark {} blocked: {}", task_id, e);
+            }
+            format!("{} (task blocked)", exceeded)
+        }
+        Some(exceeded) => exceeded.to_string(),
+        None => "budget spent".to_string(),
+    }
+}
+
+/// What a skip or abort from the TUI does to the task
+///
+/// A skipped task is blocked so the loop moves on to the next one; an
+/// aborted one is left in progress for `ralph resume`.
+fn user_stopped(settings: &RunSettings, task_id: &str, reason: &StopReason) -> TaskResult {
+    if *reason != StopReason::Skipped {
+        return TaskResult::Aborted;
+    }
+    if let Err(e) = settings.tracker.block_task(task_id) {
+        tracing::warn!("Failed to mark {} blocked: {}", task_id, e);
+    }
+    TaskResult::Skipped
+}
+
+/// Triage detail for a run that stopped at the redline
+fn rebooted(had_changes: bool) -> String {
+    let detail = if had_changes { "partial progress committed" } else { "no changes" };
+    detail.to_string()
+}
+
+/// Triage a run that didn't finish its task
+///
+/// Records the attempt (in the log and on the task), then acts on it.
+/// Returns the settings for another run, or `None` once the loop should
+/// move on: the task was decomposed or blocked. A decompose or escalate
+/// that can't be done blocks the task instead.
+async fn triage_task(
+    project_root: &Path,
+    settings: &RunSettings,
+    parsed: &ParsedTask,
+    failure: Failure,
+    detail: &str,
+) -> Result<(Attempt, Option<RunSettings>)> {
+    let task_id = &parsed.task.id;
+    let attempt = settings.triage.decide(task_id, failure, settings.backend.model(), detail)?;
+
+    let next = match &attempt.action {
+        Action::Retry => Ok(Some(settings.clone())),
+        Action::Escalate(model) => settings.escalated(model).map(Some),
+        Action::Decompose => decompose_stuck(project_root, settings, parsed, &attempt).await.map(|_| None),
+        Action::Block => settings.tracker.block_task(task_id).map(|_| None),
+    };
+    let (attempt, next) = match next {
+        Ok(next) => (attempt, next),
+        Err(e) => {
+            let blocked = settings.triage.blocked_instead(&attempt, &e.to_string())?;
+            settings.tracker.block_task(task_id)?;
+            (blocked, None)
+        }
+    };
+
+    if let Err(e) = settings.tracker.note_task(task_id, &attempt.note()) {
+        tracing::warn!("Failed to add the triage note to {}: {}", task_id, e);
+    }
+    Ok((attempt, next))
+}
+
+/// A completed task starts its triage count again if it is ever reopened
+fn clear_attempts(settings: &RunSettings, task_id: &str) {
+    if let Err(e) = settings.triage.completed(task_id) {
+        tracing::warn!("Failed to clear the attempt history of {}: {}", task_id, e);
+    }
+}
+
+/// Split a task that keeps failing into subtasks; the task then waits on them
+async fn decompose_stuck(project_root: &Path, settings: &RunSettings, parsed: &ParsedTask, attempt: &Attempt) -> Result<()> {
+    if settings.tracker.name() != "beads" {
+        anyhow::bail!("decompose needs the beads tracker");
+    }
+    let reason = format!("{} attempt(s) so far; the last stopped with {}", attempt.attempt, attempt.failure);
+    let meter = settings.costs.meter(&parsed.task.id);
+    decompose::decompose_stuck(project_root, parsed, settings.backend.as_ref(), &meter, &reason).await?;
+    if settings.auto_sync {
+        settings.tracker.sync()?;
+    }
+    Ok(())
+}
+
+/// Compare the project with a fixture's recorded file tree after `run --replay`
+fn check_replay(project_root: &Path, fixture: &Path) -> Result<()> {
+    let diffs = Fixture::load(fixture)?.diff_tree(project_root)?;
+    if diffs.is_empty() {
+        say!("Replay matches the file tree recorded in {}", fixture.display());
+        return Ok(());
+    }
+    say!("Replay differs from the file tree recorded in {}:", fixture.display());
+    for diff in &diffs {
+        say!("  {}", diff);
+    }
+    anyhow::bail!("{} file(s) differ from the recording", diffs.len())
+}
+
+/// Whether the harness, rather than the model, closes the task
+///
+/// True behind a verification gate, and for trackers the model's `beads`
+/// tool can't reach.
+fn harness_closes(settings: &RunSettings, verifying: bool) -> bool {
+    verifying || !settings.tracker.model_closes_tasks()
+}
+
+/// Whether a completed run should close its task
+///
+/// A verified run always does. Without verification the harness only acts
+/// on a deferred close, so ending the turn early doesn't complete the task.
+fn should_close(settings: &RunSettings, run: &verify::VerifiedRun) -> bool {
+    run.report.is_some() || (harness_closes(settings, false) && run.result.close_reason.is_some())
+}
+
 /// Run the Ralph loop continuously
 async fn run_loop(
     project_root: &PathBuf,
-    max_iterations: usize,
-    redline_threshold: u32,
+    settings: &RunSettings,
     max_tasks: Option<usize>,
     fail_streak_limit: usize,
-    auto_sync: bool,
 ) -> Result<()> {
     let mut tasks_completed = 0;
     let mut consecutive_failures = 0;
-    let mut reboot_count = 0;
-    let mut no_changes_count = 0;
-    const MAX_REBOOTS_PER_TASK: usize = 3;
-
-    println!("\n========================================");
-    println!("  RALPH LOOP - CONTINUOUS MODE");
-    println!("  Max iterations per task: {}", max_iterations);
-    println!("  Redline threshold: {} tokens", redline_threshold);
-    println!("  Fail streak limit: {}", fail_streak_limit);
-    println!("========================================\n");
+    // Consecutive tasks that failed verification (`stop_on: test_fail_streak`)
+    let mut test_failures = 0u32;
+
+    say!("\n========================================");
+    say!("  RALPH LOOP - CONTINUOUS MODE");
+    say!("  Backend: {} ({})", settings.backend.name(), settings.backend.model());
+    say!("  Max iterations per task: {}", settings.max_iterations);
+    say!("  Redline threshold: {} tokens", settings.redline_threshold);
+    if let Some(compactor) = &settings.compactor {
+        say!("  On redline: compact ({:?})", compactor.method());
+    }
+    say!("  Fail streak limit: {}", fail_streak_limit);
+    say!("========================================\n");
 
     loop {
         // Check if we've hit max tasks
         if let Some(max) = max_tasks {
             if tasks_completed >= max {
-                println!("Reached max tasks limit ({})", max);
+                say!("Reached max tasks limit ({})", max);
                 break;
             }
         }
 
+        if let Some(exceeded) = settings.costs.exceeded(None) {
+            say!("\nStopping: {}", exceeded);
+            break;
+        }
+
         // Find next task
-        let parsed = match find_next_task(project_root)? {
+        let parsed = match settings.tracker.next_task()? {
             Some(t) => t,
             None => {
-                println!("\nNo more ready tasks!");
+                say!("\nNo more ready tasks!");
                 break;
             }
         };
 
-        println!("\n--- Starting Task: {} ---", parsed.task.id);
-        println!("    {}\n", parsed.task.title);
-        reboot_count = 0;
-        no_changes_count = 0;
+        say!("\n--- Starting Task: {} ---", parsed.task.id);
+        say!("    {}\n", parsed.task.title);
+        let mut task_settings = settings.clone();
+        let mut aborted = false;
 
-        // Run for this task (with reboot support)
+        // Run for this task until it completes or triage moves on
         loop {
-            match run_single(project_root, Some(&parsed.task.id), max_iterations, redline_threshold, auto_sync).await {
+            let (failure, detail) = match run_single(project_root, Some(&parsed.task.id), &task_settings, None).await {
                 Ok(TaskResult::Completed) => {
                     tasks_completed += 1;
                     consecutive_failures = 0;
-                    println!("\n✅ Task {} completed successfully!", parsed.task.id);
+                    test_failures = 0;
+                    say!("\n✅ Task {} completed successfully!", parsed.task.id);
                     break;
                 }
                 Ok(TaskResult::NeedsReboot { had_changes }) => {
-                    reboot_count += 1;
-                    if !had_changes {
-                        no_changes_count += 1;
-                    } else {
-                        no_changes_count = 0;
-                    }
-
                     // Check if task was actually completed during this run
-                    if let Ok(Some(refreshed)) = find_next_task(project_root) {
+                    if let Ok(Some(refreshed)) = settings.tracker.next_task() {
                         if refreshed.task.id != parsed.task.id {
                             // Task was completed! Move on.
                             tasks_completed += 1;
                             consecutive_failures = 0;
-                            println!("\n✅ Task {} was completed. Moving to next task.", parsed.task.id);
+                            say!("\n✅ Task {} was completed. Moving to next task.", parsed.task.id);
                             break;
                         }
                     } else {
                         // All tasks complete
                         tasks_completed += 1;
-                        println!("\n✅ Task {} was completed (last task!).", parsed.task.id);
-                        break;
-                    }
-
-                    if reboot_count >= MAX_REBOOTS_PER_TASK {
-                        println!("\n⚠️  Task {} hit redline {} times. Moving to next task.", parsed.task.id, reboot_count);
-                        if no_changes_count >= reboot_count {
-                            consecutive_failures += 1;
-                        } else {
-                            consecutive_failures = 0;
-                        }
+                        say!("\n✅ Task {} was completed (last task!).", parsed.task.id);
                         break;
                     }
-                    println!("\n🔄 Rebooting with fresh context (attempt {}/{})...\n", reboot_count, MAX_REBOOTS_PER_TASK);
-                    tokio::time::sleep(tokio::time::Duration::from_secs(2)).await;
+                    (Failure::Redline, rebooted(had_changes))
                 }
                 Ok(TaskResult::MaxIterations) => {
-                    consecutive_failures += 1;
-                    println!("\nTask {} hit max iterations without completing.", parsed.task.id);
+                    say!("\nTask {} hit max iterations without completing.", parsed.task.id);
+                    (Failure::MaxIterations, String::new())
+                }
+                Ok(TaskResult::VerificationFailed) => {
+                    test_failures += 1;
+                    say!("\nTask {} failed verification.", parsed.task.id);
+                    (Failure::VerificationFailed, String::new())
+                }
+                Ok(TaskResult::BudgetExceeded) => {
+                    say!("\nTask {} stopped: budget spent.", parsed.task.id);
+                    break;
+                }
+                Ok(TaskResult::Skipped) => {
+                    say!("\nTask {} skipped and blocked.", parsed.task.id);
+                    break;
+                }
+                Ok(TaskResult::Aborted) => {
+                    aborted = true;
                     break;
                 }
                 Err(e) => {
-                    consecutive_failures += 1;
                     tracing::error!("Task {} failed: {}", parsed.task.id, e);
-                    println!("\nTask {} FAILED: {}", parsed.task.id, e);
+                    say!("\nTask {} FAILED: {}", parsed.task.id, e);
+                    (Failure::of(&e), e.to_string())
+                }
+            };
+
+            match triage_task(project_root, &task_settings, &parsed, failure, &detail).await {
+                Ok((attempt, Some(next))) => {
+                    say!("\n🔄 {}\n", attempt.note());
+                    tokio::time::sleep(settings.triage.pause(&attempt)).await;
+                    task_settings = next;
+                }
+                Ok((attempt, None)) => {
+                    say!("\n⚠️  {}", attempt.note());
+                    if attempt.action == Action::Decompose {
+                        consecutive_failures = 0;
+                    } else {
+                        consecutive_failures += 1;
+                    }
+                    break;
+                }
+                Err(e) => {
+                    consecutive_failures += 1;
+                    say!("\nTriage failed for {}: {}", parsed.task.id, e);
                     break;
                 }
             }
         }
 
+        if aborted {
+            say!("\nStopped by the user.");
+            break;
+        }
+
+        if settings.test_fail_streak.is_some_and(|limit| test_failures >= limit) {
+            say!("\nStopping: {} consecutive verification failures", test_failures);
+            break;
+        }
+
         if consecutive_failures >= fail_streak_limit {
-            println!(
+            say!(
                 "\nStopping: {} consecutive failures (limit: {})",
                 consecutive_failures, fail_streak_limit
             );
@@ -560,20 +1152,58 @@ async fn run_loop(
         tokio::time::sleep(tokio::time::Duration::from_secs(2)).await;
     }
 
-    println!("\n========================================");
-    println!("  LOOP SESSION COMPLETE");
-    println!("  Tasks completed: {}", tasks_completed);
-    println!("========================================\n");
+    say!("\n========================================");
+    say!("  LOOP SESSION COMPLETE");
+    say!("  Tasks completed: {}", tasks_completed);
+    say!("  Estimated spend: ${:.4}", settings.costs.run_total());
+    say!("========================================\n");
 
     Ok(())
 }
 
+/// Resume a task from the latest session in its transcript
+async fn resume_task(
+    project_root: &PathBuf,
+    task_id: &str,
+    mut point: ResumePoint,
+    compact: bool,
+    settings: &RunSettings,
+) -> Result<TaskResult> {
+    if point.last_stop == Some(StopReason::Completed) {
+        anyhow::bail!(
+            "Task {} completed in its last session. Use 'ralph run --issue {}' to start over.",
+            task_id,
+            task_id
+        );
+    }
+
+    let ended = match &point.last_stop {
+        Some(reason) => format!("{:?}", reason),
+        None => "interrupted".to_string(),
+    };
+    say!(
+        "\nResuming {} after iteration {} ({} messages, last session: {})",
+        task_id,
+        point.iteration,
+        point.messages.len(),
+        ended
+    );
+
+    if compact {
+        let before = point.messages.len();
+        point = point.compacted(compaction::DEFAULT_KEEP_RECENT);
+        say!("Compacted conversation: {} -> {} messages", before, point.messages.len());
+    }
+
+    run_single(project_root, Some(task_id), settings, Some(point)).await
+}
+
 /// Show current progress
-fn show_status(project_root: &PathBuf) -> Result<()> {
-    let summary = get_progress_summary(project_root)?;
+fn show_status(tracker: &dyn Tracker) -> Result<()> {
+    let summary = tracker.progress()?;
 
     println!("\n========================================");
-    println!("  BEADS PROGRESS");
+    println!("  {} PROGRESS", tracker.name().to_uppercase());
     println!("========================================");
     println!(
         "  Tasks: {}/{} ({:.1}%)",
@@ -596,11 +1226,11 @@ fn show_status(project_root: &PathBuf) -> Result<()> {
 }
 
 /// List tasks
-fn list_tasks(project_root: &PathBuf, show_all: bool) -> Result<()> {
+fn list_tasks(tracker: &dyn Tracker, show_all: bool) -> Result<()> {
     let tasks = if show_all {
-        task_parser::get_all_open_tasks(project_root)?
+        tracker.open_tasks()?
     } else {
-        get_ready_tasks(project_root)?
+        tracker.ready_tasks()?
     };
 
     if tasks.is_empty() {
@@ -640,8 +1270,8 @@ fn list_tasks(project_root: &PathBuf, show_all: bool) -> Result<()> {
 }
 
 /// Show next task to implement
-fn show_next(project_root: &PathBuf) -> Result<()> {
-    match find_next_task(project_root)? {
+fn show_next(tracker: &dyn Tracker) -> Result<()> {
+    match tracker.next_task()? {
         Some(parsed) => {
             println!("\n========================================");
             println!("  NEXT TASK");
@@ -684,8 +1314,8 @@ fn show_next(project_root: &PathBuf) -> Result<()> {
 }
 
 /// Show details of a specific task
-fn show_task(project_root: &PathBuf, task_id: &str) -> Result<()> {
-    let task = get_task(project_root, task_id)?;
+fn show_task(tracker: &dyn Tracker, triage: &Triage, task_id: &str) -> Result<()> {
+    let task = tracker.get_task(task_id)?;
     let parsed = parse_task(&task);
 
     println!("\n========================================");
@@ -704,6 +1334,11 @@ fn show_task(project_root: &PathBuf, task_id: &str) -> Result<()> {
         println!("  Labels: {}", task.labels.join(", "));
     }
 
+    let chain = tracker.blocked_by(task_id)?;
+    if !chain.is_empty() {
+        println!("  Blocked by: {}", chain.join(" → "));
+    }
+
     if !parsed.acceptance_criteria.is_empty() {
         println!("\n  Acceptance Criteria:");
         for ac in &parsed.acceptance_criteria {
@@ -720,6 +1355,85 @@ fn show_task(project_root: &PathBuf, task_id: &str) -> Result<()> {
         println!("\n  Notes:\n{}", task.notes);
     }
 
+    // Trackers without notes only have the attempt log
+    let attempts: Vec<Attempt> = triage
+        .attempts()?
+        .into_iter()
+        .filter(|a| a.task_id == task.id && !task.notes.contains(&a.note()))
+        .collect();
+    if !attempts.is_empty() {
+        println!("\n  Attempts:");
+        for attempt in &attempts {
+            println!("    {}", attempt.note());
+        }
+    }
+
+    println!("========================================\n");
+
+    Ok(())
+}
+
+/// Report spend from the ledger, with today's total against the budgets
+fn show_costs(
+    project_root: &Path,
+    config: &ProjectConfig,
+    by: GroupBy,
+    since: Option<chrono::NaiveDate>,
+) -> Result<()> {
+    let ledger = Ledger::new(project_root);
+    let all = ledger.entries()?;
+    let today = chrono::Local::now().date_naive();
+    let spent_today: f64 = all.iter().filter(|e| e.day() == today).map(|e| e.cost_usd).sum();
+
+    let entries = costs::since(all, since);
+    if entries.is_empty() {
+        println!("\nNo spend recorded in {}.", ledger.path().display());
+        return Ok(());
+    }
+    let rows = costs::breakdown(&entries, by);
+
+    println!("\n========================================");
+    println!("  SPEND{}", since.map(|d| format!(" SINCE {}", d)).unwrap_or_default());
+    println!("========================================");
+    println!(
+        "  Total: ${:.4} over {} session(s)",
+        rows.iter().map(|r| r.cost_usd).sum::<f64>(),
+        entries.len()
+    );
+    println!(
+        "  Tokens: {} in / {} out",
+        rows.iter().map(|r| r.input_tokens).sum::<u64>(),
+        rows.iter().map(|r| r.output_tokens).sum::<u64>()
+    );
+    match config.budget.per_day_usd {
+        Some(limit) => println!("  Today: ${:.4} of ${:.2} daily budget", spent_today, limit),
+        None => println!("  Today: ${:.4}", spent_today),
+    }
+    if let Some(limit) = config.budget.per_run_usd {
+        println!("  Run budget: ${:.2}", limit);
+    }
+    if let Some(limit) = config.budget.per_task_usd {
+        println!("  Task budget: ${:.2}", limit);
+    }
+
+    let label = match by {
+        GroupBy::Task => "Task",
+        GroupBy::Run => "Run",
+        GroupBy::Model => "Model",
+        GroupBy::Day => "Day",
+    };
+    println!("----------------------------------------");
+    println!("  {:<38} {:>10} {:>9} {:>12} {:>10}", label, "Cost", "Sessions", "Input", "Output");
+    for row in rows {
+        println!(
+            "  {:<38} {:>10} {:>9} {:>12} {:>10}",
+            row.key,
+            format!("${:.4}", row.cost_usd),
+            row.sessions,
+            row.input_tokens,
+            row.output_tokens
+        );
+    }
     println!("========================================\n");
 
     Ok(())
@@ -728,17 +1442,19 @@ fn show_task(project_root: &PathBuf, task_id: &str) -> Result<()> {
 /// Decompose large tasks into smaller subtasks
 async fn decompose_command(
     project_root: &PathBuf,
+    config: &ProjectConfig,
+    backend_spec: &BackendSpec,
     dry_run: bool,
+    plan_only: bool,
     specific_issue: Option<&str>,
 ) -> Result<()> {
-    let api_key = std::env::var("ANTHROPIC_API_KEY")
-        .context("ANTHROPIC_API_KEY environment variable not set")?;
 
     if let Some(issue_id) = specific_issue {
         // Decompose specific task
-        let task = get_task(project_root, issue_id)?;
+        let task = task_parser::get_task(project_root, issue_id)?;
         let parsed = parse_task(&task);
-        let analysis = decompose::analyze_task(&parsed, project_root);
+        let history = decompose::History::load(project_root);
+        let analysis = decompose::analyze_task(&parsed, project_root, &history);
 
         println!("\n========================================");
         println!("  TASK ANALYSIS: {}", task.id);
@@ -747,6 +1463,20 @@ async fn decompose_command(
         println!("  Description chars: {}", analysis.description_chars);
         println!("  Criteria count: {}", analysis.criteria_count);
         println!("  Has subtasks: {}", analysis.has_subtasks);
+        println!("  Estimated files: {}", analysis.estimated_files.len());
+        for file in &analysis.estimated_files {
+            println!("    {}", file);
+        }
+        if let (Some(tokens), Some(iterations)) = (analysis.predicted_tokens, analysis.predicted_iterations) {
+            println!(
+                "  Predicted: ~{}k tokens, ~{} iterations (median of {} past tasks: {}k, {})",
+                tokens / 1000,
+                iterations,
+                history.tasks,
+                history.median_tokens / 1000,
+                history.median_iterations
+            );
+        }
         println!("  Needs decomposition: {}", analysis.is_too_large);
         if !analysis.reason.is_empty() {
             println!("  Reason: {}", analysis.reason);
@@ -764,20 +1494,23 @@ async fn decompose_command(
         }
 
         println!("Decomposing task...\n");
-        let subtasks = decompose::decompose_task(&parsed, &api_key).await?;
+        let backend = model_backend(project_root, config, backend_spec)?;
+        let meter = Costs::new(project_root, &config.budget)?.meter(&task.id);
+        let plan = decompose::decompose_task(&parsed, &analysis, backend.as_ref(), &meter).await?;
 
-        if subtasks.is_empty() {
+        if plan.subtasks.is_empty() {
             println!("No subtasks suggested.");
             return Ok(());
         }
 
-        println!("\nCreating {} subtasks...\n", subtasks.len());
-        let created = decompose::create_subtasks(
-            project_root,
-            &task.id,
-            &task.labels,
-            &subtasks,
-        )?;
+        if plan_only {
+            println!("{}", plan.render());
+            println!("PLAN ONLY: nothing was created. Run without --plan-only to create these subtasks.");
+            return Ok(());
+        }
+
+        println!("\nCreating {} subtasks...\n", plan.subtasks.len());
+        let created = decompose::create_subtasks(project_root, &task, &plan)?;
 
         println!("\n✓ Created {} subtasks.", created.len());
         
@@ -798,12 +1531,14 @@ async fn decompose_command(
             for (parsed, analysis) in &needs_decomposition {
                 println!("  {} · {}", parsed.task.id, parsed.task.title);
                 println!("    Reason: {}", analysis.reason);
-                println!("    Description: {} chars, {} criteria\n", 
-                    analysis.description_chars, analysis.criteria_count);
+                println!("    Description: {} chars, {} criteria, ~{} files\n",
+                    analysis.description_chars, analysis.criteria_count, analysis.estimated_files.len());
             }
             println!("Run without --dry-run to decompose these tasks.");
         } else {
-            decompose::preprocess_tasks(project_root, &api_key).await?;
+            let backend = model_backend(project_root, config, backend_spec)?;
+            let costs = Costs::new(project_root, &config.budget)?;
+            decompose::preprocess_tasks(project_root, backend.as_ref(), &costs, plan_only).await?;
         }
     }
 
@@ -814,29 +1549,43 @@ async fn decompose_command(
 /// 
 /// Includes:
 /// - Codebase map (if available)
-/// - Recent progress from previous iterations
+/// - What the knowledge store knows that bears on this task
 /// - Explicit anti-patterns section
 /// - 3-iteration rule enforcement
-fn build_system_prompt(project_root: &PathBuf) -> String {
-    // Load codebase map (if available)
+async fn build_system_prompt(project_root: &Path, knowledge: &KnowledgeStore, parsed: &ParsedTask) -> String {
+    // A hand-written codemap if there is one, the generated outline otherwise
     let codemap = progress::load_codebase_summary(project_root);
+    let repo_map = if codemap.is_empty() {
+        repomap::load(project_root).await
+    } else {
+        String::new()
+    };
     
-    // Load recent progress (last 3 entries)
-    let recent_progress = progress::load_recent_progress(project_root, 3)
-        .unwrap_or_default();
+    // Entries matching the task's files and keywords
+    let recent_progress = knowledge
+        .relevant(&Query::for_task(parsed))
+        .unwrap_or_else(|e| {
+            tracing::warn!("Failed to read the knowledge store: {}", e);
+            String::new()
+        });
 
     // Build codemap section
-    let codemap_section = if codemap.is_empty() {
-        "No codemap available. The task description contains file paths - trust them.".to_string()
-    } else {
+    let codemap_section = if !codemap.is_empty() {
         format!("```\n{}\n```", codemap)
+    } else if !repo_map.is_empty() {
+        format!(
+            "Source files and their public items, the most used first:\n\n```\n{}\n```",
+            repo_map
+        )
+    } else {
+        "No codemap available. The task description contains file paths - trust them.".to_string()
     };
 
     // Build progress section
     let progress_section = if recent_progress.is_empty() {
-        "No previous progress recorded. You're starting fresh.".to_string()
+        "Nothing recorded yet that relates to this task. You're starting fresh.".to_string()
     } else {
-        format!("Study these patterns from previous work:\n\n{}", recent_progress)
+        format!("Study what earlier runs learned about this project:\n\n{}", recent_progress)
     };
 
     format!(
@@ -867,6 +1616,7 @@ fn build_system_prompt(project_root: &PathBuf) -> String {
 | edit_file | Create/modify files | empty old_string = new file |
 | bash | Build/test/git | Build commands only, NOT exploration |
 | beads | Task management | close when done |
+| remember | Save a lesson for future tasks | pitfalls, file notes, commands |
 
 ## Creating New Files
 
@@ -897,6 +1647,14 @@ edit_file path="path/to/NewFile.cs" old_string="" new_string="<full file content
 - Close task: `beads action="close" task_id="<id>" reason="<summary>"`
 - Create subtask: `beads action="create" title="..." description="..." blocks="<parent-id>"`
 
+## Remembering
+
+When you hit something a later task would trip over (a non-obvious build
+step, a file with a hidden invariant, an approach that failed), save it:
+```
+remember kind="pitfall" text="<what to avoid and why>" files=["path/to/file"]
+```
+
 ## Completion
 
 When done, close the task immediately:
@@ -1018,12 +1776,18 @@ fn build_task_prompt(parsed: &ParsedTask) -> String {
 /// Run with TUI interface
 async fn run_tui(
     project_root: &PathBuf,
-    max_iterations: usize,
-    redline_threshold: u32,
+    settings: RunSettings,
     max_tasks: Option<usize>,
     fail_streak_limit: usize,
-    auto_sync: bool,
+    attended: bool,
 ) -> Result<()> {
+    // Approval requests are answered in the TUI
+    let (approval_tx, mut approval_rx) = mpsc::channel(1);
+    let mut settings = settings.with_approver(attended, Arc::new(ChannelApprover::new(approval_tx)));
+    // Pause, steering, skip and abort reach the loop through this
+    let control = LoopControl::new();
+    settings.control = Some(control.clone());
+
     // Initialize terminal
     enable_raw_mode()?;
     let mut stdout = stdout();
@@ -1032,19 +1796,20 @@ async fn run_tui(
     let mut terminal = Terminal::new(backend)?;
 
     // Create app state
-    let mut app = App::new(redline_threshold);
+    let mut app = App::new(settings.redline_threshold, project_root.clone());
+    app.control = Some(control);
     app.is_running = true;
 
     // Load all ready tasks
-    let ready_tasks = get_ready_tasks(project_root).unwrap_or_default();
+    let ready_tasks = settings.tracker.ready_tasks().unwrap_or_default();
     let tasks: Vec<Task> = ready_tasks.iter().map(|task| {
         let parsed = parse_task(task);
         let criteria_done = parsed.acceptance_criteria.iter().filter(|c| c.completed).count();
         let criteria_total = parsed.acceptance_criteria.len();
         
         Task {
-            id: 0, // Use index as numeric ID for TUI
-            name: format!("{}: {}", task.id, task.title),
+            id: task.id.clone(),
+            name: task.title.clone(),
             status: match task.status.as_str() {
                 "in_progress" => TaskStatus::InProgress,
                 "closed" => TaskStatus::Completed,
@@ -1052,6 +1817,7 @@ async fn run_tui(
             },
             criteria_done,
             criteria_total,
+            detail: parsed,
         }
     }).collect();
     
@@ -1060,8 +1826,8 @@ async fn run_tui(
     // Create event handler
     let event_handler = EventHandler::new(50);
 
-    // Create channels for output
-    let (output_tx, mut output_rx) = mpsc::channel::<String>(1000);
+    // Create channels for loop events
+    let (output_tx, mut output_rx) = mpsc::channel::<LoopEvent>(1000);
 
     // Clone for the spawned task
     let project_root_clone = project_root.clone();
@@ -1070,11 +1836,9 @@ async fn run_tui(
     let loop_handle = tokio::spawn(async move {
         run_loop_internal(
             &project_root_clone,
-            max_iterations,
-            redline_threshold,
+            &settings,
             max_tasks,
             fail_streak_limit,
-            auto_sync,
             output_tx,
         ).await
     });
@@ -1086,24 +1850,15 @@ async fn run_tui(
             tui::ui::Ui::render(frame, &app);
         })?;
 
-        // Handle output from the loop
-        while let Ok(text) = output_rx.try_recv() {
-            let level = if text.starts_with("---") {
-                OutputLevel::Info
-            } else if text.starts_with("[") && text.contains("Executing tool:") {
-                OutputLevel::Tool
-            } else if text.starts_with("[REDLINE") {
-                OutputLevel::Error
-            } else if text.contains("✓") || text.contains("complete") {
-                OutputLevel::Success
-            } else {
-                OutputLevel::Text
-            };
-            
-            for line in text.lines() {
-                if !line.is_empty() {
-                    app.add_output(level, line.to_string());
-                }
+        // Handle events from the loop
+        while let Ok(event) = output_rx.try_recv() {
+            app.handle_loop_event(event);
+        }
+
+        // Show a tool call waiting for approval
+        if app.approval.is_none() {
+            if let Ok(pending) = approval_rx.try_recv() {
+                app.request_approval(pending);
             }
         }
 
@@ -1139,104 +1894,155 @@ async fn run_tui(
 /// Internal loop runner that sends output to channel
 async fn run_loop_internal(
     project_root: &PathBuf,
-    max_iterations: usize,
-    redline_threshold: u32,
+    settings: &RunSettings,
     max_tasks: Option<usize>,
     fail_streak_limit: usize,
-    auto_sync: bool,
-    output_tx: mpsc::Sender<String>,
+    events: EventSender,
 ) -> Result<()> {
     let mut tasks_completed = 0;
     let mut consecutive_failures = 0;
-    let mut reboot_count = 0;
-    let mut no_changes_count = 0;
-    const MAX_REBOOTS_PER_TASK: usize = 3;
+    // Consecutive tasks that failed verification (`stop_on: test_fail_streak`)
+    let mut test_failures = 0u32;
 
-    let _ = output_tx.send("Starting Ralph Loop...\n".to_string()).await;
+    events::status(&events, Level::Info, "Starting Ralph Loop...").await;
 
     loop {
         if let Some(max) = max_tasks {
             if tasks_completed >= max {
-                let _ = output_tx.send(format!("Reached max tasks limit ({})\n", max)).await;
+                events::status(&events, Level::Info, format!("Reached max tasks limit ({})", max)).await;
                 break;
             }
         }
 
-        let parsed = match find_next_task(project_root)? {
+        if let Some(exceeded) = settings.costs.exceeded(None) {
+            events::status(&events, Level::Warning, format!("Stopping: {}", exceeded)).await;
+            break;
+        }
+
+        let parsed = match settings.tracker.next_task()? {
             Some(t) => t,
             None => {
-                let _ = output_tx.send("✓ No more ready tasks!\n".to_string()).await;
+                events::status(&events, Level::Success, "✓ No more ready tasks!").await;
                 break;
             }
         };
 
-        let _ = output_tx.send(format!("\n→ Starting Task: {}\n  {}\n", parsed.task.id, parsed.task.title)).await;
-        reboot_count = 0;
-        no_changes_count = 0;
+        events::emit(
+            Some(&events),
+            LoopEvent::TaskStarted {
+                task_id: parsed.task.id.clone(),
+                title: parsed.task.title.clone(),
+                base: git::current_commit_short(project_root).ok(),
+            },
+        )
+        .await;
+        let completed_before = tasks_completed;
+        let mut task_settings = settings.clone();
+        let mut aborted = false;
 
         loop {
-            match run_single_internal(project_root, Some(&parsed.task.id), max_iterations, redline_threshold, auto_sync, output_tx.clone()).await {
+            let (failure, detail) = match run_single_internal(project_root, Some(&parsed.task.id), &task_settings, events.clone()).await {
                 Ok(TaskResult::Completed) => {
                     tasks_completed += 1;
                     consecutive_failures = 0;
-                    let _ = output_tx.send(format!("\n✓ Task {} completed!\n", parsed.task.id)).await;
+                    test_failures = 0;
+                    events::status(&events, Level::Success, format!("✓ Task {} completed!", parsed.task.id)).await;
                     break;
                 }
                 Ok(TaskResult::NeedsReboot { had_changes }) => {
-                    reboot_count += 1;
-                    if !had_changes {
-                        no_changes_count += 1;
-                    } else {
-                        no_changes_count = 0;
-                    }
-
-                    if let Ok(Some(refreshed)) = find_next_task(project_root) {
+                    if let Ok(Some(refreshed)) = settings.tracker.next_task() {
                         if refreshed.task.id != parsed.task.id {
                             tasks_completed += 1;
                             consecutive_failures = 0;
-                            let _ = output_tx.send(format!("\n✓ Task {} was completed!\n", parsed.task.id)).await;
+                            events::status(&events, Level::Success, format!("✓ Task {} was completed!", parsed.task.id)).await;
                             break;
                         }
                     } else {
                         tasks_completed += 1;
-                        let _ = output_tx.send(format!("\n✓ Task {} was completed (last task)!\n", parsed.task.id)).await;
-                        break;
-                    }
-
-                    if reboot_count >= MAX_REBOOTS_PER_TASK {
-                        let _ = output_tx.send(format!("\n⚠ Hit redline {} times, moving on\n", reboot_count)).await;
-                        if no_changes_count >= reboot_count {
-                            consecutive_failures += 1;
-                        } else {
-                            consecutive_failures = 0;
-                        }
+                        events::status(&events, Level::Success, format!("✓ Task {} was completed (last task)!", parsed.task.id)).await;
                         break;
                     }
-                    let _ = output_tx.send(format!("\n🔄 Rebooting ({}/{})...\n", reboot_count, MAX_REBOOTS_PER_TASK)).await;
-                    tokio::time::sleep(tokio::time::Duration::from_secs(2)).await;
+                    (Failure::Redline, rebooted(had_changes))
                 }
                 Ok(TaskResult::MaxIterations) => {
-                    consecutive_failures += 1;
-                    let _ = output_tx.send(format!("\n⚠ Max iterations reached\n")).await;
+                    events::status(&events, Level::Warning, "⚠ Max iterations reached").await;
+                    (Failure::MaxIterations, String::new())
+                }
+                Ok(TaskResult::VerificationFailed) => {
+                    test_failures += 1;
+                    events::status(&events, Level::Error, "✗ Verification failed").await;
+                    (Failure::VerificationFailed, String::new())
+                }
+                Ok(TaskResult::BudgetExceeded) => {
+                    events::status(&events, Level::Error, "✗ Budget spent").await;
+                    break;
+                }
+                Ok(TaskResult::Skipped) => {
+                    events::status(&events, Level::Warning, format!("⏭ Task {} skipped and blocked", parsed.task.id)).await;
+                    break;
+                }
+                Ok(TaskResult::Aborted) => {
+                    aborted = true;
+                    break;
+                }
+                Err(e) => {
+                    events::status(&events, Level::Error, format!("✗ Error: {}", e)).await;
+                    (Failure::of(&e), e.to_string())
+                }
+            };
+
+            match triage_task(project_root, &task_settings, &parsed, failure, &detail).await {
+                Ok((attempt, Some(next))) => {
+                    events::status(&events, Level::Info, format!("🔄 {}", attempt.note())).await;
+                    tokio::time::sleep(settings.triage.pause(&attempt)).await;
+                    task_settings = next;
+                }
+                Ok((attempt, None)) => {
+                    events::status(&events, Level::Warning, format!("⚠ {}", attempt.note())).await;
+                    if attempt.action == Action::Decompose {
+                        consecutive_failures = 0;
+                    } else {
+                        consecutive_failures += 1;
+                    }
                     break;
                 }
                 Err(e) => {
                     consecutive_failures += 1;
-                    let _ = output_tx.send(format!("\n✗ Error: {}\n", e)).await;
+                    events::status(&events, Level::Error, format!("✗ Triage failed: {}", e)).await;
                     break;
                 }
             }
         }
 
+        events::emit(
+            Some(&events),
+            LoopEvent::TaskFinished {
+                task_id: parsed.task.id.clone(),
+                completed: tasks_completed > completed_before,
+                head: git::current_commit_short(project_root).ok(),
+            },
+        )
+        .await;
+
+        if aborted {
+            events::status(&events, Level::Warning, "■ Stopped by the user").await;
+            break;
+        }
+
+        if settings.test_fail_streak.is_some_and(|limit| test_failures >= limit) {
+            events::status(&events, Level::Warning, format!("Stopping: {} consecutive verification failures", test_failures)).await;
+            break;
+        }
+
         if consecutive_failures >= fail_streak_limit {
-            let _ = output_tx.send(format!("\nStopping: {} consecutive failures\n", consecutive_failures)).await;
+            events::status(&events, Level::Warning, format!("Stopping: {} consecutive failures", consecutive_failures)).await;
             break;
         }
 
         tokio::time::sleep(tokio::time::Duration::from_secs(2)).await;
     }
 
-    let _ = output_tx.send(format!("\nLoop complete. {} tasks done.\n", tasks_completed)).await;
+    events::status(&events, Level::Success, format!("Loop complete. {} tasks done, ${:.4} spent.", tasks_completed, settings.costs.run_total())).await;
     Ok(())
 }
 
@@ -1244,119 +2050,120 @@ async fn run_loop_internal(
 async fn run_single_internal(
     project_root: &PathBuf,
     task_id: Option<&str>,
-    max_iterations: usize,
-    redline_threshold: u32,
-    auto_sync: bool,
-    output_tx: mpsc::Sender<String>,
+    settings: &RunSettings,
+    events: EventSender,
 ) -> Result<TaskResult> {
+    let tracker = &settings.tracker;
+
     let parsed = if let Some(id) = task_id {
-        let task = get_task(project_root, id)?;
+        let task = tracker.get_task(id)?;
         parse_task(&task)
     } else {
-        find_next_task(project_root)?.ok_or_else(|| anyhow::anyhow!("No ready tasks!"))?
+        tracker.next_task()?.ok_or_else(|| anyhow::anyhow!("No ready tasks!"))?
     };
 
     // Mark as in_progress
-    task_parser::update_task_status(project_root, &parsed.task.id, "in_progress")?;
+    tracker.start_task(&parsed.task.id)?;
 
-    let api_key = std::env::var("ANTHROPIC_API_KEY")
-        .context("ANTHROPIC_API_KEY environment variable not set")?;
-
-    let system_prompt = build_system_prompt(project_root);
+    let system_prompt = build_system_prompt(project_root, &KnowledgeStore::new(project_root), &parsed).await;
     let task_prompt = build_task_prompt(&parsed);
 
-    let client = ClaudeClient::new(api_key, project_root);
+    let verifier = verify::Verifier::for_task(&settings.verify, settings.require_tests, project_root, &parsed, settings.sandbox.as_ref())?;
+
+    let client = ClaudeClient::new(settings.backend.clone(), project_root)
+        .with_transcript(Transcript::new(project_root, &parsed.task.id))
+        .with_compaction(settings.compactor.clone())
+        .with_approval(settings.approval.clone())
+        .with_sandbox(settings.sandbox.clone())
+        .with_mcp(settings.mcp.clone())
+        .with_costs(settings.costs.meter(&parsed.task.id))
+        .with_control(settings.control.clone())
+        .defer_task_close(harness_closes(settings, verifier.is_some()));
+
+    let run = verify::run_verified(
+        &client,
+        verifier.as_ref(),
+        &system_prompt,
+        ResumePoint::fresh(&task_prompt),
+        settings.max_iterations,
+        settings.redline_threshold,
+        Some(events.clone()),
+    )
+    .await?;
+    if let Err(e) = knowledge::record_verified(project_root, &run) {
+        tracing::warn!("Failed to record verification commands: {}", e);
+    }
 
-    let result = client
-        .run_agentic_loop(&system_prompt, &task_prompt, max_iterations, redline_threshold, Some(output_tx.clone()))
-        .await?;
+    finish_task(project_root, settings, &parsed, &run, Some(&events)).await
+}
 
-    // Track modified files for progress recording
-    let modified_files = progress::extract_modified_files(&result.tool_outputs);
-    
-    let task_result = match result.stop_reason {
-        StopReason::Completed => {
-            // Record progress
-            if !modified_files.is_empty() {
-                let summary = format!(
-                    "Completed task: {}\n\nCriteria: {}",
-                    parsed.task.title,
-                    parsed.acceptance_criteria
-                        .iter()
-                        .map(|ac| format!("- {}", ac.text))
-                        .collect::<Vec<_>>()
-                        .join("\n")
-                );
-                let _ = progress::append_progress(
-                    project_root,
-                    &parsed.task.id,
-                    &summary,
-                    &modified_files,
-                );
-            }
-            
-            if auto_sync {
-                task_parser::sync_beads(project_root)?;
-                if let Some(hash) = git::auto_commit_task(project_root, &parsed.task.id, &parsed.task.title)? {
-                    let _ = output_tx.send(format!("Committed: {}\n", hash)).await;
-                }
-            }
-            TaskResult::Completed
-        }
-        StopReason::Redline => {
-            // Record partial progress
-            if !modified_files.is_empty() {
-                let summary = format!(
-                    "Partial progress on: {} (hit redline)\n\nModified {} file(s)",
-                    parsed.task.title,
-                    modified_files.len()
-                );
-                let _ = progress::append_progress(
-                    project_root,
-                    &parsed.task.id,
-                    &summary,
-                    &modified_files,
-                );
-            }
-            
-            let had_changes = if auto_sync {
-                task_parser::sync_beads(project_root)?;
-                if let Some(hash) = git::auto_commit_task(project_root, &parsed.task.id, &parsed.task.title)? {
-                    let _ = output_tx.send(format!("Committed partial: {}\n", hash)).await;
-                    true
-                } else {
-                    false
-                }
-            } else {
-                !modified_files.is_empty()
-            };
-            TaskResult::NeedsReboot { had_changes }
-        }
-        StopReason::MaxIterations => {
-            // Record partial progress
-            if !modified_files.is_empty() {
-                let summary = format!(
-                    "Partial progress on: {} (max iterations)\n\nModified {} file(s)",
-                    parsed.task.title,
-                    modified_files.len()
-                );
-                let _ = progress::append_progress(
-                    project_root,
-                    &parsed.task.id,
-                    &summary,
-                    &modified_files,
-                );
-            }
-            
-            if auto_sync {
-                task_parser::sync_beads(project_root)?;
-                if let Some(hash) = git::auto_commit_task(project_root, &parsed.task.id, &parsed.task.title)? {
-                    let _ = output_tx.send(format!("Committed partial: {}\n", hash)).await;
-                }
-            }
-            TaskResult::MaxIterations
-        }
-    };
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use backends::MockBackend;
+    use serde_json::json;
+    use tempfile::TempDir;
+
+    /// A repo with one open issue in a GitHub export
+    fn issue_repo() -> TempDir {
+        let temp = TempDir::new().unwrap();
+        std::fs::write(
+            temp.path().join("issues.json"),
+            r#"[{"number": 1, "title": "Write notes", "state": "OPEN", "labels": [], "body": ""}]"#,
+        )
+        .unwrap();
+        temp
+    }
 
-    Ok(task_result)
+    fn settings(root: &Path, config: &ProjectConfig, spec: &BackendSpec) -> RunSettings {
+        let tracker = tracker::create(TrackerKind::Issues, root, Some(Path::new("issues.json"))).unwrap();
+        RunSettings::new(root, config, spec, tracker, 10, 150_000, true)
+            .unwrap()
+            .attended(false)
+    }
+
+    #[tokio::test]
+    async fn test_run_single_replays_a_recording() {
+        let recorded = issue_repo();
+        let root = recorded.path().to_path_buf();
+        let config = ProjectConfig::default();
+        let mock = BackendSpec {
+            kind: BackendKind::Mock,
+            model: "mock".to_string(),
+        };
+        let mut recording = settings(&root, &config, &mock);
+        recording.backend = Arc::new(MockBackend::new(vec![
+            MockBackend::tool_use(
+                "edit_file",
+                json!({"path": "notes.txt", "old_string": "", "new_string": "first\n"}),
+            ),
+            MockBackend::tool_use("beads", json!({"action": "close", "task_id": "1", "reason": "Notes written"})),
+            MockBackend::text("Done."),
+        ]));
+        let recorder = recording.record(&root);
+        assert_eq!(run_single(&root, None, &recording, None).await.unwrap(), TaskResult::Completed);
+        assert_eq!(recording.tracker.get_task("1").unwrap().status, "closed");
+
+        let mut fixture = recorder.fixture();
+        fixture.final_tree = replay::snapshot(&root).unwrap();
+        assert_eq!(fixture.exchanges.len(), 3);
+        assert_eq!(fixture.final_tree["notes.txt"], "first\n");
+        assert!(fixture.final_tree["issues.json"].contains("CLOSED"));
+        // Closed with the model's reason, not the harness's default
+        assert!(fixture.final_tree["issues.json"].contains("Notes written"));
+        let fixtures = TempDir::new().unwrap();
+        let path = fixtures.path().join("notes.json");
+        fixture.save(&path).unwrap();
+
+        // The same run in a fresh checkout, with no model behind it
+        let fresh = issue_repo();
+        let root = fresh.path().to_path_buf();
+        let mut config = ProjectConfig::default();
+        config.backend.endpoints.insert("replay".to_string(), path.display().to_string());
+        let spec = backends::resolve(Some(BackendKind::Replay), None, &config.backend).unwrap();
+        let replaying = settings(&root, &config, &spec);
+        assert_eq!(run_single(&root, None, &replaying, None).await.unwrap(), TaskResult::Completed);
+        assert_eq!(replaying.tracker.get_task("1").unwrap().status, "closed");
+        assert_eq!(Fixture::load(&path).unwrap().diff_tree(&root).unwrap(), Vec::<String>::new());
+    }
 }
@@ -0,0 +1,350 @@
+//! MCP client - Project tools from external Model Context Protocol servers
+//!
+//! A project that needs a domain tool or two (a schema inspector, an
+//! internal docs search, ...) declares MCP servers in its config:
+//!
+//! ```yaml
+//! mcp:
+//!   servers:
+//!     schema:
+//!       command: ./tools/schema-mcp
+//!       args: [--db, dev.sqlite]
+//!       allow: [list_tables, describe_table]
+//! ```
+//!
+//! Servers are started on first use and spoken to over stdio, one JSON-RPC
+//! message per line. Their tools join the schema as `<server>__<tool>`,
+//! filtered by the server's `allow`/`deny` lists, and calls to them are
+//! routed back to the server. Results take the same path as a primitive's:
+//! truncated, emitted as events and written to the transcript. A server that
+//! fails to start is logged and left out; the loop runs without it.
+
+use anyhow::{bail, Context, Result};
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use std::time::Duration;
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader, Lines};
+use tokio::process::{Child, ChildStdin, ChildStdout, Command};
+use tokio::sync::{Mutex, OnceCell};
+use tokio::time::timeout;
+
+use crate::config::{McpConfig, McpServerConfig};
+use crate::primitives::{truncate_output, ToolDefinition, ToolResult, MAX_OUTPUT_BYTES};
+
+/// Between a server's name and its tool's in the names the model sees
+pub const SEPARATOR: &str = "__";
+
+/// Protocol revision requested from servers
+const PROTOCOL_VERSION: &str = "2025-06-18";
+
+/// The tools of a project's MCP servers
+pub struct McpTools {
+    project_root: PathBuf,
+    config: McpConfig,
+    connected: OnceCell<Connected>,
+}
+
+/// Running servers and where each exposed tool goes
+struct Connected {
+    servers: Vec<Server>,
+    /// Exposed name -> (server index, the server's name for the tool)
+    routes: HashMap<String, (usize, String)>,
+    definitions: Vec<ToolDefinition>,
+}
+
+impl McpTools {
+    pub fn new(project_root: &Path, config: &McpConfig) -> Self {
+        Self {
+            project_root: project_root.to_path_buf(),
+            config: config.clone(),
+            connected: OnceCell::new(),
+        }
+    }
+
+    /// Schema entries for every allowed tool, starting the servers if needed
+    pub async fn definitions(&self) -> Vec<ToolDefinition> {
+        self.connected().await.definitions.clone()
+    }
+
+    /// Run a call if `name` is one of the servers' tools; `None` otherwise
+    pub async fn call(&self, name: &str, input: &Value) -> Option<ToolResult> {
+        if !name.contains(SEPARATOR) {
+            return None;
+        }
+        let connected = self.connected().await;
+        let (index, tool) = connected.routes.get(name)?;
+        let server = &connected.servers[*index];
+        Some(match server.call(tool, input).await {
+            Ok(result) => tool_result(&result),
+            Err(e) => ToolResult::error(format!("MCP server {} failed: {:#}", server.name, e)),
+        })
+    }
+
+    async fn connected(&self) -> &Connected {
+        self.connected.get_or_init(|| self.connect()).await
+    }
+
+    async fn connect(&self) -> Connected {
+        let mut connected = Connected {
+            servers: Vec::new(),
+            routes: HashMap::new(),
+            definitions: Vec::new(),
+        };
+
+        for (name, config) in &self.config.servers {
+            let (server, tools) = match Server::start(name, config, &self.project_root).await {
+                Ok(started) => started,
+                Err(e) => {
+                    tracing::warn!("MCP server {} unavailable: {:#}", name, e);
+                    continue;
+                }
+            };
+
+            let index = connected.servers.len();
+            for tool in tools {
+                let Some(tool_name) = tool.get("name").and_then(Value::as_str) else {
+                    continue;
+                };
+                let allowed = config.allow.is_empty() || config.allow.iter().any(|a| a == tool_name);
+                if !allowed || config.deny.iter().any(|d| d == tool_name) {
+                    continue;
+                }
+
+                let exposed = format!("{}{}{}", name, SEPARATOR, tool_name);
+                if !is_valid_tool_name(&exposed) {
+                    tracing::warn!("Skipping MCP tool {}: not a valid tool name", exposed);
+                    continue;
+                }
+                connected.definitions.push(ToolDefinition {
+                    name: exposed.clone(),
+                    description: tool.get("description").and_then(Value::as_str).unwrap_or_default().to_string(),
+                    input_schema: tool.get("inputSchema").cloned().unwrap_or_else(|| json!({"type": "object"})),
+                });
+                connected.routes.insert(exposed, (index, tool_name.to_string()));
+            }
+            connected.servers.push(server);
+        }
+
+        if !connected.definitions.is_empty() {
+            tracing::info!("{} MCP tools available", connected.definitions.len());
+        }
+        connected
+    }
+}
+
+/// One running server
+struct Server {
+    name: String,
+    call_timeout: Duration,
+    io: Mutex<ServerIo>,
+}
+
+impl Server {
+    /// Spawn a server, introduce ourselves and list its tools
+    async fn start(name: &str, config: &McpServerConfig, project_root: &Path) -> Result<(Self, Vec<Value>)> {
+        // Relative program paths are relative to the project, bare names come from PATH
+        let program = if config.command.contains('/') {
+            project_root.join(&config.command)
+        } else {
+            PathBuf::from(&config.command)
+        };
+        let mut child = Command::new(&program)
+            .args(&config.args)
+            .envs(&config.env)
+            .current_dir(project_root)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .kill_on_drop(true)
+            .spawn()
+            .with_context(|| format!("Failed to start {}", config.command))?;
+
+        let mut io = ServerIo {
+            stdin: child.stdin.take().context("No stdin")?,
+            stdout: BufReader::new(child.stdout.take().context("No stdout")?).lines(),
+            _child: child,
+            next_id: 0,
+        };
+
+        let call_timeout = Duration::from_secs(config.timeout_secs);
+        let tools = timeout(call_timeout, io.handshake())
+            .await
+            .with_context(|| format!("No answer within {}s", config.timeout_secs))??;
+
+        let server = Self {
+            name: name.to_string(),
+            call_timeout,
+            io: Mutex::new(io),
+        };
+        Ok((server, tools))
+    }
+
+    async fn call(&self, tool: &str, arguments: &Value) -> Result<Value> {
+        let mut io = self.io.lock().await;
+        let request = io.request("tools/call", json!({ "name": tool, "arguments": arguments }));
+        match timeout(self.call_timeout, request).await {
+            Ok(result) => result,
+            Err(_) => bail!("{} timed out after {}s", tool, self.call_timeout.as_secs()),
+        }
+    }
+}
+
+/// The pipes to a server; one request at a time
+struct ServerIo {
+    stdin: ChildStdin,
+    stdout: Lines<BufReader<ChildStdout>>,
+    /// Killed when dropped
+    _child: Child,
+    next_id: u64,
+}
+
+impl ServerIo {
+    /// `initialize`, then every page of `tools/list`
+    async fn handshake(&mut self) -> Result<Vec<Value>> {
+        self.request(
+            "initialize",
+            json!({
+                "protocolVersion": PROTOCOL_VERSION,
+                "capabilities": {},
+                "clientInfo": { "name": "ralph", "version": env!("CARGO_PKG_VERSION") },
+            }),
+        )
+        .await?;
+        self.send(json!({ "jsonrpc": "2.0", "method": "notifications/initialized" })).await?;
+
+        let mut tools = Vec::new();
+        let mut cursor: Option<String> = None;
+        loop {
+            let params = match &cursor {
+                Some(cursor) => json!({ "cursor": cursor }),
+                None => json!({}),
+            };
+            let page = self.request("tools/list", params).await?;
+            tools.extend(page.get("tools").and_then(Value::as_array).cloned().unwrap_or_default());
+            cursor = page.get("nextCursor").and_then(Value::as_str).map(str::to_string);
+            if cursor.is_none() {
+                return Ok(tools);
+            }
+        }
+    }
+
+    /// Send a request and wait for its response
+    async fn request(&mut self, method: &str, params: Value) -> Result<Value> {
+        self.next_id += 1;
+        let id = self.next_id;
+        self.send(json!({ "jsonrpc": "2.0", "id": id, "method": method, "params": params }))
+            .await?;
+
+        loop {
+            let line = self.stdout.next_line().await?.context("Server closed its output")?;
+            let Ok(message) = serde_json::from_str::<Value>(&line) else {
+                continue;
+            };
+
+            // Requests and notifications from the server; answer requests so it doesn't wait
+            if let Some(method) = message.get("method").and_then(Value::as_str) {
+                if let Some(request_id) = message.get("id") {
+                    let reply = if method == "ping" {
+                        json!({ "jsonrpc": "2.0", "id": request_id, "result": {} })
+                    } else {
+                        json!({ "jsonrpc": "2.0", "id": request_id, "error": { "code": -32601, "message": "Not supported" } })
+                    };
+                    self.send(reply).await?;
+                }
+                continue;
+            }
+
+            // A late answer to a call that timed out
+            if message.get("id").and_then(Value::as_u64) != Some(id) {
+                continue;
+            }
+            if let Some(error) = message.get("error") {
+                let text = error.get("message").and_then(Value::as_str).unwrap_or("unknown error");
+                bail!("{} failed: {}", method, text);
+            }
+            return Ok(message.get("result").cloned().unwrap_or(Value::Null));
+        }
+    }
+
+    async fn send(&mut self, message: Value) -> Result<()> {
+        let mut line = message.to_string();
+        line.push('\n');
+        self.stdin.write_all(line.as_bytes()).await?;
+        self.stdin.flush().await?;
+        Ok(())
+    }
+}
+
+/// A `tools/call` result as the model sees it
+fn tool_result(result: &Value) -> ToolResult {
+    let mut parts = Vec::new();
+    for item in result.get("content").and_then(Value::as_array).into_iter().flatten() {
+        let text = item
+            .get("text")
+            .or_else(|| item.get("resource").and_then(|r| r.get("text")))
+            .and_then(Value::as_str);
+        match text {
+            Some(text) => parts.push(text.to_string()),
+            None => {
+                let kind = item.get("type").and_then(Value::as_str).unwrap_or("unknown");
+                parts.push(format!("[{} content omitted]", kind));
+            }
+        }
+    }
+    if parts.is_empty() {
+        if let Some(structured) = result.get("structuredContent") {
+            parts.push(structured.to_string());
+        }
+    }
+
+    let text = truncate_output(parts.join("\n"), MAX_OUTPUT_BYTES);
+    if result.get("isError").and_then(Value::as_bool).unwrap_or(false) {
+        ToolResult::error(text)
+    } else {
+        ToolResult::success(text)
+    }
+}
+
+/// Tool names the model APIs accept
+fn is_valid_tool_name(name: &str) -> bool {
+    name.len() <= 64 && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[tokio::test]
+    async fn test_stub_server_tools() {
+        let temp = TempDir::new().unwrap();
+        let stub = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/mcp/stub_server.sh");
+        let config: McpConfig = serde_yaml::from_str(&format!(
+            "servers:\n  stub:\n    command: bash\n    args: ['{}']\n    deny: [secret]\n  broken:\n    command: ./missing-server\n",
+            stub.display()
+        ))
+        .unwrap();
+        let mcp = McpTools::new(temp.path(), &config);
+
+        let names: Vec<String> = mcp.definitions().await.into_iter().map(|d| d.name).collect();
+        assert_eq!(names, ["stub__echo", "stub__fail", "stub__big"]);
+
+        let echo = mcp.call("stub__echo", &json!({"text": "hello"})).await.unwrap();
+        assert!(echo.success);
+        assert_eq!(echo.output, "hello");
+
+        let fail = mcp.call("stub__fail", &json!({})).await.unwrap();
+        assert!(!fail.success);
+        assert_eq!(fail.error.as_deref(), Some("boom"));
+
+        let big = mcp.call("stub__big", &json!({})).await.unwrap();
+        assert!(big.output.len() < 60_000);
+        assert!(big.output.ends_with("[Truncated: output is 60000 bytes]"));
+
+        // Denied tools and primitives aren't routed here
+        assert!(mcp.call("stub__secret", &json!({})).await.is_none());
+        assert!(mcp.call("read_file", &json!({"path": "x"})).await.is_none());
+    }
+}
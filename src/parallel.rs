@@ -0,0 +1,480 @@
+//! Parallel Loop - Run independent tasks concurrently in git worktrees
+//!
+//! `ralph loop --parallel N` keeps up to N agentic loops running at once.
+//! Each task gets its own worktree under `.ralph/worktrees/<task-id>` on a
+//! `ralph/<task-id>` branch, so agents never see each other's half-finished
+//! edits. Finished branches are merged back into the current branch one at a
+//! time; a merge that conflicts is aborted and the task is marked blocked for
+//! a human to merge by hand.
+//!
+//! A run that doesn't finish goes through `triage` like in the serial loop:
+//! retries pick up from the partial work committed on the branch, and an
+//! escalated task keeps its bigger model for its remaining attempts.
+//!
+//! Tracker state is only touched from the main checkout: beads changes made
+//! inside a worktree are discarded, and the harness closes tasks itself once
+//! their branch has merged. Verification, when configured, runs inside the
+//! worktree before the branch is considered for merging.
+
+use anyhow::Result;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::path::{Path, PathBuf};
+use tokio::sync::mpsc;
+use tokio::task::JoinSet;
+
+use crate::claude_client::{ClaudeClient, StopReason};
+use crate::events::{self, say, LoopEvent};
+use crate::git::{self, MergeOutcome};
+use crate::knowledge::{self, KnowledgeStore};
+use crate::progress;
+use crate::task_parser::{parse_task, ParsedTask};
+use crate::tracker::Tracker;
+use crate::transcript::{ResumePoint, Transcript};
+use crate::triage::{Action, Failure};
+use crate::verify::{self, Verifier};
+use crate::{build_system_prompt, build_task_prompt, clear_attempts, harness_closes, triage_task, RunSettings};
+
+const WORKTREE_DIR: &str = ".ralph/worktrees";
+const BRANCH_PREFIX: &str = "ralph/";
+
+/// What one worktree run produced
+struct WorktreeRun {
+    parsed: ParsedTask,
+    branch: String,
+    stop_reason: StopReason,
+    /// Completed and passed verification (or none was configured)
+    verified: bool,
+    close_reason: String,
+    modified_files: Vec<String>,
+}
+
+/// Branch used for a task's worktree
+pub fn task_branch(task_id: &str) -> String {
+    format!("{}{}", BRANCH_PREFIX, sanitize(task_id))
+}
+
+fn worktree_path(project_root: &Path, task_id: &str) -> PathBuf {
+    project_root.join(WORKTREE_DIR).join(sanitize(task_id))
+}
+
+fn sanitize(task_id: &str) -> String {
+    task_id
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() || c == '-' || c == '_' || c == '.' { c } else { '-' })
+        .collect()
+}
+
+/// Pick up to `limit` ready tasks that aren't running or excluded
+fn next_ready_tasks(
+    tracker: &dyn Tracker,
+    limit: usize,
+    exclude: &HashSet<String>,
+) -> Result<Vec<ParsedTask>> {
+    let mut picked = Vec::new();
+
+    for task in tracker.ready_tasks()? {
+        if picked.len() >= limit {
+            break;
+        }
+        if task.status == "closed" || task.status == "completed" || exclude.contains(&task.id) {
+            continue;
+        }
+
+        let parsed = parse_task(&task);
+        if !parsed.all_complete || parsed.acceptance_criteria.is_empty() {
+            picked.push(parsed);
+        }
+    }
+
+    Ok(picked)
+}
+
+/// Run the loop with up to `parallel` tasks at once
+pub async fn run_parallel(
+    project_root: &Path,
+    settings: &RunSettings,
+    parallel: usize,
+    max_tasks: Option<usize>,
+    fail_streak_limit: usize,
+) -> Result<()> {
+    let parallel = parallel.max(1);
+    let tracker = settings.tracker.as_ref();
+
+    // Keep worktrees out of `git add -A` in the main checkout
+    let worktree_root = project_root.join(WORKTREE_DIR);
+    std::fs::create_dir_all(&worktree_root)?;
+    std::fs::write(worktree_root.join(".gitignore"), "*\n")?;
+
+    say!("\n========================================");
+    say!("  RALPH LOOP - PARALLEL MODE");
+    say!("  Backend: {} ({})", settings.backend.name(), settings.backend.model());
+    say!("  Concurrent tasks: {}", parallel);
+    say!("  Max iterations per task: {}", settings.max_iterations);
+    say!("  Redline threshold: {} tokens", settings.redline_threshold);
+    say!("  Fail streak limit: {}", fail_streak_limit);
+    say!("========================================\n");
+
+    let mut running: JoinSet<Result<WorktreeRun>> = JoinSet::new();
+    // Task ID and title behind each spawned run, so a panicked one can be parked
+    let mut spawned: HashMap<tokio::task::Id, (String, String)> = HashMap::new();
+    let mut active: HashSet<String> = HashSet::new();
+    // Tasks that won't be picked again this session
+    let mut done: HashSet<String> = HashSet::new();
+    // Incomplete tasks waiting for another attempt on their branch
+    let mut retry: VecDeque<ParsedTask> = VecDeque::new();
+    // Tasks escalated to another model
+    let mut escalated: HashMap<String, RunSettings> = HashMap::new();
+
+    let mut started = 0usize;
+    let mut tasks_completed = 0usize;
+    let mut consecutive_failures = 0usize;
+    let mut needs_attention: Vec<(String, String)> = Vec::new();
+
+    loop {
+        // Fill free slots, unless we're winding down
+        let stopping = consecutive_failures >= fail_streak_limit
+            || max_tasks.is_some_and(|max| started >= max)
+            || settings.costs.exceeded(None).is_some();
+
+        if !stopping {
+            let mut free = parallel - running.len();
+            let mut batch = Vec::new();
+            while free > 0 {
+                let Some(parsed) = retry.pop_front() else { break };
+                batch.push(parsed);
+                free -= 1;
+            }
+            if free > 0 {
+                let mut exclude = active.clone();
+                exclude.extend(done.iter().cloned());
+                exclude.extend(batch.iter().map(|p| p.task.id.clone()));
+                exclude.extend(retry.iter().map(|p| p.task.id.clone()));
+                batch.extend(next_ready_tasks(tracker, free, &exclude)?);
+            }
+
+            for parsed in batch {
+                if max_tasks.is_some_and(|max| started >= max) {
+                    break;
+                }
+                let id = parsed.task.id.clone();
+                let attempt = settings.triage.history(&id).map_or(0, |h| h.len()) + 1;
+                tracker.start_task(&id)?;
+                say!("→ [{}] Starting in worktree (attempt {}): {}", id, attempt, parsed.task.title);
+
+                active.insert(id.clone());
+                started += 1;
+                let title = parsed.task.title.clone();
+                let root = project_root.to_path_buf();
+                let settings = escalated.get(&id).unwrap_or(settings).clone();
+                let handle = running.spawn(async move { run_in_worktree(&root, parsed, &settings).await });
+                spawned.insert(handle.id(), (id, title));
+            }
+        }
+
+        // Nothing running and nothing to start: done
+        let Some(joined) = running.join_next_with_id().await else {
+            break;
+        };
+        let (task, result) = match joined {
+            Ok((task, result)) => (task, Ok(result)),
+            Err(e) => (e.id(), Err(e)),
+        };
+        let Some((id, title)) = spawned.remove(&task) else {
+            continue;
+        };
+        active.remove(&id);
+
+        // A panicked run never got to save its work; park it and keep draining the rest
+        let result = match result {
+            Ok(result) => result,
+            Err(e) => {
+                let worktree = worktree_path(project_root, &id);
+                if worktree.exists() {
+                    if let Err(e) = save_and_remove_worktree(project_root, &worktree, &id, &title) {
+                        tracing::warn!("[{}] Failed to clean up worktree: {}", id, e);
+                    }
+                }
+                let reason = format!("run panicked: {}", e);
+                mark_needs_attention(tracker, &id, &task_branch(&id), &reason);
+                needs_attention.push((id.clone(), reason));
+                done.insert(id);
+                consecutive_failures += 1;
+                continue;
+            }
+        };
+
+        match result {
+            Ok(run) if run.verified => {
+                let message = format!("Merge task {}: {}", id, run.parsed.task.title);
+                match git::merge_branch(project_root, &run.branch, &message) {
+                    Ok(MergeOutcome::Merged) | Ok(MergeOutcome::UpToDate) => {
+                        let _ = git::delete_branch(project_root, &run.branch);
+                        tracker.complete_task(&id, Some(&run.close_reason))?;
+                        clear_attempts(settings, &id);
+                        if !run.modified_files.is_empty() {
+                            let summary = format!("Completed task: {}", run.parsed.task.title);
+                            if let Err(e) =
+                                knowledge::record_outcome(project_root, &id, &summary, &run.modified_files)
+                            {
+                                tracing::warn!("Failed to record progress: {}", e);
+                            }
+                        }
+                        if settings.auto_sync {
+                            tracker.sync()?;
+                        }
+                        tasks_completed += 1;
+                        consecutive_failures = 0;
+                        done.insert(id.clone());
+                        say!("✅ [{}] Completed and merged {}", id, run.branch);
+                    }
+                    Ok(MergeOutcome::Conflict(files)) => {
+                        let reason = format!("merge conflict in {}", files.join(", "));
+                        mark_needs_attention(tracker, &id, &run.branch, &reason);
+                        needs_attention.push((id.clone(), reason));
+                        done.insert(id.clone());
+                    }
+                    Err(e) => {
+                        let reason = format!("merge failed: {}", e);
+                        mark_needs_attention(tracker, &id, &run.branch, &reason);
+                        needs_attention.push((id.clone(), reason));
+                        done.insert(id.clone());
+                    }
+                }
+            }
+            Ok(run) if run.stop_reason == StopReason::BudgetExceeded => {
+                match settings.costs.exceeded(Some(&id)) {
+                    Some(exceeded) if !exceeded.stops_loop() => {
+                        let reason = exceeded.to_string();
+                        mark_needs_attention(tracker, &id, &run.branch, &reason);
+                        needs_attention.push((id.clone(), reason));
+                    }
+                    _ => say!("💸 [{}] Stopped on budget; partial progress is on {}", id, run.branch),
+                }
+                done.insert(id.clone());
+            }
+            Ok(run) => {
+                // Partial work is committed on the branch; triage decides whether to go on from there
+                let failure = match run.stop_reason {
+                    StopReason::Completed => Failure::VerificationFailed,
+                    StopReason::Redline => Failure::Redline,
+                    _ => Failure::MaxIterations,
+                };
+                say!("⚠️  [{}] Partial progress on {}: {}", id, run.branch, failure);
+                let task_settings = escalated.remove(&id).unwrap_or_else(|| settings.clone());
+                let triaged = triage_task(project_root, &task_settings, &run.parsed, failure, "").await;
+                retry_or_park(triaged, run.parsed, &mut retry, &mut escalated, &mut needs_attention, &mut consecutive_failures);
+            }
+            Err(e) => {
+                tracing::error!("Task {} failed: {}", id, e);
+                say!("❌ [{}] FAILED: {}", id, e);
+                let parsed = tracker.get_task(&id).map(|task| parse_task(&task));
+                match parsed {
+                    Ok(parsed) => {
+                        let task_settings = escalated.remove(&id).unwrap_or_else(|| settings.clone());
+                        let triaged = triage_task(project_root, &task_settings, &parsed, Failure::of(&e), &e.to_string()).await;
+                        retry_or_park(triaged, parsed, &mut retry, &mut escalated, &mut needs_attention, &mut consecutive_failures);
+                    }
+                    Err(_) => {
+                        consecutive_failures += 1;
+                        done.insert(id.clone());
+                    }
+                }
+            }
+        }
+        for parked in needs_attention.iter().map(|(id, _)| id) {
+            done.insert(parked.clone());
+        }
+
+        if consecutive_failures >= fail_streak_limit && !running.is_empty() {
+            say!(
+                "\nStopping: {} consecutive failures (limit: {}). Waiting for {} running task(s)...",
+                consecutive_failures,
+                fail_streak_limit,
+                running.len()
+            );
+        }
+    }
+
+    say!("\n========================================");
+    say!("  PARALLEL LOOP COMPLETE");
+    say!("  Tasks completed: {}", tasks_completed);
+    say!("  Estimated spend: ${:.4}", settings.costs.run_total());
+    if let Some(exceeded) = settings.costs.exceeded(None) {
+        say!("  Stopped: {}", exceeded);
+    }
+    if !needs_attention.is_empty() {
+        say!("  Needs attention:");
+        for (id, reason) in &needs_attention {
+            say!("    {} ({}) - branch {}", id, reason, task_branch(id));
+        }
+    }
+    say!("========================================\n");
+
+    Ok(())
+}
+
+/// Run one task's agentic loop in its own worktree and commit the result
+async fn run_in_worktree(
+    project_root: &Path,
+    parsed: ParsedTask,
+    settings: &RunSettings,
+) -> Result<WorktreeRun> {
+    let id = parsed.task.id.clone();
+    let branch = task_branch(&id);
+    let worktree = worktree_path(project_root, &id);
+
+    // Clean up after a crashed run
+    if worktree.exists() {
+        let _ = git::remove_worktree(project_root, &worktree);
+        let _ = std::fs::remove_dir_all(&worktree);
+    }
+    git::add_worktree(project_root, &worktree, &branch)?;
+
+    let (tx, rx) = mpsc::channel::<LoopEvent>(100);
+    let printer = tokio::spawn(print_prefixed(id.clone(), rx));
+
+    let result = async {
+        let verifier = Verifier::for_task(&settings.verify, settings.require_tests, &worktree, &parsed, settings.sandbox.as_ref())?;
+        let client = ClaudeClient::new(settings.backend.clone(), &worktree)
+            .with_transcript(Transcript::new(project_root, &id))
+            .with_compaction(settings.compactor.clone())
+            .with_sandbox(settings.sandbox.clone())
+            // Shared by every worker; the servers themselves run in the project root
+            .with_mcp(settings.mcp.clone())
+            .with_costs(settings.costs.meter(&id))
+            .defer_task_close(harness_closes(settings, verifier.is_some()));
+        verify::run_verified(
+            &client,
+            verifier.as_ref(),
+            &build_system_prompt(&worktree, &KnowledgeStore::new(project_root), &parsed).await,
+            ResumePoint::fresh(&build_task_prompt(&parsed)),
+            settings.max_iterations,
+            settings.redline_threshold,
+            Some(tx),
+        )
+        .await
+    }
+    .await;
+    let _ = printer.await;
+
+    // Lessons the model saved go to the main store, not onto the branch
+    let store = KnowledgeStore::new(project_root);
+    let learned = store
+        .absorb(&KnowledgeStore::new(&worktree))
+        .and_then(|_| match &result {
+            Ok(run) => knowledge::record_verified(project_root, run),
+            Err(_) => Ok(()),
+        });
+    if let Err(e) = learned {
+        tracing::warn!("[{}] Failed to record what was learned: {}", id, e);
+    }
+
+    // Whatever happened, keep the work on the branch and drop the worktree
+    let saved = save_and_remove_worktree(project_root, &worktree, &id, &parsed.task.title);
+
+    let run = result?;
+    saved?;
+
+    let close_reason = match &run.report {
+        Some(_) => run.close_reason(),
+        None => format!("Completed by ralph on {}", branch),
+    };
+    Ok(WorktreeRun {
+        modified_files: progress::extract_modified_files(&run.result.tool_outputs),
+        verified: run.succeeded(),
+        stop_reason: run.result.stop_reason,
+        close_reason,
+        parsed,
+        branch,
+    })
+}
+
+/// Commit a worktree's changes to its branch, minus tracker and knowledge
+/// files, then remove the worktree
+fn save_and_remove_worktree(project_root: &Path, worktree: &Path, task_id: &str, title: &str) -> Result<()> {
+    let committed = git::discard_changes(worktree, ".beads")
+        .and_then(|_| git::discard_changes(worktree, knowledge::KNOWLEDGE_FILE))
+        .and_then(|_| git::auto_commit_task(worktree, task_id, title));
+    let removed = git::remove_worktree(project_root, worktree);
+    committed?;
+    removed
+}
+
+/// Queue a triaged task for another run, or park it
+fn retry_or_park(
+    triaged: Result<(crate::triage::Attempt, Option<RunSettings>)>,
+    parsed: ParsedTask,
+    retry: &mut VecDeque<ParsedTask>,
+    escalated: &mut HashMap<String, RunSettings>,
+    needs_attention: &mut Vec<(String, String)>,
+    consecutive_failures: &mut usize,
+) {
+    let id = parsed.task.id.clone();
+    match triaged {
+        Ok((attempt, Some(next))) => {
+            say!("🔄 [{}] {}", id, attempt.note());
+            if matches!(attempt.action, Action::Escalate(_)) {
+                escalated.insert(id, next);
+            }
+            retry.push_back(parsed);
+        }
+        Ok((attempt, None)) if attempt.action == Action::Decompose => {
+            say!("🪓 [{}] {}", id, attempt.note());
+            *consecutive_failures = 0;
+        }
+        Ok((attempt, None)) => {
+            say!("🛑 [{}] Needs attention: {} (branch {})", id, attempt.note(), task_branch(&id));
+            needs_attention.push((id, attempt.note()));
+            *consecutive_failures += 1;
+        }
+        Err(e) => {
+            say!("❌ [{}] Triage failed: {}", id, e);
+            *consecutive_failures += 1;
+        }
+    }
+}
+
+/// Print streamed output a line at a time, prefixed with the task ID
+async fn print_prefixed(task_id: String, mut rx: mpsc::Receiver<LoopEvent>) {
+    let mut buffer = String::new();
+    while let Some(event) = rx.recv().await {
+        if events::json_stdout() {
+            println!("{}", event.to_json_line(&task_id));
+            continue;
+        }
+        buffer.push_str(&event.to_string());
+        while let Some(pos) = buffer.find('\n') {
+            let line: String = buffer.drain(..=pos).collect();
+            let line = line.trim_end();
+            if !line.is_empty() {
+                println!("[{}] {}", task_id, line);
+            }
+        }
+    }
+    if !buffer.trim().is_empty() {
+        println!("[{}] {}", task_id, buffer.trim_end());
+    }
+}
+
+/// Park a task for a human: mark it blocked and say which branch to look at
+fn mark_needs_attention(tracker: &dyn Tracker, task_id: &str, branch: &str, reason: &str) {
+    say!("🛑 [{}] Needs attention: {} (branch {})", task_id, reason, branch);
+    if let Err(e) = tracker.block_task(task_id) {
+        tracing::warn!("Failed to mark {} blocked: {}", task_id, e);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_task_branch_and_worktree_names() {
+        assert_eq!(task_branch("proj-a1b.2"), "ralph/proj-a1b.2");
+        assert_eq!(task_branch("weird id/x"), "ralph/weird-id-x");
+        assert_eq!(
+            worktree_path(Path::new("/repo"), "proj-1"),
+            PathBuf::from("/repo/.ralph/worktrees/proj-1")
+        );
+    }
+}
@@ -1,21 +1,35 @@
-//! The Six Primitives - Minimum viable toolbelt for agentic coding
+//! The Primitives - Minimum viable toolbelt for agentic coding
 //!
 //! 1. read_file - Read file contents
 //! 2. list_files - List directory contents
 //! 3. bash - Execute shell commands with timeout
+//!    process - Background commands such as dev servers
 //! 4. edit_file - Modify files with uniqueness check
 //! 5. code_search - Ripgrep wrapper for pattern search
+//!    find_symbol / find_references - Definitions and uses from the symbol index
 //! 6. beads - Issue tracker operations (show, update, close, ready)
+//! 7. remember - Save a lesson to the knowledge store for later tasks
 
 use anyhow::{Context, Result};
 use serde::{Deserialize, Serialize};
 use std::path::Path;
 use std::process::Stdio;
+use std::sync::LazyLock;
 use std::time::Duration;
+use tachikoma_primitives::edit_file::write_atomic;
+use tachikoma_primitives::read_file::ReadFileError;
+use tachikoma_primitives::{
+    bash_start_command, BashResult, CodeSearchResult, EditFileResult, ListFilesResult, MultiEditResult, PrimitiveContext, ProcessResult, ProcessTable,
+    PrimitiveError, PrimitiveRegistry, ReadFileResult, SymbolKind, SymbolSearchResult,
+};
 use tokio::io::AsyncReadExt;
 use tokio::process::Command;
 use tokio::time::timeout;
 
+use crate::beads;
+use crate::knowledge::{Entry, Kind, KnowledgeStore};
+use crate::sandbox::{Sandbox, Violation};
+
 /// Tool definition for Claude API
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ToolDefinition {
@@ -30,6 +44,9 @@ pub struct ToolResult {
     pub success: bool,
     pub output: String,
     pub error: Option<String>,
+    /// Set when a sandboxed command failed because of a sandbox rule
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub violation: Option<Violation>,
 }
 
 impl ToolResult {
@@ -38,6 +55,7 @@ impl ToolResult {
             success: true,
             output: output.into(),
             error: None,
+            violation: None,
         }
     }
 
@@ -46,123 +64,53 @@ impl ToolResult {
             success: false,
             output: String::new(),
             error: Some(error.into()),
+            violation: None,
+        }
+    }
+
+    /// A command that broke a sandbox rule, with what it printed
+    pub fn sandbox_violation(violation: Violation, output: impl Into<String>) -> Self {
+        let output = output.into();
+        Self {
+            success: false,
+            error: Some(format!("{}\n\n{}", violation, output)),
+            output,
+            violation: Some(violation),
         }
     }
 }
 
+/// What ralph adds to the registry's descriptions
+const GUIDANCE: &[(&str, &str)] = &[
+    ("read_file", "For large files (>500 lines), use start_line/end_line to read specific sections instead of the whole file."),
+    ("list_files", "WARNING: Recursive listings stop at 50 entries - use targeted paths instead of broad recursive listings."),
+    ("bash", "Use it for build/test/git commands: exploring with find, grep -r, cat, head, tail, tree or ls -R is blocked in favour of the other tools. The timeout is at most 600 seconds."),
+    ("process", "Start servers and watchers with it instead of backgrounding them with & in bash, wait for the line saying they're ready, and kill them when done. They are killed when the session ends."),
+    ("edit_file", "To create a file, pass an empty old_string. Returns a diff of the change."),
+    ("multi_edit", "Prefer it to several edit_file calls for a refactor: if any edit fails, no file is changed. Returns a diff of every change."),
+    ("find_symbol", "Prefer it to code_search when you know the name of what you're looking for."),
+    ("find_references", "Use it to see what a change to a function or type would affect."),
+];
+
 /// Get all tool definitions for Claude API
 pub fn get_tool_definitions() -> Vec<ToolDefinition> {
-    vec![
-        ToolDefinition {
-            name: "read_file".to_string(),
-            description: "Read the contents of a file at the given path. For large files (>500 lines), use start_line/end_line to read specific sections instead of the whole file.".to_string(),
-            input_schema: serde_json::json!({
-                "type": "object",
-                "properties": {
-                    "path": {
-                        "type": "string",
-                        "description": "The path to the file to read"
-                    },
-                    "start_line": {
-                        "type": "integer",
-                        "description": "Read from this line (1-indexed). Use with end_line for targeted reads of large files."
-                    },
-                    "end_line": {
-                        "type": "integer",
-                        "description": "Read until this line (inclusive). Omit to read to end of file."
-                    }
-                },
-                "required": ["path"]
-            }),
-        },
-        ToolDefinition {
-            name: "list_files".to_string(),
-            description: "List files and directories at the given path. Returns a list of entries with their types. WARNING: Recursive mode is limited to 50 entries - use targeted paths instead of broad recursive searches.".to_string(),
-            input_schema: serde_json::json!({
-                "type": "object",
-                "properties": {
-                    "path": {
-                        "type": "string",
-                        "description": "The directory path to list (be specific, avoid root-level recursive listing)"
-                    },
-                    "recursive": {
-                        "type": "boolean",
-                        "description": "Whether to list recursively (default: false). Limited to 50 entries - prefer non-recursive with specific paths."
-                    }
-                },
-                "required": ["path"]
-            }),
-        },
-        ToolDefinition {
-            name: "bash".to_string(),
-            description: "Execute a bash command. Returns stdout, stderr, and exit code. Has a timeout of 120 seconds by default.".to_string(),
-            input_schema: serde_json::json!({
-                "type": "object",
-                "properties": {
-                    "command": {
-                        "type": "string",
-                        "description": "The bash command to execute"
-                    },
-                    "timeout_secs": {
-                        "type": "integer",
-                        "description": "Timeout in seconds (default: 120, max: 600)"
-                    },
-                    "cwd": {
-                        "type": "string",
-                        "description": "Working directory for the command"
-                    }
-                },
-                "required": ["command"]
-            }),
-        },
-        ToolDefinition {
-            name: "edit_file".to_string(),
-            description: "Edit a file by replacing old_string with new_string. The old_string must be unique in the file (appear exactly once). For creating new files, use old_string as empty string.".to_string(),
-            input_schema: serde_json::json!({
-                "type": "object",
-                "properties": {
-                    "path": {
-                        "type": "string",
-                        "description": "The path to the file to edit"
-                    },
-                    "old_string": {
-                        "type": "string",
-                        "description": "The exact string to find and replace (must be unique). Empty string for new file."
-                    },
-                    "new_string": {
-                        "type": "string",
-                        "description": "The string to replace old_string with"
-                    }
-                },
-                "required": ["path", "old_string", "new_string"]
-            }),
-        },
-        ToolDefinition {
-            name: "code_search".to_string(),
-            description: "Search for a pattern in the codebase using ripgrep. Returns matching lines with file paths and line numbers.".to_string(),
-            input_schema: serde_json::json!({
-                "type": "object",
-                "properties": {
-                    "pattern": {
-                        "type": "string",
-                        "description": "The regex pattern to search for"
-                    },
-                    "path": {
-                        "type": "string",
-                        "description": "The directory to search in (default: current directory)"
-                    },
-                    "file_pattern": {
-                        "type": "string",
-                        "description": "Glob pattern to filter files (e.g., '*.rs', '*.ts')"
-                    },
-                    "max_results": {
-                        "type": "integer",
-                        "description": "Maximum number of results to return (default: 50)"
-                    }
-                },
-                "required": ["pattern"]
-            }),
-        },
+    let mut tools: Vec<ToolDefinition> = REGISTRY
+        .mcp_tools()
+        .into_iter()
+        .map(|tool| {
+            let description = match GUIDANCE.iter().find(|(name, _)| *name == tool.name) {
+                Some((_, guidance)) => format!("{} {}", tool.description, guidance),
+                None => tool.description,
+            };
+            ToolDefinition {
+                name: tool.name,
+                description,
+                input_schema: tool.input_schema,
+            }
+        })
+        .collect();
+
+    tools.extend([
         ToolDefinition {
             name: "beads".to_string(),
             description: "Interact with the beads issue tracker. Actions: 'ready' (list unblocked tasks), 'show' (get task details), 'update' (change task status), 'close' (mark task complete), 'sync' (commit beads changes), 'create' (create new task), 'decompose' (break large task into subtasks).".to_string(),
@@ -216,18 +164,58 @@ pub fn get_tool_definitions() -> Vec<ToolDefinition> {
                 "required": ["action"]
             }),
         },
-    ]
+        ToolDefinition {
+            name: "remember".to_string(),
+            description: "Save a lesson for future tasks on this project: a pitfall to avoid, a note about a file, or a build/test command that works. Later tasks that touch the same files or topics see it in their prompt. Only save what isn't obvious from the code.".to_string(),
+            input_schema: serde_json::json!({
+                "type": "object",
+                "properties": {
+                    "kind": {
+                        "type": "string",
+                        "enum": ["pitfall", "file_note", "command"],
+                        "description": "What the lesson is"
+                    },
+                    "text": {
+                        "type": "string",
+                        "description": "The lesson in one or two sentences, or the exact command"
+                    },
+                    "files": {
+                        "type": "array",
+                        "items": {"type": "string"},
+                        "description": "Files the lesson is about (required for file_note)"
+                    }
+                },
+                "required": ["kind", "text"]
+            }),
+        },
+    ]);
+    tools
 }
 
 /// Execute a tool call
-pub async fn execute_tool(name: &str, input: &serde_json::Value, project_root: &Path) -> ToolResult {
+///
+/// `sandbox` isolates `bash` commands when the project enables it;
+/// `processes` holds the session's background processes.
+pub async fn execute_tool(
+    name: &str,
+    input: &serde_json::Value,
+    project_root: &Path,
+    sandbox: Option<&Sandbox>,
+    processes: &ProcessTable,
+) -> ToolResult {
+    let ctx = PrimitiveContext {
+        processes: processes.clone(),
+        ..PrimitiveContext::new(project_root.to_path_buf())
+    };
     match name {
-        "read_file" => read_file(input, project_root).await,
-        "list_files" => list_files(input, project_root).await,
-        "bash" => bash(input, project_root).await,
-        "edit_file" => edit_file(input, project_root).await,
-        "code_search" => code_search(input, project_root).await,
+        "bash" => bash(input, &ctx, sandbox).await,
+        "process" => process(input, &ctx, sandbox).await,
+        "edit_file" => edit_file(input, &ctx).await,
+        "read_file" | "list_files" | "code_search" | "multi_edit" | "find_symbol" | "find_references" => {
+            run_primitive(name, input.clone(), &ctx).await
+        }
         "beads" => beads(input, project_root).await,
+        "remember" => remember(input, project_root),
         _ => ToolResult::error(format!("Unknown tool: {}", name)),
     }
 }
@@ -236,222 +224,230 @@ pub async fn execute_tool(name: &str, input: &serde_json::Value, project_root: &
 // Tool Implementations
 // ============================================================================
 
-/// 1. read_file - Read file contents with optional line range
-async fn read_file(input: &serde_json::Value, project_root: &Path) -> ToolResult {
-    let path_str = match input.get("path").and_then(|v| v.as_str()) {
-        Some(p) => p,
-        None => return ToolResult::error("Missing required parameter: path"),
-    };
+/// The five file and shell primitives, shared with `tachikoma mcp serve`
+static REGISTRY: LazyLock<PrimitiveRegistry> = LazyLock::new(PrimitiveRegistry::with_defaults);
 
-    let path = resolve_path(path_str, project_root);
-    
-    // Optional line range (1-indexed)
-    let start_line = input.get("start_line").and_then(|v| v.as_u64()).map(|l| l as usize);
-    let end_line = input.get("end_line").and_then(|v| v.as_u64()).map(|l| l as usize);
-
-    match tokio::fs::read_to_string(&path).await {
-        Ok(content) => {
-            // If line range specified, extract those lines
-            if start_line.is_some() || end_line.is_some() {
-                let lines: Vec<&str> = content.lines().collect();
-                let total_lines = lines.len();
-                
-                let start = start_line.unwrap_or(1).saturating_sub(1); // Convert to 0-indexed
-                let end = end_line.unwrap_or(total_lines).min(total_lines);
-                
-                if start >= total_lines {
-                    return ToolResult::error(format!(
-                        "start_line {} exceeds file length ({} lines)",
-                        start + 1,
-                        total_lines
-                    ));
-                }
-                
-                let selected: Vec<String> = lines[start..end]
-                    .iter()
-                    .enumerate()
-                    .map(|(i, line)| format!("{:4}| {}", start + i + 1, line))
-                    .collect();
-                
-                return ToolResult::success(format!(
-                    "Lines {}-{} of {} total:\n\n{}",
-                    start + 1,
-                    end,
-                    total_lines,
-                    selected.join("\n")
-                ));
+/// Run one of the registry's primitives and render its result for the model
+async fn run_primitive(name: &str, mut input: serde_json::Value, ctx: &PrimitiveContext) -> ToolResult {
+    // Keep broad calls from burning the context
+    if let Some(fields) = input.as_object_mut() {
+        match name {
+            "list_files" if fields.get("recursive").and_then(|v| v.as_bool()) == Some(true) => {
+                fields.entry("limit").or_insert(RECURSIVE_LIST_LIMIT.into());
             }
-            
-            // Full file read - truncate if too large (avoid blowing up context)
-            let max_size = 100_000; // ~100KB
-            let lines: Vec<&str> = content.lines().collect();
-            
-            if content.len() > max_size {
-                // Safe truncation at character boundary
-                let mut end = max_size;
-                while !content.is_char_boundary(end) && end > 0 {
-                    end -= 1;
+            "code_search" => {
+                fields.entry("max_matches").or_insert(SEARCH_LIMIT.into());
+            }
+            "find_symbol" | "find_references" => {
+                fields.entry("max_results").or_insert(SEARCH_LIMIT.into());
+            }
+            "bash" | "process" => {
+                if let Some(secs) = fields.get("timeout_secs").and_then(|v| v.as_u64()) {
+                    fields.insert("timeout_secs".to_string(), secs.min(MAX_BASH_TIMEOUT_SECS).into());
                 }
-                let truncated = &content[..end];
-                ToolResult::success(format!(
-                    "{}\n\n[Truncated: file is {} bytes ({} lines), showing first {} bytes]\n\
-                    [TIP: Use start_line/end_line to read specific sections]",
-                    truncated,
-                    content.len(),
-                    lines.len(),
-                    max_size
-                ))
-            } else {
-                ToolResult::success(content)
             }
+            _ => {}
         }
-        Err(e) => ToolResult::error(format!("Failed to read file {}: {}", path.display(), e)),
     }
-}
 
-/// 2. list_files - List directory contents
-async fn list_files(input: &serde_json::Value, project_root: &Path) -> ToolResult {
-    let path_str = match input.get("path").and_then(|v| v.as_str()) {
-        Some(p) => p,
-        None => return ToolResult::error("Missing required parameter: path"),
+    let output = match REGISTRY.execute(name, ctx, input).await {
+        Ok(output) => output,
+        Err(e) => return ToolResult::error(describe_error(&e)),
     };
 
-    let recursive = input.get("recursive").and_then(|v| v.as_bool()).unwrap_or(false);
-    let path = resolve_path(path_str, project_root);
+    let rendered = match name {
+        "read_file" => serde_json::from_value(output).map(render_read),
+        "list_files" => serde_json::from_value(output).map(render_list),
+        "bash" => serde_json::from_value(output).map(render_bash),
+        "process" => serde_json::from_value(output).map(render_process),
+        "edit_file" => serde_json::from_value(output).map(render_edit),
+        "code_search" => serde_json::from_value(output).map(render_search),
+        "multi_edit" => serde_json::from_value(output).map(render_multi_edit),
+        "find_symbol" | "find_references" => serde_json::from_value(output).map(render_symbols),
+        _ => return ToolResult::error(format!("Unknown tool: {}", name)),
+    };
+    match rendered {
+        Ok(result) => result,
+        Err(e) => ToolResult::error(format!("Unexpected {} output: {}", name, e)),
+    }
+}
 
-    if recursive {
-        list_files_recursive(&path).await
-    } else {
-        list_files_flat(&path).await
+/// A primitive's error, with the hints the crate attaches
+fn describe_error(error: &PrimitiveError) -> String {
+    match error {
+        PrimitiveError::ReadFile(ReadFileError::NotFound { path, suggestion: Some(similar) }) => {
+            format!("File not found: {}\nDid you mean {}?", path.display(), similar)
+        }
+        PrimitiveError::NotUnique { count, details } => format!(
+            "old_string is not unique - found {} matches. Add more context to make it unique.\n\n{}",
+            count, details
+        ),
+        PrimitiveError::TargetNotFound => {
+            "old_string not found in file. Make sure it matches exactly (including whitespace).".to_string()
+        }
+        PrimitiveError::EditFailed { edit, path, source } => {
+            format!("Edit {} ({}) failed, no file was changed: {}", edit, path.display(), describe_error(source))
+        }
+        other => other.to_string(),
     }
 }
 
-/// Directories to always skip when listing files
-const IGNORED_DIRS: &[&str] = &[
-    "node_modules", ".git", "target", ".next", "dist", "__pycache__",
-    "bin", "obj", ".cache", ".pytest_cache", "build", "coverage",
-    ".nuxt", ".output", ".turbo", "venv", ".venv", "vendor",
-    ".idea", ".vscode", ".vs", "packages", ".angular",
-];
+fn render_read(result: ReadFileResult) -> ToolResult {
+    if !result.truncated && result.content.len() <= MAX_READ_BYTES {
+        return ToolResult::success(result.content);
+    }
+    ToolResult::success(format!(
+        "{}\n\n[TIP: Use start_line/end_line to read specific sections]",
+        truncate_output(result.content, MAX_READ_BYTES)
+    ))
+}
 
-/// File patterns to skip
-const IGNORED_FILES: &[&str] = &[
-    ".DS_Store", "Thumbs.db", ".gitkeep",
-];
+fn render_list(result: ListFilesResult) -> ToolResult {
+    let mut entries: Vec<String> = result
+        .entries
+        .iter()
+        .map(|entry| {
+            let relative = entry.path.strip_prefix(&result.base_path).unwrap_or(&entry.path);
+            if entry.is_dir {
+                format!("dir\t{}/", relative.display())
+            } else {
+                format!("file\t{}", relative.display())
+            }
+        })
+        .collect();
+    entries.sort();
 
-fn should_skip_entry(name: &str, is_dir: bool) -> bool {
-    if is_dir {
-        IGNORED_DIRS.contains(&name)
-    } else {
-        IGNORED_FILES.contains(&name)
+    if result.truncated {
+        entries.push(format!(
+            "\n[TRUNCATED at {} of {} entries - Use targeted paths instead of recursive listing]",
+            result.entries.len(),
+            result.total_count
+        ));
     }
+    ToolResult::success(entries.join("\n"))
 }
 
-async fn list_files_flat(path: &Path) -> ToolResult {
-    let mut entries = Vec::new();
+fn render_bash(result: BashResult) -> ToolResult {
+    let mut combined = format!(
+        "Exit code: {}\n\nSTDOUT:\n{}\n\nSTDERR:\n{}",
+        result.exit_code, result.stdout, result.stderr
+    );
+    if result.stdout_truncated {
+        combined.push_str(&format!("\n\n[stdout truncated: {} bytes in total]", result.stdout_total_bytes));
+    }
+    if result.stderr_truncated {
+        combined.push_str(&format!("\n\n[stderr truncated: {} bytes in total]", result.stderr_total_bytes));
+    }
+    let combined = truncate_output(combined, MAX_OUTPUT_BYTES);
+
+    if result.timed_out {
+        return ToolResult::error(format!(
+            "Command timed out after {} seconds\n\n{}",
+            result.metadata.duration.as_secs(),
+            combined
+        ));
+    }
+    ToolResult::success(combined)
+}
 
-    let mut dir = match tokio::fs::read_dir(path).await {
-        Ok(d) => d,
-        Err(e) => return ToolResult::error(format!("Failed to read directory: {}", e)),
+fn render_process(result: ProcessResult) -> ToolResult {
+    let mut output = match result.exit_code {
+        None => format!("Process {} is running: {}", result.id, result.command),
+        Some(code) => format!("Process {} exited with code {}: {}", result.id, code, result.command),
     };
+    match result.matched {
+        Some(true) => output.push_str("\nPattern found."),
+        Some(false) if result.running => output.push_str("\nPattern not found before the timeout."),
+        Some(false) => output.push_str("\nPattern not found before the process exited."),
+        None => {}
+    }
+    if result.dropped_bytes > 0 {
+        output.push_str(&format!("\n[{} bytes of older output were dropped]", result.dropped_bytes));
+    }
+    if !result.output.is_empty() {
+        output.push_str(&format!(
+            "\n\nOUTPUT (bytes {}-{}):\n{}",
+            result.offset, result.next_offset, result.output
+        ));
+    }
+    ToolResult::success(truncate_output(output, MAX_OUTPUT_BYTES))
+}
 
-    while let Ok(Some(entry)) = dir.next_entry().await {
-        let name = entry.file_name();
-        let name_str = name.to_string_lossy();
-        
-        let is_dir = entry.file_type().await.map(|ft| ft.is_dir()).unwrap_or(false);
-        
-        // Skip ignored entries
-        if should_skip_entry(&name_str, is_dir) {
-            continue;
-        }
+fn render_edit(result: EditFileResult) -> ToolResult {
+    ToolResult::success(truncate_output(
+        format!(
+            "Successfully edited {}. Replaced {} occurrence(s).\n\n{}",
+            result.path.display(),
+            result.replacements,
+            result.diff
+        ),
+        MAX_OUTPUT_BYTES,
+    ))
+}
 
-        let file_type = match entry.file_type().await {
-            Ok(ft) => {
-                if ft.is_dir() {
-                    "dir"
-                } else if ft.is_symlink() {
-                    "symlink"
-                } else {
-                    "file"
-                }
-            }
-            Err(_) => "unknown",
-        };
+fn render_multi_edit(result: MultiEditResult) -> ToolResult {
+    let verb = if result.dry_run { "Would change" } else { "Changed" };
+    let files: Vec<String> = result
+        .files
+        .iter()
+        .map(|file| format!("{} ({})", file.path.display(), format!("{:?}", file.kind).to_lowercase()))
+        .collect();
+    ToolResult::success(truncate_output(
+        format!("{} {} file(s): {}\n\n{}", verb, files.len(), files.join(", "), result.diff),
+        MAX_OUTPUT_BYTES,
+    ))
+}
 
-        entries.push(format!("{}\t{}", file_type, name_str));
+fn render_search(result: CodeSearchResult) -> ToolResult {
+    if result.matches.is_empty() {
+        return ToolResult::success("No matches found.");
+    }
+    let lines: Vec<String> = result
+        .matches
+        .iter()
+        .map(|m| format!("{}:{}: {}", m.path.display(), m.line_number, m.line_content.trim()))
+        .collect();
+    let mut output = format!("Found {} matches:\n\n{}", result.matches.len(), lines.join("\n"));
+    if result.truncated {
+        output.push_str(&format!("\n\n[Showing {} of {} matches]", result.matches.len(), result.total_count));
     }
+    ToolResult::success(truncate_output(output, MAX_OUTPUT_BYTES))
+}
 
-    entries.sort();
-    
-    // Limit entries to avoid context bloat
-    if entries.len() > 100 {
-        entries.truncate(100);
-        entries.push("[Truncated: showing first 100 entries]".to_string());
+fn render_symbols(result: SymbolSearchResult) -> ToolResult {
+    if result.matches.is_empty() {
+        return ToolResult::success(format!("No symbol named {} found.", result.query));
     }
-    
-    ToolResult::success(entries.join("\n"))
+    let lines: Vec<String> = result
+        .matches
+        .iter()
+        .map(|m| {
+            let what = match m.kind {
+                SymbolKind::Reference => String::new(),
+                kind => format!("{:?} ", kind).to_lowercase(),
+            };
+            let scope = m.scope.as_deref().map(|s| format!(" (in {})", s)).unwrap_or_default();
+            format!("{}:{}: {}{}{}: {}", m.path.display(), m.line_number, what, m.name, scope, m.line_content.trim())
+        })
+        .collect();
+    let mut output = format!("Found {} matches:\n\n{}", result.matches.len(), lines.join("\n"));
+    if result.truncated {
+        output.push_str(&format!("\n\n[Showing {} of {} matches]", result.matches.len(), result.total_count));
+    }
+    ToolResult::success(truncate_output(output, MAX_OUTPUT_BYTES))
 }
 
-/// Recursive limit - strict to prevent context burn
+/// Recursive listing limit - strict to prevent context burn
 const RECURSIVE_LIST_LIMIT: usize = 50;
 
-async fn list_files_recursive(path: &Path) -> ToolResult {
-    let mut entries = Vec::new();
-    let mut stack = vec![path.to_path_buf()];
-    let mut truncated = false;
-
-    while let Some(current) = stack.pop() {
-        let mut dir = match tokio::fs::read_dir(&current).await {
-            Ok(d) => d,
-            Err(_) => continue,
-        };
-
-        while let Ok(Some(entry)) = dir.next_entry().await {
-            let entry_path = entry.path();
-            let relative = entry_path.strip_prefix(path).unwrap_or(&entry_path);
-            let name = entry.file_name();
-            let name_str = name.to_string_lossy();
-
-            if let Ok(ft) = entry.file_type().await {
-                if ft.is_dir() {
-                    // Skip ignored directories
-                    if !should_skip_entry(&name_str, true) {
-                        stack.push(entry_path.clone());
-                        entries.push(format!("dir\t{}/", relative.display()));
-                    }
-                } else {
-                    // Skip ignored files
-                    if !should_skip_entry(&name_str, false) {
-                        entries.push(format!("file\t{}", relative.display()));
-                    }
-                }
-            }
-            
-            // Strict limit to prevent context burn
-            if entries.len() >= RECURSIVE_LIST_LIMIT {
-                truncated = true;
-                break;
-            }
-        }
+/// Matches returned by a search that doesn't ask for a number
+const SEARCH_LIMIT: usize = 50;
 
-        if truncated {
-            break;
-        }
-    }
+/// Longest timeout a command may ask for
+const MAX_BASH_TIMEOUT_SECS: u64 = 600;
 
-    entries.sort();
-    
-    if truncated {
-        entries.push(format!(
-            "\n[TRUNCATED at {} entries - Use targeted paths instead of recursive listing]\n\
-            TIP: Navigate to specific subdirectories rather than listing the whole tree.",
-            RECURSIVE_LIST_LIMIT
-        ));
-    }
-    
-    ToolResult::success(entries.join("\n"))
-}
+/// Full-file reads kept in the conversation, in bytes
+const MAX_READ_BYTES: usize = 100_000;
 
 /// Blocked bash command patterns - use dedicated tools instead
 const BLOCKED_BASH_PATTERNS: &[(&str, &str)] = &[
@@ -496,7 +492,8 @@ fn check_blocked_bash(command: &str) -> Option<String> {
 /// 3. bash - Execute shell commands with timeout
 /// 
 /// Blocks exploratory commands (find, grep -r, cat) in favor of dedicated tools.
-async fn bash(input: &serde_json::Value, project_root: &Path) -> ToolResult {
+/// With a sandbox, failures caused by its rules come back as violations.
+async fn bash(input: &serde_json::Value, ctx: &PrimitiveContext, sandbox: Option<&Sandbox>) -> ToolResult {
     let command = match input.get("command").and_then(|v| v.as_str()) {
         Some(c) => c,
         None => return ToolResult::error("Missing required parameter: command"),
@@ -507,21 +504,66 @@ async fn bash(input: &serde_json::Value, project_root: &Path) -> ToolResult {
         return ToolResult::error(error_msg);
     }
 
+    match sandbox {
+        Some(sandbox) => sandboxed_bash(command, input, &ctx.working_dir, sandbox).await,
+        None => run_primitive("bash", input.clone(), ctx).await,
+    }
+}
+
+/// 3b. process - Background commands
+///
+/// Started commands get the same checks as bash, and with a sandbox they are
+/// started inside its wrapper.
+async fn process(input: &serde_json::Value, ctx: &PrimitiveContext, sandbox: Option<&Sandbox>) -> ToolResult {
+    if input.get("action").and_then(|v| v.as_str()) == Some("start") {
+        let Some(command) = input.get("command").and_then(|v| v.as_str()) else {
+            return ToolResult::error("Missing required parameter: command");
+        };
+        if let Some(error_msg) = check_blocked_bash(command) {
+            return ToolResult::error(error_msg);
+        }
+        if let Some(sandbox) = sandbox {
+            return sandboxed_start(command, input, ctx, sandbox).await;
+        }
+    }
+    run_primitive("process", input.clone(), ctx).await
+}
+
+/// Start a background process inside the sandbox wrapper
+async fn sandboxed_start(command: &str, input: &serde_json::Value, ctx: &PrimitiveContext, sandbox: &Sandbox) -> ToolResult {
+    let cwd = input
+        .get("working_dir")
+        .and_then(|v| v.as_str())
+        .map(|p| resolve_path(p, &ctx.working_dir))
+        .unwrap_or_else(|| ctx.working_dir.clone());
+
+    let mut cmd = sandbox.command(command, &ctx.working_dir);
+    cmd.current_dir(&cwd);
+    match bash_start_command(ctx, command, cmd).await {
+        Ok(result) => render_process(result),
+        Err(e) => ToolResult::error(describe_error(&e)),
+    }
+}
+
+/// Run a command inside the sandbox wrapper
+///
+/// The registry's `bash` spawns `bash -c` directly, so sandboxed commands
+/// are started here.
+async fn sandboxed_bash(command: &str, input: &serde_json::Value, project_root: &Path, sandbox: &Sandbox) -> ToolResult {
     let timeout_secs = input
         .get("timeout_secs")
         .and_then(|v| v.as_u64())
         .unwrap_or(120)
-        .min(600); // Max 10 minutes
+        .min(MAX_BASH_TIMEOUT_SECS);
 
     let cwd = input
-        .get("cwd")
+        .get("working_dir")
         .and_then(|v| v.as_str())
         .map(|p| resolve_path(p, project_root))
         .unwrap_or_else(|| project_root.to_path_buf());
 
-    let child = match Command::new("bash")
-        .arg("-c")
-        .arg(command)
+    let child = match sandbox
+        .command(command, project_root)
         .current_dir(&cwd)
         .stdout(Stdio::piped())
         .stderr(Stdio::piped())
@@ -547,16 +589,11 @@ async fn bash(input: &serde_json::Value, project_root: &Path) -> ToolResult {
                 exit_code, stdout, stderr
             );
 
-            // Truncate if too large
-            let max_size = 50_000;
-            if combined.len() > max_size {
-                ToolResult::success(format!(
-                    "{}\n\n[Truncated: output is {} bytes]",
-                    &combined[..max_size],
-                    combined.len()
-                ))
-            } else {
-                ToolResult::success(combined)
+            let combined = truncate_output(combined, MAX_OUTPUT_BYTES);
+
+            match sandbox.violation(&output.status, &stderr) {
+                Some(violation) => ToolResult::sandbox_violation(violation, combined),
+                None => ToolResult::success(combined),
             }
         }
         Ok(Err(e)) => ToolResult::error(format!("Command failed: {}", e)),
@@ -574,158 +611,30 @@ async fn bash(input: &serde_json::Value, project_root: &Path) -> ToolResult {
 }
 
 /// 4. edit_file - Modify files with uniqueness check
-async fn edit_file(input: &serde_json::Value, project_root: &Path) -> ToolResult {
-    let path_str = match input.get("path").and_then(|v| v.as_str()) {
-        Some(p) => p,
-        None => return ToolResult::error("Missing required parameter: path"),
-    };
-
-    let old_string = match input.get("old_string").and_then(|v| v.as_str()) {
-        Some(s) => s,
-        None => return ToolResult::error("Missing required parameter: old_string"),
-    };
-
-    let new_string = match input.get("new_string").and_then(|v| v.as_str()) {
-        Some(s) => s,
-        None => return ToolResult::error("Missing required parameter: new_string"),
-    };
-
-    let path = resolve_path(path_str, project_root);
-
-    // Handle new file creation
-    if old_string.is_empty() {
-        // Ensure parent directory exists
-        if let Some(parent) = path.parent() {
-            if let Err(e) = tokio::fs::create_dir_all(parent).await {
-                return ToolResult::error(format!("Failed to create directory: {}", e));
-            }
-        }
-
-        return match tokio::fs::write(&path, new_string).await {
-            Ok(_) => ToolResult::success(format!("Created new file: {}", path.display())),
-            Err(e) => ToolResult::error(format!("Failed to create file: {}", e)),
-        };
-    }
-
-    // Read existing file
-    let content = match tokio::fs::read_to_string(&path).await {
-        Ok(c) => c,
-        Err(e) => return ToolResult::error(format!("Failed to read file: {}", e)),
-    };
-
-    // Check uniqueness
-    let matches: Vec<_> = content.match_indices(old_string).collect();
-
-    if matches.is_empty() {
-        return ToolResult::error(format!(
-            "old_string not found in file. Make sure it matches exactly (including whitespace).\n\nSearched for:\n{}\n\nIn file: {}",
-            old_string,
-            path.display()
-        ));
-    }
-
-    if matches.len() > 1 {
-        return ToolResult::error(format!(
-            "old_string is not unique - found {} matches. Add more context to make it unique.",
-            matches.len()
-        ));
-    }
-
-    // Perform replacement
-    let new_content = content.replacen(old_string, new_string, 1);
-
-    match tokio::fs::write(&path, new_content).await {
-        Ok(_) => ToolResult::success(format!(
-            "Successfully edited {}. Replaced {} bytes with {} bytes.",
-            path.display(),
-            old_string.len(),
-            new_string.len()
-        )),
-        Err(e) => ToolResult::error(format!("Failed to write file: {}", e)),
-    }
-}
-
-/// 5. code_search - Ripgrep wrapper
-async fn code_search(input: &serde_json::Value, project_root: &Path) -> ToolResult {
-    let pattern = match input.get("pattern").and_then(|v| v.as_str()) {
-        Some(p) => p,
-        None => return ToolResult::error("Missing required parameter: pattern"),
-    };
-
-    let search_path = input
-        .get("path")
-        .and_then(|v| v.as_str())
-        .map(|p| resolve_path(p, project_root))
-        .unwrap_or_else(|| project_root.to_path_buf());
-
-    let file_pattern = input.get("file_pattern").and_then(|v| v.as_str());
-    let max_results = input
-        .get("max_results")
-        .and_then(|v| v.as_u64())
-        .unwrap_or(50) as usize;
-
-    let mut cmd = Command::new("rg");
-    cmd.arg("--json")
-        .arg("--max-count")
-        .arg(max_results.to_string())
-        .arg("-n") // line numbers
-        .arg("--no-heading");
-
-    if let Some(fp) = file_pattern {
-        cmd.arg("-g").arg(fp);
+///
+/// An empty `old_string` creates (or overwrites) the file; anything else is
+/// a unique replacement by the registry's `edit_file`.
+async fn edit_file(input: &serde_json::Value, ctx: &PrimitiveContext) -> ToolResult {
+    if input.get("old_string").and_then(|v| v.as_str()) != Some("") {
+        return run_primitive("edit_file", input.clone(), ctx).await;
     }
 
-    // Ignore common directories
-    cmd.arg("--glob").arg("!node_modules")
-        .arg("--glob").arg("!.git")
-        .arg("--glob").arg("!target")
-        .arg("--glob").arg("!dist");
-
-    cmd.arg(pattern).arg(&search_path);
-
-    let output = match cmd.output().await {
-        Ok(o) => o,
-        Err(e) => {
-            return ToolResult::error(format!(
-                "Failed to run ripgrep (is rg installed?): {}",
-                e
-            ))
-        }
+    let (Some(path_str), Some(content)) = (
+        input.get("path").and_then(|v| v.as_str()),
+        input.get("new_string").and_then(|v| v.as_str()),
+    ) else {
+        return ToolResult::error("Missing required parameters: path and new_string");
     };
+    let path = resolve_path(path_str, &ctx.working_dir);
 
-    if !output.status.success() && output.stdout.is_empty() {
-        // No matches found is not an error
-        if output.status.code() == Some(1) {
-            return ToolResult::success("No matches found.");
-        }
-        return ToolResult::error(format!(
-            "ripgrep failed: {}",
-            String::from_utf8_lossy(&output.stderr)
-        ));
-    }
-
-    // Parse JSON output into human-readable format
-    let stdout = String::from_utf8_lossy(&output.stdout);
-    let mut results = Vec::new();
-
-    for line in stdout.lines() {
-        if let Ok(json) = serde_json::from_str::<serde_json::Value>(line) {
-            if json.get("type").and_then(|v| v.as_str()) == Some("match") {
-                if let Some(data) = json.get("data") {
-                    let path = data.get("path").and_then(|p| p.get("text")).and_then(|t| t.as_str()).unwrap_or("");
-                    let line_num = data.get("line_number").and_then(|l| l.as_u64()).unwrap_or(0);
-                    let text = data.get("lines").and_then(|l| l.get("text")).and_then(|t| t.as_str()).unwrap_or("");
-
-                    results.push(format!("{}:{}: {}", path, line_num, text.trim()));
-                }
-            }
+    if let Some(parent) = path.parent() {
+        if let Err(e) = tokio::fs::create_dir_all(parent).await {
+            return ToolResult::error(format!("Failed to create directory: {}", e));
         }
     }
-
-    if results.is_empty() {
-        ToolResult::success("No matches found.")
-    } else {
-        ToolResult::success(format!("Found {} matches:\n\n{}", results.len(), results.join("\n")))
+    match write_atomic(&path, content.as_bytes()) {
+        Ok(()) => ToolResult::success(format!("Created new file: {}", path.display())),
+        Err(e) => ToolResult::error(format!("Failed to create file: {}", e)),
     }
 }
 
@@ -789,6 +698,21 @@ async fn beads(input: &serde_json::Value, project_root: &Path) -> ToolResult {
 }
 
 async fn beads_ready(project_root: &Path) -> ToolResult {
+    if let Some(store) = beads::for_project(project_root) {
+        return match store.ready() {
+            Ok(tasks) if tasks.is_empty() => ToolResult::success("No ready work."),
+            Ok(tasks) => ToolResult::success(
+                tasks
+                    .iter()
+                    .enumerate()
+                    .map(|(i, t)| format!("{}. [P{}] {}: {}", i + 1, t.priority, t.id, t.title))
+                    .collect::<Vec<_>>()
+                    .join("\n"),
+            ),
+            Err(e) => ToolResult::error(format!("Failed to read beads store: {}", e)),
+        };
+    }
+
     let output = Command::new("bd")
         .args(["ready"])
         .current_dir(project_root)
@@ -811,6 +735,28 @@ async fn beads_ready(project_root: &Path) -> ToolResult {
 }
 
 async fn beads_show(project_root: &Path, task_id: &str) -> ToolResult {
+    if let Some(store) = beads::for_project(project_root) {
+        return match store.get(task_id) {
+            Ok(task) => {
+                let mut out = format!(
+                    "{}: {}\nStatus: {} | Priority: P{} | Type: {}\n",
+                    task.id, task.title, task.status, task.priority, task.issue_type
+                );
+                if !task.depends_on.is_empty() {
+                    out.push_str(&format!("Depends on: {}\n", task.depends_on.join(", ")));
+                }
+                if !task.description.is_empty() {
+                    out.push_str(&format!("\nDescription:\n{}\n", task.description));
+                }
+                if !task.notes.is_empty() {
+                    out.push_str(&format!("\nNotes:\n{}\n", task.notes));
+                }
+                ToolResult::success(out)
+            }
+            Err(e) => ToolResult::error(e.to_string()),
+        };
+    }
+
     let output = Command::new("bd")
         .args(["show", task_id])
         .current_dir(project_root)
@@ -834,6 +780,13 @@ async fn beads_show(project_root: &Path, task_id: &str) -> ToolResult {
 }
 
 async fn beads_update(project_root: &Path, task_id: &str, status: &str) -> ToolResult {
+    if let Some(store) = beads::for_project(project_root) {
+        return match store.set_status(task_id, status) {
+            Ok(()) => ToolResult::success(format!("Updated {} status to {}", task_id, status)),
+            Err(e) => ToolResult::error(format!("Update failed: {}", e)),
+        };
+    }
+
     let output = Command::new("bd")
         .args(["update", task_id, &format!("--status={}", status)])
         .current_dir(project_root)
@@ -856,6 +809,13 @@ async fn beads_update(project_root: &Path, task_id: &str, status: &str) -> ToolR
 }
 
 async fn beads_close(project_root: &Path, task_id: &str, reason: Option<&str>) -> ToolResult {
+    if let Some(store) = beads::for_project(project_root) {
+        return match store.close(task_id, reason) {
+            Ok(()) => ToolResult::success(format!("Closed task {}", task_id)),
+            Err(e) => ToolResult::error(format!("Close failed: {}", e)),
+        };
+    }
+
     let mut args = vec!["close", task_id];
     let reason_arg;
     
@@ -904,6 +864,9 @@ async fn beads_sync(project_root: &Path) -> ToolResult {
                 ))
             }
         }
+        Err(_) if beads::for_project(project_root).is_some() => {
+            ToolResult::success("Beads store is up to date (bd not installed, nothing to sync)")
+        }
         Err(e) => ToolResult::error(format!("Failed to run bd sync: {}", e)),
     }
 }
@@ -1002,10 +965,50 @@ async fn beads_decompose(project_root: &Path, task_id: &str) -> ToolResult {
     }
 }
 
+/// 7. remember - Append a lesson to the knowledge store
+fn remember(input: &serde_json::Value, project_root: &Path) -> ToolResult {
+    let kind = match input.get("kind").and_then(|v| v.as_str()).map(Kind::parse) {
+        Some(Some(kind)) if kind != Kind::Outcome => kind,
+        _ => return ToolResult::error("kind must be one of: pitfall, file_note, command"),
+    };
+    let text = match input.get("text").and_then(|v| v.as_str()) {
+        Some(t) if !t.trim().is_empty() => t.trim(),
+        _ => return ToolResult::error("Missing required parameter: text"),
+    };
+    let files: Vec<String> = input
+        .get("files")
+        .and_then(|v| v.as_array())
+        .map(|files| files.iter().filter_map(|f| f.as_str()).map(str::to_string).collect())
+        .unwrap_or_default();
+    if kind == Kind::FileNote && files.is_empty() {
+        return ToolResult::error("A file_note needs the files it is about");
+    }
+
+    match KnowledgeStore::new(project_root).record(Entry::new(kind, text).with_files(&files)) {
+        Ok(()) => ToolResult::success("Saved. Later tasks will see this when relevant."),
+        Err(e) => ToolResult::error(format!("Failed to save: {}", e)),
+    }
+}
+
 // ============================================================================
 // Helpers
 // ============================================================================
 
+/// Command and external tool output kept in the conversation, in bytes
+pub const MAX_OUTPUT_BYTES: usize = 50_000;
+
+/// Cut tool output to `max` bytes on a character boundary, noting the full size
+pub fn truncate_output(output: String, max: usize) -> String {
+    if output.len() <= max {
+        return output;
+    }
+    let mut end = max;
+    while !output.is_char_boundary(end) {
+        end -= 1;
+    }
+    format!("{}\n\n[Truncated: output is {} bytes]", &output[..end], output.len())
+}
+
 /// Resolve a path relative to project root
 fn resolve_path(path_str: &str, project_root: &Path) -> std::path::PathBuf {
     let path = Path::new(path_str);
@@ -1023,14 +1026,96 @@ mod tests {
     #[test]
     fn test_tool_definitions() {
         let tools = get_tool_definitions();
-        assert_eq!(tools.len(), 6);
+        assert_eq!(tools.len(), 11);
 
         let names: Vec<_> = tools.iter().map(|t| t.name.as_str()).collect();
         assert!(names.contains(&"read_file"));
         assert!(names.contains(&"list_files"));
         assert!(names.contains(&"bash"));
+        assert!(names.contains(&"process"));
         assert!(names.contains(&"edit_file"));
         assert!(names.contains(&"code_search"));
+        assert!(names.contains(&"multi_edit"));
+        assert!(names.contains(&"find_symbol"));
+        assert!(names.contains(&"find_references"));
         assert!(names.contains(&"beads"));
+        assert!(names.contains(&"remember"));
+    }
+
+    #[tokio::test]
+    async fn test_primitives_run_through_registry() {
+        let temp = tempfile::TempDir::new().unwrap();
+        let root = temp.path();
+        let processes = ProcessTable::default();
+        let processes = &processes;
+        let run = |name: &'static str, input: serde_json::Value| async move {
+            execute_tool(name, &input, root, None, processes).await
+        };
+
+        let created = run("edit_file", serde_json::json!({"path": "src/lib.rs", "old_string": "", "new_string": "fn a() {}\n"})).await;
+        assert!(created.success, "{:?}", created.error);
+
+        let edited = run("edit_file", serde_json::json!({"path": "src/lib.rs", "old_string": "fn a", "new_string": "fn b"})).await;
+        assert!(edited.output.contains("-fn a() {}\n+fn b() {}"), "{}", edited.output);
+
+        let missing = run("read_file", serde_json::json!({"path": "src/lib.r"})).await;
+        assert!(missing.error.unwrap().contains("Did you mean"));
+
+        let patched = run(
+            "multi_edit",
+            serde_json::json!({"patch": "--- a/src/lib.rs\n+++ b/src/lib.rs\n@@ -1 +1 @@\n-fn b() {}\n+fn c() {}\n"}),
+        )
+        .await;
+        assert!(patched.output.starts_with("Changed 1 file(s):"), "{}", patched.output);
+
+        let listed = run("list_files", serde_json::json!({"path": "."})).await;
+        assert_eq!(listed.output, "dir\tsrc/");
+
+        let symbol = run("find_symbol", serde_json::json!({"name": "c"})).await;
+        assert_eq!(symbol.output, "Found 1 matches:\n\nsrc/lib.rs:1: function c: fn c() {}");
+
+        let bash = run("bash", serde_json::json!({"command": "echo hi", "working_dir": "src"})).await;
+        assert!(bash.output.starts_with("Exit code: 0\n\nSTDOUT:\nhi"), "{}", bash.output);
+
+        let started = run("process", serde_json::json!({"action": "start", "command": "echo up; sleep 30"})).await;
+        assert!(started.output.starts_with("Process p1 is running"), "{}", started.output);
+        let ready = run("process", serde_json::json!({"action": "wait", "id": "p1", "pattern": "up"})).await;
+        assert!(ready.output.contains("Pattern found.\n\nOUTPUT (bytes 0-3):\nup"), "{}", ready.output);
+        let killed = run("process", serde_json::json!({"action": "kill", "id": "p1"})).await;
+        assert!(killed.output.contains("exited with code"), "{}", killed.output);
+    }
+
+    #[tokio::test]
+    async fn test_process_starts_in_sandbox() {
+        // Needs unprivileged user namespaces, which CI containers may not allow
+        let supported = std::process::Command::new("unshare")
+            .args(["--user", "--map-root-user", "--mount", "true"])
+            .status()
+            .is_ok_and(|s| s.success());
+        if !supported {
+            eprintln!("skipping test_process_starts_in_sandbox: unshare cannot create a user namespace here");
+            return;
+        }
+
+        let temp = tempfile::TempDir::new().unwrap();
+        let config = crate::config::SandboxConfig {
+            backend: crate::sandbox::SandboxBackend::Unshare,
+            ..Default::default()
+        };
+        let sandbox = Sandbox::from_config(&config).unwrap().unwrap();
+        let (root, sandbox) = (temp.path(), &sandbox);
+        let processes = ProcessTable::default();
+        let processes = &processes;
+        let run = |input: serde_json::Value| async move {
+            execute_tool("process", &input, root, Some(sandbox), processes).await
+        };
+
+        let command = "echo ok > inside.txt; touch /etc/ralph-sandbox-test; echo done";
+        let started = run(serde_json::json!({"action": "start", "command": command})).await;
+        assert!(started.success, "{:?}", started.error);
+        let exited = run(serde_json::json!({"action": "wait", "id": "p1"})).await;
+        assert!(exited.output.contains("Read-only file system"), "{}", exited.output);
+        assert!(root.join("inside.txt").exists());
+        assert!(!Path::new("/etc/ralph-sandbox-test").exists());
     }
 }
@@ -1,108 +1,12 @@
-//! Progress tracking for Ralph Loop
+//! Progress helpers for Ralph Loop
 //!
-//! Maintains a progress.md file that documents completed work,
-//! patterns learned, and files modified. This is injected into
-//! subsequent runs to give the agent context without exploration.
+//! Loads the codebase map injected into every system prompt, and pulls the
+//! files a session modified out of its tool results. What was learned from
+//! each task is kept in the knowledge store (see `knowledge`).
 
-use anyhow::Result;
-use std::io::Write;
 use std::path::Path;
 
-const PROGRESS_FILE: &str = ".ralph/progress.md";
-const MAX_PROGRESS_SIZE: usize = 8000; // ~2000 tokens max to avoid bloat
-
-/// Load recent progress summary (last N entries)
-///
-/// Returns a formatted string suitable for injection into the system prompt.
-/// Entries are separated by "## " headers with timestamps.
-pub fn load_recent_progress(project_root: &Path, max_entries: usize) -> Result<String> {
-    let progress_path = project_root.join(PROGRESS_FILE);
-
-    if !progress_path.exists() {
-        return Ok(String::new());
-    }
-
-    let content = std::fs::read_to_string(&progress_path)?;
-
-    // Parse markdown entries (## headers separate entries)
-    let entries: Vec<&str> = content
-        .split("\n## ")
-        .filter(|s| !s.trim().is_empty())
-        .collect();
-
-    if entries.is_empty() {
-        return Ok(String::new());
-    }
-
-    // Take last N entries
-    let recent: Vec<&str> = entries.iter().rev().take(max_entries).rev().cloned().collect();
-
-    // Reconstruct with ## prefix (first entry may or may not have it)
-    let mut result = String::new();
-    for (i, entry) in recent.iter().enumerate() {
-        if i == 0 && !entry.starts_with("## ") {
-            // First chunk might be file header, include as-is
-            result.push_str(entry);
-        } else {
-            result.push_str("\n## ");
-            result.push_str(entry);
-        }
-    }
-
-    // Truncate if too large to avoid context bloat
-    if result.len() > MAX_PROGRESS_SIZE {
-        // Find a good truncation point (end of an entry)
-        if let Some(pos) = result[..MAX_PROGRESS_SIZE].rfind("\n## ") {
-            result.truncate(pos);
-            result.push_str("\n\n[Earlier progress entries omitted]");
-        } else {
-            result.truncate(MAX_PROGRESS_SIZE);
-            result.push_str("\n...[truncated]");
-        }
-    }
-
-    Ok(result)
-}
-
-/// Append a progress entry after completing a task
-///
-/// Records the task ID, what was done, and which files were modified.
-/// This creates a breadcrumb trail for future iterations.
-pub fn append_progress(
-    project_root: &Path,
-    task_id: &str,
-    summary: &str,
-    files_changed: &[String],
-) -> Result<()> {
-    let progress_path = project_root.join(PROGRESS_FILE);
-
-    // Ensure .ralph directory exists
-    if let Some(parent) = progress_path.parent() {
-        std::fs::create_dir_all(parent)?;
-    }
-
-    let timestamp = chrono::Local::now().format("%Y-%m-%d %H:%M");
-    
-    let files_section = if files_changed.is_empty() {
-        String::new()
-    } else {
-        format!("\n**Files:** {}", files_changed.join(", "))
-    };
-
-    let entry = format!(
-        "\n## {} - {}\n\n{}{}\n\n---\n",
-        timestamp, task_id, summary, files_section
-    );
-
-    let mut file = std::fs::OpenOptions::new()
-        .create(true)
-        .append(true)
-        .open(&progress_path)?;
-
-    writeln!(file, "{}", entry)?;
-
-    Ok(())
-}
+const MAX_CODEMAP_SIZE: usize = 8000; // ~2000 tokens max to avoid bloat
 
 /// Load codebase summary from CODEMAP files
 ///
@@ -124,7 +28,7 @@ pub fn load_codebase_summary(project_root: &Path) -> String {
         if path.exists() {
             if let Ok(content) = std::fs::read_to_string(&path) {
                 // Truncate if too large (aim for ~2000 tokens = ~8000 chars)
-                let max_chars = MAX_PROGRESS_SIZE;
+                let max_chars = MAX_CODEMAP_SIZE;
                 if content.len() > max_chars {
                     // Safe truncation at character boundary
                     let mut end = max_chars;
@@ -147,10 +51,11 @@ pub fn load_codebase_summary(project_root: &Path) -> String {
 
 /// Extract files that were modified from tool result output
 ///
-/// Parses edit_file success messages to extract file paths.
+/// Parses edit_file and multi_edit success messages to extract file paths.
 /// Handles formats:
 /// - "Successfully edited path/to/file.rs. Replaced X bytes with Y bytes."
 /// - "Created new file: path/to/file.rs"
+/// - "Changed 2 file(s): src/a.rs (modified), src/b.rs (created)\n\n<diff>"
 pub fn extract_modified_files(tool_outputs: &[String]) -> Vec<String> {
     let mut files = Vec::new();
     
@@ -166,6 +71,16 @@ pub fn extract_modified_files(tool_outputs: &[String]) -> Vec<String> {
             // Format: "Created new file: path/to/file.ext"
             let path = &output["Created new file: ".len()..];
             files.push(path.trim().to_string());
+        } else if let Some(rest) = output.strip_prefix("Changed ") {
+            // multi_edit: "Changed N file(s): path (kind), path (kind)"; a
+            // dry run says "Would change" and touches nothing
+            let summary = rest.lines().next().unwrap_or_default();
+            if let Some((_, list)) = summary.split_once(" file(s): ") {
+                for entry in list.split(", ") {
+                    let path = entry.rfind(" (").map_or(entry, |i| &entry[..i]);
+                    files.push(path.trim().to_string());
+                }
+            }
         }
     }
     
@@ -179,39 +94,6 @@ mod tests {
     use super::*;
     use tempfile::TempDir;
 
-    #[test]
-    fn test_load_empty_progress() {
-        let temp = TempDir::new().unwrap();
-        let result = load_recent_progress(temp.path(), 3).unwrap();
-        assert!(result.is_empty());
-    }
-
-    #[test]
-    fn test_append_and_load_progress() {
-        let temp = TempDir::new().unwrap();
-        
-        // Append some progress
-        append_progress(
-            temp.path(),
-            "task-001",
-            "Implemented feature X",
-            &["src/main.rs".to_string(), "src/lib.rs".to_string()],
-        ).unwrap();
-        
-        append_progress(
-            temp.path(),
-            "task-002",
-            "Fixed bug Y",
-            &["src/bug.rs".to_string()],
-        ).unwrap();
-
-        // Load it back
-        let loaded = load_recent_progress(temp.path(), 5).unwrap();
-        assert!(loaded.contains("task-001"));
-        assert!(loaded.contains("task-002"));
-        assert!(loaded.contains("src/main.rs"));
-    }
-
     #[test]
     fn test_extract_modified_files() {
         let outputs = vec![
@@ -226,6 +108,16 @@ mod tests {
         assert!(files.contains(&"src/new_module.rs".to_string()));
     }
 
+    #[test]
+    fn test_extract_multi_edit_files() {
+        let outputs = vec![
+            "Changed 2 file(s): src/lib.rs (modified), src/new.rs (created)\n\n--- a/src/lib.rs\n+++ b/src/lib.rs".to_string(),
+            "Would change 1 file(s): src/dry.rs (modified)\n\n".to_string(),
+        ];
+
+        assert_eq!(extract_modified_files(&outputs), ["src/lib.rs", "src/new.rs"]);
+    }
+
     #[test]
     fn test_load_codebase_summary_not_found() {
         let temp = TempDir::new().unwrap();
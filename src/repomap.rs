@@ -0,0 +1,272 @@
+//! Repo map - An outline of the codebase for the system prompt
+//!
+//! Without a hand-written CODEMAP the agent starts every task blind and
+//! spends its first iterations listing directories. The repo map gives it
+//! the lay of the land instead: each source file with its top-level public
+//! items, the files the rest of the code leans on most first, cut to a
+//! token budget:
+//!
+//! ```text
+//! src/config.rs: struct ProjectConfig, struct McpConfig, fn load_config
+//! src/git.rs: fn is_git_repo, fn commit, struct CommitInfo, +9 more
+//! ```
+//!
+//! A file ranks by how many other files use its items. Building the map
+//! parses every Rust, TypeScript and Python file, so it's cached in
+//! `.ralph/cache/` (ignored by git). The cache is keyed on HEAD's tree plus
+//! the size and modification time of every file changed or added since, so
+//! it's rebuilt when a commit or an uncommitted edit changes the code.
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::Path;
+use tachikoma_primitives::symbols::{self, Language};
+use tachikoma_primitives::{list_files_recursive, PrimitiveContext, RecursiveOptions, SymbolKind};
+
+use crate::git;
+
+const CACHE_FILE: &str = ".ralph/cache/repomap.json";
+
+/// Budget for the outline, in tokens (a CODEMAP gets ~2000)
+const MAX_TOKENS: usize = 1500;
+
+/// Rough size of a token, in characters
+const CHARS_PER_TOKEN: usize = 4;
+
+/// Items listed per file before the rest are just counted
+const MAX_ITEMS_PER_FILE: usize = 10;
+
+#[derive(Serialize, Deserialize)]
+struct Cached {
+    /// What the map was built from (see [`snapshot`])
+    tree: String,
+    map: String,
+}
+
+/// The repo map for a project, from the cache while the code is unchanged
+///
+/// Empty when there's no source the map understands.
+pub async fn load(project_root: &Path) -> String {
+    let tree = snapshot(project_root);
+    let cache = project_root.join(CACHE_FILE);
+    if let Some(tree) = &tree {
+        let cached = std::fs::read_to_string(&cache)
+            .ok()
+            .and_then(|json| serde_json::from_str::<Cached>(&json).ok());
+        if let Some(cached) = cached.filter(|cached| cached.tree == *tree) {
+            return cached.map;
+        }
+    }
+
+    let map = match build(project_root, MAX_TOKENS).await {
+        Ok(map) => map,
+        Err(e) => {
+            tracing::warn!("Failed to build the repo map: {:#}", e);
+            return String::new();
+        }
+    };
+    if let Some(tree) = tree {
+        if let Err(e) = save(&cache, Cached { tree, map: map.clone() }) {
+            tracing::warn!("Failed to cache the repo map: {:#}", e);
+        }
+    }
+    map
+}
+
+/// HEAD's tree, plus the size and modification time of each file that
+/// differs from it; `None` outside a repository with commits
+fn snapshot(project_root: &Path) -> Option<String> {
+    let mut snapshot = git::head_tree(project_root).ok()?;
+    for file in git::changed_files(project_root).ok()? {
+        if file.starts_with(".ralph/") {
+            continue;
+        }
+        let stamp = std::fs::metadata(project_root.join(&file)).ok().map(|meta| {
+            let modified = meta.modified().ok().and_then(|t| t.duration_since(std::time::UNIX_EPOCH).ok());
+            (meta.len(), modified.unwrap_or_default().as_nanos())
+        });
+        snapshot.push_str(&format!("\n{} {:?}", file, stamp));
+    }
+    Some(snapshot)
+}
+
+fn save(cache: &Path, cached: Cached) -> Result<()> {
+    if let Some(dir) = cache.parent() {
+        std::fs::create_dir_all(dir)?;
+        // Worktrees commit with `git add -A`; keep the cache out of it
+        std::fs::write(dir.join(".gitignore"), "*\n")?;
+    }
+    std::fs::write(cache, serde_json::to_string(&cached)?)?;
+    Ok(())
+}
+
+/// One file's line in the map
+struct Outline {
+    path: String,
+    /// (kind, name) of each top-level public item
+    items: Vec<(&'static str, String)>,
+    score: usize,
+}
+
+/// Build the outline, at most about `max_tokens` long
+pub async fn build(project_root: &Path, max_tokens: usize) -> Result<String> {
+    let root = project_root
+        .canonicalize()
+        .with_context(|| format!("Cannot open {}", project_root.display()))?;
+    let ctx = PrimitiveContext::new(root.clone());
+    let listing = list_files_recursive(&ctx, ".", RecursiveOptions::default()).await?;
+
+    let mut outlines = Vec::new();
+    let mut identifiers = Vec::new();
+    for entry in &listing.entries {
+        let Ok(relative) = entry.path.strip_prefix(&root) else {
+            continue;
+        };
+        // Ralph's own state (worktrees included) and other dot directories
+        if relative.components().any(|c| c.as_os_str().to_string_lossy().starts_with('.')) {
+            continue;
+        }
+        let Some(language) = Language::from_path(relative) else {
+            continue;
+        };
+        let Ok(source) = std::fs::read_to_string(&entry.path) else {
+            continue;
+        };
+
+        let parsed = symbols::parse(language, &source);
+        let items = parsed
+            .definitions
+            .iter()
+            .filter(|d| d.public && d.scope.is_none())
+            .map(|d| (label(d.kind), d.name.clone()))
+            .collect();
+        outlines.push(Outline {
+            path: relative.display().to_string(),
+            items,
+            score: 0,
+        });
+        identifiers.push(parsed.identifiers);
+    }
+
+    // How many files use each item's name; one of them is the file defining it
+    let mut users: HashMap<&str, usize> = outlines
+        .iter()
+        .flat_map(|outline| outline.items.iter().map(|(_, name)| (name.as_str(), 0)))
+        .collect();
+    for used in &identifiers {
+        for name in used {
+            if let Some(count) = users.get_mut(name.as_str()) {
+                *count += 1;
+            }
+        }
+    }
+    let scores: Vec<usize> = outlines
+        .iter()
+        .map(|outline| outline.items.iter().map(|(_, name)| users[name.as_str()].saturating_sub(1)).sum())
+        .collect();
+    for (outline, score) in outlines.iter_mut().zip(scores) {
+        outline.score = score;
+    }
+
+    outlines.retain(|outline| !outline.items.is_empty());
+    outlines.sort_by(|a, b| b.score.cmp(&a.score).then_with(|| a.path.cmp(&b.path)));
+    Ok(render(&outlines, max_tokens * CHARS_PER_TOKEN))
+}
+
+fn render(outlines: &[Outline], max_chars: usize) -> String {
+    let mut map = String::new();
+    for (shown, outline) in outlines.iter().enumerate() {
+        let mut items: Vec<String> = outline
+            .items
+            .iter()
+            .take(MAX_ITEMS_PER_FILE)
+            .map(|(kind, name)| format!("{} {}", kind, name))
+            .collect();
+        if outline.items.len() > MAX_ITEMS_PER_FILE {
+            items.push(format!("+{} more", outline.items.len() - MAX_ITEMS_PER_FILE));
+        }
+        let line = format!("{}: {}\n", outline.path, items.join(", "));
+
+        if map.len() + line.len() > max_chars {
+            let rest = outlines.len() - shown;
+            map.push_str(&format!("... and {} more file{}\n", rest, if rest == 1 { "" } else { "s" }));
+            break;
+        }
+        map.push_str(&line);
+    }
+    map.trim_end().to_string()
+}
+
+/// How an item is introduced in the map
+fn label(kind: SymbolKind) -> &'static str {
+    match kind {
+        SymbolKind::Function | SymbolKind::Method => "fn",
+        SymbolKind::Struct => "struct",
+        SymbolKind::Enum => "enum",
+        SymbolKind::Union => "union",
+        SymbolKind::Trait => "trait",
+        SymbolKind::Impl => "impl",
+        SymbolKind::Class => "class",
+        SymbolKind::Interface => "interface",
+        SymbolKind::Type => "type",
+        SymbolKind::Module => "mod",
+        SymbolKind::Const => "const",
+        SymbolKind::Static => "static",
+        SymbolKind::Macro => "macro",
+        SymbolKind::Reference => "ref",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[tokio::test]
+    async fn test_build_ranks_and_budgets() {
+        let temp = TempDir::new().unwrap();
+        let root = temp.path();
+        std::fs::create_dir_all(root.join("src")).unwrap();
+        std::fs::create_dir_all(root.join(".ralph/worktrees/x")).unwrap();
+        std::fs::write(root.join("src/util.rs"), "pub fn helper() {}\nfn private() {}\n").unwrap();
+        std::fs::write(
+            root.join("src/config.rs"),
+            "pub struct Config;\n\nimpl Config {\n    pub fn new() -> Self { Config }\n}\n\npub fn load() -> Config { Config }\n",
+        )
+        .unwrap();
+        std::fs::write(root.join("src/main.rs"), "fn main() {\n    let _ = config::load();\n}\n").unwrap();
+        std::fs::write(root.join("tool.py"), "def run(config):\n    return Config\n\ndef _hidden():\n    pass\n").unwrap();
+        std::fs::write(root.join(".ralph/worktrees/x/copy.rs"), "pub fn copy() {}\n").unwrap();
+
+        let map = build(root, MAX_TOKENS).await.unwrap();
+        assert_eq!(map, "src/config.rs: struct Config, fn load\nsrc/util.rs: fn helper\ntool.py: fn run");
+
+        let map = build(root, 12).await.unwrap();
+        assert_eq!(map, "src/config.rs: struct Config, fn load\n... and 2 more files");
+    }
+
+    #[tokio::test]
+    async fn test_cache_follows_uncommitted_edits() {
+        let temp = TempDir::new().unwrap();
+        let root = temp.path();
+        let git = |args: &[&str]| {
+            let status = std::process::Command::new("git").args(args).current_dir(root).status().unwrap();
+            assert!(status.success());
+        };
+        git(&["init", "-q"]);
+        std::fs::write(root.join("lib.rs"), "pub fn first() {}\n").unwrap();
+        git(&["add", "."]);
+        git(&["-c", "user.name=t", "-c", "user.email=t@t", "commit", "-qm", "init"]);
+
+        assert_eq!(load(root).await, "lib.rs: fn first");
+        assert!(root.join(CACHE_FILE).exists());
+
+        // Same HEAD, but the working tree changed
+        std::fs::write(root.join("lib.rs"), "pub fn first() {}\npub fn second() {}\n").unwrap();
+        std::fs::write(root.join("new.rs"), "pub fn third() {}\n").unwrap();
+        let map = load(root).await;
+        assert!(map.contains("lib.rs: fn first, fn second"), "{}", map);
+        assert!(map.contains("new.rs: fn third"), "{}", map);
+    }
+}
@@ -0,0 +1,517 @@
+//! Sandbox - Isolated execution for the `bash` primitive
+//!
+//! Opt-in per project with a `sandbox:` section in `.tachikoma/config.yaml`.
+//! Commands then run in their own Linux namespaces:
+//!
+//! - the filesystem, every mount included, is read-only except the project
+//!   directory, `/tmp` and any `sandbox.writable` paths
+//! - there is no network unless `sandbox.network: true` (loopback still works)
+//! - CPU time is capped with an rlimit; memory and process count, when set,
+//!   are capped for the whole command by a cgroup from `systemd-run --user`
+//! - only a few environment variables are passed through (`sandbox.env`
+//!   adds more), so API keys stay outside
+//!
+//! Two backends do the isolation: bubblewrap (`bwrap`), or `unshare` from
+//! util-linux with an unprivileged user namespace. `auto` uses bwrap when it
+//! is installed. When a command fails because it hit one of these rules, the
+//! tool error names the rule so the model changes approach instead of
+//! retrying.
+
+use anyhow::{bail, Result};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::os::unix::process::ExitStatusExt;
+use std::path::{Path, PathBuf};
+use std::process::ExitStatus;
+use tokio::process::Command;
+
+use crate::config::SandboxConfig;
+
+/// Exit code the sandbox setup uses when it can't isolate the command
+const SETUP_FAILED: i32 = 125;
+
+/// `SIGXCPU`, sent when the CPU time limit runs out
+const SIGXCPU: i32 = 24;
+
+/// What `RUN_CHECKING_MEMORY` prints when the scope's OOM killer fired
+const OUT_OF_MEMORY: &str = "ralph-sandbox: the command ran out of memory";
+
+/// Variables a sandboxed command always gets from Ralph's environment
+const BASE_ENV: &[&str] = &[
+    "PATH", "HOME", "USER", "LOGNAME", "SHELL", "TERM", "TZ", "TMPDIR", "LANG", "LC_ALL", "LC_CTYPE",
+    // systemd-run finds the user's service manager through these
+    "XDG_RUNTIME_DIR", "DBUS_SESSION_BUS_ADDRESS",
+];
+
+/// Runs after the namespaces exist: writable paths become their own mounts,
+/// then every other mount is made read-only. `$1` is the command, the rest
+/// are the writable paths.
+///
+/// Inside a user namespace a mount's nosuid, nodev, noexec and atime flags
+/// are locked, and a remount that drops one fails, so each remount repeats
+/// the options from mountinfo (field 6).
+const MOUNT_SETUP: &str = r#"
+cmd=$1; shift
+for dir in "$@"; do
+    mount --bind "$dir" "$dir" || fail "cannot bind $dir"
+done
+while read -r _ _ _ _ point opts _; do
+    point=$(printf '%b' "$point")
+    for keep in "$@" /proc /sys; do
+        case "$point" in "$keep"|"$keep"/*) continue 2 ;; esac
+    done
+    case "$point" in /dev|/dev/pts|/dev/pts/*) continue ;; esac
+    # Hidden under a later mount, which gets its own turn
+    [ -e "$point" ] || continue
+    opts=${opts#r[ow]}; opts=${opts#,}
+    mount -o "remount,bind,ro${opts:+,$opts}" "$point" || fail "cannot make $point read-only"
+done < /proc/self/mountinfo
+cd "$PWD" || fail "cannot enter $PWD"
+"#;
+
+/// Runs `$cmd`, then reports an OOM kill from the cgroup's `memory.events`,
+/// which counts them for the scope and everything in it
+const RUN_CHECKING_MEMORY: &str = r#"bash -c "$cmd"
+status=$?
+events=/sys/fs/cgroup$(sed -n 's/^0:://p' /proc/self/cgroup)/memory.events
+kills=$(sed -n 's/^oom_kill //p' "$events" 2>/dev/null)
+[ "${kills:-0}" -gt 0 ] && echo "ralph-sandbox: the command ran out of memory" >&2
+exit $status"#;
+
+/// How commands are isolated (`sandbox.backend`)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SandboxBackend {
+    /// Run commands directly
+    #[default]
+    None,
+    /// bwrap if installed, otherwise unshare
+    Auto,
+    /// bubblewrap
+    Bwrap,
+    /// util-linux `unshare` with a user namespace
+    Unshare,
+}
+
+impl fmt::Display for SandboxBackend {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            SandboxBackend::None => "none",
+            SandboxBackend::Auto => "auto",
+            SandboxBackend::Bwrap => "bwrap",
+            SandboxBackend::Unshare => "unshare",
+        })
+    }
+}
+
+/// Which sandbox rule a command ran into
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ViolationKind {
+    /// Wrote outside the writable paths
+    ReadOnlyFilesystem,
+    /// Tried to reach the network
+    Network,
+    /// Ran out of CPU time
+    CpuLimit,
+    /// Killed by the OOM killer of its memory-limited cgroup
+    MemoryLimit,
+    /// Hit the process limit
+    ProcessLimit,
+    /// The sandbox itself couldn't be set up
+    Setup,
+}
+
+impl fmt::Display for ViolationKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            ViolationKind::ReadOnlyFilesystem => "read_only_filesystem",
+            ViolationKind::Network => "network",
+            ViolationKind::CpuLimit => "cpu_limit",
+            ViolationKind::MemoryLimit => "memory_limit",
+            ViolationKind::ProcessLimit => "process_limit",
+            ViolationKind::Setup => "setup",
+        })
+    }
+}
+
+/// A command failure caused by the sandbox
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Violation {
+    pub kind: ViolationKind,
+    /// What the rule is, for the model
+    pub detail: String,
+}
+
+impl fmt::Display for Violation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "SANDBOX VIOLATION ({}): {}", self.kind, self.detail)
+    }
+}
+
+/// A resolved sandbox for one project
+#[derive(Debug, Clone)]
+pub struct Sandbox {
+    backend: SandboxBackend,
+    config: SandboxConfig,
+}
+
+impl Sandbox {
+    /// Resolve `sandbox.backend`; `None` when sandboxing is off
+    ///
+    /// Fails if the requested backend isn't installed rather than silently
+    /// running unsandboxed.
+    pub fn from_config(config: &SandboxConfig) -> Result<Option<Self>> {
+        let backend = match config.backend {
+            SandboxBackend::None => return Ok(None),
+            SandboxBackend::Auto if on_path("bwrap") => SandboxBackend::Bwrap,
+            SandboxBackend::Auto => SandboxBackend::Unshare,
+            other => other,
+        };
+        let program = backend.to_string();
+        if !on_path(&program) {
+            bail!("sandbox.backend is {} but `{}` is not installed", config.backend, program);
+        }
+        if (config.memory_mb.is_some() || config.max_procs.is_some()) && !on_path("systemd-run") {
+            bail!("sandbox.memory_mb and sandbox.max_procs need `systemd-run`, which is not installed");
+        }
+        Ok(Some(Self {
+            backend,
+            config: config.clone(),
+        }))
+    }
+
+    /// One-line description for the run header
+    pub fn describe(&self) -> String {
+        format!(
+            "{} ({}, {}s CPU, {}, {})",
+            self.backend,
+            if self.config.network { "network allowed" } else { "no network" },
+            self.config.cpu_secs,
+            self.config.memory_mb.map_or("no memory limit".to_string(), |mb| format!("{} MB", mb)),
+            self.config.max_procs.map_or("no process limit".to_string(), |n| format!("{} processes", n))
+        )
+    }
+
+    /// The command that runs `command` inside the sandbox
+    ///
+    /// The caller sets the working directory, which must be visible inside
+    /// (anything under `/` is).
+    pub fn command(&self, command: &str, project_root: &Path) -> Command {
+        let writable = self.writable(project_root);
+        let limits = format!(
+            "fail() {{ echo \"ralph-sandbox: $*\" >&2; exit {}; }}\n\
+             ulimit -t {} || fail \"cannot limit CPU time\"\n",
+            SETUP_FAILED, self.config.cpu_secs
+        );
+        let run = match self.config.memory_mb {
+            Some(_) => RUN_CHECKING_MEMORY,
+            None => "bash -c \"$cmd\"",
+        };
+
+        // The command runs as a child of the wrapper shell, not as the
+        // namespace's init, which would ignore the rlimit signals
+        let mut cmd = match self.backend {
+            SandboxBackend::Bwrap => {
+                let mut cmd = self.limited("bwrap");
+                cmd.args(["--die-with-parent", "--unshare-pid", "--ro-bind", "/", "/"])
+                    .args(["--dev", "/dev", "--proc", "/proc"]);
+                if !self.config.network {
+                    cmd.arg("--unshare-net");
+                }
+                for dir in &writable {
+                    cmd.arg("--bind").arg(dir).arg(dir);
+                }
+                cmd.args(["--", "bash", "-c"])
+                    .arg(format!("{}cmd=$1\n{}", limits, run))
+                    .arg("ralph-sandbox")
+                    .arg(command);
+                cmd
+            }
+            _ => {
+                let mut cmd = self.limited("unshare");
+                cmd.args(["--user", "--map-root-user", "--mount", "--pid", "--fork"])
+                    .args(["--kill-child", "--mount-proc"]);
+                let mut script = format!("{}{}", limits, MOUNT_SETUP);
+                if !self.config.network {
+                    cmd.arg("--net");
+                    script.push_str("ip link set lo up 2>/dev/null\n");
+                }
+                script.push_str(run);
+                cmd.args(["bash", "-c", &script, "ralph-sandbox", command]).args(&writable);
+                cmd
+            }
+        };
+        cmd.env_clear();
+        for name in BASE_ENV.iter().copied().chain(self.config.env.iter().map(String::as_str)) {
+            if let Some(value) = std::env::var_os(name) {
+                cmd.env(name, value);
+            }
+        }
+        cmd.kill_on_drop(true);
+        cmd
+    }
+
+    /// `program`, in a cgroup of its own when memory or processes are limited
+    ///
+    /// The limits cover the command and everything it starts, unlike rlimits,
+    /// which are per process (address space) or per user (processes). The
+    /// kernel enforces `MemoryMax` by OOM-killing a process in the scope,
+    /// which the wrapper script reports from `memory.events`.
+    fn limited(&self, program: &str) -> Command {
+        if !self.scoped() {
+            return Command::new(program);
+        }
+        let mut cmd = Command::new("systemd-run");
+        cmd.args(["--user", "--scope", "--quiet", "--collect"]);
+        if let Some(mb) = self.config.memory_mb {
+            cmd.arg(format!("--property=MemoryMax={}M", mb)).arg("--property=MemorySwapMax=0");
+        }
+        if let Some(n) = self.config.max_procs {
+            cmd.arg(format!("--property=TasksMax={}", n));
+        }
+        cmd.args(["--", program]);
+        cmd
+    }
+
+    /// Work out whether a failed command broke a sandbox rule
+    pub fn violation(&self, status: &ExitStatus, stderr: &str) -> Option<Violation> {
+        if status.success() {
+            return None;
+        }
+        let code = status.code();
+        let has = |needles: &[&str]| needles.iter().any(|n| stderr.contains(n));
+
+        let (kind, detail) = if code == Some(SETUP_FAILED) && has(&["ralph-sandbox: "]) {
+            (ViolationKind::Setup, format!("the {} sandbox could not be set up", self.backend))
+        } else if self.scoped() && has(&["Failed to start transient scope", "Failed to connect to bus"]) {
+            (
+                ViolationKind::Setup,
+                "sandbox.memory_mb and sandbox.max_procs need a systemd user session".to_string(),
+            )
+        } else if has(&["Read-only file system"]) {
+            (
+                ViolationKind::ReadOnlyFilesystem,
+                "only the project directory, /tmp and sandbox.writable paths can be written".to_string(),
+            )
+        } else if !self.config.network
+            && has(&[
+                "Network is unreachable",
+                "Could not resolve host",
+                "Temporary failure in name resolution",
+                "Name or service not known",
+                "failed to lookup address",
+            ])
+        {
+            (
+                ViolationKind::Network,
+                "network access is disabled; work offline (e.g. cargo --offline)".to_string(),
+            )
+        } else if code == Some(128 + SIGXCPU)
+            || status.signal() == Some(SIGXCPU)
+            || has(&["CPU time limit exceeded"])
+        {
+            (ViolationKind::CpuLimit, format!("the command used more than {}s of CPU time", self.config.cpu_secs))
+        } else if let Some(mb) = self.config.memory_mb.filter(|_| {
+            // A SIGKILL alone could be anything (a timeout, `kill -9`), so
+            // it takes the scope's OOM count or an allocation failure
+            has(&[OUT_OF_MEMORY, "Cannot allocate memory", "memory allocation of", "out of memory", "std::bad_alloc"])
+        }) {
+            (ViolationKind::MemoryLimit, format!("the command needed more than {} MB of memory", mb))
+        } else if let Some(n) = self
+            .config
+            .max_procs
+            .filter(|_| has(&["fork: retry", "fork: Resource temporarily unavailable"]))
+        {
+            (ViolationKind::ProcessLimit, format!("the command started more than {} processes", n))
+        } else {
+            return None;
+        };
+
+        Some(Violation { kind, detail })
+    }
+
+    /// Whether commands run in a cgroup scope
+    fn scoped(&self) -> bool {
+        self.config.memory_mb.is_some() || self.config.max_procs.is_some()
+    }
+
+    /// The project, `/tmp` and `sandbox.writable` paths that exist
+    fn writable(&self, project_root: &Path) -> Vec<PathBuf> {
+        let home = std::env::var_os("HOME").map(PathBuf::from);
+        let mut dirs = vec![project_root.to_path_buf(), PathBuf::from("/tmp")];
+        for path in &self.config.writable {
+            let path = match (path.strip_prefix("~/"), &home) {
+                (Some(rest), Some(home)) => home.join(rest),
+                _ => project_root.join(path),
+            };
+            dirs.push(path);
+        }
+
+        let mut resolved: Vec<PathBuf> = dirs.iter().filter_map(|d| d.canonicalize().ok()).collect();
+        resolved.dedup();
+        resolved
+    }
+}
+
+fn on_path(program: &str) -> bool {
+    std::env::var_os("PATH")
+        .is_some_and(|path| std::env::split_paths(&path).any(|dir| dir.join(program).is_file()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sandbox(network: bool) -> Sandbox {
+        Sandbox {
+            backend: SandboxBackend::Unshare,
+            config: SandboxConfig {
+                backend: SandboxBackend::Unshare,
+                network,
+                ..Default::default()
+            },
+        }
+    }
+
+    #[test]
+    fn test_violation_classification() {
+        let sandbox = sandbox(false);
+        let failed = ExitStatus::from_raw(1 << 8);
+
+        let violation = sandbox
+            .violation(&failed, "touch: cannot touch '/etc/x': Read-only file system")
+            .unwrap();
+        assert_eq!(violation.kind, ViolationKind::ReadOnlyFilesystem);
+        assert!(violation.to_string().starts_with("SANDBOX VIOLATION (read_only_filesystem)"));
+
+        let violation = sandbox.violation(&failed, "curl: (6) Could not resolve host: example.com").unwrap();
+        assert_eq!(violation.kind, ViolationKind::Network);
+        assert_eq!(sandbox.violation(&ExitStatus::from_raw(152 << 8), "").unwrap().kind, ViolationKind::CpuLimit);
+
+        // Ordinary failures, successes and allowed network use aren't violations
+        assert!(sandbox.violation(&failed, "error[E0425]: cannot find value `x`").is_none());
+        assert!(sandbox.violation(&ExitStatus::from_raw(0), "Read-only file system").is_none());
+        assert!(self::sandbox(true).violation(&failed, "Could not resolve host").is_none());
+
+        // Memory and process rules only apply when those limits are set
+        let killed = ExitStatus::from_raw(137 << 8);
+        let fork_failed = "bash: fork: retry: Resource temporarily unavailable";
+        assert!(sandbox.violation(&killed, "").is_none());
+        assert!(sandbox.violation(&failed, fork_failed).is_none());
+        let mut limited = self::sandbox(false);
+        limited.config.memory_mb = Some(512);
+        limited.config.max_procs = Some(64);
+        assert!(limited.violation(&killed, "").is_none());
+        assert_eq!(limited.violation(&killed, OUT_OF_MEMORY).unwrap().kind, ViolationKind::MemoryLimit);
+        assert_eq!(limited.violation(&failed, fork_failed).unwrap().kind, ViolationKind::ProcessLimit);
+        assert_eq!(
+            limited.violation(&failed, "Failed to connect to bus: No medium found").unwrap().kind,
+            ViolationKind::Setup
+        );
+    }
+
+    #[test]
+    fn test_limits_use_a_scope() {
+        let mut sandbox = sandbox(false);
+        assert_eq!(sandbox.command("true", Path::new("/tmp")).as_std().get_program(), "unshare");
+
+        sandbox.config.memory_mb = Some(512);
+        let cmd = sandbox.command("true", Path::new("/tmp"));
+        let args: Vec<_> = cmd.as_std().get_args().map(|a| a.to_string_lossy().into_owned()).collect();
+        assert_eq!(cmd.as_std().get_program(), "systemd-run");
+        assert!(args.contains(&"--property=MemoryMax=512M".to_string()));
+        assert!(!args.iter().any(|a| a.starts_with("--property=TasksMax")));
+        assert_eq!(args[args.iter().position(|a| a == "--").unwrap() + 1], "unshare");
+        assert!(args.iter().any(|a| a.contains("/memory.events") && a.contains(OUT_OF_MEMORY)));
+    }
+
+    #[test]
+    fn test_environment_is_allowlisted() {
+        let mut sandbox = sandbox(false);
+        sandbox.config.env = vec!["PWD".to_string()];
+        let cmd = sandbox.command("true", Path::new("/tmp"));
+        let names: Vec<String> = cmd.as_std().get_envs().map(|(k, _)| k.to_string_lossy().into_owned()).collect();
+
+        assert!(names.iter().all(|n| BASE_ENV.contains(&n.as_str()) || n == "PWD"), "{:?}", names);
+        assert_eq!(names.contains(&"PATH".to_string()), std::env::var_os("PATH").is_some());
+        assert_eq!(names.contains(&"PWD".to_string()), std::env::var_os("PWD").is_some());
+    }
+
+    /// Whether `unshare` can make an unprivileged user namespace here, which
+    /// CI containers may not allow; says why the test is skipped if not
+    fn user_namespaces(test: &str) -> bool {
+        let supported = std::process::Command::new("unshare")
+            .args(["--user", "--map-root-user", "--mount", "true"])
+            .status()
+            .is_ok_and(|s| s.success());
+        if !supported {
+            eprintln!("skipping {}: unshare cannot create a user namespace here", test);
+        }
+        supported
+    }
+
+    #[tokio::test]
+    async fn test_unshare_read_only_root() {
+        if !user_namespaces("test_unshare_read_only_root") {
+            return;
+        }
+
+        let temp = tempfile::TempDir::new().unwrap();
+        let sandbox = sandbox(false);
+        let run = |command: &str| {
+            let mut cmd = sandbox.command(command, temp.path());
+            cmd.current_dir(temp.path());
+            cmd.output()
+        };
+
+        let output = run("echo ok > inside.txt").await.unwrap();
+        assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+        assert!(temp.path().join("inside.txt").exists());
+
+        let output = run("touch /etc/ralph-sandbox-test").await.unwrap();
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        assert_eq!(
+            sandbox.violation(&output.status, &stderr).map(|v| v.kind),
+            Some(ViolationKind::ReadOnlyFilesystem)
+        );
+        assert!(!Path::new("/etc/ralph-sandbox-test").exists());
+    }
+
+    #[tokio::test]
+    async fn test_unshare_locked_mounts_are_read_only() {
+        if !user_namespaces("test_unshare_locked_mounts_are_read_only") {
+            return;
+        }
+        // Needs /srv to mount a tmpfs over
+        if !Path::new("/srv").is_dir() {
+            eprintln!("skipping test_unshare_locked_mounts_are_read_only: there is no /srv");
+            return;
+        }
+
+        // A nosuid tmpfs mounted in an outer namespace has that flag locked
+        // in the sandbox's
+        let temp = tempfile::TempDir::new().unwrap();
+        let sandbox = sandbox(false);
+        let inner = sandbox.command("touch /srv/ralph-sandbox-test", temp.path());
+        let inner = inner.as_std();
+        let mut cmd = Command::new("unshare");
+        cmd.args(["--user", "--map-root-user", "--mount", "bash", "-c"])
+            .arg("mount -t tmpfs -o nosuid,nodev tmpfs /srv || exit 99; exec \"$@\"")
+            .arg("outer")
+            .arg(inner.get_program())
+            .args(inner.get_args())
+            .env_clear()
+            .envs(inner.get_envs().filter_map(|(k, v)| Some((k, v?))))
+            .current_dir(temp.path());
+        let output = cmd.output().await.unwrap();
+        let stderr = String::from_utf8_lossy(&output.stderr);
+
+        assert_eq!(
+            sandbox.violation(&output.status, &stderr).map(|v| v.kind),
+            Some(ViolationKind::ReadOnlyFilesystem),
+            "{}",
+            stderr
+        );
+    }
+}
@@ -1,37 +1,18 @@
 //! Task Parser - Integrates with Beads issue tracker
 //!
-//! Parses issues from `bd ready` or `.beads/issues.jsonl` to find work.
+//! Reads issues from `.beads/issues.jsonl` (see `beads`), or from the `bd`
+//! CLI when there is no JSONL store, to find work.
 //! Extracts acceptance criteria from issue descriptions.
 
 use anyhow::{Context, Result};
 use regex::Regex;
-use serde::{Deserialize, Serialize};
 use std::path::Path;
 use std::process::Command;
 
-/// A beads issue entry
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct BeadTask {
-    pub id: String,
-    pub title: String,
-    #[serde(default)]
-    pub description: String,
-    #[serde(default)]
-    pub notes: String,
-    pub status: String,
-    #[serde(default)]
-    pub priority: u8,
-    #[serde(default)]
-    pub issue_type: String,
-    #[serde(default)]
-    pub owner: Option<String>,
-    #[serde(default)]
-    pub labels: Vec<String>,
-    #[serde(default)]
-    pub depends_on: Vec<String>,
-    #[serde(default)]
-    pub blocks: Vec<String>,
-}
+use crate::beads;
+
+/// A beads issue entry (also what `bd ... --json` prints)
+pub use tachikoma_plugin::trackers::beads::Bead as BeadTask;
 
 /// Acceptance criteria item from an issue description
 #[derive(Debug, Clone)]
@@ -51,6 +32,10 @@ pub struct ParsedTask {
 
 /// Get all ready (unblocked) tasks from beads
 pub fn get_ready_tasks(project_root: &Path) -> Result<Vec<BeadTask>> {
+    if let Some(store) = beads::for_project(project_root) {
+        return Ok(store.ready()?);
+    }
+
     let output = Command::new("bd")
         .args(["ready", "--json"])
         .current_dir(project_root)
@@ -73,6 +58,10 @@ pub fn get_ready_tasks(project_root: &Path) -> Result<Vec<BeadTask>> {
 
 /// Get all open tasks from beads
 pub fn get_all_open_tasks(project_root: &Path) -> Result<Vec<BeadTask>> {
+    if let Some(store) = beads::for_project(project_root) {
+        return Ok(store.open()?);
+    }
+
     let output = Command::new("bd")
         .args(["list", "--status=open", "--json"])
         .current_dir(project_root)
@@ -94,6 +83,10 @@ pub fn get_all_open_tasks(project_root: &Path) -> Result<Vec<BeadTask>> {
 
 /// Get a specific task by ID
 pub fn get_task(project_root: &Path, task_id: &str) -> Result<BeadTask> {
+    if let Some(store) = beads::for_project(project_root) {
+        return Ok(store.get(task_id)?);
+    }
+
     let output = Command::new("bd")
         .args(["show", task_id, "--json"])
         .current_dir(project_root)
@@ -155,32 +148,35 @@ pub fn parse_task(task: &BeadTask) -> ParsedTask {
     }
 }
 
-/// Find the next unblocked, uncompleted task
-pub fn find_next_task(project_root: &Path) -> Result<Option<ParsedTask>> {
-    let tasks = get_ready_tasks(project_root)?;
-
-    for task in tasks {
-        // Skip closed/completed tasks
-        if task.status == "closed" || task.status == "completed" {
-            continue;
-        }
+/// Update a task's status
+pub fn update_task_status(project_root: &Path, task_id: &str, status: &str) -> Result<()> {
+    if let Some(store) = beads::for_project(project_root) {
+        return Ok(store.set_status(task_id, status)?);
+    }
 
-        let parsed = parse_task(&task);
+    let output = Command::new("bd")
+        .args(["update", task_id, &format!("--status={}", status)])
+        .current_dir(project_root)
+        .output()
+        .context("Failed to run 'bd update'")?;
 
-        // Return first task that has uncompleted criteria or no criteria defined
-        // (If no criteria, we still need to work on it)
-        if !parsed.all_complete || parsed.acceptance_criteria.is_empty() {
-            return Ok(Some(parsed));
-        }
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        anyhow::bail!("bd update failed: {}", stderr);
     }
 
-    Ok(None)
+    Ok(())
 }
 
-/// Update a task's status via bd
-pub fn update_task_status(project_root: &Path, task_id: &str, status: &str) -> Result<()> {
+/// Add a line to a task's notes
+pub fn append_task_note(project_root: &Path, task_id: &str, note: &str) -> Result<()> {
+    if let Some(store) = beads::for_project(project_root) {
+        return Ok(store.append_note(task_id, note)?);
+    }
+
+    let notes = crate::beads::append_line(&get_task(project_root, task_id)?.notes, note);
     let output = Command::new("bd")
-        .args(["update", task_id, &format!("--status={}", status)])
+        .args(["update", task_id, &format!("--notes={}", notes)])
         .current_dir(project_root)
         .output()
         .context("Failed to run 'bd update'")?;
@@ -193,8 +189,12 @@ pub fn update_task_status(project_root: &Path, task_id: &str, status: &str) -> R
     Ok(())
 }
 
-/// Close a task via bd
+/// Close a task
 pub fn close_task(project_root: &Path, task_id: &str, reason: Option<&str>) -> Result<()> {
+    if let Some(store) = beads::for_project(project_root) {
+        return Ok(store.close(task_id, reason)?);
+    }
+
     let mut args = vec!["close".to_string(), task_id.to_string()];
     
     if let Some(r) = reason {
@@ -218,12 +218,18 @@ pub fn close_task(project_root: &Path, task_id: &str, reason: Option<&str>) -> R
 }
 
 /// Sync beads state (commits and pushes .beads/ changes)
+///
+/// With a native store and no `bd` installed there is nothing to sync: the
+/// JSONL is already up to date and gets committed with the task's changes.
 pub fn sync_beads(project_root: &Path) -> Result<()> {
-    let output = Command::new("bd")
-        .args(["sync"])
-        .current_dir(project_root)
-        .output()
-        .context("Failed to run 'bd sync'")?;
+    let output = match Command::new("bd").args(["sync"]).current_dir(project_root).output() {
+        Ok(output) => output,
+        Err(e) if beads::for_project(project_root).is_some() => {
+            tracing::debug!("Skipping bd sync: {}", e);
+            return Ok(());
+        }
+        Err(e) => return Err(e).context("Failed to run 'bd sync'"),
+    };
 
     if !output.status.success() {
         let stderr = String::from_utf8_lossy(&output.stderr);
@@ -0,0 +1,484 @@
+//! Trackers - Where tasks come from and where their status goes
+//!
+//! The loop works through a [`Tracker`], picked with `--tracker`. Each kind
+//! is one of tachikoma-plugin's built-in `TrackerPlugin`s, driven through
+//! [`PluginTracker`]:
+//!
+//! - `beads` (default): the beads issue store (`.beads/issues.jsonl`); a
+//!   project without the JSONL export, or `RALPH_BEADS=bd`, is driven
+//!   through the `bd` CLI instead ([`BdTracker`])
+//! - `specs`: THE PIN (`specs/README.md`); a spec is done once every box in
+//!   its Acceptance Criteria section is ticked
+//! - `issues`: an offline GitHub (`gh issue list --json ...`) or Jira search
+//!   export
+//!
+//! Every tracker hands out `BeadTask`s so prompts, verification and progress
+//! work the same whatever the source.
+//!
+//! Only beads is reachable from the model's `beads` tool. With any other
+//! tracker the model's `beads close` is deferred and the harness completes
+//! the task itself.
+
+use anyhow::{Context, Result};
+use regex::Regex;
+use std::future::Future;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use tachikoma_plugin::trackers::{BeadsTracker, IssueExportTracker, SpecsTracker};
+use tachikoma_plugin::{Task, TaskStatus, TrackerPlugin};
+
+use crate::beads;
+use crate::task_parser::{self, parse_task, BeadTask, ParsedTask, ProgressSummary};
+
+const DEFAULT_SPECS_DIR: &str = "specs";
+
+/// Which tracker drives the loop
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum TrackerKind {
+    /// Beads issue tracker (.beads/)
+    Beads,
+    /// specs/README.md lookup table and spec checkboxes
+    Specs,
+    /// GitHub or Jira issue export (JSON file given with --tracker-path)
+    Issues,
+}
+
+impl TrackerKind {
+    fn name(&self) -> &'static str {
+        match self {
+            TrackerKind::Beads => "beads",
+            TrackerKind::Specs => "specs",
+            TrackerKind::Issues => "issues",
+        }
+    }
+}
+
+/// A source of tasks
+pub trait Tracker: Send + Sync {
+    fn name(&self) -> &'static str;
+
+    /// Unblocked tasks that still need work, in the order to work them
+    fn ready_tasks(&self) -> Result<Vec<BeadTask>>;
+
+    /// Every task that isn't closed
+    fn open_tasks(&self) -> Result<Vec<BeadTask>>;
+
+    fn get_task(&self, id: &str) -> Result<BeadTask>;
+
+    /// Mark a task in progress
+    fn start_task(&self, id: &str) -> Result<()>;
+
+    fn complete_task(&self, id: &str, reason: Option<&str>) -> Result<()>;
+
+    /// Park a task for a human
+    fn block_task(&self, id: &str) -> Result<()>;
+
+    fn progress(&self) -> Result<ProgressSummary>;
+
+    /// Add a line to a task's notes (triage history); trackers without
+    /// notes keep it only in `.ralph/attempts.jsonl`
+    fn note_task(&self, _id: &str, _note: &str) -> Result<()> {
+        Ok(())
+    }
+
+    /// Persist tracker state after a task (e.g. `bd sync`)
+    fn sync(&self) -> Result<()> {
+        Ok(())
+    }
+
+    /// Open tasks that `id` is waiting on, deepest first
+    fn blocked_by(&self, _id: &str) -> Result<Vec<String>> {
+        Ok(Vec::new())
+    }
+
+    /// Whether the model's `beads close` reaches this tracker
+    fn model_closes_tasks(&self) -> bool {
+        false
+    }
+
+    /// Find the next unblocked, uncompleted task
+    fn next_task(&self) -> Result<Option<ParsedTask>> {
+        for task in self.ready_tasks()? {
+            // Skip closed/completed tasks
+            if task.status == "closed" || task.status == "completed" {
+                continue;
+            }
+
+            let parsed = parse_task(&task);
+
+            // Return first task that has uncompleted criteria or no criteria defined
+            // (If no criteria, we still need to work on it)
+            if !parsed.all_complete || parsed.acceptance_criteria.is_empty() {
+                return Ok(Some(parsed));
+            }
+        }
+
+        Ok(None)
+    }
+}
+
+/// Open the tracker for a project
+///
+/// `path` is the specs directory for `specs` (default `specs/`) and the
+/// export file for `issues`, relative to the project root.
+pub fn create(kind: TrackerKind, project_root: &Path, path: Option<&Path>) -> Result<Arc<dyn Tracker>> {
+    let root = project_root.to_path_buf();
+
+    let plugin: Box<dyn TrackerPlugin> = match kind {
+        TrackerKind::Beads => {
+            if !root.join(".beads").exists() {
+                anyhow::bail!(
+                    ".beads/ directory not found at {}. Is this a beads-tracked project?\nRun 'bd init' to initialize beads, or pick another --tracker.",
+                    root.display()
+                );
+            }
+            if beads::for_project(&root).is_none() {
+                return Ok(Arc::new(BdTracker { root }));
+            }
+            Box::new(BeadsTracker::new())
+        }
+        TrackerKind::Specs => Box::new(SpecsTracker::with_dir(path.unwrap_or(Path::new(DEFAULT_SPECS_DIR)))),
+        TrackerKind::Issues => {
+            let file = path.context("--tracker issues needs --tracker-path <export.json>")?;
+            Box::new(IssueExportTracker::new(file))
+        }
+    };
+
+    Ok(Arc::new(PluginTracker::open(kind, plugin, &root)?))
+}
+
+/// Wait for a tracker plugin call
+///
+/// The built-in trackers only touch the filesystem and run short commands,
+/// so this never needs the tokio runtime the loop itself runs on.
+fn wait<T>(call: impl Future<Output = tachikoma_plugin::Result<T>>) -> Result<T> {
+    Ok(futures::executor::block_on(call)?)
+}
+
+// ============================================================================
+// Tracker plugins
+// ============================================================================
+
+/// A `TrackerPlugin` driven from the loop
+pub struct PluginTracker {
+    kind: TrackerKind,
+    plugin: Box<dyn TrackerPlugin>,
+}
+
+impl PluginTracker {
+    /// Initialize `plugin` against the project
+    pub fn open(kind: TrackerKind, mut plugin: Box<dyn TrackerPlugin>, project_root: &Path) -> Result<Self> {
+        wait(plugin.init(project_root))?;
+        Ok(Self { kind, plugin })
+    }
+}
+
+impl Tracker for PluginTracker {
+    fn name(&self) -> &'static str {
+        self.kind.name()
+    }
+
+    fn ready_tasks(&self) -> Result<Vec<BeadTask>> {
+        Ok(wait(self.plugin.ready_tasks())?.into_iter().map(|t| bead_task(t, self.kind)).collect())
+    }
+
+    fn open_tasks(&self) -> Result<Vec<BeadTask>> {
+        Ok(wait(self.plugin.list_tasks())?
+            .into_iter()
+            .filter(|t| t.status != TaskStatus::Closed)
+            .map(|t| bead_task(t, self.kind))
+            .collect())
+    }
+
+    fn get_task(&self, id: &str) -> Result<BeadTask> {
+        wait(self.plugin.get_task(id))?
+            .map(|t| bead_task(t, self.kind))
+            .with_context(|| format!("Task {} not found in the {} tracker", id, self.name()))
+    }
+
+    fn start_task(&self, id: &str) -> Result<()> {
+        wait(self.plugin.start_task(id))
+    }
+
+    fn complete_task(&self, id: &str, reason: Option<&str>) -> Result<()> {
+        wait(self.plugin.complete_task_with_reason(id, reason))
+    }
+
+    fn block_task(&self, id: &str) -> Result<()> {
+        wait(self.plugin.block_task(id))
+    }
+
+    fn progress(&self) -> Result<ProgressSummary> {
+        let progress = wait(self.plugin.progress())?;
+        Ok(ProgressSummary {
+            total_tasks: progress.total_tasks,
+            completed_tasks: progress.completed_tasks,
+            ready_tasks: wait(self.plugin.ready_tasks())?.len(),
+            total_criteria: progress.total_criteria,
+            completed_criteria: progress.completed_criteria,
+        })
+    }
+
+    fn note_task(&self, id: &str, note: &str) -> Result<()> {
+        wait(self.plugin.note_task(id, note))
+    }
+
+    fn sync(&self) -> Result<()> {
+        wait(self.plugin.sync())
+    }
+
+    fn blocked_by(&self, id: &str) -> Result<Vec<String>> {
+        wait(self.plugin.blocked_by(id))
+    }
+
+    fn model_closes_tasks(&self) -> bool {
+        self.kind == TrackerKind::Beads
+    }
+}
+
+/// A plugin task as the loop sees it
+///
+/// Checkboxes are taken out of the description and the tracker's own
+/// criteria written into `notes` instead, so `parse_task` counts exactly the
+/// criteria the tracker does (for a spec, only its Acceptance Criteria).
+fn bead_task(task: Task, kind: TrackerKind) -> BeadTask {
+    let checkbox_re = Regex::new(r"^\s*[-*]\s*\[[ xX]\]").unwrap();
+    let without_checkboxes = |text: &str| {
+        text.lines()
+            .filter(|line| !checkbox_re.is_match(line))
+            .collect::<Vec<_>>()
+            .join("\n")
+    };
+    let strings = |key: &str| -> Vec<String> {
+        task.metadata[key]
+            .as_array()
+            .map(|v| v.iter().filter_map(|s| s.as_str().map(str::to_string)).collect())
+            .unwrap_or_default()
+    };
+
+    let mut notes = without_checkboxes(task.metadata["notes"].as_str().unwrap_or_default())
+        .trim_end()
+        .to_string();
+    for criterion in &task.criteria {
+        let line = format!("- [{}] {}", if criterion.completed { "x" } else { " " }, criterion.text);
+        notes = beads::append_line(&notes, &line);
+    }
+
+    BeadTask {
+        description: without_checkboxes(&task.description),
+        notes,
+        status: task.status.as_str().to_string(),
+        priority: task.priority,
+        issue_type: task.metadata["issue_type"].as_str().unwrap_or(kind.name()).to_string(),
+        owner: task.metadata["owner"].as_str().map(str::to_string),
+        labels: strings("labels"),
+        blocks: strings("blocks"),
+        depends_on: task.dependencies,
+        id: task.id,
+        title: task.name,
+    }
+}
+
+// ============================================================================
+// bd CLI
+// ============================================================================
+
+/// Beads through the `bd` CLI, for projects without a JSONL export
+pub struct BdTracker {
+    root: PathBuf,
+}
+
+impl Tracker for BdTracker {
+    fn name(&self) -> &'static str {
+        "beads"
+    }
+
+    fn ready_tasks(&self) -> Result<Vec<BeadTask>> {
+        task_parser::get_ready_tasks(&self.root)
+    }
+
+    fn open_tasks(&self) -> Result<Vec<BeadTask>> {
+        task_parser::get_all_open_tasks(&self.root)
+    }
+
+    fn get_task(&self, id: &str) -> Result<BeadTask> {
+        task_parser::get_task(&self.root, id)
+    }
+
+    fn start_task(&self, id: &str) -> Result<()> {
+        task_parser::update_task_status(&self.root, id, "in_progress")
+    }
+
+    fn complete_task(&self, id: &str, reason: Option<&str>) -> Result<()> {
+        task_parser::close_task(&self.root, id, reason)
+    }
+
+    fn block_task(&self, id: &str) -> Result<()> {
+        task_parser::update_task_status(&self.root, id, "blocked")
+    }
+
+    fn note_task(&self, id: &str, note: &str) -> Result<()> {
+        task_parser::append_task_note(&self.root, id, note)
+    }
+
+    fn progress(&self) -> Result<ProgressSummary> {
+        task_parser::get_progress_summary(&self.root)
+    }
+
+    fn sync(&self) -> Result<()> {
+        task_parser::sync_beads(&self.root)
+    }
+
+    fn model_closes_tasks(&self) -> bool {
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_beads_tracker() {
+        let temp = TempDir::new().unwrap();
+        std::fs::create_dir_all(temp.path().join(".beads")).unwrap();
+        std::fs::write(
+            temp.path().join(".beads/issues.jsonl"),
+            concat!(
+                r#"{"id":"b-1","title":"Base","status":"open","priority":1,"labels":["api"],"description":"Build it\n- [ ] a\n- [x] b","notes":"Earlier attempt"}"#,
+                "\n",
+                r#"{"id":"b-2","title":"After","status":"open","priority":0,"dependencies":[{"issue_id":"b-2","depends_on_id":"b-1","type":"blocks"}]}"#,
+                "\n",
+            ),
+        )
+        .unwrap();
+
+        let tracker = create(TrackerKind::Beads, temp.path(), None).unwrap();
+        assert!(tracker.model_closes_tasks());
+
+        let next = tracker.next_task().unwrap().unwrap();
+        assert_eq!(next.task.id, "b-1");
+        assert_eq!(next.task.labels, ["api"]);
+        assert_eq!(next.task.blocks, ["b-2"]);
+        assert_eq!(next.task.description, "Build it");
+        assert_eq!(next.task.notes, "Earlier attempt\n- [ ] a\n- [x] b");
+        assert_eq!(next.acceptance_criteria.len(), 2);
+        assert_eq!(tracker.blocked_by("b-2").unwrap(), ["b-1"]);
+
+        tracker.note_task("b-1", "attempt 2: blocked").unwrap();
+        tracker.complete_task("b-1", Some("done")).unwrap();
+        assert_eq!(tracker.next_task().unwrap().unwrap().task.id, "b-2");
+        tracker.block_task("b-2").unwrap();
+        assert!(tracker.next_task().unwrap().is_none());
+        assert_eq!(tracker.get_task("b-2").unwrap().status, "blocked");
+        assert!(tracker.get_task("b-9").is_err());
+
+        let store = std::fs::read_to_string(temp.path().join(".beads/issues.jsonl")).unwrap();
+        let base: serde_json::Value = serde_json::from_str(store.lines().next().unwrap()).unwrap();
+        assert_eq!(base["close_reason"], "done");
+        assert_eq!(base["notes"], "Earlier attempt\nattempt 2: blocked");
+    }
+
+    #[test]
+    fn test_specs_tracker() {
+        let temp = TempDir::new().unwrap();
+        let specs = temp.path().join("specs");
+        std::fs::create_dir_all(specs.join("phase-00-setup")).unwrap();
+        std::fs::write(
+            specs.join("README.md"),
+            "## Phase 0: Setup (001-003)\n| Spec | File | Keywords |\n|---|---|---|\n\
+             | 001 | [Scaffold](phase-00-setup/001-scaffold.md) | init |\n\
+             | 002 | [Workspace](phase-00-setup/002-workspace.md) | cargo |\n\
+             | 003 | [CI](phase-00-setup/003-ci.md) | ci |\n",
+        )
+        .unwrap();
+        std::fs::write(
+            specs.join("phase-00-setup/001-scaffold.md"),
+            "# 001 - Scaffold\n\n**Status:** Planned\n**Dependencies:** None\n\n## Acceptance Criteria\n\n- [x] dirs\n- [ ] readme\n\n## Notes\n\n- [ ] not a criterion\n",
+        )
+        .unwrap();
+        std::fs::write(
+            specs.join("phase-00-setup/002-workspace.md"),
+            "# 002 - Workspace\n\n**Status:** Planned\n**Dependencies:** 001-scaffold\n\n## Acceptance Criteria\n\n- [ ] Cargo.toml\n",
+        )
+        .unwrap();
+        std::fs::write(
+            specs.join("phase-00-setup/003-ci.md"),
+            "# 003 - CI\n\n**Status:** Planned\n\n## Acceptance Criteria\n\n- [ ] workflow\n",
+        )
+        .unwrap();
+
+        let tracker = create(TrackerKind::Specs, temp.path(), None).unwrap();
+
+        let next = tracker.next_task().unwrap().unwrap();
+        assert_eq!(next.task.id, "001");
+        assert_eq!(next.acceptance_criteria.len(), 2);
+        assert_eq!(next.task.issue_type, "spec (phase 0)");
+        assert_eq!(tracker.ready_tasks().unwrap().len(), 2);
+
+        tracker.start_task("001").unwrap();
+        assert_eq!(tracker.get_task("001").unwrap().status, "in_progress");
+
+        tracker.complete_task("001", Some("done")).unwrap();
+        let spec = std::fs::read_to_string(specs.join("phase-00-setup/001-scaffold.md")).unwrap();
+        assert!(spec.contains("**Status:** Complete"));
+        assert!(spec.contains("- [x] readme"));
+        assert!(spec.contains("- [ ] not a criterion"));
+
+        let next = tracker.next_task().unwrap().unwrap();
+        assert_eq!(next.task.id, "002");
+        assert_eq!(next.task.depends_on, ["001"]);
+
+        tracker.block_task("002").unwrap();
+        assert_eq!(tracker.next_task().unwrap().unwrap().task.id, "003");
+
+        let progress = tracker.progress().unwrap();
+        assert_eq!((progress.completed_tasks, progress.total_tasks, progress.ready_tasks), (1, 3, 1));
+    }
+
+    #[test]
+    fn test_github_export() {
+        let temp = TempDir::new().unwrap();
+        std::fs::write(
+            temp.path().join("issues.json"),
+            r##"[
+                {"number": 7, "title": "Add login", "state": "OPEN", "labels": [{"name": "P1"}],
+                 "assignees": [{"login": "octo"}], "body": "Depends on #3\n\n- [ ] form\n- [ ] session"},
+                {"number": 3, "title": "Add users table", "state": "OPEN", "labels": [], "body": ""},
+                {"number": 1, "title": "Old", "state": "CLOSED", "labels": [], "body": ""}
+            ]"##,
+        )
+        .unwrap();
+
+        let tracker = create(TrackerKind::Issues, temp.path(), Some(Path::new("issues.json"))).unwrap();
+        assert!(!tracker.model_closes_tasks());
+
+        // #7 has the higher priority but waits on #3
+        assert_eq!(tracker.next_task().unwrap().unwrap().task.id, "3");
+        tracker.start_task("3").unwrap();
+        assert_eq!(tracker.get_task("3").unwrap().status, "in_progress");
+
+        tracker.complete_task("3", None).unwrap();
+        let next = tracker.next_task().unwrap().unwrap();
+        assert_eq!((next.task.id.as_str(), next.task.priority), ("7", 1));
+        assert_eq!(next.task.owner.as_deref(), Some("octo"));
+        assert_eq!(next.task.issue_type, "issue");
+        assert_eq!(next.acceptance_criteria.len(), 2);
+
+        let saved: serde_json::Value =
+            serde_json::from_str(&std::fs::read_to_string(temp.path().join("issues.json")).unwrap()).unwrap();
+        assert_eq!(saved[1]["state"], "CLOSED");
+        assert_eq!(saved[1]["labels"], serde_json::json!([]));
+    }
+
+    #[test]
+    fn test_missing_sources() {
+        let temp = TempDir::new().unwrap();
+        assert!(create(TrackerKind::Beads, temp.path(), None).is_err());
+        assert!(create(TrackerKind::Specs, temp.path(), None).is_err());
+        assert!(create(TrackerKind::Issues, temp.path(), None).is_err());
+        assert!(create(TrackerKind::Issues, temp.path(), Some(Path::new("missing.json"))).is_err());
+    }
+}
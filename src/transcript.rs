@@ -0,0 +1,381 @@
+//! Transcripts - Per-task JSONL record of the agentic loop
+//!
+//! Every iteration's new prompt messages, model response and tool results
+//! are appended to `.ralph/transcripts/<task-id>.jsonl` as they happen, so a
+//! run that is killed or stops early can be picked up again with
+//! `ralph resume <task-id>`.
+//!
+//! A transcript holds one session per run. Each session opens with a `start`
+//! record carrying the conversation it began from (the task prompt for a
+//! fresh run, the rebuilt conversation for a resume) and closes with an
+//! `end` record - unless the process died, which is exactly the case resume
+//! is for.
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use crate::claude_client::{append_user, ApiResponse, ContentBlock, LoopResult, Message, Role, StopReason, EDIT_TOOLS};
+use crate::compaction;
+
+const TRANSCRIPT_DIR: &str = ".ralph/transcripts";
+
+/// One line of a transcript
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Record {
+    /// A run (fresh or resumed) began from `messages`
+    Start {
+        timestamp: String,
+        task_id: String,
+        backend: String,
+        model: String,
+        /// Iterations already completed before this session
+        iteration: usize,
+        /// Tool calls waited for a human's approval (`--attended`)
+        #[serde(default)]
+        attended: bool,
+        messages: Vec<Message>,
+    },
+    /// One model call and the tool calls it triggered
+    Iteration {
+        timestamp: String,
+        iteration: usize,
+        /// Messages added to the conversation just before the call (interventions)
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        injected: Vec<Message>,
+        response: ApiResponse,
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        tool_results: Vec<ContentBlock>,
+    },
+    /// The conversation was compacted in place; `messages` replaces it
+    Compacted {
+        timestamp: String,
+        iteration: usize,
+        messages: Vec<Message>,
+    },
+    /// The loop returned normally
+    End {
+        timestamp: String,
+        stop_reason: StopReason,
+        iterations: usize,
+        input_tokens: u32,
+        output_tokens: u32,
+    },
+}
+
+/// Appends records to a task's transcript file
+#[derive(Debug, Clone)]
+pub struct Transcript {
+    path: PathBuf,
+    task_id: String,
+}
+
+impl Transcript {
+    pub fn new(project_root: &Path, task_id: &str) -> Self {
+        Self {
+            path: transcript_path(project_root, task_id),
+            task_id: task_id.to_string(),
+        }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Open a new session
+    pub fn start(&self, backend: &str, model: &str, iteration: usize, attended: bool, messages: &[Message]) -> Result<()> {
+        self.append(&Record::Start {
+            timestamp: now(),
+            task_id: self.task_id.clone(),
+            backend: backend.to_string(),
+            model: model.to_string(),
+            iteration,
+            attended,
+            messages: messages.to_vec(),
+        })
+    }
+
+    /// Record a completed iteration
+    pub fn record_iteration(
+        &self,
+        iteration: usize,
+        injected: &[Message],
+        response: &ApiResponse,
+        tool_results: &[ContentBlock],
+    ) -> Result<()> {
+        self.append(&Record::Iteration {
+            timestamp: now(),
+            iteration,
+            injected: injected.to_vec(),
+            response: response.clone(),
+            tool_results: tool_results.to_vec(),
+        })
+    }
+
+    /// Record a mid-session compaction
+    pub fn record_compaction(&self, iteration: usize, messages: &[Message]) -> Result<()> {
+        self.append(&Record::Compacted {
+            timestamp: now(),
+            iteration,
+            messages: messages.to_vec(),
+        })
+    }
+
+    /// Close the session with the loop's outcome
+    pub fn finish(&self, result: &LoopResult) -> Result<()> {
+        self.append(&Record::End {
+            timestamp: now(),
+            stop_reason: result.stop_reason.clone(),
+            iterations: result.iterations,
+            input_tokens: result.total_input_tokens,
+            output_tokens: result.total_output_tokens,
+        })
+    }
+
+    fn append(&self, record: &Record) -> Result<()> {
+        if let Some(parent) = self.path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+
+        let line = serde_json::to_string(record)?;
+        let mut file = std::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)
+            .with_context(|| format!("Failed to open transcript {}", self.path.display()))?;
+        writeln!(file, "{}", line)?;
+
+        Ok(())
+    }
+}
+
+/// Where a task's transcript lives
+///
+/// Task IDs are used as file names, with anything that isn't safe in a path
+/// replaced by '_'.
+pub fn transcript_path(project_root: &Path, task_id: &str) -> PathBuf {
+    let file_name: String = task_id
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() || c == '-' || c == '_' || c == '.' { c } else { '_' })
+        .collect();
+    project_root.join(TRANSCRIPT_DIR).join(format!("{}.jsonl", file_name))
+}
+
+/// The state a loop starts (or restarts) from
+#[derive(Debug, Clone)]
+pub struct ResumePoint {
+    pub messages: Vec<Message>,
+    /// Iterations already completed
+    pub iteration: usize,
+    /// Successful edit_file outputs so far (for progress tracking)
+    pub tool_outputs: Vec<String>,
+    /// How the last session ended (None if it was interrupted)
+    pub last_stop: Option<StopReason>,
+    /// Whether the last session ran attended, so a resume asks again
+    pub attended: bool,
+}
+
+impl ResumePoint {
+    /// A fresh conversation starting from the task prompt
+    pub fn fresh(initial_message: &str) -> Self {
+        Self {
+            messages: vec![Message {
+                role: Role::User,
+                content: vec![ContentBlock::Text {
+                    text: initial_message.to_string(),
+                }],
+            }],
+            iteration: 0,
+            tool_outputs: Vec::new(),
+            last_stop: None,
+            attended: false,
+        }
+    }
+
+    /// Replace everything but the task prompt and the last `keep_recent`
+    /// exchanges with a short outline of what was done
+    pub fn compacted(mut self, keep_recent: usize) -> Self {
+        self.messages = compaction::compact(&self.messages, keep_recent);
+        self
+    }
+}
+
+/// Rebuild the latest session of a task's transcript
+pub fn load(project_root: &Path, task_id: &str) -> Result<ResumePoint> {
+    let path = transcript_path(project_root, task_id);
+    let content = std::fs::read_to_string(&path)
+        .with_context(|| format!("No transcript for task {} (expected {})", task_id, path.display()))?;
+
+    let mut records = Vec::new();
+    for (line_no, line) in content.lines().enumerate() {
+        if line.trim().is_empty() {
+            continue;
+        }
+        match serde_json::from_str::<Record>(line) {
+            Ok(record) => records.push(record),
+            // A kill mid-write leaves a torn last line; everything before it is intact
+            Err(e) => tracing::warn!("Skipping transcript line {}: {}", line_no + 1, e),
+        }
+    }
+
+    let start = records
+        .iter()
+        .rposition(|r| matches!(r, Record::Start { .. }))
+        .with_context(|| format!("Transcript {} has no session to resume", path.display()))?;
+
+    let mut point = ResumePoint {
+        messages: Vec::new(),
+        iteration: 0,
+        tool_outputs: Vec::new(),
+        last_stop: None,
+        attended: false,
+    };
+
+    for record in records.drain(start..) {
+        match record {
+            Record::Start {
+                iteration,
+                attended,
+                messages,
+                ..
+            } => {
+                point.iteration = iteration;
+                point.attended = attended;
+                point.messages = messages;
+            }
+            Record::Iteration {
+                iteration,
+                injected,
+                response,
+                tool_results,
+                ..
+            } => {
+                point.iteration = iteration;
+                for message in injected {
+                    append_user(&mut point.messages, message.content);
+                }
+                point.messages.push(Message {
+                    role: Role::Assistant,
+                    content: response.content,
+                });
+                if !tool_results.is_empty() {
+                    point.messages.push(Message {
+                        role: Role::User,
+                        content: tool_results,
+                    });
+                }
+            }
+            Record::Compacted { messages, .. } => point.messages = messages,
+            Record::End { stop_reason, .. } => point.last_stop = Some(stop_reason),
+        }
+    }
+
+    point.tool_outputs = edit_outputs(&point.messages);
+    Ok(point)
+}
+
+/// Successful edit_file and multi_edit results in a conversation
+fn edit_outputs(messages: &[Message]) -> Vec<String> {
+    let mut tool_names: HashMap<&str, &str> = HashMap::new();
+    let mut outputs = Vec::new();
+
+    for block in messages.iter().flat_map(|m| &m.content) {
+        match block {
+            ContentBlock::ToolUse { id, name, .. } => {
+                tool_names.insert(id, name);
+            }
+            ContentBlock::ToolResult {
+                tool_use_id,
+                content,
+                is_error,
+            } => {
+                let name = tool_names.get(tool_use_id.as_str());
+                if is_error.is_none() && name.is_some_and(|name| EDIT_TOOLS.contains(name)) {
+                    outputs.push(content.clone());
+                }
+            }
+            ContentBlock::Text { .. } => {}
+        }
+    }
+
+    outputs
+}
+
+fn now() -> String {
+    chrono::Local::now().to_rfc3339()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backends::MockBackend;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_record_and_load_latest_session() {
+        let temp = TempDir::new().unwrap();
+        let transcript = Transcript::new(temp.path(), "proj-1.2");
+
+        // An older session that must be ignored
+        transcript.start("mock", "mock", 0, true, &ResumePoint::fresh("old").messages).unwrap();
+
+        let initial = ResumePoint::fresh("Create hello.txt").messages;
+        transcript.start("mock", "mock", 0, false, &initial).unwrap();
+        let edit = MockBackend::tool_use(
+            "edit_file",
+            serde_json::json!({"path": "hello.txt", "old_string": "", "new_string": "hi"}),
+        );
+        let ContentBlock::ToolUse { id, .. } = &edit.content[0] else {
+            panic!("expected tool use");
+        };
+        let results = vec![ContentBlock::ToolResult {
+            tool_use_id: id.clone(),
+            content: "Created new file: hello.txt".to_string(),
+            is_error: None,
+        }];
+        transcript.record_iteration(1, &[], &edit, &results).unwrap();
+
+        // Simulate a kill in the middle of writing the next record
+        let mut file = std::fs::OpenOptions::new().append(true).open(transcript.path()).unwrap();
+        write!(file, "{{\"type\":\"iteration\",\"itera").unwrap();
+
+        let point = load(temp.path(), "proj-1.2").unwrap();
+        assert_eq!(point.iteration, 1);
+        assert_eq!(point.messages.len(), 3);
+        assert!(matches!(point.messages[1].role, Role::Assistant));
+        assert_eq!(point.tool_outputs, ["Created new file: hello.txt"]);
+        assert!(point.last_stop.is_none());
+        assert!(!point.attended);
+        assert!(load(temp.path(), "missing").is_err());
+    }
+
+    #[test]
+    fn test_resume_keeps_multi_edit_outputs() {
+        let temp = TempDir::new().unwrap();
+        let transcript = Transcript::new(temp.path(), "proj-2");
+        transcript.start("mock", "mock", 0, true, &ResumePoint::fresh("Rename foo").messages).unwrap();
+
+        let edit = MockBackend::tool_use(
+            "multi_edit",
+            serde_json::json!({"edits": [{"path": "src/lib.rs", "old_string": "foo", "new_string": "bar"}]}),
+        );
+        let ContentBlock::ToolUse { id, .. } = &edit.content[0] else {
+            panic!("expected tool use");
+        };
+        let output = "Changed 1 file(s): src/lib.rs (modified)\n\n--- a/src/lib.rs\n+++ b/src/lib.rs";
+        let results = vec![ContentBlock::ToolResult {
+            tool_use_id: id.clone(),
+            content: output.to_string(),
+            is_error: None,
+        }];
+        transcript.record_iteration(1, &[], &edit, &results).unwrap();
+
+        let point = load(temp.path(), "proj-2").unwrap();
+        assert_eq!(point.tool_outputs, [output]);
+        assert!(point.attended);
+        assert_eq!(crate::progress::extract_modified_files(&point.tool_outputs), ["src/lib.rs"]);
+    }
+}
@@ -0,0 +1,354 @@
+//! Triage - What the loop does with a task that didn't finish
+//!
+//! Every unfinished run is an attempt. The way it stopped (max iterations,
+//! redline, failed verification, an API error that outlasted the model-call
+//! retries, ...) picks the next step from the `triage` config:
+//!
+//! - `retry`: run the task again with a fresh context
+//! - `escalate: <model>`: run it again on a bigger model
+//! - `decompose`: split it into subtasks (beads only)
+//! - `block`: park it for a human
+//!
+//! Attempts go to `.ralph/attempts.jsonl` and, as a one-line note, onto the
+//! task itself, so the next run's prompt and `ralph show` see the history.
+//! After `max_attempts` the task is blocked whatever the policy says; a
+//! human unblocking it starts the count again, and so does the task
+//! completing.
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use crate::backends::retry::{ErrorClass, RetryPolicy};
+use crate::config::TriageConfig;
+
+/// Attempt log, relative to the project root
+pub const ATTEMPTS_FILE: &str = ".ralph/attempts.jsonl";
+
+/// Pause before retrying a task that didn't hit an API error
+const RETRY_PAUSE: Duration = Duration::from_secs(2);
+
+/// How a run ended without finishing its task
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Failure {
+    MaxIterations,
+    Redline,
+    VerificationFailed,
+    /// A model call was still rate limited (429) after its retries
+    RateLimited,
+    /// The provider was still overloaded (529/503) after the retries
+    Overloaded,
+    /// Other server or network errors that outlasted the retries
+    ApiError,
+    /// Anything else that made the run fail
+    Error,
+}
+
+impl Failure {
+    /// Classify an error from a run
+    pub fn of(error: &anyhow::Error) -> Self {
+        match ErrorClass::of(error) {
+            ErrorClass::RateLimited => Failure::RateLimited,
+            ErrorClass::Overloaded => Failure::Overloaded,
+            ErrorClass::Transient => Failure::ApiError,
+            ErrorClass::Fatal => Failure::Error,
+        }
+    }
+
+    fn is_api(self) -> bool {
+        matches!(self, Failure::RateLimited | Failure::Overloaded | Failure::ApiError)
+    }
+}
+
+impl fmt::Display for Failure {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Failure::MaxIterations => "max iterations",
+            Failure::Redline => "redline",
+            Failure::VerificationFailed => "verification failed",
+            Failure::RateLimited => "rate limited",
+            Failure::Overloaded => "provider overloaded",
+            Failure::ApiError => "API error",
+            Failure::Error => "error",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// Next step for an unfinished task
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Action {
+    Retry,
+    /// Retry on this model (`backend.brain` syntax, e.g. `claude-opus-4-1`)
+    Escalate(String),
+    Decompose,
+    Block,
+}
+
+impl fmt::Display for Action {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Action::Retry => write!(f, "retry"),
+            Action::Escalate(model) => write!(f, "escalate to {}", model),
+            Action::Decompose => write!(f, "decompose"),
+            Action::Block => write!(f, "block"),
+        }
+    }
+}
+
+/// One unfinished run
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Attempt {
+    pub timestamp: DateTime<Utc>,
+    pub task_id: String,
+    /// 1-based, counted since the task was last blocked
+    pub attempt: usize,
+    pub failure: Failure,
+    /// Model the run was on
+    pub model: String,
+    pub action: Action,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub detail: String,
+}
+
+impl Attempt {
+    /// One line for the task's notes
+    pub fn note(&self) -> String {
+        let mut note = format!(
+            "[triage {}] attempt {}: {} on {} → {}",
+            self.timestamp.format("%Y-%m-%d %H:%M"),
+            self.attempt,
+            self.failure,
+            self.model,
+            self.action
+        );
+        if !self.detail.is_empty() {
+            note.push_str(&format!(" ({})", self.detail));
+        }
+        note
+    }
+}
+
+/// A line of the attempt log
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(untagged)]
+enum Record {
+    Attempt(Attempt),
+    /// The task with this id completed
+    Completed { timestamp: DateTime<Utc>, completed: String },
+}
+
+/// Picks the next step for unfinished tasks and keeps their attempt history
+pub struct Triage {
+    config: TriageConfig,
+    api_retry: RetryPolicy,
+    path: PathBuf,
+}
+
+impl Triage {
+    pub fn new(project_root: &Path, config: &TriageConfig) -> Self {
+        Self {
+            config: config.clone(),
+            api_retry: RetryPolicy::from_config(&config.api_retry),
+            path: project_root.join(ATTEMPTS_FILE),
+        }
+    }
+
+    /// Record an unfinished run and decide what happens next
+    pub fn decide(&self, task_id: &str, failure: Failure, model: &str, detail: &str) -> Result<Attempt> {
+        let history = self.history(task_id)?;
+        let attempt = history.len() + 1;
+        let same = history.iter().filter(|a| a.failure == failure).count();
+
+        let policy = match failure {
+            Failure::MaxIterations => &self.config.max_iterations,
+            Failure::Redline => &self.config.redline,
+            Failure::VerificationFailed => &self.config.verification_failed,
+            Failure::RateLimited => &self.config.rate_limited,
+            Failure::Overloaded => &self.config.overloaded,
+            Failure::ApiError => &self.config.api_error,
+            Failure::Error => &self.config.error,
+        };
+        let action = if attempt >= self.config.max_attempts {
+            Action::Block
+        } else {
+            policy.get(same).or(policy.last()).cloned().unwrap_or(Action::Retry)
+        };
+
+        let attempt = Attempt {
+            timestamp: Utc::now(),
+            task_id: task_id.to_string(),
+            attempt,
+            failure,
+            model: model.to_string(),
+            action,
+            detail: detail.lines().next().unwrap_or_default().chars().take(200).collect(),
+        };
+        self.append(&attempt)?;
+        Ok(attempt)
+    }
+
+    /// Record that a planned step couldn't be taken and the task was blocked instead
+    pub fn blocked_instead(&self, attempt: &Attempt, why: &str) -> Result<Attempt> {
+        let blocked = Attempt {
+            timestamp: Utc::now(),
+            action: Action::Block,
+            detail: format!("{} failed: {}", attempt.action, why),
+            ..attempt.clone()
+        };
+        self.append(&blocked)?;
+        Ok(blocked)
+    }
+
+    /// How long to wait before the retry an attempt asked for
+    pub fn pause(&self, attempt: &Attempt) -> Duration {
+        if attempt.failure.is_api() {
+            self.api_retry.delay_for_attempt(attempt.attempt as u32).max(RETRY_PAUSE)
+        } else {
+            RETRY_PAUSE
+        }
+    }
+
+    /// Record that a task completed, so a later run of it starts counting again
+    pub fn completed(&self, task_id: &str) -> Result<()> {
+        if self.history(task_id)?.is_empty() {
+            return Ok(());
+        }
+        self.append(&Record::Completed {
+            timestamp: Utc::now(),
+            completed: task_id.to_string(),
+        })
+    }
+
+    /// A task's attempts since it was last blocked or completed
+    pub fn history(&self, task_id: &str) -> Result<Vec<Attempt>> {
+        let mut history: Vec<Attempt> = Vec::new();
+        for record in self.records()? {
+            match record {
+                Record::Attempt(attempt) if attempt.task_id == task_id => {
+                    if history.last().is_some_and(|last| last.action == Action::Block) {
+                        history.clear();
+                    }
+                    history.push(attempt);
+                }
+                Record::Completed { completed, .. } if completed == task_id => history.clear(),
+                _ => {}
+            }
+        }
+        if history.last().is_some_and(|last| last.action == Action::Block) {
+            history.clear();
+        }
+        Ok(history)
+    }
+
+    /// Every recorded attempt, oldest first
+    pub fn attempts(&self) -> Result<Vec<Attempt>> {
+        Ok(self
+            .records()?
+            .into_iter()
+            .filter_map(|record| match record {
+                Record::Attempt(attempt) => Some(attempt),
+                Record::Completed { .. } => None,
+            })
+            .collect())
+    }
+
+    fn records(&self) -> Result<Vec<Record>> {
+        if !self.path.exists() {
+            return Ok(Vec::new());
+        }
+        let content = std::fs::read_to_string(&self.path)
+            .with_context(|| format!("Failed to read {}", self.path.display()))?;
+        Ok(content
+            .lines()
+            .filter(|line| !line.trim().is_empty())
+            .filter_map(|line| match serde_json::from_str(line) {
+                Ok(record) => Some(record),
+                Err(e) => {
+                    tracing::warn!("Skipping bad attempt entry: {}", e);
+                    None
+                }
+            })
+            .collect())
+    }
+
+    fn append(&self, record: &impl Serialize) -> Result<()> {
+        if let Some(dir) = self.path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        let mut file = std::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)
+            .with_context(|| format!("Failed to open {}", self.path.display()))?;
+        writeln!(file, "{}", serde_json::to_string(record)?)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_policy_per_failure_and_attempt_limit() {
+        let temp = TempDir::new().unwrap();
+        let config = TriageConfig {
+            max_attempts: 5,
+            max_iterations: vec![Action::Retry, Action::Escalate("opus".to_string())],
+            redline: vec![Action::Retry, Action::Decompose],
+            ..Default::default()
+        };
+        let triage = Triage::new(temp.path(), &config);
+
+        let steps: Vec<Action> = [Failure::MaxIterations, Failure::Redline, Failure::MaxIterations, Failure::Redline]
+            .into_iter()
+            .map(|failure| triage.decide("t-1", failure, "sonnet", "").unwrap().action)
+            .collect();
+        assert_eq!(
+            steps,
+            [Action::Retry, Action::Retry, Action::Escalate("opus".to_string()), Action::Decompose]
+        );
+
+        // Fifth attempt: blocked, whatever the policy says
+        let last = triage.decide("t-1", Failure::RateLimited, "opus", "429 Too Many Requests\nbody").unwrap();
+        assert_eq!((last.attempt, &last.action), (5, &Action::Block));
+        assert!(last.note().ends_with("attempt 5: rate limited on opus → block (429 Too Many Requests)"));
+        assert!(triage.pause(&last) >= RETRY_PAUSE);
+
+        // Unblocking starts the count again; other tasks have their own
+        assert!(triage.history("t-1").unwrap().is_empty());
+        assert_eq!(triage.decide("t-1", Failure::Error, "opus", "").unwrap().attempt, 1);
+        assert_eq!(triage.decide("t-2", Failure::Redline, "sonnet", "").unwrap().attempt, 1);
+        assert_eq!(triage.attempts().unwrap().len(), 7);
+    }
+
+    #[test]
+    fn test_completing_clears_history() {
+        let temp = TempDir::new().unwrap();
+        let triage = Triage::new(temp.path(), &TriageConfig::default());
+        triage.decide("t-1", Failure::Redline, "sonnet", "").unwrap();
+        triage.decide("t-1", Failure::MaxIterations, "sonnet", "").unwrap();
+        triage.decide("t-2", Failure::Redline, "sonnet", "").unwrap();
+
+        triage.completed("t-1").unwrap();
+        assert!(triage.history("t-1").unwrap().is_empty());
+        assert_eq!(triage.history("t-2").unwrap().len(), 1);
+
+        // Reopened later, it starts from the first attempt; the log keeps everything
+        assert_eq!(triage.decide("t-1", Failure::Redline, "sonnet", "").unwrap().attempt, 1);
+        assert_eq!(triage.attempts().unwrap().len(), 4);
+
+        // Nothing to clear: nothing is written
+        triage.completed("t-3").unwrap();
+        let lines = std::fs::read_to_string(temp.path().join(ATTEMPTS_FILE)).unwrap();
+        assert_eq!(lines.lines().count(), 5);
+    }
+}
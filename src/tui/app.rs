@@ -1,7 +1,16 @@
 //! App state machine for the TUI
 
-use std::collections::VecDeque;
+use std::collections::{HashMap, VecDeque};
+use std::path::PathBuf;
 use chrono::{DateTime, Utc};
+use tokio::sync::oneshot;
+
+use crate::approval::{ApprovalRequest, Decision, PendingApproval};
+use crate::claude_client::StopReason;
+use crate::control::{Interrupt, LoopControl};
+use crate::events::{Level, LoopEvent};
+use crate::git;
+use crate::task_parser::ParsedTask;
 
 /// Maximum number of output lines to keep in history
 const MAX_OUTPUT_LINES: usize = 10000;
@@ -13,6 +22,10 @@ pub enum View {
     Split,
     Dashboard,
     Help,
+    /// Metadata and acceptance criteria of the selected task
+    Detail,
+    /// Files the selected task changed so far
+    Diff,
 }
 
 /// Status of a task/spec
@@ -27,11 +40,13 @@ pub enum TaskStatus {
 /// A task/spec entry
 #[derive(Debug, Clone)]
 pub struct Task {
-    pub id: u32,
+    pub id: String,
     pub name: String,
     pub status: TaskStatus,
     pub criteria_done: usize,
     pub criteria_total: usize,
+    /// The beads issue, for the detail view
+    pub detail: ParsedTask,
 }
 
 /// A single line of output with metadata
@@ -54,16 +69,21 @@ pub enum OutputLevel {
     Text,
 }
 
-/// Loop event for structured updates
-#[derive(Debug, Clone)]
-pub enum LoopEvent {
-    IterationStart(usize),
-    ToolCall { name: String, input: String },
-    ToolResult { name: String, output: String, success: bool },
-    Text(String),
-    TokenUpdate { input: u32, output: u32 },
-    SpecComplete(u32),
-    Redline,
+/// What is being typed into the approval prompt
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PromptInput {
+    /// Why the call is denied (sent to the model)
+    Reason,
+    /// A replacement bash command
+    Command,
+}
+
+/// A tool call waiting for approve/deny/edit (`ralph tui --attended`)
+pub struct ApprovalPrompt {
+    pub request: ApprovalRequest,
+    /// Text being typed, if the user chose deny or edit
+    pub input: Option<(PromptInput, String)>,
+    reply: oneshot::Sender<Decision>,
 }
 
 /// Main app state
@@ -81,7 +101,11 @@ pub struct App {
     // Execution state
     pub is_running: bool,
     pub is_paused: bool,
-    pub current_spec_id: Option<u32>,
+    pub current_task: Option<String>,
+    /// Handle on the running loop (pause, steer, skip, abort)
+    pub control: Option<LoopControl>,
+    /// Steering message being typed while paused
+    pub steer_input: Option<String>,
     
     // Metrics
     pub iterations: usize,
@@ -96,6 +120,8 @@ pub struct App {
     
     // Output buffer
     pub output_lines: VecDeque<OutputLine>,
+    /// Streamed text not yet ended by a newline
+    partial_text: String,
     
     // Progress
     pub specs_completed: usize,
@@ -109,6 +135,17 @@ pub struct App {
     // Should quit
     pub should_quit: bool,
     pub quit_requested: bool,
+
+    // Attended mode
+    pub approval: Option<ApprovalPrompt>,
+
+    // Diff view
+    pub project_root: PathBuf,
+    /// Commit each task started from, and the one it ended at once finished
+    task_commits: HashMap<String, (String, Option<String>)>,
+    /// Title and diff shown in the diff view
+    pub diff: Option<(String, String)>,
+    pub diff_scroll: usize,
 }
 
 /// Which pane has focus
@@ -121,7 +158,7 @@ pub enum FocusPane {
 
 impl App {
     /// Create a new app with default state
-    pub fn new(redline_threshold: u32) -> Self {
+    pub fn new(redline_threshold: u32, project_root: PathBuf) -> Self {
         Self {
             current_view: View::default(),
             selected_task: 0,
@@ -131,7 +168,9 @@ impl App {
             tasks: Vec::new(),
             is_running: false,
             is_paused: false,
-            current_spec_id: None,
+            current_task: None,
+            control: None,
+            steer_input: None,
             iterations: 0,
             input_tokens: 0,
             output_tokens: 0,
@@ -140,6 +179,7 @@ impl App {
             commits: 0,
             session_start: Utc::now(),
             output_lines: VecDeque::with_capacity(MAX_OUTPUT_LINES),
+            partial_text: String::new(),
             specs_completed: 0,
             specs_total: 0,
             criteria_completed: 0,
@@ -147,6 +187,11 @@ impl App {
             redline_threshold,
             should_quit: false,
             quit_requested: false,
+            approval: None,
+            project_root,
+            task_commits: HashMap::new(),
+            diff: None,
+            diff_scroll: 0,
         }
     }
 
@@ -213,55 +258,128 @@ impl App {
 
     /// Handle a loop event
     pub fn handle_loop_event(&mut self, event: LoopEvent) {
+        if !matches!(event, LoopEvent::TextDelta { .. }) {
+            self.flush_text();
+        }
         match event {
-            LoopEvent::IterationStart(n) => {
-                self.iterations = n;
-                self.add_output(OutputLevel::Info, format!("--- Iteration {} ---", n));
+            LoopEvent::IterationStart { iteration, .. } => {
+                self.iterations = iteration;
+                self.add_output(OutputLevel::Info, format!("--- Iteration {} ---", iteration));
+            }
+            LoopEvent::TextDelta { text } => {
+                self.partial_text.push_str(&text);
+                while let Some(pos) = self.partial_text.find('\n') {
+                    let line: String = self.partial_text.drain(..=pos).collect();
+                    let line = line.trim_end();
+                    if !line.is_empty() {
+                        self.add_output(OutputLevel::Text, line.to_string());
+                    }
+                }
             }
-            LoopEvent::ToolCall { name, input } => {
-                self.add_output(OutputLevel::Tool, format!("[{}] {}", name, 
-                    if input.len() > 100 { format!("{}...", &input[..100]) } else { input }));
+            LoopEvent::ToolCall { name, input, .. } => {
+                let input = input.to_string();
+                self.add_output(OutputLevel::Tool, format!("[{}] {}", name, preview(&input, 100)));
             }
-            LoopEvent::ToolResult { name, output, success } => {
+            LoopEvent::ToolResult {
+                name,
+                output,
+                success,
+                duration_ms,
+                ..
+            } => {
                 let level = if success { OutputLevel::ToolResult } else { OutputLevel::Error };
-                let preview = if output.len() > 200 { 
-                    format!("{}...", &output[..200]) 
-                } else { 
-                    output 
-                };
-                self.add_output(level, format!("[{}] → {}", name, preview));
+                let first = output.lines().next().unwrap_or_default();
+                self.add_output(level, format!("[{}] → {} ({} ms)", name, preview(first, 200), duration_ms));
             }
-            LoopEvent::Text(text) => {
-                for line in text.lines() {
-                    self.add_output(OutputLevel::Text, line.to_string());
-                }
+            LoopEvent::TokenUsage {
+                total_input_tokens,
+                total_output_tokens,
+                cost_usd,
+                ..
+            } => {
+                self.input_tokens = total_input_tokens;
+                self.output_tokens = total_output_tokens;
+                self.total_cost += cost_usd;
+            }
+            LoopEvent::Intervention { .. } => {
+                self.add_output(OutputLevel::Info, "[INTERVENTION] Exploration spiral, nudging toward edits".to_string());
+            }
+            LoopEvent::Steering { message } => {
+                self.add_output(OutputLevel::Info, format!("[STEERING] {}", message));
             }
-            LoopEvent::TokenUpdate { input, output } => {
-                self.input_tokens = input;
-                self.output_tokens = output;
-                self.update_cost();
+            LoopEvent::Compacted {
+                context_tokens,
+                messages_before,
+                messages_after,
+            } => {
+                self.add_output(
+                    OutputLevel::Info,
+                    format!(
+                        "Compacted context at {} tokens: {} -> {} messages",
+                        context_tokens, messages_before, messages_after
+                    ),
+                );
             }
-            LoopEvent::SpecComplete(id) => {
-                self.add_output(OutputLevel::Success, format!("✓ Spec {} complete!", id));
-                self.specs_completed += 1;
-                // Update task status
-                if let Some(task) = self.tasks.iter_mut().find(|t| t.id == id) {
-                    task.status = TaskStatus::Completed;
+            LoopEvent::Verifying { commands } => {
+                self.add_output(OutputLevel::Info, format!("Verifying: {}", commands.join(" && ")));
+            }
+            LoopEvent::Verified { passed, summary } => {
+                let level = if passed { OutputLevel::Success } else { OutputLevel::Error };
+                self.add_output(level, format!("Verification: {}", summary));
+            }
+            LoopEvent::Stopped { reason, detail, .. } => match reason {
+                StopReason::Redline => {
+                    self.add_output(OutputLevel::Error, "⚠ REDLINE: Token limit reached".to_string());
+                    self.reboots += 1;
+                }
+                StopReason::BudgetExceeded => {
+                    let detail = detail.unwrap_or_default();
+                    self.add_output(OutputLevel::Error, format!("⚠ BUDGET: {}", detail));
+                }
+                StopReason::Completed
+                | StopReason::MaxIterations
+                | StopReason::Skipped
+                | StopReason::Aborted => {}
+            },
+            LoopEvent::TaskStarted { task_id, title, base } => {
+                self.add_output(OutputLevel::Info, format!("→ Starting Task: {}", task_id));
+                self.add_output(OutputLevel::Info, format!("  {}", title));
+                if let Some(base) = base {
+                    self.task_commits.insert(task_id.clone(), (base, None));
                 }
+                self.set_current_task(&task_id);
             }
-            LoopEvent::Redline => {
-                self.add_output(OutputLevel::Error, "⚠ REDLINE: Token limit reached".to_string());
-                self.reboots += 1;
+            LoopEvent::TaskFinished { task_id, completed, head } => {
+                if let Some(commits) = self.task_commits.get_mut(&task_id) {
+                    commits.1 = head;
+                }
+                if let Some(task) = self.tasks.iter_mut().find(|t| t.id == task_id) {
+                    task.status = if completed { TaskStatus::Completed } else { TaskStatus::Failed };
+                }
+                if completed {
+                    self.specs_completed += 1;
+                }
+                self.current_task = None;
+            }
+            LoopEvent::Status { level, text } => {
+                let level = match level {
+                    Level::Info => OutputLevel::Info,
+                    Level::Success => OutputLevel::Success,
+                    Level::Warning | Level::Error => OutputLevel::Error,
+                };
+                for line in text.lines().filter(|l| !l.trim().is_empty()) {
+                    self.add_output(level, line.to_string());
+                }
             }
         }
     }
 
-    /// Update cost estimate based on current tokens
-    fn update_cost(&mut self) {
-        // Claude Sonnet pricing: $3/1M input, $15/1M output
-        let input_cost = (self.input_tokens as f64 / 1_000_000.0) * 3.0;
-        let output_cost = (self.output_tokens as f64 / 1_000_000.0) * 15.0;
-        self.total_cost = input_cost + output_cost;
+    /// Show streamed text that never got its newline
+    fn flush_text(&mut self) {
+        let text = std::mem::take(&mut self.partial_text);
+        if !text.trim().is_empty() {
+            self.add_output(OutputLevel::Text, text.trim_end().to_string());
+        }
     }
 
     /// Toggle between views
@@ -269,7 +387,7 @@ impl App {
         self.current_view = match self.current_view {
             View::Split => View::Dashboard,
             View::Dashboard => View::Split,
-            View::Help => View::Split,
+            View::Help | View::Detail | View::Diff => View::Split,
         };
     }
 
@@ -278,13 +396,76 @@ impl App {
         self.current_view = View::Help;
     }
 
-    /// Toggle pause
+    /// Toggle pause; the loop stops before its next model call
     pub fn toggle_pause(&mut self) {
-        self.is_paused = !self.is_paused;
-        let status = if self.is_paused { "PAUSED" } else { "RESUMED" };
+        self.set_paused(!self.is_paused);
+        let status = if self.is_paused { "PAUSED (after this iteration)" } else { "RESUMED" };
         self.add_output(OutputLevel::Info, format!("⏸ {}", status));
     }
 
+    fn set_paused(&mut self, paused: bool) {
+        self.is_paused = paused;
+        if !paused {
+            self.steer_input = None;
+        }
+        if let Some(control) = &self.control {
+            control.set_paused(paused);
+        }
+    }
+
+    /// Queue a steering message for the model's next turn
+    pub fn steer(&mut self, message: String) {
+        let message = message.trim().to_string();
+        let Some(control) = &self.control else {
+            return;
+        };
+        if message.is_empty() {
+            return;
+        }
+        control.steer(message.clone());
+        self.add_output(OutputLevel::Info, format!("Queued steering: {}", message));
+    }
+
+    /// Skip or abort the current task; this also resumes a paused loop
+    pub fn interrupt(&mut self, interrupt: Interrupt) {
+        let Some(control) = &self.control else {
+            return;
+        };
+        control.interrupt(interrupt);
+        self.is_paused = false;
+        self.steer_input = None;
+        let action = match interrupt {
+            Interrupt::Skip => "Skipping the current task",
+            Interrupt::Abort => "Aborting the loop",
+        };
+        self.add_output(OutputLevel::Info, format!("■ {}", action));
+    }
+
+    /// Show the selected task's metadata and acceptance criteria
+    pub fn open_detail(&mut self) {
+        if self.tasks.get(self.selected_task).is_some() {
+            self.current_view = View::Detail;
+        }
+    }
+
+    /// Show what the selected task changed: from the commit it started at to
+    /// the one it finished at, or to the working tree while it runs
+    pub fn open_diff(&mut self) {
+        let Some(task) = self.tasks.get(self.selected_task) else {
+            return;
+        };
+        let title = format!("{}: {}", task.id, task.name);
+        let diff = match self.task_commits.get(&task.id) {
+            Some((base, head)) => git::diff_since(&self.project_root, base, head.as_deref())
+                .unwrap_or_else(|e| format!("Could not diff: {}", e)),
+            None => "This task has not run in this session.".to_string(),
+        };
+        let diff = if diff.trim().is_empty() { "No changes.".to_string() } else { diff };
+        self.diff = Some((title, diff));
+        self.diff_scroll = 0;
+        self.current_view = View::Diff;
+    }
+
     /// Switch focus between panes
     pub fn toggle_focus(&mut self) {
         self.focus_pane = match self.focus_pane {
@@ -341,6 +522,31 @@ impl App {
         }
     }
 
+    /// Show a tool call for approval
+    pub fn request_approval(&mut self, (request, reply): PendingApproval) {
+        self.add_output(OutputLevel::Info, format!("⏸ Waiting for approval: {}", request.tool));
+        self.approval = Some(ApprovalPrompt {
+            request,
+            input: None,
+            reply,
+        });
+    }
+
+    /// Answer the pending approval
+    pub fn answer_approval(&mut self, decision: Decision) {
+        let Some(prompt) = self.approval.take() else {
+            return;
+        };
+        let summary = match &decision {
+            Decision::Approve => "approved".to_string(),
+            Decision::Deny(reason) => format!("denied: {}", reason),
+            Decision::Edit(_) => "edited".to_string(),
+        };
+        self.add_output(OutputLevel::Info, format!("[{}] {}", prompt.request.tool, summary));
+        // The loop only goes away if the session is ending
+        let _ = prompt.reply.send(decision);
+    }
+
     /// Set tasks from parsed specs
     pub fn set_tasks(&mut self, tasks: Vec<Task>) {
         self.specs_total = tasks.len();
@@ -350,21 +556,29 @@ impl App {
         self.tasks = tasks;
     }
 
-    /// Mark a spec as in progress
-    pub fn set_current_spec(&mut self, id: u32) {
-        self.current_spec_id = Some(id);
+    /// Mark a task as in progress
+    pub fn set_current_task(&mut self, id: &str) {
+        self.current_task = Some(id.to_string());
         if let Some(task) = self.tasks.iter_mut().find(|t| t.id == id) {
             task.status = TaskStatus::InProgress;
         }
-        // Select the current spec in the list
+        // Select the current task in the list
         if let Some(idx) = self.tasks.iter().position(|t| t.id == id) {
             self.selected_task = idx;
         }
     }
 }
 
+/// The first `max_chars` characters, with an ellipsis if cut
+fn preview(text: &str, max_chars: usize) -> String {
+    match text.char_indices().nth(max_chars) {
+        Some((end, _)) => format!("{}...", &text[..end]),
+        None => text.to_string(),
+    }
+}
+
 impl Default for App {
     fn default() -> Self {
-        Self::new(150_000)
+        Self::new(150_000, PathBuf::from("."))
     }
 }
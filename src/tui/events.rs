@@ -4,7 +4,9 @@ use std::time::Duration;
 use crossterm::event::{self, Event, KeyCode, KeyEvent, KeyModifiers};
 use anyhow::Result;
 
-use super::app::App;
+use super::app::{App, FocusPane, OutputLevel, PromptInput};
+use crate::approval::Decision;
+use crate::control::Interrupt;
 
 /// Event handler for keyboard input
 pub struct EventHandler {
@@ -31,6 +33,17 @@ impl EventHandler {
 
     /// Handle a key event, returns true if handled
     pub fn handle_key(&self, app: &mut App, key: KeyEvent) -> bool {
+        // A pending approval takes every key but Ctrl+C
+        let ctrl_c = key.code == KeyCode::Char('c') && key.modifiers.contains(KeyModifiers::CONTROL);
+        if app.approval.is_some() && !ctrl_c {
+            self.handle_approval_key(app, key);
+            return true;
+        }
+        if app.steer_input.is_some() && !ctrl_c {
+            self.handle_steer_key(app, key);
+            return true;
+        }
+
         // Global keybindings (work in any view)
         match key.code {
             KeyCode::Char('q') => {
@@ -63,12 +76,67 @@ impl EventHandler {
                 }
                 true
             }
+            super::app::View::Detail => {
+                match key.code {
+                    KeyCode::Char('v') => {
+                        app.open_diff();
+                    }
+                    KeyCode::Esc | KeyCode::Enter => {
+                        app.current_view = super::app::View::Split;
+                    }
+                    KeyCode::Char('?') => {
+                        app.show_help();
+                    }
+                    _ => return false,
+                }
+                true
+            }
+            super::app::View::Diff => {
+                let lines = app.diff.as_ref().map_or(0, |(_, diff)| diff.lines().count());
+                match key.code {
+                    KeyCode::Up | KeyCode::Char('k') => {
+                        app.diff_scroll = app.diff_scroll.saturating_sub(1);
+                    }
+                    KeyCode::Down | KeyCode::Char('j') => {
+                        app.diff_scroll = (app.diff_scroll + 1).min(lines.saturating_sub(1));
+                    }
+                    KeyCode::PageUp => {
+                        app.diff_scroll = app.diff_scroll.saturating_sub(20);
+                    }
+                    KeyCode::PageDown => {
+                        app.diff_scroll = (app.diff_scroll + 20).min(lines.saturating_sub(1));
+                    }
+                    // Re-read the working tree of a running task
+                    KeyCode::Char('r') => {
+                        app.open_diff();
+                    }
+                    KeyCode::Esc => {
+                        app.current_view = super::app::View::Detail;
+                    }
+                    _ => return false,
+                }
+                true
+            }
             super::app::View::Split => {
                 match key.code {
                     // Pause/Resume
                     KeyCode::Char('p') => {
                         app.toggle_pause();
                     }
+                    // While paused: steer, skip or abort the current task
+                    KeyCode::Char('s') if app.is_paused => {
+                        app.steer_input = Some(String::new());
+                    }
+                    KeyCode::Char('n') if app.is_paused => {
+                        app.interrupt(Interrupt::Skip);
+                    }
+                    KeyCode::Char('x') if app.is_paused => {
+                        app.interrupt(Interrupt::Abort);
+                    }
+                    // Drill into the selected task
+                    KeyCode::Enter if app.focus_pane == FocusPane::Tasks => {
+                        app.open_detail();
+                    }
                     // Toggle dashboard
                     KeyCode::Char('d') => {
                         app.toggle_view();
@@ -117,6 +185,77 @@ impl EventHandler {
     }
 }
 
+impl EventHandler {
+    /// Keys while a tool call waits for approval: a/d/e, then typing for deny and edit
+    fn handle_approval_key(&self, app: &mut App, key: KeyEvent) {
+        let Some(prompt) = app.approval.as_mut() else {
+            return;
+        };
+
+        if let Some((kind, text)) = prompt.input.as_mut() {
+            match key.code {
+                KeyCode::Char(c) => text.push(c),
+                KeyCode::Backspace => {
+                    text.pop();
+                }
+                KeyCode::Esc => prompt.input = None,
+                KeyCode::Enter => {
+                    let decision = match kind {
+                        PromptInput::Reason => Decision::Deny(text.clone()),
+                        PromptInput::Command => {
+                            let mut input = prompt.request.input.clone();
+                            input["command"] = text.trim().into();
+                            Decision::Edit(input)
+                        }
+                    };
+                    app.answer_approval(decision);
+                }
+                _ => {}
+            }
+            return;
+        }
+
+        match key.code {
+            KeyCode::Char('a') | KeyCode::Char('y') => app.answer_approval(Decision::Approve),
+            KeyCode::Char('d') | KeyCode::Char('n') => {
+                prompt.input = Some((PromptInput::Reason, String::new()));
+            }
+            KeyCode::Char('e') => match prompt.request.command() {
+                Some(command) => {
+                    prompt.input = Some((PromptInput::Command, command.to_string()));
+                }
+                None => app.add_output(
+                    OutputLevel::Info,
+                    "Only bash commands can be edited in the TUI; approve or deny the edit".to_string(),
+                ),
+            },
+            _ => {}
+        }
+    }
+}
+
+impl EventHandler {
+    /// Keys while a steering message is typed: Enter queues it, Esc drops it
+    fn handle_steer_key(&self, app: &mut App, key: KeyEvent) {
+        let Some(text) = app.steer_input.as_mut() else {
+            return;
+        };
+        match key.code {
+            KeyCode::Char(c) => text.push(c),
+            KeyCode::Backspace => {
+                text.pop();
+            }
+            KeyCode::Esc => app.steer_input = None,
+            KeyCode::Enter => {
+                if let Some(message) = app.steer_input.take() {
+                    app.steer(message);
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
 impl Default for EventHandler {
     fn default() -> Self {
         Self::new(100)
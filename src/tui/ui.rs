@@ -7,7 +7,7 @@ use ratatui::{
     text::{Line, Span},
 };
 
-use super::app::{App, View};
+use super::app::{App, PromptInput, Task, View};
 use super::widgets::{
     TaskListWidget, OutputPanelWidget, ProgressBarWidget, 
     TokenGaugeWidget, StatusBarWidget
@@ -23,7 +23,209 @@ impl Ui {
             View::Split => Self::render_split_view(frame, app),
             View::Dashboard => Self::render_dashboard_view(frame, app),
             View::Help => Self::render_help_view(frame, app),
+            View::Detail => Self::render_detail_view(frame, app),
+            View::Diff => Self::render_diff_view(frame, app),
         }
+
+        if app.approval.is_some() {
+            Self::render_approval(frame, app);
+        } else if app.steer_input.is_some() {
+            Self::render_steer_input(frame, app);
+        }
+    }
+
+    /// Render the steering prompt over the bottom of the screen
+    fn render_steer_input(frame: &mut Frame, app: &App) {
+        let Some(text) = &app.steer_input else {
+            return;
+        };
+        let screen = frame.area();
+        let height = 3.min(screen.height);
+        let area = Rect::new(screen.x, screen.y + screen.height - height, screen.width, height);
+        frame.render_widget(Clear, area);
+
+        let block = Block::default()
+            .title(" Steer the model (sent as a user turn when resumed) ")
+            .borders(Borders::ALL)
+            .border_style(Style::default().fg(Color::Yellow));
+        let line = Line::from(vec![
+            Span::raw(format!("{}▏", text)),
+            Span::styled("  Enter queue · Esc cancel", Style::default().fg(Color::DarkGray)),
+        ]);
+        frame.render_widget(Paragraph::new(line).block(block), area);
+    }
+
+    /// Render the selected task's beads metadata and acceptance criteria
+    fn render_detail_view(frame: &mut Frame, app: &App) {
+        let Some(task) = app.tasks.get(app.selected_task) else {
+            return;
+        };
+        let block = Block::default()
+            .title(format!(" {} ", task.id))
+            .borders(Borders::ALL)
+            .border_style(Style::default().fg(Color::Cyan));
+
+        let bold = Style::default().add_modifier(Modifier::BOLD);
+        let mut lines = vec![
+            Line::from(Span::styled(task.name.clone(), bold)),
+            Line::from(""),
+        ];
+        lines.extend(Self::metadata_lines(task));
+        lines.push(Line::from(""));
+
+        let description = &task.detail.task.description;
+        if !description.trim().is_empty() {
+            lines.push(Line::from(Span::styled("Description", bold)));
+            lines.extend(description.lines().map(|line| Line::from(line.to_string())));
+            lines.push(Line::from(""));
+        }
+
+        lines.push(Line::from(Span::styled(
+            format!("Acceptance Criteria ({}/{})", task.criteria_done, task.criteria_total),
+            bold,
+        )));
+        if task.detail.acceptance_criteria.is_empty() {
+            lines.push(Line::from(Span::styled("  none", Style::default().fg(Color::DarkGray))));
+        }
+        for criterion in &task.detail.acceptance_criteria {
+            let (mark, color) = if criterion.completed { ("[x]", Color::Green) } else { ("[ ]", Color::White) };
+            lines.push(Line::from(vec![
+                Span::styled(format!("  {} ", mark), Style::default().fg(color)),
+                Span::raw(criterion.text.clone()),
+            ]));
+        }
+        lines.push(Line::from(""));
+        lines.push(Line::from(Span::styled(
+            "v diff · Esc back",
+            Style::default().fg(Color::DarkGray),
+        )));
+
+        frame.render_widget(
+            Paragraph::new(lines).block(block).wrap(Wrap { trim: false }),
+            frame.area(),
+        );
+    }
+
+    /// One `key: value` line per beads field that is set
+    fn metadata_lines(task: &Task) -> Vec<Line<'static>> {
+        let issue = &task.detail.task;
+        let fields = [
+            ("Status", issue.status.clone()),
+            ("Priority", format!("P{}", issue.priority)),
+            ("Type", issue.issue_type.clone()),
+            ("Owner", issue.owner.clone().unwrap_or_default()),
+            ("Labels", issue.labels.join(", ")),
+            ("Depends on", issue.depends_on.join(", ")),
+            ("Blocks", issue.blocks.join(", ")),
+        ];
+        fields
+            .into_iter()
+            .filter(|(_, value)| !value.is_empty())
+            .map(|(key, value)| {
+                Line::from(vec![
+                    Span::styled(format!("{:<11}", key), Style::default().fg(Color::Cyan)),
+                    Span::raw(value),
+                ])
+            })
+            .collect()
+    }
+
+    /// Render the selected task's diff
+    fn render_diff_view(frame: &mut Frame, app: &App) {
+        let Some((title, diff)) = &app.diff else {
+            return;
+        };
+        let block = Block::default()
+            .title(format!(" Diff: {} ", title))
+            .title_bottom(" ↑/↓ scroll · r refresh · Esc back ")
+            .borders(Borders::ALL)
+            .border_style(Style::default().fg(Color::Cyan));
+
+        let lines: Vec<Line> = diff
+            .lines()
+            .skip(app.diff_scroll)
+            .map(|line| {
+                let color = if line.starts_with("diff --git") {
+                    Color::Yellow
+                } else if line.starts_with("+++") || line.starts_with("---") || line.starts_with("@@") {
+                    Color::Cyan
+                } else if line.starts_with('+') {
+                    Color::Green
+                } else if line.starts_with('-') {
+                    Color::Red
+                } else {
+                    Color::White
+                };
+                Line::from(Span::styled(line.to_string(), Style::default().fg(color)))
+            })
+            .collect();
+        frame.render_widget(Paragraph::new(lines).block(block), frame.area());
+    }
+
+    /// Render the approval popup for a pending tool call
+    fn render_approval(frame: &mut Frame, app: &App) {
+        let Some(prompt) = &app.approval else {
+            return;
+        };
+        let area = centered_rect(80, 60, frame.area());
+        frame.render_widget(Clear, area);
+
+        let block = Block::default()
+            .title(format!(" Approval needed: {} ", prompt.request.tool))
+            .borders(Borders::ALL)
+            .border_style(Style::default().fg(Color::Yellow));
+        let inner = block.inner(area);
+        frame.render_widget(block, area);
+
+        let chunks = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Min(1), Constraint::Length(2)])
+            .split(inner);
+
+        let preview: Vec<Line> = prompt
+            .request
+            .preview
+            .lines()
+            .map(|line| {
+                let color = if line.starts_with("+++") || line.starts_with("---") {
+                    Color::Cyan
+                } else if line.starts_with('+') {
+                    Color::Green
+                } else if line.starts_with('-') {
+                    Color::Red
+                } else {
+                    Color::White
+                };
+                Line::from(Span::styled(line.to_string(), Style::default().fg(color)))
+            })
+            .collect();
+        frame.render_widget(Paragraph::new(preview).wrap(Wrap { trim: false }), chunks[0]);
+
+        let footer = match &prompt.input {
+            Some((kind, text)) => {
+                let label = match kind {
+                    PromptInput::Reason => "Reason (sent to the model): ",
+                    PromptInput::Command => "Command: ",
+                };
+                Line::from(vec![
+                    Span::styled(label, Style::default().fg(Color::Yellow)),
+                    Span::raw(format!("{}▏", text)),
+                    Span::styled("  Enter send · Esc back", Style::default().fg(Color::DarkGray)),
+                ])
+            }
+            None => Line::from(vec![
+                Span::styled("[a]", Style::default().fg(Color::Green)),
+                Span::raw("pprove  "),
+                Span::styled("[d]", Style::default().fg(Color::Red)),
+                Span::raw("eny  "),
+                Span::styled("[e]", Style::default().fg(Color::Cyan)),
+                Span::raw("dit"),
+            ]),
+        };
+        frame.render_widget(
+            Paragraph::new(vec![Line::from(""), footer]),
+            chunks[1],
+        );
     }
 
     /// Render the default split view
@@ -221,6 +423,18 @@ impl Ui {
                 Span::styled("  ?      ", Style::default().fg(Color::Cyan)),
                 Span::raw("Show this help"),
             ]),
+            Line::from(vec![
+                Span::styled("  a/d/e  ", Style::default().fg(Color::Cyan)),
+                Span::raw("Approve/deny/edit a tool call (--attended)"),
+            ]),
+            Line::from(vec![
+                Span::styled("  s/n/x  ", Style::default().fg(Color::Cyan)),
+                Span::raw("While paused: steer, skip task, abort loop"),
+            ]),
+            Line::from(vec![
+                Span::styled("  Enter  ", Style::default().fg(Color::Cyan)),
+                Span::raw("Task details (v for its diff)"),
+            ]),
             Line::from(""),
             Line::from(Span::styled("Navigation", Style::default().add_modifier(Modifier::BOLD))),
             Line::from(""),
@@ -15,12 +15,22 @@ pub struct StatusBarWidget;
 impl StatusBarWidget {
     /// Render the status bar
     pub fn render(frame: &mut Frame, area: Rect, app: &App) {
-        let shortcuts = vec![
-            ("p", if app.is_paused { "resume" } else { "pause" }),
-            ("d", "dashboard"),
-            ("q", "quit"),
-            ("?", "help"),
-        ];
+        let shortcuts = if app.is_paused {
+            vec![
+                ("p", "resume"),
+                ("s", "steer"),
+                ("n", "skip"),
+                ("x", "abort"),
+                ("q", "quit"),
+            ]
+        } else {
+            vec![
+                ("p", "pause"),
+                ("d", "dashboard"),
+                ("q", "quit"),
+                ("?", "help"),
+            ]
+        };
 
         let mut spans: Vec<Span> = Vec::new();
         
@@ -37,7 +37,7 @@ impl TaskListWidget {
                 String::new()
             };
             
-            let content = format!("{} {:03} {}{}", icon, task.id, task.name, progress);
+            let content = format!("{} {} {}{}", icon, task.id, task.name, progress);
             let item = ListItem::new(content);
             
             if idx == app.selected_task {
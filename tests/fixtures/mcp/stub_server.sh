@@ -0,0 +1,49 @@
+#!/usr/bin/env bash
+# Stub MCP server for tests: newline-delimited JSON-RPC on stdio.
+#
+# Tools: echo (returns its text), fail (always an error), secret (for deny
+# lists) and big (60000 bytes of output). Sends a log notification before
+# answering tools/list, as real servers do.
+
+tools='[
+{"name":"echo","description":"Echo the text back","inputSchema":{"type":"object","properties":{"text":{"type":"string"}},"required":["text"]}},
+{"name":"fail","description":"Always fails","inputSchema":{"type":"object"}},
+{"name":"secret","description":"Should be denied","inputSchema":{"type":"object"}},
+{"name":"big","description":"Too much output","inputSchema":{"type":"object"}}
+]'
+
+echo "stub MCP server starting" >&2
+
+reply() {
+    printf '{"jsonrpc":"2.0","id":%s,"result":%s}\n' "$1" "$2"
+}
+
+text_result() {
+    printf '{"content":[{"type":"text","text":"%s"}],"isError":%s}' "$1" "$2"
+}
+
+while IFS= read -r line; do
+    id=$(grep -o '"id":[0-9]*' <<<"$line" | head -n1 | cut -d: -f2)
+    case "$line" in
+        *'"method":"initialize"'*)
+            reply "$id" '{"protocolVersion":"2025-06-18","capabilities":{"tools":{}},"serverInfo":{"name":"stub","version":"0"}}'
+            ;;
+        *'"method":"tools/list"'*)
+            echo '{"jsonrpc":"2.0","method":"notifications/message","params":{"level":"info","data":"listing"}}'
+            reply "$id" "{\"tools\":$(tr -d '\n' <<<"$tools")}"
+            ;;
+        *'"method":"tools/call"'*'"name":"echo"'* | *'"name":"echo"'*'"method":"tools/call"'*)
+            text=$(sed -n 's/.*"text":"\([^"]*\)".*/\1/p' <<<"$line")
+            reply "$id" "$(text_result "$text" false)"
+            ;;
+        *'"method":"tools/call"'*'"name":"big"'* | *'"name":"big"'*'"method":"tools/call"'*)
+            reply "$id" "$(text_result "$(head -c 60000 /dev/zero | tr '\0' x)" false)"
+            ;;
+        *'"method":"tools/call"'*)
+            reply "$id" "$(text_result boom true)"
+            ;;
+        *'"id":'*)
+            printf '{"jsonrpc":"2.0","id":%s,"error":{"code":-32601,"message":"Method not found"}}\n' "$id"
+            ;;
+    esac
+done